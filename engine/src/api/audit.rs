@@ -0,0 +1,249 @@
+/*
+Audit log of the state changing API calls.
+
+The middleware records who changed what, and when. For configs, channels, users and presets
+the old state gets loaded before the request runs, so the log holds the old and new values of
+the changed fields. Other calls store the request body as new value. Passwords and secrets
+are masked.
+*/
+
+use std::collections::BTreeMap;
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::{header::CONTENT_TYPE, Method},
+    middleware::Next,
+    web, Error, HttpMessage,
+};
+use futures_util::StreamExt;
+use log::*;
+use serde_json::{Map, Value};
+use sqlx::{Pool, Sqlite};
+use tokio::sync::Mutex;
+
+use crate::{
+    db::{
+        handles,
+        models::{AuditEntry, UserMeta},
+    },
+    player::controller::ChannelController,
+};
+
+/// Values bigger than this are not stored, only their size.
+const MAX_VALUE_LENGTH: usize = 65536;
+
+/// Calls which change nothing, even when they are no GET request.
+const READ_ONLY: [&str; 4] = ["/browse/", "/validate", "/generate-uuid", "/preview/"];
+
+fn is_audited(method: &Method, path: &str) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+        && !READ_ONLY.iter().any(|p| path.contains(p))
+}
+
+/// Channel of the request path, users, tokens, groups and workers belong to no channel.
+pub fn path_channel(path: &str) -> Option<i32> {
+    let segments = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+
+    match (segments.get(1), segments.get(2)) {
+        (Some(&"worker"), Some(&"channel")) => {}
+        (Some(&"user" | &"token" | &"tokens" | &"group" | &"worker"), _) => return None,
+        _ => {}
+    }
+
+    segments.iter().find_map(|s| s.parse().ok())
+}
+
+fn flatten(prefix: &str, value: &Value, map: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(obj) if !obj.is_empty() => {
+            for (key, val) in obj {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+
+                flatten(&path, val, map);
+            }
+        }
+        _ => {
+            map.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+fn is_secret(field: &str) -> bool {
+    let key = field.rsplit('.').next().unwrap_or_default();
+
+    key.contains("password") || key.contains("secret") || key == "token"
+}
+
+fn to_object(map: BTreeMap<String, Value>) -> Value {
+    Value::Object(
+        map.into_iter()
+            .map(|(k, v)| {
+                let v = if is_secret(&k) {
+                    Value::String("***".to_string())
+                } else {
+                    v
+                };
+
+                (k, v)
+            })
+            .collect::<Map<String, Value>>(),
+    )
+}
+
+/// Old and new values of the changed fields, as objects with the field path as key.
+///
+/// Only fields in `new` count, so partial updates show only what they changed.
+pub fn changes(old: &Value, new: &Value) -> (Value, Value) {
+    let mut old_map = BTreeMap::new();
+    let mut new_map = BTreeMap::new();
+
+    flatten("", old, &mut old_map);
+    flatten("", new, &mut new_map);
+
+    new_map.retain(|k, v| old_map.get(k) != Some(v));
+    old_map.retain(|k, _| new_map.contains_key(k));
+
+    (to_object(old_map), to_object(new_map))
+}
+
+fn stored_value(value: Value) -> Option<String> {
+    let text = value.to_string();
+
+    if text.len() > MAX_VALUE_LENGTH {
+        return Some(Value::String(format!("<{} bytes>", text.len())).to_string());
+    }
+
+    Some(text)
+}
+
+/// Current state of the object, which the request changes.
+async fn old_value(req: &ServiceRequest) -> Option<Value> {
+    let pool = req.app_data::<web::Data<Pool<Sqlite>>>()?;
+    let segments = req
+        .path()
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>();
+
+    match (req.method().as_str(), segments.as_slice()) {
+        ("PUT", ["api", "playout", section @ ("config" | "advanced"), id]) => {
+            let controllers = req.app_data::<web::Data<Mutex<ChannelController>>>()?;
+            let manager = controllers.lock().await.get(id.parse().ok()?).await?;
+            let config = manager.config.lock().await;
+
+            if *section == "config" {
+                serde_json::to_value(&*config).ok()
+            } else {
+                serde_json::to_value(&config.advanced).ok()
+            }
+        }
+        ("PATCH", ["api", "channel", id]) => {
+            let channel = handles::select_channel(pool, &id.parse().ok()?)
+                .await
+                .ok()?;
+
+            serde_json::to_value(channel).ok()
+        }
+        ("PUT", ["api", "user", id]) => {
+            let user = handles::select_user(pool, id.parse().ok()?).await.ok()?;
+
+            serde_json::to_value(user).ok()
+        }
+        ("PUT", ["api", "presets", channel, id]) => {
+            let id = id.parse::<i32>().ok()?;
+            let presets = handles::select_presets(pool, channel.parse().ok()?)
+                .await
+                .ok()?;
+
+            serde_json::to_value(presets.into_iter().find(|p| p.id == id)?).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Middleware, which writes the state changing calls to the audit log.
+///
+/// It needs to run after the authentication, to know the user.
+pub async fn audit_log(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    if !is_audited(req.method(), req.path()) {
+        return next.call(req).await;
+    }
+
+    let is_json = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    let mut body = None;
+
+    // uploads are not read, the body would be too big
+    if is_json {
+        let mut payload = req.take_payload();
+        let mut bytes = web::BytesMut::new();
+
+        while let Some(chunk) = payload.next().await {
+            bytes.extend_from_slice(&chunk?);
+        }
+
+        let bytes = bytes.freeze();
+        let (_, mut new_payload) = actix_http::h1::Payload::create(true);
+        new_payload.unread_data(bytes.clone());
+        req.set_payload(new_payload.into());
+
+        body = serde_json::from_slice::<Value>(&bytes).ok();
+    }
+
+    let old = old_value(&req).await;
+    let user = req.extensions().get::<UserMeta>().cloned();
+    let pool = req.app_data::<web::Data<Pool<Sqlite>>>().cloned();
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+
+    let res = next.call(req).await?;
+
+    let (old_value, new_value) = match (old, body) {
+        (Some(old), Some(new)) => {
+            let (old, new) = changes(&old, &new);
+
+            (stored_value(old), stored_value(new))
+        }
+        (_, Some(new)) => {
+            let mut map = BTreeMap::new();
+            flatten("", &new, &mut map);
+
+            (None, stored_value(to_object(map)))
+        }
+        _ => (None, None),
+    };
+
+    let entry = AuditEntry {
+        channel_id: path_channel(&path),
+        user_id: user.as_ref().map(|u| u.id),
+        username: user.map(|u| u.username).unwrap_or_default(),
+        method,
+        path,
+        status: res.status().as_u16().into(),
+        old_value,
+        new_value,
+        ..Default::default()
+    };
+
+    if let Some(pool) = pool {
+        if let Err(e) = handles::insert_audit_entry(&pool, &entry).await {
+            error!("Write audit log failed: {e}");
+        }
+    }
+
+    Ok(res)
+}
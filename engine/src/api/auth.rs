@@ -1,9 +1,13 @@
+use std::collections::BTreeMap;
+
 use actix_web::{error::ErrorUnauthorized, http::StatusCode, web, Error, Responder};
 use argon2::{password_hash::PasswordHash, Argon2, PasswordVerifier};
 use chrono::{TimeDelta, Utc};
 use jsonwebtoken::{self, DecodingKey, EncodingKey, Header, Validation};
 use log::*;
+use rand::{distr::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use sqlx::{Pool, Sqlite};
 
 use crate::{
@@ -12,8 +16,11 @@ use crate::{
 };
 
 // Token lifetime
-const ACCESS_LIFETIME: i64 = 3;
-const REFRESH_LIFETIME: i64 = 30;
+pub const ACCESS_LIFETIME: i64 = 3;
+pub const REFRESH_LIFETIME: i64 = 30;
+
+/// Prefix of API tokens, to tell them apart from JWTs.
+pub const API_TOKEN_PREFIX: &str = "ffp_";
 
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct Claims {
@@ -21,6 +28,8 @@ pub struct Claims {
     pub channels: Vec<i32>,
     pub username: String,
     pub role: Role,
+    #[serde(default)]
+    pub channel_roles: BTreeMap<i32, Role>,
     exp: i64,
 }
 
@@ -31,6 +40,7 @@ impl Claims {
             channels: user.channel_ids.unwrap_or_default(),
             username: user.username,
             role,
+            channel_roles: user.channel_roles,
             exp: (Utc::now() + TimeDelta::try_days(lifetime).unwrap()).timestamp(),
         }
     }
@@ -67,6 +77,76 @@ pub async fn decode_jwt(token: &str) -> Result<Claims, Error> {
         .map_err(|e| ErrorUnauthorized(e.to_string()))
 }
 
+/// New random API token, the plain token is only shown once to the user.
+pub fn generate_api_token() -> String {
+    let token: String = rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect();
+
+    format!("{API_TOKEN_PREFIX}{token}")
+}
+
+/// Hash of an API token, as it is stored in the database.
+pub fn hash_api_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Claims of an API token.
+///
+/// The role of the user gets limited by the role of the token, and the channels by the
+/// channels of the token. A token with channels can not have global admin rights.
+pub async fn api_token_claims(pool: &Pool<Sqlite>, token: &str) -> Result<Claims, ServiceError> {
+    let api_token = handles::select_valid_api_token(pool, &hash_api_token(token))
+        .await
+        .map_err(|_| ServiceError::Unauthorized("Invalid API token".to_string()))?;
+    let user = handles::select_user(pool, api_token.user_id).await?;
+    let role = handles::select_role(pool, &user.role_id.unwrap_or_default()).await?;
+    let mut max_role = handles::select_role(pool, &api_token.role_id).await?;
+
+    if !api_token.channel_ids.is_empty() {
+        max_role = max_role.limit(&Role::ChannelAdmin);
+    }
+
+    handles::update_api_token_used(pool, api_token.id).await?;
+
+    let mut claims = Claims::new(user, role.limit(&max_role), ACCESS_LIFETIME);
+
+    if !api_token.channel_ids.is_empty() {
+        claims
+            .channels
+            .retain(|c| api_token.channel_ids.contains(c));
+    }
+
+    claims.channel_roles = claims
+        .channel_roles
+        .into_iter()
+        .filter(|(c, _)| claims.channels.contains(c))
+        .map(|(c, r)| (c, r.limit(&max_role)))
+        .collect();
+
+    Ok(claims)
+}
+
+/// Access and refresh token for a user, which is already authenticated.
+pub async fn login_tokens(
+    pool: &Pool<Sqlite>,
+    user: User,
+) -> Result<(String, String), ServiceError> {
+    let role = handles::select_role(pool, &user.role_id.unwrap_or_default()).await?;
+    let access_claims = Claims::new(user.clone(), role.clone(), ACCESS_LIFETIME);
+    let refresh_claims = Claims::new(user, role, REFRESH_LIFETIME);
+
+    Ok((
+        encode_jwt(access_claims).await?,
+        encode_jwt(refresh_claims).await?,
+    ))
+}
+
 pub async fn authorize(
     pool: &Pool<Sqlite>,
     credentials: Credentials,
@@ -1,2 +1,4 @@
+pub mod audit;
 pub mod auth;
+pub mod oidc;
 pub mod routes;
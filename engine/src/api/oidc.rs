@@ -0,0 +1,260 @@
+/*
+OpenID Connect login, for identity providers like Keycloak or Authentik.
+
+The login runs the authorization code flow: the user gets redirected to the provider and comes
+back with a code, which gets exchanged for an ID token. The state parameter is a short living
+JWT, signed with the secret of ffplayout, so no session needs to be stored between the requests.
+
+A provider account is bound to its own user, by issuer and subject of the ID token. New accounts
+get a new user with the guest role and without channels, a global admin has to give them access.
+The provider username is only a proposal for the name, it is never used to log in as an existing
+user, because most providers let the users choose it.
+*/
+
+use chrono::{TimeDelta, Utc};
+use jsonwebtoken::{self, jwk::JwkSet, DecodingKey, EncodingKey, Header, Validation};
+use log::*;
+use rand::{distr::Alphanumeric, Rng};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+
+use crate::{
+    db::{
+        handles,
+        models::{GlobalSettings, User},
+        GLOBAL_SETTINGS,
+    },
+    utils::errors::ServiceError,
+};
+
+// Minutes for the login on the provider side
+const STATE_LIFETIME: i64 = 10;
+const GUEST_ROLE_ID: i32 = 4;
+
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct StateClaims {
+    nonce: String,
+    exp: i64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct IdClaims {
+    pub sub: String,
+    pub preferred_username: Option<String>,
+    pub email: Option<String>,
+    pub nonce: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackParams {
+    pub code: String,
+    pub state: String,
+}
+
+fn provider_error(e: impl ToString) -> ServiceError {
+    ServiceError::ServiceUnavailable(format!("OIDC provider: {}", e.to_string()))
+}
+
+fn secret() -> Vec<u8> {
+    let config = GLOBAL_SETTINGS.get().unwrap();
+
+    config.secret.clone().unwrap_or_default().into_bytes()
+}
+
+/// OIDC login is enabled when issuer, client id and redirect url are set.
+pub fn is_enabled(global: &GlobalSettings) -> bool {
+    !global.oidc_issuer.is_empty()
+        && !global.oidc_client_id.is_empty()
+        && !global.oidc_redirect_url.is_empty()
+}
+
+async fn discovery(global: &GlobalSettings) -> Result<Discovery, ServiceError> {
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        global.oidc_issuer.trim_end_matches('/')
+    );
+
+    reqwest::get(url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(provider_error)?
+        .json::<Discovery>()
+        .await
+        .map_err(provider_error)
+}
+
+/// URL of the provider login, with a signed state and a nonce for the ID token.
+pub async fn login_url(global: &GlobalSettings) -> Result<Url, ServiceError> {
+    let discovery = discovery(global).await?;
+    let nonce: String = rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+    let state = jsonwebtoken::encode(
+        &Header::default(),
+        &StateClaims {
+            nonce: nonce.clone(),
+            exp: (Utc::now() + TimeDelta::try_minutes(STATE_LIFETIME).unwrap()).timestamp(),
+        },
+        &EncodingKey::from_secret(&secret()),
+    )?;
+
+    Url::parse_with_params(
+        &discovery.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("client_id", &global.oidc_client_id),
+            ("redirect_uri", &global.oidc_redirect_url),
+            ("scope", "openid profile email"),
+            ("state", &state),
+            ("nonce", &nonce),
+        ],
+    )
+    .map_err(provider_error)
+}
+
+async fn verify_id_token(
+    global: &GlobalSettings,
+    discovery: &Discovery,
+    id_token: &str,
+) -> Result<IdClaims, ServiceError> {
+    let header = jsonwebtoken::decode_header(id_token)?;
+    let jwks = reqwest::get(&discovery.jwks_uri)
+        .await
+        .map_err(provider_error)?
+        .json::<JwkSet>()
+        .await
+        .map_err(provider_error)?;
+    let jwk = match header.kid {
+        Some(kid) => jwks.find(&kid),
+        None => jwks.keys.first(),
+    }
+    .ok_or(ServiceError::Unauthorized(
+        "No key for the ID token".to_string(),
+    ))?;
+
+    let mut validation = Validation::new(header.alg);
+    validation.set_audience(&[&global.oidc_client_id]);
+    validation.set_issuer(&[&discovery.issuer]);
+
+    Ok(
+        jsonwebtoken::decode::<IdClaims>(id_token, &DecodingKey::from_jwk(jwk)?, &validation)?
+            .claims,
+    )
+}
+
+/// Exchange the code from the provider for the user.
+pub async fn callback(
+    pool: &Pool<Sqlite>,
+    global: &GlobalSettings,
+    params: CallbackParams,
+) -> Result<User, ServiceError> {
+    let state = jsonwebtoken::decode::<StateClaims>(
+        &params.state,
+        &DecodingKey::from_secret(&secret()),
+        &Validation::default(),
+    )?
+    .claims;
+    let discovery = discovery(global).await?;
+    let response = reqwest::Client::new()
+        .post(&discovery.token_endpoint)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", &params.code),
+            ("redirect_uri", &global.oidc_redirect_url),
+            ("client_id", &global.oidc_client_id),
+            ("client_secret", &global.oidc_client_secret),
+        ])
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(provider_error)?
+        .json::<TokenResponse>()
+        .await
+        .map_err(provider_error)?;
+
+    let claims = verify_id_token(global, &discovery, &response.id_token).await?;
+
+    if claims.nonce.as_deref() != Some(state.nonce.as_str()) {
+        return Err(ServiceError::Unauthorized(
+            "Wrong nonce in ID token".to_string(),
+        ));
+    }
+
+    provider_user(pool, &discovery.issuer, claims).await
+}
+
+/// User of the provider account, a new account gets a new user.
+pub async fn provider_user(
+    pool: &Pool<Sqlite>,
+    issuer: &str,
+    claims: IdClaims,
+) -> Result<User, ServiceError> {
+    if let Ok(user) = handles::select_oidc_user(pool, issuer, &claims.sub).await {
+        return Ok(user);
+    }
+
+    let name = claims
+        .preferred_username
+        .clone()
+        .unwrap_or_else(|| claims.sub.clone());
+    let username = free_username(pool, &name).await;
+
+    // the password is never used, the user logs in over the provider
+    let password: String = rand::rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect();
+    let user = User {
+        id: 0,
+        mail: Some(claims.email.unwrap_or_default()),
+        username: username.clone(),
+        password,
+        role_id: Some(GUEST_ROLE_ID),
+        channel_ids: Some(vec![]),
+        token: None,
+        channel_roles: Default::default(),
+    };
+
+    handles::insert_user(pool, user).await?;
+
+    let user = handles::select_login(pool, &username).await?;
+    handles::update_user_oidc(pool, user.id, issuer, &claims.sub).await?;
+
+    info!("Create user {username} from OIDC login");
+
+    Ok(user)
+}
+
+/// The name, or the name with the first free number, when a user has it already.
+async fn free_username(pool: &Pool<Sqlite>, name: &str) -> String {
+    let mut username = name.to_string();
+    let mut number = 1;
+
+    // select_login aggregates, so it returns a row also for unknown names
+    while handles::select_login(pool, &username)
+        .await
+        .is_ok_and(|u| u.username == username)
+    {
+        number += 1;
+        username = format!("{name}-{number}");
+    }
+
+    username
+}
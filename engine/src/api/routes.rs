@@ -9,8 +9,8 @@
 /// For all endpoints an (Bearer) authentication is required.\
 /// `{id}` represent the channel id, and at default is 1.
 use std::{
-    env,
-    path::{Path, PathBuf},
+    env, io,
+    path::{Component, Path, PathBuf},
     sync::{atomic::Ordering, Arc},
 };
 
@@ -27,7 +27,8 @@ use argon2::{
     password_hash::{rand_core::OsRng, SaltString},
     Argon2, PasswordHasher,
 };
-use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Utc};
+use futures_util::future::join_all;
 use log::*;
 use path_clean::PathClean;
 use regex::Regex;
@@ -36,33 +37,60 @@ use sqlx::{Pool, Sqlite};
 use tokio::{fs, sync::Mutex};
 
 use crate::{
-    api::auth::{self, Credentials, TokenRefreshRequest},
+    api::{
+        auth::{self, Credentials, TokenRefreshRequest},
+        oidc::{self, CallbackParams},
+    },
     db::{
         handles,
-        models::{Channel, Role, TextPreset, User, UserMeta},
+        models::{
+            ApiToken, AuditQuery, Channel, ChannelGroup, LibraryQuery, Role, TextPreset, User,
+            UserMeta, Worker,
+        },
+        GLOBAL_SETTINGS,
     },
     file::{
         norm_abs_path, resolve_path,
         utils::{media_map::SharedMediaMap, ABS_PATH_INDICATOR},
-        MoveObject, PathObject,
+        MoveObject, PathObject, StorageBackend,
     },
     player::{
-        controller::ChannelController,
+        controller::{ChannelController, ChannelManager},
+        output::{
+            hls_key,
+            recorder::{self, ClipParams},
+        },
         utils::{
-            get_data_map, get_date_range, import::import_file, sec_to_time, time_to_sec,
-            JsonPlaylist,
+            get_data_map, get_date_range,
+            import::import_file,
+            progress::{self, ChannelMetrics},
+            sec_to_time, time_to_sec, JsonPlaylist,
         },
     },
     utils::{
         advanced_config::AdvancedConfig,
+        asrun,
         channels::{create_channel, delete_channel},
-        config::{get_config, PlayoutConfig, Template},
-        control::{control_state, send_message, ControlParams, Process, ProcessCtl},
+        config::{get_config, is_global_admin_field, is_live_field, PlayoutConfig, Template},
+        config_check,
+        control::{
+            clear_messages, control_state, insert_next, process_command, queue_message,
+            send_message, send_volume, ControlParams, InsertParams, Process, ProcessCtl,
+        },
+        epg,
         errors::ServiceError,
+        history, library,
         mail::MailQueue,
         naive_date_time_from_str,
-        playlist::{delete_playlist, generate_playlist, read_playlist, write_playlist},
-        public_path, read_log_file, system, TextFilter,
+        playlist::{
+            delete_playlist, generate_playlist, read_playlist, save_revision, write_playlist,
+        },
+        preview,
+        preview_session::{self, PreviewRequest},
+        public_path, read_log_file, system,
+        time_machine::time_now,
+        workers::{self, WorkerChannel, WorkerChannelState},
+        TextFilter, TextMessage,
     },
     vec_strings,
 };
@@ -73,6 +101,43 @@ pub struct DateObj {
     date: String,
 }
 
+/// Result of a group operation on one channel.
+#[derive(Debug, Serialize)]
+struct GroupResultObj {
+    id: i32,
+    success: bool,
+    message: String,
+    /// Changed fields, which need a restart of the channel.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    restart: Vec<String>,
+}
+
+impl GroupResultObj {
+    fn new(id: i32, result: Result<String, ServiceError>) -> Self {
+        match result {
+            Ok(message) => Self {
+                id,
+                success: true,
+                message,
+                restart: vec![],
+            },
+            Err(e) => Self {
+                id,
+                success: false,
+                message: e.to_string(),
+                restart: vec![],
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ConfigUpdateObj {
+    message: String,
+    /// Changed fields, which need a restart of the channel.
+    restart: Vec<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 struct FileObj {
     #[serde(default)]
@@ -86,6 +151,35 @@ pub struct PathsObj {
     template: Option<Template>,
 }
 
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct HistoryObj {
+    #[serde(default)]
+    start: String,
+    #[serde(default)]
+    end: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PreviewObj {
+    path: String,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct AsRunObj {
+    #[serde(default)]
+    format: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EpgObj {
+    #[serde(default = "epg_days")]
+    days: i64,
+}
+
+fn epg_days() -> i64 {
+    7
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ImportObj {
     #[serde(default)]
@@ -176,6 +270,77 @@ pub async fn refresh(
     auth::refresh(&pool.into_inner(), data.into_inner()).await
 }
 
+/// **OIDC Login**
+///
+/// Check if the login over an OpenID Connect provider is enabled.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/auth/oidc/
+/// ```
+/// **Response:**
+///
+/// ```JSON
+/// {
+///     "enabled": true
+/// }
+/// ```
+///
+/// The browser opens `/auth/oidc/login`, which redirects to the provider. The provider redirects
+/// back to `/auth/oidc/callback`, from there the browser gets redirected to the frontend with
+/// access and refresh token in the URL fragment.
+#[get("/oidc/")]
+pub async fn oidc_status() -> Result<impl Responder, ServiceError> {
+    let global = GLOBAL_SETTINGS.get().unwrap();
+
+    Ok(web::Json(serde_json::json!({
+        "enabled": oidc::is_enabled(global),
+    })))
+}
+
+#[get("/oidc/login")]
+pub async fn oidc_login() -> Result<impl Responder, ServiceError> {
+    let global = GLOBAL_SETTINGS.get().unwrap();
+
+    if !oidc::is_enabled(global) {
+        return Err(ServiceError::BadRequest(
+            "OIDC login is not enabled".to_string(),
+        ));
+    }
+
+    let url = oidc::login_url(global).await?;
+
+    Ok(HttpResponse::Found()
+        .append_header(("Location", url.to_string()))
+        .finish())
+}
+
+#[get("/oidc/callback")]
+pub async fn oidc_callback(
+    pool: web::Data<Pool<Sqlite>>,
+    params: web::Query<CallbackParams>,
+) -> Result<impl Responder, ServiceError> {
+    let global = GLOBAL_SETTINGS.get().unwrap();
+
+    if !oidc::is_enabled(global) {
+        return Err(ServiceError::BadRequest(
+            "OIDC login is not enabled".to_string(),
+        ));
+    }
+
+    let user = oidc::callback(&pool, global, params.into_inner()).await?;
+    let username = user.username.clone();
+    let (access_token, refresh_token) = auth::login_tokens(&pool, user).await?;
+
+    info!("user {username} login over OIDC");
+
+    Ok(HttpResponse::Found()
+        .append_header((
+            "Location",
+            format!("/#access={access_token}&refresh={refresh_token}"),
+        ))
+        .finish())
+}
+
 /// From here on all request **must** contain the authorization header:\
 /// `"Authorization: Bearer <TOKEN>"`
 /// **Get current User**
@@ -186,7 +351,7 @@ pub async fn refresh(
 /// ```
 #[get("/user")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
     ty = "Role"
 )]
 async fn get_user(
@@ -249,18 +414,24 @@ async fn get_users(pool: web::Data<Pool<Sqlite>>) -> Result<impl Responder, Serv
 /// ```
 #[put("/user/{id}")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
     ty = "Role",
-    expr = "*id == user.id || role.has_authority(&Role::GlobalAdmin)"
+    expr = "*id == user.id || user.role == Role::GlobalAdmin"
 )]
 async fn update_user(
     pool: web::Data<Pool<Sqlite>>,
     id: web::Path<i32>,
     data: web::Json<User>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
+    if user.api_token {
+        return Err(ServiceError::Forbidden(
+            "API tokens can not change users".to_string(),
+        ));
+    }
+
     let channel_ids = data.channel_ids.clone().unwrap_or_default();
+    let channel_roles = data.channel_roles.clone();
     let mut fields = String::new();
 
     if let Some(mail) = data.mail.clone() {
@@ -271,6 +442,14 @@ async fn update_user(
         fields.push_str(&format!("mail = '{mail}'"));
     }
 
+    if let Some(role_id) = data.role_id.filter(|_| user.role == Role::GlobalAdmin) {
+        if !fields.is_empty() {
+            fields.push_str(", ");
+        }
+
+        fields.push_str(&format!("role_id = {role_id}"));
+    }
+
     if !data.password.is_empty() {
         if !fields.is_empty() {
             fields.push_str(", ");
@@ -293,6 +472,11 @@ async fn update_user(
 
     handles::update_user(&pool, *id, fields).await?;
 
+    // only global admins give access to channels
+    if user.role != Role::GlobalAdmin {
+        return Ok("Update Success");
+    }
+
     let related_channels = handles::select_related_channels(&pool, Some(*id)).await?;
 
     for channel in related_channels {
@@ -302,15 +486,166 @@ async fn update_user(
     }
 
     handles::insert_user_channel(&pool, *id, channel_ids).await?;
+    handles::update_user_channel_roles(&pool, *id, &channel_roles).await?;
 
     Ok("Update Success")
 }
 
+/// **Get API Tokens**
+///
+/// Tokens of the current user, global admins get the tokens of all users.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/tokens' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/tokens")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role"
+)]
+async fn get_api_tokens(
+    pool: web::Data<Pool<Sqlite>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let user_id = (user.role != Role::GlobalAdmin).then_some(user.id);
+    let tokens = handles::select_api_tokens(&pool, user_id).await?;
+
+    Ok(web::Json(tokens))
+}
+
+/// **Add API Token**
+///
+/// The token gets the rights of the user, but not more as `role_id`. With `channel_ids` the token
+/// works only for this channels. `expires_in` is in days, without it the token never expires.
+///
+/// ```BASH
+/// curl -X POST 'http://127.0.0.1:8787/api/token' -H 'Content-Type: application/json' \
+/// -d '{"name": "scheduler", "role_id": 3, "channel_ids": [1], "expires_in": 365}' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+/// **Response:**
+///
+/// The token is only shown this time.
+///
+/// ```JSON
+/// {
+///     "id": 1,
+///     "token": "ffp_<TOKEN>"
+/// }
+/// ```
+#[post("/token")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role"
+)]
+async fn add_api_token(
+    pool: web::Data<Pool<Sqlite>>,
+    data: web::Json<ApiToken>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    if user.api_token {
+        return Err(ServiceError::Forbidden(
+            "API tokens can not create tokens".to_string(),
+        ));
+    }
+
+    let mut api_token = data.into_inner();
+
+    if api_token.name.trim().is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Token name is missing".to_string(),
+        ));
+    }
+
+    let token = auth::generate_api_token();
+    api_token.user_id = user.id;
+    api_token.token_hash = auth::hash_api_token(&token);
+
+    let id = handles::insert_api_token(&pool, &api_token).await?;
+
+    Ok(web::Json(serde_json::json!({
+        "id": id,
+        "token": token,
+    })))
+}
+
+/// **Revoke API Token**
+///
+/// ```BASH
+/// curl -X DELETE 'http://127.0.0.1:8787/api/token/1' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/token/{token_id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role"
+)]
+async fn revoke_api_token(
+    pool: web::Data<Pool<Sqlite>>,
+    token_id: web::Path<i32>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let user_id = (user.role != Role::GlobalAdmin).then_some(user.id);
+    let result = handles::revoke_api_token(&pool, *token_id, user_id).await?;
+
+    if result.rows_affected() == 0 {
+        return Err(ServiceError::NoContent("Token not found".to_string()));
+    }
+
+    Ok("Revoke Success")
+}
+
+/// **Audit Log**
+///
+/// State changing API calls, newest first. Global admins get all entries, channel admins the
+/// entries of their channels. Filter with `channel`, `username`, `method`, `path` (part of the
+/// path), `from` and `to` (date or date time), `limit` (default 100) and `offset`.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/audit?channel=1&from=2024-01-01&to=2024-01-31' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+/// **Response:**
+///
+/// ```JSON
+/// [
+///     {
+///         "id": 1,
+///         "channel_id": 1,
+///         "user_id": 2,
+///         "username": "operator",
+///         "method": "PUT",
+///         "path": "/api/playout/config/1",
+///         "status": 200,
+///         "old_value": { "processing.volume": 1.0 },
+///         "new_value": { "processing.volume": 0.8 },
+///         "created_at": "2024-01-10 08:12:44"
+///     }
+/// ]
+/// ```
+#[get("/audit")]
+#[protect(any("Role::GlobalAdmin", "Role::ChannelAdmin"), ty = "Role")]
+async fn get_audit_log(
+    pool: web::Data<Pool<Sqlite>>,
+    query: web::Query<AuditQuery>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let channels = (user.role != Role::GlobalAdmin).then(|| {
+        user.channels
+            .iter()
+            .filter(|c| user.has_role(c, &[Role::ChannelAdmin]))
+            .copied()
+            .collect::<Vec<_>>()
+    });
+    let entries = handles::select_audit_entries(&pool, &query, channels.as_deref()).await?;
+
+    Ok(web::Json(entries))
+}
+
 /// **Add User**
 ///
 /// ```BASH
 /// curl -X POST 'http://127.0.0.1:8787/api/user/' -H 'Content-Type: application/json' \
-/// -d '{"mail": "<MAIL>", "username": "<USER>", "password": "<PASS>", "role_id": 1, "channel_id": 1}' \
+/// -d '{"mail": "<MAIL>", "username": "<USER>", "password": "<PASS>", "role_id": 1, "channel_ids": [1, 2], "channel_roles": {"2": "guest"}}' \
 /// -H 'Authorization: Bearer <TOKEN>'
 /// ```
 #[post("/user/")]
@@ -370,14 +705,13 @@ async fn remove_user(
 /// ```
 #[get("/channel/{id}")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
 )]
 async fn get_channel(
     pool: web::Data<Pool<Sqlite>>,
     id: web::Path<i32>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     if let Ok(channel) = handles::select_channel(&pool, &id).await {
@@ -387,6 +721,41 @@ async fn get_channel(
     Err(ServiceError::InternalServerError)
 }
 
+/// **Get EPG**
+///
+/// Program guide in XMLTV format, from the playlists of today and the next days.
+/// The number of days can be set with `days`, default is 7.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/channel/1/epg.xml?days=3 -H "Authorization: Bearer <TOKEN>"
+/// ```
+#[get("/channel/{id}/epg.xml")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
+)]
+async fn get_epg(
+    id: web::Path<i32>,
+    obj: web::Query<EpgObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<HttpResponse, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+    let channel_name = manager.channel.lock().await.name.clone();
+    let programme = epg::programme(&config, obj.days.min(31)).await;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/xml; charset=utf-8")
+        .body(epg::xmltv(*id, &channel_name, &programme)))
+}
+
 /// **Get settings from all Channels**
 ///
 /// ```BASH
@@ -394,7 +763,7 @@ async fn get_channel(
 /// ```
 #[get("/channels")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
     ty = "Role"
 )]
 async fn get_all_channels(
@@ -419,7 +788,7 @@ async fn get_all_channels(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin])"
 )]
 async fn patch_channel(
     pool: web::Data<Pool<Sqlite>>,
@@ -450,7 +819,8 @@ async fn patch_channel(
     let new_config = get_config(&pool, *id).await?;
 
     manager.update_config(new_config).await;
-    manager.clone().update_channel(&data).await;
+    manager.clone().update_channel(&data).await?;
+    workers::sync_channel(&pool, &manager).await;
 
     Ok("Update Success")
 }
@@ -475,6 +845,7 @@ async fn add_channel(
         controllers.into_inner(),
         queue.into_inner(),
         data.into_inner(),
+        None,
     )
     .await
     {
@@ -501,93 +872,545 @@ async fn remove_channel(
     Ok(web::Json("Delete Channel Success"))
 }
 
-/// #### ffplayout Config
+/// #### Channel Groups
 ///
-/// **Get Advanced Config**
+/// Channels in a group can be controlled together. A channel can be only in one group.
+///
+/// **Get Groups**
 ///
 /// ```BASH
-/// curl -X GET http://127.0.0.1:8787/api/playout/advanced/1 -H 'Authorization: Bearer <TOKEN>'
+/// curl -X GET http://127.0.0.1:8787/api/groups -H "Authorization: Bearer <TOKEN>"
 /// ```
+/// **Response:**
 ///
-/// Response is a JSON object
-#[get("/playout/advanced/{id}")]
+/// ```JSON
+/// [{ "id": 1, "name": "News", "channel_ids": [1, 2] }]
+/// ```
+#[get("/groups")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
-    ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role"
 )]
-async fn get_advanced_config(
-    id: web::Path<i32>,
-    controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
+async fn get_channel_groups(
+    pool: web::Data<Pool<Sqlite>>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
-    let manager = controllers
-        .lock()
-        .await
-        .get(*id)
-        .await
-        .ok_or_else(|| ServiceError::BadRequest(format!("Channel ({id}) not exists!")))?;
-    let config = manager.config.lock().await.advanced.clone();
+    let mut groups = handles::select_channel_groups(&pool).await?;
 
-    Ok(web::Json(config))
+    if user.role != Role::GlobalAdmin {
+        for group in &mut groups {
+            group.channel_ids.retain(|c| user.channels.contains(c));
+        }
+    }
+
+    Ok(web::Json(groups))
 }
 
-/// **Get related Advanced Config**
+/// **Add Group**
 ///
 /// ```BASH
-/// curl -X GET http://127.0.0.1:8787/api/playout/advanced/1/ -H 'Authorization: Bearer <TOKEN>'
+/// curl -X POST http://127.0.0.1:8787/api/group/ -H "Content-Type: application/json" \
+/// -d '{"name": "News", "channel_ids": [1, 2]}' -H "Authorization: Bearer <TOKEN>"
 /// ```
+#[post("/group/")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn add_channel_group(
+    pool: web::Data<Pool<Sqlite>>,
+    data: web::Json<ChannelGroup>,
+) -> Result<impl Responder, ServiceError> {
+    let id = handles::insert_channel_group(&pool, &data).await?;
+
+    Ok(web::Json(handles::select_channel_group(&pool, id).await?))
+}
+
+/// **Update Group**
 ///
-/// Response is a JSON object
-#[get("/playout/advanced/{id}/")]
-#[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
-    ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
-)]
-async fn get_related_advanced_config(
+/// Name and channels of the group.
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/group/1 -H "Content-Type: application/json" \
+/// -d '{"name": "News", "channel_ids": [1, 2, 3]}' -H "Authorization: Bearer <TOKEN>"
+/// ```
+#[put("/group/{id}")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn update_channel_group(
     pool: web::Data<Pool<Sqlite>>,
     id: web::Path<i32>,
-    role: AuthDetails<Role>,
-    user: web::ReqData<UserMeta>,
+    data: web::Json<ChannelGroup>,
 ) -> Result<impl Responder, ServiceError> {
-    match handles::select_related_advanced_configuration(&pool, *id).await {
-        Ok(configs) => Ok(web::Json(
-            configs
-                .iter()
-                .map(|c| AdvancedConfig::new(c.clone()))
-                .collect::<Vec<_>>(),
-        )),
-        Err(e) => {
-            error!("Advanced config: {e}");
+    handles::update_channel_group(&pool, *id, &data).await?;
 
-            Err(ServiceError::InternalServerError)
-        }
-    }
+    Ok(web::Json("Update Success"))
 }
 
-/// **Delete Advanced Config**
+/// **Delete Group**
+///
+/// The channels of the group stay.
 ///
 /// ```BASH
-/// curl -X DELETE http://127.0.0.1:8787/api/playout/advanced/ -H 'Authorization: Bearer <TOKEN>'
+/// curl -X DELETE http://127.0.0.1:8787/api/group/1 -H "Authorization: Bearer <TOKEN>"
 /// ```
-///
-/// Response is a JSON object
-#[delete("/playout/advanced/{channel}/{id}")]
-#[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
-    ty = "Role",
-    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
-)]
-async fn remove_related_advanced_config(
+#[delete("/group/{id}")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn remove_channel_group(
     pool: web::Data<Pool<Sqlite>>,
-    path: web::Path<(i32, i32)>,
-    controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
-    user: web::ReqData<UserMeta>,
+    id: web::Path<i32>,
 ) -> Result<impl Responder, ServiceError> {
-    let (_, id) = path.into_inner();
+    handles::delete_channel_group(&pool, *id).await?;
+
+    Ok(web::Json("Delete Success"))
+}
+
+/// **Group Process Control**
+///
+/// Run a process command on all channels of the group, at the same time. Commands are the same
+/// as in the process control of a channel. Channels, which the user can not control, are skipped.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/group/1/process/ -H 'Content-Type: application/json' \
+/// -d '{"command": "restart"}' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+/// **Response:**
+///
+/// ```JSON
+/// [
+///     { "id": 1, "success": true, "message": "Success" },
+///     { "id": 2, "success": false, "message": "Forbidden: No access to channel" }
+/// ]
+/// ```
+#[post("/group/{id}/process/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role"
+)]
+async fn group_process_control(
+    pool: web::Data<Pool<Sqlite>>,
+    id: web::Path<i32>,
+    proc: web::Json<Process>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let group = handles::select_channel_group(&pool, *id).await?;
+    let command = proc.into_inner().command;
+    let mut tasks = vec![];
+
+    for channel_id in group.channel_ids {
+        let manager = controllers.lock().await.get(channel_id).await;
+        let allowed = user.has_role(&channel_id, &[Role::ChannelAdmin, Role::User]);
+        let command = command.clone();
+
+        tasks.push(async move {
+            let result = match manager {
+                Some(_) if !allowed => {
+                    Err(ServiceError::Forbidden("No access to channel".to_string()))
+                }
+                Some(manager) => process_command(&manager, command)
+                    .await
+                    .map(ToString::to_string),
+                None => Err(ServiceError::BadRequest("Channel not found".to_string())),
+            };
+
+            GroupResultObj::new(channel_id, result)
+        });
+    }
+
+    Ok(web::Json(join_all(tasks).await))
+}
+
+/// **Group Config Patch**
+///
+/// Apply a partial config to all channels of the group, like a new volume or logo. Each channel
+/// gets validated on its own, the response lists the result and the fields which need a restart.
+/// Channels, which the user can not configure, are skipped.
+///
+/// ```BASH
+/// curl -X PATCH http://127.0.0.1:8787/api/group/1/config -H 'Content-Type: application/json' \
+/// -d '{"processing": {"volume": 0.8}}' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+/// **Response:**
+///
+/// ```JSON
+/// [{ "id": 1, "success": true, "message": "Update success" }]
+/// ```
+#[patch("/group/{id}/config")]
+#[protect(any("Role::GlobalAdmin", "Role::ChannelAdmin"), ty = "Role")]
+async fn patch_group_config(
+    pool: web::Data<Pool<Sqlite>>,
+    id: web::Path<i32>,
+    data: web::Json<serde_json::Value>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+    mail_queues: web::Data<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
+) -> Result<impl Responder, ServiceError> {
+    let group = handles::select_channel_group(&pool, *id).await?;
+    let mut results = vec![];
+
+    for channel_id in group.channel_ids {
+        if !user.has_role(&channel_id, &[Role::ChannelAdmin]) {
+            results.push(GroupResultObj::new(
+                channel_id,
+                Err(ServiceError::Forbidden("No access to channel".to_string())),
+            ));
+            continue;
+        }
+
+        let Some(manager) = controllers.lock().await.get(channel_id).await else {
+            results.push(GroupResultObj::new(
+                channel_id,
+                Err(ServiceError::BadRequest("Channel not found".to_string())),
+            ));
+            continue;
+        };
+
+        let config = manager.config.lock().await.patch(&data);
+        let result = match config {
+            Ok(config) => store_playout_config(&pool, &manager, config, &user, &mail_queues).await,
+            Err(e) => Err(e.into()),
+        };
+
+        results.push(match result {
+            Ok(restart) => GroupResultObj {
+                restart,
+                ..GroupResultObj::new(channel_id, Ok("Update success".to_string()))
+            },
+            Err(e) => GroupResultObj::new(channel_id, Err(e)),
+        });
+    }
+
+    Ok(web::Json(results))
+}
+
+/// #### Worker Nodes
+///
+/// Channels can run on remote worker nodes, which are normal ffplayout engines. The controller
+/// keeps the configs and places the channels, the workers need the same shared storage.
+/// For each worker an API token of a global admin on the worker is needed.
+///
+/// **Get Workers**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/workers -H "Authorization: Bearer <TOKEN>"
+/// ```
+/// **Response:**
+///
+/// ```JSON
+/// [{ "id": 1, "name": "node-2", "url": "http://10.0.0.2:8787", "online": true, "failures": 0, "last_seen": "2025-01-20 10:12:05" }]
+/// ```
+#[get("/workers")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn get_workers(pool: web::Data<Pool<Sqlite>>) -> Result<impl Responder, ServiceError> {
+    Ok(web::Json(handles::select_workers(&pool).await?))
+}
+
+/// **Add Worker**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/worker/ -H "Content-Type: application/json" \
+/// -d '{"name": "node-2", "url": "http://10.0.0.2:8787", "token": "<WORKER TOKEN>"}' \
+/// -H "Authorization: Bearer <TOKEN>"
+/// ```
+#[post("/worker/")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn add_worker(
+    pool: web::Data<Pool<Sqlite>>,
+    data: web::Json<Worker>,
+) -> Result<impl Responder, ServiceError> {
+    let id = handles::insert_worker(&pool, &data).await?;
+    let worker = handles::select_worker(&pool, id).await?;
+
+    match workers::status(&worker).await {
+        Ok(_) => handles::update_worker_state(&pool, id, true, 0).await?,
+        Err(e) => {
+            warn!("{e}");
+            handles::update_worker_state(&pool, id, false, 1).await?
+        }
+    };
+
+    Ok(web::Json(handles::select_worker(&pool, id).await?))
+}
+
+/// **Delete Worker**
+///
+/// Channels of the worker get placed back on the controller.
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/worker/1 -H "Authorization: Bearer <TOKEN>"
+/// ```
+#[delete("/worker/{id}")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn remove_worker(
+    pool: web::Data<Pool<Sqlite>>,
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<impl Responder, ServiceError> {
+    let channels = handles::select_related_channels(&pool, None).await?;
+
+    for channel in channels.iter().filter(|c| c.worker_id == Some(*id)) {
+        if let Some(manager) = controllers.lock().await.get(channel.id).await {
+            workers::place_channel(&pool, &manager, None).await?;
+        }
+    }
+
+    handles::delete_worker(&pool, *id).await?;
+
+    Ok(web::Json("Delete Success"))
+}
+
+#[derive(Debug, Deserialize)]
+struct Placement {
+    worker_id: Option<i32>,
+}
+
+/// **Place Channel**
+///
+/// Run the channel on a worker, or with `null` on the controller.
+/// An active channel gets stopped on its old place and started on the new one.
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/channel/1/worker -H "Content-Type: application/json" \
+/// -d '{"worker_id": 1}' -H "Authorization: Bearer <TOKEN>"
+/// ```
+#[put("/channel/{id}/worker")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn place_channel(
+    pool: web::Data<Pool<Sqlite>>,
+    id: web::Path<i32>,
+    data: web::Json<Placement>,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or_else(|| ServiceError::BadRequest(format!("Channel {id} not found!")))?;
+    let worker = match data.worker_id {
+        Some(worker_id) => Some(handles::select_worker(&pool, worker_id).await?),
+        None => None,
+    };
+
+    if worker.as_ref().is_some_and(|w| !w.online) {
+        return Err(ServiceError::Conflict("Worker is offline".to_string()));
+    }
+
+    workers::place_channel(&pool, &manager, worker.as_ref()).await?;
+
+    Ok(web::Json("Update Success"))
+}
+
+/// **Worker Status**
+///
+/// On a worker node: the channels, which the controller has created here, and if they run,
+/// for the health check of the controller.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/worker/status -H "Authorization: Bearer <TOKEN>"
+/// ```
+/// **Response:**
+///
+/// ```JSON
+/// [{ "id": 1, "running": true }]
+/// ```
+#[get("/worker/status")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn worker_status(
+    pool: web::Data<Pool<Sqlite>>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let placed = handles::select_controller_channels(&pool, user.id).await?;
+    let managers = controllers.lock().await.managers.clone();
+    let mut states = vec![];
+
+    for manager in managers.into_iter().filter(|m| placed.contains(&m.id)) {
+        states.push(WorkerChannelState {
+            id: manager.id,
+            running: manager.is_alive.load(Ordering::SeqCst),
+        });
+    }
+
+    Ok(web::Json(states))
+}
+
+/// **Worker Channel**
+///
+/// On a worker node: create or update a channel with the configs from the controller.
+/// The response lists the changed fields, which need a restart. A channel with the same ID,
+/// which was not created by this controller, is not touched, the response is then `409`.
+///
+/// ```BASH
+/// curl -X PUT http://127.0.0.1:8787/api/worker/channel/1 -H "Content-Type: application/json" \
+/// -d '{"channel": { <CHANNEL> }, "config": { <CONFIG> }, "advanced": { <ADVANCED CONFIG> }}' \
+/// -H "Authorization: Bearer <TOKEN>"
+/// ```
+#[put("/worker/channel/{id}")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn update_worker_channel(
+    pool: web::Data<Pool<Sqlite>>,
+    id: web::Path<i32>,
+    data: web::Json<WorkerChannel>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+    queue: web::Data<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
+) -> Result<impl Responder, ServiceError> {
+    let data = data.into_inner();
+    let manager = controllers.lock().await.get(*id).await;
+    let manager = match manager {
+        Some(manager) => {
+            if handles::select_controller_channel(&pool, *id).await? != Some(user.id) {
+                return Err(ServiceError::Conflict(format!(
+                    "Channel {id} exists on the worker"
+                )));
+            }
+
+            manager
+        }
+        None => {
+            create_channel(
+                &pool,
+                controllers.clone().into_inner(),
+                queue.clone().into_inner(),
+                data.channel.clone(),
+                Some(*id),
+            )
+            .await?;
+            handles::insert_controller_channel(&pool, *id, user.id).await?;
+
+            controllers
+                .lock()
+                .await
+                .get(*id)
+                .await
+                .ok_or(ServiceError::InternalServerError)?
+        }
+    };
+
+    handles::update_channel(&pool, *id, data.channel.clone()).await?;
+    manager.clone().update_channel(&data.channel).await?;
+    handles::update_advanced_configuration(&pool, *id, data.advanced).await?;
+
+    let restart = store_playout_config(&pool, &manager, data.config, &user, &queue).await?;
+
+    Ok(web::Json(restart))
+}
+
+/// **Remove Worker Channel**
+///
+/// On a worker node: stop the channel and delete it. Only channels, which this controller
+/// has created, can be deleted.
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/worker/channel/1 -H "Authorization: Bearer <TOKEN>"
+/// ```
+#[delete("/worker/channel/{id}")]
+#[protect("Role::GlobalAdmin", ty = "Role")]
+async fn remove_worker_channel(
+    pool: web::Data<Pool<Sqlite>>,
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+    queue: web::Data<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
+) -> Result<impl Responder, ServiceError> {
+    if handles::select_controller_channel(&pool, *id).await? != Some(user.id) {
+        return Err(ServiceError::Conflict(format!(
+            "Channel {id} was not created by this controller"
+        )));
+    }
+
+    if let Some(manager) = controllers.lock().await.get(*id).await {
+        manager.channel.lock().await.active = false;
+        manager.stop_all(true).await;
+    }
+
+    delete_channel(&pool, *id, controllers.into_inner(), queue.into_inner()).await?;
+
+    Ok(web::Json("Delete Channel Success"))
+}
+
+/// #### ffplayout Config
+///
+/// **Get Advanced Config**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/playout/advanced/1 -H 'Authorization: Bearer <TOKEN>'
+/// ```
+///
+/// Response is a JSON object
+#[get("/playout/advanced/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin])"
+)]
+async fn get_advanced_config(
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or_else(|| ServiceError::BadRequest(format!("Channel ({id}) not exists!")))?;
+    let config = manager.config.lock().await.advanced.clone();
+
+    Ok(web::Json(config))
+}
+
+/// **Get related Advanced Config**
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/playout/advanced/1/ -H 'Authorization: Bearer <TOKEN>'
+/// ```
+///
+/// Response is a JSON object
+#[get("/playout/advanced/{id}/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin])"
+)]
+async fn get_related_advanced_config(
+    pool: web::Data<Pool<Sqlite>>,
+    id: web::Path<i32>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    match handles::select_related_advanced_configuration(&pool, *id).await {
+        Ok(configs) => Ok(web::Json(
+            configs
+                .iter()
+                .map(|c| AdvancedConfig::new(c.clone()))
+                .collect::<Vec<_>>(),
+        )),
+        Err(e) => {
+            error!("Advanced config: {e}");
+
+            Err(ServiceError::InternalServerError)
+        }
+    }
+}
+
+/// **Delete Advanced Config**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/playout/advanced/ -H 'Authorization: Bearer <TOKEN>'
+/// ```
+///
+/// Response is a JSON object
+#[delete("/playout/advanced/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.has_role(&path.0, &[Role::ChannelAdmin])"
+)]
+async fn remove_related_advanced_config(
+    pool: web::Data<Pool<Sqlite>>,
+    path: web::Path<(i32, i32)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (_, id) = path.into_inner();
 
     let manager = controllers
         .lock()
@@ -617,17 +1440,15 @@ async fn remove_related_advanced_config(
 /// ```
 #[put("/playout/advanced/{id}")]
 #[protect(
-    "Role::GlobalAdmin",
-    "Role::ChannelAdmin",
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin])"
 )]
 async fn update_advanced_config(
     pool: web::Data<Pool<Sqlite>>,
     id: web::Path<i32>,
     data: web::Json<AdvancedConfig>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -641,6 +1462,7 @@ async fn update_advanced_config(
     let new_config = get_config(&pool, *id).await?;
 
     manager.update_config(new_config).await;
+    workers::sync_channel(&pool, &manager).await;
 
     Ok(web::Json("Update success"))
 }
@@ -653,17 +1475,15 @@ async fn update_advanced_config(
 /// ```
 #[post("/playout/advanced/{id}/")]
 #[protect(
-    "Role::GlobalAdmin",
-    "Role::ChannelAdmin",
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin])"
 )]
 async fn add_advanced_config(
     pool: web::Data<Pool<Sqlite>>,
     id: web::Path<i32>,
     data: web::Json<AdvancedConfig>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -692,12 +1512,11 @@ async fn add_advanced_config(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 async fn get_playout_config(
     id: web::Path<i32>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -711,25 +1530,131 @@ async fn get_playout_config(
     Ok(web::Json(config))
 }
 
+/// Validate and store the config of a channel, and apply it to the running channel.
+///
+/// Response are the changed fields, which need a restart.
+/// Fields, which run commands or write files on the host, only a global admin can change.
+async fn store_playout_config(
+    pool: &Pool<Sqlite>,
+    manager: &ChannelManager,
+    mut data: PlayoutConfig,
+    user: &UserMeta,
+    mail_queues: &Mutex<Vec<Arc<Mutex<MailQueue>>>>,
+) -> Result<Vec<String>, ServiceError> {
+    let id = manager.channel.lock().await.id;
+    let p = manager.channel.lock().await.storage.clone();
+    let storage = Path::new(&p);
+    let config_id = manager.config.lock().await.general.id;
+
+    if user.role != Role::GlobalAdmin {
+        let denied = manager
+            .config
+            .lock()
+            .await
+            .changed_fields(&data)
+            .into_iter()
+            .filter(|f| is_global_admin_field(f))
+            .collect::<Vec<_>>();
+
+        if !denied.is_empty() {
+            return Err(ServiceError::Forbidden(format!(
+                "Only global admins can change: {}",
+                denied.join(", ")
+            )));
+        }
+    }
+
+    // check if received data are in abs or relative path
+    let logo = resolve_path(storage, &data.processing.logo, ABS_PATH_INDICATOR)?;
+    let font = resolve_path(storage, &data.text.font, ABS_PATH_INDICATOR)?;
+    let filler = resolve_path(storage, &data.storage.filler, ABS_PATH_INDICATOR)?;
+
+    data.processing.logo = logo;
+    data.storage.filler = filler;
+    data.text.font = font;
+
+    let issues = config_check::check(&data, storage).await;
+
+    if !issues.is_empty() {
+        let messages = issues.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        return Err(ServiceError::BadRequest(messages.join("\n")));
+    }
+
+    let old_config = manager.config.lock().await.clone();
+
+    handles::update_configuration(pool, config_id, data).await?;
+
+    // the channel keeps the old config, when the new one can not be loaded
+    let new_config = match get_config(pool, id).await {
+        Ok(config) => config,
+        Err(e) => {
+            handles::update_configuration(pool, config_id, old_config.clone()).await?;
+
+            return Err(e);
+        }
+    };
+    let mut queues = mail_queues.lock().await;
+
+    for queue in queues.iter_mut() {
+        let mut queue_lock = queue.lock().await;
+
+        if queue_lock.id == id {
+            if queue_lock.config.recipient != new_config.mail.recipient {
+                queue_lock.clear_raw();
+            }
+
+            queue_lock.update(new_config.mail.clone());
+            break;
+        }
+    }
+
+    let restart = old_config
+        .changed_fields(&new_config)
+        .into_iter()
+        .filter(|f| !is_live_field(f))
+        .collect::<Vec<_>>();
+
+    if restart.is_empty() {
+        let volume_changed = old_config.processing.volume != new_config.processing.volume;
+        let mut config = old_config;
+        config.apply_live(&new_config);
+
+        manager.update_config(config).await;
+
+        if volume_changed && manager.is_alive.load(Ordering::SeqCst) {
+            send_volume(manager).await;
+        }
+    } else {
+        manager.update_config(new_config).await;
+    }
+
+    workers::sync_channel(pool, manager).await;
+
+    Ok(restart)
+}
+
 /// **Update Config**
 ///
 /// ```BASH
 /// curl -X PUT http://127.0.0.1:8787/api/playout/config/1 -H "Content-Type: application/json" \
 /// -d { <CONFIG DATA> } -H 'Authorization: Bearer <TOKEN>'
 /// ```
+///
+/// Logo, text style, volume, filler, mail and event hooks apply to the running channel, from
+/// the next clip on. `restart` in the response lists the changed fields, which need a restart.
 #[allow(clippy::too_many_arguments)]
 #[put("/playout/config/{id}")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin])"
 )]
 async fn update_playout_config(
     pool: web::Data<Pool<Sqlite>>,
     id: web::Path<i32>,
-    mut data: web::Json<PlayoutConfig>,
+    data: web::Json<PlayoutConfig>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
     mail_queues: web::Data<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
 ) -> Result<impl Responder, ServiceError> {
@@ -739,39 +1664,59 @@ async fn update_playout_config(
         .get(*id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    let p = manager.channel.lock().await.storage.clone();
-    let storage = Path::new(&p);
-    let config_id = manager.config.lock().await.general.id;
-
-    // check if received data are in abs or relative path
-    let logo = resolve_path(storage, &data.processing.logo, ABS_PATH_INDICATOR)?;
-    let font = resolve_path(storage, &data.text.font, ABS_PATH_INDICATOR)?;
-    let filler = resolve_path(storage, &data.storage.filler, ABS_PATH_INDICATOR)?;
-
-    data.processing.logo = logo;
-    data.storage.filler = filler;
-    data.text.font = font;
-
-    handles::update_configuration(&pool, config_id, data.into_inner()).await?;
-    let new_config = get_config(&pool, *id).await?;
-    let mut queues = mail_queues.lock().await;
-
-    for queue in queues.iter_mut() {
-        let mut queue_lock = queue.lock().await;
+    let restart =
+        store_playout_config(&pool, &manager, data.into_inner(), &user, &mail_queues).await?;
 
-        if queue_lock.id == *id {
-            if queue_lock.config.recipient != new_config.mail.recipient {
-                queue_lock.clear_raw();
-            }
+    Ok(web::Json(ConfigUpdateObj {
+        message: "Update success".to_string(),
+        restart,
+    }))
+}
 
-            queue_lock.update(new_config.mail.clone());
-            break;
-        }
-    }
+/// **Validate Config**
+///
+/// Check a config without storing it. The response is a list of problems, empty when the
+/// config is valid. Updates are checked in the same way and fail with the problems.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/playout/config/1/validate -H "Content-Type: application/json" \
+/// -d { <CONFIG DATA> } -H 'Authorization: Bearer <TOKEN>'
+/// ```
+///
+/// Response is a JSON array:
+///
+/// ```JSON
+/// [
+///     { "field": "processing.fps", "message": "must be between 0 and 120" },
+///     { "field": "output.output_param", "message": "encoder libx265 requested but not available in /usr/bin/ffmpeg" }
+/// ]
+/// ```
+#[post("/playout/config/{id}/validate")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin])"
+)]
+async fn validate_playout_config(
+    id: web::Path<i32>,
+    mut data: web::Json<PlayoutConfig>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let p = manager.channel.lock().await.storage.clone();
+    let storage = Path::new(&p);
 
-    manager.update_config(new_config).await;
+    data.processing.logo = resolve_path(storage, &data.processing.logo, ABS_PATH_INDICATOR)?;
+    data.text.font = resolve_path(storage, &data.text.font, ABS_PATH_INDICATOR)?;
+    data.storage.filler = resolve_path(storage, &data.storage.filler, ABS_PATH_INDICATOR)?;
 
-    Ok(web::Json("Update success"))
+    Ok(web::Json(config_check::check(&data, storage).await))
 }
 
 /// #### Text Presets
@@ -786,14 +1731,13 @@ async fn update_playout_config(
 /// ```
 #[get("/presets/{id}")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
 )]
 async fn get_presets(
     pool: web::Data<Pool<Sqlite>>,
     id: web::Path<i32>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     if let Ok(presets) = handles::select_presets(&pool, *id).await {
@@ -814,13 +1758,12 @@ async fn get_presets(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&path.0, &[Role::ChannelAdmin, Role::User])"
 )]
 async fn update_preset(
     pool: web::Data<Pool<Sqlite>>,
     path: web::Path<(i32, i32)>,
     data: web::Json<TextPreset>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let (_, id) = path.into_inner();
@@ -846,13 +1789,12 @@ async fn update_preset(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 async fn add_preset(
     pool: web::Data<Pool<Sqlite>>,
     id: web::Path<i32>,
     data: web::Json<TextPreset>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     if handles::insert_preset(&pool, data.into_inner())
@@ -868,56 +1810,120 @@ async fn add_preset(
 /// **Delete Preset**
 ///
 /// ```BASH
-/// curl -X DELETE http://127.0.0.1:8787/api/presets/1/1 -H 'Content-Type: application/json' \
-/// -H 'Authorization: Bearer <TOKEN>'
+/// curl -X DELETE http://127.0.0.1:8787/api/presets/1/1 -H 'Content-Type: application/json' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/presets/{channel}/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.has_role(&path.0, &[Role::ChannelAdmin, Role::User])"
+)]
+async fn delete_preset(
+    pool: web::Data<Pool<Sqlite>>,
+    path: web::Path<(i32, i32)>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (_, id) = path.into_inner();
+
+    if handles::delete_preset(&pool, &id).await.is_ok() {
+        return Ok("Delete preset Success");
+    }
+
+    Err(ServiceError::InternalServerError)
+}
+
+/// ### ffplayout controlling
+///
+/// here we communicate with the engine for:
+/// - jump to last or next clip
+/// - reset playlist state
+/// - get infos about current, next, last clip
+/// - send text to the engine, for overlaying it (as lower third etc.)
+///
+/// **Send Text to ffplayout**
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/text/ \
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>' \
+/// -d '{"text": "Hello from ffplayout", "x": "(w-text_w)/2", "y": "(h-text_h)/2", fontsize": "24", "line_spacing": "4", "fontcolor": "#ffffff", "box": "1", "boxcolor": "#000000", "boxborderw": "4", "alpha": "1.0"}'
+/// ```
+#[post("/control/{id}/text/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
+)]
+pub async fn send_text_message(
+    id: web::Path<i32>,
+    data: web::Json<TextFilter>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    match send_message(manager, data.into_inner()).await {
+        Ok(res) => Ok(web::Json(res)),
+        Err(e) => Err(e),
+    }
+}
+
+/// **Queue Text Message**
+///
+/// Messages from the queue are shown one after the other, each for its `duration` in seconds.
+/// The text can have the template variables `{{title}}`, `{{next_title}}` and `{{time}}`,
+/// `position` can be: lower_third, top, top_left, top_right, center, bottom, bottom_left, bottom_right.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/message/ \
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>' \
+/// -d '{"text": "Next: {{next_title}}", "duration": 10, "position": "lower_third", "fontsize": 32, "fontcolor": "#ffffff", "box": 1, "boxcolor": "#000000@0.6"}'
 /// ```
-#[delete("/presets/{channel}/{id}")]
+///
+/// **Clear Text Messages**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/control/1/message/ -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/control/{id}/message/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&path.0) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
-async fn delete_preset(
-    pool: web::Data<Pool<Sqlite>>,
-    path: web::Path<(i32, i32)>,
-    role: AuthDetails<Role>,
+pub async fn add_text_message(
+    id: web::Path<i32>,
+    data: web::Json<TextMessage>,
+    controllers: web::Data<Mutex<ChannelController>>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
-    let (_, id) = path.into_inner();
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
 
-    if handles::delete_preset(&pool, &id).await.is_ok() {
-        return Ok("Delete preset Success");
+    match queue_message(manager, data.into_inner()).await {
+        Ok(res) => Ok(web::Json(res)),
+        Err(e) => Err(e),
     }
-
-    Err(ServiceError::InternalServerError)
 }
 
-/// ### ffplayout controlling
-///
-/// here we communicate with the engine for:
-/// - jump to last or next clip
-/// - reset playlist state
-/// - get infos about current, next, last clip
-/// - send text to the engine, for overlaying it (as lower third etc.)
-///
-/// **Send Text to ffplayout**
-///
-/// ```BASH
-/// curl -X POST http://127.0.0.1:8787/api/control/1/text/ \
-/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>' \
-/// -d '{"text": "Hello from ffplayout", "x": "(w-text_w)/2", "y": "(h-text_h)/2", fontsize": "24", "line_spacing": "4", "fontcolor": "#ffffff", "box": "1", "boxcolor": "#000000", "boxborderw": "4", "alpha": "1.0"}'
-/// ```
-#[post("/control/{id}/text/")]
+#[delete("/control/{id}/message/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
-pub async fn send_text_message(
+pub async fn clear_text_messages(
     id: web::Path<i32>,
-    data: web::Json<TextFilter>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -927,7 +1933,7 @@ pub async fn send_text_message(
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
 
-    match send_message(manager, data.into_inner()).await {
+    match clear_messages(manager).await {
         Ok(res) => Ok(web::Json(res)),
         Err(e) => Err(e),
     }
@@ -937,24 +1943,28 @@ pub async fn send_text_message(
 ///
 /// - next
 /// - back
+/// - goto (with `index` or `source`, optional `position`)
+/// - seek (with `position` or `offset`)
+/// - hold (with `mode`: `loop` or `freeze`)
+/// - release
+/// - take
 /// - reset
 ///
 /// ```BASH
 /// curl -X POST http://127.0.0.1:8787/api/control/1/playout/ -H 'Content-Type: application/json'
-/// -d '{ "command": "reset" }' -H 'Authorization: Bearer <TOKEN>'
+/// -d '{ "control": "goto", "index": 12 }' -H 'Authorization: Bearer <TOKEN>'
 /// ```
 #[post("/control/{id}/playout/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 pub async fn control_playout(
     pool: web::Data<Pool<Sqlite>>,
     id: web::Path<i32>,
     control: web::Json<ControlParams>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -972,7 +1982,54 @@ pub async fn control_playout(
 
     manager.is_processing.store(true, Ordering::SeqCst);
 
-    let resp = match control_state(&pool, &manager, &control.control).await {
+    let resp = match control_state(&pool, &manager, &control).await {
+        Ok(res) => Ok(web::Json(res)),
+        Err(e) => Err(e),
+    };
+
+    manager.is_processing.store(false, Ordering::SeqCst);
+
+    resp
+}
+
+/// **Insert Next**
+///
+/// Insert a clip, or a live source with duration, as next item of the running playlist.
+/// With mode `push` the following clips start later, with `trim` the following filler gets shorter.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/insert/ -H 'Content-Type: application/json'
+/// -d '{ "source": "srt://127.0.0.1:9000", "duration": 300, "mode": "trim" }' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/control/{id}/insert/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
+)]
+pub async fn control_insert(
+    pool: web::Data<Pool<Sqlite>>,
+    id: web::Path<i32>,
+    data: web::Json<InsertParams>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    if manager.is_processing.load(Ordering::SeqCst) {
+        return Err(ServiceError::Conflict(
+            "A command is already being processed, please wait".to_string(),
+        ));
+    }
+
+    manager.is_processing.store(true, Ordering::SeqCst);
+
+    let resp = match insert_next(&pool, &manager, &data).await {
         Ok(res) => Ok(web::Json(res)),
         Err(e) => Err(e),
     };
@@ -1003,19 +2060,29 @@ pub async fn control_playout(
 ///       "index": 39,
 ///       "ingest": false,
 ///       "mode": "playlist",
-///       "played": 67.808
+///       "played": 67.808,
+///       "encoder": {
+///         "frame": 1695,
+///         "fps": 25.0,
+///         "bitrate": 2512.4,
+///         "speed": 1.0,
+///         "drop_frames": 0,
+///         "dup_frames": 2,
+///         "total_size": 21289472,
+///         "out_time": 67.8,
+///         "updated": "2024-06-01T10:01:07.808+02:00"
+///       }
 ///     }
 /// ```
 #[get("/control/{id}/media/current")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
 )]
 pub async fn media_current(
     id: web::Path<i32>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -1035,6 +2102,7 @@ pub async fn media_current(
 /// Control ffplayout process, like:
 /// - start
 /// - stop
+/// - stop_after_current (let the current clip finish, then stop)
 /// - restart
 /// - status
 ///
@@ -1047,13 +2115,12 @@ pub async fn media_current(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 pub async fn process_control(
     id: web::Path<i32>,
     proc: web::Json<Process>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -1062,51 +2129,113 @@ pub async fn process_control(
         .get(*id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    manager.list_init.store(true, Ordering::SeqCst);
 
-    if manager.is_processing.load(Ordering::SeqCst) {
-        return Err(ServiceError::Conflict(
-            "A command is already being processed, please wait".to_string(),
-        ));
-    }
+    Ok(web::Json(
+        process_command(&manager, proc.into_inner().command).await?,
+    ))
+}
 
-    manager.is_processing.store(true, Ordering::SeqCst);
+/// #### Archive Recorder Control
+///
+/// Start, stop, restart the archive recorder, or get its status.
+/// The recorder writes the program output to `recorder.path`.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/control/1/recorder/
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// -d '{"command": "start"}'
+/// ```
+#[post("/control/{id}/recorder/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
+)]
+pub async fn recorder_control(
+    id: web::Path<i32>,
+    proc: web::Json<Process>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
 
     match proc.into_inner().command {
         ProcessCtl::Status => {
-            manager.is_processing.store(false, Ordering::SeqCst);
-
-            if manager.is_alive.load(Ordering::SeqCst) {
+            if manager.recorder.is_alive.load(Ordering::SeqCst) {
                 return Ok(web::Json("active"));
             }
             return Ok(web::Json("not running"));
         }
         ProcessCtl::Start => {
-            if !manager.is_alive.load(Ordering::SeqCst) {
-                manager.channel.lock().await.active = true;
-                manager.start().await?;
-            }
+            manager.recorder.start(&config).await?;
         }
         ProcessCtl::Stop => {
-            manager.channel.lock().await.active = false;
-            manager.stop_all(true).await;
+            manager.recorder.stop().await;
+        }
+        ProcessCtl::StopAfterCurrent => {
+            return Err(ServiceError::BadRequest(
+                "Command is not supported by the recorder".to_string(),
+            ));
         }
         ProcessCtl::Restart => {
-            manager.channel.lock().await.active = false;
-            manager.stop_all(false).await;
+            manager.recorder.stop().await;
 
             tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
-            manager.channel.lock().await.active = true;
-            manager.start().await?;
+            manager.recorder.start(&config).await?;
         }
     }
 
-    manager.is_processing.store(false, Ordering::SeqCst);
-
     Ok(web::Json("Success"))
 }
 
+/// **Export Clip**
+///
+/// Cut a clip from the archive recordings, with stream copy, and save it in the channel storage.
+/// Start and end are in local time, the name is optional.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/channel/1/clip -H 'Content-Type: application/json'
+/// -d '{"start": "2024-10-01T12:00:00", "end": "2024-10-01T12:05:30", "name": "goal.ts"}'
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/channel/{id}/clip")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
+)]
+pub async fn export_clip(
+    id: web::Path<i32>,
+    data: web::Json<ClipParams>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    if !matches!(*manager.storage.lock().await, StorageBackend::Local(_)) {
+        return Err(ServiceError::Conflict(
+            "Clip export needs a local storage".to_string(),
+        ));
+    }
+
+    let name = recorder::export_clip(&config, &data.into_inner()).await?;
+
+    Ok(web::Json(name))
+}
+
 /// #### ffplayout Playlist Operations
 ///
 /// **Get playlist**
@@ -1117,15 +2246,14 @@ pub async fn process_control(
 /// ```
 #[get("/playlist/{id}")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
 )]
 pub async fn get_playlist(
     id: web::Path<i32>,
     obj: web::Query<DateObj>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -1153,13 +2281,12 @@ pub async fn get_playlist(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 pub async fn save_playlist(
     id: web::Path<i32>,
     data: web::Json<JsonPlaylist>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -1177,10 +2304,88 @@ pub async fn save_playlist(
         media.source = storage.sanitized_file_path(&cloned_media_source);
     }
 
-    match write_playlist(&config, data).await {
-        Ok(res) => Ok(web::Json(res)),
-        Err(e) => Err(e),
+    let old = read_playlist(&config, data.date.clone()).await.ok();
+    let res = write_playlist(&config, data.clone()).await?;
+
+    if let Err(e) = save_revision(&manager.db_pool, *id, Some(user.id), old.as_ref(), &data).await {
+        error!("Save playlist revision failed: {e}");
+    }
+
+    Ok(web::Json(res))
+}
+
+/// **Playlist Revisions**
+///
+/// List the saved revisions of a playlist, newest first, with user, time and changed clips.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/playlist/1/revisions/2022-06-20
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/playlist/{id}/revisions/{date}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role",
+    expr = "user.has_role(&params.0, &[Role::ChannelAdmin, Role::User, Role::Guest])"
+)]
+pub async fn get_playlist_revisions(
+    pool: web::Data<Pool<Sqlite>>,
+    params: web::Path<(i32, String)>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (id, date) = params.into_inner();
+
+    match handles::select_playlist_revisions(&pool, id, &date).await {
+        Ok(revisions) => Ok(web::Json(revisions)),
+        Err(e) => {
+            error!("{e}");
+            Err(ServiceError::InternalServerError)
+        }
+    }
+}
+
+/// **Rollback Playlist**
+///
+/// Write the playlist of a previous revision back, the rollback is saved as new revision.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/playlist/1/rollback/12
+/// -H 'Content-Type: application/json' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/playlist/{id}/rollback/{revision}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.has_role(&params.0, &[Role::ChannelAdmin, Role::User])"
+)]
+pub async fn rollback_playlist(
+    params: web::Path<(i32, i32)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (id, revision) = params.into_inner();
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    let revision = handles::select_playlist_revision(&manager.db_pool, id, revision)
+        .await
+        .map_err(|_| ServiceError::NoContent("Revision not found".to_string()))?;
+    let data: JsonPlaylist = serde_json::from_str(&revision.playlist.unwrap_or_default())
+        .map_err(|e| ServiceError::Conflict(format!("Revision is not readable: {e}")))?;
+
+    let old = read_playlist(&config, data.date.clone()).await.ok();
+    let res = write_playlist(&config, data.clone()).await?;
+
+    if let Err(e) = save_revision(&manager.db_pool, id, Some(user.id), old.as_ref(), &data).await {
+        error!("Save playlist revision failed: {e}");
     }
+
+    Ok(web::Json(res))
 }
 
 /// **Generate Playlist**
@@ -1205,13 +2410,12 @@ pub async fn save_playlist(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&params.0) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&params.0, &[Role::ChannelAdmin, Role::User])"
 )]
 pub async fn gen_playlist(
     params: web::Path<(i32, String)>,
     data: Option<web::Json<PathsObj>>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let (id, date) = params.into_inner();
@@ -1262,12 +2466,11 @@ pub async fn gen_playlist(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&params.0) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&params.0, &[Role::ChannelAdmin, Role::User])"
 )]
 pub async fn del_playlist(
     params: web::Path<(i32, String)>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let (id, date) = params.into_inner();
@@ -1297,12 +2500,11 @@ pub async fn del_playlist(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 pub async fn get_log(
     id: web::Path<i32>,
     log: web::Query<DateObj>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     read_log_file(&id, &log.date).await
@@ -1320,13 +2522,12 @@ pub async fn get_log(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 pub async fn file_browser(
     id: web::Path<i32>,
     data: web::Json<PathObject>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
     duration: web::Data<SharedMediaMap>,
 ) -> Result<impl Responder, ServiceError> {
@@ -1354,13 +2555,12 @@ pub async fn file_browser(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 pub async fn add_dir(
     id: web::Path<i32>,
     data: web::Json<PathObject>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<HttpResponse, ServiceError> {
     let manager = controllers
@@ -1386,13 +2586,12 @@ pub async fn add_dir(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 pub async fn move_rename(
     id: web::Path<i32>,
     data: web::Json<MoveObject>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
     duration: web::Data<SharedMediaMap>,
 ) -> Result<impl Responder, ServiceError> {
@@ -1419,13 +2618,12 @@ pub async fn move_rename(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 pub async fn remove(
     id: web::Path<i32>,
     data: web::Json<PathObject>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
     duration: web::Data<SharedMediaMap>,
 ) -> Result<impl Responder, ServiceError> {
@@ -1438,13 +2636,71 @@ pub async fn remove(
     let storage = manager.storage.lock().await;
     let recursive = data.recursive;
 
-    match storage
-        .remove(&data.into_inner().source, duration.clone(), recursive)
-        .await
-    {
-        Ok(obj) => Ok(web::Json(obj)),
-        Err(e) => Err(e),
-    }
+    match storage
+        .remove(&data.into_inner().source, duration.clone(), recursive)
+        .await
+    {
+        Ok(obj) => Ok(web::Json(obj)),
+        Err(e) => Err(e),
+    }
+}
+
+/// **Preview File**
+///
+/// Transcode a part of a file with the filter chain of the channel to HLS, for checking it
+/// before it goes into a playlist. `seek` and `length` are in seconds, the length is at most
+/// five minutes. The session ends after five minutes, the response has the URL of its playlist.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/file/1/preview/ -H 'Content-Type: application/json'
+/// -d '{"source": "/movies/film.mp4", "seek": 120, "length": 60}' -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/file/{id}/preview/")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
+)]
+pub async fn preview_file(
+    id: web::Path<i32>,
+    data: web::Json<PreviewRequest>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+    let (source, _, _) = norm_abs_path(&config.channel.storage, &data.source)?;
+
+    let session = preview_session::start(&config, &source, &data).await?;
+
+    Ok(web::Json(session))
+}
+
+/// **Stop File Preview**
+///
+/// ```BASH
+/// curl -X DELETE http://127.0.0.1:8787/api/file/1/preview/<SESSION ID> -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[delete("/file/{id}/preview/{session}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    ty = "Role",
+    expr = "user.has_role(&path.0, &[Role::ChannelAdmin, Role::User])"
+)]
+pub async fn stop_preview_file(
+    path: web::Path<(i32, String)>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (id, session) = path.into_inner();
+
+    preview_session::stop(id, &session).await?;
+
+    Ok(web::Json("Preview stopped"))
 }
 
 /// **Upload File**
@@ -1458,7 +2714,7 @@ pub async fn remove(
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 async fn save_file(
     id: web::Path<i32>,
@@ -1466,7 +2722,6 @@ async fn save_file(
     payload: Multipart,
     obj: web::Query<FileObj>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<HttpResponse, ServiceError> {
     let manager = controllers
@@ -1518,6 +2773,47 @@ async fn get_file(
     Ok(opened_file)
 }
 
+/// **Get HLS Key**
+///
+/// Key for encrypted HLS segments, the players get the URL from the playlist.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/hls/1/key/key_1718000000.key -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/hls/{id}/key/{name}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role",
+    expr = "user.has_role(&path.0, &[Role::ChannelAdmin, Role::User, Role::Guest])"
+)]
+pub async fn get_hls_key(
+    path: web::Path<(i32, String)>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (id, name) = path.into_inner();
+
+    if !hls_key::is_key_name(&name) {
+        return Err(ServiceError::BadRequest("Invalid key name".to_string()));
+    }
+
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let public = manager.config.lock().await.channel.public.clone();
+    let key = fs::read(hls_key::dir(&public).join(&name))
+        .await
+        .map_err(|_| ServiceError::BadRequest("Key not found".to_string()))?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/octet-stream")
+        .insert_header(("Cache-Control", "no-store"))
+        .body(key))
+}
+
 /// **Get Public**
 ///
 /// Can be used for HLS Playlist and other static files in public folder
@@ -1532,9 +2828,25 @@ async fn get_public(
 ) -> Result<actix_files::NamedFile, ServiceError> {
     let (id, public, file_stem) = path.into_inner();
 
+    if Path::new(&file_stem)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(ServiceError::BadRequest("Invalid file path".to_string()));
+    }
+
+    let library_preview = public == "preview"
+        && file_stem.starts_with("library/")
+        && (file_stem.ends_with(".jpg") || file_stem.ends_with(".vtt"));
+    let mut preview_root = None;
+
     let absolute_path = if file_stem.ends_with(".ts")
+        || file_stem.ends_with(".m4s")
+        || file_stem.ends_with("_init.mp4")
+        || file_stem.ends_with(".mpd")
         || file_stem.ends_with(".m3u8")
         || file_stem.ends_with(".vtt")
+        || library_preview
     {
         let manager = controllers
             .lock()
@@ -1543,13 +2855,23 @@ async fn get_public(
             .await
             .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
         let config = manager.config.lock().await;
+
+        if library_preview {
+            preview_root = Some(preview::root(&config).clean());
+        }
+
         config.channel.public.join(public)
     } else {
         public_path()
     }
     .clean();
 
-    let path = absolute_path.join(file_stem.as_str());
+    let path = absolute_path.join(file_stem.as_str()).clean();
+
+    if preview_root.is_some_and(|root| !path.starts_with(root)) {
+        return Err(ServiceError::BadRequest("Invalid file path".to_string()));
+    }
+
     let file = actix_files::NamedFile::open(path)?; // to:do = handle for s3
 
     Ok(file
@@ -1569,11 +2891,14 @@ async fn get_public(
 /// curl -X PUT http://127.0.0.1:8787/api/file/1/import/ -H 'Authorization: Bearer <TOKEN>'
 /// -F "file=@list.m3u"
 /// ```
+///
+/// A CSV rundown with the columns `start`, `source`, `title` and `duration` is converted too.
+/// When rows are not valid, the response is a bad request with the errors per row.
 #[put("/file/{id}/import/")]
 #[protect(
     any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User])"
 )]
 async fn import_playlist(
     // to-do : check this one!
@@ -1581,7 +2906,6 @@ async fn import_playlist(
     payload: Multipart,
     obj: web::Query<ImportObj>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<HttpResponse, ServiceError> {
     let manager = controllers
@@ -1591,7 +2915,7 @@ async fn import_playlist(
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
     let channel_name = manager.channel.lock().await.name.clone();
-    let playlists = manager.config.lock().await.channel.playlists.clone();
+    let config = manager.config.lock().await.clone();
     let storage = manager.storage.lock().await;
     let file = obj.file.file_name().unwrap_or_default();
     let path = env::temp_dir().join(file);
@@ -1599,13 +2923,302 @@ async fn import_playlist(
 
     storage.upload(payload, &path, true).await?;
 
-    let response = import_file(&playlists, &obj.date, Some(channel_name), &path_clone).await?;
+    let response = import_file(
+        &config.channel.playlists,
+        &config.channel.storage,
+        config.playlist.start_sec.unwrap_or_default(),
+        &obj.date,
+        Some(channel_name),
+        &path_clone,
+    )
+    .await;
 
     fs::remove_file(path).await?;
 
+    let response = response.map_err(|e| match e.kind() {
+        io::ErrorKind::InvalidData => ServiceError::BadRequest(e.to_string()),
+        _ => e.into(),
+    })?;
+
     Ok(HttpResponse::Ok().body(response))
 }
 
+/// **Media Library**
+///
+/// Search the indexed files of the channel storage. All parameters are optional,
+/// `search` matches path, title, artist, album and genre, `codec` the video or audio codec,
+/// `query` takes a tag query like `tag:music AND duration<300`.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/library/1?search=jingle&min_duration=5&max_duration=30&limit=50' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/library/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
+)]
+pub async fn get_library(
+    pool: web::Data<Pool<Sqlite>>,
+    id: web::Path<i32>,
+    query: web::Query<LibraryQuery>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let items = library::search(&pool, *id, &query).await?;
+
+    Ok(web::Json(items))
+}
+
+/// **Scan Media Library**
+///
+/// Index new and changed files of the channel storage now, without waiting for the scanner.
+/// Response is the number of added, updated and removed files.
+///
+/// ```BASH
+/// curl -X POST http://127.0.0.1:8787/api/library/1/scan -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[post("/library/{id}/scan")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin])"
+)]
+pub async fn scan_library(
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+
+    let result = library::scan(&manager.db_pool, &config).await?;
+
+    Ok(web::Json(result))
+}
+
+/// **Library Preview**
+///
+/// Thumbnail, preview sprite and its WebVTT of an indexed file, generated when they are missing.
+/// The links go to the public route of the channel.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/library/1/preview?path=/tv-media/movies/film.mp4' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+///
+/// **Response:**
+///
+/// ```JSON
+/// {
+///     "thumbnail": "/1/preview/library/<checksum>/thumbnail.jpg",
+///     "sprite": "/1/preview/library/<checksum>/sprite.jpg",
+///     "vtt": "/1/preview/library/<checksum>/sprite.vtt"
+/// }
+/// ```
+#[get("/library/{id}/preview")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
+)]
+pub async fn get_library_preview(
+    id: web::Path<i32>,
+    obj: web::Query<PreviewObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+    let item = handles::select_library_item(&manager.db_pool, *id, &obj.path)
+        .await?
+        .ok_or(ServiceError::NoContent(format!(
+            "{} is not in the media library",
+            obj.path
+        )))?;
+
+    let preview = preview::generate(&config, &item).await?;
+
+    Ok(web::Json(preview))
+}
+
+/// **Airing History**
+///
+/// Aired clips of a date range, with start time in the channel time zone and the duration on air.
+/// Without `start` and `end` the report is from today.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/history/1?start=2024-06-01&end=2024-06-30' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/history/{id}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
+)]
+pub async fn get_history(
+    id: web::Path<i32>,
+    obj: web::Query<HistoryObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (manager, start, end) = history_range(*id, &obj, &controllers).await?;
+    let airings = handles::select_airings(&manager.db_pool, *id, &start, &end).await?;
+
+    Ok(web::Json(airings))
+}
+
+/// **Play Counts**
+///
+/// How often each source aired in a date range, with the total duration.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/history/1/plays?start=2024-06-01&end=2024-06-30' \
+/// -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/history/{id}/plays")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
+)]
+pub async fn get_play_counts(
+    id: web::Path<i32>,
+    obj: web::Query<HistoryObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let (manager, start, end) = history_range(*id, &obj, &controllers).await?;
+    let counts = handles::select_play_counts(&manager.db_pool, *id, &start, &end).await?;
+
+    Ok(web::Json(counts))
+}
+
+/// **Export Airing History**
+///
+/// Aired clips of a date range as CSV file.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/history/1/history.csv?start=2024-06-01&end=2024-06-30' \
+/// -H 'Authorization: Bearer <TOKEN>' -o history.csv
+/// ```
+#[get("/history/{id}/history.csv")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role",
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
+)]
+pub async fn export_history(
+    id: web::Path<i32>,
+    obj: web::Query<HistoryObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<HttpResponse, ServiceError> {
+    let (manager, start, end) = history_range(*id, &obj, &controllers).await?;
+    let airings = handles::select_airings(&manager.db_pool, *id, &start, &end).await?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv; charset=utf-8")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![],
+        })
+        .body(history::csv(&airings)?))
+}
+
+/// Channel and range of start times for the history, today when dates are missing.
+async fn history_range(
+    id: i32,
+    obj: &HistoryObj,
+    controllers: &web::Data<Mutex<ChannelController>>,
+) -> Result<(ChannelManager, String, String), ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let timezone = manager.config.lock().await.channel.timezone;
+    let today = time_now(&timezone).format("%Y-%m-%d").to_string();
+    let start = if obj.start.is_empty() {
+        &today
+    } else {
+        &obj.start
+    };
+    let end = if obj.end.is_empty() { start } else { &obj.end };
+    let (start, end) = history::date_range(start, end)?;
+
+    Ok((manager, start, end))
+}
+
+/// **Get As-Run Log**
+///
+/// As-run log of a playlist day, with scheduled and actual start times. The format is
+/// `xml` or `csv`, default is the format from the channel config. A log which was already
+/// written to disk is returned as it is.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/api/asrun/1/2024-06-01?format=csv' \
+/// -H 'Authorization: Bearer <TOKEN>' -o asrun.csv
+/// ```
+#[get("/asrun/{id}/{date}")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role",
+    expr = "user.has_role(&path.0, &[Role::ChannelAdmin, Role::User, Role::Guest])"
+)]
+pub async fn get_asrun(
+    path: web::Path<(i32, String)>,
+    obj: web::Query<AsRunObj>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<HttpResponse, ServiceError> {
+    let (id, date) = path.into_inner();
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|_| ServiceError::BadRequest(format!("Invalid date: {date}")))?;
+    let manager = controllers
+        .lock()
+        .await
+        .get(id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let config = manager.config.lock().await.clone();
+    let format = if obj.format.is_empty() {
+        config.asrun.format.clone()
+    } else {
+        obj.format.clone()
+    };
+    let file = asrun::file(&config, &date, &format);
+    let body = if file.is_file() {
+        fs::read_to_string(&file).await?
+    } else {
+        asrun::document(&manager.db_pool, &config, date, &format).await?
+    };
+    let content_type = match asrun::extension(&format) {
+        "csv" => "text/csv; charset=utf-8",
+        _ => "application/xml; charset=utf-8",
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(content_type)
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![],
+        })
+        .body(body))
+}
+
 /// **Program info**
 ///
 /// Get program infos about given date, or current day
@@ -1630,15 +3243,14 @@ async fn import_playlist(
 /// ```
 #[get("/program/{id}/")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
 )]
 async fn get_program(
     id: web::Path<i32>,
     obj: web::Query<ProgramObj>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -1738,14 +3350,13 @@ async fn get_program(
 /// ```
 #[get("/system/{id}")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
     ty = "Role",
-    expr = "user.channels.contains(&*id) || role.has_authority(&Role::GlobalAdmin)"
+    expr = "user.has_role(&*id, &[Role::ChannelAdmin, Role::User, Role::Guest])"
 )]
 pub async fn get_system_stat(
     id: web::Path<i32>,
     controllers: web::Data<Mutex<ChannelController>>,
-    role: AuthDetails<Role>,
     user: web::ReqData<UserMeta>,
 ) -> Result<impl Responder, ServiceError> {
     let manager = controllers
@@ -1760,3 +3371,113 @@ pub async fn get_system_stat(
 
     Ok(web::Json(stat))
 }
+
+/// ### Metrics
+///
+/// Encoder state of all channels of the user, in the text format of Prometheus.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/api/metrics -H 'Authorization: Bearer <TOKEN>'
+/// ```
+#[get("/metrics")]
+#[protect(
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
+    ty = "Role"
+)]
+pub async fn get_metrics(
+    pool: web::Data<Pool<Sqlite>>,
+    controllers: web::Data<Mutex<ChannelController>>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
+    let channels = handles::select_related_channels(&pool, Some(user.id)).await?;
+    let managers = controllers.lock().await.managers.clone();
+    let mut states = vec![];
+
+    for manager in managers
+        .iter()
+        .filter(|m| channels.iter().any(|c| c.id == m.id))
+    {
+        states.push(ChannelMetrics {
+            id: manager.id,
+            running: manager.is_alive.load(Ordering::SeqCst),
+            encoder: manager.encoder_progress.lock().await.clone(),
+        });
+    }
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(progress::metrics(&states)))
+}
+
+#[derive(Debug, Serialize)]
+struct HealthObj {
+    id: i32,
+    alive: bool,
+    ready: bool,
+}
+
+async fn channel_health(controllers: &Mutex<ChannelController>) -> Vec<HealthObj> {
+    let managers = controllers.lock().await.managers.clone();
+    let mut health = vec![];
+
+    for manager in managers {
+        if !manager.channel.lock().await.runs_here() {
+            continue;
+        }
+
+        health.push(HealthObj {
+            id: manager.id,
+            alive: manager.is_alive.load(Ordering::SeqCst),
+            ready: manager.pipeline_running().await,
+        });
+    }
+
+    health
+}
+
+/// ### Health Checks
+///
+/// For liveness and readiness probes, like in Kubernetes. They need no authentication
+/// and only check channels which are active on this node.
+///
+/// **Liveness**
+///
+/// Status 503 when the player of an active channel stopped.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/healthz
+/// ```
+/// **Response:**
+///
+/// ```JSON
+/// [{ "id": 1, "alive": true, "ready": true }]
+/// ```
+#[get("/healthz")]
+async fn healthz(controllers: web::Data<Mutex<ChannelController>>) -> impl Responder {
+    let health = channel_health(&controllers).await;
+
+    if health.iter().all(|h| h.alive) {
+        HttpResponse::Ok().json(health)
+    } else {
+        HttpResponse::ServiceUnavailable().json(health)
+    }
+}
+
+/// **Readiness**
+///
+/// Status 503 until the ffmpeg processes of all active channels run,
+/// and when no channel is active.
+///
+/// ```BASH
+/// curl -X GET http://127.0.0.1:8787/readyz
+/// ```
+#[get("/readyz")]
+async fn readyz(controllers: web::Data<Mutex<ChannelController>>) -> impl Responder {
+    let health = channel_health(&controllers).await;
+
+    if !health.is_empty() && health.iter().all(|h| h.ready) {
+        HttpResponse::Ok().json(health)
+    } else {
+        HttpResponse::ServiceUnavailable().json(health)
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use actix_web::web;
 use argon2::{
     password_hash::{rand_core::OsRng, SaltString},
@@ -7,7 +9,10 @@ use rand::{distr::Alphanumeric, Rng};
 use sqlx::{sqlite::SqliteQueryResult, Pool, Row, Sqlite};
 
 use super::models::{AdvancedConfiguration, Configuration};
-use crate::db::models::{Channel, GlobalSettings, Role, TextPreset, User};
+use crate::db::models::{
+    Airing, ApiToken, AuditEntry, AuditQuery, Channel, ChannelGroup, GlobalSettings, LibraryItem,
+    LibraryQuery, Loudness, PlayCount, Playhead, PlaylistRevision, Role, TextPreset, User, Worker,
+};
 use crate::utils::{
     advanced_config::AdvancedConfig,
     config::PlayoutConfig,
@@ -49,7 +54,8 @@ pub async fn db_migrate(conn: &Pool<Sqlite>) -> Result<bool, ProcessError> {
 
 pub async fn select_global(conn: &Pool<Sqlite>) -> Result<GlobalSettings, ProcessError> {
     const QUERY: &str =
-        "SELECT id, secret, logs, playlists, public, storage, shared, smtp_server, smtp_user, smtp_password, smtp_starttls, smtp_port FROM global WHERE id = 1";
+        "SELECT id, secret, logs, playlists, public, storage, shared, smtp_server, smtp_user, smtp_password, smtp_starttls, smtp_port,
+        oidc_issuer, oidc_client_id, oidc_client_secret, oidc_redirect_url FROM global WHERE id = 1";
 
     let result = sqlx::query_as(QUERY).fetch_one(conn).await?;
 
@@ -61,7 +67,8 @@ pub async fn update_global(
     global: GlobalSettings,
 ) -> Result<SqliteQueryResult, ProcessError> {
     const QUERY: &str = "UPDATE global SET logs = $2, playlists = $3, public = $4, storage = $5,
-            smtp_server = $6, smtp_user = $7, smtp_password = $8, smtp_starttls = $9, smtp_port = $10  WHERE id = 1";
+            smtp_server = $6, smtp_user = $7, smtp_password = $8, smtp_starttls = $9, smtp_port = $10,
+            oidc_issuer = $11, oidc_client_id = $12, oidc_client_secret = $13, oidc_redirect_url = $14 WHERE id = 1";
 
     let result = sqlx::query(QUERY)
         .bind(global.id)
@@ -74,6 +81,10 @@ pub async fn update_global(
         .bind(global.smtp_password)
         .bind(global.smtp_starttls)
         .bind(global.smtp_port)
+        .bind(global.oidc_issuer)
+        .bind(global.oidc_client_id)
+        .bind(global.oidc_client_secret)
+        .bind(global.oidc_redirect_url)
         .execute(conn)
         .await?;
 
@@ -95,7 +106,7 @@ pub async fn select_related_channels(
     let query = match user_id {
         Some(id) => format!(
             "SELECT c.id, c.name, c.preview_url, c.extra_extensions, c.active, c.public, c.playlists,
-            c.storage, c.last_date, c.time_shift, c.timezone, c.advanced_id FROM channels c
+            c.storage, c.last_date, c.time_shift, c.timezone, c.advanced_id, c.group_id, c.worker_id FROM channels c
                 left join user_channels uc on uc.channel_id = c.id
                 left join user u on u.id = uc.user_id
              WHERE u.id = {id} ORDER BY c.id ASC;"
@@ -146,6 +157,270 @@ pub async fn update_channel(
     Ok(result)
 }
 
+pub async fn select_channel_groups(conn: &Pool<Sqlite>) -> Result<Vec<ChannelGroup>, ProcessError> {
+    const QUERY: &str =
+        "SELECT g.id, g.name, group_concat(c.id, ',') as channel_ids FROM channel_groups g
+        left join channels c on c.group_id = g.id GROUP BY g.id ORDER BY g.id";
+
+    let result = sqlx::query_as(QUERY).fetch_all(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn select_channel_group(
+    conn: &Pool<Sqlite>,
+    id: i32,
+) -> Result<ChannelGroup, ProcessError> {
+    const QUERY: &str =
+        "SELECT g.id, g.name, group_concat(c.id, ',') as channel_ids FROM channel_groups g
+        left join channels c on c.group_id = g.id WHERE g.id = $1 GROUP BY g.id";
+
+    let result = sqlx::query_as(QUERY).bind(id).fetch_one(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn insert_channel_group(
+    conn: &Pool<Sqlite>,
+    group: &ChannelGroup,
+) -> Result<i32, ProcessError> {
+    const QUERY: &str = "INSERT INTO channel_groups (name) VALUES($1) RETURNING id";
+
+    let id = sqlx::query_scalar(QUERY)
+        .bind(&group.name)
+        .fetch_one(conn)
+        .await?;
+
+    update_group_channels(conn, id, &group.channel_ids).await?;
+
+    Ok(id)
+}
+
+pub async fn update_channel_group(
+    conn: &Pool<Sqlite>,
+    id: i32,
+    group: &ChannelGroup,
+) -> Result<(), ProcessError> {
+    const QUERY: &str = "UPDATE channel_groups SET name = $2 WHERE id = $1";
+
+    sqlx::query(QUERY)
+        .bind(id)
+        .bind(&group.name)
+        .execute(conn)
+        .await?;
+
+    update_group_channels(conn, id, &group.channel_ids).await
+}
+
+/// Set the channels of a group, a channel can be only in one group.
+pub async fn update_group_channels(
+    conn: &Pool<Sqlite>,
+    id: i32,
+    channel_ids: &[i32],
+) -> Result<(), ProcessError> {
+    const RESET: &str = "UPDATE channels SET group_id = NULL WHERE group_id = $1";
+    const QUERY: &str = "UPDATE channels SET group_id = $1 WHERE id = $2";
+
+    sqlx::query(RESET).bind(id).execute(conn).await?;
+
+    for channel_id in channel_ids {
+        sqlx::query(QUERY)
+            .bind(id)
+            .bind(channel_id)
+            .execute(conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn delete_channel_group(
+    conn: &Pool<Sqlite>,
+    id: i32,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const RESET: &str = "UPDATE channels SET group_id = NULL WHERE group_id = $1";
+    const QUERY: &str = "DELETE FROM channel_groups WHERE id = $1";
+
+    sqlx::query(RESET).bind(id).execute(conn).await?;
+    let result = sqlx::query(QUERY).bind(id).execute(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn select_workers(conn: &Pool<Sqlite>) -> Result<Vec<Worker>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM workers ORDER BY id";
+
+    let result = sqlx::query_as(QUERY).fetch_all(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn select_worker(conn: &Pool<Sqlite>, id: i32) -> Result<Worker, ProcessError> {
+    const QUERY: &str = "SELECT * FROM workers WHERE id = $1";
+
+    let result = sqlx::query_as(QUERY).bind(id).fetch_one(conn).await?;
+
+    Ok(result)
+}
+
+pub async fn insert_worker(conn: &Pool<Sqlite>, worker: &Worker) -> Result<i32, ProcessError> {
+    const QUERY: &str = "INSERT INTO workers (name, url, token) VALUES($1, $2, $3) RETURNING id";
+
+    let id = sqlx::query_scalar(QUERY)
+        .bind(&worker.name)
+        .bind(worker.url.trim_end_matches('/'))
+        .bind(&worker.token)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(id)
+}
+
+/// Store the result of a health check, `last_seen` changes only when the worker answers.
+pub async fn update_worker_state(
+    conn: &Pool<Sqlite>,
+    id: i32,
+    online: bool,
+    failures: i32,
+) -> Result<SqliteQueryResult, ProcessError> {
+    let query = if failures == 0 {
+        "UPDATE workers SET online = $2, failures = $3, last_seen = CURRENT_TIMESTAMP WHERE id = $1"
+    } else {
+        "UPDATE workers SET online = $2, failures = $3 WHERE id = $1"
+    };
+
+    let result = sqlx::query(query)
+        .bind(id)
+        .bind(online)
+        .bind(failures)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_worker(
+    conn: &Pool<Sqlite>,
+    id: i32,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM workers WHERE id = $1";
+
+    let result = sqlx::query(QUERY).bind(id).execute(conn).await?;
+
+    Ok(result)
+}
+
+/// Channels, which the controller has sent to the worker and not removed yet.
+pub async fn select_worker_channels(
+    conn: &Pool<Sqlite>,
+    worker_id: i32,
+) -> Result<Vec<i32>, ProcessError> {
+    const QUERY: &str = "SELECT channel_id FROM worker_channels WHERE worker_id = $1";
+
+    let result = sqlx::query_scalar(QUERY)
+        .bind(worker_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_worker_channel(
+    conn: &Pool<Sqlite>,
+    worker_id: i32,
+    channel_id: i32,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str =
+        "INSERT OR IGNORE INTO worker_channels (worker_id, channel_id) VALUES($1, $2)";
+
+    let result = sqlx::query(QUERY)
+        .bind(worker_id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_worker_channel(
+    conn: &Pool<Sqlite>,
+    worker_id: i32,
+    channel_id: i32,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM worker_channels WHERE worker_id = $1 AND channel_id = $2";
+
+    let result = sqlx::query(QUERY)
+        .bind(worker_id)
+        .bind(channel_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// On a worker: the user, with whose token a controller has created the channel.
+pub async fn select_controller_channel(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+) -> Result<Option<i32>, ProcessError> {
+    const QUERY: &str = "SELECT user_id FROM controller_channels WHERE channel_id = $1";
+
+    let result = sqlx::query_scalar(QUERY)
+        .bind(channel_id)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// On a worker: the channels, which a controller has created with the token of the user.
+pub async fn select_controller_channels(
+    conn: &Pool<Sqlite>,
+    user_id: i32,
+) -> Result<Vec<i32>, ProcessError> {
+    const QUERY: &str = "SELECT channel_id FROM controller_channels WHERE user_id = $1";
+
+    let result = sqlx::query_scalar(QUERY)
+        .bind(user_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_controller_channel(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    user_id: i32,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO controller_channels (channel_id, user_id) VALUES($1, $2)";
+
+    let result = sqlx::query(QUERY)
+        .bind(channel_id)
+        .bind(user_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// Place a channel on a worker, or on the controller with `None`.
+pub async fn update_channel_worker(
+    conn: &Pool<Sqlite>,
+    id: i32,
+    worker_id: Option<i32>,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE channels SET worker_id = $2 WHERE id = $1";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(worker_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
 pub async fn update_stat(
     conn: &Pool<Sqlite>,
     id: i32,
@@ -184,11 +459,13 @@ pub async fn update_player(
     Ok(result)
 }
 
+/// Insert a new channel, with a given id or the next free one.
 pub async fn insert_channel(
     conn: &Pool<Sqlite>,
     channel: Channel,
+    id: Option<i32>,
 ) -> Result<Channel, ProcessError> {
-    const QUERY: &str = "INSERT INTO channels (name, preview_url, extra_extensions, public, playlists, storage) VALUES($1, $2, $3, $4, $5, $6)";
+    const QUERY: &str = "INSERT INTO channels (name, preview_url, extra_extensions, public, playlists, storage, id) VALUES($1, $2, $3, $4, $5, $6, $7)";
     let result = sqlx::query(QUERY)
         .bind(channel.name)
         .bind(channel.preview_url)
@@ -196,6 +473,7 @@ pub async fn insert_channel(
         .bind(channel.public)
         .bind(channel.playlists)
         .bind(channel.storage)
+        .bind(id)
         .execute(conn)
         .await?;
 
@@ -258,7 +536,7 @@ pub async fn update_configuration(
     id: i32,
     config: PlayoutConfig,
 ) -> Result<SqliteQueryResult, ProcessError> {
-    const QUERY: &str = "UPDATE configurations SET general_stop_threshold = $2, mail_subject = $3, mail_recipient = $4, mail_level = $5, mail_interval = $6, logging_ffmpeg_level = $7, logging_ingest_level = $8, logging_detect_silence = $9, logging_ignore = $10, processing_mode = $11, processing_audio_only = $12, processing_copy_audio = $13, processing_copy_video = $14, processing_width = $15, processing_height = $16, processing_aspect = $17, processing_fps = $18, processing_add_logo = $19, processing_logo = $20, processing_logo_scale = $21, processing_logo_opacity = $22, processing_logo_position = $23, processing_audio_tracks = $24, processing_audio_track_index = $25, processing_audio_channels = $26, processing_volume = $27, processing_filter = $28, processing_override_filter = $29, processing_vtt_enable = $30, processing_vtt_dummy = $31, ingest_enable = $32, ingest_param = $33, ingest_filter = $34, playlist_day_start = $35, playlist_length = $36, playlist_infinit = $37, storage_filler = $38, storage_extensions = $39, storage_shuffle = $40, text_add = $41, text_from_filename = $42, text_font = $43, text_style = $44, text_regex = $45, task_enable = $46, task_path = $47, output_mode = $48, output_param = $49 WHERE id = $1";
+    const QUERY: &str = "UPDATE configurations SET general_stop_threshold = $2, mail_subject = $3, mail_recipient = $4, mail_level = $5, mail_interval = $6, logging_ffmpeg_level = $7, logging_ingest_level = $8, logging_detect_silence = $9, logging_ignore = $10, processing_mode = $11, processing_audio_only = $12, processing_copy_audio = $13, processing_copy_video = $14, processing_width = $15, processing_height = $16, processing_aspect = $17, processing_fps = $18, processing_add_logo = $19, processing_logo = $20, processing_logo_scale = $21, processing_logo_opacity = $22, processing_logo_position = $23, processing_audio_tracks = $24, processing_audio_track_index = $25, processing_audio_channels = $26, processing_volume = $27, processing_filter = $28, processing_override_filter = $29, processing_vtt_enable = $30, processing_vtt_dummy = $31, ingest_enable = $32, ingest_param = $33, ingest_filter = $34, playlist_day_start = $35, playlist_length = $36, playlist_infinit = $37, storage_filler = $38, storage_extensions = $39, storage_shuffle = $40, text_add = $41, text_from_filename = $42, text_font = $43, text_style = $44, text_regex = $45, task_enable = $46, task_path = $47, output_mode = $48, output_param = $49, storage_weights = $50, storage_shuffle_history = $51, storage_max_depth = $52, storage_exclude = $53, storage_cache_path = $54, storage_cache_size = $55, processing_audio_languages = $56, processing_subtitle_mode = $57, output_srt_mode = $58, output_srt_address = $59, output_srt_passphrase = $60, output_srt_latency = $61, output_ndi_name = $62, output_decklink_device = $63, output_decklink_mode = $64, output_legs = $65, recorder_enable = $66, recorder_path = $67, recorder_format = $68, recorder_segment = $69, recorder_retention = $70, recorder_param = $71, webhook_urls = $72, webhook_secret = $73, webhook_events = $74, mqtt_enable = $75, mqtt_broker = $76, mqtt_topic = $77, mqtt_username = $78, mqtt_password = $79, processing_crossfade = $80, processing_crossfade_transition = $81, processing_crossfade_curve = $82, transition_stinger_path = $83, transition_stinger_offset = $84, processing_logo_schedule = $85, processing_logo_loop = $86, processing_logo_fps = $87, text_now_next = $88, text_now_next_format = $89, text_now_next_style = $90, processing_loudnorm = $91, processing_loudnorm_i = $92, processing_loudnorm_lra = $93, processing_loudnorm_tp = $94, monitor_silence_duration = $95, monitor_silence_noise = $96, monitor_black_duration = $97, monitor_black_level = $98, monitor_freeze_duration = $99, failover_enable = $100, failover_source = $101, sync_main_url = $102, sync_secret = $103, general_drift_correction = $104, graphics_enable = $105, graphics_source = $106, graphics_command = $107, graphics_interval = $108, ticker_enable = $109, ticker_feeds = $110, ticker_interval = $111, ticker_separator = $112, ticker_schedule = $113, ticker_speed = $114, ticker_style = $115, clock_enable = $116, clock_format = $117, clock_position = $118, clock_font = $119, clock_style = $120, output_icecast_address = $121, output_icecast_mount = $122, output_icecast_username = $123, output_icecast_password = $124, output_icecast_format = $125, output_icecast_bitrate = $126, output_icecast_video = $127, output_icecast_cover = $128, output_icecast_metadata = $129, output_icecast_metadata_url = $130, visual_radio_enable = $131, visual_radio_artwork = $132, visual_radio_visualizer = $133, visual_radio_now_playing = $134, visual_radio_style = $135, storage_library_interval = $136, storage_query = $137, asrun_enable = $138, asrun_path = $139, asrun_format = $140, asrun_columns = $141, asrun_thumbnails = $142, asrun_thumbnail_interval = $143, storage_previews = $144, preview_enable = $145, preview_height = $146, preview_bitrate = $147, output_hls_ladder = $148, output_hls_fmp4 = $149, output_hls_time = $150, output_hls_low_latency = $151, output_hls_part_time = $152, output_hls_program_date_time = $153, output_hls_encryption = $154, output_hls_key_rotation = $155, output_hls_key_url = $156, output_hls_key_iv = $157, output_reconnect = $158, output_reconnect_attempts = $159, output_reconnect_delay = $160, output_reconnect_alert = $161, output_reconnect_slate = $162, output_whip_url = $163, output_whip_token = $164, output_whip_bitrate = $165, output_whip_profile = $166, processing_hw_accel = $167, processing_hw_device = $168, processing_deinterlace = $169, processing_aspect_mode = $170, processing_tonemap = $171, processing_tonemap_algorithm = $172, processing_tonemap_peak = $173, processing_fps_mode = $174, processing_color_space = $175, script_enable = $176, script_path = $177, script_timeout = $178, hooks_clip_start = $179, hooks_clip_end = $180, hooks_error = $181, hooks_timeout = $182, processing_backend = $183, playlist_gap_policy = $184, storage_shuffle_history_minutes = $185 WHERE id = $1";
 
     let result = sqlx::query(QUERY)
         .bind(id)
@@ -310,6 +588,150 @@ pub async fn update_configuration(
         .bind(config.task.path.to_string_lossy().to_string())
         .bind(config.output.mode.to_string())
         .bind(config.output.output_param)
+        .bind(
+            config
+                .storage
+                .weights
+                .iter()
+                .map(|(prefix, weight)| format!("{prefix}={weight}"))
+                .collect::<Vec<String>>()
+                .join(";"),
+        )
+        .bind(config.storage.shuffle_history as i64)
+        .bind(config.storage.max_depth as i64)
+        .bind(config.storage.exclude.join(";"))
+        .bind(config.storage.cache_path.to_string_lossy().to_string())
+        .bind(config.storage.cache_size as i64)
+        .bind(config.processing.audio_languages.join(";"))
+        .bind(config.processing.subtitle_mode.to_string())
+        .bind(config.output.srt_mode.to_string())
+        .bind(config.output.srt_address)
+        .bind(config.output.srt_passphrase)
+        .bind(config.output.srt_latency)
+        .bind(config.output.ndi_name)
+        .bind(config.output.decklink_device)
+        .bind(config.output.decklink_mode)
+        .bind(config.output.legs.join("\n"))
+        .bind(config.recorder.enable)
+        .bind(config.recorder.path.to_string_lossy().to_string())
+        .bind(config.recorder.format.to_string())
+        .bind(config.recorder.segment)
+        .bind(config.recorder.retention)
+        .bind(config.recorder.param)
+        .bind(config.webhook.urls.join("\n"))
+        .bind(config.webhook.secret)
+        .bind(config.webhook.events.join(";"))
+        .bind(config.mqtt.enable)
+        .bind(config.mqtt.broker)
+        .bind(config.mqtt.topic)
+        .bind(config.mqtt.username)
+        .bind(config.mqtt.password)
+        .bind(config.processing.crossfade)
+        .bind(config.processing.crossfade_transition)
+        .bind(config.processing.crossfade_curve)
+        .bind(config.transition.stinger_path)
+        .bind(config.transition.stinger_offset)
+        .bind(config.processing.logo_schedule.join("\n"))
+        .bind(config.processing.logo_loop)
+        .bind(config.processing.logo_fps)
+        .bind(config.text.now_next)
+        .bind(config.text.now_next_format)
+        .bind(config.text.now_next_style)
+        .bind(config.processing.loudnorm)
+        .bind(config.processing.loudnorm_i)
+        .bind(config.processing.loudnorm_lra)
+        .bind(config.processing.loudnorm_tp)
+        .bind(config.monitor.silence_duration)
+        .bind(config.monitor.silence_noise)
+        .bind(config.monitor.black_duration)
+        .bind(config.monitor.black_level)
+        .bind(config.monitor.freeze_duration)
+        .bind(config.failover.enable)
+        .bind(config.failover.source)
+        .bind(config.sync.main_url)
+        .bind(config.sync.secret)
+        .bind(config.general.drift_correction)
+        .bind(config.graphics.enable)
+        .bind(config.graphics.source)
+        .bind(config.graphics.command)
+        .bind(config.graphics.interval)
+        .bind(config.ticker.enable)
+        .bind(config.ticker.feeds.join("\n"))
+        .bind(config.ticker.interval)
+        .bind(config.ticker.separator)
+        .bind(config.ticker.schedule.join("\n"))
+        .bind(config.ticker.speed)
+        .bind(config.ticker.style)
+        .bind(config.clock.enable)
+        .bind(config.clock.format)
+        .bind(config.clock.position)
+        .bind(config.clock.font)
+        .bind(config.clock.style)
+        .bind(config.output.icecast_address)
+        .bind(config.output.icecast_mount)
+        .bind(config.output.icecast_username)
+        .bind(config.output.icecast_password)
+        .bind(config.output.icecast_format)
+        .bind(config.output.icecast_bitrate)
+        .bind(config.output.icecast_video)
+        .bind(config.output.icecast_cover)
+        .bind(config.output.icecast_metadata)
+        .bind(config.output.icecast_metadata_url)
+        .bind(config.visual_radio.enable)
+        .bind(config.visual_radio.artwork)
+        .bind(config.visual_radio.visualizer)
+        .bind(config.visual_radio.now_playing)
+        .bind(config.visual_radio.style)
+        .bind(config.storage.library_interval)
+        .bind(&config.storage.query)
+        .bind(config.asrun.enable)
+        .bind(config.asrun.path.to_string_lossy().to_string())
+        .bind(&config.asrun.format)
+        .bind(config.asrun.columns.join(";"))
+        .bind(config.asrun.thumbnails)
+        .bind(config.asrun.thumbnail_interval)
+        .bind(config.storage.previews)
+        .bind(config.preview.enable)
+        .bind(config.preview.height)
+        .bind(config.preview.bitrate)
+        .bind(config.output.hls_ladder.join("\n"))
+        .bind(config.output.hls_fmp4)
+        .bind(config.output.hls_time)
+        .bind(config.output.hls_low_latency)
+        .bind(config.output.hls_part_time)
+        .bind(config.output.hls_program_date_time)
+        .bind(config.output.hls_encryption)
+        .bind(config.output.hls_key_rotation)
+        .bind(&config.output.hls_key_url)
+        .bind(&config.output.hls_key_iv)
+        .bind(config.output.reconnect)
+        .bind(config.output.reconnect_attempts)
+        .bind(config.output.reconnect_delay)
+        .bind(config.output.reconnect_alert)
+        .bind(&config.output.reconnect_slate)
+        .bind(&config.output.whip_url)
+        .bind(&config.output.whip_token)
+        .bind(config.output.whip_bitrate)
+        .bind(&config.output.whip_profile)
+        .bind(config.processing.hw_accel.to_string())
+        .bind(&config.processing.hw_device)
+        .bind(config.processing.deinterlace.to_string())
+        .bind(config.processing.aspect_mode.to_string())
+        .bind(config.processing.tonemap.to_string())
+        .bind(&config.processing.tonemap_algorithm)
+        .bind(config.processing.tonemap_peak)
+        .bind(config.processing.fps_mode.to_string())
+        .bind(config.processing.color_space.to_string())
+        .bind(config.script.enable)
+        .bind(config.script.path.to_string_lossy().to_string())
+        .bind(config.script.timeout)
+        .bind(&config.hooks.clip_start)
+        .bind(&config.hooks.clip_end)
+        .bind(&config.hooks.error)
+        .bind(config.hooks.timeout)
+        .bind(config.processing.backend.to_string())
+        .bind(config.playlist.gap_policy.join("\n"))
+        .bind(config.storage.shuffle_history_minutes as i64)
         .execute(conn)
         .await?;
 
@@ -481,8 +903,10 @@ pub async fn select_role(conn: &Pool<Sqlite>, id: &i32) -> Result<Role, ProcessE
 
 pub async fn select_login(conn: &Pool<Sqlite>, user: &str) -> Result<User, ProcessError> {
     const QUERY: &str =
-        "SELECT u.id, u.mail, u.username, u.password, u.role_id, group_concat(uc.channel_id, ',') as channel_ids FROM user u
+        "SELECT u.id, u.mail, u.username, u.password, u.role_id, group_concat(uc.channel_id, ',') as channel_ids,
+        group_concat(uc.channel_id || ':' || r.name, ',') as channel_roles FROM user u
         left join user_channels uc on uc.user_id = u.id
+        left join roles r on r.id = uc.role_id
     WHERE u.username = $1";
 
     let result = sqlx::query_as(QUERY).bind(user).fetch_one(conn).await?;
@@ -490,9 +914,51 @@ pub async fn select_login(conn: &Pool<Sqlite>, user: &str) -> Result<User, Proce
     Ok(result)
 }
 
+/// User, which is bound to the identity of an OIDC provider.
+pub async fn select_oidc_user(
+    conn: &Pool<Sqlite>,
+    issuer: &str,
+    subject: &str,
+) -> Result<User, ProcessError> {
+    const QUERY: &str =
+        "SELECT u.id, u.mail, u.username, u.password, u.role_id, group_concat(uc.channel_id, ',') as channel_ids,
+        group_concat(uc.channel_id || ':' || r.name, ',') as channel_roles FROM user u
+        left join user_channels uc on uc.user_id = u.id
+        left join roles r on r.id = uc.role_id
+    WHERE u.oidc_issuer = $1 AND u.oidc_subject = $2 GROUP BY u.id";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(issuer)
+        .bind(subject)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_user_oidc(
+    conn: &Pool<Sqlite>,
+    id: i32,
+    issuer: &str,
+    subject: &str,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE user SET oidc_issuer = $2, oidc_subject = $3 WHERE id = $1";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(issuer)
+        .bind(subject)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
 pub async fn select_user(conn: &Pool<Sqlite>, id: i32) -> Result<User, ProcessError> {
-    const QUERY: &str = "SELECT u.id, u.mail, u.username, u.role_id, group_concat(uc.channel_id, ',') as channel_ids FROM user u
+    const QUERY: &str = "SELECT u.id, u.mail, u.username, u.role_id, group_concat(uc.channel_id, ',') as channel_ids,
+        group_concat(uc.channel_id || ':' || r.name, ',') as channel_roles FROM user u
         left join user_channels uc on uc.user_id = u.id
+        left join roles r on r.id = uc.role_id
     WHERE u.id = $1";
 
     let result = sqlx::query_as(QUERY).bind(id).fetch_one(conn).await?;
@@ -501,8 +967,10 @@ pub async fn select_user(conn: &Pool<Sqlite>, id: i32) -> Result<User, ProcessEr
 }
 
 pub async fn select_global_admins(conn: &Pool<Sqlite>) -> Result<Vec<User>, ProcessError> {
-    const QUERY: &str = "SELECT u.id, u.mail, u.username, u.role_id, group_concat(uc.channel_id, ',') as channel_ids FROM user u
+    const QUERY: &str = "SELECT u.id, u.mail, u.username, u.role_id, group_concat(uc.channel_id, ',') as channel_ids,
+        group_concat(uc.channel_id || ':' || r.name, ',') as channel_roles FROM user u
         left join user_channels uc on uc.user_id = u.id
+        left join roles r on r.id = uc.role_id
     WHERE u.role_id = 1";
 
     let result = sqlx::query_as(QUERY).fetch_all(conn).await?;
@@ -545,6 +1013,8 @@ pub async fn insert_user(conn: &Pool<Sqlite>, user: User) -> Result<(), ServiceE
         insert_user_channel(conn, user_id, channel_ids).await?;
     }
 
+    update_user_channel_roles(conn, user_id, &user.channel_roles).await?;
+
     Ok(())
 }
 
@@ -577,6 +1047,8 @@ pub async fn insert_or_update_user(conn: &Pool<Sqlite>, user: User) -> Result<()
         insert_user_channel(conn, user_id, channel_ids).await?;
     }
 
+    update_user_channel_roles(conn, user_id, &user.channel_roles).await?;
+
     Ok(())
 }
 
@@ -611,6 +1083,34 @@ pub async fn insert_user_channel(
     Ok(())
 }
 
+/// Set the roles of the user on single channels, the other channels take the role of the user.
+pub async fn update_user_channel_roles(
+    conn: &Pool<Sqlite>,
+    user_id: i32,
+    channel_roles: &BTreeMap<i32, Role>,
+) -> Result<(), ProcessError> {
+    const RESET: &str = "UPDATE user_channels SET role_id = NULL WHERE user_id = $1;";
+    const QUERY: &str = "UPDATE user_channels SET role_id = (SELECT id FROM roles WHERE name = $1)
+        WHERE user_id = $2 AND channel_id = $3;";
+
+    sqlx::query(RESET).bind(user_id).execute(conn).await?;
+
+    for (channel, role) in channel_roles {
+        if *role == Role::GlobalAdmin {
+            continue;
+        }
+
+        sqlx::query(QUERY)
+            .bind(role.to_string())
+            .bind(user_id)
+            .bind(channel)
+            .execute(conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
 pub async fn delete_user(conn: &Pool<Sqlite>, id: i32) -> Result<SqliteQueryResult, ProcessError> {
     const QUERY: &str = "DELETE FROM user WHERE id = $1;";
 
@@ -707,3 +1207,569 @@ pub async fn delete_preset(
 
     Ok(result)
 }
+
+pub async fn select_loudness(
+    conn: &Pool<Sqlite>,
+    source: &str,
+    size: i64,
+    modified: i64,
+) -> Result<Loudness, ProcessError> {
+    const QUERY: &str = "SELECT * FROM loudness WHERE source = $1 AND size = $2 AND modified = $3";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(source)
+        .bind(size)
+        .bind(modified)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_loudness(
+    conn: &Pool<Sqlite>,
+    loudness: &Loudness,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str =
+        "INSERT OR REPLACE INTO loudness (source, size, modified, input_i, input_lra, input_tp, input_thresh)
+            VALUES($1, $2, $3, $4, $5, $6, $7)";
+
+    let result = sqlx::query(QUERY)
+        .bind(&loudness.source)
+        .bind(loudness.size)
+        .bind(loudness.modified)
+        .bind(loudness.input_i)
+        .bind(loudness.input_lra)
+        .bind(loudness.input_tp)
+        .bind(loudness.input_thresh)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_blacklisted(
+    conn: &Pool<Sqlite>,
+    source: &str,
+    size: i64,
+    modified: i64,
+) -> Result<bool, ProcessError> {
+    const QUERY: &str =
+        "SELECT COUNT(*) FROM blacklist WHERE source = $1 AND size = $2 AND modified = $3";
+
+    let count: i64 = sqlx::query_scalar(QUERY)
+        .bind(source)
+        .bind(size)
+        .bind(modified)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(count > 0)
+}
+
+pub async fn insert_blacklist(
+    conn: &Pool<Sqlite>,
+    source: &str,
+    size: i64,
+    modified: i64,
+    error: &str,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str =
+        "INSERT OR REPLACE INTO blacklist (source, size, modified, error) VALUES($1, $2, $3, $4)";
+
+    let result = sqlx::query(QUERY)
+        .bind(source)
+        .bind(size)
+        .bind(modified)
+        .bind(error)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_series_episode(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    path: &str,
+) -> Result<Option<String>, ProcessError> {
+    const QUERY: &str = "SELECT episode FROM series WHERE channel_id = $1 AND path = $2";
+
+    let episode = sqlx::query_scalar(QUERY)
+        .bind(channel_id)
+        .bind(path)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(episode)
+}
+
+pub async fn update_series_episode(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    path: &str,
+    episode: &str,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT OR REPLACE INTO series (channel_id, path, episode, updated_at) VALUES($1, $2, $3, CURRENT_TIMESTAMP)";
+
+    let result = sqlx::query(QUERY)
+        .bind(channel_id)
+        .bind(path)
+        .bind(episode)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_playlist_revisions(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    date: &str,
+) -> Result<Vec<PlaylistRevision>, ProcessError> {
+    const QUERY: &str =
+        "SELECT id, channel_id, date, user_id, username, diff, created_at FROM playlist_revisions
+        WHERE channel_id = $1 AND date = $2 ORDER BY id DESC";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(date)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_playlist_revision(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    id: i32,
+) -> Result<PlaylistRevision, ProcessError> {
+    const QUERY: &str = "SELECT * FROM playlist_revisions WHERE channel_id = $1 AND id = $2";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(id)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_playlist_revision(
+    conn: &Pool<Sqlite>,
+    revision: PlaylistRevision,
+) -> Result<i32, ProcessError> {
+    const QUERY: &str =
+        "INSERT INTO playlist_revisions (channel_id, date, user_id, username, diff, playlist)
+        VALUES($1, $2, $3, $4, $5, $6) RETURNING id";
+
+    let id = sqlx::query_scalar(QUERY)
+        .bind(revision.channel_id)
+        .bind(revision.date)
+        .bind(revision.user_id)
+        .bind(revision.username)
+        .bind(revision.diff)
+        .bind(revision.playlist.unwrap_or_default())
+        .fetch_one(conn)
+        .await?;
+
+    Ok(id)
+}
+
+pub async fn insert_airing(conn: &Pool<Sqlite>, airing: &Airing) -> Result<i64, ProcessError> {
+    const QUERY: &str =
+        "INSERT INTO airings (channel_id, source, title, artist, category, started_at, planned)
+        VALUES($1, $2, $3, $4, $5, $6, $7) RETURNING id";
+
+    let id = sqlx::query_scalar(QUERY)
+        .bind(airing.channel_id)
+        .bind(&airing.source)
+        .bind(&airing.title)
+        .bind(&airing.artist)
+        .bind(&airing.category)
+        .bind(&airing.started_at)
+        .bind(airing.planned)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(id)
+}
+
+pub async fn select_playhead(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+) -> Result<Option<Playhead>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM playheads WHERE channel_id = $1";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_playhead(
+    conn: &Pool<Sqlite>,
+    playhead: &Playhead,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO playheads (channel_id, date, node_index, source, position, saved_at) VALUES($1, $2, $3, $4, $5, $6)
+        ON CONFLICT(channel_id) DO UPDATE SET
+            date = excluded.date, node_index = excluded.node_index, source = excluded.source, position = excluded.position, saved_at = excluded.saved_at";
+
+    let result = sqlx::query(QUERY)
+        .bind(playhead.channel_id)
+        .bind(&playhead.date)
+        .bind(playhead.node_index)
+        .bind(&playhead.source)
+        .bind(playhead.position)
+        .bind(&playhead.saved_at)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// Set the duration of the last airing of the channel, when it has none.
+pub async fn close_airing(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    ended_at: &str,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str =
+        "UPDATE airings SET duration = round((julianday($2) - julianday(started_at)) * 86400, 3)
+        WHERE id = (SELECT max(id) FROM airings WHERE channel_id = $1) AND duration IS NULL";
+
+    let result = sqlx::query(QUERY)
+        .bind(channel_id)
+        .bind(ended_at)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn update_airing_duration(
+    conn: &Pool<Sqlite>,
+    id: i64,
+    duration: f64,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE airings SET duration = $2 WHERE id = $1";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(duration)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// Aired clips with a start time from `start` until before `end`.
+pub async fn select_airings(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    start: &str,
+    end: &str,
+) -> Result<Vec<Airing>, ProcessError> {
+    const QUERY: &str =
+        "SELECT * FROM airings WHERE channel_id = $1 AND started_at >= $2 AND started_at < $3
+        ORDER BY started_at";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_play_counts(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    start: &str,
+    end: &str,
+) -> Result<Vec<PlayCount>, ProcessError> {
+    const QUERY: &str =
+        "SELECT source, MAX(title) AS title, MAX(artist) AS artist, COUNT(*) AS plays,
+            CAST(TOTAL(COALESCE(duration, planned)) AS REAL) AS duration
+        FROM airings WHERE channel_id = $1 AND started_at >= $2 AND started_at < $3
+        GROUP BY source ORDER BY plays DESC, source";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(start)
+        .bind(end)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+/// Size and modification time of the indexed files, to find the changed ones.
+pub async fn select_library_versions(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+) -> Result<Vec<(String, i64, i64)>, ProcessError> {
+    const QUERY: &str = "SELECT path, size, modified FROM media_library WHERE channel_id = $1";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_library_items(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+) -> Result<Vec<LibraryItem>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM media_library WHERE channel_id = $1 ORDER BY path";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_library_item(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    path: &str,
+) -> Result<Option<LibraryItem>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM media_library WHERE channel_id = $1 AND path = $2";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(path)
+        .fetch_optional(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_library(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    query: &LibraryQuery,
+) -> Result<Vec<LibraryItem>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM media_library WHERE channel_id = $1
+        AND ($2 = '' OR path LIKE '%' || $2 || '%' OR title LIKE '%' || $2 || '%' OR artist LIKE '%' || $2 || '%'
+            OR album LIKE '%' || $2 || '%' OR genre LIKE '%' || $2 || '%')
+        AND ($3 = '' OR video_codec = $3 OR audio_codec = $3)
+        AND duration >= $4 AND ($5 <= 0 OR duration <= $5) AND height >= $6
+        ORDER BY path LIMIT $7 OFFSET $8";
+
+    let limit = if query.limit > 0 { query.limit } else { 100 };
+
+    let result = sqlx::query_as(QUERY)
+        .bind(channel_id)
+        .bind(&query.search)
+        .bind(&query.codec)
+        .bind(query.min_duration)
+        .bind(query.max_duration)
+        .bind(query.min_height)
+        .bind(limit)
+        .bind(query.offset.max(0))
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn upsert_library_item(
+    conn: &Pool<Sqlite>,
+    item: &LibraryItem,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "INSERT INTO media_library (channel_id, path, size, modified, checksum, duration, video_codec, audio_codec, width, height, fps, loudness, title, artist, album, genre, tags, scanned_at)
+        VALUES($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, CURRENT_TIMESTAMP)
+        ON CONFLICT (channel_id, path) DO UPDATE SET size = $3, modified = $4, checksum = $5, duration = $6, video_codec = $7, audio_codec = $8,
+            width = $9, height = $10, fps = $11, loudness = $12, title = $13, artist = $14, album = $15, genre = $16, tags = $17, scanned_at = CURRENT_TIMESTAMP";
+
+    let result = sqlx::query(QUERY)
+        .bind(item.channel_id)
+        .bind(&item.path)
+        .bind(item.size)
+        .bind(item.modified)
+        .bind(&item.checksum)
+        .bind(item.duration)
+        .bind(&item.video_codec)
+        .bind(&item.audio_codec)
+        .bind(item.width)
+        .bind(item.height)
+        .bind(item.fps)
+        .bind(item.loudness)
+        .bind(&item.title)
+        .bind(&item.artist)
+        .bind(&item.album)
+        .bind(&item.genre)
+        .bind(&item.tags)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn delete_library_item(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    path: &str,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "DELETE FROM media_library WHERE channel_id = $1 AND path = $2";
+
+    let result = sqlx::query(QUERY)
+        .bind(channel_id)
+        .bind(path)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn select_api_tokens(
+    conn: &Pool<Sqlite>,
+    user_id: Option<i32>,
+) -> Result<Vec<ApiToken>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM api_tokens WHERE $1 IS NULL OR user_id = $1 ORDER BY id";
+
+    let result = sqlx::query_as(QUERY).bind(user_id).fetch_all(conn).await?;
+
+    Ok(result)
+}
+
+/// Select a token by its hash, only when it is not revoked and not expired.
+pub async fn select_valid_api_token(
+    conn: &Pool<Sqlite>,
+    token_hash: &str,
+) -> Result<ApiToken, ProcessError> {
+    const QUERY: &str = "SELECT * FROM api_tokens WHERE token_hash = $1 AND revoked = 0
+        AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)";
+
+    let result = sqlx::query_as(QUERY)
+        .bind(token_hash)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_api_token(conn: &Pool<Sqlite>, token: &ApiToken) -> Result<i32, ProcessError> {
+    const QUERY: &str = "INSERT INTO api_tokens (user_id, name, token_hash, role_id, channel_ids, expires_at)
+        VALUES($1, $2, $3, $4, $5, CASE WHEN $6 IS NULL THEN NULL ELSE datetime('now', '+' || $6 || ' days') END)
+        RETURNING id";
+
+    let channel_ids = token
+        .channel_ids
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<String>>()
+        .join(",");
+
+    let id = sqlx::query_scalar(QUERY)
+        .bind(token.user_id)
+        .bind(&token.name)
+        .bind(&token.token_hash)
+        .bind(token.role_id)
+        .bind(channel_ids)
+        .bind(token.expires_in)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(id)
+}
+
+pub async fn update_api_token_used(
+    conn: &Pool<Sqlite>,
+    id: i32,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str = "UPDATE api_tokens SET last_used = CURRENT_TIMESTAMP WHERE id = $1";
+
+    let result = sqlx::query(QUERY).bind(id).execute(conn).await?;
+
+    Ok(result)
+}
+
+/// Revoke a token, `user_id` limits it to the tokens of this user.
+pub async fn revoke_api_token(
+    conn: &Pool<Sqlite>,
+    id: i32,
+    user_id: Option<i32>,
+) -> Result<SqliteQueryResult, ProcessError> {
+    const QUERY: &str =
+        "UPDATE api_tokens SET revoked = 1 WHERE id = $1 AND ($2 IS NULL OR user_id = $2)";
+
+    let result = sqlx::query(QUERY)
+        .bind(id)
+        .bind(user_id)
+        .execute(conn)
+        .await?;
+
+    Ok(result)
+}
+
+pub async fn insert_audit_entry(
+    conn: &Pool<Sqlite>,
+    entry: &AuditEntry,
+) -> Result<i64, ProcessError> {
+    const QUERY: &str = "INSERT INTO audit_log (channel_id, user_id, username, method, path, status, old_value, new_value)
+        VALUES($1, $2, $3, $4, $5, $6, $7, $8) RETURNING id";
+
+    let id = sqlx::query_scalar(QUERY)
+        .bind(entry.channel_id)
+        .bind(entry.user_id)
+        .bind(&entry.username)
+        .bind(&entry.method)
+        .bind(&entry.path)
+        .bind(entry.status)
+        .bind(&entry.old_value)
+        .bind(&entry.new_value)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(id)
+}
+
+/// Audit entries, newest first. `channels` limits the result to this channels, when it is some.
+pub async fn select_audit_entries(
+    conn: &Pool<Sqlite>,
+    query: &AuditQuery,
+    channels: Option<&[i32]>,
+) -> Result<Vec<AuditEntry>, ProcessError> {
+    const QUERY: &str = "SELECT * FROM audit_log WHERE ($1 = 0 OR channel_id = $1)
+        AND ($2 = '' OR username = $2) AND ($3 = '' OR method = upper($3)) AND ($4 = '' OR path LIKE '%' || $4 || '%')
+        AND ($5 = '' OR created_at >= $5) AND ($6 = '' OR CASE WHEN length($6) = 10 THEN created_at < date($6, '+1 day') ELSE created_at <= $6 END)
+        AND ($7 = '' OR ',' || $7 || ',' LIKE '%,' || channel_id || ',%')
+        ORDER BY id DESC LIMIT $8 OFFSET $9";
+
+    let limit = if query.limit > 0 { query.limit } else { 100 };
+    let channels = channels
+        .map(|c| {
+            c.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join(",")
+        })
+        .map(|c| if c.is_empty() { "-".to_string() } else { c })
+        .unwrap_or_default();
+
+    let result = sqlx::query_as(QUERY)
+        .bind(query.channel)
+        .bind(&query.username)
+        .bind(&query.method)
+        .bind(&query.path)
+        .bind(&query.from)
+        .bind(&query.to)
+        .bind(channels)
+        .bind(limit)
+        .bind(query.offset.max(0))
+        .fetch_all(conn)
+        .await?;
+
+    Ok(result)
+}
@@ -8,7 +8,7 @@ use std::{
 use faccess::PathExt;
 use inquire::Confirm;
 use log::*;
-use sqlx::{migrate::MigrateDatabase, Pool, Sqlite, SqlitePool};
+use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePoolOptions, Pool, Sqlite, SqlitePool};
 
 pub mod handles;
 pub mod models;
@@ -64,6 +64,18 @@ pub static DB_PATH: LazyLock<Result<Cow<'static, Path>, io::Error>> = LazyLock::
 
 pub static GLOBAL_SETTINGS: OnceLock<GlobalSettings> = OnceLock::new();
 pub async fn db_pool() -> Result<Pool<Sqlite>, ProcessError> {
+    if ARGS.single_channel && ARGS.db.is_none() {
+        // one connection stays open, otherwise the in-memory database gets lost
+        let conn = SqlitePoolOptions::new()
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+
+        return Ok(conn);
+    }
+
     let db_path = DB_PATH
         .as_ref()
         .map_err(|e| ProcessError::IO(e.to_string()))?;
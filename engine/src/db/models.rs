@@ -1,4 +1,4 @@
-use std::{error::Error, fmt, str::FromStr};
+use std::{collections::BTreeMap, error::Error, fmt, str::FromStr};
 
 use chrono_tz::Tz;
 use regex::Regex;
@@ -25,6 +25,10 @@ pub struct GlobalSettings {
     pub smtp_password: String,
     pub smtp_starttls: bool,
     pub smtp_port: u16,
+    pub oidc_issuer: String,
+    pub oidc_client_id: String,
+    pub oidc_client_secret: String,
+    pub oidc_redirect_url: String,
 }
 
 impl GlobalSettings {
@@ -46,6 +50,10 @@ impl GlobalSettings {
                 smtp_password: String::new(),
                 smtp_starttls: false,
                 smtp_port: 465,
+                oidc_issuer: String::new(),
+                oidc_client_id: String::new(),
+                oidc_client_secret: String::new(),
+                oidc_redirect_url: String::new(),
             },
         }
     }
@@ -68,6 +76,17 @@ pub struct Channel {
     pub timezone: Option<Tz>,
     #[serde(default)]
     pub advanced_id: Option<i32>,
+    #[serde(default)]
+    pub group_id: Option<i32>,
+    #[serde(default)]
+    pub worker_id: Option<i32>,
+}
+
+impl Channel {
+    /// The channel should run on this node and not on a worker.
+    pub fn runs_here(&self) -> bool {
+        self.active && self.worker_id.is_none()
+    }
 }
 
 impl FromRow<'_, SqliteRow> for Channel {
@@ -100,10 +119,57 @@ impl FromRow<'_, SqliteRow> for Channel {
             time_shift: row.try_get("time_shift").unwrap_or_default(),
             timezone,
             advanced_id: row.try_get("advanced_id").unwrap_or_default(),
+            group_id: row.try_get("group_id").unwrap_or_default(),
+            worker_id: row.try_get("worker_id").unwrap_or_default(),
+        })
+    }
+}
+
+/// Channels, which get controlled together.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ChannelGroup {
+    #[serde(default)]
+    pub id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub channel_ids: Vec<i32>,
+}
+
+impl FromRow<'_, SqliteRow> for ChannelGroup {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(Self {
+            id: row.try_get("id").unwrap_or_default(),
+            name: row.try_get("name").unwrap_or_default(),
+            channel_ids: row
+                .try_get::<String, &str>("channel_ids")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|i| i.parse::<i32>().ok())
+                .collect(),
         })
     }
 }
 
+/// Remote engine, which runs channels for the controller.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct Worker {
+    #[serde(default)]
+    pub id: i32,
+    pub name: String,
+    /// Base URL of the worker API, like `http://10.0.0.2:8787`.
+    pub url: String,
+    /// API token of a global admin on the worker.
+    #[serde(default, skip_serializing)]
+    pub token: String,
+    #[serde(default)]
+    pub online: bool,
+    /// Failed health checks in a row.
+    #[serde(default)]
+    pub failures: i32,
+    #[serde(default)]
+    pub last_seen: Option<String>,
+}
+
 fn default_id() -> i32 {
     1
 }
@@ -119,6 +185,9 @@ pub struct User {
     pub password: String,
     pub role_id: Option<i32>,
     pub channel_ids: Option<Vec<i32>>,
+    /// Role on single channels, it overrides the role of the user there.
+    #[serde(default)]
+    pub channel_roles: BTreeMap<i32, Role>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub token: Option<String>,
 }
@@ -138,20 +207,117 @@ impl FromRow<'_, SqliteRow> for User {
                     .map(|i| i.parse::<i32>().unwrap_or_default())
                     .collect(),
             ),
+            channel_roles: channel_roles(
+                &row.try_get::<String, &str>("channel_roles")
+                    .unwrap_or_default(),
+            ),
             token: None,
         })
     }
 }
 
+/// Parse the channel roles from the database, like `1:user,2:guest`.
+///
+/// Global admin is not possible as channel role.
+pub fn channel_roles(value: &str) -> BTreeMap<i32, Role> {
+    value
+        .split(',')
+        .filter_map(|r| r.split_once(':'))
+        .filter_map(|(id, role)| Some((id.parse().ok()?, Role::set_role(role))))
+        .filter(|(_, role)| *role != Role::GlobalAdmin)
+        .collect()
+}
+
+/// Long lived token for automation, only the hash of the token is stored.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ApiToken {
+    #[serde(default)]
+    pub id: i32,
+    #[serde(default)]
+    pub user_id: i32,
+    pub name: String,
+    #[serde(skip)]
+    pub token_hash: String,
+    /// Highest role of the token, it can not be higher as the role of the user.
+    pub role_id: i32,
+    /// Channels of the token, empty for all channels of the user.
+    #[serde(default)]
+    pub channel_ids: Vec<i32>,
+    #[serde(default)]
+    pub created_at: String,
+    /// Days until the token expires, only for creating the token.
+    #[serde(default, skip_serializing)]
+    pub expires_in: Option<i64>,
+    #[serde(default)]
+    pub expires_at: Option<String>,
+    #[serde(default)]
+    pub last_used: Option<String>,
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+impl FromRow<'_, SqliteRow> for ApiToken {
+    fn from_row(row: &SqliteRow) -> sqlx::Result<Self> {
+        Ok(Self {
+            id: row.try_get("id").unwrap_or_default(),
+            user_id: row.try_get("user_id").unwrap_or_default(),
+            name: row.try_get("name").unwrap_or_default(),
+            token_hash: row.try_get("token_hash").unwrap_or_default(),
+            role_id: row.try_get("role_id").unwrap_or_default(),
+            channel_ids: row
+                .try_get::<String, &str>("channel_ids")
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|i| i.parse::<i32>().ok())
+                .collect(),
+            created_at: row.try_get("created_at").unwrap_or_default(),
+            expires_in: None,
+            expires_at: row.try_get("expires_at").unwrap_or_default(),
+            last_used: row.try_get("last_used").unwrap_or_default(),
+            revoked: row.try_get("revoked").unwrap_or_default(),
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct UserMeta {
     pub id: i32,
     pub channels: Vec<i32>,
+    pub role: Role,
+    pub channel_roles: BTreeMap<i32, Role>,
+    /// Request is authenticated with an API token.
+    #[serde(default)]
+    pub api_token: bool,
+    #[serde(default)]
+    pub username: String,
 }
 
 impl UserMeta {
-    pub fn new(id: i32, channels: Vec<i32>) -> Self {
-        Self { id, channels }
+    pub fn new(
+        id: i32,
+        channels: Vec<i32>,
+        role: Role,
+        channel_roles: BTreeMap<i32, Role>,
+    ) -> Self {
+        Self {
+            id,
+            channels,
+            role,
+            channel_roles,
+            api_token: false,
+            username: String::new(),
+        }
+    }
+
+    /// Role of the user on the channel.
+    pub fn channel_role(&self, channel: &i32) -> &Role {
+        self.channel_roles.get(channel).unwrap_or(&self.role)
+    }
+
+    /// Access to the channel with one of the roles, global admins have access to all channels.
+    pub fn has_role(&self, channel: &i32, roles: &[Role]) -> bool {
+        self.role == Role::GlobalAdmin
+            || (self.channels.contains(channel) && roles.contains(self.channel_role(channel)))
     }
 }
 
@@ -169,6 +335,24 @@ impl Role {
     pub fn set_role(role: &str) -> Self {
         role.parse().unwrap_or(Self::Guest)
     }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Self::GlobalAdmin => 0,
+            Self::ChannelAdmin => 1,
+            Self::User => 2,
+            Self::Guest => 3,
+        }
+    }
+
+    /// The role, but not higher as `max`.
+    pub fn limit(&self, max: &Role) -> Role {
+        if self.rank() < max.rank() {
+            max.clone()
+        } else {
+            self.clone()
+        }
+    }
 }
 
 impl FromStr for Role {
@@ -242,6 +426,149 @@ pub struct TextPreset {
     pub alpha: String,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, sqlx::FromRow)]
+pub struct PlaylistRevision {
+    pub id: i32,
+    pub channel_id: i32,
+    pub date: String,
+    pub user_id: Option<i32>,
+    pub username: String,
+    pub diff: String,
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playlist: Option<String>,
+    pub created_at: String,
+}
+
+/// Aired clip, with its start time in the channel time zone and the actual duration.
+/// Last known position of a channel, for continuing there after a restart.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, sqlx::FromRow)]
+pub struct Playhead {
+    pub channel_id: i32,
+    /// Date of the playlist, empty in folder mode.
+    pub date: String,
+    pub node_index: i64,
+    pub source: String,
+    /// Position in the clip file, in seconds.
+    pub position: f64,
+    pub saved_at: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, sqlx::FromRow)]
+pub struct Airing {
+    pub id: i64,
+    pub channel_id: i32,
+    pub source: String,
+    pub title: String,
+    pub artist: String,
+    pub category: String,
+    pub started_at: String,
+    /// Duration from the playlist.
+    pub planned: f64,
+    /// Duration on air, None while the clip is running.
+    pub duration: Option<f64>,
+}
+
+/// How often a source aired in a time range.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, sqlx::FromRow)]
+pub struct PlayCount {
+    pub source: String,
+    pub title: String,
+    pub artist: String,
+    pub plays: i64,
+    pub duration: f64,
+}
+
+/// File of the media library, with probe data and embedded tags.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, sqlx::FromRow)]
+pub struct LibraryItem {
+    pub id: i32,
+    pub channel_id: i32,
+    pub path: String,
+    pub size: i64,
+    pub modified: i64,
+    pub checksum: String,
+    pub duration: f64,
+    pub video_codec: String,
+    pub audio_codec: String,
+    pub width: i64,
+    pub height: i64,
+    pub fps: f64,
+    /// Integrated loudness in LUFS, from the loudness cache.
+    pub loudness: Option<f64>,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub genre: String,
+    /// All embedded tags, as JSON object.
+    pub tags: sqlx::types::Json<BTreeMap<String, String>>,
+    pub scanned_at: String,
+}
+
+/// State changing API call, with the changed fields.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub channel_id: Option<i32>,
+    pub user_id: Option<i32>,
+    pub username: String,
+    pub method: String,
+    pub path: String,
+    pub status: i32,
+    /// JSON object with the old values of the changed fields.
+    #[serde(serialize_with = "serialize_json_text")]
+    pub old_value: Option<String>,
+    /// JSON object with the new values of the changed fields, or the request body.
+    #[serde(serialize_with = "serialize_json_text")]
+    pub new_value: Option<String>,
+    pub created_at: String,
+}
+
+/// Filter for the audit log, empty values match all.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AuditQuery {
+    pub channel: i32,
+    pub username: String,
+    pub method: String,
+    /// Text in the request path.
+    pub path: String,
+    /// Start date or time, like `2024-01-01` or `2024-01-01 12:00:00`.
+    pub from: String,
+    /// End date or time, the date is included.
+    pub to: String,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Search and filter for the media library, empty values match all.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LibraryQuery {
+    /// Text in path, title, artist, album or genre.
+    pub search: String,
+    /// Video or audio codec.
+    pub codec: String,
+    /// Tag query, like `tag:music AND duration<300`.
+    pub query: String,
+    pub min_duration: f64,
+    pub max_duration: f64,
+    pub min_height: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Serialize JSON from the database as JSON, not as string.
+fn serialize_json_text<S>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    value
+        .as_deref()
+        .and_then(|v| serde_json::from_str::<serde_json::Value>(v).ok())
+        .serialize(serializer)
+}
+
 /// Deserialize number or string
 pub fn deserialize_number_or_string<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
@@ -283,6 +610,8 @@ pub struct Configuration {
     pub id: i32,
     pub channel_id: i32,
     pub general_stop_threshold: f64,
+    #[serde(default)]
+    pub general_drift_correction: f64,
 
     pub mail_subject: String,
     pub mail_recipient: String,
@@ -323,6 +652,50 @@ pub struct Configuration {
     pub processing_vtt_enable: bool,
     #[serde(default)]
     pub processing_vtt_dummy: Option<String>,
+    #[serde(default)]
+    pub processing_audio_languages: String,
+    #[serde(default)]
+    pub processing_subtitle_mode: String,
+    #[serde(default)]
+    pub processing_crossfade: f64,
+    #[serde(default = "default_crossfade_transition")]
+    pub processing_crossfade_transition: String,
+    #[serde(default = "default_crossfade_curve")]
+    pub processing_crossfade_curve: String,
+    #[serde(default)]
+    pub processing_logo_schedule: String,
+    #[serde(default)]
+    pub processing_logo_loop: i64,
+    #[serde(default)]
+    pub processing_logo_fps: f64,
+    #[serde(default)]
+    pub processing_loudnorm: bool,
+    #[serde(default = "default_loudnorm_i")]
+    pub processing_loudnorm_i: f64,
+    #[serde(default = "default_loudnorm_lra")]
+    pub processing_loudnorm_lra: f64,
+    #[serde(default = "default_loudnorm_tp")]
+    pub processing_loudnorm_tp: f64,
+    #[serde(default = "default_hw_accel")]
+    pub processing_hw_accel: String,
+    #[serde(default)]
+    pub processing_hw_device: String,
+    #[serde(default = "default_deinterlace")]
+    pub processing_deinterlace: String,
+    #[serde(default = "default_aspect_mode")]
+    pub processing_aspect_mode: String,
+    #[serde(default = "default_tonemap")]
+    pub processing_tonemap: String,
+    #[serde(default = "default_tonemap_algorithm")]
+    pub processing_tonemap_algorithm: String,
+    #[serde(default = "default_tonemap_peak")]
+    pub processing_tonemap_peak: f64,
+    #[serde(default = "default_fps_mode")]
+    pub processing_fps_mode: String,
+    #[serde(default = "default_color_space")]
+    pub processing_color_space: String,
+    #[serde(default = "default_backend")]
+    pub processing_backend: String,
 
     pub ingest_enable: bool,
     pub ingest_param: String,
@@ -332,22 +705,263 @@ pub struct Configuration {
     pub playlist_day_start: String,
     pub playlist_length: String,
     pub playlist_infinit: bool,
+    #[serde(default)]
+    pub playlist_gap_policy: String,
 
     pub storage_filler: String,
     pub storage_extensions: String,
     pub storage_shuffle: bool,
+    #[serde(default)]
+    pub storage_max_depth: i64,
+    #[serde(default)]
+    pub storage_exclude: String,
+    #[serde(default)]
+    pub storage_weights: String,
+    #[serde(default)]
+    pub storage_shuffle_history: i64,
+    #[serde(default)]
+    pub storage_shuffle_history_minutes: i64,
+    #[serde(default)]
+    pub storage_cache_path: String,
+    #[serde(default = "default_cache_size")]
+    pub storage_cache_size: i64,
+    #[serde(default = "default_library_interval")]
+    pub storage_library_interval: f64,
+    #[serde(default)]
+    pub storage_query: String,
+    #[serde(default)]
+    pub storage_previews: bool,
 
     pub text_add: bool,
     pub text_from_filename: bool,
     pub text_font: String,
     pub text_style: String,
     pub text_regex: String,
+    #[serde(default)]
+    pub text_now_next: bool,
+    #[serde(default = "default_now_next_format")]
+    pub text_now_next_format: String,
+    #[serde(default = "default_now_next_style")]
+    pub text_now_next_style: String,
 
     pub task_enable: bool,
     pub task_path: String,
 
+    #[serde(default)]
+    pub script_enable: bool,
+    #[serde(default)]
+    pub script_path: String,
+    #[serde(default = "default_script_timeout")]
+    pub script_timeout: f64,
+
+    #[serde(default)]
+    pub recorder_enable: bool,
+    #[serde(default)]
+    pub recorder_path: String,
+    #[serde(default)]
+    pub recorder_format: String,
+    #[serde(default = "default_recorder_segment")]
+    pub recorder_segment: i64,
+    #[serde(default)]
+    pub recorder_retention: i64,
+    #[serde(default)]
+    pub recorder_param: String,
+
+    #[serde(default)]
+    pub webhook_urls: String,
+    #[serde(default)]
+    pub webhook_secret: String,
+    #[serde(default)]
+    pub webhook_events: String,
+
+    #[serde(default)]
+    pub hooks_clip_start: String,
+    #[serde(default)]
+    pub hooks_clip_end: String,
+    #[serde(default)]
+    pub hooks_error: String,
+    #[serde(default = "default_hooks_timeout")]
+    pub hooks_timeout: f64,
+
+    #[serde(default)]
+    pub mqtt_enable: bool,
+    #[serde(default)]
+    pub mqtt_broker: String,
+    #[serde(default)]
+    pub mqtt_topic: String,
+    #[serde(default)]
+    pub mqtt_username: String,
+    #[serde(default)]
+    pub mqtt_password: String,
+
+    #[serde(default)]
+    pub transition_stinger_path: String,
+    #[serde(default)]
+    pub transition_stinger_offset: f64,
+
+    #[serde(default)]
+    pub monitor_silence_duration: f64,
+    #[serde(default = "default_silence_noise")]
+    pub monitor_silence_noise: f64,
+    #[serde(default)]
+    pub monitor_black_duration: f64,
+    #[serde(default = "default_black_level")]
+    pub monitor_black_level: f64,
+    #[serde(default)]
+    pub monitor_freeze_duration: f64,
+
+    #[serde(default)]
+    pub failover_enable: bool,
+    #[serde(default)]
+    pub failover_source: String,
+
+    #[serde(default)]
+    pub sync_main_url: String,
+    #[serde(default)]
+    pub sync_secret: String,
+
+    #[serde(default)]
+    pub graphics_enable: bool,
+    #[serde(default)]
+    pub graphics_source: String,
+    #[serde(default)]
+    pub graphics_command: String,
+    #[serde(default = "default_graphics_interval")]
+    pub graphics_interval: f64,
+
+    #[serde(default)]
+    pub ticker_enable: bool,
+    #[serde(default)]
+    pub ticker_feeds: String,
+    #[serde(default = "default_ticker_interval")]
+    pub ticker_interval: f64,
+    #[serde(default = "default_ticker_separator")]
+    pub ticker_separator: String,
+    #[serde(default)]
+    pub ticker_schedule: String,
+    #[serde(default = "default_ticker_speed")]
+    pub ticker_speed: f64,
+    #[serde(default = "default_ticker_style")]
+    pub ticker_style: String,
+
+    #[serde(default)]
+    pub clock_enable: bool,
+    #[serde(default = "default_clock_format")]
+    pub clock_format: String,
+    #[serde(default = "default_clock_position")]
+    pub clock_position: String,
+    #[serde(default)]
+    pub clock_font: String,
+    #[serde(default = "default_clock_style")]
+    pub clock_style: String,
+
+    #[serde(default)]
+    pub visual_radio_enable: bool,
+    #[serde(default)]
+    pub visual_radio_artwork: String,
+    #[serde(default = "default_visual_radio_visualizer")]
+    pub visual_radio_visualizer: String,
+    #[serde(default = "default_now_playing")]
+    pub visual_radio_now_playing: bool,
+    #[serde(default = "default_visual_radio_style")]
+    pub visual_radio_style: String,
+
+    #[serde(default)]
+    pub asrun_enable: bool,
+    #[serde(default)]
+    pub asrun_path: String,
+    #[serde(default = "default_asrun_format")]
+    pub asrun_format: String,
+    #[serde(default = "default_asrun_columns")]
+    pub asrun_columns: String,
+    #[serde(default)]
+    pub asrun_thumbnails: bool,
+    #[serde(default)]
+    pub asrun_thumbnail_interval: f64,
+
+    #[serde(default)]
+    pub preview_enable: bool,
+    #[serde(default = "default_preview_height")]
+    pub preview_height: i64,
+    #[serde(default = "default_preview_bitrate")]
+    pub preview_bitrate: i64,
+
     pub output_mode: String,
     pub output_param: String,
+    #[serde(default)]
+    pub output_srt_mode: String,
+    #[serde(default)]
+    pub output_srt_address: String,
+    #[serde(default)]
+    pub output_srt_passphrase: String,
+    #[serde(default = "default_srt_latency")]
+    pub output_srt_latency: i64,
+    #[serde(default)]
+    pub output_ndi_name: String,
+    #[serde(default)]
+    pub output_decklink_device: String,
+    #[serde(default)]
+    pub output_decklink_mode: String,
+    #[serde(default)]
+    pub output_legs: String,
+    #[serde(default)]
+    pub output_icecast_address: String,
+    #[serde(default)]
+    pub output_icecast_mount: String,
+    #[serde(default)]
+    pub output_icecast_username: String,
+    #[serde(default)]
+    pub output_icecast_password: String,
+    #[serde(default)]
+    pub output_icecast_format: String,
+    #[serde(default = "default_icecast_bitrate")]
+    pub output_icecast_bitrate: i64,
+    #[serde(default)]
+    pub output_icecast_video: String,
+    #[serde(default)]
+    pub output_icecast_cover: String,
+    #[serde(default)]
+    pub output_icecast_metadata: bool,
+    #[serde(default)]
+    pub output_icecast_metadata_url: String,
+    #[serde(default)]
+    pub output_hls_ladder: String,
+    #[serde(default)]
+    pub output_hls_fmp4: bool,
+    #[serde(default = "default_hls_time")]
+    pub output_hls_time: f64,
+    #[serde(default)]
+    pub output_hls_low_latency: bool,
+    #[serde(default = "default_hls_part_time")]
+    pub output_hls_part_time: f64,
+    #[serde(default)]
+    pub output_hls_program_date_time: bool,
+    #[serde(default)]
+    pub output_hls_encryption: bool,
+    #[serde(default)]
+    pub output_hls_key_rotation: i64,
+    #[serde(default)]
+    pub output_hls_key_url: String,
+    #[serde(default = "default_hls_key_iv")]
+    pub output_hls_key_iv: String,
+    #[serde(default)]
+    pub output_reconnect: bool,
+    #[serde(default)]
+    pub output_reconnect_attempts: i64,
+    #[serde(default = "default_reconnect_delay")]
+    pub output_reconnect_delay: i64,
+    #[serde(default = "default_reconnect_alert")]
+    pub output_reconnect_alert: i64,
+    #[serde(default)]
+    pub output_reconnect_slate: String,
+    #[serde(default)]
+    pub output_whip_url: String,
+    #[serde(default)]
+    pub output_whip_token: String,
+    #[serde(default = "default_whip_bitrate")]
+    pub output_whip_bitrate: i64,
+    #[serde(default = "default_whip_profile")]
+    pub output_whip_profile: String,
 }
 
 impl Configuration {
@@ -356,6 +970,7 @@ impl Configuration {
             id,
             channel_id,
             general_stop_threshold: config.general.stop_threshold,
+            general_drift_correction: config.general.drift_correction,
             mail_subject: config.mail.subject,
             mail_recipient: config.mail.recipient,
             mail_level: config.mail.mail_level.to_string(),
@@ -385,24 +1000,165 @@ impl Configuration {
             processing_override_filter: config.processing.override_filter,
             processing_vtt_enable: config.processing.vtt_enable,
             processing_vtt_dummy: config.processing.vtt_dummy,
+            processing_audio_languages: config.processing.audio_languages.join(";"),
+            processing_subtitle_mode: config.processing.subtitle_mode.to_string(),
+            processing_crossfade: config.processing.crossfade,
+            processing_crossfade_transition: config.processing.crossfade_transition,
+            processing_crossfade_curve: config.processing.crossfade_curve,
+            processing_logo_schedule: config.processing.logo_schedule.join("\n"),
+            processing_logo_loop: config.processing.logo_loop,
+            processing_logo_fps: config.processing.logo_fps,
+            processing_loudnorm: config.processing.loudnorm,
+            processing_loudnorm_i: config.processing.loudnorm_i,
+            processing_loudnorm_lra: config.processing.loudnorm_lra,
+            processing_loudnorm_tp: config.processing.loudnorm_tp,
+            processing_hw_accel: config.processing.hw_accel.to_string(),
+            processing_hw_device: config.processing.hw_device,
+            processing_deinterlace: config.processing.deinterlace.to_string(),
+            processing_aspect_mode: config.processing.aspect_mode.to_string(),
+            processing_tonemap: config.processing.tonemap.to_string(),
+            processing_tonemap_algorithm: config.processing.tonemap_algorithm,
+            processing_tonemap_peak: config.processing.tonemap_peak,
+            processing_fps_mode: config.processing.fps_mode.to_string(),
+            processing_color_space: config.processing.color_space.to_string(),
+            processing_backend: config.processing.backend.to_string(),
             ingest_enable: config.ingest.enable,
             ingest_param: config.ingest.input_param,
             ingest_filter: config.ingest.custom_filter,
             playlist_day_start: config.playlist.day_start,
             playlist_length: config.playlist.length,
             playlist_infinit: config.playlist.infinit,
+            playlist_gap_policy: config.playlist.gap_policy.join("\n"),
             storage_filler: config.storage.filler,
             storage_extensions: config.storage.extensions.join(";"),
             storage_shuffle: config.storage.shuffle,
+            storage_max_depth: config.storage.max_depth as i64,
+            storage_exclude: config.storage.exclude.join(";"),
+            storage_shuffle_history: config.storage.shuffle_history as i64,
+            storage_shuffle_history_minutes: config.storage.shuffle_history_minutes as i64,
+            storage_cache_path: config.storage.cache_path.to_string_lossy().to_string(),
+            storage_cache_size: config.storage.cache_size as i64,
+            storage_library_interval: config.storage.library_interval,
+            storage_query: config.storage.query.clone(),
+            storage_previews: config.storage.previews,
+            storage_weights: config
+                .storage
+                .weights
+                .iter()
+                .map(|(prefix, weight)| format!("{prefix}={weight}"))
+                .collect::<Vec<String>>()
+                .join(";"),
             text_add: config.text.add_text,
             text_font: config.text.font,
             text_from_filename: config.text.text_from_filename,
             text_style: config.text.style,
             text_regex: config.text.regex,
+            text_now_next: config.text.now_next,
+            text_now_next_format: config.text.now_next_format,
+            text_now_next_style: config.text.now_next_style,
             task_enable: config.task.enable,
             task_path: config.task.path.to_string_lossy().to_string(),
+            script_enable: config.script.enable,
+            script_path: config.script.path.to_string_lossy().to_string(),
+            script_timeout: config.script.timeout,
+            recorder_enable: config.recorder.enable,
+            recorder_path: config.recorder.path.to_string_lossy().to_string(),
+            recorder_format: config.recorder.format.to_string(),
+            recorder_segment: config.recorder.segment,
+            recorder_retention: config.recorder.retention,
+            recorder_param: config.recorder.param,
+            webhook_urls: config.webhook.urls.join("\n"),
+            webhook_secret: config.webhook.secret,
+            webhook_events: config.webhook.events.join(";"),
+            hooks_clip_start: config.hooks.clip_start,
+            hooks_clip_end: config.hooks.clip_end,
+            hooks_error: config.hooks.error,
+            hooks_timeout: config.hooks.timeout,
+            mqtt_enable: config.mqtt.enable,
+            mqtt_broker: config.mqtt.broker,
+            mqtt_topic: config.mqtt.topic,
+            mqtt_username: config.mqtt.username,
+            mqtt_password: config.mqtt.password,
+            transition_stinger_path: config.transition.stinger_path,
+            transition_stinger_offset: config.transition.stinger_offset,
+            monitor_silence_duration: config.monitor.silence_duration,
+            monitor_silence_noise: config.monitor.silence_noise,
+            monitor_black_duration: config.monitor.black_duration,
+            monitor_black_level: config.monitor.black_level,
+            monitor_freeze_duration: config.monitor.freeze_duration,
+            failover_enable: config.failover.enable,
+            failover_source: config.failover.source.clone(),
+            sync_main_url: config.sync.main_url.clone(),
+            sync_secret: config.sync.secret.clone(),
+            graphics_enable: config.graphics.enable,
+            graphics_source: config.graphics.source.clone(),
+            graphics_command: config.graphics.command.clone(),
+            graphics_interval: config.graphics.interval,
+            ticker_enable: config.ticker.enable,
+            ticker_feeds: config.ticker.feeds.join("\n"),
+            ticker_interval: config.ticker.interval,
+            ticker_separator: config.ticker.separator.clone(),
+            ticker_schedule: config.ticker.schedule.join("\n"),
+            ticker_speed: config.ticker.speed,
+            ticker_style: config.ticker.style.clone(),
+            clock_enable: config.clock.enable,
+            clock_format: config.clock.format.clone(),
+            clock_position: config.clock.position.clone(),
+            clock_font: config.clock.font.clone(),
+            clock_style: config.clock.style.clone(),
+            visual_radio_enable: config.visual_radio.enable,
+            visual_radio_artwork: config.visual_radio.artwork.clone(),
+            visual_radio_visualizer: config.visual_radio.visualizer.clone(),
+            visual_radio_now_playing: config.visual_radio.now_playing,
+            visual_radio_style: config.visual_radio.style.clone(),
+            asrun_enable: config.asrun.enable,
+            asrun_path: config.asrun.path.to_string_lossy().to_string(),
+            asrun_format: config.asrun.format.clone(),
+            asrun_columns: config.asrun.columns.join(";"),
+            asrun_thumbnails: config.asrun.thumbnails,
+            asrun_thumbnail_interval: config.asrun.thumbnail_interval,
+            preview_enable: config.preview.enable,
+            preview_height: config.preview.height,
+            preview_bitrate: config.preview.bitrate,
             output_mode: config.output.mode.to_string(),
             output_param: config.output.output_param,
+            output_srt_mode: config.output.srt_mode.to_string(),
+            output_srt_address: config.output.srt_address,
+            output_srt_passphrase: config.output.srt_passphrase,
+            output_srt_latency: config.output.srt_latency,
+            output_ndi_name: config.output.ndi_name,
+            output_decklink_device: config.output.decklink_device,
+            output_decklink_mode: config.output.decklink_mode,
+            output_legs: config.output.legs.join("\n"),
+            output_icecast_address: config.output.icecast_address,
+            output_icecast_mount: config.output.icecast_mount,
+            output_icecast_username: config.output.icecast_username,
+            output_icecast_password: config.output.icecast_password,
+            output_icecast_format: config.output.icecast_format,
+            output_icecast_bitrate: config.output.icecast_bitrate,
+            output_icecast_video: config.output.icecast_video,
+            output_icecast_cover: config.output.icecast_cover,
+            output_icecast_metadata: config.output.icecast_metadata,
+            output_icecast_metadata_url: config.output.icecast_metadata_url,
+            output_hls_ladder: config.output.hls_ladder.join("\n"),
+            output_hls_fmp4: config.output.hls_fmp4,
+            output_hls_time: config.output.hls_time,
+            output_hls_low_latency: config.output.hls_low_latency,
+            output_hls_part_time: config.output.hls_part_time,
+            output_hls_program_date_time: config.output.hls_program_date_time,
+            output_hls_encryption: config.output.hls_encryption,
+            output_hls_key_rotation: config.output.hls_key_rotation,
+            output_hls_key_url: config.output.hls_key_url,
+            output_hls_key_iv: config.output.hls_key_iv,
+            output_reconnect: config.output.reconnect,
+            output_reconnect_attempts: config.output.reconnect_attempts,
+            output_reconnect_delay: config.output.reconnect_delay,
+            output_reconnect_alert: config.output.reconnect_alert,
+            output_reconnect_slate: config.output.reconnect_slate,
+            output_whip_url: config.output.whip_url,
+            output_whip_token: config.output.whip_token,
+            output_whip_bitrate: config.output.whip_bitrate,
+            output_whip_profile: config.output.whip_profile,
         }
     }
 }
@@ -419,6 +1175,209 @@ fn default_channels() -> u8 {
     2
 }
 
+fn default_cache_size() -> i64 {
+    3
+}
+
+fn default_library_interval() -> f64 {
+    60.0
+}
+
+fn default_srt_latency() -> i64 {
+    120
+}
+
+fn default_recorder_segment() -> i64 {
+    3600
+}
+
+fn default_crossfade_transition() -> String {
+    "fade".to_string()
+}
+
+fn default_crossfade_curve() -> String {
+    "tri".to_string()
+}
+
+fn default_loudnorm_i() -> f64 {
+    -23.0
+}
+
+fn default_loudnorm_lra() -> f64 {
+    7.0
+}
+
+fn default_loudnorm_tp() -> f64 {
+    -1.0
+}
+
+fn default_silence_noise() -> f64 {
+    -60.0
+}
+
+fn default_black_level() -> f64 {
+    0.1
+}
+
+fn default_graphics_interval() -> f64 {
+    5.0
+}
+
+fn default_ticker_interval() -> f64 {
+    300.0
+}
+
+fn default_ticker_separator() -> String {
+    " +++ ".to_string()
+}
+
+fn default_ticker_speed() -> f64 {
+    100.0
+}
+
+fn default_ticker_style() -> String {
+    "y=h-50:fontsize=28:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=8".to_string()
+}
+
+fn default_icecast_bitrate() -> i64 {
+    128
+}
+
+fn default_clock_format() -> String {
+    "%H:%M:%S".to_string()
+}
+
+fn default_clock_position() -> String {
+    "x=w-tw-40:y=40".to_string()
+}
+
+fn default_clock_style() -> String {
+    "fontsize=32:fontcolor=white:shadowx=2:shadowy=2".to_string()
+}
+
+fn default_visual_radio_visualizer() -> String {
+    "spectrum".to_string()
+}
+
+fn default_now_playing() -> bool {
+    true
+}
+
+fn default_visual_radio_style() -> String {
+    "x=40:y=40:fontsize=36:fontcolor=white:box=1:boxcolor=black@0.5:boxborderw=10".to_string()
+}
+
+fn default_asrun_format() -> String {
+    "xml".to_string()
+}
+
+fn default_asrun_columns() -> String {
+    "scheduled;actual;deviation;planned;duration;title;source;status".to_string()
+}
+
+fn default_hooks_timeout() -> f64 {
+    30.0
+}
+
+fn default_script_timeout() -> f64 {
+    2.0
+}
+
+fn default_preview_height() -> i64 {
+    360
+}
+
+fn default_preview_bitrate() -> i64 {
+    600
+}
+
+fn default_hls_time() -> f64 {
+    6.0
+}
+
+fn default_hls_part_time() -> f64 {
+    0.5
+}
+
+fn default_hls_key_iv() -> String {
+    "sequence".to_string()
+}
+
+fn default_reconnect_delay() -> i64 {
+    30
+}
+
+fn default_reconnect_alert() -> i64 {
+    60
+}
+
+fn default_whip_bitrate() -> i64 {
+    2500
+}
+
+fn default_whip_profile() -> String {
+    "baseline".to_string()
+}
+
+fn default_hw_accel() -> String {
+    "none".to_string()
+}
+
+fn default_deinterlace() -> String {
+    "auto".to_string()
+}
+
+fn default_aspect_mode() -> String {
+    "auto".to_string()
+}
+
+fn default_tonemap() -> String {
+    "zscale".to_string()
+}
+
+fn default_tonemap_algorithm() -> String {
+    "hable".to_string()
+}
+
+fn default_tonemap_peak() -> f64 {
+    100.0
+}
+
+fn default_fps_mode() -> String {
+    "auto".to_string()
+}
+
+fn default_color_space() -> String {
+    "auto".to_string()
+}
+
+fn default_backend() -> String {
+    "ffmpeg".to_string()
+}
+
+fn default_now_next_format() -> String {
+    "Now: {{title}} / Next: {{next_title}}".to_string()
+}
+
+fn default_now_next_style() -> String {
+    "x=w*0.05:y=h*0.9:fontsize=24:fontcolor=#ffffff:box=1:boxcolor=#000000@0.6:boxborderw=6"
+        .to_string()
+}
+
+/// Measured loudness of a file, from the first loudnorm pass.
+///
+/// Size and modification time identify the analyzed version, a changed file gets measured again.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, sqlx::FromRow)]
+pub struct Loudness {
+    pub source: String,
+    pub size: i64,
+    pub modified: i64,
+    pub input_i: f64,
+    pub input_lra: f64,
+    pub input_tp: f64,
+    pub input_thresh: f64,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize, sqlx::FromRow)]
 pub struct AdvancedConfiguration {
     pub id: i32,
@@ -0,0 +1,1041 @@
+use std::{
+    collections::HashMap,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, LazyLock,
+    },
+    time::Duration,
+};
+
+use actix_multipart::Multipart;
+use actix_web::{web, HttpRequest, HttpResponse};
+use futures_util::{stream, TryStreamExt as _};
+use lexical_sort::{natural_lexical_cmp, PathSort};
+use log::*;
+use rand::{distr::Alphanumeric, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use regex::Regex;
+use reqwest::Url;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+        TcpStream,
+    },
+    sync::{Mutex, MutexGuard},
+    task::JoinHandle,
+};
+
+use crate::file::{
+    remote_abs_path,
+    utils::{login, media_map::SharedMediaMap},
+    MoveObject, PathObject, Storage, VideoFile,
+};
+use crate::player::{
+    input::folder::{insert_media, remove_media},
+    utils::{file_extension, include_file_extension, probe::MediaProbe, Media},
+};
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
+
+pub const FTP_INDICATOR: &str = "ftp://";
+
+/// Line of a unix style LIST response.
+static LIST_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([\-dl])\S*\s+\d+\s+\S+\s+\S+\s+(\d+)\s+(\w{3}\s+\d+\s+[\d:]+)\s+(.+)$").unwrap()
+});
+
+/// Entry from a FTP directory listing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FtpEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: String,
+}
+
+/// Files which changed between two listings, with path and signature.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Changes {
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Changes {
+    pub fn new(previous: &HashMap<String, String>, current: &HashMap<String, String>) -> Self {
+        let mut changes = Self::default();
+
+        for (path, signature) in current {
+            match previous.get(path) {
+                None => changes.added.push(path.clone()),
+                Some(s) if s != signature => changes.modified.push(path.clone()),
+                _ => {}
+            }
+        }
+
+        changes.removed = previous
+            .keys()
+            .filter(|p| !current.contains_key(*p))
+            .cloned()
+            .collect();
+
+        changes.added.sort();
+        changes.modified.sort();
+        changes.removed.sort();
+
+        changes
+    }
+}
+
+/// Minimal async FTP client, it supports only the commands which the storage needs.
+///
+/// Data connections are always passive and are opened to the host of the control connection,
+/// which works also with servers behind NAT.
+struct FtpClient {
+    host: String,
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+impl FtpClient {
+    async fn connect(url: &Url) -> io::Result<Self> {
+        let host = url.host_str().unwrap_or_default().to_string();
+        let stream = TcpStream::connect((host.as_str(), url.port().unwrap_or(21))).await?;
+        let (reader, writer) = stream.into_split();
+        let mut client = Self {
+            host,
+            reader: BufReader::new(reader),
+            writer,
+        };
+
+        client.expect_response(&[220]).await?;
+
+        let user = if url.username().is_empty() {
+            "anonymous".to_string()
+        } else {
+            percent_decode(url.username())
+        };
+
+        match client.command(&format!("USER {user}")).await? {
+            (230, _) => {}
+            (331, _) => {
+                client
+                    .expect(
+                        &format!(
+                            "PASS {}",
+                            percent_decode(url.password().unwrap_or_default())
+                        ),
+                        &[202, 230],
+                    )
+                    .await?;
+            }
+            (_, msg) => return Err(io::Error::other(format!("FTP login failed: {msg}"))),
+        }
+
+        client.expect("TYPE I", &[200]).await?;
+
+        Ok(client)
+    }
+
+    async fn response(&mut self) -> io::Result<(u32, String)> {
+        let mut line = String::new();
+
+        if self.reader.read_line(&mut line).await? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "FTP connection closed",
+            ));
+        }
+
+        let code = line
+            .get(..3)
+            .and_then(|c| c.parse().ok())
+            .ok_or_else(|| io::Error::other(format!("Invalid FTP response: {line}")))?;
+        let mut message = line.clone();
+
+        // multiline response, ends with "<code> "
+        if line.as_bytes().get(3) == Some(&b'-') {
+            let end = format!("{code} ");
+
+            loop {
+                line.clear();
+
+                if self.reader.read_line(&mut line).await? == 0 {
+                    break;
+                }
+
+                message.push_str(&line);
+
+                if line.starts_with(&end) {
+                    break;
+                }
+            }
+        }
+
+        Ok((code, message.trim_end().to_string()))
+    }
+
+    async fn expect_response(&mut self, codes: &[u32]) -> io::Result<String> {
+        let (code, msg) = self.response().await?;
+
+        if codes.contains(&code) {
+            Ok(msg)
+        } else {
+            Err(io::Error::other(msg))
+        }
+    }
+
+    async fn command(&mut self, cmd: &str) -> io::Result<(u32, String)> {
+        // a line break in an argument would send a second command
+        if cmd.contains(['\r', '\n']) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid ftp command: {}", cmd.escape_debug()),
+            ));
+        }
+
+        self.writer
+            .write_all(format!("{cmd}\r\n").as_bytes())
+            .await?;
+        self.response().await
+    }
+
+    async fn expect(&mut self, cmd: &str, codes: &[u32]) -> io::Result<String> {
+        let (code, msg) = self.command(cmd).await?;
+
+        if codes.contains(&code) {
+            Ok(msg)
+        } else {
+            // only the command name, to not log the password
+            let name = cmd.split_whitespace().next().unwrap_or_default();
+
+            Err(io::Error::other(format!("FTP {name} failed: {msg}")))
+        }
+    }
+
+    async fn data_stream(&mut self) -> io::Result<TcpStream> {
+        let (code, msg) = self.command("EPSV").await?;
+
+        let port = if code == 229 {
+            // 229 Entering Extended Passive Mode (|||6446|)
+            msg.split('|').nth(3).and_then(|p| p.parse().ok())
+        } else {
+            // 227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)
+            let msg = self.expect("PASV", &[227]).await?;
+            let numbers: Vec<u16> = msg
+                .split(['(', ')'])
+                .nth(1)
+                .unwrap_or_default()
+                .split(',')
+                .filter_map(|n| n.trim().parse().ok())
+                .collect();
+
+            (numbers.len() == 6).then(|| numbers[4] * 256 + numbers[5])
+        }
+        .ok_or_else(|| io::Error::other(format!("Invalid passive response: {msg}")))?;
+
+        TcpStream::connect((self.host.as_str(), port)).await
+    }
+
+    async fn list(&mut self, path: &str) -> io::Result<Vec<FtpEntry>> {
+        let mut data = self.data_stream().await?;
+        let (code, _) = self.command(&format!("MLSD {path}")).await?;
+        let mlsd = matches!(code, 125 | 150);
+
+        if !mlsd {
+            // server don't support MLSD, fallback to LIST
+            data = self.data_stream().await?;
+            self.expect(&format!("LIST {path}"), &[125, 150]).await?;
+        }
+
+        let mut text = String::new();
+        data.read_to_string(&mut text).await?;
+        drop(data);
+
+        self.expect_response(&[226, 250]).await?;
+
+        let entries = text
+            .lines()
+            .filter_map(|l| {
+                if mlsd {
+                    parse_mlsd_line(l)
+                } else {
+                    parse_list_line(l)
+                }
+            })
+            .filter(|e| e.name != "." && e.name != "..")
+            .collect();
+
+        Ok(entries)
+    }
+
+    async fn retrieve<W: AsyncWrite + Unpin>(
+        &mut self,
+        path: &str,
+        writer: &mut W,
+    ) -> io::Result<u64> {
+        let mut data = self.retrieve_stream(path).await?;
+        let size = tokio::io::copy(&mut data, writer).await?;
+        drop(data);
+
+        self.expect_response(&[226, 250]).await?;
+
+        Ok(size)
+    }
+
+    async fn retrieve_stream(&mut self, path: &str) -> io::Result<TcpStream> {
+        let data = self.data_stream().await?;
+        self.expect(&format!("RETR {path}"), &[125, 150]).await?;
+
+        Ok(data)
+    }
+
+    async fn store_stream(&mut self, path: &str) -> io::Result<TcpStream> {
+        let data = self.data_stream().await?;
+        self.expect(&format!("STOR {path}"), &[125, 150]).await?;
+
+        Ok(data)
+    }
+
+    async fn is_dir(&mut self, path: &str) -> io::Result<bool> {
+        Ok(self.command(&format!("CWD {path}")).await?.0 == 250)
+    }
+
+    async fn is_file(&mut self, path: &str) -> io::Result<bool> {
+        Ok(self.command(&format!("SIZE {path}")).await?.0 == 213)
+    }
+}
+
+pub fn parse_mlsd_line(line: &str) -> Option<FtpEntry> {
+    // type=file;size=1830;modify=20240411113112; clip.mp4
+    let (facts, name) = line.split_once(' ')?;
+    let mut entry = FtpEntry {
+        name: name.to_string(),
+        ..Default::default()
+    };
+
+    for fact in facts.split(';') {
+        match fact.split_once('=') {
+            Some((key, value)) if key.eq_ignore_ascii_case("type") => {
+                let value = value.to_lowercase();
+
+                if value == "cdir" || value == "pdir" {
+                    return None;
+                }
+
+                entry.is_dir = value == "dir";
+            }
+            Some((key, value)) if key.eq_ignore_ascii_case("size") => {
+                entry.size = value.parse().unwrap_or_default();
+            }
+            Some((key, value)) if key.eq_ignore_ascii_case("modify") => {
+                entry.modified = value.to_string();
+            }
+            _ => {}
+        }
+    }
+
+    Some(entry)
+}
+
+pub fn parse_list_line(line: &str) -> Option<FtpEntry> {
+    // -rw-r--r-- 1 ftp ftp 1830 Apr 11 11:31 clip.mp4
+    let captures = LIST_LINE.captures(line)?;
+
+    Some(FtpEntry {
+        name: captures[4].to_string(),
+        is_dir: &captures[1] == "d",
+        size: captures[2].parse().unwrap_or_default(),
+        modified: captures[3].to_string(),
+    })
+}
+
+/// Path of the url, without percent encoding.
+pub fn decoded_path(url: &Url) -> String {
+    percent_decode(url.path())
+}
+
+/// Text from an url part, like user, password or path, without percent encoding.
+pub fn percent_decode(text: &str) -> String {
+    let path = text.as_bytes();
+    let mut decoded = Vec::with_capacity(path.len());
+    let mut i = 0;
+
+    while i < path.len() {
+        let hex = path
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+
+        match (path[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Download file from FTP url to the given writer, with the login of its storage.
+pub async fn download<W: AsyncWrite + Unpin>(url: &str, writer: &mut W) -> io::Result<u64> {
+    let url = login::resolve(url).unwrap_or_else(|| url.to_string());
+    let url = Url::parse(&url).map_err(io::Error::other)?;
+    let mut client = FtpClient::connect(&url).await?;
+    let size = client.retrieve(&decoded_path(&url), writer).await?;
+    let _ = client.command("QUIT").await;
+
+    Ok(size)
+}
+
+#[derive(Clone)]
+pub struct FtpStorage {
+    pub root: PathBuf,
+    original_root: PathBuf,
+    pub extensions: Vec<String>,
+    url: Url,
+    client: Arc<Mutex<Option<FtpClient>>>,
+    pub watch_handler: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl std::fmt::Debug for FtpStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FtpStorage")
+            .field("root", &self.root)
+            .field("host", &self.url.host_str())
+            .field("extensions", &self.extensions)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FtpStorage {
+    pub async fn new(root: PathBuf, extensions: Vec<String>) -> Result<Self, ServiceError> {
+        let url = Url::parse(&root.to_string_lossy())
+            .map_err(|e| ServiceError::BadRequest(format!("Invalid FTP url: {e}")))?;
+        let path = decoded_path(&url).trim_end_matches('/').to_string();
+
+        login::register(&url);
+
+        Ok(Self {
+            root: PathBuf::from(if path.is_empty() { "/" } else { &path }),
+            original_root: root,
+            extensions,
+            url,
+            client: Arc::new(Mutex::new(None)),
+            watch_handler: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Get shared control connection, reconnect when it is not alive.
+    async fn connection(&self) -> io::Result<MutexGuard<'_, Option<FtpClient>>> {
+        let mut client = self.client.lock().await;
+
+        let alive = match client.as_mut() {
+            Some(c) => c.command("NOOP").await.is_ok_and(|(code, _)| code == 200),
+            None => false,
+        };
+
+        if !alive {
+            *client = Some(FtpClient::connect(&self.url).await?);
+        }
+
+        Ok(client)
+    }
+
+    /// Convert storage path (full url, absolute remote path, or relative path) to absolute remote path.
+    fn remote_path(&self, path: &str) -> Result<String, ServiceError> {
+        remote_abs_path(&self.root, &self.original_root, path)
+    }
+
+    /// Path relative to storage root, like the S3 storage returns object keys.
+    fn relative_path(&self, remote: &str) -> String {
+        remote
+            .strip_prefix(&*self.root.to_string_lossy())
+            .unwrap_or(remote)
+            .trim_start_matches('/')
+            .to_string()
+    }
+
+    /// Url of the file without login, the connections resolve it from the storage.
+    fn file_url(&self, remote: &str) -> String {
+        let mut url = login::public(&self.url);
+        url.set_path(remote);
+
+        url.to_string()
+    }
+
+    async fn list(&self, path: &str) -> io::Result<Vec<FtpEntry>> {
+        let mut client = self.connection().await?;
+        let result = client.as_mut().unwrap().list(path).await;
+
+        if result.is_err() {
+            *client = None;
+        }
+
+        result
+    }
+
+    /// List all files recursive, with their modification signature.
+    async fn walk_files(&self, path: &str) -> io::Result<HashMap<String, String>> {
+        let mut files = HashMap::new();
+        let mut folders = vec![path.to_string()];
+
+        while let Some(folder) = folders.pop() {
+            for entry in self.list(&folder).await? {
+                let remote = format!("{}/{}", folder.trim_end_matches('/'), entry.name);
+
+                if entry.is_dir {
+                    folders.push(remote);
+                } else {
+                    files.insert(remote, format!("{}:{}", entry.size, entry.modified));
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Poll the FTP root for changes and update the `sources` list accordingly.
+    ///
+    /// FTP has no notifications, so new, removed and modified files are detected
+    /// by comparing the size and modification time from the directory listing.
+    pub async fn watch_ftp(
+        &self,
+        config: PlayoutConfig,
+        is_alive: Arc<AtomicBool>,
+        sources: Arc<Mutex<Vec<Media>>>,
+        current_index: Arc<AtomicUsize>,
+    ) {
+        let id = config.general.channel_id;
+        let root = self.root.to_string_lossy().to_string();
+        let mut previous: Option<HashMap<String, String>> = None;
+
+        info!(target: Target::file_mail(), channel = id;
+            "Monitoring FTP folder: <b><magenta>{}{root}</></b>",
+            self.url.host_str().unwrap_or_default()
+        );
+
+        while is_alive.load(Ordering::SeqCst) {
+            match self.walk_files(&root).await {
+                Ok(current) => {
+                    if let Some(previous) = &previous {
+                        let changes = Changes::new(previous, &current);
+                        let updates = changes
+                            .added
+                            .iter()
+                            .map(|p| (p, false))
+                            .chain(changes.modified.iter().map(|p| (p, true)));
+
+                        for (path, modified) in updates {
+                            if !include_file_extension(&config, Path::new(path))
+                                || config.storage.is_excluded(&self.root, Path::new(path))
+                            {
+                                continue;
+                            }
+
+                            let url = self.file_url(path);
+
+                            if modified {
+                                remove_media(&sources, &current_index, &url).await;
+                                info!(target: Target::file_mail(), channel = id; "Modified FTP file: <b><magenta>{path}</></b>");
+                            } else {
+                                info!(target: Target::file_mail(), channel = id; "Added FTP file: <b><magenta>{path}</></b>");
+                            }
+
+                            let mut media = Media::new(0, &url, false).await;
+                            media.key =
+                                self.sanitized_file_path(&format!("/{}", self.relative_path(path)));
+
+                            insert_media(&config, &sources, &current_index, media).await;
+                        }
+
+                        for path in &changes.removed {
+                            if remove_media(&sources, &current_index, &self.file_url(path)).await {
+                                info!(target: Target::file_mail(), channel = id; "Removed FTP file: <b><magenta>{path}</></b>");
+                            }
+                        }
+                    }
+
+                    previous = Some(current);
+                }
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "Error listing FTP folder: {e}");
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        }
+    }
+
+    async fn remove_recursive(&self, client: &mut FtpClient, path: &str) -> io::Result<()> {
+        let mut folders = vec![path.to_string()];
+        let mut remove_folders = vec![];
+
+        while let Some(folder) = folders.pop() {
+            for entry in client.list(&folder).await? {
+                let remote = format!("{}/{}", folder.trim_end_matches('/'), entry.name);
+
+                if entry.is_dir {
+                    folders.push(remote);
+                } else {
+                    client.expect(&format!("DELE {remote}"), &[250]).await?;
+                }
+            }
+
+            remove_folders.push(folder);
+        }
+
+        for folder in remove_folders.iter().rev() {
+            client.expect(&format!("RMD {folder}"), &[250]).await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Storage for FtpStorage {
+    /// Generate proper prefix key for objects' paths.
+    fn path_prefix_generator(&self) -> String {
+        format!("[FTP:]/{}", self.url.host_str().unwrap_or_default())
+    }
+
+    /// Returns a sanitized file path by stripping the root and prefixing with "[FTP:]/{host}".
+    fn sanitized_file_path(&self, path: &str) -> String {
+        let remote = self
+            .remote_path(path)
+            .unwrap_or_else(|_| self.root.to_string_lossy().to_string());
+        let staged_path = remote
+            .strip_prefix(&*self.root.to_string_lossy())
+            .unwrap_or(&remote);
+
+        format!(
+            "{}/{}",
+            self.path_prefix_generator(),
+            staged_path.trim_start_matches('/')
+        )
+    }
+
+    fn interpreted_file_path(&self, path: &str) -> String {
+        let path_prefix = &self.path_prefix_generator();
+        let cleaned_path = path.strip_prefix(path_prefix).unwrap_or(path);
+
+        let remote = self
+            .remote_path(cleaned_path)
+            .unwrap_or_else(|_| self.root.to_string_lossy().to_string());
+
+        self.file_url(&remote)
+    }
+
+    fn echo_log(&self) {
+        info!(
+            "<blue>FTP Storage initialized at '{}{}'",
+            self.url.host_str().unwrap_or_default(),
+            self.root.to_string_lossy()
+        );
+    }
+
+    async fn fetch_file_path(&self, file_path: &str) -> Result<String, ServiceError> {
+        Ok(self.file_url(&self.remote_path(file_path)?))
+    }
+
+    async fn browser(
+        &self,
+        path_obj: &PathObject,
+        dur_data: web::Data<SharedMediaMap>,
+    ) -> Result<PathObject, ServiceError> {
+        let media_duration = dur_data;
+        let remote = self.remote_path(&path_obj.source)?;
+        let host = self.url.host_str().unwrap_or_default().to_string();
+        let mut obj = PathObject::new(self.relative_path(&remote), Some(host));
+        obj.folders_only = path_obj.folders_only;
+
+        if remote != self.root.to_string_lossy() && !path_obj.folders_only {
+            let parent = Path::new(&remote)
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let mut parent_folders: Vec<String> = self
+                .list(&parent)
+                .await?
+                .into_iter()
+                .filter(|e| e.is_dir)
+                .map(|e| e.name)
+                .collect();
+
+            parent_folders.path_sort(natural_lexical_cmp);
+            obj.parent_folders = Some(parent_folders);
+        }
+
+        let mut folders = vec![];
+        let mut files = vec![];
+
+        for entry in self.list(&remote).await? {
+            // ignore hidden files/folders
+            if entry.name.starts_with('.') {
+                continue;
+            }
+
+            if entry.is_dir {
+                folders.push(entry.name);
+            } else if !path_obj.folders_only {
+                if let Some(ext) = file_extension(Path::new(&entry.name)) {
+                    if self.extensions.contains(&ext.to_lowercase()) {
+                        files.push(entry.name);
+                    }
+                }
+            }
+        }
+
+        folders.path_sort(natural_lexical_cmp);
+        files.path_sort(natural_lexical_cmp);
+
+        let mut media_files = vec![];
+
+        for name in files {
+            let file = format!("{}/{name}", remote.trim_end_matches('/'));
+
+            if let Some(duration) = media_duration.get_obj(&file).await {
+                media_files.push(VideoFile { name, duration });
+            } else {
+                match MediaProbe::new(&self.file_url(&file)).await {
+                    Ok(probe) => {
+                        let duration = probe.format.duration.unwrap_or_default();
+                        media_duration.add_obj(file, duration).await?;
+                        media_files.push(VideoFile { name, duration });
+                    }
+                    Err(e) => error!("{e:?}"),
+                };
+            }
+        }
+
+        obj.folders = Some(folders);
+        obj.files = Some(media_files);
+
+        Ok(obj)
+    }
+
+    async fn mkdir(&self, path_obj: &PathObject) -> Result<(), ServiceError> {
+        let remote = self.remote_path(&path_obj.source)?;
+        let mut client = self.connection().await?;
+
+        client
+            .as_mut()
+            .unwrap()
+            .expect(&format!("MKD {remote}"), &[257])
+            .await
+            .map_err(|e| ServiceError::BadRequest(e.to_string()))?;
+
+        info!("create folder: <b><magenta>{remote}</></b>");
+
+        Ok(())
+    }
+
+    async fn rename(
+        &self,
+        move_object: &MoveObject,
+        duration: web::Data<SharedMediaMap>,
+    ) -> Result<MoveObject, ServiceError> {
+        let source = self.remote_path(&move_object.source)?;
+        let mut target = self.remote_path(&move_object.target)?;
+        let mut guard = self.connection().await?;
+        let client = guard.as_mut().unwrap();
+
+        if !client.is_file(&source).await? && !client.is_dir(&source).await? {
+            return Err(ServiceError::BadRequest("Source file not exist!".into()));
+        }
+
+        if client.is_dir(&target).await? {
+            let name = Path::new(&source).file_name().unwrap_or_default();
+            target = format!("{target}/{}", name.to_string_lossy());
+        }
+
+        if client.is_file(&target).await? {
+            return Err(ServiceError::BadRequest(
+                "Target file already exists!".into(),
+            ));
+        }
+
+        client.expect(&format!("RNFR {source}"), &[350]).await?;
+        client.expect(&format!("RNTO {target}"), &[250]).await?;
+        duration.update_obj(&source, &target).await?;
+
+        Ok(MoveObject {
+            source: source.rsplit('/').next().unwrap_or_default().to_string(),
+            target: target.rsplit('/').next().unwrap_or_default().to_string(),
+        })
+    }
+
+    async fn remove(
+        &self,
+        source_path: &str,
+        duration: web::Data<SharedMediaMap>,
+        recursive: bool,
+    ) -> Result<(), ServiceError> {
+        let source = self.remote_path(source_path)?;
+        let mut guard = self.connection().await?;
+        let client = guard.as_mut().unwrap();
+
+        if client.is_file(&source).await? {
+            client
+                .expect(&format!("DELE {source}"), &[250])
+                .await
+                .map_err(|_| ServiceError::BadRequest("Delete file failed!".into()))?;
+            duration.remove_obj(&source).await?;
+
+            return Ok(());
+        }
+
+        if !client.is_dir(&source).await? {
+            return Err(ServiceError::BadRequest("Source does not exists!".into()));
+        }
+
+        let result = if recursive {
+            self.remove_recursive(client, &source).await
+        } else {
+            client
+                .expect(&format!("RMD {source}"), &[250])
+                .await
+                .map(|_| ())
+        };
+
+        result.map_err(|e| {
+            error!("{e}");
+            ServiceError::BadRequest("Delete folder failed! (Folder must be empty)".into())
+        })
+    }
+
+    async fn upload(
+        &self,
+        mut data: Multipart,
+        path: &Path,
+        _is_abs: bool,
+    ) -> Result<(), ServiceError> {
+        let folder = self.remote_path(&path.to_string_lossy())?;
+
+        while let Some(mut field) = data.try_next().await? {
+            let content_disposition = field.content_disposition().ok_or("No content")?;
+            debug!("{content_disposition}");
+            let rand_string: String = rand::rng()
+                .sample_iter(&Alphanumeric)
+                .take(20)
+                .map(char::from)
+                .collect();
+            let filename = content_disposition
+                .get_filename()
+                .map_or_else(|| rand_string.to_string(), sanitize_filename::sanitize);
+            let filepath = format!("{}/{filename}", folder.trim_end_matches('/'));
+
+            let mut guard = self.connection().await?;
+            let client = guard.as_mut().unwrap();
+
+            if client.is_file(&filepath).await? {
+                return Err(ServiceError::Conflict("Target already exists!".into()));
+            }
+
+            let mut stream = client.store_stream(&filepath).await?;
+
+            loop {
+                match field.try_next().await {
+                    Ok(Some(chunk)) => stream.write_all(&chunk).await?,
+                    Ok(None) => break,
+                    Err(e) => {
+                        drop(stream);
+                        let _ = client.expect_response(&[226, 250, 426, 451]).await;
+
+                        if e.to_string().contains("stream is incomplete") {
+                            info!("Delete non finished file: {filepath}");
+
+                            let _ = client.expect(&format!("DELE {filepath}"), &[250]).await;
+                        }
+
+                        return Err(e.into());
+                    }
+                }
+            }
+
+            stream.shutdown().await?;
+            drop(stream);
+            client.expect_response(&[226, 250]).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn watchman(
+        &mut self,
+        config: PlayoutConfig,
+        is_alive: Arc<AtomicBool>,
+        sources: Arc<Mutex<Vec<Media>>>,
+        current_index: Arc<AtomicUsize>,
+    ) {
+        let ftp_storage = self.clone();
+        let task = tokio::spawn(async move {
+            ftp_storage
+                .watch_ftp(config, is_alive, sources, current_index)
+                .await;
+        });
+
+        *self.watch_handler.lock().await = Some(task);
+    }
+
+    async fn stop_watch(&mut self) {
+        let mut watch_handler = self.watch_handler.lock().await;
+
+        if let Some(handler) = watch_handler.as_mut() {
+            handler.abort();
+        }
+    }
+
+    async fn fill_filler_list(
+        &mut self,
+        config: &PlayoutConfig,
+        fillers: Option<Arc<Mutex<Vec<Media>>>>,
+    ) -> Vec<Media> {
+        let id = config.general.channel_id;
+        let filler_path = match self.remote_path(&config.storage.filler_path.to_string_lossy()) {
+            Ok(path) => path,
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "{e}");
+                return vec![];
+            }
+        };
+        let mut filler_list = vec![];
+
+        let paths = if self.is_dir(&filler_path).await {
+            match self.walk_files(&filler_path).await {
+                Ok(files) => files.into_keys().collect(),
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "{e}");
+                    vec![]
+                }
+            }
+        } else {
+            vec![filler_path]
+        };
+
+        for (index, path) in paths.iter().enumerate() {
+            if !include_file_extension(config, Path::new(path)) {
+                continue;
+            }
+
+            let mut media = Media::new(index, &self.file_url(path), false).await;
+
+            if fillers.is_none() {
+                if let Err(e) = media.add_probe(false).await {
+                    error!(target: Target::file_mail(), channel = id; "{e:?}");
+                };
+            }
+
+            filler_list.push(media);
+        }
+
+        if config.storage.shuffle {
+            let mut rng = StdRng::from_os_rng();
+            filler_list.shuffle(&mut rng);
+        } else {
+            filler_list.sort_by(|d1, d2| natural_lexical_cmp(&d1.source, &d2.source));
+        }
+
+        for (index, item) in filler_list.iter_mut().enumerate() {
+            item.index = Some(index);
+        }
+
+        if let Some(f) = fillers.as_ref() {
+            f.lock().await.clone_from(&filler_list);
+        }
+
+        filler_list
+    }
+
+    async fn copy_assets(&self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    async fn is_dir<P: AsRef<Path>>(&self, input: P) -> bool {
+        let Ok(remote) = self.remote_path(&input.as_ref().to_string_lossy()) else {
+            return false;
+        };
+
+        match self.connection().await {
+            Ok(mut client) => client
+                .as_mut()
+                .unwrap()
+                .is_dir(&remote)
+                .await
+                .unwrap_or_default(),
+            Err(_) => false,
+        }
+    }
+
+    async fn is_file<P: AsRef<Path>>(&self, input: P) -> bool {
+        let Ok(remote) = self.remote_path(&input.as_ref().to_string_lossy()) else {
+            return false;
+        };
+
+        match self.connection().await {
+            Ok(mut client) => client
+                .as_mut()
+                .unwrap()
+                .is_file(&remote)
+                .await
+                .unwrap_or_default(),
+            Err(_) => false,
+        }
+    }
+
+    /// Collects all paths under a FTP folder, relative to the storage root.
+    async fn walk_dir<P: AsRef<Path>>(&self, input: P) -> Result<Vec<PathBuf>, ServiceError> {
+        let remote = self.remote_path(&input.as_ref().to_string_lossy())?;
+        let mut contents = vec![];
+        let mut folders = vec![remote];
+
+        while let Some(folder) = folders.pop() {
+            for entry in self.list(&folder).await? {
+                let path = format!("{}/{}", folder.trim_end_matches('/'), entry.name);
+
+                contents.push(PathBuf::from(self.relative_path(&path)));
+
+                if entry.is_dir {
+                    folders.push(path);
+                }
+            }
+        }
+
+        Ok(contents)
+    }
+
+    /// Stream file from FTP server, over its own connection.
+    async fn open_media(
+        &self,
+        _req: &HttpRequest,
+        file_path: &str,
+    ) -> Result<HttpResponse, ServiceError> {
+        let remote = self.remote_path(file_path)?;
+        let mut client = FtpClient::connect(&self.url).await?;
+        let data = client.retrieve_stream(&remote).await?;
+
+        let body = stream::unfold(Some((data, client)), |state| async move {
+            let (mut data, client) = state?;
+            let mut buffer = vec![0u8; 64 * 1024];
+
+            match data.read(&mut buffer).await {
+                Ok(0) => None,
+                Ok(n) => {
+                    buffer.truncate(n);
+                    Some((Ok(web::Bytes::from(buffer)), Some((data, client))))
+                }
+                Err(e) => Some((Err::<web::Bytes, io::Error>(e), None)),
+            }
+        });
+
+        Ok(HttpResponse::Ok()
+            .content_type("application/octet-stream")
+            .streaming(body))
+    }
+}
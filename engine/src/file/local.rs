@@ -1,6 +1,9 @@
 use std::{
     path::{Path, PathBuf},
-    sync::{atomic::AtomicBool, Arc},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize},
+        Arc,
+    },
 };
 
 #[cfg(target_family = "unix")]
@@ -11,7 +14,7 @@ use actix_web::{
     http::header::{ContentDisposition, DispositionType},
     web, HttpRequest, HttpResponse,
 };
-use async_walkdir::WalkDir;
+use async_walkdir::{Filtering, WalkDir};
 
 // use futures_util::TryStreamExt as _;
 use tokio_stream::StreamExt;
@@ -49,6 +52,44 @@ impl LocalStorage {
             watch_handler: Arc::new(Mutex::new(None)),
         }
     }
+
+    /// Walk the folder for media, without going into excluded folders
+    /// and folders which have only files below `max_depth`.
+    pub async fn walk_media_dir<P: AsRef<Path>>(
+        &self,
+        input: P,
+        config: &PlayoutConfig,
+    ) -> Result<Vec<PathBuf>, ServiceError> {
+        let root = input.as_ref().to_path_buf();
+        let storage = Arc::new(config.storage.clone());
+        let mut contents = vec![];
+        let mut entries = WalkDir::new(&root).filter(move |entry| {
+            let root = root.clone();
+            let storage = storage.clone();
+
+            async move {
+                let path = entry.path();
+                let depth = path
+                    .strip_prefix(&root)
+                    .map_or(0, |p| p.components().count());
+                let too_deep = storage.max_depth > 0
+                    && depth >= storage.max_depth
+                    && entry.file_type().await.is_ok_and(|t| t.is_dir());
+
+                if too_deep || storage.is_excluded(&root, &path) {
+                    Filtering::IgnoreDir
+                } else {
+                    Filtering::Continue
+                }
+            }
+        });
+
+        while let Some(Ok(entry)) = entries.next().await {
+            contents.push(entry.path());
+        }
+
+        Ok(contents)
+    }
 }
 
 impl Drop for LocalStorage {
@@ -375,9 +416,10 @@ impl Storage for LocalStorage {
         config: PlayoutConfig,
         is_alive: Arc<AtomicBool>,
         sources: Arc<Mutex<Vec<Media>>>,
+        current_index: Arc<AtomicUsize>,
     ) {
         self.watch_handler = Arc::new(Mutex::new(Some(tokio::spawn(async {
-            watch(config, is_alive, sources).await;
+            watch(config, is_alive, sources, current_index).await;
         }))));
     }
 
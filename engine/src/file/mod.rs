@@ -1,6 +1,9 @@
 use std::{
     path::{Path, PathBuf},
-    sync::{atomic::AtomicBool, Arc},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize},
+        Arc,
+    },
 };
 
 use actix_multipart::Multipart;
@@ -10,15 +13,21 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use utils::ABS_PATH_INDICATOR;
 
+pub mod ftp;
 mod local;
 mod s3;
+pub mod sftp;
 pub mod utils;
+pub mod webdav;
 
 use crate::file::{utils::filler, utils::media_map::SharedMediaMap};
 use crate::player::utils::Media;
 use crate::utils::{config::PlayoutConfig, errors::ServiceError};
 
+use ftp::FTP_INDICATOR;
 use s3::S3_INDICATOR;
+use sftp::SFTP_INDICATOR;
+use webdav::{DAVS_INDICATOR, DAV_INDICATOR};
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct PathObject {
@@ -63,12 +72,18 @@ pub struct VideoFile {
 pub enum StorageType {
     Local,
     S3,
+    Ftp,
+    Sftp,
+    WebDav,
 }
 
 #[derive(Clone, Debug)]
 pub enum StorageBackend {
     Local(local::LocalStorage),
     S3(s3::S3Storage),
+    Ftp(ftp::FtpStorage),
+    Sftp(sftp::SftpStorage),
+    WebDav(webdav::WebDavStorage),
 }
 
 impl StorageBackend {
@@ -76,6 +91,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.interpreted_file_path(path),
             StorageBackend::S3(storage) => storage.interpreted_file_path(path),
+            StorageBackend::Ftp(storage) => storage.interpreted_file_path(path),
+            StorageBackend::Sftp(storage) => storage.interpreted_file_path(path),
+            StorageBackend::WebDav(storage) => storage.interpreted_file_path(path),
         }
     }
 
@@ -83,6 +101,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.sanitized_file_path(path),
             StorageBackend::S3(storage) => storage.sanitized_file_path(path),
+            StorageBackend::Ftp(storage) => storage.sanitized_file_path(path),
+            StorageBackend::Sftp(storage) => storage.sanitized_file_path(path),
+            StorageBackend::WebDav(storage) => storage.sanitized_file_path(path),
         }
     }
 
@@ -90,6 +111,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.echo_log(),
             StorageBackend::S3(storage) => storage.echo_log(),
+            StorageBackend::Ftp(storage) => storage.echo_log(),
+            StorageBackend::Sftp(storage) => storage.echo_log(),
+            StorageBackend::WebDav(storage) => storage.echo_log(),
         }
     }
 
@@ -97,6 +121,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.fetch_file_path(file_path).await,
             StorageBackend::S3(storage) => storage.fetch_file_path(file_path).await,
+            StorageBackend::Ftp(storage) => storage.fetch_file_path(file_path).await,
+            StorageBackend::Sftp(storage) => storage.fetch_file_path(file_path).await,
+            StorageBackend::WebDav(storage) => storage.fetch_file_path(file_path).await,
         }
     }
 
@@ -108,6 +135,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.browser(path_obj, dur_data).await,
             StorageBackend::S3(storage) => storage.browser(path_obj, dur_data).await,
+            StorageBackend::Ftp(storage) => storage.browser(path_obj, dur_data).await,
+            StorageBackend::Sftp(storage) => storage.browser(path_obj, dur_data).await,
+            StorageBackend::WebDav(storage) => storage.browser(path_obj, dur_data).await,
         }
     }
 
@@ -115,6 +145,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.mkdir(path_obj).await,
             StorageBackend::S3(storage) => storage.mkdir(path_obj).await,
+            StorageBackend::Ftp(storage) => storage.mkdir(path_obj).await,
+            StorageBackend::Sftp(storage) => storage.mkdir(path_obj).await,
+            StorageBackend::WebDav(storage) => storage.mkdir(path_obj).await,
         }
     }
 
@@ -126,6 +159,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.rename(move_object, duration).await,
             StorageBackend::S3(storage) => storage.rename(move_object, duration).await,
+            StorageBackend::Ftp(storage) => storage.rename(move_object, duration).await,
+            StorageBackend::Sftp(storage) => storage.rename(move_object, duration).await,
+            StorageBackend::WebDav(storage) => storage.rename(move_object, duration).await,
         }
     }
 
@@ -140,6 +176,11 @@ impl StorageBackend {
                 storage.remove(source_path, duration, recursive).await
             }
             StorageBackend::S3(storage) => storage.remove(source_path, duration, recursive).await,
+            StorageBackend::Ftp(storage) => storage.remove(source_path, duration, recursive).await,
+            StorageBackend::Sftp(storage) => storage.remove(source_path, duration, recursive).await,
+            StorageBackend::WebDav(storage) => {
+                storage.remove(source_path, duration, recursive).await
+            }
         }
     }
 
@@ -152,6 +193,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.upload(payload, path, is_abs).await,
             StorageBackend::S3(storage) => storage.upload(payload, path, is_abs).await,
+            StorageBackend::Ftp(storage) => storage.upload(payload, path, is_abs).await,
+            StorageBackend::Sftp(storage) => storage.upload(payload, path, is_abs).await,
+            StorageBackend::WebDav(storage) => storage.upload(payload, path, is_abs).await,
         }
     }
 
@@ -160,10 +204,34 @@ impl StorageBackend {
         config: PlayoutConfig,
         is_alive: Arc<AtomicBool>,
         sources: Arc<Mutex<Vec<Media>>>,
+        current_index: Arc<AtomicUsize>,
     ) {
         match self {
-            StorageBackend::Local(storage) => storage.watchman(config, is_alive, sources).await,
-            StorageBackend::S3(storage) => storage.watchman(config, is_alive, sources).await,
+            StorageBackend::Local(storage) => {
+                storage
+                    .watchman(config, is_alive, sources, current_index)
+                    .await;
+            }
+            StorageBackend::S3(storage) => {
+                storage
+                    .watchman(config, is_alive, sources, current_index)
+                    .await;
+            }
+            StorageBackend::Ftp(storage) => {
+                storage
+                    .watchman(config, is_alive, sources, current_index)
+                    .await;
+            }
+            StorageBackend::Sftp(storage) => {
+                storage
+                    .watchman(config, is_alive, sources, current_index)
+                    .await;
+            }
+            StorageBackend::WebDav(storage) => {
+                storage
+                    .watchman(config, is_alive, sources, current_index)
+                    .await;
+            }
         }
     }
 
@@ -171,6 +239,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.stop_watch().await,
             StorageBackend::S3(storage) => storage.stop_watch().await,
+            StorageBackend::Ftp(storage) => storage.stop_watch().await,
+            StorageBackend::Sftp(storage) => storage.stop_watch().await,
+            StorageBackend::WebDav(storage) => storage.stop_watch().await,
         }
     }
 
@@ -186,6 +257,9 @@ impl StorageBackend {
             match self {
                 StorageBackend::Local(storage) => storage.fill_filler_list(config, fillers).await,
                 StorageBackend::S3(storage) => storage.fill_filler_list(config, fillers).await,
+                StorageBackend::Ftp(storage) => storage.fill_filler_list(config, fillers).await,
+                StorageBackend::Sftp(storage) => storage.fill_filler_list(config, fillers).await,
+                StorageBackend::WebDav(storage) => storage.fill_filler_list(config, fillers).await,
             }
         }
     }
@@ -194,6 +268,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.copy_assets().await,
             StorageBackend::S3(storage) => storage.copy_assets().await,
+            StorageBackend::Ftp(storage) => storage.copy_assets().await,
+            StorageBackend::Sftp(storage) => storage.copy_assets().await,
+            StorageBackend::WebDav(storage) => storage.copy_assets().await,
         }
     }
 
@@ -201,6 +278,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.is_dir(input).await,
             StorageBackend::S3(storage) => storage.is_dir(input).await,
+            StorageBackend::Ftp(storage) => storage.is_dir(input).await,
+            StorageBackend::Sftp(storage) => storage.is_dir(input).await,
+            StorageBackend::WebDav(storage) => storage.is_dir(input).await,
         }
     }
 
@@ -208,6 +288,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.is_file(input).await,
             StorageBackend::S3(storage) => storage.is_file(input).await,
+            StorageBackend::Ftp(storage) => storage.is_file(input).await,
+            StorageBackend::Sftp(storage) => storage.is_file(input).await,
+            StorageBackend::WebDav(storage) => storage.is_file(input).await,
         }
     }
 
@@ -215,6 +298,21 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.walk_dir(input).await,
             StorageBackend::S3(storage) => storage.walk_dir(input).await,
+            StorageBackend::Ftp(storage) => storage.walk_dir(input).await,
+            StorageBackend::Sftp(storage) => storage.walk_dir(input).await,
+            StorageBackend::WebDav(storage) => storage.walk_dir(input).await,
+        }
+    }
+
+    /// Walk the folder for media, the local storage skips excluded folders already while walking.
+    pub async fn walk_media_dir<P: AsRef<Path>>(
+        &self,
+        input: P,
+        config: &PlayoutConfig,
+    ) -> Result<Vec<PathBuf>, ServiceError> {
+        match self {
+            StorageBackend::Local(storage) => storage.walk_media_dir(input, config).await,
+            _ => self.walk_dir(input).await,
         }
     }
 
@@ -226,6 +324,9 @@ impl StorageBackend {
         match self {
             StorageBackend::Local(storage) => storage.open_media(_req, path_obj).await,
             StorageBackend::S3(storage) => storage.open_media(_req, path_obj).await,
+            StorageBackend::Ftp(storage) => storage.open_media(_req, path_obj).await,
+            StorageBackend::Sftp(storage) => storage.open_media(_req, path_obj).await,
+            StorageBackend::WebDav(storage) => storage.open_media(_req, path_obj).await,
         }
     }
 }
@@ -259,6 +360,7 @@ trait Storage {
         config: PlayoutConfig,
         is_alive: Arc<AtomicBool>,
         sources: Arc<Mutex<Vec<Media>>>,
+        current_index: Arc<AtomicUsize>,
     );
     async fn stop_watch(&mut self);
     async fn fill_filler_list(
@@ -284,6 +386,18 @@ pub fn select_storage_type<S: AsRef<std::ffi::OsStr>>(path: S) -> StorageType {
         return StorageType::S3;
     }
 
+    if path_str.starts_with(FTP_INDICATOR) {
+        return StorageType::Ftp;
+    }
+
+    if path_str.starts_with(SFTP_INDICATOR) {
+        return StorageType::Sftp;
+    }
+
+    if path_str.starts_with(DAV_INDICATOR) || path_str.starts_with(DAVS_INDICATOR) {
+        return StorageType::WebDav;
+    }
+
     StorageType::Local
 }
 
@@ -291,13 +405,20 @@ pub async fn init_storage(
     storage_type: StorageType,
     root: PathBuf,
     extensions: Vec<String>,
-) -> StorageBackend {
-    match storage_type {
+) -> Result<StorageBackend, ServiceError> {
+    let storage = match storage_type {
         StorageType::Local => {
             StorageBackend::Local(local::LocalStorage::new(root, extensions).await)
         }
         StorageType::S3 => StorageBackend::S3(s3::S3Storage::new(root, extensions).await),
-    }
+        StorageType::Ftp => StorageBackend::Ftp(ftp::FtpStorage::new(root, extensions).await?),
+        StorageType::Sftp => StorageBackend::Sftp(sftp::SftpStorage::new(root, extensions).await?),
+        StorageType::WebDav => {
+            StorageBackend::WebDav(webdav::WebDavStorage::new(root, extensions).await?)
+        }
+    };
+
+    Ok(storage)
 }
 
 /// Normalize absolut path
@@ -342,6 +463,51 @@ pub fn norm_abs_path(
     Ok((path.clone(), path_suffix, source_relative))
 }
 
+/// Absolute path on a remote storage (FTP, SFTP, WebDAV)
+///
+/// The input can be a full url path, an absolute remote path, or a path relative to root.
+/// Like [`norm_abs_path`] it is not possible to break out from root,
+/// a path which resolves outside of it returns an error.
+pub fn remote_abs_path(
+    root: &Path,
+    original_root: &Path,
+    input_path: &str,
+) -> Result<String, ServiceError> {
+    let original_root = original_root.to_string_lossy();
+    let root = root.to_string_lossy();
+    let root = root.trim_end_matches('/');
+
+    let relative = match input_path.strip_prefix(&*original_root) {
+        Some(p) => p,
+        None => match input_path.strip_prefix(root) {
+            Some(p) if p.is_empty() || p.starts_with('/') => p,
+            _ => input_path,
+        },
+    };
+
+    let mut components = vec![];
+
+    for component in relative.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                if components.pop().is_none() {
+                    return Err(ServiceError::Forbidden(format!(
+                        "Path '{input_path}' is outside of the storage"
+                    )));
+                }
+            }
+            c => components.push(c),
+        }
+    }
+
+    if components.is_empty() {
+        return Ok(root.to_string());
+    }
+
+    Ok(format!("{root}/{}", components.join("/")))
+}
+
 pub fn resolve_path(
     storage: &Path,
     path: &str,
@@ -4,7 +4,7 @@ use std::{
     io::{Error, ErrorKind},
     path::{Path, PathBuf},
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc,
     },
     time::Duration,
@@ -19,10 +19,17 @@ use log::*;
 
 use rand::{distr::Alphanumeric, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
 use regex::Regex;
+use reqwest::Url;
 use tokio::{sync::Mutex, task::JoinHandle};
 
-use crate::file::{utils::media_map::SharedMediaMap, MoveObject, PathObject, Storage, VideoFile};
-use crate::player::utils::{include_file_extension, probe::MediaProbe, Media};
+use crate::file::{
+    utils::{login, media_map::SharedMediaMap},
+    MoveObject, PathObject, Storage, VideoFile,
+};
+use crate::player::{
+    input::folder::{insert_media, remove_media},
+    utils::{include_file_extension, probe::MediaProbe, Media},
+};
 use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
 
 use aws_config::Region;
@@ -53,6 +60,12 @@ impl S3Storage {
             .unwrap_or_else(|e| panic!("Invalid S3 schema!: {}", e));
         let credentials_cloned = credentials.clone();
         let endpoint_url_cloned = endpoint_url.clone();
+
+        // presigned urls are path style, so they are below endpoint and bucket
+        if let Ok(url) = Url::parse(&format!("{}/{bucket}", endpoint_url.trim_end_matches('/'))) {
+            login::register(&url);
+        }
+
         Self {
             root: PathBuf::new(),
             original_root: root,
@@ -92,6 +105,7 @@ impl S3Storage {
         config: PlayoutConfig,
         is_alive: Arc<AtomicBool>,
         sources: Arc<Mutex<Vec<Media>>>,
+        current_index: Arc<AtomicUsize>,
     ) {
         let id = config.general.channel_id;
         let mut previous_keys = HashSet::new();
@@ -121,9 +135,8 @@ impl S3Storage {
                         if include_file_extension(&config, Path::new(key)) {
                             let fetched_path =
                                 &self.fetch_file_path(key).await.unwrap_or(key.to_string());
-                            let media =
-                                Media::new(id.try_into().unwrap(), fetched_path, false).await;
-                            sources.lock().await.push(media);
+                            let media = Media::new(0, fetched_path, false).await;
+                            insert_media(&config, &sources, &current_index, media).await;
                             info!(target: Target::file_mail(), channel = id;
                                 "Added S3 object: <b><magenta>{}</></b>",
                                 &key
@@ -134,7 +147,7 @@ impl S3Storage {
                     // Detect removed objects
                     let removed = previous_keys.difference(&current_keys);
                     for key in removed {
-                        sources.lock().await.retain(|x| x.source != *key);
+                        remove_media(&sources, &current_index, key).await;
                         info!(target: Target::file_mail(), channel = id;
                             "Removed S3 object: <b><magenta>{}</></b>",
                             key
@@ -683,10 +696,13 @@ impl Storage for S3Storage {
         config: PlayoutConfig,
         is_alive: Arc<AtomicBool>,
         sources: Arc<Mutex<Vec<Media>>>,
+        current_index: Arc<AtomicUsize>,
     ) {
         let s3_storage = self.clone();
         let task = tokio::spawn(async move {
-            s3_storage.watch_s3(config, is_alive, sources).await;
+            s3_storage
+                .watch_s3(config, is_alive, sources, current_index)
+                .await;
         });
 
         *self.watch_handler.lock().await = Some(task);
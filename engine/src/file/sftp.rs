@@ -0,0 +1,771 @@
+/*
+SFTP storage.
+
+The storage runs the OpenSSH `sftp` client in batch mode, one shared connection per host. The
+login works with keys only, like `sftp://user@host:22/media`: the service user needs the key and
+the host in its `known_hosts`, a password in the url is not used.
+
+Clips get `sftp://` urls. ffmpeg reads them with libssh, when it is built with it, otherwise set
+the cache path of the storage, so the clips play from the local cache.
+*/
+
+use std::{
+    collections::HashMap,
+    env, io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix_multipart::Multipart;
+use actix_web::{
+    http::header::{ContentDisposition, DispositionType},
+    web, HttpRequest, HttpResponse,
+};
+use futures_util::TryStreamExt as _;
+use lexical_sort::{natural_lexical_cmp, PathSort};
+use log::*;
+use rand::{distr::Alphanumeric, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use reqwest::Url;
+use tokio::{fs, io::AsyncWriteExt, process::Command, sync::Mutex, task::JoinHandle};
+
+use crate::file::{
+    ftp::{decoded_path, parse_list_line, Changes, FtpEntry},
+    remote_abs_path,
+    utils::{login, media_map::SharedMediaMap},
+    MoveObject, PathObject, Storage, VideoFile,
+};
+use crate::player::{
+    input::folder::{insert_media, remove_media},
+    utils::{file_extension, include_file_extension, probe::MediaProbe, Media},
+};
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
+
+pub const SFTP_INDICATOR: &str = "sftp://";
+
+/// Quote a path for a batch command.
+fn quote(path: &str) -> String {
+    format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Check a batch command, before it goes to sftp.
+///
+/// A control character in a path would start a new command
+/// and a leading `!` runs a local shell command.
+pub fn valid_batch_command(command: &str) -> bool {
+    !command.chars().any(char::is_control) && !command.trim_start().starts_with('!')
+}
+
+/// Check host and user of the url, before they go to sftp as arguments.
+///
+/// A leading `-` would be read as an ssh option, like `-oProxyCommand=...`.
+pub fn valid_target(url: &Url) -> bool {
+    let host = url.host_str().unwrap_or_default();
+
+    !host.is_empty() && !host.starts_with('-') && !url.username().starts_with('-')
+}
+
+/// Run batch commands over sftp, returns the output without the echoed commands.
+///
+/// The commands stop at the first error, a command with a leading `-` can fail.
+async fn batch(url: &Url, commands: &[String]) -> io::Result<String> {
+    if let Some(command) = commands.iter().find(|c| !valid_batch_command(c)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid sftp command: {}", command.escape_debug()),
+        ));
+    }
+
+    if !valid_target(url) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid sftp host: {url}"),
+        ));
+    }
+
+    let host = url.host_str().unwrap_or_default();
+    let target = if url.username().is_empty() {
+        host.to_string()
+    } else {
+        format!("{}@{host}", url.username())
+    };
+    let control_path = env::temp_dir().join("ffplayout-sftp-%C");
+
+    let mut child = Command::new("sftp")
+        .args(["-q", "-b", "-", "-P", &url.port().unwrap_or(22).to_string()])
+        .args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=10"])
+        .args(["-o", "ControlMaster=auto", "-o", "ControlPersist=60"])
+        .arg("-o")
+        .arg(format!("ControlPath={}", control_path.to_string_lossy()))
+        .arg("--")
+        .arg(target)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(commands.join("\n").as_bytes()).await?;
+        stdin.write_all(b"\n").await?;
+    }
+
+    let output = child.wait_with_output().await?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+
+        return Err(io::Error::other(format!("SFTP failed: {}", error.trim())));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.starts_with("sftp>"))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// Download file from SFTP url to the local target, with the login of its storage.
+pub async fn download(url: &str, target: &Path) -> io::Result<()> {
+    let url = login::resolve(url).unwrap_or_else(|| url.to_string());
+    let url = Url::parse(&url).map_err(io::Error::other)?;
+    let get = format!(
+        "get {} {}",
+        quote(&decoded_path(&url)),
+        quote(&target.to_string_lossy())
+    );
+
+    batch(&url, &[get]).await.map(|_| ())
+}
+
+#[derive(Clone)]
+pub struct SftpStorage {
+    pub root: PathBuf,
+    original_root: PathBuf,
+    pub extensions: Vec<String>,
+    url: Url,
+    pub watch_handler: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl std::fmt::Debug for SftpStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SftpStorage")
+            .field("root", &self.root)
+            .field("host", &self.url.host_str())
+            .field("extensions", &self.extensions)
+            .finish_non_exhaustive()
+    }
+}
+
+impl SftpStorage {
+    pub async fn new(root: PathBuf, extensions: Vec<String>) -> Result<Self, ServiceError> {
+        let url = Url::parse(&root.to_string_lossy())
+            .map_err(|e| ServiceError::BadRequest(format!("Invalid SFTP url: {e}")))?;
+        let path = decoded_path(&url).trim_end_matches('/').to_string();
+
+        if url.password().is_some() {
+            warn!("SFTP storage logs in with keys only, the password of the url is not used");
+        }
+
+        login::register(&url);
+
+        Ok(Self {
+            root: PathBuf::from(if path.is_empty() { "/" } else { &path }),
+            original_root: root,
+            extensions,
+            url,
+            watch_handler: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Convert storage path (full url, absolute remote path, or relative path) to absolute remote path.
+    fn remote_path(&self, path: &str) -> Result<String, ServiceError> {
+        remote_abs_path(&self.root, &self.original_root, path)
+    }
+
+    /// Path relative to storage root, like the S3 storage returns object keys.
+    fn relative_path(&self, remote: &str) -> String {
+        remote
+            .strip_prefix(&*self.root.to_string_lossy())
+            .unwrap_or(remote)
+            .trim_start_matches('/')
+            .to_string()
+    }
+
+    /// Url of the file without login, the connections resolve it from the storage.
+    fn file_url(&self, remote: &str) -> String {
+        let mut url = login::public(&self.url);
+        url.set_path(remote);
+
+        url.to_string()
+    }
+
+    async fn batch(&self, commands: &[String]) -> io::Result<String> {
+        batch(&self.url, commands).await
+    }
+
+    async fn list(&self, path: &str) -> io::Result<Vec<FtpEntry>> {
+        let output = self.batch(&[format!("ls -la {}", quote(path))]).await?;
+
+        let entries = output
+            .lines()
+            .filter_map(parse_list_line)
+            .map(|mut e| {
+                // sftp can list the names with the folder path
+                e.name = e.name.rsplit('/').next().unwrap_or_default().to_string();
+                e
+            })
+            .filter(|e| !e.name.is_empty() && e.name != "." && e.name != "..")
+            .collect();
+
+        Ok(entries)
+    }
+
+    async fn remote_is_dir(&self, path: &str) -> bool {
+        self.batch(&[format!("cd {}", quote(path))]).await.is_ok()
+    }
+
+    async fn remote_is_file(&self, path: &str) -> bool {
+        !self.remote_is_dir(path).await
+            && self.batch(&[format!("ls {}", quote(path))]).await.is_ok()
+    }
+
+    /// List all files recursive, with their modification signature.
+    async fn walk_files(&self, path: &str) -> io::Result<HashMap<String, String>> {
+        let mut files = HashMap::new();
+        let mut folders = vec![path.to_string()];
+
+        while let Some(folder) = folders.pop() {
+            for entry in self.list(&folder).await? {
+                let remote = format!("{}/{}", folder.trim_end_matches('/'), entry.name);
+
+                if entry.is_dir {
+                    folders.push(remote);
+                } else {
+                    files.insert(remote, format!("{}:{}", entry.size, entry.modified));
+                }
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Poll the SFTP root for changes and update the `sources` list accordingly.
+    ///
+    /// Like FTP, new, removed and modified files are detected by comparing the size
+    /// and modification time from the directory listing.
+    pub async fn watch_sftp(
+        &self,
+        config: PlayoutConfig,
+        is_alive: Arc<AtomicBool>,
+        sources: Arc<Mutex<Vec<Media>>>,
+        current_index: Arc<AtomicUsize>,
+    ) {
+        let id = config.general.channel_id;
+        let root = self.root.to_string_lossy().to_string();
+        let mut previous: Option<HashMap<String, String>> = None;
+
+        info!(target: Target::file_mail(), channel = id;
+            "Monitoring SFTP folder: <b><magenta>{}{root}</></b>",
+            self.url.host_str().unwrap_or_default()
+        );
+
+        while is_alive.load(Ordering::SeqCst) {
+            match self.walk_files(&root).await {
+                Ok(current) => {
+                    if let Some(previous) = &previous {
+                        let changes = Changes::new(previous, &current);
+                        let updates = changes
+                            .added
+                            .iter()
+                            .map(|p| (p, false))
+                            .chain(changes.modified.iter().map(|p| (p, true)));
+
+                        for (path, modified) in updates {
+                            if !include_file_extension(&config, Path::new(path))
+                                || config.storage.is_excluded(&self.root, Path::new(path))
+                            {
+                                continue;
+                            }
+
+                            let url = self.file_url(path);
+
+                            if modified {
+                                remove_media(&sources, &current_index, &url).await;
+                                info!(target: Target::file_mail(), channel = id; "Modified SFTP file: <b><magenta>{path}</></b>");
+                            } else {
+                                info!(target: Target::file_mail(), channel = id; "Added SFTP file: <b><magenta>{path}</></b>");
+                            }
+
+                            let mut media = Media::new(0, &url, false).await;
+                            media.key =
+                                self.sanitized_file_path(&format!("/{}", self.relative_path(path)));
+
+                            insert_media(&config, &sources, &current_index, media).await;
+                        }
+
+                        for path in &changes.removed {
+                            if remove_media(&sources, &current_index, &self.file_url(path)).await {
+                                info!(target: Target::file_mail(), channel = id; "Removed SFTP file: <b><magenta>{path}</></b>");
+                            }
+                        }
+                    }
+
+                    previous = Some(current);
+                }
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "Error listing SFTP folder: {e}");
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        }
+    }
+
+    async fn remove_recursive(&self, path: &str) -> io::Result<()> {
+        let mut folders = vec![path.to_string()];
+        let mut remove_folders = vec![];
+        let mut commands = vec![];
+
+        while let Some(folder) = folders.pop() {
+            for entry in self.list(&folder).await? {
+                let remote = format!("{}/{}", folder.trim_end_matches('/'), entry.name);
+
+                if entry.is_dir {
+                    folders.push(remote);
+                } else {
+                    commands.push(format!("rm {}", quote(&remote)));
+                }
+            }
+
+            remove_folders.push(folder);
+        }
+
+        for folder in remove_folders.iter().rev() {
+            commands.push(format!("rmdir {}", quote(folder)));
+        }
+
+        self.batch(&commands).await.map(|_| ())
+    }
+}
+
+impl Storage for SftpStorage {
+    /// Generate proper prefix key for objects' paths.
+    fn path_prefix_generator(&self) -> String {
+        format!("[SFTP:]/{}", self.url.host_str().unwrap_or_default())
+    }
+
+    /// Returns a sanitized file path by stripping the root and prefixing with "[SFTP:]/{host}".
+    fn sanitized_file_path(&self, path: &str) -> String {
+        let remote = self
+            .remote_path(path)
+            .unwrap_or_else(|_| self.root.to_string_lossy().to_string());
+        let staged_path = remote
+            .strip_prefix(&*self.root.to_string_lossy())
+            .unwrap_or(&remote);
+
+        format!(
+            "{}/{}",
+            self.path_prefix_generator(),
+            staged_path.trim_start_matches('/')
+        )
+    }
+
+    fn interpreted_file_path(&self, path: &str) -> String {
+        let path_prefix = &self.path_prefix_generator();
+        let cleaned_path = path.strip_prefix(path_prefix).unwrap_or(path);
+
+        let remote = self
+            .remote_path(cleaned_path)
+            .unwrap_or_else(|_| self.root.to_string_lossy().to_string());
+
+        self.file_url(&remote)
+    }
+
+    fn echo_log(&self) {
+        info!(
+            "<blue>SFTP Storage initialized at '{}{}'",
+            self.url.host_str().unwrap_or_default(),
+            self.root.to_string_lossy()
+        );
+    }
+
+    async fn fetch_file_path(&self, file_path: &str) -> Result<String, ServiceError> {
+        Ok(self.file_url(&self.remote_path(file_path)?))
+    }
+
+    async fn browser(
+        &self,
+        path_obj: &PathObject,
+        dur_data: web::Data<SharedMediaMap>,
+    ) -> Result<PathObject, ServiceError> {
+        let media_duration = dur_data;
+        let remote = self.remote_path(&path_obj.source)?;
+        let host = self.url.host_str().unwrap_or_default().to_string();
+        let mut obj = PathObject::new(self.relative_path(&remote), Some(host));
+        obj.folders_only = path_obj.folders_only;
+
+        if remote != self.root.to_string_lossy() && !path_obj.folders_only {
+            let parent = Path::new(&remote)
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let mut parent_folders: Vec<String> = self
+                .list(&parent)
+                .await?
+                .into_iter()
+                .filter(|e| e.is_dir)
+                .map(|e| e.name)
+                .collect();
+
+            parent_folders.path_sort(natural_lexical_cmp);
+            obj.parent_folders = Some(parent_folders);
+        }
+
+        let mut folders = vec![];
+        let mut files = vec![];
+
+        for entry in self.list(&remote).await? {
+            // ignore hidden files/folders
+            if entry.name.starts_with('.') {
+                continue;
+            }
+
+            if entry.is_dir {
+                folders.push(entry.name);
+            } else if !path_obj.folders_only {
+                if let Some(ext) = file_extension(Path::new(&entry.name)) {
+                    if self.extensions.contains(&ext.to_lowercase()) {
+                        files.push(entry.name);
+                    }
+                }
+            }
+        }
+
+        folders.path_sort(natural_lexical_cmp);
+        files.path_sort(natural_lexical_cmp);
+
+        let mut media_files = vec![];
+
+        for name in files {
+            let file = format!("{}/{name}", remote.trim_end_matches('/'));
+
+            if let Some(duration) = media_duration.get_obj(&file).await {
+                media_files.push(VideoFile { name, duration });
+            } else {
+                match MediaProbe::new(&self.file_url(&file)).await {
+                    Ok(probe) => {
+                        let duration = probe.format.duration.unwrap_or_default();
+                        media_duration.add_obj(file, duration).await?;
+                        media_files.push(VideoFile { name, duration });
+                    }
+                    Err(e) => error!("{e:?}"),
+                };
+            }
+        }
+
+        obj.folders = Some(folders);
+        obj.files = Some(media_files);
+
+        Ok(obj)
+    }
+
+    async fn mkdir(&self, path_obj: &PathObject) -> Result<(), ServiceError> {
+        let remote = self.remote_path(&path_obj.source)?;
+
+        self.batch(&[format!("mkdir {}", quote(&remote))])
+            .await
+            .map_err(|e| ServiceError::BadRequest(e.to_string()))?;
+
+        info!("create folder: <b><magenta>{remote}</></b>");
+
+        Ok(())
+    }
+
+    async fn rename(
+        &self,
+        move_object: &MoveObject,
+        duration: web::Data<SharedMediaMap>,
+    ) -> Result<MoveObject, ServiceError> {
+        let source = self.remote_path(&move_object.source)?;
+        let mut target = self.remote_path(&move_object.target)?;
+
+        if !self.remote_is_dir(&source).await && !self.remote_is_file(&source).await {
+            return Err(ServiceError::BadRequest("Source file not exist!".into()));
+        }
+
+        if self.remote_is_dir(&target).await {
+            let name = Path::new(&source).file_name().unwrap_or_default();
+            target = format!("{target}/{}", name.to_string_lossy());
+        }
+
+        if self.remote_is_file(&target).await {
+            return Err(ServiceError::BadRequest(
+                "Target file already exists!".into(),
+            ));
+        }
+
+        self.batch(&[format!("rename {} {}", quote(&source), quote(&target))])
+            .await?;
+        duration.update_obj(&source, &target).await?;
+
+        Ok(MoveObject {
+            source: source.rsplit('/').next().unwrap_or_default().to_string(),
+            target: target.rsplit('/').next().unwrap_or_default().to_string(),
+        })
+    }
+
+    async fn remove(
+        &self,
+        source_path: &str,
+        duration: web::Data<SharedMediaMap>,
+        recursive: bool,
+    ) -> Result<(), ServiceError> {
+        let source = self.remote_path(source_path)?;
+
+        if !self.remote_is_dir(&source).await {
+            if !self.remote_is_file(&source).await {
+                return Err(ServiceError::BadRequest("Source does not exists!".into()));
+            }
+
+            self.batch(&[format!("rm {}", quote(&source))])
+                .await
+                .map_err(|_| ServiceError::BadRequest("Delete file failed!".into()))?;
+            duration.remove_obj(&source).await?;
+
+            return Ok(());
+        }
+
+        let result = if recursive {
+            self.remove_recursive(&source).await
+        } else {
+            self.batch(&[format!("rmdir {}", quote(&source))])
+                .await
+                .map(|_| ())
+        };
+
+        result.map_err(|e| {
+            error!("{e}");
+            ServiceError::BadRequest("Delete folder failed! (Folder must be empty)".into())
+        })
+    }
+
+    async fn upload(
+        &self,
+        mut data: Multipart,
+        path: &Path,
+        _is_abs: bool,
+    ) -> Result<(), ServiceError> {
+        let folder = self.remote_path(&path.to_string_lossy())?;
+
+        while let Some(mut field) = data.try_next().await? {
+            let content_disposition = field.content_disposition().ok_or("No content")?;
+            debug!("{content_disposition}");
+            let rand_string: String = rand::rng()
+                .sample_iter(&Alphanumeric)
+                .take(20)
+                .map(char::from)
+                .collect();
+            let filename = content_disposition
+                .get_filename()
+                .map_or_else(|| rand_string.to_string(), sanitize_filename::sanitize);
+            let filepath = format!("{}/{filename}", folder.trim_end_matches('/'));
+
+            if self.remote_is_file(&filepath).await {
+                return Err(ServiceError::Conflict("Target already exists!".into()));
+            }
+
+            // sftp uploads from a file, the upload goes to a temporary file first
+            let local = env::temp_dir().join(format!("ffplayout_upload_{rand_string}"));
+            let mut file = fs::File::create(&local).await?;
+
+            loop {
+                match field.try_next().await {
+                    Ok(Some(chunk)) => file.write_all(&chunk).await?,
+                    Ok(None) => break,
+                    Err(e) => {
+                        drop(file);
+                        fs::remove_file(&local).await?;
+
+                        return Err(e.into());
+                    }
+                }
+            }
+
+            file.flush().await?;
+            drop(file);
+
+            let put = format!(
+                "put {} {}",
+                quote(&local.to_string_lossy()),
+                quote(&filepath)
+            );
+            let result = self.batch(&[put]).await;
+
+            fs::remove_file(&local).await?;
+            result?;
+        }
+
+        Ok(())
+    }
+
+    async fn watchman(
+        &mut self,
+        config: PlayoutConfig,
+        is_alive: Arc<AtomicBool>,
+        sources: Arc<Mutex<Vec<Media>>>,
+        current_index: Arc<AtomicUsize>,
+    ) {
+        let sftp_storage = self.clone();
+        let task = tokio::spawn(async move {
+            sftp_storage
+                .watch_sftp(config, is_alive, sources, current_index)
+                .await;
+        });
+
+        *self.watch_handler.lock().await = Some(task);
+    }
+
+    async fn stop_watch(&mut self) {
+        let mut watch_handler = self.watch_handler.lock().await;
+
+        if let Some(handler) = watch_handler.as_mut() {
+            handler.abort();
+        }
+    }
+
+    async fn fill_filler_list(
+        &mut self,
+        config: &PlayoutConfig,
+        fillers: Option<Arc<Mutex<Vec<Media>>>>,
+    ) -> Vec<Media> {
+        let id = config.general.channel_id;
+        let filler_path = match self.remote_path(&config.storage.filler_path.to_string_lossy()) {
+            Ok(path) => path,
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "{e}");
+                return vec![];
+            }
+        };
+        let mut filler_list = vec![];
+
+        let paths = if self.remote_is_dir(&filler_path).await {
+            match self.walk_files(&filler_path).await {
+                Ok(files) => files.into_keys().collect(),
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "{e}");
+                    vec![]
+                }
+            }
+        } else {
+            vec![filler_path]
+        };
+
+        for (index, path) in paths.iter().enumerate() {
+            if !include_file_extension(config, Path::new(path)) {
+                continue;
+            }
+
+            let mut media = Media::new(index, &self.file_url(path), false).await;
+
+            if fillers.is_none() {
+                if let Err(e) = media.add_probe(false).await {
+                    error!(target: Target::file_mail(), channel = id; "{e:?}");
+                };
+            }
+
+            filler_list.push(media);
+        }
+
+        if config.storage.shuffle {
+            let mut rng = StdRng::from_os_rng();
+            filler_list.shuffle(&mut rng);
+        } else {
+            filler_list.sort_by(|d1, d2| natural_lexical_cmp(&d1.source, &d2.source));
+        }
+
+        for (index, item) in filler_list.iter_mut().enumerate() {
+            item.index = Some(index);
+        }
+
+        if let Some(f) = fillers.as_ref() {
+            f.lock().await.clone_from(&filler_list);
+        }
+
+        filler_list
+    }
+
+    async fn copy_assets(&self) -> Result<(), std::io::Error> {
+        Ok(())
+    }
+
+    async fn is_dir<P: AsRef<Path>>(&self, input: P) -> bool {
+        let Ok(remote) = self.remote_path(&input.as_ref().to_string_lossy()) else {
+            return false;
+        };
+
+        self.remote_is_dir(&remote).await
+    }
+
+    async fn is_file<P: AsRef<Path>>(&self, input: P) -> bool {
+        let Ok(remote) = self.remote_path(&input.as_ref().to_string_lossy()) else {
+            return false;
+        };
+
+        self.remote_is_file(&remote).await
+    }
+
+    /// Collects all paths under a SFTP folder, relative to the storage root.
+    async fn walk_dir<P: AsRef<Path>>(&self, input: P) -> Result<Vec<PathBuf>, ServiceError> {
+        let remote = self.remote_path(&input.as_ref().to_string_lossy())?;
+        let mut contents = vec![];
+        let mut folders = vec![remote];
+
+        while let Some(folder) = folders.pop() {
+            for entry in self.list(&folder).await? {
+                let path = format!("{}/{}", folder.trim_end_matches('/'), entry.name);
+
+                contents.push(PathBuf::from(self.relative_path(&path)));
+
+                if entry.is_dir {
+                    folders.push(path);
+                }
+            }
+        }
+
+        Ok(contents)
+    }
+
+    /// Download file from SFTP server to a temporary file and send it.
+    async fn open_media(
+        &self,
+        req: &HttpRequest,
+        file_path: &str,
+    ) -> Result<HttpResponse, ServiceError> {
+        let remote = self.remote_path(file_path)?;
+        let local = env::temp_dir().join(format!("ffplayout_media_{}", uuid::Uuid::new_v4()));
+
+        self.batch(&[format!(
+            "get {} {}",
+            quote(&remote),
+            quote(&local.to_string_lossy())
+        )])
+        .await?;
+
+        let file = actix_files::NamedFile::open(&local);
+
+        // the open file stays readable, after the path is removed
+        fs::remove_file(&local).await?;
+
+        Ok(file?
+            .set_content_disposition(ContentDisposition {
+                disposition: DispositionType::Attachment,
+                parameters: vec![],
+            })
+            .into_response(req))
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::HashSet,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Arc, LazyLock},
+};
+
+use log::*;
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+
+use crate::file::{
+    ftp::{self, FTP_INDICATOR},
+    sftp::{self, SFTP_INDICATOR},
+    utils::login,
+};
+use crate::player::{controller::ChannelManager, utils::Media};
+use crate::utils::{config::PlayoutConfig, logging::Target};
+
+/// Cache files which are currently downloading.
+static IN_PROGRESS: LazyLock<Mutex<HashSet<PathBuf>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Local cache for media from remote storage.
+///
+/// The current clip and the next `storage.cache_size` clips are downloaded to a subfolder
+/// `<channel id>` of `storage.cache_path`, so that playback not depends on the response time
+/// of the object storage, (S)FTP or WebDAV server. Cached files which are not needed anymore
+/// are removed, other files in the folder are not touched.
+///
+/// Clones share one lock, prefetches run one after the other, so that the cleanup of an older
+/// prefetch not removes the files of a newer one.
+#[derive(Clone, Debug)]
+pub struct MediaCache {
+    id: i32,
+    path: PathBuf,
+    size: usize,
+    lock: Arc<Mutex<()>>,
+}
+
+impl MediaCache {
+    pub fn new(config: &PlayoutConfig) -> Option<Self> {
+        if config.storage.cache_path.as_os_str().is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            id: config.general.channel_id,
+            path: config
+                .storage
+                .cache_path
+                .join(config.general.channel_id.to_string()),
+            size: config.storage.cache_size,
+            lock: Arc::new(Mutex::new(())),
+        })
+    }
+
+    /// Only urls from a configured storage get downloaded, other remote sources play as they are.
+    fn is_cacheable(source: &str) -> bool {
+        let lower = source.to_lowercase();
+
+        (lower.starts_with("http://")
+            || lower.starts_with("https://")
+            || lower.starts_with(FTP_INDICATOR)
+            || lower.starts_with(SFTP_INDICATOR))
+            && login::is_storage_source(source)
+    }
+
+    /// Folder of the channel cache.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Build cache file name from media key, presigned urls changes, the key not.
+    pub fn cache_file(&self, node: &Media) -> PathBuf {
+        let name = if node.key.is_empty() {
+            node.source.split('?').next().unwrap_or_default()
+        } else {
+            &node.key
+        };
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+
+        let mut file = format!("{:016x}", hasher.finish());
+
+        if let Some(ext) = Path::new(name).extension() {
+            file.push('.');
+            file.push_str(&ext.to_string_lossy());
+        }
+
+        self.path.join(file)
+    }
+
+    /// Prefetch the current and upcoming clips, and play the current one from cache, when possible.
+    pub async fn process(&self, manager: &ChannelManager, node: &mut Media) {
+        let index = manager.current_index.load(Ordering::SeqCst);
+        let mut upcoming = vec![node.clone()];
+        upcoming.extend(
+            manager
+                .current_list
+                .lock()
+                .await
+                .iter()
+                .skip(index)
+                .take(self.size)
+                .cloned(),
+        );
+
+        self.prefetch(upcoming);
+        self.apply(node).await;
+    }
+
+    /// Replace the remote source with the cached file, when it exists.
+    pub async fn apply(&self, node: &mut Media) {
+        if !Self::is_cacheable(&node.source) {
+            return;
+        }
+
+        let cache_file = self.cache_file(node);
+
+        if fs::metadata(&cache_file).await.is_ok_and(|m| m.is_file()) {
+            let local = cache_file.to_string_lossy().to_string();
+
+            if let Some(cmd) = node.cmd.as_mut() {
+                for arg in cmd.iter_mut().filter(|a| **a == node.source) {
+                    arg.clone_from(&local);
+                }
+            }
+
+            debug!(target: Target::file_mail(), channel = self.id; "Play from cache: <b><magenta>{local}</></b>");
+
+            node.source = local;
+        }
+    }
+
+    /// Download the given nodes in background and remove all other files from cache.
+    pub fn prefetch(&self, nodes: Vec<Media>) {
+        let cache = self.clone();
+
+        tokio::spawn(async move { cache.fetch(&nodes).await });
+    }
+
+    /// Download the given nodes, when they are not cached yet, and remove all other cached files.
+    pub async fn fetch(&self, nodes: &[Media]) {
+        let _lock = self.lock.lock().await;
+
+        if let Err(e) = fs::create_dir_all(&self.path).await {
+            error!(target: Target::file_mail(), channel = self.id; "Create cache folder failed: {e}");
+            return;
+        }
+
+        let mut keep = HashSet::new();
+
+        for node in nodes.iter().filter(|n| Self::is_cacheable(&n.source)) {
+            let cache_file = self.cache_file(node);
+            keep.insert(cache_file.clone());
+
+            if cache_file.is_file() || !IN_PROGRESS.lock().await.insert(cache_file.clone()) {
+                continue;
+            }
+
+            if let Err(e) = self.download(&node.source, &cache_file).await {
+                error!(target: Target::file_mail(), channel = self.id;
+                    "Caching <b><magenta>{}</></b> failed: {e}", node.source
+                );
+            }
+
+            IN_PROGRESS.lock().await.remove(&cache_file);
+        }
+
+        self.cleanup(&keep).await;
+    }
+
+    /// File names of the cache are a 16 digit hash, with the extension of the source,
+    /// or `.part` while downloading.
+    fn is_cache_file(path: &Path) -> bool {
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .is_some_and(|s| s.len() == 16 && s.bytes().all(|b| b.is_ascii_hexdigit()))
+    }
+
+    async fn download(&self, url: &str, target: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let part = target.with_extension("part");
+        let mut file = fs::File::create(&part).await?;
+
+        if url.to_lowercase().starts_with(FTP_INDICATOR) {
+            ftp::download(url, &mut file).await?;
+        } else if url.to_lowercase().starts_with(SFTP_INDICATOR) {
+            sftp::download(url, &part).await?;
+        } else {
+            // WebDAV clips need the login of their storage
+            let url = login::resolve(url).unwrap_or_else(|| url.to_string());
+            let mut response = reqwest::get(url).await?.error_for_status()?;
+
+            while let Some(chunk) = response.chunk().await? {
+                file.write_all(&chunk).await?;
+            }
+        }
+
+        file.flush().await?;
+        fs::rename(&part, target).await?;
+
+        debug!(target: Target::file_mail(), channel = self.id; "Cached <b><magenta>{target:?}</></b>");
+
+        Ok(())
+    }
+
+    async fn cleanup(&self, keep: &HashSet<PathBuf>) {
+        let Ok(mut entries) = fs::read_dir(&self.path).await else {
+            return;
+        };
+        let in_progress = IN_PROGRESS.lock().await.clone();
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+
+            if !entry.file_type().await.is_ok_and(|t| t.is_file())
+                || !Self::is_cache_file(&path)
+                || keep.contains(&path)
+                || in_progress
+                    .iter()
+                    .any(|p| *p == path || p.with_extension("part") == path)
+            {
+                continue;
+            }
+
+            if let Err(e) = fs::remove_file(&path).await {
+                warn!(target: Target::file_mail(), channel = self.id; "Remove cached file failed: {e}");
+            }
+        }
+    }
+}
@@ -9,11 +9,14 @@ use log::*;
 use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 use tokio::sync::Mutex;
 
-use crate::player::utils::{include_file_extension, Media};
+use crate::player::utils::{include_file_extension, is_close, Media};
 use crate::utils::{config::PlayoutConfig, logging::Target};
 
 use super::ABS_PATH_INDICATOR;
 
+/// Clips shorter than this are not taken, the playout skips them.
+const MIN_LENGTH: f64 = 1.0;
+
 pub async fn absolute_fill_filler_list(
     config: &PlayoutConfig,
     fillers: Option<Arc<Mutex<Vec<Media>>>>,
@@ -86,3 +89,59 @@ pub async fn absolute_fill_filler_list(
 
     filler_list
 }
+
+/// Probe the fillers without probe, the selection needs their durations.
+pub async fn add_probes(fillers: &mut [Media], channel_id: i32) {
+    for filler in fillers.iter_mut().filter(|f| f.probe.is_none()) {
+        if let Err(e) = filler.add_probe(false).await {
+            error!(target: Target::file_mail(), channel = channel_id; "{e:?}");
+        }
+    }
+}
+
+/// Filler clips, whose durations fill `length` best, as index and length to play.
+///
+/// The longest clip, which fits into the remaining time, comes first and every clip plays once,
+/// before one repeats. So whole clips fill the time and only the last one can get a cut.
+/// With `trim_last`, the rest gets the clip, which needs the smallest cut, otherwise a rest
+/// shorter than the remaining clips stays open.
+pub fn select(durations: &[f64], length: f64, trim_last: bool) -> Vec<(usize, f64)> {
+    let mut order = (0..durations.len())
+        .filter(|i| durations[*i] >= MIN_LENGTH)
+        .collect::<Vec<_>>();
+    let mut used = vec![false; durations.len()];
+    let mut selected = vec![];
+    let mut remaining = length;
+
+    // stable sort, clips with the same length keep the order of the list
+    order.sort_by(|a, b| durations[*b].total_cmp(&durations[*a]));
+
+    while remaining >= MIN_LENGTH {
+        let fits = |i: &&usize| durations[**i] <= remaining;
+        let Some(&i) = order
+            .iter()
+            .filter(fits)
+            .find(|i| !used[**i])
+            .or_else(|| order.iter().find(fits))
+        else {
+            break;
+        };
+
+        if used[i] {
+            // all fitting clips played, start a new round
+            used.fill(false);
+        }
+
+        used[i] = true;
+        selected.push((i, durations[i]));
+        remaining -= durations[i];
+    }
+
+    if trim_last && !is_close(remaining, 0.0, 0.001) {
+        if let Some(&i) = order.iter().rev().find(|i| durations[**i] > remaining) {
+            selected.push((i, remaining));
+        }
+    }
+
+    selected
+}
@@ -0,0 +1,94 @@
+/*
+Logins of remote storages.
+
+Clips from FTP and SFTP storages have urls without user info, so the login does not end up in
+logs, the history or the API. Processes which read the clips, like ffmpeg and ffprobe, get the
+login from the storage config of the channel, when they start.
+
+The roots of all remote storages are registered here, also the ones without login, so that only
+urls from a configured storage get downloaded to the media cache.
+*/
+
+use std::{
+    path::Path,
+    sync::{LazyLock, RwLock},
+};
+
+use reqwest::Url;
+
+/// Storage urls, with their login, registered by the storage backends.
+static STORAGES: LazyLock<RwLock<Vec<Url>>> = LazyLock::new(|| RwLock::new(vec![]));
+
+fn has_login(url: &Url) -> bool {
+    !url.username().is_empty() || url.password().is_some()
+}
+
+/// Remember a storage url, a newer login for the same root replaces the old one.
+pub fn register(storage: &Url) {
+    let mut storages = STORAGES.write().unwrap();
+
+    if !has_login(storage) && storages.iter().any(|s| public(s) == *storage) {
+        return;
+    }
+
+    storages.retain(|s| public(s) != public(storage));
+    storages.push(storage.clone());
+}
+
+/// Url without user and password.
+pub fn public(url: &Url) -> Url {
+    let mut url = url.clone();
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+
+    url
+}
+
+/// Check if the url is below the root of the storage.
+fn contains(storage: &Url, url: &Url) -> bool {
+    storage.scheme() == url.scheme()
+        && storage.host_str() == url.host_str()
+        && storage.port_or_known_default() == url.port_or_known_default()
+        && Path::new(url.path()).starts_with(storage.path())
+}
+
+/// Check if the source is an url from a registered storage.
+pub fn is_storage_source(source: &str) -> bool {
+    let Ok(url) = Url::parse(source) else {
+        return false;
+    };
+
+    STORAGES.read().unwrap().iter().any(|s| contains(s, &url))
+}
+
+/// Url with the login of the storage, which contains the source.
+///
+/// `None` when the source is no url or there is no login for it. When more storages match,
+/// the one with the longest root wins.
+pub fn resolve(source: &str) -> Option<String> {
+    let mut url = Url::parse(source).ok()?;
+
+    if !url.username().is_empty() {
+        return None;
+    }
+
+    let storages = STORAGES.read().unwrap();
+    let login = storages
+        .iter()
+        .filter(|s| has_login(s) && contains(s, &url))
+        .max_by_key(|s| s.path().len())?;
+
+    let _ = url.set_username(login.username());
+    let _ = url.set_password(login.password());
+
+    Some(url.to_string())
+}
+
+/// Process arguments with the login for remote storage urls.
+///
+/// The arguments must not be logged anymore.
+pub fn args(args: Vec<String>) -> Vec<String> {
+    args.into_iter()
+        .map(|arg| resolve(&arg).unwrap_or(arg))
+        .collect()
+}
@@ -1,4 +1,6 @@
+pub mod cache;
 pub mod filler;
+pub mod login;
 pub mod media_map;
 pub mod watcher;
 
@@ -1,7 +1,7 @@
 use std::{
     path::Path,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         mpsc::channel,
         Arc,
     },
@@ -17,16 +17,21 @@ use notify::{
 use notify_debouncer_full::new_debouncer;
 use tokio::sync::Mutex;
 
-use crate::player::utils::{include_file_extension, Media};
+use crate::player::{
+    input::folder::{insert_media, remove_media},
+    utils::{include_file_extension, Media},
+};
 use crate::utils::{config::PlayoutConfig, logging::Target};
 
 /// Create a watcher, which monitor file changes.
 /// When a change is register, update the current file list.
 /// This makes it possible, to play infinitely and and always new files to it.
+/// The current playback index is kept in sync, so changes never skip or repeat a clip.
 pub async fn watch(
     config: PlayoutConfig,
     is_alive: Arc<AtomicBool>,
     sources: Arc<Mutex<Vec<Media>>>,
+    current_index: Arc<AtomicUsize>,
 ) {
     let id = config.general.channel_id;
     let path = Path::new(&config.channel.storage);
@@ -54,12 +59,14 @@ pub async fn watch(
                             Create(CreateKind::File) | Modify(ModifyKind::Name(RenameMode::To)) => {
                                 let new_path = &event.paths[0];
 
-                                if new_path.is_file() && include_file_extension(&config, new_path) {
-                                    let index = sources.lock().await.len();
+                                if new_path.is_file()
+                                    && include_file_extension(&config, new_path)
+                                    && !config.storage.is_excluded(path, new_path)
+                                {
                                     let media =
-                                        Media::new(index, &new_path.to_string_lossy(), false).await;
+                                        Media::new(0, &new_path.to_string_lossy(), false).await;
 
-                                    sources.lock().await.push(media);
+                                    insert_media(&config, &sources, &current_index, media).await;
                                     info!(target: Target::file_mail(), channel = id; "Create new file: <b><magenta>{new_path:?}</></b>");
                                 }
                             }
@@ -67,12 +74,15 @@ pub async fn watch(
                             | Modify(ModifyKind::Name(RenameMode::From)) => {
                                 let old_path = &event.paths[0];
 
-                                if !old_path.is_file() && include_file_extension(&config, old_path)
+                                if !old_path.is_file()
+                                    && include_file_extension(&config, old_path)
+                                    && remove_media(
+                                        &sources,
+                                        &current_index,
+                                        &old_path.to_string_lossy(),
+                                    )
+                                    .await
                                 {
-                                    sources
-                                        .lock()
-                                        .await
-                                        .retain(|x| x.source != old_path.to_string_lossy());
                                     info!(target: Target::file_mail(), channel = id; "Remove file: <b><magenta>{old_path:?}</></b>");
                                 }
                             }
@@ -80,22 +90,24 @@ pub async fn watch(
                                 let old_path = &event.paths[0];
                                 let new_path = &event.paths[1];
 
-                                let mut media_list = sources.lock().await;
-
-                                if let Some(index) = media_list
+                                let position = sources
+                                    .lock()
+                                    .await
                                     .iter()
-                                    .position(|x| *x.source == old_path.display().to_string())
-                                {
+                                    .position(|x| *x.source == old_path.display().to_string());
+
+                                if let Some(index) = position {
                                     let media =
                                         Media::new(index, &new_path.to_string_lossy(), false).await;
-                                    media_list[index] = media;
+                                    sources.lock().await[index] = media;
                                     info!(target: Target::file_mail(), channel = id; "Move file: <b><magenta>{old_path:?}</></b> to <b><magenta>{new_path:?}</></b>");
-                                } else if include_file_extension(&config, new_path) {
-                                    let index = media_list.len();
+                                } else if include_file_extension(&config, new_path)
+                                    && !config.storage.is_excluded(path, new_path)
+                                {
                                     let media =
-                                        Media::new(index, &new_path.to_string_lossy(), false).await;
+                                        Media::new(0, &new_path.to_string_lossy(), false).await;
 
-                                    media_list.push(media);
+                                    insert_media(&config, &sources, &current_index, media).await;
                                     info!(target: Target::file_mail(), channel = id; "Create new file: <b><magenta>{new_path:?}</></b>");
                                 }
                             }
@@ -1,9 +1,11 @@
 use std::sync::{Arc, LazyLock, Mutex};
 
-use actix_web::{dev::ServiceRequest, Error, HttpMessage};
+use actix_web::{dev::ServiceRequest, http::Method, web, Error, HttpMessage};
 use actix_web_grants::authorities::AttachAuthorities;
 use actix_web_httpauth::extractors::bearer::BearerAuth;
 use clap::Parser;
+use log::*;
+use sqlx::{Pool, Sqlite};
 use sysinfo::{Disks, Networks, System};
 
 pub mod api;
@@ -31,13 +33,48 @@ pub async fn validator(
     req: ServiceRequest,
     credentials: BearerAuth,
 ) -> Result<ServiceRequest, (Error, ServiceRequest)> {
-    // We just get permissions from JWT
-    match auth::decode_jwt(credentials.token()).await {
+    let token = credentials.token();
+    let is_api_token = token.starts_with(auth::API_TOKEN_PREFIX);
+
+    // API tokens are checked against the database, for JWTs the permissions are in the token
+    let claims = if is_api_token {
+        match req.app_data::<web::Data<Pool<Sqlite>>>() {
+            Some(pool) => auth::api_token_claims(pool, token)
+                .await
+                .map_err(Error::from),
+            None => Err(actix_web::error::ErrorInternalServerError("No database")),
+        }
+    } else {
+        auth::decode_jwt(token).await
+    };
+
+    match claims {
         Ok(claims) => {
-            req.attach(vec![claims.role]);
+            if req.method() != Method::GET {
+                info!(
+                    "{} {} by <b><magenta>{}</></b>{}",
+                    req.method(),
+                    req.path(),
+                    claims.username,
+                    if is_api_token { " (API token)" } else { "" }
+                );
+            }
+
+            // the roles on single channels open the routes, the routes check the channel
+            let mut roles = vec![claims.role.clone()];
+            roles.extend(claims.channel_roles.values().cloned());
+            req.attach(roles);
+
+            let mut user = UserMeta::new(
+                claims.id,
+                claims.channels,
+                claims.role,
+                claims.channel_roles,
+            );
+            user.api_token = is_api_token;
+            user.username = claims.username;
 
-            req.extensions_mut()
-                .insert(UserMeta::new(claims.id, claims.channels));
+            req.extensions_mut().insert(user);
 
             Ok(req)
         }
@@ -1,10 +1,15 @@
 use std::{
     collections::HashSet,
+    path::Path,
     sync::{atomic::AtomicBool, Arc},
     thread,
+    time::Duration,
 };
 
-use actix_web::{middleware::Logger, web, App, HttpServer};
+use actix_web::{
+    middleware::{self, Logger},
+    web, App, HttpServer,
+};
 use actix_web_httpauth::middleware::HttpAuthentication;
 
 #[cfg(any(debug_assertions, not(feature = "embed_frontend")))]
@@ -13,26 +18,35 @@ use actix_files::Files;
 #[cfg(all(not(debug_assertions), feature = "embed_frontend"))]
 use actix_web_static_files::ResourceFiles;
 
+use futures_util::future::join_all;
 use log::*;
 use tokio::{fs::File, io::AsyncReadExt, sync::Mutex};
 
 use ffplayout::{
-    api::routes::*,
+    api::{audit::audit_log, routes::*},
     db::{db_drop, db_pool, handles, init_globales},
     file::utils::media_map::MediaMap,
     player::{
         controller::{ChannelController, ChannelManager},
-        utils::{get_date, is_remote, json_validate::validate_playlist, JsonPlaylist},
+        utils::{
+            get_date,
+            import::{foreign_playlist_path, import_file, read_foreign_playlist},
+            is_remote,
+            json_validate::{dry_run, validate_playlist},
+            JsonPlaylist,
+        },
     },
     sse::{broadcast::Broadcaster, routes::*, SseAuthState},
     utils::{
         args_parse::init_args,
-        config::get_config,
+        config::{get_config, PlayoutConfig},
         errors::ProcessError,
+        ffmpeg_bundle,
         logging::init_logging,
         mail::{self, MailQueue},
         playlist::generate_playlist,
         time_machine::set_mock_time,
+        workers,
     },
     validator, ARGS,
 };
@@ -43,6 +57,9 @@ use ffplayout::utils::public_path;
 #[cfg(all(not(debug_assertions), feature = "embed_frontend"))]
 include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 
+/// Seconds to wait on shutdown, until ffmpeg has closed the output.
+const DRAIN_TIMEOUT: u64 = 10;
+
 fn thread_counter() -> usize {
     let available_threads = thread::available_parallelism()
         .map(std::num::NonZero::get)
@@ -51,6 +68,82 @@ fn thread_counter() -> usize {
     (available_threads / 2).max(2)
 }
 
+/// Read playlist from disk, for today or the given date.
+async fn read_playlist(
+    config: &PlayoutConfig,
+    date: Option<&str>,
+) -> Result<JsonPlaylist, ProcessError> {
+    let mut playlist_path = config.channel.playlists.clone();
+    let start_sec = config.playlist.start_sec.unwrap();
+    let date = date.map_or_else(
+        || get_date(false, start_sec, false, &config.channel.timezone),
+        str::to_string,
+    );
+
+    if playlist_path.is_dir() || is_remote(&playlist_path.to_string_lossy()) {
+        let d: Vec<&str> = date.split('-').collect();
+
+        if d.len() != 3 {
+            return Err(ProcessError::Custom(format!("Invalid date: {date}")));
+        }
+
+        playlist_path = playlist_path
+            .join(d[0])
+            .join(d[1])
+            .join(date.clone())
+            .with_extension("json");
+    }
+
+    if !playlist_path.is_file() {
+        if let Some(foreign) = foreign_playlist_path(&playlist_path) {
+            return Ok(read_foreign_playlist(&foreign, &date).await?);
+        }
+    }
+
+    let mut f = File::options()
+        .read(true)
+        .write(false)
+        .open(&playlist_path)
+        .await?;
+
+    let mut contents = String::new();
+    f.read_to_string(&mut contents).await?;
+
+    Ok(serde_json::from_str(&contents)?)
+}
+
+/// Wait for SIGTERM or Ctrl+C.
+async fn shutdown_signal() {
+    #[cfg(target_family = "unix")]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = term.recv() => {}
+                    _ = tokio::signal::ctrl_c() => {}
+                }
+            }
+            Err(e) => {
+                error!("Listen for SIGTERM: {e}");
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(target_family = "unix"))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Stop all channels and let ffmpeg finish the output.
+async fn drain_channels(controllers: &Mutex<ChannelController>) {
+    let drain_timeout = Duration::from_secs(ARGS.drain_timeout.unwrap_or(DRAIN_TIMEOUT));
+    let managers = controllers.lock().await.managers.clone();
+
+    join_all(managers.iter().map(|m| m.drain(drain_timeout))).await;
+}
+
 #[tokio::main]
 async fn main() -> Result<(), ProcessError> {
     let shared_duration = Arc::new(MediaMap::create(3000)); // to-do : implement it in frontend as input
@@ -70,16 +163,36 @@ async fn main() -> Result<(), ProcessError> {
     // LoggerHandle should be kept alive until the end
     let _logger = init_logging(mail_queues.clone());
 
+    ffmpeg_bundle::init().await?;
+
     let channel_controllers = Arc::new(Mutex::new(ChannelController::new()));
 
+    if ARGS.single_channel && ARGS.listen.is_none() {
+        return Err(ProcessError::Input(
+            "--single-channel needs --listen, for the API and the health checks".to_string(),
+        ));
+    }
+
     if let Some(conn) = &ARGS.listen {
         let channels = handles::select_related_channels(&pool, None).await?;
 
-        for channel in channels.into_iter() {
+        // in single channel mode an existing database can have more channels
+        for channel in channels
+            .into_iter()
+            .filter(|c| !ARGS.single_channel || c.id == 1)
+        {
             let config = get_config(&pool, channel.id).await?;
             let m_queue = Arc::new(Mutex::new(MailQueue::new(channel.id, config.mail.clone())));
             let channel_active = channel.active;
-            let manager = ChannelManager::new(pool.clone(), channel, config).await;
+            let channel_worker = channel.worker_id;
+            let channel_id = channel.id;
+            let manager = match ChannelManager::new(pool.clone(), channel, config).await {
+                Ok(manager) => manager,
+                Err(e) => {
+                    error!("Channel {channel_id} can't start: {e}");
+                    continue;
+                }
+            };
 
             if init {
                 if let Err(e) = manager.storage.lock().await.copy_assets().await {
@@ -91,7 +204,7 @@ async fn main() -> Result<(), ProcessError> {
 
             mail_queues.lock().await.push(m_queue);
 
-            if channel_active {
+            if channel_active && channel_worker.is_none() {
                 manager.start().await?;
             }
 
@@ -105,6 +218,9 @@ async fn main() -> Result<(), ProcessError> {
             .ok_or(ProcessError::IO(
                 "<ADRESSE>:<PORT> needed! For example: 127.0.0.1:8787".to_string(),
             ))?;
+
+        tokio::spawn(workers::run(pool.clone(), channel_controllers.clone()));
+
         let controllers = web::Data::from(channel_controllers.clone());
         let queues = web::Data::from(mail_queues);
         let auth_state = web::Data::new(SseAuthState {
@@ -117,12 +233,14 @@ async fn main() -> Result<(), ProcessError> {
         let db_clone = pool.clone();
 
         // no 'allow origin' here, give it to the reverse proxy
-        HttpServer::new(move || {
+        let server = HttpServer::new(move || {
             let auth = HttpAuthentication::bearer(validator);
             let db_pool = web::Data::new(db_clone.clone());
             // Customize logging format to get IP though proxies.
             let logger = Logger::new("%{r}a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T")
-                .exclude_regex(r"/_nuxt/*");
+                .exclude_regex(r"/_nuxt/*")
+                .exclude("/healthz")
+                .exclude("/readyz");
 
             let mut web_app = App::new()
                 .app_data(db_pool)
@@ -132,9 +250,19 @@ async fn main() -> Result<(), ProcessError> {
                 .app_data(web::Data::new(shared_duration.clone()))
                 .app_data(web::Data::from(Arc::clone(&broadcast_data)))
                 .wrap(logger)
-                .service(web::scope("/auth").service(login).service(refresh))
+                .service(healthz)
+                .service(readyz)
+                .service(
+                    web::scope("/auth")
+                        .service(login)
+                        .service(refresh)
+                        .service(oidc_status)
+                        .service(oidc_login)
+                        .service(oidc_callback),
+                )
                 .service(
                     web::scope("/api")
+                        .wrap(middleware::from_fn(audit_log))
                         .wrap(auth)
                         .service(add_user)
                         .service(get_user)
@@ -148,22 +276,48 @@ async fn main() -> Result<(), ProcessError> {
                         .service(update_advanced_config)
                         .service(get_playout_config)
                         .service(update_playout_config)
+                        .service(validate_playout_config)
                         .service(add_preset)
                         .service(get_presets)
                         .service(update_preset)
                         .service(delete_preset)
                         .service(get_channel)
+                        .service(get_epg)
                         .service(get_all_channels)
                         .service(patch_channel)
                         .service(add_channel)
                         .service(remove_channel)
+                        .service(get_channel_groups)
+                        .service(add_channel_group)
+                        .service(update_channel_group)
+                        .service(remove_channel_group)
+                        .service(group_process_control)
+                        .service(patch_group_config)
+                        .service(get_workers)
+                        .service(add_worker)
+                        .service(remove_worker)
+                        .service(place_channel)
+                        .service(worker_status)
+                        .service(update_worker_channel)
+                        .service(remove_worker_channel)
                         .service(update_user)
+                        .service(get_api_tokens)
+                        .service(add_api_token)
+                        .service(revoke_api_token)
+                        .service(get_audit_log)
                         .service(send_text_message)
+                        .service(add_text_message)
+                        .service(clear_text_messages)
                         .service(control_playout)
+                        .service(control_insert)
                         .service(media_current)
                         .service(process_control)
+                        .service(recorder_control)
+                        .service(export_clip)
                         .service(get_playlist)
                         .service(save_playlist)
+                        .service(get_playlist_revisions)
+                        .service(rollback_playlist)
                         .service(gen_playlist)
                         .service(del_playlist)
                         .service(get_log)
@@ -172,16 +326,29 @@ async fn main() -> Result<(), ProcessError> {
                         .service(move_rename)
                         .service(remove)
                         .service(save_file)
+                        .service(preview_file)
+                        .service(stop_preview_file)
                         .service(import_playlist)
+                        .service(get_library)
+                        .service(scan_library)
+                        .service(get_library_preview)
+                        .service(get_history)
+                        .service(get_play_counts)
+                        .service(export_history)
+                        .service(get_asrun)
+                        .service(get_hls_key)
                         .service(get_program)
                         .service(get_system_stat)
+                        .service(get_metrics)
                         .service(generate_uuid),
                 )
                 .service(
                     web::scope("/data")
                         .service(validate_uuid)
-                        .service(event_stream),
+                        .service(event_stream)
+                        .service(sync_playhead),
                 )
+                .service(ws_channel)
                 .service(get_file)
                 .service(get_public);
 
@@ -203,15 +370,42 @@ async fn main() -> Result<(), ProcessError> {
         })
         .bind((addr, port))?
         .workers(thread_counter())
-        .run()
-        .await?;
+        // signals are handled here, channels must be drained before the server stops
+        .disable_signals()
+        // open event streams would hold the shutdown for the default of 30 seconds
+        .shutdown_timeout(5)
+        .run();
+
+        let handle = server.handle();
+        let drain_controllers = channel_controllers.clone();
+
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            info!("Shutdown, drain channels");
+
+            drain_channels(&drain_controllers).await;
+            handle.stop(true).await;
+        });
+
+        server.await?;
     } else if ARGS.drop_db {
         db_drop().await;
     } else if let Some(channel_ids) = &ARGS.channel {
+        if ARGS.foreground {
+            let drain_controllers = channel_controllers.clone();
+
+            tokio::spawn(async move {
+                shutdown_signal().await;
+                info!("Shutdown, drain channels");
+
+                drain_channels(&drain_controllers).await;
+            });
+        }
+
         for (index, channel_id) in channel_ids.iter().enumerate() {
             let config = get_config(&pool, *channel_id).await?;
             let channel = handles::select_channel(&pool, channel_id).await?;
-            let manager = ChannelManager::new(pool.clone(), channel, config.clone()).await;
+            let manager = ChannelManager::new(pool.clone(), channel, config.clone()).await?;
 
             if ARGS.foreground {
                 let m_queue = Arc::new(Mutex::new(MailQueue::new(*channel_id, config.mail)));
@@ -224,29 +418,7 @@ async fn main() -> Result<(), ProcessError> {
                 // run a simple playlist generator and save them to disk
                 generate_playlist(manager).await?;
             } else if ARGS.validate {
-                let mut playlist_path = config.channel.playlists.clone();
-                let start_sec = config.playlist.start_sec.unwrap();
-                let date = get_date(false, start_sec, false, &config.channel.timezone);
-
-                if playlist_path.is_dir() || is_remote(&playlist_path.to_string_lossy()) {
-                    let d: Vec<&str> = date.split('-').collect();
-                    playlist_path = playlist_path
-                        .join(d[0])
-                        .join(d[1])
-                        .join(date.clone())
-                        .with_extension("json");
-                }
-
-                let mut f = File::options()
-                    .read(true)
-                    .write(false)
-                    .open(&playlist_path)
-                    .await?;
-
-                let mut contents = String::new();
-                f.read_to_string(&mut contents).await?;
-
-                let playlist: JsonPlaylist = serde_json::from_str(&contents)?;
+                let playlist = read_playlist(&config, None).await?;
 
                 validate_playlist(
                     config,
@@ -255,6 +427,25 @@ async fn main() -> Result<(), ProcessError> {
                     Arc::new(AtomicBool::new(false)),
                 )
                 .await;
+            } else if let Some(import) = &ARGS.import_playlist {
+                match import_file(
+                    &config.channel.playlists,
+                    &config.channel.storage,
+                    config.playlist.start_sec.unwrap_or_default(),
+                    &import[1],
+                    Some(manager.channel.lock().await.name.clone()),
+                    Path::new(&import[0]),
+                )
+                .await
+                {
+                    Ok(msg) => info!("{msg}"),
+                    Err(e) => error!("Import playlist failed:\n{e}"),
+                }
+            } else if let Some(date) = &ARGS.validate_only {
+                let date = Some(date.as_str()).filter(|d| !d.is_empty());
+                let playlist = read_playlist(&config, date).await?;
+
+                dry_run(&config, playlist).await?;
             } else if ARGS.test_mail {
                 mail::send_mail(&config.mail, "This is just a test email...".to_string()).await?;
             }
@@ -265,14 +456,13 @@ async fn main() -> Result<(), ProcessError> {
             -l 127.0.0.1
             --channel 1 2 --foreground
             --channel 1 --generate 2025-01-20 - 2025-01-25
+            --channel 1 --validate-only 2025-01-20
+            --channel 1 --import-playlist rundown.csv 2025-01-20
         Run ffplayout -h for more information."
         );
     }
 
-    for manager in &channel_controllers.lock().await.managers {
-        manager.channel.lock().await.active = false;
-        manager.stop_all(false).await;
-    }
+    drain_channels(&channel_controllers).await;
 
     pool.close().await;
 
@@ -1,5 +1,7 @@
 use std::{
-    cmp, fmt,
+    cmp,
+    collections::VecDeque,
+    fmt,
     path::Path,
     sync::{
         atomic::{AtomicBool, AtomicUsize, Ordering},
@@ -11,17 +13,28 @@ use async_walkdir::WalkDir;
 use log::*;
 use m3u8_rs::Playlist;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use sqlx::{Pool, Sqlite};
 use tokio::{
     fs,
     io::{self, AsyncReadExt},
     process::{Child, ChildStdout},
-    sync::Mutex,
-    time::{sleep, Duration, Instant},
+    sync::{broadcast, Mutex},
+    time::{sleep, timeout, Duration, Instant},
 };
 use tokio_stream::StreamExt;
 
-use crate::utils::{config::PlayoutConfig, errors::ServiceError};
+use crate::utils::{
+    asrun, clock,
+    config::PlayoutConfig,
+    config_check,
+    errors::ServiceError,
+    event_hooks,
+    events::{self, EventKind, PlayoutEvent},
+    graphics, history, hw_accel, library, metadata, mqtt, playhead, preview, sync, ticker,
+    time_machine::time_now,
+    webhook, TextMessage,
+};
 use crate::ARGS;
 use crate::{
     db::{handles, models::Channel},
@@ -29,7 +42,10 @@ use crate::{
 };
 use crate::{
     file::{init_storage, select_storage_type, StorageBackend},
-    player::{output::player, utils::Media},
+    player::{
+        output::{player, preview_stream::PreviewStream, proof::ProofOfPlay, recorder::Recorder},
+        utils::{progress::EncoderProgress, Media},
+    },
 };
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -55,6 +71,16 @@ impl fmt::Display for ProcessUnit {
 
 use ProcessUnit::*;
 
+/// Hold the current clip, until it gets released.
+#[derive(Clone, Debug, Copy, Eq, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HoldMode {
+    /// Play the clip again.
+    Loop,
+    /// Freeze on the last frame.
+    Freeze,
+}
+
 #[derive(Clone, Debug)]
 pub struct ChannelManager {
     pub id: i32,
@@ -68,19 +94,36 @@ pub struct ChannelManager {
     pub ingest_is_alive: Arc<AtomicBool>,
     pub is_alive: Arc<AtomicBool>,
     pub is_processing: Arc<AtomicBool>,
+    pub stop_after_current: Arc<AtomicBool>,
     pub filter_chain: Option<Arc<Mutex<Vec<String>>>>,
     pub current_date: Arc<Mutex<String>>,
     pub list_init: Arc<AtomicBool>,
     pub current_media: Arc<Mutex<Option<Media>>>,
+    /// When the decoder started the current media, for its position.
+    pub clip_started: Arc<Mutex<Option<Instant>>>,
+    pub encoder_progress: Arc<Mutex<Option<EncoderProgress>>>,
     pub current_list: Arc<Mutex<Vec<Media>>>,
     pub filler_list: Arc<Mutex<Vec<Media>>>,
     pub current_index: Arc<AtomicUsize>,
     pub filler_index: Arc<AtomicUsize>,
     pub storage: Arc<Mutex<StorageBackend>>,
+    pub recorder: Recorder,
+    pub proof: ProofOfPlay,
+    pub preview_stream: PreviewStream,
+    pub events: broadcast::Sender<PlayoutEvent>,
+    pub text_queue: Arc<Mutex<VecDeque<TextMessage>>>,
+    pub text_queue_active: Arc<AtomicBool>,
+    pub decoder_failures: Arc<Mutex<(String, usize)>>,
+    pub hold: Arc<Mutex<Option<HoldMode>>>,
+    pub manual_wait: Arc<AtomicBool>,
 }
 
 impl ChannelManager {
-    pub async fn new(db_pool: Pool<Sqlite>, channel: Channel, config: PlayoutConfig) -> Self {
+    pub async fn new(
+        db_pool: Pool<Sqlite>,
+        channel: Channel,
+        config: PlayoutConfig,
+    ) -> Result<Self, ServiceError> {
         let s_type = select_storage_type(&config.channel.storage);
         let channel_extensions = channel.extra_extensions.clone();
         let mut extensions = config.storage.extensions.clone();
@@ -92,20 +135,38 @@ impl ChannelManager {
         extensions.append(&mut extra_extensions);
 
         let storage = Arc::new(Mutex::new(
-            init_storage(s_type, config.channel.storage.clone(), extensions).await,
+            init_storage(s_type, config.channel.storage.clone(), extensions).await?,
         ));
 
         let storage_ins = storage.lock().await.clone();
         storage_ins.echo_log();
 
-        Self {
+        let config = Arc::new(Mutex::new(config));
+        let events = events::channel();
+
+        tokio::spawn(webhook::run(config.clone(), events.subscribe()));
+        tokio::spawn(mqtt::run(config.clone(), events.subscribe()));
+        tokio::spawn(event_hooks::run(config.clone(), events.subscribe()));
+        tokio::spawn(metadata::run(config.clone(), events.subscribe()));
+        tokio::spawn(library::run(db_pool.clone(), config.clone()));
+        tokio::spawn(preview::run(db_pool.clone(), config.clone()));
+        tokio::spawn(asrun::run(db_pool.clone(), config.clone()));
+        tokio::spawn(history::run(
+            db_pool.clone(),
+            config.clone(),
+            events.subscribe(),
+        ));
+
+        Ok(Self {
             id: channel.id,
             db_pool,
             is_alive: Arc::new(AtomicBool::new(false)),
             channel: Arc::new(Mutex::new(channel)),
-            config: Arc::new(Mutex::new(config)),
+            config,
             list_init: Arc::new(AtomicBool::new(true)),
             current_media: Arc::new(Mutex::new(None)),
+            clip_started: Arc::new(Mutex::new(None)),
+            encoder_progress: Arc::new(Mutex::new(None)),
             current_list: Arc::new(Mutex::new(vec![Media::default()])),
             filler_list: Arc::new(Mutex::new(vec![])),
             current_index: Arc::new(AtomicUsize::new(0)),
@@ -116,13 +177,40 @@ impl ChannelManager {
             ingest_stdout: Arc::new(Mutex::new(None)),
             ingest_is_alive: Arc::new(AtomicBool::new(false)),
             is_processing: Arc::new(AtomicBool::new(false)),
+            stop_after_current: Arc::new(AtomicBool::new(false)),
             filter_chain: None,
             current_date: Arc::new(Mutex::new(String::new())),
             storage,
-        }
+            recorder: Recorder::default(),
+            proof: ProofOfPlay::default(),
+            preview_stream: PreviewStream::default(),
+            events,
+            text_queue: Arc::new(Mutex::new(VecDeque::new())),
+            text_queue_active: Arc::new(AtomicBool::new(false)),
+            decoder_failures: Arc::new(Mutex::new((String::new(), 0))),
+            hold: Arc::new(Mutex::new(None)),
+            manual_wait: Arc::new(AtomicBool::new(false)),
+        })
     }
 
-    pub async fn update_channel(self, other: &Channel) {
+    /// Notify subscribers, like webhooks, about a playout event.
+    pub fn emit(&self, event: EventKind, data: Value) {
+        let _ = self.events.send(PlayoutEvent::new(event, self.id, data));
+    }
+
+    pub async fn update_channel(self, other: &Channel) -> Result<(), ServiceError> {
+        let s_path = Path::new(&other.storage);
+        let s_type = select_storage_type(s_path);
+        let mut extensions = self.config.lock().await.storage.extensions.clone();
+        let mut extra_extensions = other
+            .extra_extensions
+            .split(',')
+            .map(Into::into)
+            .collect::<Vec<String>>();
+
+        extensions.append(&mut extra_extensions);
+
+        let new_storage = init_storage(s_type, s_path.to_path_buf(), extensions).await?;
         let mut channel = self.channel.lock().await;
 
         channel.name.clone_from(&other.name);
@@ -133,22 +221,14 @@ impl ChannelManager {
         channel.time_shift.clone_from(&other.time_shift);
         channel.timezone.clone_from(&other.timezone);
 
-        let s_path = Path::new(&other.storage);
-        let s_type = select_storage_type(s_path);
-        let channel_extensions = channel.extra_extensions.clone();
-        let mut extensions = self.config.lock().await.storage.extensions.clone();
-        let mut extra_extensions = channel_extensions
-            .split(',')
-            .map(Into::into)
-            .collect::<Vec<String>>();
-
-        extensions.append(&mut extra_extensions);
         let mut storage = self.storage.lock().await;
 
-        *storage = init_storage(s_type, s_path.to_path_buf(), extensions).await;
+        *storage = new_storage;
 
         let storage_ins = &storage.clone();
         storage_ins.echo_log();
+
+        Ok(())
     }
 
     pub async fn update_config(&self, new_config: PlayoutConfig) {
@@ -161,17 +241,20 @@ impl ChannelManager {
             return Ok(()); // runs already, don't start multiple instances
         }
 
+        self.stop_after_current.store(false, Ordering::SeqCst);
+
         let self_clone = self.clone();
         let channel_id = self.channel.lock().await.id;
 
         handles::update_player(&self.db_pool, channel_id, true).await?;
+        self.emit(EventKind::ChannelStart, json!({}));
 
         tokio::spawn(async move {
             const MAX_DELAY: Duration = Duration::from_secs(180);
             let mut elapsed = Duration::from_secs(5);
             let mut retry_delay = Duration::from_millis(500);
 
-            while self_clone.channel.lock().await.active {
+            while self_clone.channel.lock().await.runs_here() {
                 self_clone.is_alive.store(true, Ordering::SeqCst);
                 self_clone.list_init.store(true, Ordering::SeqCst);
 
@@ -180,7 +263,7 @@ impl ChannelManager {
                 if let Err(e) = run_channel(self_clone.clone()).await {
                     self_clone.stop_all(false).await;
 
-                    if !self_clone.channel.lock().await.active {
+                    if !self_clone.channel.lock().await.runs_here() {
                         break;
                     }
 
@@ -197,6 +280,11 @@ impl ChannelManager {
 
                     error!(target: Target::all(), channel = channel_id; "Run channel <yellow>{channel_id}</> failed: {e} | {retry_msg}");
 
+                    self_clone.emit(
+                        EventKind::Restart,
+                        json!({"error": e.to_string(), "retry_in": retry_delay.as_secs()}),
+                    );
+
                     trace!(
                         "Runtime has <yellow>{}</> active tasks",
                         tokio::runtime::Handle::current()
@@ -226,6 +314,7 @@ impl ChannelManager {
         let channel_id = self.channel.lock().await.id;
 
         handles::update_player(&self.db_pool, channel_id, true).await?;
+        self.emit(EventKind::ChannelStart, json!({}));
 
         if index + 1 == ARGS.channel.clone().unwrap_or_default().len() {
             run_channel(self_clone).await?;
@@ -260,6 +349,129 @@ impl ChannelManager {
         self.wait(unit).await;
     }
 
+    /// The ffmpeg processes of the playout run, decoder or ingest, and the encoder when the
+    /// output is not segmented.
+    pub async fn pipeline_running(&self) -> bool {
+        if !self.is_alive.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let segmented = self.config.lock().await.output.mode.is_segmented();
+
+        (self.is_running(Decoder).await || self.is_running(Ingest).await)
+            && (segmented || self.is_running(Encoder).await)
+    }
+
+    async fn is_running(&self, unit: ProcessUnit) -> bool {
+        let child = match unit {
+            Decoder => &self.decoder,
+            Encoder => &self.encoder,
+            Ingest => &self.ingest,
+        };
+
+        child
+            .lock()
+            .await
+            .as_mut()
+            .is_some_and(|p| matches!(p.try_wait(), Ok(None)))
+    }
+
+    /// Ask the process to end, ffmpeg finishes then the output, like the last HLS segment.
+    async fn terminate(&self, unit: ProcessUnit) {
+        let child = match unit {
+            Decoder => &self.decoder,
+            Encoder => &self.encoder,
+            Ingest => &self.ingest,
+        };
+
+        if let Some(p) = child.lock().await.as_mut() {
+            #[cfg(target_family = "unix")]
+            if let Some(pid) = p.id() {
+                use nix::{sys::signal, unistd::Pid};
+
+                if let Err(e) = signal::kill(Pid::from_raw(pid as i32), signal::Signal::SIGTERM) {
+                    error!(target: Target::file_mail(), channel = self.id; "Terminate {unit} process: {e}");
+                }
+            }
+
+            #[cfg(not(target_family = "unix"))]
+            if let Err(e) = p.start_kill() {
+                error!(target: Target::file_mail(), channel = self.id; "Terminate {unit} process: {e}");
+            }
+        }
+    }
+
+    /// Stop the channel for a shutdown of the engine.
+    ///
+    /// The inputs get terminated first, the encoder ends when its input is closed. What still
+    /// runs after the drain timeout gets killed. Playhead and airing history are saved, and the
+    /// channel stays active in the database, so it starts again with the engine.
+    pub async fn drain(&self, drain_timeout: Duration) {
+        // the run loop ends, but the database keeps the active state
+        self.channel.lock().await.active = false;
+
+        if !self.is_alive.swap(false, Ordering::SeqCst) {
+            self.stop_all(false).await;
+            return;
+        }
+
+        info!(target: Target::file_mail(), channel = self.id; "Drain channel <yellow>{}</>", self.id);
+
+        // the output ends here, when ffmpeg has written what it got
+        playhead::save(self).await;
+
+        for unit in [Decoder, Ingest] {
+            self.terminate(unit).await;
+        }
+
+        let finished = timeout(drain_timeout, async {
+            for unit in [Decoder, Ingest, Encoder] {
+                while self.is_running(unit).await {
+                    sleep(Duration::from_millis(50)).await;
+                }
+            }
+        })
+        .await
+        .is_ok();
+
+        if !finished {
+            warn!(target: Target::file_mail(), channel = self.id; "Channel <yellow>{}</> not drained in {} seconds, kill it", self.id, drain_timeout.as_secs());
+        }
+
+        self.save_state().await;
+        self.emit(EventKind::ChannelStop, json!({}));
+
+        self.recorder.stop().await;
+        self.proof.stop();
+        self.preview_stream.stop().await;
+        self.stop_all(false).await;
+    }
+
+    /// Store the playhead and the end of the clip on air.
+    async fn save_state(&self) {
+        let date = self.current_date.lock().await.clone();
+        let time_shift = self.channel.lock().await.time_shift;
+        let config = self.config.lock().await.clone();
+        let now = time_now(&config.channel.timezone)
+            .format(history::TIME_FORMAT)
+            .to_string();
+
+        if let Err(e) = handles::update_stat(
+            &self.db_pool,
+            self.id,
+            &Some(date).filter(|d| !d.is_empty()),
+            time_shift,
+        )
+        .await
+        {
+            error!(target: Target::file_mail(), channel = self.id; "Save playhead: {e}");
+        }
+
+        if let Err(e) = handles::close_airing(&self.db_pool, self.id, &now).await {
+            error!(target: Target::file_mail(), channel = self.id; "Save airing duration failed: {e}");
+        }
+    }
+
     /// Wait for process to proper close.
     /// This prevents orphaned/zombi processes in system
     pub async fn wait(&self, unit: ProcessUnit) {
@@ -298,6 +510,28 @@ impl ChannelManager {
         tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
     }
 
+    /// Let the current clip play to its end, then stop the channel.
+    pub fn stop_after_current(&self) {
+        info!(target: Target::file_mail(), channel = self.id; "Stop playout after the current clip");
+
+        self.stop_after_current.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop the channel permanently, after the output has finished the last clip.
+    pub async fn finish(&self) {
+        self.stop_after_current.store(false, Ordering::SeqCst);
+        self.channel.lock().await.active = false;
+
+        if timeout(Duration::from_secs(10), self.wait(Encoder))
+            .await
+            .is_err()
+        {
+            warn!(target: Target::file_mail(), channel = self.id; "Encoder did not finish in time");
+        }
+
+        self.stop_all(true).await;
+    }
+
     /// No matter what is running, terminate them all.
     pub async fn stop_all(&self, permanent: bool) {
         let channel_id = self.channel.lock().await.id;
@@ -305,17 +539,23 @@ impl ChannelManager {
         if permanent {
             if self.is_alive.load(Ordering::SeqCst) {
                 debug!(target: Target::all(), channel = channel_id; "Deactivate playout and stop all child processes from channel: <yellow>{channel_id}</>");
+                self.emit(EventKind::ChannelStop, json!({}));
             }
 
             if let Err(e) = handles::update_player(&self.db_pool, channel_id, false).await {
                 error!(target: Target::all(), channel = channel_id; "Player status cannot be written: {e}");
             };
+
+            self.recorder.stop().await;
+            self.proof.stop();
+            self.preview_stream.stop().await;
         } else {
             debug!(target: Target::all(), channel = channel_id; "Stop all child processes from channel: <yellow>{channel_id}</>");
         }
 
         self.is_alive.store(false, Ordering::SeqCst);
         self.ingest_is_alive.store(false, Ordering::SeqCst);
+        self.encoder_progress.lock().await.take();
 
         for unit in [Decoder, Encoder, Ingest] {
             self.stop(unit).await;
@@ -394,7 +634,53 @@ async fn run_channel(manager: ChannelManager) -> Result<(), ServiceError> {
             .await;
     }
 
-    player(manager).await
+    clock::check(channel_id).await;
+
+    // the API checks the config already, but ffmpeg could be replaced since then
+    for issue in config_check::check_ffmpeg(&config).await {
+        error!(target: Target::file_mail(), channel = channel_id; "{issue}");
+    }
+
+    if let Err(e) = hw_accel::detect(&config).await {
+        error!(target: Target::file_mail(), channel = channel_id; "{e}");
+    }
+
+    let clock_watch = tokio::spawn(clock::watch(manager.clone()));
+    // the position of a clip from the last run is not valid anymore
+    manager.clip_started.lock().await.take();
+    let save_playhead = tokio::spawn(playhead::run(manager.clone()));
+    let follow_main =
+        (!config.sync.main_url.is_empty()).then(|| tokio::spawn(sync::follow(manager.clone())));
+    let graphics = config
+        .graphics
+        .enable
+        .then(|| tokio::spawn(graphics::run(manager.clone())));
+
+    if config.ticker.enable {
+        if let Err(e) = ticker::init(&config).await {
+            error!(target: Target::file_mail(), channel = channel_id; "Ticker text could not be created: {e}");
+        }
+    }
+
+    let news = config
+        .ticker
+        .enable
+        .then(|| tokio::spawn(ticker::run(manager.clone())));
+
+    let result = player(manager.clone()).await;
+
+    clock_watch.abort();
+    // a hold does not outlast the player
+    manager.hold.lock().await.take();
+    manager.manual_wait.store(false, Ordering::SeqCst);
+
+    save_playhead.abort();
+
+    for task in [follow_main, graphics, news].into_iter().flatten() {
+        task.abort();
+    }
+
+    result
 }
 
 pub async fn drain_hls_path(path: &Path) -> io::Result<()> {
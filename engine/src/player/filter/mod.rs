@@ -6,15 +6,31 @@ use shlex::split;
 use tokio::sync::Mutex;
 
 mod custom;
+pub mod plugins;
 pub mod v_drawtext;
 
 use crate::player::{
     controller::ProcessUnit::{self, *},
-    utils::{calc_aspect, custom_format, fps_calc, fraction, is_close, Media},
+    utils::{
+        calc_aspect, custom_format,
+        decoder_params::DecoderParams,
+        detect::METADATA_PRINT,
+        fps_calc, fraction, is_close, is_remote, logo_path,
+        probe::{interlaced, VideoStream},
+        subtitle::{burn_filter, burn_in, subtitle_source},
+        visual_radio, Media,
+    },
 };
 use crate::utils::{
-    config::{OutputMode::*, PlayoutConfig},
+    config::{
+        AspectMode, ColorSpace, Deinterlace, FpsMode, PlayoutConfig, SubtitleMode, Tonemap,
+        IMAGE_FORMAT,
+    },
+    graphics::overlay_path,
+    hw_accel,
     logging::Target,
+    ticker::ticker_path,
+    time_machine::time_now,
 };
 use crate::vec_strings;
 
@@ -36,6 +52,8 @@ impl fmt::Display for FilterType {
 use FilterType::*;
 
 const HW_FILTER_POSTFIX: &[&str; 6] = &["_cuda", "_npp", "_opencl", "_vaapi", "_vulkan", "_qsv"];
+/// Filters which only change timestamps or metadata, they take frames from GPU and system memory.
+const HW_PASSTHROUGH: &[&str; 7] = &["fps", "null", "setdar", "setpts", "setsar", "settb", "trim"];
 
 #[derive(Debug, Clone)]
 pub struct Filters {
@@ -56,6 +74,8 @@ pub struct Filters {
     video_position: i32,
     audio_last: i32,
     video_last: i32,
+    /// Input and stream index per audio track, from the clip's audio_tracks.
+    audio_inputs: Vec<(i32, i32)>,
 }
 
 impl Filters {
@@ -85,10 +105,22 @@ impl Filters {
             video_position: 0,
             audio_last: -1,
             video_last: -1,
+            audio_inputs: vec![],
+        }
+    }
+
+    fn audio_selector(&self, track_nr: i32) -> String {
+        match self.audio_inputs.get(track_nr as usize) {
+            Some((input, stream)) => format!("{input}:a:{stream}"),
+            None => format!("{}:a:{track_nr}", self.audio_position),
         }
     }
 
     pub fn add(&mut self, filter: &str, track_nr: i32, filter_type: FilterType) {
+        let input = match filter_type {
+            Audio => self.audio_selector(track_nr),
+            Video => format!("{}:v:{track_nr}", self.video_position),
+        };
         let (map, chain, position, last) = match filter_type {
             Audio => (
                 &mut self.audio_map,
@@ -124,11 +156,12 @@ impl Filters {
                 chain_start.push_str(&sep);
             } else {
                 // build audio/video selector like [0:a:0]
-                chain_start.push_str(&format!("{sep}[{position}:{filter_type}:{track_nr}]"));
+                chain_start.push_str(&format!("{sep}[{input}]"));
             }
 
             if self.hw_context
                 && !is_hw(filter)
+                && !is_passthrough(filter)
                 && !filter.contains("hwdownload")
                 && !filter.contains("hwupload")
                 && filter_type == Video
@@ -159,7 +192,7 @@ impl Filters {
         for (i, filter) in self.v_chain.iter().enumerate() {
             if filter.starts_with("movie=") {
                 if self.hw_context
-                    && !last_is_hw(&self.video_chain)
+                    && !last_is_hw(&self.video_chain, self.hw_context)
                     && !self.config.advanced.is_empty_filter()
                 {
                     let hw_up = hw_upload_str(&self.config);
@@ -169,7 +202,14 @@ impl Filters {
                 }
                 self.video_chain.push_str("[v];");
             } else if filter.starts_with("overlay") {
-                let hw_up = hw_upload(&self.config, &self.video_chain, filter);
+                let mut hw_up = hw_upload(&self.config, &self.video_chain, filter, self.hw_context);
+
+                // the layer keeps its alpha channel in the GPU memory
+                if let Some(upload) = hw_accel::overlay_upload(&self.config.processing.hw_accel)
+                    .filter(|_| !hw_up.is_empty())
+                {
+                    hw_up = upload;
+                }
 
                 if !hw_up.is_empty() {
                     self.video_chain.push(',');
@@ -180,8 +220,8 @@ impl Filters {
             } else if i > 0 && i < self.v_chain.len() && !filter.starts_with("[") {
                 self.video_chain.push(',');
 
-                let hw_dl = hw_download(&self.video_chain, filter);
-                let hw_ul = hw_upload(&self.config, &self.video_chain, filter);
+                let hw_dl = hw_download(&self.video_chain, filter, self.hw_context);
+                let hw_ul = hw_upload(&self.config, &self.video_chain, filter, self.hw_context);
 
                 if (!hw_dl.is_empty() || !hw_ul.is_empty())
                     && !filter.starts_with("movie=")
@@ -211,7 +251,7 @@ impl Filters {
 
         if self.video_last >= 0 && !self.video_chain.is_empty() && !self.video_chain.ends_with(']')
         {
-            if self.hw_context && !last_is_hw(&self.video_chain) {
+            if self.hw_context && !last_is_hw(&self.video_chain, self.hw_context) {
                 let hw_up = hw_upload_str(&self.config);
 
                 self.video_chain.push_str(",format=nv12,");
@@ -248,7 +288,7 @@ impl Filters {
                 .output_cmd
                 .as_ref()
                 .is_some_and(|p| p.iter().filter(|&n| *n == "-map").count() > 1)
-                && self.config.output.mode == HLS)
+                && self.config.output.mode.is_segmented())
             || (self
                 .config
                 .output
@@ -272,7 +312,7 @@ impl Filters {
 
         if self.audio_last == -1 {
             for i in 0..self.config.processing.audio_tracks {
-                let a_map = format!("{}:a:{i}", self.audio_position);
+                let a_map = self.audio_selector(i);
 
                 if !o_map.contains(&a_map) {
                     o_map.append(&mut vec_strings!["-map", a_map]);
@@ -294,30 +334,54 @@ fn is_hw(filter: &str) -> bool {
     HW_FILTER_POSTFIX.iter().any(|p| filter.contains(p))
 }
 
-fn last_is_hw(chain: &str) -> bool {
-    let parts: Vec<&str> = chain.split_terminator([',', ';']).map(str::trim).collect();
+fn is_image(source: &str) -> bool {
+    source
+        .rsplit_once('.')
+        .is_some_and(|(_, e)| IMAGE_FORMAT.contains(&e.to_lowercase().as_str()))
+}
+
+fn is_passthrough(filter: &str) -> bool {
+    let mut name = filter.trim();
+
+    // skip input links, like [0:v:0]
+    while let Some(rest) = name.strip_prefix('[').and_then(|n| n.split_once(']')) {
+        name = rest.1;
+    }
+
+    !name.contains([',', ';'])
+        && HW_PASSTHROUGH.contains(&name.split(['=', '[']).next().unwrap_or_default())
+}
+
+/// Check if the chain ends in the GPU memory, `start_hw` is the state of the input frames.
+fn last_is_hw(chain: &str, start_hw: bool) -> bool {
+    let parts: Vec<&str> = chain
+        .split_terminator([',', ';'])
+        .map(str::trim)
+        .filter(|p| !is_passthrough(p))
+        .collect();
 
     match parts.len() {
-        0 => false,
+        0 => start_hw,
         1 => {
             let last = parts[0];
-            HW_FILTER_POSTFIX.iter().any(|p| last.contains(p)) && !last.contains("hwdownload")
+            (is_hw(last) || last.starts_with("hwupload")) && !last.contains("hwdownload")
         }
         _ => {
             let last = parts[parts.len() - 1];
             let second_last = parts[parts.len() - 2];
-            HW_FILTER_POSTFIX.iter().any(|p| last.contains(p))
+            (is_hw(last) || last.starts_with("hwupload"))
                 && !last.contains("hwdownload")
                 && !second_last.contains("hwdownload")
         }
     }
 }
 
-fn hw_download(chain: &str, f: &str) -> String {
+fn hw_download(chain: &str, f: &str, start_hw: bool) -> String {
     let mut filter = String::new();
 
-    if last_is_hw(chain)
+    if last_is_hw(chain, start_hw)
         && !is_hw(f)
+        && !is_passthrough(f)
         && !f.starts_with("null[")
         && !f.starts_with("[")
         && !f.contains("hwdownload")
@@ -334,6 +398,10 @@ fn hw_download(chain: &str, f: &str) -> String {
 }
 
 fn hw_upload_str(config: &PlayoutConfig) -> String {
+    if let Some(upload) = hw_accel::upload(&config.processing.hw_accel) {
+        return upload.to_string();
+    }
+
     if config
         .advanced
         .decoder
@@ -347,54 +415,223 @@ fn hw_upload_str(config: &PlayoutConfig) -> String {
     "hwupload".to_string()
 }
 
-fn hw_upload(config: &PlayoutConfig, chain: &str, f: &str) -> String {
+fn hw_upload(config: &PlayoutConfig, chain: &str, f: &str, start_hw: bool) -> String {
     let mut filter = String::new();
 
-    if !last_is_hw(chain) && is_hw(f) && !f.contains("hwdownload") && !f.contains("hwupload") {
+    if !last_is_hw(chain, start_hw)
+        && is_hw(f)
+        && !f.contains("hwdownload")
+        && !f.contains("hwupload")
+    {
         filter = hw_upload_str(config);
     }
 
     filter
 }
 
-fn deinterlace(config: &PlayoutConfig, chain: &mut Filters, field_order: &Option<String>) {
-    if let Some(order) = field_order {
-        if order != "progressive" {
-            let deinterlace = match config.advanced.filter.deinterlace.clone() {
-                Some(deinterlace) => deinterlace,
-                None => "yadif=0:-1:0".to_string(),
-            };
+/// Deinterlace the clip, when it needs it.
+///
+/// In auto mode a progressive field order from ffprobe is trusted, the other clips get sampled
+/// with idet, because many files have a wrong or unknown field order.
+async fn deinterlace(
+    config: &PlayoutConfig,
+    chain: &mut Filters,
+    node: &Media,
+    field_order: &Option<String>,
+    force: Option<bool>,
+) {
+    let interlaced = match (force, &config.processing.deinterlace) {
+        (Some(force), _) => force,
+        (None, Deinterlace::On) => true,
+        (None, Deinterlace::Off) => false,
+        (None, Deinterlace::Auto) => match field_order.as_deref() {
+            Some("progressive") => false,
+            order => {
+                let flagged = order.is_some_and(|o| o != "unknown");
+
+                if is_remote(&node.source) || is_image(&node.source) {
+                    flagged
+                } else {
+                    interlaced(&node.source, node.seek).await.unwrap_or(flagged)
+                }
+            }
+        },
+    };
 
-            chain.add(&deinterlace, 0, Video);
-        }
+    if interlaced {
+        let deinterlace = match config.advanced.filter.deinterlace.clone() {
+            Some(deinterlace) => deinterlace,
+            None => "yadif=0:-1:0".to_string(),
+        };
+
+        chain.add(&deinterlace, 0, Video);
     }
 }
 
-fn pad(config: &PlayoutConfig, chain: &mut Filters, aspect: f64) {
-    if !is_close(aspect, config.processing.aspect, 0.03) {
-        let (numerator, denominator) = fraction(config.processing.aspect, 100);
+/// Tone map HDR clips to the SDR output, in BT.709.
+fn tonemap(config: &PlayoutConfig, chain: &mut Filters, v_stream: &VideoStream) {
+    if !v_stream.is_hdr() {
+        return;
+    }
 
-        let pad = match config.advanced.filter.pad_video.clone() {
-            Some(pad_video) => custom_format(
-                &pad_video,
-                &[&numerator.to_string(), &denominator.to_string()],
-            ),
-            None => format!("pad='ih*{numerator}/{denominator}:ih:(ow-iw)/2:(oh-ih)/2'"),
-        };
+    let algorithm = if config.processing.tonemap_algorithm.is_empty() {
+        "hable"
+    } else {
+        &config.processing.tonemap_algorithm
+    };
+    let peak = if config.processing.tonemap_peak > 0.0 {
+        config.processing.tonemap_peak
+    } else {
+        100.0
+    };
+
+    let filter = match config.processing.tonemap {
+        Tonemap::Off => return,
+        Tonemap::Zscale => format!(
+            "zscale=t=linear:npl={peak},format=gbrpf32le,zscale=p=bt709,\
+            tonemap=tonemap={algorithm}:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p"
+        ),
+        Tonemap::Libplacebo => format!(
+            "libplacebo=tonemapping={algorithm}:colorspace=bt709:color_primaries=bt709:\
+            color_trc=bt709:range=tv:format=yuv420p"
+        ),
+    };
 
-        chain.add(&pad, 0, Video);
+    chain.add(&filter, 0, Video);
+}
+
+/// Convert clips in another color matrix or in full range to the color space of the output.
+fn color_space(config: &PlayoutConfig, chain: &mut Filters, v_stream: &VideoStream) {
+    let Some(target) = config
+        .processing
+        .color_space
+        .target(config.processing.height)
+    else {
+        return;
+    };
+    let out_matrix = match target {
+        ColorSpace::Bt709 => "bt709",
+        _ => "bt601",
+    };
+    // tone mapped clips are in BT.709 already
+    let (in_matrix, full_range) = if v_stream.is_hdr() && config.processing.tonemap != Tonemap::Off
+    {
+        ("bt709", false)
+    } else {
+        (
+            v_stream.color_matrix(),
+            v_stream.color_range.as_deref() == Some("pc"),
+        )
+    };
+
+    if in_matrix == out_matrix && !full_range {
+        return;
     }
+
+    let in_range = if full_range { "full" } else { "tv" };
+
+    chain.add(
+        &format!(
+            "scale=in_color_matrix={in_matrix}:out_color_matrix={out_matrix}:in_range={in_range}:out_range=tv"
+        ),
+        0,
+        Video,
+    );
 }
 
-fn fps(config: &PlayoutConfig, chain: &mut Filters, fps: f64) {
-    if fps != config.processing.fps {
-        let fps_filter = match config.advanced.filter.fps.clone() {
-            Some(fps) => custom_format(&fps, &[&config.processing.fps]),
-            None => format!("fps={}", config.processing.fps),
-        };
+/// Fit the clip into the output aspect, with the aspect mode of the clip or the config.
+fn pad(config: &PlayoutConfig, chain: &mut Filters, node: &Media, aspect: f64) {
+    if is_close(aspect, config.processing.aspect, 0.03) {
+        return;
+    }
+
+    let (numerator, denominator) = fraction(config.processing.aspect, 100);
+    // width and height in the output aspect, in pixels of the clip
+    let width = format!("ih*{numerator}/{denominator}/sar");
+    let height = format!("iw*sar*{denominator}/{numerator}");
+    let wider = aspect > config.processing.aspect;
+
+    let filter = match (
+        node.aspect_mode.unwrap_or(config.processing.aspect_mode),
+        wider,
+    ) {
+        (AspectMode::Stretch, _) => return,
+        (AspectMode::Auto | AspectMode::Pillarbox, false) => {
+            match config.advanced.filter.pad_video.clone() {
+                Some(pad_video) => custom_format(
+                    &pad_video,
+                    &[&numerator.to_string(), &denominator.to_string()],
+                ),
+                None => format!("pad='{width}:ih:(ow-iw)/2:(oh-ih)/2'"),
+            }
+        }
+        (AspectMode::Auto | AspectMode::Letterbox, true) => {
+            format!("pad='iw:{height}:(ow-iw)/2:(oh-ih)/2'")
+        }
+        (AspectMode::Letterbox | AspectMode::CenterCrop, false) => format!("crop='iw:{height}'"),
+        (AspectMode::Pillarbox | AspectMode::CenterCrop, true) => format!("crop='{width}:ih'"),
+    };
+
+    chain.add(&filter, 0, Video);
+}
+
+/// Frame rate of the clip and the conversion to the output frame rate.
+///
+/// In auto mode, soft telecine takes the average frame rate, interlaced 29.97 fps clips get an
+/// inverse telecine for film rates and the others drop or blend frames, by the ratio of the rates.
+fn fps_mode(config: &PlayoutConfig, node: &Media, v_stream: &VideoStream) -> (f64, FpsMode) {
+    let target = config.processing.fps;
+    let mut rate = fps_calc(&v_stream.frame_rate, 1.0);
+    let average = v_stream
+        .avg_frame_rate
+        .as_deref()
+        .map(|r| fps_calc(r, 0.0))
+        .unwrap_or_default();
+
+    if is_close(rate, 29.97, 0.01) && is_close(average, 23.976, 0.01) {
+        rate = average;
+    }
+
+    let mode = node.fps_mode.unwrap_or(config.processing.fps_mode);
+
+    if mode != FpsMode::Auto {
+        return (rate, mode);
+    }
+
+    let interlaced = v_stream
+        .field_order
+        .as_deref()
+        .is_some_and(|o| o != "progressive" && o != "unknown");
+    let ratio = rate.max(target) / rate.min(target).max(1.0);
+
+    if is_close(rate, 29.97, 0.01) && interlaced && target < 25.0 {
+        (rate, FpsMode::Ivtc)
+    } else if is_close(ratio, ratio.round(), 0.01) {
+        (rate, FpsMode::Drop)
+    } else {
+        (rate, FpsMode::Blend)
+    }
+}
 
-        chain.add(&fps_filter, 0, Video);
+fn fps(config: &PlayoutConfig, chain: &mut Filters, fps: f64, mode: FpsMode) {
+    let target = config.processing.fps;
+
+    if fps == target && mode != FpsMode::Ivtc {
+        return;
     }
+
+    let fps_filter = match mode {
+        FpsMode::Blend => format!("framerate=fps={target}"),
+        FpsMode::Interpolate => {
+            format!("minterpolate=fps={target}:mi_mode=mci:mc_mode=aobmc:me_mode=bidir:vsbmc=1")
+        }
+        _ => match config.advanced.filter.fps.clone() {
+            Some(fps) => custom_format(&fps, &[&target]),
+            None => format!("fps={target}"),
+        },
+    };
+
+    chain.add(&fps_filter, 0, Video);
 }
 
 fn scale(config: &PlayoutConfig, chain: &mut Filters, width: Option<i64>, height: Option<i64>) {
@@ -450,7 +687,8 @@ fn fade(
         }
     }
 
-    if node.seek > 0.0 || node.unit == Ingest {
+    // the crossfade from the previous clip has faded in already
+    if (node.seek > 0.0 && node.crossfade_in == 0.0) || node.unit == Ingest {
         let mut fade_in = format!("{t}fade=in:st=0:d=0.5");
 
         if t == "a" {
@@ -464,7 +702,9 @@ fn fade(
         chain.add(&fade_in, nr, filter_type);
     }
 
-    if (node.out != node.duration && node.out - node.seek > 1.0) || fade_audio {
+    if ((node.out != node.duration && node.out - node.seek > 1.0) || fade_audio)
+        && !has_transition(node, nr, filter_type)
+    {
         let mut fade_out = format!("{t}fade=out:st={}:d=1.0", (node.out - node.seek - 1.0));
 
         if t == "a" {
@@ -479,30 +719,109 @@ fn fade(
     }
 }
 
+fn has_transition(node: &Media, nr: i32, filter_type: FilterType) -> bool {
+    node.unit == Decoder
+        && node.transition.as_ref().is_some_and(|t| match filter_type {
+            Audio => (nr as usize) < t.audio_streams,
+            Video => t.video,
+        })
+}
+
+/// Crossfade into the head of the next clip.
+///
+/// The next clip gets read with a movie source and normalized to the output format,
+/// its decoder starts then after the transition.
+fn crossfade(
+    config: &PlayoutConfig,
+    chain: &mut Filters,
+    node: &Media,
+    nr: i32,
+    filter_type: FilterType,
+) {
+    if !has_transition(node, nr, filter_type) {
+        return;
+    }
+
+    let Some(transition) = &node.transition else {
+        return;
+    };
+
+    let source = transition.source.replace('\\', "/").replace(':', "\\\\:");
+    let offset = node.out - node.seek - transition.duration;
+
+    let filter = match filter_type {
+        Video => {
+            let (width, height) = (config.processing.width, config.processing.height);
+            let format = format!(
+                "fps={},format=yuv420p,setdar=dar={}",
+                config.processing.fps, config.processing.aspect
+            );
+
+            format!(
+                "{format}[vxa];movie={source}:seek_point={}:s=dv,trim=duration={},setpts=PTS-STARTPTS,\
+                scale={width}:{height}:force_original_aspect_ratio=decrease,\
+                pad={width}:{height}:(ow-iw)/2:(oh-ih)/2,{format}[vxb];\
+                [vxa][vxb]xfade=transition={}:duration={}:offset={offset}",
+                transition.seek,
+                transition.duration,
+                config.processing.crossfade_transition,
+                transition.duration,
+            )
+        }
+        Audio => {
+            let format = format!(
+                "aresample=48000,aformat=sample_fmts=fltp:channel_layouts={}c",
+                config.processing.audio_channels
+            );
+
+            format!(
+                "{format}[axa{nr}];amovie={source}:seek_point={}:s=a\\\\:{nr},\
+                atrim=duration={},asetpts=PTS-STARTPTS,{format}[axb{nr}];\
+                [axa{nr}][axb{nr}]acrossfade=d={}:c1={curve}:c2={curve}",
+                transition.seek,
+                transition.duration,
+                transition.duration,
+                curve = config.processing.crossfade_curve,
+            )
+        }
+    };
+
+    chain.add(&filter, nr, filter_type);
+}
+
 fn overlay(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media) {
-    if config.processing.add_logo
-        && Path::new(&config.processing.logo_path).is_file()
-        && &node.category != "advertisement"
-    {
-        let logo_path = config
-            .processing
-            .logo_path
-            .replace('\\', "/")
-            .replace(':', "\\\\:");
+    if let Some(logo) = logo_path(config, node) {
+        let logo_path = logo.replace('\\', "/").replace(':', "\\\\:");
 
         let movie = match &config.advanced.filter.logo {
-            Some(logo) => {
-                custom_format(logo, &[logo_path, config.processing.logo_opacity.to_string()])
-        },
-            None => format!(
-                "movie={logo_path}:loop=0,setpts=N/(FRAME_RATE*TB),format=rgba,colorchannelmixer=aa={}",
-                config.processing.logo_opacity,
+            Some(logo) => custom_format(
+                logo,
+                &[logo_path, config.processing.logo_opacity.to_string()],
             ),
+            None => {
+                let fps = if config.processing.logo_fps > 0.0 {
+                    config.processing.logo_fps.to_string()
+                } else {
+                    "FRAME_RATE".to_string()
+                };
+                // hold the last frame, when the animation is over
+                let hold = if config.processing.logo_loop > 0 {
+                    ",tpad=stop=-1:stop_mode=clone"
+                } else {
+                    ""
+                };
+
+                format!(
+                    "movie={logo_path}:loop={},setpts=N/({fps}*TB){hold},format=rgba,colorchannelmixer=aa={}",
+                    config.processing.logo_loop.max(0),
+                    config.processing.logo_opacity,
+                )
+            }
         };
 
         chain.add(&movie, 0, Video);
 
-        if node.last_ad {
+        if node.last_ad || node.logo_in {
             let fade_in = match config.advanced.filter.overlay_logo_fade_in.clone() {
                 Some(fade_in) => fade_in,
                 None => "fade=in:st=0:d=1.0:alpha=1".to_string(),
@@ -511,7 +830,7 @@ fn overlay(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media) {
             chain.add(&fade_in, 0, Video);
         }
 
-        if node.next_ad {
+        if node.next_ad || node.logo_out {
             let length = node.out - node.seek - 1.0;
 
             let fade_out = match &config.advanced.filter.overlay_logo_fade_out {
@@ -523,9 +842,22 @@ fn overlay(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media) {
         }
 
         if !config.processing.logo_scale.is_empty() {
+            // a percentage scales to the output width
+            let logo_scale = match config
+                .processing
+                .logo_scale
+                .strip_suffix('%')
+                .and_then(|p| p.trim().parse::<f64>().ok())
+            {
+                Some(percent) => format!(
+                    "{}:-1",
+                    (config.processing.width as f64 * percent / 100.0).round()
+                ),
+                None => config.processing.logo_scale.clone(),
+            };
             let scale = match &config.advanced.filter.overlay_logo_scale {
-                Some(logo_scale) => custom_format(logo_scale, &[&config.processing.logo_scale]),
-                None => format!("scale={}", config.processing.logo_scale),
+                Some(custom_scale) => custom_format(custom_scale, &[&logo_scale]),
+                None => format!("scale={logo_scale}"),
             };
 
             chain.add(&scale, 0, Video);
@@ -540,6 +872,84 @@ fn overlay(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media) {
     }
 }
 
+/// Lay the rendered HTML graphics over the program.
+fn graphics(config: &PlayoutConfig, chain: &mut Filters) {
+    let path = overlay_path(config);
+
+    if !config.graphics.enable || !path.is_file() {
+        return;
+    }
+
+    let path = path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .replace(':', "\\\\:");
+
+    // the image2 demuxer opens the file on every loop, so new renderings show up
+    chain.add(
+        &format!("movie={path}:f=image2:loop=0,setpts=N/(FRAME_RATE*TB),format=rgba"),
+        0,
+        Video,
+    );
+    chain.add(
+        &format!(
+            "{}=0:0:shortest=1",
+            hw_accel::overlay(&config.processing.hw_accel).unwrap_or("overlay")
+        ),
+        0,
+        Video,
+    );
+}
+
+/// Overlay the stinger over the cut, its head at the end of the clip
+/// and the part after the offset at the start of the next one.
+fn stinger(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    if node.unit != Decoder {
+        return;
+    }
+
+    let path = config
+        .transition
+        .stinger_path
+        .replace('\\', "/")
+        .replace(':', "\\\\:");
+    let offset = config.transition.stinger_offset;
+    let mut scale = format!(
+        "format=rgba,scale={}:{}",
+        config.processing.width, config.processing.height
+    );
+    let overlay = hw_accel::overlay(&config.processing.hw_accel).unwrap_or("overlay");
+
+    if let Some(upload) = hw_accel::overlay_upload(&config.processing.hw_accel) {
+        scale.push(',');
+        scale.push_str(&upload);
+    }
+
+    if node.stinger_in {
+        chain.add(
+            &format!(
+                "null[sia];movie={path}:seek_point={offset},setpts=PTS-STARTPTS,{scale}[sib];\
+                [sia][sib]{overlay}=eof_action=pass"
+            ),
+            0,
+            Video,
+        );
+    }
+
+    if node.stinger_out {
+        let start = node.out - node.seek - offset;
+
+        chain.add(
+            &format!(
+                "null[soa];movie={path},setpts=PTS-STARTPTS+{start}/TB,{scale}[sob];\
+                [soa][sob]{overlay}=eof_action=pass"
+            ),
+            0,
+            Video,
+        );
+    }
+}
+
 fn extend_video(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media) {
     if let Some(video_duration) = node
         .probe
@@ -560,6 +970,34 @@ fn extend_video(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media) {
     }
 }
 
+/// burn in sidecar or embedded subtitles
+fn subtitles(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    if config.processing.subtitle_mode == SubtitleMode::None || node.unit != Decoder {
+        return;
+    }
+
+    if let Some(source) = subtitle_source(node).filter(|s| burn_in(config, s)) {
+        chain.add(&burn_filter(node, &source), 0, Video);
+    }
+}
+
+/// Draw the text on its own layer, when a hardware profile is active,
+/// so that the program frames stay in the GPU memory.
+fn text_layer(config: &PlayoutConfig, chain: &Filters, filter: &str) -> String {
+    let accel = &config.processing.hw_accel;
+    let (Some(overlay), Some(upload)) = (hw_accel::overlay(accel), hw_accel::overlay_upload(accel))
+    else {
+        return filter.to_string();
+    };
+    let n = chain.v_chain.iter().filter(|f| f.contains("[tl")).count();
+
+    format!(
+        "null[tl{n}a];color=c=black@0:s={}x{}:r={},format=rgba,{filter},{upload}[tl{n}b];\
+        [tl{n}a][tl{n}b]{overlay}=shortest=1",
+        config.processing.width, config.processing.height, config.processing.fps
+    )
+}
+
 /// add drawtext filter for lower thirds messages
 async fn add_text(
     config: &PlayoutConfig,
@@ -568,12 +1006,72 @@ async fn add_text(
     filter_chain: &Option<Arc<Mutex<Vec<String>>>>,
 ) {
     if config.text.add_text
-        && (config.text.text_from_filename || config.output.mode == HLS || node.unit == Encoder)
+        && (config.text.text_from_filename
+            || config.output.mode.is_segmented()
+            || node.unit == Encoder)
     {
         let filter = v_drawtext::filter_node(config, Some(node), filter_chain).await;
 
-        chain.add(&filter, 0, Video);
+        chain.add(&text_layer(config, chain, &filter), 0, Video);
+    }
+}
+
+/// Scroll the news ticker from right to left, in HLS mode it starts again with each clip.
+fn ticker(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    let path = ticker_path(config);
+
+    if !config.ticker.enable
+        || !(config.output.mode.is_segmented() || node.unit == Encoder)
+        || !path.is_file()
+    {
+        return;
+    }
+
+    let font = if Path::new(&config.text.font_path).is_file() {
+        format!(":fontfile='{}'", config.text.font_path)
+    } else {
+        String::new()
+    };
+
+    let filter = format!(
+        "drawtext=textfile='{}':reload=1:expansion=none:x=w-mod(t*{}\\,w+tw):{}{font}",
+        path.to_string_lossy()
+            .replace('\\', "/")
+            .replace(':', "\\\\:"),
+        config.ticker.speed,
+        config.ticker.style
+    );
+
+    chain.add(&text_layer(config, chain, &filter), 0, Video);
+}
+
+/// Digital clock in the time zone of the channel.
+///
+/// drawtext counts the time from the frame timestamp, so the offset is the local time at the
+/// clip start. A new clip gets a new offset, this also follows summer and winter time.
+fn clock(config: &PlayoutConfig, chain: &mut Filters) {
+    if !config.clock.enable {
+        return;
     }
+
+    let offset = time_now(&config.channel.timezone)
+        .naive_local()
+        .and_utc()
+        .timestamp_millis() as f64
+        / 1000.0;
+    let format = config.clock.format.replace(':', "\\\\:");
+    let font = if Path::new(&config.clock.font_path).is_file() {
+        format!(":fontfile='{}'", config.clock.font_path)
+    } else {
+        String::new()
+    };
+
+    let filter = format!(
+        "drawtext=text='%{{pts\\:gmtime\\:{offset:.3}\\:{format}}}':{}:{}{font}",
+        config.clock.position, config.clock.style
+    );
+
+    chain.add(&text_layer(config, chain, &filter), 0, Video);
 }
 
 fn add_audio(config: &PlayoutConfig, chain: &mut Filters, node: &Media, nr: i32) {
@@ -589,7 +1087,7 @@ fn add_audio(config: &PlayoutConfig, chain: &mut Filters, node: &Media, nr: i32)
 }
 
 fn extend_audio(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media, nr: i32) {
-    if !Path::new(&node.audio).is_file() {
+    if !Path::new(&node.audio.source).is_file() {
         if let Some(audio_duration) = node
             .probe
             .as_ref()
@@ -598,21 +1096,121 @@ fn extend_audio(config: &PlayoutConfig, chain: &mut Filters, node: &mut Media, n
         {
             if node.out - node.seek > audio_duration - node.seek + 0.1 && node.duration >= node.out
             {
-                let apad = match config.advanced.filter.apad.clone() {
-                    Some(apad) => custom_format(&apad, &[node.out - node.seek]),
-                    None => format!("apad=whole_dur={}", node.out - node.seek),
-                };
-
-                chain.add(&apad, nr, Audio);
+                pad_audio(config, chain, node, nr);
             }
         }
     }
 }
 
+fn pad_audio(config: &PlayoutConfig, chain: &mut Filters, node: &Media, nr: i32) {
+    let apad = match config.advanced.filter.apad.clone() {
+        Some(apad) => custom_format(&apad, &[node.out - node.seek]),
+        None => format!("apad=whole_dur={}", node.out - node.seek),
+    };
+
+    chain.add(&apad, nr, Audio);
+}
+
+/// Cut the frames between the keyframe and the seek point, after the decoder seeked to the keyframe.
+fn accurate_seek(chain: &mut Filters, node: &Media, nr: i32, filter_type: FilterType) {
+    let Some(keyframe) = node.keyframe else {
+        return;
+    };
+    let offset = node.seek - keyframe;
+
+    if node.seek > 0.5 && offset > 0.0 {
+        let trim = match filter_type {
+            Audio => format!("atrim=start={offset:.3},asetpts=PTS-STARTPTS"),
+            Video => format!("trim=start={offset:.3},setpts=PTS-STARTPTS"),
+        };
+
+        chain.add(&trim, nr, filter_type);
+    }
+}
+
+/// Detect black and frozen frames in the clip, before it gets padded and logo and text are on top.
+///
+/// Images are still by nature, so they get no freeze detection.
+fn video_detect(config: &PlayoutConfig, chain: &mut Filters, node: &Media) {
+    if node.unit != Decoder {
+        return;
+    }
+
+    let mut detect = vec![];
+
+    if config.monitor.black_duration > 0.0 {
+        detect.push(format!(
+            "blackdetect=d=0:pix_th={}",
+            config.monitor.black_level
+        ));
+    }
+
+    if config.monitor.freeze_duration > 0.0 && !is_image(&node.source) {
+        detect.push("freezedetect=d=0.5".to_string());
+    }
+
+    if !detect.is_empty() {
+        detect.push(format!("metadata={METADATA_PRINT}"));
+        chain.add(&detect.join(","), 0, Video);
+    }
+}
+
+fn silence_detect(config: &PlayoutConfig, chain: &mut Filters, node: &Media, nr: i32) {
+    if config.monitor.silence_duration > 0.0 && node.unit == Decoder && nr == 0 {
+        chain.add(
+            &format!(
+                "silencedetect=n={}dB:d=0.5,ametadata={METADATA_PRINT}",
+                config.monitor.silence_noise
+            ),
+            nr,
+            Audio,
+        );
+    }
+}
+
+fn channel_map(chain: &mut Filters, node: &Media, nr: i32) {
+    if let Some(pan) = node.audio.pan() {
+        chain.add(&pan, nr, Audio);
+    }
+}
+
+fn clip_gain(chain: &mut Filters, node: &Media, nr: i32) {
+    if node.audio.gain_db != 0.0 {
+        chain.add(&format!("volume={}dB", node.audio.gain_db), nr, Audio);
+    }
+}
+
+/// EBU R128 normalization, linear with the cached measurement of the first audio stream,
+/// otherwise in dynamic mode.
+fn loudnorm(config: &PlayoutConfig, chain: &mut Filters, node: &Media, nr: i32) {
+    if !config.processing.loudnorm {
+        return;
+    }
+
+    let mut loudnorm = format!(
+        "loudnorm=I={}:LRA={}:TP={}",
+        config.processing.loudnorm_i, config.processing.loudnorm_lra, config.processing.loudnorm_tp
+    );
+
+    if let Some(measured) = node.loudness.as_ref().filter(|_| nr == 0) {
+        loudnorm.push_str(&format!(
+            ":measured_I={}:measured_LRA={}:measured_TP={}:measured_thresh={}:linear=true",
+            measured.input_i, measured.input_lra, measured.input_tp, measured.input_thresh
+        ));
+    }
+
+    // loudnorm works internally with 192 kHz
+    chain.add(&format!("{loudnorm},aresample=48000"), nr, Audio);
+}
+
 fn audio_volume(config: &PlayoutConfig, chain: &mut Filters, nr: i32) {
     if config.processing.volume != 1.0 {
         let volume = match config.advanced.filter.volume.clone() {
             Some(volume) => custom_format(&volume, &[config.processing.volume]),
+            // with a zmq socket the volume can change in the running clip
+            None if config.text.zmq_stream_socket.is_some() => {
+                format!("volume@volume{nr}={}", config.processing.volume)
+            }
             None => format!("volume={}", config.processing.volume),
         };
 
@@ -729,6 +1327,7 @@ pub async fn filter_chains(
     if node.unit == Encoder {
         if !config.processing.audio_only {
             add_text(config, &mut filters, node, filter_chain).await;
+            ticker(config, &mut filters, node);
         }
 
         if let Some(f) = config.output.output_filter.clone() {
@@ -743,31 +1342,64 @@ pub async fn filter_chains(
     }
 
     if !config.processing.audio_only && !config.processing.copy_video {
-        if let Some(probe) = node.probe.as_ref() {
-            if Path::new(&node.audio).is_file() {
+        if visual_radio::is_active(config, node) {
+            filters.video_position = visual_radio::video_input(config, node);
+            filters.add(&visual_radio::filter(config, node), 0, Video);
+        } else if let Some(probe) = node.probe.as_ref() {
+            if Path::new(&node.audio.source).is_file() {
                 filters.audio_position = 1;
             }
 
             if let Some(v_stream) = &probe.video.first() {
                 let aspect = calc_aspect(config, &v_stream.aspect_ratio);
-                let frame_per_sec = fps_calc(&v_stream.frame_rate, 1.0);
+                let (frame_per_sec, fps_mode) = fps_mode(config, node, v_stream);
+                let params = DecoderParams::parse(&node.custom_decoder_params);
 
-                deinterlace(config, &mut filters, &v_stream.field_order);
-                pad(config, &mut filters, aspect);
-                fps(config, &mut filters, frame_per_sec);
+                accurate_seek(&mut filters, node, 0, Video);
+
+                if let Some(pix_fmt) = &params.pix_fmt {
+                    filters.add(&format!("format={pix_fmt}"), 0, Video);
+                }
+
+                // the inverse telecine matches the fields and deinterlaces only the rest
+                if fps_mode == FpsMode::Ivtc && params.deinterlace.is_none() {
+                    filters.add("fieldmatch,yadif=deint=interlaced,decimate", 0, Video);
+                } else {
+                    deinterlace(
+                        config,
+                        &mut filters,
+                        node,
+                        &v_stream.field_order,
+                        params.deinterlace,
+                    )
+                    .await;
+                }
+
+                tonemap(config, &mut filters, v_stream);
+                color_space(config, &mut filters, v_stream);
+                pad(config, &mut filters, node, aspect);
+                fps(config, &mut filters, frame_per_sec, fps_mode);
                 scale(config, &mut filters, v_stream.width, v_stream.height);
                 setdar(config, &mut filters, aspect);
             }
 
+            video_detect(config, &mut filters, node);
             extend_video(config, &mut filters, node);
         } else {
-            fps(config, &mut filters, 0.0);
+            fps(config, &mut filters, 0.0, FpsMode::Drop);
             scale(config, &mut filters, None, None);
+            video_detect(config, &mut filters, node);
         }
 
+        subtitles(config, &mut filters, node);
         add_text(config, &mut filters, node, filter_chain).await;
+        ticker(config, &mut filters, node);
+        clock(config, &mut filters);
         fade(config, &mut filters, node, 0, Video);
+        crossfade(config, &mut filters, node, 0, Video);
         overlay(config, &mut filters, node);
+        stinger(config, &mut filters, node);
+        graphics(config, &mut filters);
     }
 
     let (proc_vf, proc_af) = if node.unit == Ingest {
@@ -777,10 +1409,15 @@ pub async fn filter_chains(
     };
 
     let (list_vf, list_af) = custom::filter_node(config.general.channel_id, &node.custom_filter);
+    let (plugin_vf, plugin_af) = plugins::load(config, &node.filters).await;
 
     if !config.processing.copy_video {
         custom(&proc_vf, &mut filters, 0, Video);
         custom(&list_vf, &mut filters, 0, Video);
+
+        for filter in &plugin_vf {
+            custom(filter, &mut filters, 0, Video);
+        }
     }
 
     let mut audio_indexes = vec![];
@@ -793,14 +1430,35 @@ pub async fn filter_chains(
         audio_indexes.push(config.processing.audio_track_index);
     }
 
+    filters.audio_inputs = node.audio_track_inputs();
+
     if !config.processing.copy_audio {
         for i in audio_indexes {
-            if node
+            let (input, stream) = filters
+                .audio_inputs
+                .get(i as usize)
+                .copied()
+                .unwrap_or((0, i));
+
+            if input == 0
+                && node.audio.source.is_empty()
+                && node
+                    .probe
+                    .as_ref()
+                    .is_some_and(|p| p.audio.get(stream as usize).is_some())
+            {
+                accurate_seek(&mut filters, node, i, Audio);
+            }
+
+            if input > 0 && !node.audio_tracks.is_empty() {
+                // external audio track, pad it to the clip length
+                pad_audio(config, &mut filters, node, i);
+            } else if node
                 .probe
                 .as_ref()
-                .and_then(|p| p.audio.get(i as usize))
+                .and_then(|p| p.audio.get(stream as usize))
                 .is_some()
-                || Path::new(&node.audio).is_file()
+                || (node.audio_tracks.is_empty() && Path::new(&node.audio.source).is_file())
             {
                 extend_audio(config, &mut filters, node, i);
             } else if node.unit == Decoder && !node.source.contains("color=c=") {
@@ -816,17 +1474,26 @@ pub async fn filter_chains(
             // is important for split filter in HLS mode
             filters.add("anull", i, Audio);
 
+            channel_map(&mut filters, node, i);
+            silence_detect(config, &mut filters, node, i);
+            loudnorm(config, &mut filters, node, i);
             fade(config, &mut filters, node, i, Audio);
+            crossfade(config, &mut filters, node, i, Audio);
             audio_volume(config, &mut filters, i);
+            clip_gain(&mut filters, node, i);
 
             custom(&proc_af, &mut filters, i, Audio);
             custom(&list_af, &mut filters, i, Audio);
+
+            for filter in &plugin_af {
+                custom(filter, &mut filters, i, Audio);
+            }
         }
     } else if config.processing.audio_track_index > -1 {
         error!(target: Target::file_mail(), channel = config.general.channel_id; "Setting 'audio_track_index' other than '-1' is not allowed in audio copy mode!");
     }
 
-    if config.output.mode == HLS {
+    if config.output.mode.is_segmented() {
         if let Some(f) = config.output.output_filter.clone() {
             process_output_filters(config, &mut filters, &f);
         }
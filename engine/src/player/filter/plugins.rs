@@ -0,0 +1,122 @@
+/*
+Filter plugins.
+
+Named and parameterized filter snippets, one TOML file per plugin in the `filters.d` folder of
+the channel playlists. Playlist items reference them in `filters`, parameters follow the name:
+`["old_film_look:grain=20", "mono_downmix"]`. The filter builder adds them after the custom
+filters, sorted by their `order`. Plugins with the same order keep the order of the playlist.
+
+    order = 10
+    video = "curves=vintage,noise=alls={grain}:allf=t,vignette"
+    audio = ""
+
+    [params]
+    grain = "12"
+*/
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use log::*;
+use serde::Deserialize;
+use tokio::fs;
+
+use crate::utils::{config::PlayoutConfig, logging::Target};
+
+/// Folder of the plugins.
+pub fn dir(config: &PlayoutConfig) -> PathBuf {
+    config.channel.playlists.join("filters.d")
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct FilterPlugin {
+    /// Position in the chain, lower numbers come first.
+    #[serde(default)]
+    pub order: i32,
+    #[serde(default)]
+    pub video: String,
+    #[serde(default)]
+    pub audio: String,
+    /// Default values of the placeholders.
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+}
+
+impl FilterPlugin {
+    /// Fill the `{name}` placeholders, with the parameters of the reference or the defaults.
+    pub fn fill(&self, params: &BTreeMap<String, String>) -> (String, String) {
+        let mut video = self.video.clone();
+        let mut audio = self.audio.clone();
+        let mut values = self.params.clone();
+        values.extend(params.clone());
+
+        for (key, value) in values {
+            let placeholder = format!("{{{key}}}");
+            video = video.replace(&placeholder, &value);
+            audio = audio.replace(&placeholder, &value);
+        }
+
+        (video, audio)
+    }
+}
+
+/// Split a reference like `name:key=value:key=value` into name and parameters.
+pub fn reference(value: &str) -> (&str, BTreeMap<String, String>) {
+    let mut parts = value.split(':');
+    let name = parts.next().unwrap_or_default().trim();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+
+    (name, params)
+}
+
+/// Only plain names are valid, so that a reference can not leave the plugin folder.
+fn is_plugin_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Video and audio filters of the referenced plugins, in the order of the chain.
+pub async fn load(config: &PlayoutConfig, references: &[String]) -> (Vec<String>, Vec<String>) {
+    let id = config.general.channel_id;
+    let mut plugins = vec![];
+
+    for value in references {
+        let (name, params) = reference(value);
+
+        if !is_plugin_name(name) {
+            error!(target: Target::file_mail(), channel = id; "Filter plugin name <b><magenta>{name}</></b> is not valid!");
+            continue;
+        }
+
+        let file = dir(config).join(name).with_extension("toml");
+
+        let plugin = match fs::read_to_string(&file).await {
+            Ok(contents) => match toml_edit::de::from_str::<FilterPlugin>(&contents) {
+                Ok(plugin) => plugin,
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "Filter plugin <b><magenta>{name}</></b>: {e}");
+                    continue;
+                }
+            },
+            Err(_) => {
+                error!(target: Target::file_mail(), channel = id; "Filter plugin <b><magenta>{name}</></b> not found!");
+                continue;
+            }
+        };
+
+        plugins.push((plugin.order, plugin.fill(&params)));
+    }
+
+    // stable sort, the playlist order stays for the same order number
+    plugins.sort_by_key(|(order, _)| *order);
+
+    plugins
+        .into_iter()
+        .map(|(_, filter)| filter)
+        .filter(|(v, a)| !v.is_empty() || !a.is_empty())
+        .unzip()
+}
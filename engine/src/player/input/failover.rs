@@ -0,0 +1,136 @@
+/*
+Failover source, for when the primary source fails.
+
+When the playlist is missing, or the storage is not reachable, the playout switches to the
+failover source: a folder, which plays its clips in order, a single clip or image, or a live
+input URL. After each failover clip the primary source gets checked again, so the playout
+switches back as soon as it recovers.
+*/
+
+use std::path::{Path, PathBuf};
+
+use log::*;
+use serde_json::json;
+use tokio::fs;
+
+use crate::player::{
+    controller::ChannelManager,
+    utils::{include_file_extension, is_remote, loop_image, seek_and_length, Media},
+};
+use crate::utils::{
+    config::{PlayoutConfig, IMAGE_FORMAT},
+    events::EventKind,
+    logging::Target,
+};
+
+/// Longest play time of images and live inputs, before the primary source is checked again.
+pub const FAILOVER_CHECK: f64 = 60.0;
+
+#[derive(Debug, Default)]
+pub struct Failover {
+    index: usize,
+    active: bool,
+}
+
+impl Failover {
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    /// Next source from the failover folder, or the configured source itself.
+    async fn source(&mut self, config: &PlayoutConfig) -> Option<String> {
+        let source = config.failover.source.trim();
+
+        if is_remote(source) || Path::new(source).is_file() {
+            return Some(source.to_string());
+        }
+
+        let mut entries = fs::read_dir(source).await.ok()?;
+        let mut files: Vec<PathBuf> = vec![];
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+
+            if path.is_file() && include_file_extension(config, &path) {
+                files.push(path);
+            }
+        }
+
+        files.sort();
+
+        let file = files.get(self.index % files.len().max(1))?;
+        self.index = (self.index + 1) % files.len();
+
+        Some(file.to_string_lossy().to_string())
+    }
+
+    /// Failover clip with at most the given duration.
+    ///
+    /// Gives None when failover is disabled, or its source is not available.
+    pub async fn next(
+        &mut self,
+        manager: &ChannelManager,
+        config: &PlayoutConfig,
+        index: usize,
+        begin: f64,
+        duration: f64,
+    ) -> Option<Media> {
+        if !config.failover.enable || duration < 1.0 {
+            return None;
+        }
+
+        let id = config.general.channel_id;
+        let source = self.source(config).await?;
+        let mut node = Media::new(index, &source, true).await;
+        let is_image = source
+            .rsplit_once('.')
+            .is_some_and(|(_, e)| IMAGE_FORMAT.contains(&e.to_lowercase().as_str()));
+
+        if node.probe.is_none() && !is_remote(&source) {
+            warn!(target: Target::file_mail(), channel = id;
+                "Failover source <b><magenta>{source}</></b> is not playable"
+            );
+
+            return None;
+        }
+
+        node.begin = Some(begin);
+
+        if is_image || node.duration <= 0.0 {
+            node.duration = duration.min(FAILOVER_CHECK);
+        }
+
+        node.out = node.duration.min(duration);
+        node.cmd = Some(if is_image {
+            loop_image(config, &node)
+        } else {
+            seek_and_length(config, &mut node)
+        });
+
+        if !self.active {
+            self.active = true;
+
+            warn!(target: Target::file_mail(), channel = id;
+                "Switch to failover source <b><magenta>{}</></b>", config.failover.source
+            );
+
+            manager.emit(
+                EventKind::Failover,
+                json!({"active": true, "source": config.failover.source}),
+            );
+        }
+
+        Some(node)
+    }
+
+    /// Switch back, when the primary source plays again.
+    pub fn recover(&mut self, manager: &ChannelManager) {
+        if self.active {
+            self.active = false;
+
+            info!(target: Target::file_mail(), channel = manager.id; "Primary source is back, leave failover");
+
+            manager.emit(EventKind::Failover, json!({"active": false}));
+        }
+    }
+}
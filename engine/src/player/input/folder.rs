@@ -1,21 +1,44 @@
-use std::sync::atomic::Ordering;
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
 
+use chrono::{DateTime, Local, TimeDelta};
 use log::*;
-use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use rand::{
+    distr::{weighted::WeightedIndex, Distribution},
+    rngs::StdRng,
+    seq::SliceRandom,
+    Rng, SeedableRng,
+};
+use tokio::sync::Mutex;
 
+use crate::db::models::Playhead;
 use crate::player::{
     controller::ChannelManager,
-    utils::{include_file_extension, time_in_seconds, Media},
+    input::failover::Failover,
+    utils::{
+        blacklist, include_file_extension, loudness::add_loudness, rights, seek_and_length,
+        time_in_seconds, Media,
+    },
+};
+use crate::utils::{
+    config::PlayoutConfig, library, logging::Target, playhead, time_machine::time_now,
 };
-use crate::utils::{config::PlayoutConfig, logging::Target};
 
 /// Folder Sources
 ///
 /// Like playlist source, we create here a folder list for iterate over it.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct FolderSource {
     manager: ChannelManager,
     current_node: Media,
+    history: ShuffleHistory,
+    failover: Failover,
+    resume: Option<Playhead>,
 }
 
 impl FolderSource {
@@ -25,10 +48,11 @@ impl FolderSource {
         let mut media_list = vec![];
         let mut index: usize = 0;
         let storage = manager.storage.lock().await.clone();
+        let query = config.storage.query.trim();
 
         if !config.storage.paths.is_empty() && config.general.generate.is_some() {
             path_list.extend(&config.storage.paths);
-        } else {
+        } else if query.is_empty() {
             path_list.push(&config.channel.storage);
         }
         if let Some(dates) = &config.general.generate {
@@ -42,10 +66,13 @@ impl FolderSource {
                 error!(target: Target::file_mail(), channel = id; "Path not exists: <b><magenta>{path:?}</></b>");
             }
 
-            match storage.walk_dir(path).await {
+            match storage.walk_media_dir(path, config).await {
                 Ok(paths) => {
                     for p in paths {
-                        if storage.is_file(&p).await && include_file_extension(config, &p) {
+                        if storage.is_file(&p).await
+                            && include_file_extension(config, &p)
+                            && !config.storage.is_excluded(path, &p)
+                        {
                             let fetched_path = storage
                                 .fetch_file_path(&p.to_string_lossy())
                                 .await
@@ -69,7 +96,34 @@ impl FolderSource {
             }
         }
 
-        if media_list.is_empty() {
+        if path_list.is_empty() {
+            info!(target: Target::file_mail(), channel = id; "Take files from library query: <b><magenta>{query}</></b>");
+
+            match library::query(&manager.db_pool, id, query).await {
+                Ok(items) => {
+                    for item in items {
+                        let mut media = Media::new(0, &item.path, false).await;
+                        let key_path = if item.path.starts_with('/') {
+                            item.path.clone()
+                        } else {
+                            format!("/{}", item.path)
+                        };
+                        media.key = storage.sanitized_file_path(&key_path);
+
+                        media_list.push(media);
+                    }
+                }
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "Library query failed: {e}");
+                }
+            }
+        }
+
+        if media_list.is_empty() && path_list.is_empty() {
+            error!(target: Target::file_mail(), channel = id;
+                "no playable files found for library query: <b><magenta>{query}</></b>"
+            );
+        } else if media_list.is_empty() {
             error!(target: Target::file_mail(), channel = id;
                 "no playable files found under: <b><magenta>{:?}</></b>",
                 path_list
@@ -78,8 +132,7 @@ impl FolderSource {
 
         if config.storage.shuffle {
             info!(target: Target::file_mail(), channel = id; "Shuffle files");
-            let mut rng = StdRng::from_os_rng();
-            media_list.shuffle(&mut rng);
+            media_list.shuffle(&mut StdRng::from_os_rng());
         } else {
             media_list.sort_by(|d1, d2| d1.source.cmp(&d2.source));
         }
@@ -92,9 +145,18 @@ impl FolderSource {
 
         *manager.current_list.lock().await = media_list;
 
+        // the playlist generator starts always from the beginning
+        let resume = match config.general.generate {
+            Some(_) => None,
+            None => playhead::load(&manager).await,
+        };
+
         Self {
             manager,
             current_node: Media::default(),
+            history: ShuffleHistory::default(),
+            failover: Failover::default(),
+            resume,
         }
     }
 
@@ -104,14 +166,57 @@ impl FolderSource {
         Self {
             manager: manager.clone(),
             current_node: Media::default(),
+            history: ShuffleHistory::default(),
+            failover: Failover::default(),
+            resume: None,
         }
     }
 
+    /// Continue with the clip from the saved playhead, when it is still in the list.
+    ///
+    /// Returns the position in the clip. The list can be shuffled new, so the clip is searched
+    /// by its source.
+    async fn resume_playhead(&mut self) -> Option<f64> {
+        let playhead = self.resume.take()?;
+        let index = self
+            .manager
+            .current_list
+            .lock()
+            .await
+            .iter()
+            .position(|m| m.source == playhead.source)?;
+
+        info!(target: Target::file_mail(), channel = self.manager.id;
+            "Resume at position <yellow>{:.3}</>: <b><magenta>{}</></b>", playhead.position, playhead.source
+        );
+
+        self.manager.current_index.store(index, Ordering::SeqCst);
+
+        Some(playhead.position)
+    }
+
+    /// Clip is on the blacklist, or outside its rights window.
+    ///
+    /// The playlist generator checks the rights window by itself, with the time of the playlist.
+    async fn is_blocked(&self, config: &PlayoutConfig) -> bool {
+        let node = &self.current_node;
+
+        blacklist::is_listed(&self.manager, &node.source).await
+            || (config.general.generate.is_none()
+                && rights::is_blocked(
+                    config,
+                    &node.source,
+                    &time_now(&config.channel.timezone),
+                    node.out - node.seek,
+                )
+                .await)
+    }
+
     async fn shuffle(&mut self) {
-        let mut rng = StdRng::from_os_rng();
         let mut nodes = self.manager.current_list.lock().await;
 
-        nodes.shuffle(&mut rng);
+        nodes.shuffle(&mut StdRng::from_os_rng());
+        self.history.apply(&mut nodes);
 
         for (index, item) in nodes.iter_mut().enumerate() {
             item.index = Some(index);
@@ -129,22 +234,222 @@ impl FolderSource {
     }
 }
 
+/// Recently played sources, for the no-repeat window of the shuffle.
+#[derive(Debug, Default)]
+pub struct ShuffleHistory {
+    entries: VecDeque<(String, DateTime<Local>)>,
+}
+
+impl ShuffleHistory {
+    /// Remember a played source.
+    ///
+    /// The history keeps the last `items` sources and the sources of the last `minutes`,
+    /// but never the whole list, so at least one clip stays fresh.
+    pub fn add(
+        &mut self,
+        source: &str,
+        time: DateTime<Local>,
+        items: usize,
+        minutes: usize,
+        list_len: usize,
+    ) {
+        self.entries.push_back((source.to_string(), time));
+
+        let since = time - TimeDelta::minutes(minutes as i64);
+        let keep = self
+            .entries
+            .iter()
+            .rev()
+            .enumerate()
+            .take_while(|(i, (_, played))| *i < items || (minutes > 0 && *played > since))
+            .count()
+            .min(list_len.saturating_sub(1));
+
+        while self.entries.len() > keep {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn contains(&self, source: &str) -> bool {
+        self.entries.iter().any(|(s, _)| s == source)
+    }
+
+    /// Move recently played clips to the end, so they are not repeated right away.
+    pub fn apply(&self, nodes: &mut Vec<Media>) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let (fresh, recent): (Vec<Media>, Vec<Media>) =
+            nodes.drain(..).partition(|m| !self.contains(&m.source));
+
+        nodes.extend(fresh);
+        nodes.extend(recent);
+    }
+}
+
+/// Pick the next clip by the weights from `storage.weights`, the chance of a clip is its
+/// weight, compared to the weights of the other clips. Clips are picked with replacement,
+/// so a clip with weight 2 plays twice as often as a clip with weight 1.
+///
+/// Clips of the history are left out, as long as other clips can be picked. Weight 0 takes
+/// a clip out of the shuffle; only when no clip has a weight, all clips get the same chance.
+pub fn weighted_pick(
+    config: &PlayoutConfig,
+    nodes: &[Media],
+    history: &ShuffleHistory,
+) -> Option<usize> {
+    if nodes.is_empty() {
+        return None;
+    }
+
+    let mut rng = StdRng::from_os_rng();
+    let weights = nodes
+        .iter()
+        .map(|m| config.storage.weight(&m.source).max(0.0))
+        .collect::<Vec<_>>();
+    let fresh = weights
+        .iter()
+        .zip(nodes)
+        .map(|(w, m)| if history.contains(&m.source) { 0.0 } else { *w })
+        .collect::<Vec<_>>();
+
+    WeightedIndex::new(&fresh)
+        .or_else(|_| WeightedIndex::new(&weights))
+        .map(|dist| dist.sample(&mut rng))
+        .ok()
+        .or_else(|| Some(rng.random_range(0..nodes.len())))
+}
+
+/// Insert a new media into the running folder list, without disturbing the playback order.
+///
+/// In shuffle mode the media gets a random position which is still ahead in the current round,
+/// otherwise it is placed at its sorted position. When the media lands before the current
+/// playback position, the index is shifted, so that the next clip stays the same.
+pub async fn insert_media(
+    config: &PlayoutConfig,
+    sources: &Arc<Mutex<Vec<Media>>>,
+    current_index: &Arc<AtomicUsize>,
+    media: Media,
+) {
+    let mut nodes = sources.lock().await;
+
+    if nodes.iter().any(|m| m.source == media.source) {
+        return;
+    }
+
+    let index = current_index.load(Ordering::SeqCst).min(nodes.len());
+
+    let position = if config.storage.shuffle {
+        StdRng::from_os_rng().random_range(index..=nodes.len())
+    } else {
+        nodes.partition_point(|m| m.source < media.source)
+    };
+
+    if position < index {
+        current_index.fetch_add(1, Ordering::SeqCst);
+    }
+
+    nodes.insert(position, media);
+
+    for (i, item) in nodes.iter_mut().enumerate() {
+        item.index = Some(i);
+    }
+}
+
+/// Remove media from the running folder list and keep the current playback position.
+///
+/// Returns `true` when a media with the given source was found.
+pub async fn remove_media(
+    sources: &Arc<Mutex<Vec<Media>>>,
+    current_index: &Arc<AtomicUsize>,
+    source: &str,
+) -> bool {
+    let mut nodes = sources.lock().await;
+
+    let Some(position) = nodes.iter().position(|m| m.source == source) else {
+        return false;
+    };
+
+    if position < current_index.load(Ordering::SeqCst) {
+        current_index.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    nodes.remove(position);
+
+    for (i, item) in nodes.iter_mut().enumerate() {
+        item.index = Some(i);
+    }
+
+    true
+}
+
 /// Create iterator for folder source
 impl FolderSource {
+    /// Remember the source of the current node, the history is limited by `storage.shuffle_history`
+    /// and `storage.shuffle_history_minutes`.
+    async fn add_history(&mut self, config: &PlayoutConfig) {
+        let list_len = self.manager.current_list.lock().await.len();
+
+        self.history.add(
+            &self.current_node.source,
+            Local::now(),
+            config.storage.shuffle_history,
+            config.storage.shuffle_history_minutes,
+            list_len,
+        );
+    }
+
     pub async fn next(&mut self) -> Option<Media> {
         let config = self.manager.config.lock().await.clone();
         let id = config.general.id;
 
+        // the playlist generator uses the folder source too, but without failover
+        if config.general.generate.is_none() && self.manager.current_list.lock().await.is_empty() {
+            let begin = time_in_seconds(&config.channel.timezone);
+            let mut node = self
+                .failover
+                .next(&self.manager, &config, 0, begin, f64::MAX)
+                .await?;
+
+            add_loudness(&self.manager, &config, &mut node).await;
+            node.add_filter(&config, &self.manager.filter_chain).await;
+
+            return Some(node);
+        }
+
+        self.failover.recover(&self.manager);
+
+        let resume = self.resume_playhead().await;
+
+        // with weights, every clip is picked on its own, the list is not played in rounds
+        if config.storage.shuffle && !config.storage.weights.is_empty() && resume.is_none() {
+            let nodes = self.manager.current_list.lock().await;
+
+            if let Some(index) = weighted_pick(&config, &nodes, &self.history) {
+                self.manager.current_index.store(index, Ordering::SeqCst);
+            }
+        }
+
         if self.manager.current_index.load(Ordering::SeqCst)
             < self.manager.current_list.lock().await.len()
         {
             let i = self.manager.current_index.load(Ordering::SeqCst);
             self.current_node = self.manager.current_list.lock().await[i].clone();
             let _ = self.current_node.add_probe(false).await.ok();
+
+            if let Some(seek) = resume.filter(|s| *s < self.current_node.out) {
+                self.current_node.seek = seek;
+                self.current_node.cmd = Some(seek_and_length(&config, &mut self.current_node));
+            }
+
+            add_loudness(&self.manager, &config, &mut self.current_node).await;
+            self.current_node.skip = self.is_blocked(&config).await;
             self.current_node
                 .add_filter(&config, &self.manager.filter_chain)
                 .await;
             self.current_node.begin = Some(time_in_seconds(&config.channel.timezone));
+            self.add_history(&config).await;
             self.manager.current_index.fetch_add(1, Ordering::SeqCst);
         } else {
             if config.storage.shuffle {
@@ -166,10 +471,13 @@ impl FolderSource {
                 None => return None,
             };
             let _ = self.current_node.add_probe(false).await.ok();
+            add_loudness(&self.manager, &config, &mut self.current_node).await;
+            self.current_node.skip = self.is_blocked(&config).await;
             self.current_node
                 .add_filter(&config, &self.manager.filter_chain)
                 .await;
             self.current_node.begin = Some(time_in_seconds(&config.channel.timezone));
+            self.add_history(&config).await;
             self.manager.current_index.store(1, Ordering::SeqCst);
         }
 
@@ -8,6 +8,7 @@ use tokio::{
 
 use crate::utils::{
     config::{PlayoutConfig, FFMPEG_IGNORE_ERRORS, FFMPEG_UNRECOVERABLE_ERRORS},
+    ffmpeg_bundle,
     logging::{log_line, Target},
 };
 use crate::vec_strings;
@@ -138,7 +139,7 @@ pub async fn ingest_server(
         let proc_ctl = manager.clone();
         let level = config.logging.ingest_level.clone();
         let ignore = config.logging.ignore_lines.clone();
-        let mut server_proc = Command::new("ffmpeg")
+        let mut server_proc = Command::new(ffmpeg_bundle::ffmpeg())
             .args(server_cmd.clone())
             .kill_on_drop(true)
             .stdout(Stdio::piped())
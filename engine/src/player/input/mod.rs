@@ -1,5 +1,6 @@
 use log::*;
 
+pub mod failover;
 pub mod folder;
 pub mod ingest;
 pub mod playlist;
@@ -30,6 +31,7 @@ pub async fn source_generator(manager: ChannelManager) -> SourceIterator {
     let id = config.general.channel_id;
     let is_alive = manager.is_alive.clone();
     let current_list = manager.current_list.clone();
+    let current_index = manager.current_index.clone();
 
     match config.processing.mode {
         Folder => {
@@ -39,7 +41,9 @@ pub async fn source_generator(manager: ChannelManager) -> SourceIterator {
             // Spawn a task to monitor folder for file changes.
             {
                 let mut storage = manager.storage.lock().await;
-                storage.watchman(config_clone, is_alive, current_list).await;
+                storage
+                    .watchman(config_clone, is_alive, current_list, current_index)
+                    .await;
             }
 
             let folder_source = FolderSource::new(&config, manager);
@@ -4,26 +4,46 @@ use std::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
+    time::Instant,
 };
 
 use log::*;
+use serde_json::json;
 
 use crate::db::handles;
+use crate::file::utils::filler::add_probes;
 use crate::player::{
-    controller::ChannelManager,
+    controller::{ChannelManager, HoldMode},
+    input::failover::Failover,
     utils::{
-        gen_dummy, get_delta, is_close, is_remote,
+        blacklist, drift_correction, gap, gen_dummy, get_delta, is_close, is_live, is_remote,
         json_serializer::{read_json, set_defaults},
-        loop_filler, loop_image, modified_time,
-        probe::MediaProbe,
-        seek_and_length, time_in_seconds, JsonPlaylist, Media,
+        logo_path, loop_filler, loop_image,
+        loudness::add_loudness,
+        modified_time,
+        probe::{keyframe_before, MediaProbe},
+        rights,
+        script::{self, Hook},
+        seek_and_length, splice_index, time_in_seconds, JsonPlaylist, Media, Trigger,
     },
 };
 use crate::utils::{
     config::{PlayoutConfig, IMAGE_FORMAT},
+    events::EventKind,
     logging::Target,
+    playhead,
+    time_machine::time_now,
 };
 
+/// Filler length, before a dropped live source gets tried again.
+const LIVE_RETRY: f64 = 10.0;
+
+/// Length of a frozen frame, it repeats until the hold gets released.
+const HOLD_FREEZE: f64 = 60.0;
+
+/// Length of a slate or dummy, it repeats until a manual clip gets its take.
+const MANUAL_WAIT: f64 = 60.0;
+
 /// Struct for current playlist.
 ///
 /// Here we prepare the init clip and build a iterator where we pull our clips.
@@ -39,6 +59,12 @@ pub struct CurrentProgram {
     is_alive: Arc<AtomicBool>,
     last_json_path: Option<String>,
     last_node_ad: bool,
+    live_end: Option<f64>,
+    failover: Failover,
+    waiting: bool,
+    resume: bool,
+    /// Sources, which filled the current gap already.
+    gap_played: Vec<String>,
 }
 
 /// Prepare a playlist iterator.
@@ -61,6 +87,11 @@ impl CurrentProgram {
             is_alive,
             last_json_path: None,
             last_node_ad: false,
+            live_end: None,
+            failover: Failover::default(),
+            waiting: false,
+            resume: true,
+            gap_played: vec![],
         }
     }
 
@@ -75,7 +106,6 @@ impl CurrentProgram {
                 && self.json_playlist.modified != modified_time(&path).await
             {
                 info!(target: Target::file_mail(), channel = self.channel_id; "Reload playlist <b><magenta>{path}</></b>");
-                self.manager.list_init.store(true, Ordering::SeqCst);
                 get_current = true;
                 reload = true;
             }
@@ -85,6 +115,12 @@ impl CurrentProgram {
 
         let storage = &self.manager.storage.clone();
         if get_current {
+            let old_list = self.manager.current_list.lock().await.clone();
+            let last_playlist = (
+                self.json_playlist.path.clone(),
+                self.json_playlist.date.clone(),
+            );
+
             self.json_playlist = read_json(
                 storage,
                 &mut self.config,
@@ -96,6 +132,15 @@ impl CurrentProgram {
             )
             .await;
 
+            if self.json_playlist.path.is_none()
+                && (last_playlist.0.is_some() || last_playlist.1 != self.json_playlist.date)
+            {
+                self.manager.emit(
+                    EventKind::PlaylistMissing,
+                    json!({"date": self.json_playlist.date}),
+                );
+            }
+
             if !reload {
                 if let Some(file) = &self.json_playlist.path {
                     info!(target: Target::file_mail(), channel = self.channel_id; "Read playlist: <b><magenta>{file}</></b>");
@@ -128,6 +173,20 @@ impl CurrentProgram {
                 .await
                 .clone_from(&self.json_playlist.program);
 
+            if reload {
+                let index = self.manager.current_index.load(Ordering::SeqCst);
+
+                // keep the current clip and continue with the changed clips after it
+                match splice_index(&old_list, index, &self.json_playlist.program) {
+                    Some(next) if !self.manager.list_init.load(Ordering::SeqCst) => {
+                        debug!(target: Target::file_mail(), channel = self.channel_id; "Splice changed playlist, continue with clip <yellow>{next}</>");
+
+                        self.manager.current_index.store(next, Ordering::SeqCst);
+                    }
+                    _ => self.manager.list_init.store(true, Ordering::SeqCst),
+                }
+            }
+
             if self.json_playlist.path.is_none() {
                 trace!("missing playlist");
 
@@ -195,6 +254,16 @@ impl CurrentProgram {
                 info!(target: Target::file_mail(), channel = self.channel_id; "Read next playlist: <b><magenta>{file}</></b>");
             }
 
+            if let Some(first) = self.json_playlist.program.first_mut() {
+                script::run(
+                    &self.config,
+                    Hook::Midnight,
+                    &self.json_playlist.date,
+                    first,
+                )
+                .await;
+            }
+
             self.manager.list_init.store(false, Ordering::SeqCst);
             self.set_status(&Some(self.json_playlist.date.clone()), 0.0)
                 .await;
@@ -236,20 +305,27 @@ impl CurrentProgram {
         };
     }
 
-    // Check if last and/or next clip is a advertisement.
+    /// Let the script of the channel change the clip, before it gets prepared.
+    async fn run_hook(&self, hook: Hook, node: &mut Media) {
+        let date = self.manager.current_date.lock().await.clone();
+
+        script::run(&self.config, hook, &date, node).await;
+    }
+
+    // Check if last and/or next clip is a advertisement, or has another logo.
     async fn last_next_ad(&mut self, node: &mut Media) {
         let index = self.manager.current_index.load(Ordering::SeqCst);
         let current_list = self.manager.current_list.lock().await;
+        let logo = logo_path(&self.config, node);
 
-        if index + 1 < current_list.len() && &current_list[index + 1].category == "advertisement" {
-            node.next_ad = true;
+        if index + 1 < current_list.len() {
+            node.next_ad = &current_list[index + 1].category == "advertisement";
+            node.logo_out = logo_path(&self.config, &current_list[index + 1]) != logo;
         }
 
-        if index > 0
-            && index < current_list.len()
-            && &current_list[index - 1].category == "advertisement"
-        {
-            node.last_ad = true;
+        if index > 0 && index < current_list.len() {
+            node.last_ad = &current_list[index - 1].category == "advertisement";
+            node.logo_in = logo_path(&self.config, &current_list[index - 1]) != logo;
         }
     }
 
@@ -265,8 +341,55 @@ impl CurrentProgram {
         time_sec
     }
 
+    // After start continue at the saved playhead, when it belongs to the current playlist.
+    // The playlist gets shifted, so that the clip from the playhead is the current one.
+    async fn resume_playhead(&mut self) {
+        if !std::mem::take(&mut self.resume) {
+            return;
+        }
+
+        let Some(playhead) = playhead::load(&self.manager)
+            .await
+            .filter(|p| p.date == self.json_playlist.date)
+        else {
+            return;
+        };
+
+        let Some(node) = self
+            .manager
+            .current_list
+            .lock()
+            .await
+            .get(playhead.node_index as usize)
+            .filter(|n| n.source == playhead.source)
+            .cloned()
+        else {
+            return;
+        };
+
+        let position =
+            playhead.position + playhead::elapsed(&playhead, &self.config.channel.timezone);
+
+        // the clip would be over by now, the clock decides
+        if position < node.seek || position >= node.out {
+            return;
+        }
+
+        let shift = node.begin.unwrap_or_default() + position - node.seek - self.get_current_time();
+
+        info!(target: Target::file_mail(), channel = self.channel_id;
+            "Resume at clip <yellow>{}</>, position <yellow>{position:.3}</>: <b><magenta>{}</></b>",
+            playhead.node_index, playhead.source
+        );
+
+        self.set_status(&Some(self.json_playlist.date.clone()), shift)
+            .await;
+    }
+
     // On init or reload we need to seek for the current clip.
     async fn get_current_clip(&mut self) {
+        self.resume_playhead().await;
+
         let mut time_sec = self.get_current_time();
         let shift = self.manager.channel.lock().await.time_shift;
 
@@ -315,7 +438,20 @@ impl CurrentProgram {
             node_clone.seek += time_sec
                 - (node_clone.begin.unwrap() - self.manager.channel.lock().await.time_shift);
 
+            // seek from the keyframe, so the init clip starts at the exact position
+            if node_clone.seek > 0.5
+                && node_clone.out <= node_clone.duration
+                && Path::new(&node_clone.source).is_file()
+            {
+                let probe_time = Instant::now();
+
+                node_clone.keyframe = keyframe_before(&node_clone.source, node_clone.seek).await;
+                // the clock runs on while probing
+                node_clone.seek += probe_time.elapsed().as_secs_f64();
+            }
+
             self.last_next_ad(&mut node_clone).await;
+            self.run_hook(Hook::BeforeItem, &mut node_clone).await;
 
             self.manager.current_index.fetch_add(1, Ordering::SeqCst);
 
@@ -326,6 +462,7 @@ impl CurrentProgram {
                 .source
                 .contains(&self.config.channel.storage.to_string_lossy().to_string())
                 || self.current_node.source.contains("color=c=#121212")
+                || self.failover.is_active()
             {
                 is_filler = true;
             }
@@ -343,7 +480,8 @@ impl CurrentProgram {
         media.out = total_delta;
 
         self.last_next_ad(&mut media).await;
-        self.gen_source(media, 0).await;
+        self.run_hook(Hook::Gap, &mut media).await;
+        self.gap_source(media, 0).await;
 
         self.manager
             .current_list
@@ -380,7 +518,11 @@ impl CurrentProgram {
         }
 
         self.json_playlist.start_sec = Some(time_sec);
-        set_defaults(&mut self.json_playlist);
+        set_defaults(
+            &mut self.json_playlist,
+            self.config.processing.crossfade,
+            self.config.processing.fps,
+        );
         self.manager
             .current_list
             .lock()
@@ -486,6 +628,16 @@ impl CurrentProgram {
                     return;
                 }
             }
+
+            let correction = drift_correction(
+                &mut node,
+                shifted_delta,
+                self.config.general.drift_correction,
+            );
+
+            if correction != 0.0 {
+                debug!(target: Target::file_mail(), channel = self.channel_id; "Drift correction: <yellow>{correction:.3}</> seconds");
+            }
         }
 
         if (total_delta > node.out - node.seek && !last)
@@ -509,6 +661,82 @@ impl CurrentProgram {
         self.current_node = node;
     }
 
+    /// When a live source drops before its scheduled end, fill the gap with filler.
+    ///
+    /// The filler sets list_init, so afterwards the current playlist clip gets initialized again,
+    /// which tries the live source for the remaining time, or continues with the next clip.
+    async fn live_fallback(&mut self) -> bool {
+        let Some(live_end) = self.live_end.take() else {
+            return false;
+        };
+        let time_sec = self.get_current_time();
+        let remaining = live_end - time_sec;
+
+        if remaining < 1.0 {
+            return false;
+        }
+
+        warn!(target: Target::file_mail(), channel = self.channel_id;
+            "Live source <b><magenta>{}</></b> dropped, <yellow>{remaining:.2}</> seconds before end",
+            self.current_node.source
+        );
+
+        let index = self.manager.current_index.load(Ordering::SeqCst);
+        let mut media = Media::new(index, "", false).await;
+        media.begin = Some(time_sec);
+        media.duration = remaining.min(LIVE_RETRY);
+        media.out = media.duration;
+
+        self.run_hook(Hook::Gap, &mut media).await;
+        self.gen_source(media, 0).await;
+
+        true
+    }
+
+    /// Fill a gap in the playlist, with the gap policy for its length.
+    ///
+    /// Without a matching policy, or when a hook has set a source, the clip goes as it is to
+    /// `gen_source`, which takes the filler for an empty source.
+    async fn gap_source(&mut self, mut node: Media, last_index: usize) {
+        let gap = node.out - node.seek;
+
+        if let Some(policy) = gap::policy(&self.config.playlist.gap_rules, gap)
+            .filter(|_| node.source.is_empty())
+            .cloned()
+        {
+            match gap::fill(
+                &self.manager,
+                &self.config,
+                &policy,
+                gap,
+                self.json_playlist.program.last(),
+                &self.gap_played,
+            )
+            .await
+            {
+                Some((source, out)) => {
+                    info!(target: Target::file_mail(), channel = self.channel_id;
+                        "Fill gap of <yellow>{gap:.2}</> seconds with <b><magenta>{source}</></b>, policy: <yellow>{policy}</>"
+                    );
+
+                    self.gap_played.push(source.clone());
+
+                    node.source = source;
+                    node.seek = 0.0;
+                    node.out = out;
+                    node.duration = out;
+                }
+                None => {
+                    warn!(target: Target::file_mail(), channel = self.channel_id;
+                        "Gap policy <yellow>{policy}</> has nothing to play, use filler"
+                    );
+                }
+            }
+        }
+
+        self.gen_source(node, last_index).await;
+    }
+
     /// Generate the source CMD, or when clip not exist, get a dummy.
     pub async fn gen_source(&mut self, mut node: Media, last_index: usize) {
         let node_index = node.index.unwrap_or_default();
@@ -544,8 +772,20 @@ impl CurrentProgram {
             };
         }
 
+        let blacklisted = blacklist::is_listed(&self.manager, &node.source).await;
+        let out_of_rights = !blacklisted
+            && rights::is_blocked(
+                &self.config,
+                &node.source,
+                &time_now(&self.config.channel.timezone),
+                duration,
+            )
+            .await;
+
         // separate if condition, because of node.add_probe() in last condition
-        if node.probe.is_some() {
+        if node.probe.is_some() && !blacklisted && !out_of_rights {
+            self.failover.recover(&self.manager);
+
             if node
                 .source
                 .rsplit_once('.')
@@ -560,17 +800,34 @@ impl CurrentProgram {
                     self.duplicate_for_seek_and_loop(&mut node).await;
                 }
 
+                // clips with SOM start at the exact frame, the trim filter cuts from the keyframe
+                if node.som.is_some()
+                    && node.keyframe.is_none()
+                    && node.seek > 0.5
+                    && Path::new(&node.source).is_file()
+                {
+                    node.keyframe = keyframe_before(&node.source, node.seek).await;
+                }
+
                 node.cmd = Some(seek_and_length(&self.config, &mut node));
             }
+        } else if let Some(failover_node) = self.failover_source(&node, duration).await {
+            // Set list_init to true, to check the primary source after this clip.
+            self.manager.list_init.store(true, Ordering::SeqCst);
+
+            node = failover_node;
         } else {
             trace!("clip index: {node_index} | last index: {last_index}");
+            let missing_source = node.source.clone();
 
             // Last index is the index from the last item from the node list.
-            if node_index < last_index {
+            if blacklisted {
+                warn!(target: Target::file_mail(), channel = self.channel_id; "Source is blacklisted: <b><magenta>{}</></b>", node.source);
+            } else if !out_of_rights && node_index < last_index {
                 error!(target: Target::file_mail(), channel = self.channel_id; "Source not found: <b><magenta>{}</></b>", node.source);
             }
 
-            let fillers = self.manager.filler_list.lock().await;
+            let mut fillers = self.manager.filler_list.lock().await;
 
             // Set list_init to true, to stay in sync.
             self.manager.list_init.store(true, Ordering::SeqCst);
@@ -584,22 +841,36 @@ impl CurrentProgram {
                 .await
                 && !fillers.is_empty()
             {
-
                 let mut index = self.manager.filler_index.fetch_add(1, Ordering::SeqCst);
 
                 if index > fillers.len() - 1 {
                     index = 0;
                 }
 
-                let mut filler_media = fillers[index].clone();
-
-                trace!("take filler: {}", filler_media.source);
-
                 if index == fillers.len() - 1 {
                     // reset index for next round
                     self.manager.filler_index.store(0, Ordering::SeqCst);
                 }
 
+                if node.duration > 0.0 {
+                    // take the filler, which fits the missing time best, to avoid cuts
+                    add_probes(&mut fillers, self.channel_id).await;
+
+                    let clips = fillers
+                        .iter()
+                        .map(|f| (f.source.clone(), f.duration))
+                        .collect::<Vec<_>>();
+
+                    if let Some(i) = gap::best_fit(&clips, duration, &self.gap_played) {
+                        index = i;
+                    }
+                }
+
+                let mut filler_media = fillers[index].clone();
+
+                trace!("take filler: {}", filler_media.source);
+                self.gap_played.push(filler_media.source.clone());
+
                 if filler_media.probe.is_none() {
                     if let Err(e) = filler_media.add_probe(false).await {
                         error!(target: Target::file_mail(), channel = self.channel_id; "{e:?}");
@@ -684,13 +955,45 @@ impl CurrentProgram {
                 }
             }
 
+            node.transition = None;
+
             warn!(
                 target: Target::file_mail(), channel = self.channel_id;
                 "Generate filler with <yellow>{:.2}</> seconds length!",
                 node.out
             );
+
+            self.manager.emit(
+                EventKind::Filler,
+                json!({"missing": missing_source, "source": node.source, "duration": node.out}),
+            );
+        }
+
+        if let Some(mut transition) = node.transition.take() {
+            // the next clip needs to be known, for fading all its streams
+            match MediaProbe::new(&transition.source).await {
+                Ok(probe) => {
+                    transition.video = !probe.video.is_empty();
+                    transition.audio_streams = probe.audio.len();
+                    node.transition = Some(transition);
+                }
+                Err(e) => {
+                    warn!(target: Target::file_mail(), channel = self.channel_id;
+                        "No crossfade into <b><magenta>{}</></b>: {e}", transition.source
+                    );
+                }
+            }
+        }
+
+        let stinger = &self.config.transition;
+
+        if !stinger.stinger_path.is_empty() && Path::new(&stinger.stinger_path).is_file() {
+            // the cut gets covered only, when the previous clip has played the stinger head
+            node.stinger_in = self.current_node.stinger_out;
+            node.stinger_out = node.out - node.seek > stinger.stinger_offset;
         }
 
+        add_loudness(&self.manager, &self.config, &mut node).await;
         node.add_filter(&self.config, &self.manager.filter_chain.clone()) // to-do : for fillter announcement
             .await;
 
@@ -704,6 +1007,141 @@ impl CurrentProgram {
         self.current_node = node;
     }
 
+    /// Repeat the current clip, or freeze on its last frame, as long as the hold is active.
+    ///
+    /// The playlist does not move on meanwhile, the release jumps to the next clip
+    /// and saves the time shift.
+    async fn hold_source(&mut self, mode: HoldMode) {
+        let index = self.current_node.index.unwrap_or_default();
+        let list = self.manager.current_list.lock().await;
+        let last_index = list.len().saturating_sub(1);
+        let mut node = list
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| self.current_node.clone());
+
+        drop(list);
+
+        node.transition = None;
+        node.skip = false;
+        self.live_end = None;
+
+        if mode == HoldMode::Loop || is_remote(&node.source) {
+            debug!(target: Target::file_mail(), channel = self.channel_id; "Hold, loop clip <b><magenta>{}</></b>", node.source);
+
+            self.gen_source(node, last_index).await;
+
+            return;
+        }
+
+        debug!(target: Target::file_mail(), channel = self.channel_id; "Hold, freeze last frame of <b><magenta>{}</></b>", node.source);
+
+        if node.probe.is_none() {
+            if let Err(e) = node.add_probe(false).await {
+                trace!("{e:?}");
+            };
+        }
+
+        // one frame, cloned by the pad filter
+        node.seek = (node.out - 1.0 / self.config.processing.fps).max(0.0);
+        node.keyframe = None;
+        node.custom_filter = format!(
+            "tpad=stop_mode=clone:stop_duration={HOLD_FREEZE}[c_v_out];apad=pad_dur={HOLD_FREEZE}[c_a_out]"
+        );
+        node.cmd = Some(seek_and_length(&self.config, &mut node));
+        node.add_filter(&self.config, &self.manager.filter_chain.clone())
+            .await;
+
+        self.current_node = node;
+    }
+
+    /// Filler, or the slate from the filler path, while a manual clip waits for the take.
+    async fn wait_source(&mut self, index: usize) {
+        let fillers = self.manager.filler_list.lock().await.clone();
+        let mut node = if fillers.is_empty() {
+            let slate = self
+                .config
+                .storage
+                .filler_path
+                .to_string_lossy()
+                .to_string();
+
+            Media::new(index, &slate, true).await
+        } else {
+            let i = self.manager.filler_index.fetch_add(1, Ordering::SeqCst) % fillers.len();
+            let mut filler = fillers[i].clone();
+
+            if filler.probe.is_none() {
+                if let Err(e) = filler.add_probe(false).await {
+                    trace!("{e:?}");
+                };
+            }
+
+            filler
+        };
+
+        node.index = Some(index);
+        node.seek = 0.0;
+        node.skip = false;
+        node.transition = None;
+        self.live_end = None;
+
+        let is_image = Path::new(&node.source)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .filter(|e| IMAGE_FORMAT.contains(&e.as_str()))
+            .is_some();
+
+        if node.probe.is_none() {
+            let (source, cmd) = gen_dummy(&self.config, MANUAL_WAIT);
+
+            node.source = source;
+            node.cmd = Some(cmd);
+            node.out = MANUAL_WAIT;
+            node.duration = MANUAL_WAIT;
+        } else if is_image {
+            node.out = MANUAL_WAIT;
+            node.cmd = Some(loop_image(&self.config, &node));
+        } else {
+            node.out = node.duration;
+            node.cmd = Some(seek_and_length(&self.config, &mut node));
+        }
+
+        node.add_filter(&self.config, &self.manager.filter_chain.clone())
+            .await;
+
+        self.current_node = node;
+    }
+
+    /// Failover clip, when the playlist is missing or the storage is not reachable.
+    async fn failover_source(&mut self, node: &Media, duration: f64) -> Option<Media> {
+        if !self.config.failover.enable {
+            return None;
+        }
+
+        let storage_lost = !self
+            .manager
+            .storage
+            .lock()
+            .await
+            .is_dir(&self.config.channel.storage)
+            .await;
+
+        if self.json_playlist.path.is_some() && !storage_lost {
+            return None;
+        }
+
+        self.failover
+            .next(
+                &self.manager,
+                &self.config,
+                node.index.unwrap_or_default(),
+                node.begin.unwrap_or_default(),
+                duration,
+            )
+            .await
+    }
+
     async fn duplicate_for_seek_and_loop(&mut self, node: &mut Media) {
         let mut nodes = self.manager.current_list.lock().await;
         let index = node.index.unwrap_or_default();
@@ -740,11 +1178,26 @@ impl CurrentProgram {
 /// Build the playlist iterator
 impl CurrentProgram {
     pub async fn next(&mut self) -> Option<Media> {
+        let hold = *self.manager.hold.lock().await;
+
+        if let Some(mode) = hold {
+            self.hold_source(mode).await;
+
+            return Some(self.current_node.clone());
+        }
+
+        // config changes, which need no restart
+        self.config.apply_live(&*self.manager.config.lock().await);
+
         self.last_json_path.clone_from(&self.json_playlist.path);
         self.last_node_ad = self.current_node.last_ad;
         self.check_for_playlist(self.manager.list_init.load(Ordering::SeqCst))
             .await;
 
+        if !self.manager.list_init.load(Ordering::SeqCst) && self.live_fallback().await {
+            return Some(self.current_node.clone());
+        }
+
         if self.manager.list_init.load(Ordering::SeqCst) {
             trace!("Init playlist, from next iterator");
             let init_clip_is_filler = match self.json_playlist.path {
@@ -777,8 +1230,9 @@ impl CurrentProgram {
                 media.out = total_delta;
 
                 self.last_next_ad(&mut media).await;
+                self.run_hook(Hook::Gap, &mut media).await;
 
-                self.gen_source(media, last_index).await;
+                self.gap_source(media, last_index).await;
             }
         } else if self.manager.current_index.load(Ordering::SeqCst)
             < self.manager.current_list.lock().await.len()
@@ -793,11 +1247,30 @@ impl CurrentProgram {
 
             drop(node_list);
 
+            if node.trigger == Trigger::Manual
+                && (!self.waiting || self.manager.manual_wait.load(Ordering::SeqCst))
+            {
+                if !self.waiting {
+                    info!(target: Target::file_mail(), channel = self.channel_id; "Wait for take of <b><magenta>{}</></b>", node.source);
+
+                    self.waiting = true;
+                    self.manager.manual_wait.store(true, Ordering::SeqCst);
+                }
+
+                self.wait_source(index).await;
+
+                return Some(self.current_node.clone());
+            }
+
+            self.waiting = false;
+            self.gap_played.clear();
+
             if index == last_index {
                 is_last = true;
             }
 
             self.last_next_ad(&mut node).await;
+            self.run_hook(Hook::BeforeItem, &mut node).await;
             self.timed_source(node, is_last, last_index).await;
 
             self.manager.current_index.fetch_add(1, Ordering::SeqCst);
@@ -828,14 +1301,19 @@ impl CurrentProgram {
             }
 
             self.manager.current_index.store(0, Ordering::SeqCst);
+            self.gap_played.clear();
             self.last_next_ad(&mut first_node).await;
             first_node.last_ad = self.last_node_ad;
+            self.run_hook(Hook::BeforeItem, &mut first_node).await;
 
             self.gen_source(first_node, 0).await;
 
             self.manager.current_index.store(1, Ordering::SeqCst);
         }
 
+        self.live_end = (is_live(&self.current_node.source) && self.current_node.cmd.is_some())
+            .then(|| self.get_current_time() + self.current_node.out - self.current_node.seek);
+
         Some(self.current_node.clone())
     }
 }
@@ -0,0 +1,160 @@
+use std::process::Stdio;
+
+use log::*;
+use tokio::process::{Child, Command};
+
+use crate::player::filter::v_drawtext;
+use crate::utils::{
+    config::{PlayoutConfig, VideoMode},
+    errors::ServiceError,
+    ffmpeg_bundle,
+    logging::{fmt_cmd, Target},
+};
+use crate::vec_strings;
+
+/// SDI embeds 2, 8 or 16 audio channels.
+fn sdi_channels(channels: i32) -> i32 {
+    match channels {
+        0..=2 => 2,
+        3..=8 => 8,
+        _ => 16,
+    }
+}
+
+/// Merge all audio tracks to one stream, with a channel count the card supports.
+///
+/// The tracks are placed one after another, unused channels stay silent.
+fn audio_filter(config: &PlayoutConfig) -> String {
+    let tracks = if config.processing.audio_track_index == -1 {
+        config.processing.audio_tracks
+    } else {
+        1
+    };
+    let channels = tracks * config.processing.audio_channels as i32;
+    let target = sdi_channels(channels);
+    let mut filter = (0..tracks)
+        .map(|i| format!("[0:a:{i}]"))
+        .collect::<String>();
+
+    if tracks > 1 {
+        filter.push_str(&format!("amerge=inputs={tracks},"));
+    }
+
+    if channels == target {
+        filter.push_str("anull[aout]");
+    } else {
+        let map = (0..channels.min(target))
+            .map(|c| format!("|c{c}=c{c}"))
+            .collect::<String>();
+
+        filter.push_str(&format!("pan={target}c{map}[aout]"));
+    }
+
+    filter
+}
+
+/// Get device name, when the device is given by its index.
+async fn device_name(device: &str) -> String {
+    let Ok(index) = device.parse::<usize>() else {
+        return device.to_string();
+    };
+
+    let Ok(output) = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(["-hide_banner", "-sinks", "decklink"])
+        .output()
+        .await
+    else {
+        return device.to_string();
+    };
+
+    // list entries looks like: "  DeckLink Mini Monitor [DeckLink Mini Monitor]"
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| l.starts_with(' ') || l.starts_with('*'))
+        .filter_map(|l| {
+            l.trim_start_matches(['*', ' '])
+                .rsplit_once(" [")
+                .map(|(name, _)| name.to_string())
+        })
+        .nth(index)
+        .unwrap_or(device.to_string())
+}
+
+/// DeckLink Output
+///
+/// Play out to a Blackmagic DeckLink card. The card clocks the output,
+/// so the input is not read in realtime.
+pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, ServiceError> {
+    let id = config.general.channel_id;
+    let mut enc_cmd = vec_strings![
+        "-hide_banner",
+        "-nostats",
+        "-progress",
+        "pipe:2",
+        "-v",
+        log_format
+    ];
+    let mut video_filter = "[0:v]".to_string();
+
+    if VideoMode::new(&config.output.decklink_mode).is_none() {
+        return Err(ServiceError::Conflict(format!(
+            "Unknown DeckLink video mode: {}",
+            config.output.decklink_mode
+        )));
+    }
+
+    if let Some(input_cmd) = &config.advanced.encoder.input_cmd {
+        enc_cmd.append(&mut input_cmd.clone());
+    }
+
+    enc_cmd.append(&mut vec_strings!["-i", "pipe:0"]);
+
+    if config.text.add_text && !config.text.text_from_filename {
+        if let Some(socket) = config.text.zmq_stream_socket.clone() {
+            debug!(target: Target::file_mail(), channel = id;
+                "Using drawtext filter, listening on address: <yellow>{}</>",
+                socket
+            );
+
+            video_filter.push_str(&v_drawtext::filter_node(config, None, &None).await);
+            video_filter.push(',');
+        }
+    }
+
+    if let Some(order) = VideoMode::new(&config.output.decklink_mode).and_then(|m| m.field_order) {
+        video_filter.push_str(&format!("setfield={}ff,", &order[..1]));
+    }
+
+    video_filter.push_str("format=uyvy422[vout]");
+
+    enc_cmd.append(&mut vec_strings![
+        "-filter_complex",
+        format!("{video_filter};{}", audio_filter(config)),
+        "-map",
+        "[vout]",
+        "-map",
+        "[aout]"
+    ]);
+
+    let mut output_cmd = config.output.output_cmd.clone().unwrap_or_default();
+
+    if let Some(device) = output_cmd.last_mut() {
+        *device = device_name(device).await;
+    }
+
+    enc_cmd.append(&mut output_cmd);
+
+    debug!(target: Target::file_mail(), channel = id;
+        "Encoder CMD: <bright-blue>ffmpeg {}</>",
+        fmt_cmd(&enc_cmd)
+    );
+
+    let child = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(enc_cmd)
+        .kill_on_drop(true)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    Ok(child)
+}
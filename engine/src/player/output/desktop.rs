@@ -7,6 +7,7 @@ use crate::player::filter::v_drawtext;
 use crate::utils::errors::ServiceError;
 use crate::utils::{
     config::PlayoutConfig,
+    ffmpeg_bundle,
     logging::{fmt_cmd, Target},
 };
 use crate::vec_strings;
@@ -73,7 +74,7 @@ pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, S
         fmt_cmd(&enc_cmd)
     );
 
-    let child = Command::new("ffplay")
+    let child = Command::new(ffmpeg_bundle::ffplay())
         .args(enc_cmd)
         .kill_on_drop(true)
         .stdin(Stdio::piped())
@@ -17,27 +17,40 @@ out:
 
 */
 
-use std::{process::Stdio, sync::atomic::Ordering};
+use std::{
+    process::Stdio,
+    sync::{atomic::Ordering, Arc},
+};
 
+use chrono::Local;
 use log::*;
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
     process::Command,
+    sync::Mutex,
+    time::Instant,
 };
 
-use crate::utils::{logging::log_line, task_runner};
+use crate::utils::{control::send_now_next, ffmpeg_bundle, logging::log_line, task_runner};
 use crate::vec_strings;
 use crate::{
+    file::utils::{cache::MediaCache, login},
     player::{
         controller::{ChannelManager, ProcessUnit::*},
         input::source_generator,
+        output::hls_key,
         utils::{
-            get_delta, is_free_tcp_port, prepare_output_cmd, sec_to_time, stderr_reader,
-            valid_stream, Media,
+            blacklist,
+            detect::Detector,
+            get_delta, is_free_tcp_port, prepare_output_cmd,
+            progress::Progress,
+            scte35::{cue_playlist, hls_cue_writer, redirect_playlist, CueEvent},
+            sec_to_time, stderr_reader, subtitle, valid_stream, Media,
         },
     },
     utils::{
         errors::ServiceError,
+        events::{clip_data, clip_start_data, EventKind},
         logging::{fmt_cmd, Target},
     },
 };
@@ -87,7 +100,8 @@ async fn ingest_writer(manager: ChannelManager) -> Result<(), ServiceError> {
         }
     }
 
-    let server_cmd = prepare_output_cmd(&config, server_prefix.clone(), &dummy_media.filter);
+    let mut server_cmd = prepare_output_cmd(&config, server_prefix.clone(), &dummy_media.filter);
+    redirect_playlist(&config, &mut server_cmd);
     let mut is_running;
 
     if let Some(url) = stream_input.iter().find(|s| s.contains("://")) {
@@ -119,7 +133,7 @@ async fn ingest_writer(manager: ChannelManager) -> Result<(), ServiceError> {
 
     loop {
         let mut level = &config.logging.ffmpeg_level;
-        let mut server_proc = Command::new("ffmpeg")
+        let mut server_proc = Command::new(ffmpeg_bundle::ffmpeg())
             .args(server_cmd.clone())
             .stderr(Stdio::piped())
             .kill_on_drop(true)
@@ -169,18 +183,28 @@ async fn ingest_writer(manager: ChannelManager) -> Result<(), ServiceError> {
     Ok(())
 }
 
-async fn write(manager: &ChannelManager, ff_log_format: &str) -> Result<(), ServiceError> {
+async fn write(
+    manager: &ChannelManager,
+    ff_log_format: &str,
+    cues: Arc<Mutex<Vec<CueEvent>>>,
+) -> Result<(), ServiceError> {
     let config = manager.config.lock().await.clone();
     let get_source = source_generator(manager.clone());
     let ingest_is_alive = manager.ingest_is_alive.clone();
     let id = config.general.channel_id;
     let current_media = manager.current_media.clone();
     let is_alive = manager.is_alive.clone();
+    let media_cache = MediaCache::new(&config);
 
     let mut get_source = get_source.await;
 
-    while let Some(node) = get_source.next().await {
+    while let Some(mut node) = get_source.next().await {
         *current_media.lock().await = Some(node.clone());
+        manager.clip_started.lock().await.take();
+
+        if let Some(cache) = &media_cache {
+            cache.process(manager, &mut node).await;
+        }
         let ignore = config.logging.ignore_lines.clone();
 
         if !is_alive.load(Ordering::SeqCst) {
@@ -202,6 +226,34 @@ async fn write(manager: &ChannelManager, ff_log_format: &str) -> Result<(), Serv
             node.key
         );
 
+        *manager.clip_started.lock().await = Some(Instant::now());
+        manager.emit(EventKind::ClipStart, clip_start_data(manager, &node).await);
+        manager.proof.capture(&config, &node.source).await;
+
+        if config.text.now_next {
+            tokio::spawn(send_now_next(manager.clone(), node.clone()));
+        }
+
+        if let Some(cue) = &node.scte35 {
+            let event = CueEvent::new(cue, Local::now());
+
+            info!(target: Target::file_mail(), channel = id;
+                "SCTE-35 cue <yellow>{}</> at <yellow>{}</>, break duration <yellow>{}</>",
+                event.event_id,
+                event.start.format("%H:%M:%S%.3f"),
+                sec_to_time(event.duration)
+            );
+
+            if cue_playlist(&config).is_some() {
+                cues.lock().await.push(event);
+            } else {
+                error!(target: Target::file_mail(), channel = id;
+                    "SCTE-35 cue <yellow>{}</> is not written, HLS ladders with one playlist per rendition are not supported",
+                    event.event_id
+                );
+            }
+        }
+
         if config.task.enable {
             if config.task.path.is_file() {
                 let manager3 = manager.clone();
@@ -215,7 +267,15 @@ async fn write(manager: &ChannelManager, ff_log_format: &str) -> Result<(), Serv
             }
         }
 
-        let mut dec_prefix = vec_strings!["-hide_banner", "-nostats", "-v", &ff_log_format];
+        // in HLS mode this process is also the encoder
+        let mut dec_prefix = vec_strings![
+            "-hide_banner",
+            "-nostats",
+            "-progress",
+            "pipe:2",
+            "-v",
+            &ff_log_format
+        ];
 
         if let Some(decoder_input_cmd) = &config.advanced.decoder.input_cmd {
             dec_prefix.append(&mut decoder_input_cmd.clone());
@@ -239,16 +299,20 @@ async fn write(manager: &ChannelManager, ff_log_format: &str) -> Result<(), Serv
 
         insert_readrate(&mut cmd, read_rate);
 
+        let mut subtitle_cmd = subtitle::output_cmd(&config, &node, &cmd);
+
         dec_prefix.append(&mut cmd);
-        let dec_cmd = prepare_output_cmd(&config, dec_prefix, &node.filter);
+        dec_prefix.append(&mut subtitle_cmd);
+        let mut dec_cmd = prepare_output_cmd(&config, dec_prefix, &node.filter);
+        redirect_playlist(&config, &mut dec_cmd);
 
         debug!(target: Target::file_mail(), channel = id;
             "HLS writer CMD: <bright-blue>ffmpeg {}</>",
             fmt_cmd(&dec_cmd)
         );
 
-        let mut dec_proc = Command::new("ffmpeg")
-            .args(dec_cmd)
+        let mut dec_proc = Command::new(ffmpeg_bundle::ffmpeg())
+            .args(login::args(dec_cmd))
             .kill_on_drop(true)
             .stderr(Stdio::piped())
             .spawn()?;
@@ -256,9 +320,36 @@ async fn write(manager: &ChannelManager, ff_log_format: &str) -> Result<(), Serv
         let dec_err = BufReader::new(dec_proc.stderr.take().unwrap());
         *manager.decoder.lock().await = Some(dec_proc);
 
-        stderr_reader(dec_err, ignore, Decoder, id).await?;
+        let result = stderr_reader(
+            dec_err,
+            ignore,
+            Decoder,
+            id,
+            Detector::new(manager, &config, &node),
+            Some(Progress::new(manager).await),
+        )
+        .await;
+
+        match result {
+            Ok(_) => {
+                manager.wait(Decoder).await;
+                blacklist::played(manager).await;
+            }
+            Err(e) => {
+                if !blacklist::failed(manager, &node.source, &e.to_string()).await {
+                    return Err(e);
+                }
+
+                manager.wait(Decoder).await;
+                manager.list_init.store(true, Ordering::SeqCst);
+            }
+        }
+
+        manager.emit(EventKind::ClipEnd, clip_data(&node));
 
-        manager.wait(Decoder).await;
+        if manager.stop_after_current.load(Ordering::SeqCst) {
+            break;
+        }
 
         while ingest_is_alive.load(Ordering::SeqCst) {
             tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
@@ -275,6 +366,16 @@ pub async fn writer(manager: &ChannelManager, ff_log_format: &str) -> Result<(),
     let config = manager.config.lock().await.clone();
 
     let manager2 = manager.clone();
+    let cues = Arc::new(Mutex::new(vec![]));
+
+    tokio::spawn(hls_cue_writer(
+        config.clone(),
+        cues.clone(),
+        manager.is_alive.clone(),
+    ));
+
+    hls_key::prepare(&config).await?;
+    tokio::spawn(hls_key::rotation(config.clone(), manager.is_alive.clone()));
 
     let handle_ingest = if config.ingest.enable {
         // spawn a thread for ffmpeg ingest server
@@ -294,7 +395,7 @@ pub async fn writer(manager: &ChannelManager, ff_log_format: &str) -> Result<(),
             result?;
         }
 
-        result = write(manager, ff_log_format) => {
+        result = write(manager, ff_log_format, cues) => {
             result?;
         }
     }
@@ -0,0 +1,169 @@
+/*
+HLS encryption.
+
+Segments of the HLS output get encrypted with AES-128. The keys are written to `keys` in the
+channel public path, which is not served as static files. ffmpeg reads the key info file at
+start, and with key rotation before every segment, so a new key applies from the next segment.
+The players load the keys from the key URL, by default from the API.
+*/
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::*;
+use rand::Rng;
+use tokio::{fs, time::sleep};
+
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
+
+/// Key URL, when no template is configured.
+pub const DEFAULT_URL: &str = "/api/hls/{channel}/key/{key}";
+/// Keys are kept this long, so that older segments in the playlist stay playable.
+const KEY_RETENTION: Duration = Duration::from_secs(86400);
+
+/// Folder of the keys.
+pub fn dir(public: &Path) -> PathBuf {
+    public.join("keys")
+}
+
+/// Key info file, which ffmpeg reads.
+pub fn info_file(public: &Path) -> PathBuf {
+    dir(public).join("key_info")
+}
+
+/// Only plain key names are valid, like `key_1718000000.key`.
+pub fn is_key_name(name: &str) -> bool {
+    name.strip_prefix("key_")
+        .and_then(|n| n.strip_suffix(".key"))
+        .is_some_and(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// URL of a key, from the template with `{channel}` and `{key}` placeholders.
+pub fn url(config: &PlayoutConfig, name: &str) -> String {
+    let template = if config.output.hls_key_url.is_empty() {
+        DEFAULT_URL
+    } else {
+        &config.output.hls_key_url
+    };
+
+    template
+        .replace("{channel}", &config.general.channel_id.to_string())
+        .replace("{key}", name)
+}
+
+/// Content of the key info file: key URL, key file and optional IV.
+///
+/// Without IV, ffmpeg uses the sequence number of each segment.
+pub fn key_info(config: &PlayoutConfig, name: &str, iv: Option<&str>) -> String {
+    let file = dir(&config.channel.public).join(name);
+    let mut info = format!("{}\n{}\n", url(config, name), file.to_string_lossy());
+
+    if let Some(iv) = iv {
+        info.push_str(iv);
+        info.push('\n');
+    }
+
+    info
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Write a new key and point the key info file to it.
+pub async fn rotate(config: &PlayoutConfig) -> Result<String, ServiceError> {
+    let dir = dir(&config.channel.public);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let name = format!("key_{timestamp}.key");
+    let key: [u8; 16] = rand::rng().random();
+    let iv = if config.output.hls_key_iv == "random" {
+        let iv: [u8; 16] = rand::rng().random();
+        Some(hex(&iv))
+    } else {
+        None
+    };
+
+    fs::create_dir_all(&dir).await?;
+    fs::write(dir.join(&name), key).await?;
+
+    // ffmpeg can read the info file at any segment, so it gets replaced in one step
+    let tmp = dir.join("key_info.tmp");
+    fs::write(&tmp, key_info(config, &name, iv.as_deref())).await?;
+    fs::rename(&tmp, info_file(&config.channel.public)).await?;
+
+    debug!(target: Target::file_mail(), channel = config.general.channel_id;
+        "New HLS key <b><magenta>{name}</></b>"
+    );
+
+    Ok(name)
+}
+
+/// Remove keys, which are older than the retention time.
+async fn cleanup(config: &PlayoutConfig) -> Result<(), ServiceError> {
+    let mut entries = fs::read_dir(dir(&config.channel.public)).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if !is_key_name(&name) {
+            continue;
+        }
+
+        let age = entry
+            .metadata()
+            .await?
+            .modified()?
+            .elapsed()
+            .unwrap_or_default();
+
+        if age > KEY_RETENTION {
+            fs::remove_file(entry.path()).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Create the first key, before the HLS output starts.
+pub async fn prepare(config: &PlayoutConfig) -> Result<(), ServiceError> {
+    if config.output.hls_encryption {
+        rotate(config).await?;
+        cleanup(config).await?;
+    }
+
+    Ok(())
+}
+
+/// Write a new key at each rotation interval, as long as the channel is running.
+pub async fn rotation(config: PlayoutConfig, is_alive: Arc<AtomicBool>) {
+    if !config.output.hls_encryption || config.output.hls_key_rotation <= 0 {
+        return;
+    }
+
+    let interval = Duration::from_secs(config.output.hls_key_rotation as u64);
+
+    loop {
+        sleep(interval).await;
+
+        if !is_alive.load(Ordering::SeqCst) {
+            break;
+        }
+
+        if let Err(e) = rotate(&config).await {
+            error!(target: Target::file_mail(), channel = config.general.channel_id; "HLS key rotation: {e}");
+        }
+
+        if let Err(e) = cleanup(&config).await {
+            warn!(target: Target::file_mail(), channel = config.general.channel_id; "HLS key cleanup: {e}");
+        }
+    }
+}
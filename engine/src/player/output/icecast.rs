@@ -0,0 +1,105 @@
+/*
+Icecast output.
+
+Radio channels run without video pipeline, the encoder writes the audio as MPEG-TS
+stream to stdout. The relay copies it to a sender, which pushes it to the Icecast mount.
+Optional a second leg generates a video from cover image and a spectrum visualizer,
+for platforms which only take video streams.
+*/
+
+use shlex::split;
+
+use super::{relay::Leg, srt::url_encode};
+use crate::utils::config::PlayoutConfig;
+use crate::vec_strings;
+
+/// Build the Icecast url from config.
+pub fn icecast_url(config: &PlayoutConfig) -> String {
+    let output = &config.output;
+    let mount = output.icecast_mount.trim_start_matches('/');
+
+    format!(
+        "icecast://{}:{}@{}/{mount}",
+        url_encode(&output.icecast_username),
+        url_encode(&output.icecast_password),
+        output.icecast_address
+    )
+}
+
+/// Content type and muxer of the audio format.
+fn format(config: &PlayoutConfig) -> (&'static str, &'static str) {
+    match config.output.icecast_format.as_str() {
+        "aac" => ("audio/aac", "adts"),
+        "opus" => ("audio/ogg", "ogg"),
+        _ => ("audio/mpeg", "mp3"),
+    }
+}
+
+/// Output leg for the Icecast sender.
+pub fn leg(config: &PlayoutConfig) -> Leg {
+    let (content_type, muxer) = format(config);
+
+    Leg {
+        name: format!("Icecast {}", config.output.icecast_mount),
+        cmd: vec_strings![
+            "-map",
+            "0:a",
+            "-c",
+            "copy",
+            "-content_type",
+            content_type,
+            "-f",
+            muxer,
+            icecast_url(config)
+        ],
+        secret: Some(url_encode(&config.output.icecast_password)),
+        udp: None,
+    }
+}
+
+/// Output leg with generated video, cover art in the background and a spectrum at the bottom.
+pub fn video_leg(config: &PlayoutConfig) -> Option<Leg> {
+    let mut params = split(&config.output.icecast_video).filter(|p| !p.is_empty())?;
+    let (width, height) = (config.processing.width, config.processing.height);
+    let fps = config.processing.fps;
+
+    let mut cmd = if config.output.icecast_cover.is_empty() {
+        vec_strings![
+            "-f",
+            "lavfi",
+            "-i",
+            format!("color=c=black:s={width}x{height}:r={fps}")
+        ]
+    } else {
+        vec_strings![
+            "-loop",
+            "1",
+            "-framerate",
+            fps,
+            "-i",
+            config.output.icecast_cover
+        ]
+    };
+
+    cmd.append(&mut vec_strings![
+        "-filter_complex",
+        format!(
+            "[1:v]scale={width}:{height},setsar=1,format=yuv420p[bg];\
+            [0:a]showfreqs=s={width}x{}:mode=bar:ascale=log:fscale=log:colors=white,format=rgba[spec];\
+            [bg][spec]overlay=0:H-h:shortest=1[vout]",
+            height / 4
+        ),
+        "-map",
+        "[vout]",
+        "-map",
+        "0:a"
+    ]);
+    cmd.append(&mut params);
+
+    Some(Leg {
+        name: "Icecast video".to_string(),
+        cmd,
+        secret: None,
+        udp: None,
+    })
+}
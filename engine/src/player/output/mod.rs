@@ -1,33 +1,52 @@
-use std::{process::Stdio, sync::atomic::Ordering};
+use std::sync::{atomic::Ordering, Arc};
 
+use chrono::Local;
 use log::*;
 use tokio::{
     io::{AsyncReadExt, AsyncWriteExt, BufReader, BufWriter},
-    process::{ChildStdin, Command},
+    process::ChildStdin,
+    sync::Mutex,
+    time::Instant,
 };
 
+mod decklink;
 mod desktop;
 mod hls;
+pub mod hls_key;
+pub mod icecast;
 mod null;
+pub mod pipeline;
+pub mod preview_stream;
+pub mod proof;
+pub mod recorder;
+pub mod relay;
+mod srt;
 mod stream;
+pub mod whip;
 
+use crate::file::utils::cache::MediaCache;
 use crate::player::{
     controller::{ChannelManager, ProcessUnit::*},
     input::{ingest_server, source_generator},
-    utils::{sec_to_time, stderr_reader},
+    output::pipeline::PipelineBackend,
+    utils::{
+        blacklist, detect::Detector, progress::Progress, scte35::CueEvent, sec_to_time,
+        stderr_reader,
+    },
 };
 use crate::utils::{
-    config::OutputMode::*,
+    control::send_now_next,
     errors::ServiceError,
-    logging::{fmt_cmd, Target},
+    events::{clip_data, clip_start_data, EventKind},
+    logging::Target,
     task_runner,
 };
-use crate::vec_strings;
 
 async fn play(
     manager: ChannelManager,
     mut enc_writer: BufWriter<ChildStdin>,
     ff_log_format: &str,
+    cues: Arc<Mutex<Vec<CueEvent>>>,
 ) -> Result<(), ServiceError> {
     let config = manager.config.lock().await.clone();
     let id = config.general.channel_id;
@@ -36,12 +55,19 @@ async fn play(
     let ingest_is_alive = manager.ingest_is_alive.clone();
     let mut buffer = vec![0u8; 64 * 1024];
     let mut live_on = false;
+    let media_cache = MediaCache::new(&config);
+    let backend = PipelineBackend::new(&config);
 
     // get source iterator
     let mut node_sources = source_generator(manager.clone()).await;
 
-    while let Some(node) = node_sources.next().await {
+    while let Some(mut node) = node_sources.next().await {
         *manager.current_media.lock().await = Some(node.clone());
+        manager.clip_started.lock().await.take();
+
+        if let Some(cache) = &media_cache {
+            cache.process(&manager, &mut node).await;
+        }
         let ignore_dec = config.logging.ignore_lines.clone();
 
         if !is_alive.load(Ordering::SeqCst) {
@@ -51,7 +77,7 @@ async fn play(
 
         trace!("Decoder CMD: {:?}", node.cmd);
 
-        let mut cmd = match node.cmd {
+        let cmd = match node.cmd.take() {
             Some(cmd) => cmd,
             None => break,
         };
@@ -77,9 +103,39 @@ async fn play(
             "Play for <yellow>{}</>{c_index}: <b><magenta>{}  {}</></b>",
             sec_to_time(node.out - node.seek),
             node.source,
-            node.audio
+            node.audio.source
         );
 
+        let clip = clip_data(&node);
+        let detector = Detector::new(&manager, &config, &node);
+        *manager.clip_started.lock().await = Some(Instant::now());
+        manager.emit(EventKind::ClipStart, clip_start_data(&manager, &node).await);
+        manager.proof.capture(&config, &node.source).await;
+
+        if config.text.now_next {
+            tokio::spawn(send_now_next(manager.clone(), node.clone()));
+        }
+
+        if let Some(cue) = &node.scte35 {
+            if relay::has_cue_output(&config) {
+                let event = CueEvent::new(cue, Local::now());
+
+                info!(target: Target::file_mail(), channel = id;
+                    "SCTE-35 cue <yellow>{}</> at <yellow>{}</>, break duration <yellow>{}</>",
+                    event.event_id,
+                    event.start.format("%H:%M:%S%.3f"),
+                    sec_to_time(event.duration)
+                );
+
+                cues.lock().await.push(event);
+            } else {
+                error!(target: Target::file_mail(), channel = id;
+                    "SCTE-35 cue <yellow>{}</> is not sent, it needs HLS output or an output leg with a udp:// address",
+                    cue.event_id
+                );
+            }
+        }
+
         if config.task.enable {
             if config.task.path.is_file() {
                 let channel_mgr_3 = manager.clone();
@@ -93,52 +149,19 @@ async fn play(
             }
         }
 
-        let mut dec_cmd = vec_strings!["-hide_banner", "-nostats", "-v", &ff_log_format];
-
-        if let Some(decoder_input_cmd) = &config.advanced.decoder.input_cmd {
-            dec_cmd.append(&mut decoder_input_cmd.clone());
-        }
-
-        dec_cmd.append(&mut cmd);
-
-        if let Some(mut filter) = node.filter {
-            dec_cmd.append(&mut filter.cmd());
-            dec_cmd.append(&mut filter.map());
-        }
-
-        if config.processing.vtt_enable && dec_cmd.iter().any(|s| s.ends_with(".vtt")) {
-            let i = dec_cmd
-                .iter()
-                .filter(|&n| n == "-i")
-                .count()
-                .saturating_sub(1);
-
-            dec_cmd.append(&mut vec_strings!("-map", format!("{i}:s"), "-c:s", "copy"));
-        }
-
-        if let Some(cmd) = &config.processing.cmd {
-            dec_cmd.extend_from_slice(cmd);
-        }
-
-        debug!(target: Target::file_mail(), channel = id;
-            "Decoder CMD: <bright-blue>ffmpeg {}</>",
-            fmt_cmd(&dec_cmd)
-        );
-
-        // create ffmpeg decoder instance, for reading the input files
-        let mut dec_proc = Command::new("ffmpeg")
-            .args(dec_cmd)
-            .kill_on_drop(true)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
+        // create decoder instance, for reading the input files
+        let mut dec_proc = backend
+            .decoder(&config, &mut node, cmd, ff_log_format)
+            .await?;
 
         let mut decoder_stdout = dec_proc.stdout.take().unwrap();
         let dec_err = BufReader::new(dec_proc.stderr.take().unwrap());
 
         *manager.clone().decoder.lock().await = Some(dec_proc);
 
-        let error_decoder_task = tokio::spawn(stderr_reader(dec_err, ignore_dec, Decoder, id));
+        let error_decoder_task = tokio::spawn(stderr_reader(
+            dec_err, ignore_dec, Decoder, id, detector, None,
+        ));
 
         loop {
             if ingest_is_alive.load(Ordering::SeqCst) {
@@ -160,6 +183,9 @@ async fn play(
                     }
 
                     enc_writer.write_all(&buffer[..num]).await?;
+                    manager.recorder.feed(&buffer[..num]).await;
+                    manager.proof.feed(&buffer[..num]).await;
+                    manager.preview_stream.feed(&buffer[..num]).await;
                 }
             } else {
                 // read from decoder instance
@@ -177,13 +203,33 @@ async fn play(
                 }
 
                 enc_writer.write_all(&buffer[..num]).await?;
+                manager.recorder.feed(&buffer[..num]).await;
+                manager.proof.feed(&buffer[..num]).await;
+                manager.preview_stream.feed(&buffer[..num]).await;
             }
         }
 
         drop(decoder_stdout);
 
         manager.wait(Decoder).await;
-        error_decoder_task.await??;
+
+        match error_decoder_task.await? {
+            Ok(_) => blacklist::played(&manager).await,
+            Err(e) => {
+                if !blacklist::failed(&manager, &node.source, &e.to_string()).await {
+                    return Err(e);
+                }
+
+                // sync the playlist again, the blacklisted file gets replaced by filler
+                playlist_init.store(true, Ordering::SeqCst);
+            }
+        }
+
+        manager.emit(EventKind::ClipEnd, clip);
+
+        if manager.stop_after_current.load(Ordering::SeqCst) {
+            break;
+        }
     }
 
     Ok(())
@@ -205,29 +251,64 @@ pub async fn player(manager: ChannelManager) -> Result<(), ServiceError> {
     let ignore_enc = config.logging.ignore_lines.clone();
     let channel_id = config.general.channel_id;
 
-    if config.output.mode == HLS {
+    if config.recorder.enable {
+        if let Err(e) = manager.recorder.start(&config).await {
+            error!(target: Target::file_mail(), channel = channel_id; "Start recorder failed: {e}");
+        }
+    }
+
+    if config.preview.enable {
+        if let Err(e) = manager.preview_stream.start(&config).await {
+            error!(target: Target::file_mail(), channel = channel_id; "Start preview stream failed: {e}");
+        }
+    }
+
+    manager.proof.start(&config);
+
+    if config.output.mode.is_segmented() {
         hls::writer(&manager, &ff_log_format).await?;
-        manager.stop_all(false).await;
+
+        if manager.stop_after_current.load(Ordering::SeqCst) {
+            manager.finish().await;
+        } else {
+            manager.stop_all(false).await;
+        }
 
         return Ok(());
     }
 
     // get ffmpeg output instance
-    let mut enc_proc = match config.output.mode {
-        Desktop => desktop::output(&config, &ff_log_format).await?,
-        Null => null::output(&config, &ff_log_format).await?,
-        Stream => stream::output(&config, &ff_log_format).await?,
-        _ => panic!("Output mode doesn't exists!"),
-    };
+    let mut enc_proc = PipelineBackend::init(&config)
+        .encoder(&config, &ff_log_format)
+        .await?;
 
     let enc_err = BufReader::new(enc_proc.stderr.take().unwrap());
     let enc_writer = BufWriter::new(enc_proc.stdin.take().unwrap());
 
+    let cues = Arc::new(Mutex::new(vec![]));
+
+    if relay::is_relay(&config) {
+        tokio::spawn(relay::relay(
+            manager.clone(),
+            config.clone(),
+            ff_log_format.clone(),
+            enc_proc.stdout.take().unwrap(),
+            cues.clone(),
+        ));
+    }
+
     *manager.encoder.lock().await = Some(enc_proc);
     let mgr_clone2 = manager.clone();
 
     // spawn a task to log ffmpeg output error messages
-    let handle_enc_stderr = tokio::spawn(stderr_reader(enc_err, ignore_enc, Encoder, channel_id));
+    let handle_enc_stderr = tokio::spawn(stderr_reader(
+        enc_err,
+        ignore_enc,
+        Encoder,
+        channel_id,
+        None,
+        Some(Progress::new(&manager).await),
+    ));
 
     // spawn a task for ffmpeg ingest server and create a channel for package sending
     let handle_ingest = if config.ingest.enable {
@@ -251,11 +332,16 @@ pub async fn player(manager: ChannelManager) -> Result<(), ServiceError> {
             result?;
         }
 
-        result = play(manager.clone(), enc_writer, &ff_log_format) => {
+        result = play(manager.clone(), enc_writer, &ff_log_format, cues) => {
             result?;
         }
     }
 
+    if manager.stop_after_current.load(Ordering::SeqCst) {
+        // encoder input is closed, it ends after the last frames are written
+        manager.finish().await;
+    }
+
     trace!("Out of source loop");
 
     Ok(())
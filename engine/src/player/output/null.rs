@@ -5,6 +5,7 @@ use tokio::process::{Child, Command};
 
 use crate::utils::{
     config::PlayoutConfig,
+    ffmpeg_bundle,
     logging::{fmt_cmd, Target},
 };
 use crate::vec_strings;
@@ -21,7 +22,14 @@ use crate::{
 /// Instead of streaming, we run a ffplay instance and play on desktop.
 pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, ServiceError> {
     let id = config.general.channel_id;
-    let mut enc_prefix = vec_strings!["-hide_banner", "-nostats", "-v", log_format];
+    let mut enc_prefix = vec_strings![
+        "-hide_banner",
+        "-nostats",
+        "-progress",
+        "pipe:2",
+        "-v",
+        log_format
+    ];
     let mut media = Media {
         unit: Encoder,
         ..Default::default()
@@ -41,7 +49,7 @@ pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, S
         fmt_cmd(&enc_cmd)
     );
 
-    let child = Command::new("ffmpeg")
+    let child = Command::new(ffmpeg_bundle::ffmpeg())
         .args(enc_cmd)
         .kill_on_drop(true)
         .stdin(Stdio::piped())
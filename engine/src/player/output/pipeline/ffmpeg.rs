@@ -0,0 +1,92 @@
+use std::process::Stdio;
+
+use log::*;
+use tokio::process::{Child, Command};
+
+use super::Pipeline;
+use crate::file::utils::login;
+use crate::player::{
+    output::{decklink, desktop, null, stream},
+    utils::{subtitle, Media},
+};
+use crate::utils::{
+    config::{OutputMode::*, PlayoutConfig},
+    errors::ServiceError,
+    ffmpeg_bundle,
+    logging::{fmt_cmd, Target},
+};
+use crate::vec_strings;
+
+#[derive(Clone, Debug, Default)]
+pub struct FfmpegPipeline;
+
+impl Pipeline for FfmpegPipeline {
+    async fn decoder(
+        &self,
+        config: &PlayoutConfig,
+        node: &mut Media,
+        mut cmd: Vec<String>,
+        log_format: &str,
+    ) -> Result<Child, ServiceError> {
+        let id = config.general.channel_id;
+        let mut dec_cmd = vec_strings!["-hide_banner", "-nostats", "-v", log_format];
+
+        if let Some(decoder_input_cmd) = &config.advanced.decoder.input_cmd {
+            dec_cmd.append(&mut decoder_input_cmd.clone());
+        }
+
+        let mut subtitle_cmd = subtitle::output_cmd(config, node, &cmd);
+
+        dec_cmd.append(&mut cmd);
+
+        if let Some(mut filter) = node.filter.take() {
+            dec_cmd.append(&mut filter.cmd());
+            dec_cmd.append(&mut filter.map());
+        }
+
+        dec_cmd.append(&mut subtitle_cmd);
+
+        if config.processing.vtt_enable && dec_cmd.iter().any(|s| s.ends_with(".vtt")) {
+            let i = dec_cmd
+                .iter()
+                .filter(|&n| n == "-i")
+                .count()
+                .saturating_sub(1);
+
+            dec_cmd.append(&mut vec_strings!("-map", format!("{i}:s"), "-c:s", "copy"));
+        }
+
+        if let Some(cmd) = &config.processing.cmd {
+            dec_cmd.extend_from_slice(cmd);
+        }
+
+        debug!(target: Target::file_mail(), channel = id;
+            "Decoder CMD: <bright-blue>ffmpeg {}</>",
+            fmt_cmd(&dec_cmd)
+        );
+
+        // create ffmpeg decoder instance, for reading the input files
+        let child = Command::new(ffmpeg_bundle::ffmpeg())
+            .args(login::args(dec_cmd))
+            .kill_on_drop(true)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        Ok(child)
+    }
+
+    async fn encoder(
+        &self,
+        config: &PlayoutConfig,
+        log_format: &str,
+    ) -> Result<Child, ServiceError> {
+        match config.output.mode {
+            Desktop => desktop::output(config, log_format).await,
+            Null => null::output(config, log_format).await,
+            Stream | Ndi | Srt | Icecast | Whip => stream::output(config, log_format).await,
+            Decklink => decklink::output(config, log_format).await,
+            _ => panic!("Output mode doesn't exists!"),
+        }
+    }
+}
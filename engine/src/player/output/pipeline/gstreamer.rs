@@ -0,0 +1,453 @@
+use std::{path::Path, process::Stdio};
+
+use log::*;
+use tokio::process::{Child, Command};
+
+use super::Pipeline;
+use crate::player::{
+    output::relay,
+    utils::{is_remote, Media},
+};
+use crate::utils::{
+    config::{HwAccel, OutputMode, OutputMode::*, PlayoutConfig, SubtitleMode, VideoMode},
+    errors::ServiceError,
+    logging::{fmt_cmd, Target},
+};
+use crate::vec_strings;
+
+const GST_LAUNCH: &str = "gst-launch-1.0";
+
+#[derive(Clone, Debug, Default)]
+pub struct GstPipeline;
+
+/// Outputs, which GStreamer can encode.
+pub fn has_output(mode: &OutputMode) -> bool {
+    matches!(mode, Null | Desktop | Ndi | Decklink | Stream | Srt)
+}
+
+/// Clip can be decoded by GStreamer, it has no feature which needs ffmpeg filters.
+pub fn can_decode(config: &PlayoutConfig, node: &Media) -> bool {
+    let processing = &config.processing;
+    let Some(probe) = &node.probe else {
+        return false;
+    };
+    let (Some(video), Some(_)) = (probe.video.first(), probe.audio.first()) else {
+        return false;
+    };
+
+    let plain_config = !processing.audio_only
+        && !processing.copy_audio
+        && !processing.copy_video
+        && !processing.add_logo
+        && !processing.loudnorm
+        && !processing.override_filter
+        && !processing.vtt_enable
+        && processing.custom_filter.is_empty()
+        && processing.crossfade <= 0.0
+        && processing.audio_tracks == 1
+        && processing.audio_channels <= 2
+        && processing.subtitle_mode == SubtitleMode::None
+        && !config.text.add_text
+        && !config.graphics.enable
+        && !config.ticker.enable
+        && !config.clock.enable
+        && !config.visual_radio.enable
+        && config.monitor.silence_duration <= 0.0
+        && config.monitor.black_duration <= 0.0
+        && config.monitor.freeze_duration <= 0.0
+        && config.advanced.decoder.input_cmd.is_none()
+        && config.advanced.decoder.output_cmd.is_none();
+
+    let plain_clip = !video.is_hdr()
+        && video
+            .field_order
+            .as_deref()
+            .is_none_or(|o| o == "progressive")
+        && !is_remote(&node.source)
+        && Path::new(&node.source).is_file()
+        && node.seek <= 0.0
+        && (node.out - node.duration).abs() < 0.1
+        && node.audio.is_empty()
+        && node.audio_tracks.is_empty()
+        && node.custom_filter.is_empty()
+        && node.filters.is_empty()
+        && node.custom_decoder_params.is_empty()
+        && node.logo.is_none()
+        && node.aspect_mode.is_none()
+        && node.fps_mode.is_none()
+        && node.transition.is_none()
+        && !node.stinger_in
+        && !node.stinger_out;
+
+    plain_config && plain_clip
+}
+
+/// Frame rate as fraction, NTSC rates like 29.97 become 30000/1001.
+fn fraction(fps: f64) -> String {
+    if fps.fract() == 0.0 {
+        format!("{fps}/1")
+    } else {
+        format!("{}/1001", (fps * 1001.0).round())
+    }
+}
+
+/// Bitrate in kbit/s from a value like `4000k` or `4M`.
+fn kbits(value: &str) -> Option<u64> {
+    let value = value.trim();
+
+    if let Some(k) = value.strip_suffix(['k', 'K']) {
+        k.parse().ok()
+    } else if let Some(m) = value.strip_suffix('M') {
+        m.parse::<u64>().ok().map(|m| m * 1000)
+    } else {
+        value.parse::<u64>().ok().map(|b| b / 1000)
+    }
+}
+
+/// Value of a parameter from the output command, like `-b:v`.
+fn param<'a>(cmd: &'a [String], key: &str) -> Option<&'a str> {
+    cmd.iter()
+        .position(|p| p == key)
+        .and_then(|i| cmd.get(i + 1))
+        .map(String::as_str)
+}
+
+/// Pipeline of the decoder, it produces the same MPEG-TS as the ffmpeg decoder.
+pub fn decoder_pipeline(config: &PlayoutConfig, node: &Media) -> Vec<String> {
+    let processing = &config.processing;
+    let bitrate = processing.width * processing.height / 16 * 1000;
+
+    vec_strings![
+        "filesrc",
+        format!("location={}", node.source),
+        "!",
+        "decodebin",
+        "name=d",
+        "d.",
+        "!",
+        "queue",
+        "!",
+        "videoconvert",
+        "!",
+        "videoscale",
+        "add-borders=true",
+        "!",
+        "videorate",
+        "!",
+        format!(
+            "video/x-raw,format=I420,width={},height={},framerate={},pixel-aspect-ratio=1/1",
+            processing.width,
+            processing.height,
+            fraction(processing.fps)
+        ),
+        "!",
+        "avenc_mpeg2video",
+        format!("bitrate={bitrate}"),
+        "gop-size=1",
+        "!",
+        "mpegvideoparse",
+        "!",
+        "queue",
+        "!",
+        "mux.",
+        "d.",
+        "!",
+        "queue",
+        "!",
+        "audioconvert",
+        "!",
+        "audioresample",
+        "!",
+        format!(
+            "audio/x-raw,rate=48000,channels={}",
+            processing.audio_channels.max(1)
+        ),
+        "!",
+        "volume",
+        format!("volume={}", processing.volume),
+        "!",
+        "avenc_mp2",
+        "bitrate=384000",
+        "!",
+        "queue",
+        "!",
+        "mux.",
+        "mpegtsmux",
+        "name=mux",
+        "!",
+        "fdsink",
+        "fd=1"
+    ]
+}
+
+/// H.264 encoder for the hardware acceleration of the config.
+fn video_encoder(config: &PlayoutConfig, kbits: u64) -> Vec<String> {
+    let gop = (config.processing.fps * 2.0).round() as u64;
+
+    match config.processing.hw_accel {
+        HwAccel::None => vec_strings![
+            "x264enc",
+            format!("bitrate={kbits}"),
+            format!("key-int-max={gop}"),
+            "tune=zerolatency",
+            "speed-preset=veryfast"
+        ],
+        HwAccel::Nvenc => vec_strings![
+            "nvh264enc",
+            format!("bitrate={kbits}"),
+            format!("gop-size={gop}")
+        ],
+        HwAccel::Qsv => vec_strings![
+            "qsvh264enc",
+            format!("bitrate={kbits}"),
+            format!("gop-size={gop}")
+        ],
+        HwAccel::Vaapi => vec_strings![
+            "vah264enc",
+            format!("bitrate={kbits}"),
+            format!("key-int-max={gop}")
+        ],
+    }
+}
+
+/// Muxer and sink of a stream, to stdout for the relay or to the url of the output.
+fn stream_sink(config: &PlayoutConfig, cmd: &[String]) -> Result<Vec<String>, ServiceError> {
+    if relay::is_relay(config) {
+        return Ok(vec_strings![
+            "mpegtsmux",
+            "name=mux",
+            "!",
+            "fdsink",
+            "fd=1",
+            "sync=true"
+        ]);
+    }
+
+    let url = cmd
+        .iter()
+        .rev()
+        .find(|p| p.contains("://"))
+        .ok_or_else(|| ServiceError::Conflict("Output has no url for GStreamer".to_string()))?;
+
+    match url.split_once("://") {
+        Some(("rtmp" | "rtmps", _)) => Ok(vec_strings![
+            "flvmux",
+            "name=mux",
+            "streamable=true",
+            "!",
+            "rtmpsink",
+            format!("location={url}")
+        ]),
+        Some(("srt", _)) => Ok(vec_strings![
+            "mpegtsmux",
+            "name=mux",
+            "!",
+            "srtsink",
+            format!("uri={url}")
+        ]),
+        Some(("udp", address)) => {
+            let address = address.split(['?', '/']).next().unwrap_or_default();
+            let (host, port) = address.rsplit_once(':').ok_or_else(|| {
+                ServiceError::Conflict(format!("UDP address without port: {url}"))
+            })?;
+
+            Ok(vec_strings![
+                "mpegtsmux",
+                "name=mux",
+                "!",
+                "udpsink",
+                format!("host={host}"),
+                format!("port={port}")
+            ])
+        }
+        _ => Err(ServiceError::Conflict(format!(
+            "GStreamer can not send to {url}"
+        ))),
+    }
+}
+
+/// Pipeline of the encoder, it reads the MPEG-TS of the decoder from stdin.
+pub fn encoder_pipeline(config: &PlayoutConfig) -> Result<Vec<String>, ServiceError> {
+    let output = &config.output;
+    let mut pipeline = vec_strings!["fdsrc", "fd=0", "!", "decodebin", "name=dec"];
+    let video = vec_strings!["dec.", "!", "queue", "!", "videoconvert", "!"];
+    let audio = vec_strings!["dec.", "!", "queue", "!", "audioconvert", "!"];
+
+    match output.mode {
+        Null => {
+            pipeline.extend(vec_strings![
+                "dec.",
+                "!",
+                "queue",
+                "!",
+                "fakesink",
+                "sync=true"
+            ]);
+            pipeline.extend(vec_strings![
+                "dec.",
+                "!",
+                "queue",
+                "!",
+                "fakesink",
+                "sync=true"
+            ]);
+        }
+        Desktop => {
+            pipeline.extend(video);
+            pipeline.push("autovideosink".to_string());
+            pipeline.extend(audio);
+            pipeline.push("autoaudiosink".to_string());
+        }
+        Ndi => {
+            let name = if output.ndi_name.is_empty() {
+                format!("ffplayout-{}", config.general.channel_id)
+            } else {
+                output.ndi_name.clone()
+            };
+
+            pipeline.extend(vec_strings![
+                "ndisinkcombiner",
+                "name=combiner",
+                "!",
+                "ndisink",
+                format!("ndi-name={name}")
+            ]);
+            pipeline.extend(video);
+            pipeline.extend(vec_strings![
+                "video/x-raw,format=UYVY",
+                "!",
+                "combiner.video"
+            ]);
+            pipeline.extend(audio);
+            pipeline.extend(vec_strings![
+                "audio/x-raw,format=F32LE",
+                "!",
+                "combiner.audio"
+            ]);
+        }
+        Decklink => {
+            if VideoMode::new(&output.decklink_mode).is_none() {
+                return Err(ServiceError::Conflict(format!(
+                    "Unknown DeckLink video mode: {}",
+                    output.decklink_mode
+                )));
+            }
+
+            let device = output.decklink_device.parse::<u32>().map_err(|_| {
+                ServiceError::Conflict(format!(
+                    "GStreamer needs the DeckLink device as number, not: {}",
+                    output.decklink_device
+                ))
+            })?;
+            let mode = output.decklink_mode.to_lowercase().replace('.', "");
+
+            pipeline.extend(video);
+            pipeline.extend(vec_strings![
+                "decklinkvideosink",
+                format!("device-number={device}"),
+                format!("mode={mode}")
+            ]);
+            pipeline.extend(audio);
+            pipeline.extend(vec_strings![
+                "audioresample",
+                "!",
+                "audio/x-raw,rate=48000",
+                "!",
+                "decklinkaudiosink",
+                format!("device-number={device}")
+            ]);
+        }
+        Stream | Srt => {
+            let cmd = output.output_cmd.clone().unwrap_or_default();
+            let video_kbits = param(&cmd, "-b:v").and_then(kbits).unwrap_or(4000);
+            let audio_kbits = param(&cmd, "-b:a").and_then(kbits).unwrap_or(128);
+
+            pipeline.extend(stream_sink(config, &cmd)?);
+            pipeline.extend(video);
+            pipeline.extend(video_encoder(config, video_kbits));
+            pipeline.extend(vec_strings!["!", "h264parse", "!", "queue", "!", "mux."]);
+            pipeline.extend(audio);
+            pipeline.extend(vec_strings![
+                "audioresample",
+                "!",
+                "avenc_aac",
+                format!("bitrate={}", audio_kbits * 1000),
+                "!",
+                "aacparse",
+                "!",
+                "queue",
+                "!",
+                "mux."
+            ]);
+        }
+        _ => {
+            return Err(ServiceError::Conflict(format!(
+                "GStreamer has no {} output",
+                output.mode
+            )))
+        }
+    }
+
+    Ok(pipeline)
+}
+
+impl Pipeline for GstPipeline {
+    /// Clips which need ffmpeg filters are decoded by ffmpeg.
+    async fn decoder(
+        &self,
+        config: &PlayoutConfig,
+        node: &mut Media,
+        cmd: Vec<String>,
+        log_format: &str,
+    ) -> Result<Child, ServiceError> {
+        if !can_decode(config, node) {
+            return super::ffmpeg::FfmpegPipeline
+                .decoder(config, node, cmd, log_format)
+                .await;
+        }
+
+        let pipeline = decoder_pipeline(config, node);
+
+        debug!(target: Target::file_mail(), channel = config.general.channel_id;
+            "Decoder CMD: <bright-blue>{GST_LAUNCH} {}</>",
+            fmt_cmd(&pipeline)
+        );
+
+        let child = Command::new(GST_LAUNCH)
+            .arg("-q")
+            .args(pipeline)
+            .kill_on_drop(true)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        Ok(child)
+    }
+
+    async fn encoder(
+        &self,
+        config: &PlayoutConfig,
+        _log_format: &str,
+    ) -> Result<Child, ServiceError> {
+        let pipeline = encoder_pipeline(config)?;
+
+        debug!(target: Target::file_mail(), channel = config.general.channel_id;
+            "Encoder CMD: <bright-blue>{GST_LAUNCH} {}</>",
+            fmt_cmd(&pipeline)
+        );
+
+        let mut command = Command::new(GST_LAUNCH);
+        command
+            .args(["-q", "-e"])
+            .args(pipeline)
+            .kill_on_drop(true)
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        if relay::is_relay(config) {
+            command.stdout(Stdio::piped());
+        }
+
+        Ok(command.spawn()?)
+    }
+}
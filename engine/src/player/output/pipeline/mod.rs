@@ -0,0 +1,98 @@
+/*
+Process pipeline of a channel.
+
+The decoder reads a clip and writes MPEG-TS to stdout, the encoder reads it from stdin and
+sends it to the output. ffmpeg runs both by default. GStreamer is an experimental alternative,
+for deployments which need elements their ffmpeg build has not, like the NDI sink or hardware
+encoders. Clips and outputs which GStreamer can not handle fall back to ffmpeg.
+*/
+
+use log::*;
+use tokio::process::Child;
+
+pub mod ffmpeg;
+pub mod gstreamer;
+
+use crate::player::utils::Media;
+use crate::utils::{
+    config::{Backend, PlayoutConfig},
+    errors::ServiceError,
+    logging::Target,
+};
+
+#[derive(Clone, Debug)]
+pub enum PipelineBackend {
+    Ffmpeg(ffmpeg::FfmpegPipeline),
+    GStreamer(gstreamer::GstPipeline),
+}
+
+impl PipelineBackend {
+    pub fn new(config: &PlayoutConfig) -> Self {
+        match config.processing.backend {
+            Backend::Gstreamer if gstreamer::has_output(&config.output.mode) => {
+                Self::GStreamer(gstreamer::GstPipeline)
+            }
+            _ => Self::Ffmpeg(ffmpeg::FfmpegPipeline),
+        }
+    }
+
+    /// Pipeline of the channel, with a warning when GStreamer is set for an output it has not.
+    pub fn init(config: &PlayoutConfig) -> Self {
+        let backend = Self::new(config);
+
+        if config.processing.backend == Backend::Gstreamer && matches!(backend, Self::Ffmpeg(_)) {
+            warn!(target: Target::file_mail(), channel = config.general.channel_id;
+                "GStreamer has no {} output, use ffmpeg", config.output.mode
+            );
+        }
+
+        backend
+    }
+
+    pub async fn decoder(
+        &self,
+        config: &PlayoutConfig,
+        node: &mut Media,
+        cmd: Vec<String>,
+        log_format: &str,
+    ) -> Result<Child, ServiceError> {
+        match self {
+            PipelineBackend::Ffmpeg(pipeline) => {
+                pipeline.decoder(config, node, cmd, log_format).await
+            }
+            PipelineBackend::GStreamer(pipeline) => {
+                pipeline.decoder(config, node, cmd, log_format).await
+            }
+        }
+    }
+
+    pub async fn encoder(
+        &self,
+        config: &PlayoutConfig,
+        log_format: &str,
+    ) -> Result<Child, ServiceError> {
+        match self {
+            PipelineBackend::Ffmpeg(pipeline) => pipeline.encoder(config, log_format).await,
+            PipelineBackend::GStreamer(pipeline) => pipeline.encoder(config, log_format).await,
+        }
+    }
+}
+
+trait Pipeline {
+    /// Decoder of a clip, which writes MPEG-TS to stdout.
+    ///
+    /// `cmd` are the ffmpeg input parameters of the clip.
+    async fn decoder(
+        &self,
+        config: &PlayoutConfig,
+        node: &mut Media,
+        cmd: Vec<String>,
+        log_format: &str,
+    ) -> Result<Child, ServiceError>;
+    /// Encoder of the output, which reads MPEG-TS from stdin.
+    async fn encoder(
+        &self,
+        config: &PlayoutConfig,
+        log_format: &str,
+    ) -> Result<Child, ServiceError>;
+}
@@ -0,0 +1,244 @@
+/*
+Preview stream.
+
+A low bitrate HLS stream of the program output with one second segments, for monitoring
+the channel in the browser. It runs beside the main output and does not depend on its
+settings. In HLS mode the stream is encoded from the HLS playlist, in all other modes it
+gets a copy of the stream which goes to the encoder. The playlist is served under
+`/<channel id>/preview/stream.m3u8`.
+*/
+
+use std::{
+    path::PathBuf,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use log::*;
+use tokio::{
+    fs,
+    io::{AsyncWriteExt, BufReader},
+    process::{Child, Command},
+    sync::{mpsc, Mutex},
+};
+
+use crate::player::{
+    controller::ProcessUnit::*, output::recorder::hls_playlist, utils::stderr_reader,
+};
+use crate::utils::{
+    config::PlayoutConfig,
+    errors::ServiceError,
+    ffmpeg_bundle,
+    logging::{fmt_cmd, Target},
+};
+use crate::vec_strings;
+
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+const QUEUE_SIZE: usize = 32;
+
+/// Preview stream state of a channel.
+#[derive(Clone, Debug, Default)]
+pub struct PreviewStream {
+    pub is_alive: Arc<AtomicBool>,
+    sender: Arc<Mutex<Option<mpsc::Sender<Vec<u8>>>>>,
+}
+
+impl PreviewStream {
+    pub async fn start(&self, config: &PlayoutConfig) -> Result<(), ServiceError> {
+        if self.is_alive.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        fs::create_dir_all(path(config)).await?;
+
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        *self.sender.lock().await = Some(tx);
+        self.is_alive.store(true, Ordering::SeqCst);
+
+        tokio::spawn(preview_loop(config.clone(), rx, self.is_alive.clone()));
+
+        Ok(())
+    }
+
+    pub async fn stop(&self) {
+        self.is_alive.store(false, Ordering::SeqCst);
+        *self.sender.lock().await = None;
+    }
+
+    /// Pass a copy of the program stream to the preview, without waiting for it.
+    pub async fn feed(&self, data: &[u8]) {
+        if !self.is_alive.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if let Some(tx) = self.sender.lock().await.as_ref() {
+            let _ = tx.try_send(data.to_vec());
+        }
+    }
+}
+
+/// Folder of the preview stream, in the public path of the channel.
+pub fn path(config: &PlayoutConfig) -> PathBuf {
+    config.channel.public.join("preview")
+}
+
+/// Build the ffmpeg command for the preview stream.
+pub fn cmd(config: &PlayoutConfig, log_format: &str) -> Result<Vec<String>, ServiceError> {
+    let preview = &config.preview;
+    let dir = path(config);
+    let gop = config.processing.fps.round().max(1.0);
+    let mut cmd = vec_strings!["-hide_banner", "-nostats", "-v", log_format];
+
+    if config.output.mode.is_segmented() {
+        let playlist = hls_playlist(config).ok_or(ServiceError::Conflict(
+            "No HLS playlist found for the preview stream".to_string(),
+        ))?;
+
+        cmd.append(&mut vec_strings!["-live_start_index", "-1", "-i", playlist]);
+    } else {
+        cmd.append(&mut vec_strings!["-f", "mpegts", "-i", "pipe:0"]);
+    }
+
+    cmd.append(&mut vec_strings![
+        "-map",
+        "0:v:0?",
+        "-map",
+        "0:a:0?",
+        "-vf",
+        format!("scale=-2:{}", preview.height.max(2) / 2 * 2),
+        "-c:v",
+        "libx264",
+        "-preset",
+        "veryfast",
+        "-tune",
+        "zerolatency",
+        "-b:v",
+        format!("{}k", preview.bitrate),
+        "-maxrate",
+        format!("{}k", preview.bitrate),
+        "-bufsize",
+        format!("{}k", preview.bitrate * 2),
+        "-g",
+        gop,
+        "-keyint_min",
+        gop,
+        "-sc_threshold",
+        "0",
+        "-c:a",
+        "aac",
+        "-b:a",
+        "64k",
+        "-ac",
+        "2",
+        "-f",
+        "hls",
+        "-hls_time",
+        "1",
+        "-hls_list_size",
+        "6",
+        "-hls_flags",
+        "delete_segments+independent_segments+omit_endlist",
+        "-hls_segment_filename",
+        dir.join("stream_%d.ts").to_string_lossy(),
+        dir.join("stream.m3u8").to_string_lossy()
+    ]);
+
+    Ok(cmd)
+}
+
+fn spawn(config: &PlayoutConfig, log_format: &str) -> Result<Child, ServiceError> {
+    let id = config.general.channel_id;
+    let cmd = cmd(config, log_format)?;
+
+    debug!(target: Target::file_mail(), channel = id;
+        "Preview stream CMD: <bright-blue>ffmpeg {}</>",
+        fmt_cmd(&cmd)
+    );
+
+    let mut child = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(cmd)
+        .kill_on_drop(true)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let ignore = config.logging.ignore_lines.clone();
+    let stderr = BufReader::new(child.stderr.take().unwrap());
+
+    tokio::spawn(async move {
+        if let Err(e) = stderr_reader(stderr, ignore, Encoder, id, None, None).await {
+            error!(target: Target::file_mail(), channel = id; "Preview stream: {e}");
+        }
+    });
+
+    Ok(child)
+}
+
+/// Keep the preview stream running, until it gets stopped.
+async fn preview_loop(
+    config: PlayoutConfig,
+    mut queue: mpsc::Receiver<Vec<u8>>,
+    is_alive: Arc<AtomicBool>,
+) {
+    let id = config.general.channel_id;
+    let log_format = format!("level+{}", config.logging.ffmpeg_level.to_lowercase());
+    let mut delay = RECONNECT_MIN;
+
+    while is_alive.load(Ordering::SeqCst) {
+        let started = Instant::now();
+
+        match spawn(&config, &log_format) {
+            Ok(mut child) => {
+                let mut stdin = child.stdin.take().unwrap();
+
+                info!(target: Target::file_mail(), channel = id; "Start preview stream");
+
+                if config.output.mode.is_segmented() {
+                    tokio::select! {
+                        _ = child.wait() => {}
+                        _ = queue.recv() => {}
+                    }
+                } else {
+                    while let Some(chunk) = queue.recv().await {
+                        if stdin.write_all(&chunk).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+
+                drop(stdin);
+
+                if let Err(e) = child.kill().await {
+                    debug!(target: Target::file_mail(), channel = id; "Preview stream: {e}");
+                }
+
+                if queue.is_closed() {
+                    break;
+                }
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Preview stream failed: {e}");
+            }
+        }
+
+        if started.elapsed() > RECONNECT_MAX {
+            delay = RECONNECT_MIN;
+        }
+
+        warn!(target: Target::file_mail(), channel = id;
+            "Preview stream stopped, restart in <yellow>{}s</>", delay.as_secs()
+        );
+
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(RECONNECT_MAX);
+
+        while queue.try_recv().is_ok() {}
+    }
+
+    info!(target: Target::file_mail(), channel = id; "Preview stream stopped");
+}
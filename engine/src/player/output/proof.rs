@@ -0,0 +1,238 @@
+/*
+Proof-of-play thumbnails.
+
+Saves a JPEG frame of the program output at each clip start, and optionally every few
+minutes, next to the as-run logs. In HLS mode the frame comes from the newest HLS segment,
+in all other modes from a copy of the stream which goes to the encoder.
+*/
+
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use chrono::DateTime;
+use chrono_tz::Tz;
+use log::*;
+use tokio::{
+    fs,
+    io::AsyncWriteExt,
+    process::Command,
+    sync::{mpsc, Mutex},
+    time::{sleep, timeout},
+};
+
+use crate::player::output::recorder::hls_playlist;
+use crate::utils::{
+    asrun,
+    config::PlayoutConfig,
+    errors::ServiceError,
+    ffmpeg_bundle,
+    logging::{fmt_cmd, Target},
+    time_machine::time_now,
+};
+use crate::vec_strings;
+
+/// Longest time a capture waits for a frame.
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+/// HLS segments are written at their end, so the new clip is in the playlist a bit later.
+const HLS_DELAY: Duration = Duration::from_secs(8);
+const QUEUE_SIZE: usize = 32;
+
+/// Proof-of-play state of a channel, only one capture runs at a time.
+#[derive(Clone, Debug, Default)]
+pub struct ProofOfPlay {
+    pub is_alive: Arc<AtomicBool>,
+    sender: Arc<Mutex<Option<mpsc::Sender<Vec<u8>>>>>,
+}
+
+impl ProofOfPlay {
+    /// Start the interval captures, when they are configured.
+    pub fn start(&self, config: &PlayoutConfig) {
+        if !config.asrun.thumbnails
+            || config.asrun.thumbnail_interval <= 0.0
+            || self.is_alive.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+
+        tokio::spawn(interval_loop(self.clone(), config.clone()));
+    }
+
+    pub fn stop(&self) {
+        self.is_alive.store(false, Ordering::SeqCst);
+    }
+
+    /// Save a frame of the program output, named after the clip.
+    pub async fn capture(&self, config: &PlayoutConfig, source: &str) {
+        if !config.asrun.thumbnails {
+            return;
+        }
+
+        let mut sender = self.sender.lock().await;
+
+        if sender.is_some() {
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        *sender = Some(tx);
+
+        let file = file(config, &time_now(&config.channel.timezone), source);
+
+        tokio::spawn(capture_task(config.clone(), file, rx, self.sender.clone()));
+    }
+
+    /// Pass a copy of the program stream to a running capture, without waiting for it.
+    pub async fn feed(&self, data: &[u8]) {
+        if let Some(tx) = self.sender.lock().await.as_ref() {
+            let _ = tx.try_send(data.to_vec());
+        }
+    }
+}
+
+/// Thumbnail file, in a folder per day next to the as-run logs.
+pub fn file(config: &PlayoutConfig, time: &DateTime<Tz>, source: &str) -> PathBuf {
+    let stem = Path::new(source)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .replace(|c: char| !c.is_alphanumeric() && c != '-' && c != '_', "_");
+    let name = if stem.is_empty() {
+        time.format("%H-%M-%S").to_string()
+    } else {
+        format!("{}_{stem}", time.format("%H-%M-%S"))
+    };
+
+    asrun::path(config)
+        .join("thumbnails")
+        .join(time.format("%Y-%m-%d").to_string())
+        .join(name)
+        .with_extension("jpg")
+}
+
+/// Build the ffmpeg command for one thumbnail.
+pub fn cmd(config: &PlayoutConfig, file: &Path) -> Result<Vec<String>, ServiceError> {
+    let mut cmd = vec_strings!["-hide_banner", "-nostats", "-v", "level+error"];
+
+    if config.output.mode.is_segmented() {
+        let playlist = hls_playlist(config).ok_or(ServiceError::Conflict(
+            "No HLS playlist found for thumbnails".to_string(),
+        ))?;
+
+        cmd.append(&mut vec_strings!["-live_start_index", "-1", "-i", playlist]);
+    } else {
+        cmd.append(&mut vec_strings!["-f", "mpegts", "-i", "pipe:0"]);
+    }
+
+    cmd.append(&mut vec_strings![
+        "-frames:v",
+        "1",
+        "-q:v",
+        "3",
+        "-update",
+        "1",
+        "-y",
+        file.to_string_lossy()
+    ]);
+
+    Ok(cmd)
+}
+
+async fn capture(
+    config: &PlayoutConfig,
+    file: &Path,
+    queue: &mut mpsc::Receiver<Vec<u8>>,
+) -> Result<(), ServiceError> {
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+
+    if config.output.mode.is_segmented() {
+        sleep(HLS_DELAY).await;
+    }
+
+    let cmd = cmd(config, file)?;
+
+    debug!(target: Target::file_mail(), channel = config.general.channel_id;
+        "Thumbnail CMD: <bright-blue>ffmpeg {}</>",
+        fmt_cmd(&cmd)
+    );
+
+    let mut child = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(cmd)
+        .kill_on_drop(true)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+
+    let result = timeout(CAPTURE_TIMEOUT, async {
+        if config.output.mode.is_segmented() {
+            return child.wait().await;
+        }
+
+        while let Some(chunk) = queue.recv().await {
+            if stdin.write_all(&chunk).await.is_err() {
+                break;
+            }
+        }
+
+        child.wait().await
+    })
+    .await;
+
+    match result {
+        Ok(Ok(status)) if status.success() && file.is_file() => Ok(()),
+        Ok(Ok(status)) => Err(ServiceError::Conflict(format!(
+            "ffmpeg exited with {status}"
+        ))),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => {
+            let _ = child.kill().await;
+
+            Err(ServiceError::Conflict("no frame in time".to_string()))
+        }
+    }
+}
+
+async fn capture_task(
+    config: PlayoutConfig,
+    file: PathBuf,
+    mut queue: mpsc::Receiver<Vec<u8>>,
+    sender: Arc<Mutex<Option<mpsc::Sender<Vec<u8>>>>>,
+) {
+    let id = config.general.channel_id;
+
+    match capture(&config, &file, &mut queue).await {
+        Ok(_) => {
+            debug!(target: Target::file_mail(), channel = id;
+                "Save thumbnail <b><magenta>{}</></b>", file.display()
+            );
+        }
+        Err(e) => {
+            warn!(target: Target::file_mail(), channel = id; "Thumbnail failed: {e}");
+        }
+    }
+
+    *sender.lock().await = None;
+}
+
+async fn interval_loop(proof: ProofOfPlay, config: PlayoutConfig) {
+    let interval = Duration::from_secs_f64(config.asrun.thumbnail_interval * 60.0);
+
+    loop {
+        sleep(interval).await;
+
+        if !proof.is_alive.load(Ordering::SeqCst) {
+            break;
+        }
+
+        proof.capture(&config, "").await;
+    }
+}
@@ -0,0 +1,493 @@
+/*
+Archive recorder.
+
+Records the program output to segmented files, in parallel to the playout output,
+for compliance and air checks. In HLS mode the recorder reads the HLS playlist,
+in all other modes it gets a copy of the stream which goes to the encoder.
+*/
+
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::NaiveDateTime;
+use log::*;
+use serde::{Deserialize, Serialize};
+use shlex::split;
+use tokio::{
+    fs,
+    io::{AsyncWriteExt, BufReader},
+    process::{Child, Command},
+    sync::{mpsc, Mutex},
+};
+
+use crate::player::{controller::ProcessUnit::*, utils::stderr_reader};
+use crate::utils::{
+    config::PlayoutConfig,
+    errors::ServiceError,
+    ffmpeg_bundle,
+    logging::{fmt_cmd, Target},
+    naive_date_time_from_str,
+};
+use crate::vec_strings;
+
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_MAX: Duration = Duration::from_secs(30);
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(600);
+const QUEUE_SIZE: usize = 32;
+const FILE_TIME: &str = "%Y-%m-%d_%H-%M-%S";
+
+/// Recorder state of a channel, it can be started and stopped independent from the playout.
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    pub is_alive: Arc<AtomicBool>,
+    sender: Arc<Mutex<Option<mpsc::Sender<Vec<u8>>>>>,
+}
+
+impl Recorder {
+    pub async fn start(&self, config: &PlayoutConfig) -> Result<(), ServiceError> {
+        if config.recorder.path.as_os_str().is_empty() {
+            return Err(ServiceError::Conflict(
+                "Recorder needs a target directory".to_string(),
+            ));
+        }
+
+        if self.is_alive.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        fs::create_dir_all(&config.recorder.path).await?;
+
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        *self.sender.lock().await = Some(tx);
+        self.is_alive.store(true, Ordering::SeqCst);
+
+        tokio::spawn(record_loop(config.clone(), rx, self.is_alive.clone()));
+
+        Ok(())
+    }
+
+    /// Stop recording, the current segment gets finished.
+    pub async fn stop(&self) {
+        self.is_alive.store(false, Ordering::SeqCst);
+        *self.sender.lock().await = None;
+    }
+
+    /// Pass a copy of the program stream to the recorder, without waiting for it.
+    pub async fn feed(&self, data: &[u8]) {
+        if !self.is_alive.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if let Some(tx) = self.sender.lock().await.as_ref() {
+            let _ = tx.try_send(data.to_vec());
+        }
+    }
+}
+
+/// The media playlist from the HLS output, for variant streams the first one.
+pub(crate) fn hls_playlist(config: &PlayoutConfig) -> Option<String> {
+    let cmd = config.output.output_cmd.as_ref()?;
+
+    // low latency output writes the media playlists next to the DASH manifest
+    if let Some(mpd) = cmd.iter().rev().find(|p| p.ends_with(".mpd")) {
+        return Some(
+            Path::new(mpd)
+                .with_file_name("media_0.m3u8")
+                .to_string_lossy()
+                .to_string(),
+        );
+    }
+
+    cmd.iter()
+        .rev()
+        .find(|p| p.ends_with(".m3u8") && !p.ends_with("master.m3u8") && !p.contains(']'))
+        .map(|p| p.replace("%v", "0"))
+}
+
+/// Build the ffmpeg command for recording.
+pub fn cmd(config: &PlayoutConfig, log_format: &str) -> Result<Vec<String>, ServiceError> {
+    let recorder = &config.recorder;
+    let mut cmd = vec_strings!["-hide_banner", "-nostats", "-v", log_format];
+
+    if config.output.mode.is_segmented() {
+        let playlist = hls_playlist(config).ok_or(ServiceError::Conflict(
+            "No HLS playlist found for recording".to_string(),
+        ))?;
+
+        cmd.append(&mut vec_strings!["-i", playlist]);
+    } else {
+        cmd.append(&mut vec_strings!["-f", "mpegts", "-i", "pipe:0"]);
+    }
+
+    cmd.append(&mut split(&recorder.param).unwrap_or_default());
+
+    let segment = if recorder.segment > 0 {
+        recorder.segment
+    } else {
+        3600
+    };
+
+    cmd.append(&mut vec_strings![
+        "-f",
+        "segment",
+        "-segment_format",
+        recorder.format.muxer(),
+        "-segment_time",
+        segment,
+        "-segment_atclocktime",
+        "1",
+        "-strftime",
+        "1",
+        "-reset_timestamps",
+        "1",
+        recorder
+            .path
+            .join(format!("{FILE_TIME}.{}", recorder.format))
+            .to_string_lossy()
+    ]);
+
+    Ok(cmd)
+}
+
+fn spawn(config: &PlayoutConfig, log_format: &str) -> Result<Child, ServiceError> {
+    let id = config.general.channel_id;
+    let cmd = cmd(config, log_format)?;
+
+    debug!(target: Target::file_mail(), channel = id;
+        "Recorder CMD: <bright-blue>ffmpeg {}</>",
+        fmt_cmd(&cmd)
+    );
+
+    let mut child = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(cmd)
+        .kill_on_drop(true)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let ignore = config.logging.ignore_lines.clone();
+    let stderr = BufReader::new(child.stderr.take().unwrap());
+
+    tokio::spawn(async move {
+        if let Err(e) = stderr_reader(stderr, ignore, Encoder, id, None, None).await {
+            error!(target: Target::file_mail(), channel = id; "Recorder: {e}");
+        }
+    });
+
+    Ok(child)
+}
+
+/// Start time of a recording, from its file name.
+fn recording_time(path: &Path) -> Option<NaiveDateTime> {
+    path.file_stem()
+        .and_then(|s| NaiveDateTime::parse_from_str(&s.to_string_lossy(), FILE_TIME).ok())
+}
+
+/// Delete recordings which are older then the retention time.
+///
+/// Only files with the name pattern of the recorder get deleted, other files in the folder stay.
+pub async fn delete_old_recordings(config: &PlayoutConfig) -> Result<(), ServiceError> {
+    let recorder = &config.recorder;
+
+    if recorder.retention <= 0 {
+        return Ok(());
+    }
+
+    let max_age = Duration::from_secs(recorder.retention as u64 * 86400);
+    let extension = recorder.format.to_string();
+    let mut entries = fs::read_dir(&recorder.path).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+
+        if !path.is_file()
+            || path.extension().is_none_or(|e| e != extension.as_str())
+            || recording_time(&path).is_none()
+        {
+            continue;
+        }
+
+        let age = entry
+            .metadata()
+            .await?
+            .modified()?
+            .elapsed()
+            .unwrap_or_default();
+
+        if age > max_age {
+            debug!(target: Target::file_mail(), channel = config.general.channel_id;
+                "Delete recording <b><magenta>{}</></b>", path.display()
+            );
+
+            fs::remove_file(&path).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn cleanup_loop(config: PlayoutConfig) {
+    loop {
+        if let Err(e) = delete_old_recordings(&config).await {
+            error!(target: Target::file_mail(), channel = config.general.channel_id; "Recorder cleanup: {e}");
+        }
+
+        tokio::time::sleep(CLEANUP_INTERVAL).await;
+    }
+}
+
+/// Keep the recorder running, until it gets stopped.
+async fn record_loop(
+    config: PlayoutConfig,
+    mut queue: mpsc::Receiver<Vec<u8>>,
+    is_alive: Arc<AtomicBool>,
+) {
+    let id = config.general.channel_id;
+    let log_format = format!("level+{}", config.logging.ffmpeg_level.to_lowercase());
+    let path = config.recorder.path.clone();
+    let cleanup = tokio::spawn(cleanup_loop(config.clone()));
+    let mut delay = RECONNECT_MIN;
+
+    while is_alive.load(Ordering::SeqCst) {
+        let started = Instant::now();
+
+        match spawn(&config, &log_format) {
+            Ok(mut child) => {
+                let mut stdin = child.stdin.take().unwrap();
+
+                info!(target: Target::file_mail(), channel = id;
+                    "Start recording to <b><magenta>{}</></b>", path.display()
+                );
+
+                if config.output.mode.is_segmented() {
+                    tokio::select! {
+                        _ = child.wait() => {}
+                        _ = queue.recv() => {
+                            // quit ffmpeg, so that it can finish the segment
+                            let _ = stdin.write_all(b"q").await;
+                        }
+                    }
+                } else {
+                    while let Some(chunk) = queue.recv().await {
+                        if stdin.write_all(&chunk).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+
+                drop(stdin);
+
+                if queue.is_closed() {
+                    if let Err(e) = child.wait().await {
+                        error!(target: Target::file_mail(), channel = id; "Recorder: {e}");
+                    }
+
+                    break;
+                }
+
+                if let Err(e) = child.kill().await {
+                    debug!(target: Target::file_mail(), channel = id; "Recorder: {e}");
+                }
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Recorder failed: {e}");
+            }
+        }
+
+        if started.elapsed() > RECONNECT_MAX {
+            delay = RECONNECT_MIN;
+        }
+
+        warn!(target: Target::file_mail(), channel = id;
+            "Recorder stopped, restart in <yellow>{}s</>", delay.as_secs()
+        );
+
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(RECONNECT_MAX);
+
+        while queue.try_recv().is_ok() {}
+    }
+
+    cleanup.abort();
+
+    info!(target: Target::file_mail(), channel = id; "Recording stopped");
+}
+
+/// Clip from the archive, times are in local time, like `2024-10-01T12:00:00`.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ClipParams {
+    #[serde(deserialize_with = "naive_date_time_from_str")]
+    pub start: NaiveDateTime,
+    #[serde(deserialize_with = "naive_date_time_from_str")]
+    pub end: NaiveDateTime,
+    /// File name for the clip, default is the start time.
+    #[serde(default)]
+    pub name: String,
+}
+
+/// All recordings with their start time, sorted by time.
+async fn recordings(path: &Path) -> Result<Vec<(NaiveDateTime, PathBuf)>, ServiceError> {
+    let mut files = vec![];
+    let mut entries = fs::read_dir(path).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let file = entry.path();
+
+        if let Some(time) = recording_time(&file) {
+            files.push((time, file));
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
+
+/// Build a list for the concat demuxer, with the recordings which overlap the time range.
+///
+/// A recording lasts until the next one starts, in- and outpoint cut the range from the first and last one.
+pub fn concat_list(
+    files: &[(NaiveDateTime, PathBuf)],
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Option<String> {
+    let mut list = String::new();
+
+    for (i, (time, file)) in files.iter().enumerate() {
+        let next = files.get(i + 1).map(|(t, _)| *t);
+
+        if *time >= end || next.is_some_and(|n| n <= start) {
+            continue;
+        }
+
+        list.push_str(&format!(
+            "file '{}'\n",
+            file.to_string_lossy().replace('\'', "'\\''")
+        ));
+
+        if start > *time {
+            let inpoint = (start - *time).num_milliseconds() as f64 / 1000.0;
+            list.push_str(&format!("inpoint {inpoint}\n"));
+        }
+
+        if next.is_none_or(|n| end < n) {
+            let outpoint = (end - *time).num_milliseconds() as f64 / 1000.0;
+            list.push_str(&format!("outpoint {outpoint}\n"));
+        }
+    }
+
+    if list.is_empty() {
+        return None;
+    }
+
+    Some(list)
+}
+
+/// Cut a clip from the archive with stream copy and save it to the channel storage.
+///
+/// Returns the file name of the clip.
+pub async fn export_clip(
+    config: &PlayoutConfig,
+    params: &ClipParams,
+) -> Result<String, ServiceError> {
+    let id = config.general.channel_id;
+    let format = config.recorder.format.to_string();
+    let start = params.start;
+    let end = params.end;
+
+    if end <= start {
+        return Err(ServiceError::BadRequest(
+            "Clip end must be after start".to_string(),
+        ));
+    }
+
+    if config.recorder.path.as_os_str().is_empty() {
+        return Err(ServiceError::Conflict(
+            "Recorder has no target directory".to_string(),
+        ));
+    }
+
+    let files = recordings(&config.recorder.path).await?;
+    let list = concat_list(&files, start, end).ok_or(ServiceError::BadRequest(
+        "No recordings found for this time range".to_string(),
+    ))?;
+
+    // only the file name is used, the clip always goes to the storage root
+    let mut name = Path::new(&params.name)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or(format!("clip_{}", start.format(FILE_TIME)));
+
+    if Path::new(&name).extension().is_none() {
+        name = format!("{name}.{format}");
+    }
+
+    let target = config.channel.storage.join(&name);
+
+    if target.exists() {
+        return Err(ServiceError::Conflict(format!(
+            "File {name} already exists"
+        )));
+    }
+
+    // unique per export, exports of the same channel can run at the same time
+    let list_file =
+        std::env::temp_dir().join(format!("ffplayout_clip_{id}_{}.txt", uuid::Uuid::new_v4()));
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&list_file)
+        .await?
+        .write_all(list.as_bytes())
+        .await?;
+
+    let cmd = vec_strings![
+        "-hide_banner",
+        "-nostats",
+        "-v",
+        "error",
+        "-f",
+        "concat",
+        "-safe",
+        "0",
+        "-i",
+        list_file.to_string_lossy(),
+        "-map",
+        "0",
+        "-c",
+        "copy",
+        target.to_string_lossy()
+    ];
+
+    debug!(target: Target::file_mail(), channel = id;
+        "Clip CMD: <bright-blue>ffmpeg {}</>",
+        fmt_cmd(&cmd)
+    );
+
+    let output = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(cmd)
+        .output()
+        .await;
+    fs::remove_file(&list_file).await?;
+    let output = output?;
+
+    if !output.status.success() {
+        return Err(ServiceError::Conflict(format!(
+            "Clip export failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    info!(target: Target::file_mail(), channel = id;
+        "Export clip <yellow>{start}</> - <yellow>{end}</> to <b><magenta>{}</></b>", target.display()
+    );
+
+    Ok(name)
+}
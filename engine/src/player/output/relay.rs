@@ -0,0 +1,634 @@
+/*
+Output legs.
+
+The encoder writes a MPEG-TS stream to stdout, which gets relayed to one ffmpeg
+process per output leg, each copies the stream to its own destination.
+When a leg fails, only this leg gets restarted, the playout and the other legs keep
+running. Stream data is dropped for the leg until it is back.
+
+Reconnects wait from one second up to the configured delay, doubling each time. A leg
+which is down longer than the alert time sends an event, and after the configured attempts
+it gives up. While the encoder sends no data, the legs can get a standby slate.
+
+Output legs with only a udp:// address get the stream without ffmpeg, they are the only
+outputs which keep the SCTE-35 cues of the playlist.
+*/
+
+use std::{
+    io,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use chrono::Local;
+use log::*;
+use serde_json::json;
+use shlex::split;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{lookup_host, UdpSocket},
+    process::{Child, ChildStdout, Command},
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+    time::timeout,
+};
+
+use super::{icecast, srt, whip};
+use crate::player::{
+    controller::{ChannelManager, ProcessUnit::*},
+    utils::{
+        scte35::{CueEvent, TsInjector},
+        stderr_reader,
+    },
+};
+use crate::utils::{
+    config::{OutputMode::*, PlayoutConfig},
+    errors::ServiceError,
+    events::EventKind,
+    ffmpeg_bundle,
+    logging::{fmt_cmd, Target},
+};
+use crate::vec_strings;
+
+const RECONNECT_MIN: Duration = Duration::from_secs(1);
+/// A leg which runs this long has reached its destination.
+const CONNECTED_AFTER: Duration = Duration::from_secs(10);
+/// Time without encoder data, until the slate starts.
+const SLATE_AFTER: Duration = Duration::from_secs(2);
+const STATS_INTERVAL: Duration = Duration::from_secs(60);
+/// Chunks which can wait for a leg, around two seconds for a typical stream.
+const QUEUE_SIZE: usize = 32;
+/// Seven MPEG-TS packets, as ffmpeg sends them.
+const UDP_PAYLOAD: usize = 7 * 188;
+
+#[derive(Debug, Default)]
+struct LegStats {
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    connects: AtomicU64,
+    last_sent: AtomicU64,
+}
+
+/// One output destination, fed with a copy of the encoded stream.
+#[derive(Clone, Debug)]
+pub struct Leg {
+    pub name: String,
+    /// Output parameters, after the pipe input.
+    pub cmd: Vec<String>,
+    /// Text which should not show up in the logs.
+    pub secret: Option<String>,
+    /// Address, when the leg is sent without ffmpeg.
+    pub udp: Option<String>,
+}
+
+impl Leg {
+    fn new(name: &str, params: &str) -> Option<Self> {
+        let mut cmd = vec_strings!["-map", "0", "-c", "copy"];
+        cmd.append(&mut split(params)?);
+
+        Some(Self {
+            name: name.to_string(),
+            cmd,
+            secret: None,
+            udp: udp_target(params),
+        })
+    }
+}
+
+/// The encoder output goes through the relay, in SRT, WHIP and Icecast mode, when output legs are set
+/// and when the stream output reconnects.
+pub fn is_relay(config: &PlayoutConfig) -> bool {
+    matches!(config.output.mode, Srt | Whip | Icecast)
+        || (config.output.mode == Stream
+            && (!config.output.legs.is_empty() || config.output.push.is_some()))
+}
+
+/// Address of an output leg, which has only a udp:// address.
+pub fn udp_target(params: &str) -> Option<String> {
+    let params = split(params)?;
+    let [url] = params.as_slice() else {
+        return None;
+    };
+    let address = url.strip_prefix("udp://")?.split(['?', '/']).next()?;
+
+    (!address.is_empty()).then(|| address.to_string())
+}
+
+/// The relay has an output for SCTE-35 cues.
+pub fn has_cue_output(config: &PlayoutConfig) -> bool {
+    is_relay(config)
+        && config
+            .output
+            .legs
+            .iter()
+            .any(|params| udp_target(params).is_some())
+}
+
+fn legs(config: &PlayoutConfig) -> Vec<Leg> {
+    let id = config.general.channel_id;
+    let mut legs = vec![];
+
+    if let Some(push) = config
+        .output
+        .push
+        .as_ref()
+        .filter(|_| config.output.mode == Stream)
+    {
+        let mut cmd = vec_strings!["-map", "0", "-c", "copy"];
+        cmd.append(&mut push.clone());
+
+        legs.push(Leg {
+            name: "Stream".to_string(),
+            cmd,
+            secret: None,
+            udp: None,
+        });
+    }
+
+    if config.output.mode == Srt {
+        if config.output.srt_address.is_empty() {
+            error!(target: Target::file_mail(), channel = id; "SRT output needs an address!");
+        } else {
+            legs.push(srt::leg(config));
+        }
+    }
+
+    if config.output.mode == Whip {
+        if config.output.whip_url.is_empty() {
+            error!(target: Target::file_mail(), channel = id; "WHIP output needs an endpoint!");
+        } else {
+            legs.push(whip::leg(config));
+        }
+    }
+
+    if config.output.mode == Icecast {
+        if config.output.icecast_address.is_empty() {
+            error!(target: Target::file_mail(), channel = id; "Icecast output needs an address!");
+        } else {
+            legs.push(icecast::leg(config));
+            legs.extend(icecast::video_leg(config));
+        }
+    }
+
+    for (i, params) in config.output.legs.iter().enumerate() {
+        match Leg::new(&format!("Leg {}", i + 1), params) {
+            Some(leg) => legs.push(leg),
+            None => {
+                error!(target: Target::file_mail(), channel = id; "Invalid output leg: <b><magenta>{params}</></b>");
+            }
+        }
+    }
+
+    legs
+}
+
+fn spawn_leg(config: &PlayoutConfig, log_format: &str, leg: &Leg) -> Result<Child, ServiceError> {
+    let id = config.general.channel_id;
+    let mut cmd = vec_strings![
+        "-hide_banner",
+        "-nostats",
+        "-v",
+        log_format,
+        "-f",
+        "mpegts",
+        "-i",
+        "pipe:0"
+    ];
+    cmd.append(&mut leg.cmd.clone());
+
+    let mut log_cmd = fmt_cmd(&cmd);
+
+    if let Some(secret) = leg.secret.as_ref().filter(|s| !s.is_empty()) {
+        log_cmd = log_cmd.replace(secret, "***");
+    }
+
+    debug!(target: Target::file_mail(), channel = id;
+        "{} CMD: <bright-blue>ffmpeg {log_cmd}</>", leg.name
+    );
+
+    let mut child = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(cmd)
+        .kill_on_drop(true)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let ignore = config.logging.ignore_lines.clone();
+    let stderr = BufReader::new(child.stderr.take().unwrap());
+    let name = leg.name.clone();
+
+    tokio::spawn(async move {
+        if let Err(e) = stderr_reader(stderr, ignore, Encoder, id, None, None).await {
+            error!(target: Target::file_mail(), channel = id; "{name}: {e}");
+        }
+    });
+
+    Ok(child)
+}
+
+/// Wait time before the next connection, doubles from one second up to the configured delay.
+pub fn reconnect_delay(config: &PlayoutConfig, failures: u32) -> Duration {
+    let max = Duration::from_secs(config.output.reconnect_delay.max(1) as u64);
+
+    RECONNECT_MIN
+        .saturating_mul(2_u32.saturating_pow(failures.saturating_sub(1)))
+        .min(max)
+}
+
+/// Keep the leg running and feed it with chunks from the queue.
+async fn leg_loop(
+    manager: ChannelManager,
+    config: PlayoutConfig,
+    log_format: String,
+    leg: Leg,
+    mut queue: mpsc::Receiver<Vec<u8>>,
+    stats: Arc<LegStats>,
+) {
+    let id = config.general.channel_id;
+    let attempts = config.output.reconnect_attempts;
+    let alert = Duration::from_secs(config.output.reconnect_alert.max(0) as u64);
+    let mut failures = 0;
+    let mut down_since: Option<Instant> = None;
+    let mut alerted = false;
+
+    while manager.is_alive.load(Ordering::SeqCst) {
+        let started = Instant::now();
+
+        match spawn_leg(&config, &log_format, &leg) {
+            Ok(mut child) => {
+                let mut stdin = child.stdin.take().unwrap();
+                stats.connects.fetch_add(1, Ordering::SeqCst);
+
+                info!(target: Target::file_mail(), channel = id; "Start <b><magenta>{}</></b>", leg.name);
+
+                while let Some(chunk) = queue.recv().await {
+                    if stdin.write_all(&chunk).await.is_err() {
+                        stats
+                            .dropped
+                            .fetch_add(chunk.len() as u64, Ordering::SeqCst);
+                        break;
+                    }
+
+                    stats.sent.fetch_add(chunk.len() as u64, Ordering::SeqCst);
+
+                    if started.elapsed() > CONNECTED_AFTER && (failures > 0 || alerted) {
+                        if alerted {
+                            let seconds = down_since.map_or(0, |d| d.elapsed().as_secs());
+
+                            info!(target: Target::file_mail(), channel = id;
+                                "<b><magenta>{}</></b> is back after <yellow>{seconds}s</>", leg.name
+                            );
+                            manager.emit(
+                                EventKind::OutputUnreachable,
+                                json!({"output": leg.name, "active": false, "seconds": seconds}),
+                            );
+                        }
+
+                        failures = 0;
+                        down_since = None;
+                        alerted = false;
+                    }
+                }
+
+                if queue.is_closed() && queue.is_empty() {
+                    return;
+                }
+
+                if let Err(e) = child.kill().await {
+                    debug!(target: Target::file_mail(), channel = id; "{}: {e}", leg.name);
+                }
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "{} failed: {e}", leg.name);
+            }
+        }
+
+        if started.elapsed() > CONNECTED_AFTER {
+            failures = 0;
+        }
+
+        failures += 1;
+        let down = *down_since.get_or_insert_with(Instant::now);
+
+        if attempts > 0 && failures > attempts as u32 {
+            error!(target: Target::file_mail(), channel = id;
+                "<b><magenta>{}</></b> failed <yellow>{attempts}</> times, no more attempts", leg.name
+            );
+            break;
+        }
+
+        if !alert.is_zero() && !alerted && down.elapsed() >= alert {
+            alerted = true;
+
+            error!(target: Target::file_mail(), channel = id;
+                "<b><magenta>{}</></b> is unreachable for <yellow>{}s</>", leg.name, down.elapsed().as_secs()
+            );
+            manager.emit(
+                EventKind::OutputUnreachable,
+                json!({"output": leg.name, "active": true, "seconds": down.elapsed().as_secs()}),
+            );
+        }
+
+        let delay = reconnect_delay(&config, failures);
+
+        warn!(target: Target::file_mail(), channel = id;
+            "<b><magenta>{}</></b> stopped, restart in <yellow>{}s</>", leg.name, delay.as_secs()
+        );
+
+        tokio::time::sleep(delay).await;
+
+        // drop what was queued while disconnected
+        while let Ok(chunk) = queue.try_recv() {
+            stats
+                .dropped
+                .fetch_add(chunk.len() as u64, Ordering::SeqCst);
+        }
+    }
+}
+
+async fn udp_socket(address: &str) -> io::Result<UdpSocket> {
+    let target = lookup_host(address)
+        .await?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, address.to_string()))?;
+    let socket = UdpSocket::bind(if target.is_ipv6() {
+        "[::]:0"
+    } else {
+        "0.0.0.0:0"
+    })
+    .await?;
+    socket.connect(target).await?;
+
+    Ok(socket)
+}
+
+/// Send the chunks from the queue to a UDP destination, in datagrams of whole packets.
+async fn udp_loop(
+    config: PlayoutConfig,
+    leg: Leg,
+    address: String,
+    mut queue: mpsc::Receiver<Vec<u8>>,
+    stats: Arc<LegStats>,
+) {
+    let id = config.general.channel_id;
+    let socket = match udp_socket(&address).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            error!(target: Target::file_mail(), channel = id; "{} failed: {e}", leg.name);
+            return;
+        }
+    };
+    let mut rest = vec![];
+
+    stats.connects.fetch_add(1, Ordering::SeqCst);
+
+    info!(target: Target::file_mail(), channel = id;
+        "Start <b><magenta>{}</></b> to <yellow>{address}</>", leg.name
+    );
+
+    while let Some(chunk) = queue.recv().await {
+        rest.extend_from_slice(&chunk);
+        let end = rest.len() - rest.len() % UDP_PAYLOAD;
+
+        for datagram in rest[..end].chunks(UDP_PAYLOAD) {
+            // a destination which not listens is no reason to stop
+            if socket.send(datagram).await.is_ok() {
+                stats
+                    .sent
+                    .fetch_add(datagram.len() as u64, Ordering::SeqCst);
+            } else {
+                stats
+                    .dropped
+                    .fetch_add(datagram.len() as u64, Ordering::SeqCst);
+            }
+        }
+
+        rest.drain(..end);
+    }
+}
+
+/// Build the ffmpeg command for the standby slate, with the encoder parameters.
+pub fn slate_cmd(config: &PlayoutConfig, log_format: &str) -> Option<Vec<String>> {
+    let slate = &config.output.reconnect_slate;
+
+    if slate.is_empty() || config.processing.audio_only || config.output.mode == Icecast {
+        return None;
+    }
+
+    let params = config.output.output_cmd.as_ref()?;
+    let mut cmd = vec_strings![
+        "-hide_banner",
+        "-nostats",
+        "-v",
+        log_format,
+        "-re",
+        "-loop",
+        "1",
+        "-framerate",
+        config.processing.fps,
+        "-i",
+        slate,
+        "-f",
+        "lavfi",
+        "-i",
+        "anullsrc=r=48000:cl=stereo",
+        "-map",
+        "0:v",
+        "-map",
+        "1:a",
+        "-vf",
+        format!(
+            "scale={}:{},format=yuv420p",
+            config.processing.width, config.processing.height
+        )
+    ];
+
+    // the encoder parameters, without the mapping of the filter chain
+    let mut skip = false;
+
+    for param in params {
+        if skip {
+            skip = false;
+        } else if param == "-map" || param == "-filter_complex" {
+            skip = true;
+        } else {
+            cmd.push(param.clone());
+        }
+    }
+
+    Some(cmd)
+}
+
+/// Encode the slate and pass it to the legs, until the task gets aborted.
+async fn slate(
+    config: PlayoutConfig,
+    log_format: String,
+    senders: Vec<mpsc::Sender<Vec<u8>>>,
+) -> Result<(), ServiceError> {
+    let id = config.general.channel_id;
+    let Some(cmd) = slate_cmd(&config, &log_format) else {
+        return Ok(());
+    };
+
+    debug!(target: Target::file_mail(), channel = id;
+        "Slate CMD: <bright-blue>ffmpeg {}</>",
+        fmt_cmd(&cmd)
+    );
+
+    let mut child = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(cmd)
+        .kill_on_drop(true)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    let mut stdout = child.stdout.take().unwrap();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let bytes_len = stdout.read(&mut buffer[..]).await?;
+
+        if bytes_len == 0 {
+            break;
+        }
+
+        for tx in &senders {
+            let _ = tx.try_send(buffer[..bytes_len].to_vec());
+        }
+    }
+
+    Ok(())
+}
+
+/// Relay the encoder output to all legs and log statistics.
+///
+/// Reading from the encoder never waits for the network,
+/// chunks which not fit in the queue of a leg are dropped for this leg.
+/// UDP legs get the stream with the cues.
+pub async fn relay(
+    manager: ChannelManager,
+    config: PlayoutConfig,
+    log_format: String,
+    mut encoder: ChildStdout,
+    cues: Arc<Mutex<Vec<CueEvent>>>,
+) -> Result<(), ServiceError> {
+    let id = config.general.channel_id;
+    let mut senders = vec![];
+    let mut tasks = vec![];
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut last_stats = Instant::now();
+    let mut standby: Option<JoinHandle<Result<(), ServiceError>>> = None;
+    let mut injector = TsInjector::new();
+    let mut cue_legs = vec![];
+
+    for leg in legs(&config) {
+        let stats = Arc::new(LegStats::default());
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+
+        match leg.udp.clone() {
+            Some(address) => {
+                cue_legs.push(senders.len());
+                tasks.push(tokio::spawn(udp_loop(
+                    config.clone(),
+                    leg.clone(),
+                    address,
+                    rx,
+                    stats.clone(),
+                )));
+            }
+            None => tasks.push(tokio::spawn(leg_loop(
+                manager.clone(),
+                config.clone(),
+                log_format.clone(),
+                leg.clone(),
+                rx,
+                stats.clone(),
+            ))),
+        }
+
+        senders.push((leg, tx, stats));
+    }
+
+    loop {
+        let bytes_len = match timeout(SLATE_AFTER, encoder.read(&mut buffer[..])).await {
+            Ok(result) => result?,
+            Err(_) => {
+                if standby.is_none() && slate_cmd(&config, &log_format).is_some() {
+                    warn!(target: Target::file_mail(), channel = id; "No data from encoder, send slate");
+
+                    standby = Some(tokio::spawn(slate(
+                        config.clone(),
+                        log_format.clone(),
+                        senders.iter().map(|(_, tx, _)| tx.clone()).collect(),
+                    )));
+                }
+
+                continue;
+            }
+        };
+
+        if bytes_len == 0 {
+            break;
+        }
+
+        if let Some(task) = standby.take() {
+            info!(target: Target::file_mail(), channel = id; "Encoder is back, stop slate");
+            task.abort();
+        }
+
+        let injected = if cue_legs.is_empty() {
+            vec![]
+        } else {
+            injector.schedule(&mut *cues.lock().await, Local::now());
+            injector.process(&buffer[..bytes_len])
+        };
+
+        for (i, (_, tx, stats)) in senders.iter().enumerate() {
+            let chunk = if cue_legs.contains(&i) {
+                injected.clone()
+            } else {
+                buffer[..bytes_len].to_vec()
+            };
+            let len = chunk.len() as u64;
+
+            if tx.try_send(chunk).is_err() {
+                stats.dropped.fetch_add(len, Ordering::SeqCst);
+            }
+        }
+
+        if last_stats.elapsed() >= STATS_INTERVAL {
+            for (leg, _, stats) in &senders {
+                let sent = stats.sent.load(Ordering::SeqCst);
+                let last_sent = stats.last_sent.swap(sent, Ordering::SeqCst);
+                let bitrate = (sent - last_sent) as f64 * 8.0 / last_stats.elapsed().as_secs_f64();
+
+                info!(target: Target::file_mail(), channel = id;
+                    "{} stats: <yellow>{:.0} kbit/s</>, sent <yellow>{:.1} MB</>, dropped <yellow>{:.1} MB</>, connections <yellow>{}</>",
+                    leg.name,
+                    bitrate / 1000.0,
+                    sent as f64 / 1_000_000.0,
+                    stats.dropped.load(Ordering::SeqCst) as f64 / 1_000_000.0,
+                    stats.connects.load(Ordering::SeqCst)
+                );
+            }
+
+            last_stats = Instant::now();
+        }
+    }
+
+    if let Some(task) = standby.take() {
+        task.abort();
+    }
+
+    senders.clear();
+
+    for task in tasks {
+        task.abort();
+    }
+
+    Ok(())
+}
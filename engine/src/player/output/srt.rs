@@ -0,0 +1,58 @@
+/*
+SRT output.
+
+The encoder writes a MPEG-TS stream to stdout, which the relay copies to a separate
+ffmpeg sender with the SRT connection. When the connection drops, only the sender
+gets restarted, the playout keeps running and the stream data is dropped until the
+connection is back.
+*/
+
+use super::relay::Leg;
+use crate::utils::config::{PlayoutConfig, SrtMode};
+use crate::vec_strings;
+
+pub(crate) fn url_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Build the SRT url from config.
+pub fn srt_url(config: &PlayoutConfig) -> String {
+    let output = &config.output;
+    // ffmpeg expects the latency in microseconds
+    let mut url = format!(
+        "srt://{}?mode={}&transtype=live&latency={}",
+        output.srt_address,
+        output.srt_mode,
+        output.srt_latency.max(0) * 1000
+    );
+
+    if output.srt_mode == SrtMode::Caller {
+        url.push_str("&connect_timeout=5000");
+    }
+
+    if !output.srt_passphrase.is_empty() {
+        url.push_str(&format!(
+            "&passphrase={}&pbkeylen=16",
+            url_encode(&output.srt_passphrase)
+        ));
+    }
+
+    url
+}
+
+/// Output leg for the SRT sender.
+pub fn leg(config: &PlayoutConfig) -> Leg {
+    Leg {
+        name: format!("SRT {}", config.output.srt_address),
+        cmd: vec_strings!["-map", "0", "-c", "copy", "-f", "mpegts", srt_url(config)],
+        secret: Some(url_encode(&config.output.srt_passphrase)),
+        udp: None,
+    }
+}
@@ -3,8 +3,10 @@ use std::process::Stdio;
 use log::*;
 use tokio::process::{Child, Command};
 
+use super::relay::is_relay;
 use crate::utils::{
-    config::PlayoutConfig,
+    config::{OutputMode::*, PlayoutConfig, SubtitleMode},
+    ffmpeg_bundle,
     logging::{fmt_cmd, Target},
 };
 use crate::vec_strings;
@@ -18,10 +20,18 @@ use crate::{
 
 /// Streaming Output
 ///
-/// Prepare the ffmpeg command for streaming output
+/// Prepare the ffmpeg command for streaming output, also used for the NDI sender.
+/// With SRT output or output legs, the encoded stream goes to stdout, for the relay.
 pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, ServiceError> {
     let id = config.general.channel_id;
-    let mut enc_prefix = vec_strings!["-hide_banner", "-nostats", "-v", log_format];
+    let mut enc_prefix = vec_strings![
+        "-hide_banner",
+        "-nostats",
+        "-progress",
+        "pipe:2",
+        "-v",
+        log_format
+    ];
     let mut media = Media {
         unit: Encoder,
         ..Default::default()
@@ -34,6 +44,11 @@ pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, S
 
     enc_prefix.append(&mut vec_strings!["-re", "-i", "pipe:0"]);
 
+    if config.processing.subtitle_mode == SubtitleMode::Passthrough && config.output.mode != Ndi {
+        // DVB subtitles from the decoder
+        enc_prefix.append(&mut vec_strings!["-map", "0:s?", "-c:s", "copy"]);
+    }
+
     let enc_cmd = prepare_output_cmd(config, enc_prefix, &media.filter);
 
     debug!(target: Target::file_mail(), channel = id;
@@ -41,10 +56,17 @@ pub async fn output(config: &PlayoutConfig, log_format: &str) -> Result<Child, S
         fmt_cmd(&enc_cmd)
     );
 
-    let child = Command::new("ffmpeg")
+    let stdout = if is_relay(config) {
+        Stdio::piped()
+    } else {
+        Stdio::inherit()
+    };
+
+    let child = Command::new(ffmpeg_bundle::ffmpeg())
         .args(enc_cmd)
         .kill_on_drop(true)
         .stdin(Stdio::piped())
+        .stdout(stdout)
         .stderr(Stdio::piped())
         .spawn()?;
 
@@ -0,0 +1,32 @@
+/*
+WHIP output.
+
+The encoder writes H.264 without B-frames and Opus as MPEG-TS to stdout, the relay copies
+it to a separate ffmpeg sender, which publishes it over WebRTC to the WHIP endpoint of a
+SFU or CDN. The sender needs ffmpeg 8 or newer, with the WHIP muxer.
+*/
+
+use super::relay::Leg;
+use crate::utils::config::PlayoutConfig;
+use crate::vec_strings;
+
+/// Output leg for the WHIP sender, the token goes as bearer token to the endpoint.
+pub fn leg(config: &PlayoutConfig) -> Leg {
+    let output = &config.output;
+    let mut cmd = vec_strings!["-map", "0", "-c", "copy", "-f", "whip"];
+    let mut secret = None;
+
+    if !output.whip_token.is_empty() {
+        cmd.append(&mut vec_strings!["-authorization", output.whip_token]);
+        secret = Some(output.whip_token.clone());
+    }
+
+    cmd.push(output.whip_url.clone());
+
+    Leg {
+        name: format!("WHIP {}", output.whip_url),
+        cmd,
+        secret,
+        udp: None,
+    }
+}
@@ -0,0 +1,74 @@
+/*
+Blacklist for corrupt files.
+
+When the decoder fails again and again on the same file, the file goes to the blacklist
+table. The playlist replaces it with filler and the folder mode skips it, instead of
+restarting the playout with the broken file in a loop. A changed file, with other size
+or modification time, plays again.
+*/
+
+use std::path::Path;
+
+use log::*;
+use serde_json::json;
+
+use crate::db::handles;
+use crate::player::{controller::ChannelManager, utils::loudness::file_version};
+use crate::utils::{events::EventKind, logging::Target};
+
+/// Decoder failures in a row on the same file, until it goes to the blacklist.
+pub const MAX_FAILURES: usize = 3;
+
+/// Check if the file is on the blacklist, remote sources are never listed.
+pub async fn is_listed(manager: &ChannelManager, source: &str) -> bool {
+    if !Path::new(source).is_file() {
+        return false;
+    }
+
+    let (size, modified) = file_version(source).await;
+
+    handles::select_blacklisted(&manager.db_pool, source, size, modified)
+        .await
+        .unwrap_or_default()
+}
+
+/// Count a failed decoder run, returns true when the file is on the blacklist now.
+pub async fn failed(manager: &ChannelManager, source: &str, error: &str) -> bool {
+    let id = manager.id;
+    let mut failures = manager.decoder_failures.lock().await;
+
+    if failures.0 == source {
+        failures.1 += 1;
+    } else {
+        *failures = (source.to_string(), 1);
+    }
+
+    if failures.1 < MAX_FAILURES || !Path::new(source).is_file() {
+        return false;
+    }
+
+    *failures = (String::new(), 0);
+
+    let (size, modified) = file_version(source).await;
+
+    if let Err(e) = handles::insert_blacklist(&manager.db_pool, source, size, modified, error).await
+    {
+        error!(target: Target::file_mail(), channel = id; "Save blacklist entry failed: {e}");
+    }
+
+    error!(target: Target::file_mail(), channel = id;
+        "Decoder failed <yellow>{MAX_FAILURES}</> times on <b><magenta>{source}</></b>, file is blacklisted"
+    );
+
+    manager.emit(
+        EventKind::Corrupt,
+        json!({"source": source, "error": error}),
+    );
+
+    true
+}
+
+/// Reset the failure counter after a successful decoder run.
+pub async fn played(manager: &ChannelManager) {
+    *manager.decoder_failures.lock().await = (String::new(), 0);
+}
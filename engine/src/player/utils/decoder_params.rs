@@ -0,0 +1,43 @@
+/*
+Decoder parameters of a single clip.
+
+Some archive files need special handling, like error concealment or a forced pixel format,
+without changing the settings of the channel. A playlist item can carry them in
+`custom_decoder_params`. Input options go before the `-i` of the clip, so they win over the
+same options from the advanced decoder settings. `-pix_fmt` and `-deinterlace` are not input
+options, they go to the filter chain of the clip.
+*/
+
+use shlex::split;
+
+/// Parsed decoder parameters of a clip.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DecoderParams {
+    pub input: Vec<String>,
+    pub pix_fmt: Option<String>,
+    /// Deinterlace on or off, without it the field order of the clip decides.
+    pub deinterlace: Option<bool>,
+}
+
+impl DecoderParams {
+    pub fn parse(params: &str) -> Self {
+        let mut decoder_params = Self::default();
+        let mut iter = split(params).unwrap_or_default().into_iter();
+
+        while let Some(param) = iter.next() {
+            match param.as_str() {
+                "-pix_fmt" => decoder_params.pix_fmt = iter.next(),
+                "-deinterlace" => {
+                    decoder_params.deinterlace = iter.next().and_then(|v| match v.as_str() {
+                        "1" | "on" | "true" => Some(true),
+                        "0" | "off" | "false" => Some(false),
+                        _ => None,
+                    });
+                }
+                _ => decoder_params.input.push(param),
+            }
+        }
+
+        decoder_params
+    }
+}
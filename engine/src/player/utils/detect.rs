@@ -0,0 +1,181 @@
+/*
+Silence, black and freeze frame alarms.
+
+The decoder runs silencedetect, blackdetect and freezedetect on the clip and prints
+the frame metadata from the detect filters to stderr. When silence, black or a frozen
+picture lasts longer than the configured duration, an error gets logged and an event
+is emitted. A frozen picture also skips to the next clip.
+*/
+
+use std::time::Duration;
+
+use log::*;
+use serde_json::json;
+use tokio::{task::JoinHandle, time::sleep};
+
+use crate::player::{controller::ChannelManager, utils::Media};
+use crate::utils::{
+    config::PlayoutConfig,
+    control::{control_state, ControlParams, PlayerCtl},
+    events::{clip_data, EventKind},
+    logging::Target,
+};
+
+/// Print the frame metadata to stderr, independent of the log level.
+pub const METADATA_PRINT: &str = "mode=print:file=pipe\\\\:2:direct=1";
+
+fn label(kind: &EventKind) -> &'static str {
+    match kind {
+        EventKind::Silence => "Silence",
+        EventKind::Black => "Black frames",
+        _ => "Frozen picture",
+    }
+}
+
+/// Running detection, the alarm fires when its timer runs out.
+struct Detection {
+    start: f64,
+    alarm: JoinHandle<()>,
+}
+
+pub struct Detector {
+    manager: ChannelManager,
+    node: Media,
+    config: PlayoutConfig,
+    silence: Option<Detection>,
+    black: Option<Detection>,
+    freeze: Option<Detection>,
+}
+
+impl Detector {
+    pub fn new(manager: &ChannelManager, config: &PlayoutConfig, node: &Media) -> Option<Self> {
+        if config.monitor.silence_duration <= 0.0
+            && config.monitor.black_duration <= 0.0
+            && config.monitor.freeze_duration <= 0.0
+        {
+            return None;
+        }
+
+        Some(Self {
+            manager: manager.clone(),
+            node: node.clone(),
+            config: config.clone(),
+            silence: None,
+            black: None,
+            freeze: None,
+        })
+    }
+
+    /// Handle the metadata lines from the detect filters, returns false for all other lines.
+    pub fn process(&mut self, line: &str) -> bool {
+        let line = line.trim();
+
+        if line.starts_with("frame:") && line.contains("pts_time:") {
+            return true;
+        }
+
+        let Some((key, value)) = line.strip_prefix("lavfi.").and_then(|l| l.split_once('=')) else {
+            return false;
+        };
+        let time = value.parse::<f64>().unwrap_or_default();
+
+        match key {
+            "silence_start" => self.start(EventKind::Silence, time),
+            "silence_end" => self.end(EventKind::Silence, time),
+            "black_start" => self.start(EventKind::Black, time),
+            "black_end" => self.end(EventKind::Black, time),
+            "freezedetect.freeze_start" => self.start(EventKind::Freeze, time),
+            "freezedetect.freeze_end" => self.end(EventKind::Freeze, time),
+            _ => {
+                return key.starts_with("silence_")
+                    || key.starts_with("black_")
+                    || key.starts_with("freezedetect.")
+            }
+        }
+
+        true
+    }
+
+    fn detection(&mut self, kind: &EventKind) -> &mut Option<Detection> {
+        match kind {
+            EventKind::Silence => &mut self.silence,
+            EventKind::Black => &mut self.black,
+            _ => &mut self.freeze,
+        }
+    }
+
+    fn start(&mut self, kind: EventKind, time: f64) {
+        let duration = match kind {
+            EventKind::Silence => self.config.monitor.silence_duration,
+            EventKind::Black => self.config.monitor.black_duration,
+            _ => self.config.monitor.freeze_duration,
+        };
+
+        if duration <= 0.0 || self.detection(&kind).is_some() {
+            return;
+        }
+
+        let manager = self.manager.clone();
+        let node = self.node.clone();
+        let start = node.seek + time;
+        let event = kind.clone();
+
+        let alarm = tokio::spawn(async move {
+            sleep(Duration::from_secs_f64(duration)).await;
+
+            error!(target: Target::file_mail(), channel = manager.id;
+                "{} for more than <yellow>{duration}</> seconds at <yellow>{start:.3}</> in <b><magenta>{}</></b>",
+                label(&event),
+                node.source
+            );
+
+            let mut data = clip_data(&node);
+            data["start"] = json!(start);
+            data["duration"] = json!(duration);
+
+            let skip = event == EventKind::Freeze;
+            manager.emit(event, data);
+
+            if skip {
+                let params = ControlParams {
+                    control: PlayerCtl::Next,
+                    ..Default::default()
+                };
+
+                if let Err(e) = control_state(&manager.db_pool, &manager, &params).await {
+                    error!(target: Target::file_mail(), channel = manager.id; "Skip frozen clip failed: {e}");
+                }
+            }
+        });
+
+        *self.detection(&kind) = Some(Detection { start, alarm });
+    }
+
+    fn end(&mut self, kind: EventKind, time: f64) {
+        let id = self.manager.id;
+        let end = self.node.seek + time;
+
+        if let Some(detection) = self.detection(&kind).take() {
+            if detection.alarm.is_finished() {
+                info!(target: Target::file_mail(), channel = id;
+                    "{} ended after <yellow>{:.3}</> seconds",
+                    label(&kind),
+                    end - detection.start
+                );
+            } else {
+                detection.alarm.abort();
+            }
+        }
+    }
+}
+
+impl Drop for Detector {
+    fn drop(&mut self) {
+        for detection in [self.silence.take(), self.black.take(), self.freeze.take()]
+            .into_iter()
+            .flatten()
+        {
+            detection.alarm.abort();
+        }
+    }
+}
@@ -0,0 +1,99 @@
+/*
+Gap filling.
+
+A playlist, which ends before the next day starts, leaves a gap. The gap policy of the channel
+chooses the fill by the length of the gap, one rule per line like `60-600 fit`:
+
+- `loop` repeats the last clip of the playlist
+- `fit` takes the filler clip, which fits the gap best
+- `slate <path>` loops a slate over the gap
+- `tag <name>` or `query <query>` takes the best fitting clip from the media library
+
+A clip, which is shorter than the gap, leaves a smaller gap, which gets its own rule. When no
+rule matches, or the policy has nothing to play, the filler fills the gap like before.
+*/
+
+use std::path::Path;
+
+use log::*;
+
+use crate::file::utils::filler::{add_probes, select};
+use crate::player::{controller::ChannelManager, utils::Media};
+use crate::utils::{
+    config::{GapPolicy, GapRule, PlayoutConfig},
+    library,
+    logging::Target,
+};
+
+/// Policy of the first rule, which covers the gap length.
+pub fn policy(rules: &[GapRule], gap: f64) -> Option<&GapPolicy> {
+    rules.iter().find(|r| r.contains(gap)).map(|r| &r.policy)
+}
+
+/// Index of the clip, which fits the gap best.
+///
+/// That is the longest clip, which fits into the gap, or the one with the smallest cut, when all
+/// are longer. Clips which played in this gap already are only taken, when there is no other one.
+pub fn best_fit(clips: &[(String, f64)], gap: f64, played: &[String]) -> Option<usize> {
+    let fresh = (0..clips.len())
+        .filter(|i| !played.contains(&clips[*i].0))
+        .collect::<Vec<_>>();
+
+    [fresh, (0..clips.len()).collect()]
+        .iter()
+        .find_map(|candidates| {
+            let durations = candidates.iter().map(|i| clips[*i].1).collect::<Vec<_>>();
+
+            select(&durations, gap, true)
+                .first()
+                .map(|(i, _)| candidates[*i])
+        })
+}
+
+/// Filler clips with their length, clips without probe get probed once.
+async fn fillers(manager: &ChannelManager) -> Vec<(String, f64)> {
+    let mut fillers = manager.filler_list.lock().await;
+
+    add_probes(&mut fillers, manager.id).await;
+
+    fillers
+        .iter()
+        .map(|f| (f.source.clone(), f.duration))
+        .collect()
+}
+
+/// Source and length for the gap, `None` when the policy has nothing to play.
+pub async fn fill(
+    manager: &ChannelManager,
+    config: &PlayoutConfig,
+    policy: &GapPolicy,
+    gap: f64,
+    last: Option<&Media>,
+    played: &[String],
+) -> Option<(String, f64)> {
+    let id = config.general.channel_id;
+    let clips = match policy {
+        GapPolicy::Loop => {
+            return last
+                .filter(|node| Path::new(&node.source).is_file())
+                .map(|node| (node.source.clone(), gap));
+        }
+        GapPolicy::Slate(path) => {
+            return Path::new(path).is_file().then(|| (path.clone(), gap));
+        }
+        GapPolicy::Fit => fillers(manager).await,
+        GapPolicy::Query(query) => match library::query(&manager.db_pool, id, query).await {
+            Ok(items) => items.into_iter().map(|i| (i.path, i.duration)).collect(),
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Gap query <b><magenta>{query}</></b> failed: {e}");
+                return None;
+            }
+        },
+    };
+
+    best_fit(&clips, gap, played).map(|i| {
+        let (source, duration) = clips[i].clone();
+
+        (source, duration.min(gap))
+    })
+}
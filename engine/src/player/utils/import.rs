@@ -1,24 +1,378 @@
-/// Import text/m3u file and create a playlist out of it
+/// Import text/m3u file, or a CSV rundown, and create a playlist out of it.
+/// M3U and XSPF playlists can also be used directly as day playlists.
 use std::{
     io::{Error, ErrorKind},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
+use async_walkdir::WalkDir;
+use regex::Regex;
+use reqwest::Url;
 use tokio::{
-    fs::{create_dir_all, File},
+    fs::{self, create_dir_all, File},
     io::{AsyncBufReadExt, BufReader},
 };
+use tokio_stream::StreamExt;
 
-use crate::player::utils::{json_reader, json_serializer::JsonPlaylist, json_writer, Media};
+use crate::player::utils::{
+    is_remote, json_reader, json_serializer::JsonPlaylist, json_writer, sec_to_time, time_to_sec,
+    Media,
+};
+
+/// Playlist formats, which are read besides JSON.
+pub const FOREIGN_FORMATS: [&str; 3] = ["m3u", "m3u8", "xspf"];
+
+/// Entry of a M3U or XSPF playlist.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PlaylistEntry {
+    pub source: String,
+    pub title: Option<String>,
+    pub duration: Option<f64>,
+}
+
+/// Absolute source, relative paths are relative to the playlist.
+fn entry_source(source: &str, base: &Path) -> String {
+    if is_remote(source) || Path::new(source).is_absolute() {
+        source.to_string()
+    } else {
+        base.join(source).to_string_lossy().to_string()
+    }
+}
+
+/// Entries of a M3U playlist, with title and duration from `#EXTINF`.
+pub fn parse_m3u(data: &str, base: &Path) -> Vec<PlaylistEntry> {
+    let mut entries = vec![];
+    let mut info: Option<(Option<f64>, Option<String>)> = None;
+
+    for line in data.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            let (duration, title) = extinf.split_once(',').unwrap_or((extinf, ""));
+            // attributes like tvg-id can follow the duration
+            let duration = duration
+                .split_whitespace()
+                .next()
+                .and_then(|d| d.parse().ok())
+                .filter(|d: &f64| *d > 0.0);
+            let title = Some(title.trim().to_string()).filter(|t| !t.is_empty());
+
+            info = Some((duration, title));
+        } else if !line.starts_with('#') {
+            let (duration, title) = info.take().unwrap_or_default();
+
+            entries.push(PlaylistEntry {
+                source: entry_source(line, base),
+                title,
+                duration,
+            });
+        }
+    }
+
+    entries
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Entries of a XSPF playlist, the duration is in milliseconds.
+pub fn parse_xspf(data: &str, base: &Path) -> Vec<PlaylistEntry> {
+    let track_re = Regex::new(r"(?s)<track>(.*?)</track>").unwrap();
+    let tag = |track: &str, name: &str| {
+        Regex::new(&format!(r"(?s)<{name}>(.*?)</{name}>"))
+            .ok()
+            .and_then(|r| r.captures(track))
+            .map(|c| unescape(c[1].trim()))
+    };
+    let mut entries = vec![];
+
+    for track in track_re.captures_iter(data) {
+        let Some(location) = tag(&track[1], "location") else {
+            continue;
+        };
+
+        let source = if location.starts_with("file://") {
+            match Url::parse(&location)
+                .ok()
+                .and_then(|u| u.to_file_path().ok())
+            {
+                Some(path) => path.to_string_lossy().to_string(),
+                None => continue,
+            }
+        } else {
+            entry_source(&location, base)
+        };
+
+        entries.push(PlaylistEntry {
+            source,
+            title: tag(&track[1], "title"),
+            duration: tag(&track[1], "duration")
+                .and_then(|d| d.parse::<f64>().ok())
+                .map(|d| d / 1000.0),
+        });
+    }
+
+    entries
+}
+
+/// M3U or XSPF playlist of the day, when there is no JSON playlist.
+pub fn foreign_playlist_path(json_path: &Path) -> Option<PathBuf> {
+    FOREIGN_FORMATS
+        .iter()
+        .map(|ext| json_path.with_extension(ext))
+        .find(|p| p.is_file())
+}
+
+pub fn is_foreign_playlist(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| FOREIGN_FORMATS.contains(&e.to_string_lossy().to_lowercase().as_str()))
+}
+
+/// Read a M3U or XSPF playlist as day playlist.
+///
+/// The clips are probed for their duration, the duration from the playlist is the fallback.
+/// Entries without any duration are left out.
+pub async fn read_foreign_playlist(path: &Path, date: &str) -> Result<JsonPlaylist, Error> {
+    let data = fs::read_to_string(path).await?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    let entries = if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("xspf"))
+    {
+        parse_xspf(&data, base)
+    } else {
+        parse_m3u(&data, base)
+    };
+    let mut playlist = JsonPlaylist {
+        channel: "Channel 1".to_string(),
+        date: date.to_string(),
+        path: None,
+        start_sec: None,
+        length: None,
+        modified: None,
+        program: vec![],
+    };
+
+    for entry in entries {
+        let mut node = Media::new(0, &entry.source, true).await;
+
+        if node.duration <= 0.0 {
+            node.duration = entry.duration.unwrap_or_default();
+            node.out = node.duration;
+        }
+
+        if node.duration > 0.0 {
+            node.title = entry.title;
+            playlist.program.push(node);
+        }
+    }
+
+    Ok(playlist)
+}
+
+/// Allowed difference in seconds between the start time of a rundown row and its calculated start.
+const START_TOLERANCE: f64 = 1.0;
+
+/// Row of a rundown, with the values as they are in the file.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RundownRow {
+    pub row: usize,
+    pub start: String,
+    pub source: String,
+    pub title: String,
+    pub duration: String,
+}
+
+/// Read the rows of a CSV rundown.
+///
+/// The first line is the header, with the columns `start`, `source` (also `file`, `path`,
+/// `id` or `house_id`), `title` and `duration`. Columns can be separated by comma, semicolon or tab.
+pub fn read_rundown(data: &str) -> Result<Vec<RundownRow>, Error> {
+    let header = data.lines().next().unwrap_or_default();
+    let delimiter = [b',', b';', b'\t']
+        .into_iter()
+        .max_by_key(|d| header.matches(*d as char).count())
+        .unwrap_or(b',');
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))?
+        .iter()
+        .map(str::to_lowercase)
+        .collect();
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+    let source = column(&["source", "file", "path", "id", "house_id"]).ok_or(Error::new(
+        ErrorKind::InvalidData,
+        "Rundown has no source column",
+    ))?;
+    let start = column(&["start"]);
+    let title = column(&["title"]);
+    let duration = column(&["duration"]);
+    let mut rows = vec![];
+
+    for (i, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let value = |c: Option<usize>| {
+            c.and_then(|c| record.get(c))
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+
+        rows.push(RundownRow {
+            // header is row 1
+            row: i + 2,
+            start: value(start),
+            source: value(Some(source)),
+            title: value(title),
+            duration: value(duration),
+        });
+    }
+
+    Ok(rows)
+}
+
+/// Seconds from a time string like `hh:mm:ss.ms`, or a plain number of seconds.
+fn parse_time(time: &str) -> Option<f64> {
+    if time.contains(':') {
+        let valid = time
+            .split(':')
+            .all(|t| !t.is_empty() && t.parse::<f64>().is_ok());
+
+        return valid.then(|| time_to_sec(time, &None));
+    }
+
+    time.parse().ok().filter(|t: &f64| *t >= 0.0)
+}
+
+/// Find the file of a house ID in the storage, by its file name without extension.
+async fn house_id_file(storage: &Path, id: &str) -> Option<PathBuf> {
+    let mut entries = WalkDir::new(storage);
+
+    while let Some(Ok(entry)) = entries.next().await {
+        let path = entry.path();
+
+        if path.is_file() && path.file_stem().is_some_and(|s| s == id) {
+            return Some(path);
+        }
+    }
+
+    None
+}
+
+/// Convert rundown rows to playlist clips.
+///
+/// All rows are validated, the errors are reported with their row number.
+pub async fn rundown_program(
+    rows: Vec<RundownRow>,
+    storage: &Path,
+    day_start: f64,
+) -> Result<Vec<Media>, Vec<String>> {
+    let mut program = vec![];
+    let mut errors = vec![];
+    let mut begin = day_start;
+
+    for row in rows {
+        let mut row_errors = vec![];
+        let mut source = row.source.clone();
+
+        if source.is_empty() {
+            row_errors.push("source is missing".to_string());
+        } else if !source.contains('/') && Path::new(&source).extension().is_none() {
+            match house_id_file(storage, &source).await {
+                Some(path) => source = path.to_string_lossy().to_string(),
+                None => row_errors.push(format!("house ID {source} not found")),
+            }
+        } else if !source.starts_with("http") && !Path::new(&source).is_file() {
+            row_errors.push(format!("file {source} not exists"));
+        }
+
+        let duration = if row.duration.is_empty() {
+            None
+        } else {
+            let duration = parse_time(&row.duration);
+
+            if duration.is_none() {
+                row_errors.push(format!("invalid duration {}", row.duration));
+            }
+
+            duration
+        };
+
+        if !row.start.is_empty() {
+            match parse_time(&row.start) {
+                Some(start) => {
+                    let mut start = start;
+
+                    if start < day_start {
+                        start += 86400.0;
+                    }
+
+                    if (start - begin).abs() > START_TOLERANCE {
+                        row_errors.push(format!(
+                            "start {} does not match calculated start {}",
+                            row.start,
+                            sec_to_time(begin % 86400.0)
+                        ));
+                    }
+                }
+                None => row_errors.push(format!("invalid start {}", row.start)),
+            }
+        }
+
+        if !row_errors.is_empty() {
+            errors.push(format!("Row {}: {}", row.row, row_errors.join(", ")));
+            continue;
+        }
+
+        let mut node = Media::new(0, &source, true).await;
+
+        if let Some(duration) = duration {
+            if node.duration <= 0.0 {
+                node.duration = duration;
+            }
+
+            node.out = duration.min(node.duration);
+        }
+
+        if node.out <= 0.0 {
+            errors.push(format!("Row {}: duration is missing", row.row));
+            continue;
+        }
+
+        if !row.title.is_empty() {
+            node.title = Some(row.title);
+        }
+
+        begin += node.out - node.seek;
+        program.push(node);
+    }
+
+    if errors.is_empty() {
+        Ok(program)
+    } else {
+        Err(errors)
+    }
+}
 
 pub async fn import_file(
     playlist_root: &Path,
+    storage: &Path,
+    day_start: f64,
     date: &str,
     channel_name: Option<String>,
     path: &Path,
 ) -> Result<String, Error> {
-    let file = File::open(path).await?;
-    let reader = BufReader::new(file);
     let mut playlist = JsonPlaylist {
         channel: channel_name.unwrap_or_else(|| "Channel 1".to_string()),
         date: date.to_string(),
@@ -47,13 +401,25 @@ pub async fn import_file(
 
     create_dir_all(playlist_path).await?;
 
-    let mut lines = reader.lines();
-    while let Some(line) = lines.next_line().await? {
-        if !line.starts_with('#') {
-            let item = Media::new(0, &line, true).await;
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"))
+    {
+        let rows = read_rundown(&fs::read_to_string(path).await?)?;
+
+        playlist.program = rundown_program(rows, storage, day_start)
+            .await
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e.join("\n")))?;
+    } else {
+        let mut lines = BufReader::new(File::open(path).await?).lines();
+
+        while let Some(line) = lines.next_line().await? {
+            if !line.starts_with('#') {
+                let item = Media::new(0, &line, true).await;
 
-            if item.duration > 0.0 {
-                playlist.program.push(item);
+                if item.duration > 0.0 {
+                    playlist.program.push(item);
+                }
             }
         }
     }
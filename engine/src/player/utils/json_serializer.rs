@@ -11,8 +11,11 @@ use crate::utils::{config::DUMMY_LEN, logging::Target};
 use crate::{
     file::StorageBackend,
     player::utils::{
-        get_date, is_remote, json_validate::validate_playlist, modified_time, time_from_header,
-        Media, PlayoutConfig,
+        can_crossfade, get_date,
+        import::{foreign_playlist_path, is_foreign_playlist, read_foreign_playlist},
+        is_remote,
+        json_validate::validate_playlist,
+        modified_time, time_from_header, Media, PlayoutConfig, Transition,
     },
 };
 
@@ -71,10 +74,29 @@ fn default_channel() -> String {
     "Channel 1".to_string()
 }
 
-pub fn set_defaults(playlist: &mut JsonPlaylist) {
+pub fn set_defaults(playlist: &mut JsonPlaylist, crossfade: f64, fps: f64) {
     let mut start_sec = playlist.start_sec.unwrap();
     let mut length = 0.0;
 
+    for item in &mut playlist.program {
+        // in point without the crossfade from a former run
+        item.seek -= item.crossfade_in;
+        item.crossfade_in = 0.0;
+        item.transition = None;
+        item.apply_timecode(fps);
+    }
+
+    // The next clip starts later by the crossfade length,
+    // this keeps the following clips in sync with their begin.
+    for i in 1..playlist.program.len() {
+        if can_crossfade(crossfade, &playlist.program[i - 1], &playlist.program[i]) {
+            playlist.program[i - 1].transition =
+                Some(Transition::new(&playlist.program[i], crossfade));
+            playlist.program[i].crossfade_in = crossfade;
+            playlist.program[i].seek += crossfade;
+        }
+    }
+
     // Add extra values to every media clip
     for (i, item) in playlist.program.iter_mut().enumerate() {
         item.begin = Some(start_sec);
@@ -119,6 +141,12 @@ pub async fn read_json(
             .with_extension("json");
     }
 
+    if !playlist_path.is_file() {
+        if let Some(foreign) = foreign_playlist_path(&playlist_path) {
+            playlist_path = foreign;
+        }
+    }
+
     let mut current_file = playlist_path.as_path().display().to_string();
 
     if let Some(p) = path {
@@ -158,7 +186,11 @@ pub async fn read_json(
                         ));
                     }
 
-                    set_defaults(&mut playlist);
+                    set_defaults(
+                        &mut playlist,
+                        config.processing.crossfade,
+                        config.processing.fps,
+                    );
 
                     return playlist;
                 }
@@ -167,21 +199,32 @@ pub async fn read_json(
     } else if playlist_path.is_file() {
         let modified = modified_time(&current_file).await;
 
-        let mut f = File::options()
-            .read(true)
-            .write(false)
-            .open(&current_file)
-            .await
-            .expect("Open json playlist file.");
-        let mut contents = String::new();
-        f.read_to_string(&mut contents)
-            .await
-            .expect("Read playlist content.");
-        let mut playlist: JsonPlaylist = match serde_json::from_str(&contents) {
-            Ok(p) => p,
-            Err(e) => {
-                error!(target: Target::file_mail(), channel = id; "Playlist file not readable! {e}");
-                JsonPlaylist::new(date.clone(), start_sec)
+        let mut playlist = if is_foreign_playlist(&playlist_path) {
+            match read_foreign_playlist(&playlist_path, &date).await {
+                Ok(p) => p,
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "Playlist file not readable! {e}");
+                    JsonPlaylist::new(date.clone(), start_sec)
+                }
+            }
+        } else {
+            let mut f = File::options()
+                .read(true)
+                .write(false)
+                .open(&current_file)
+                .await
+                .expect("Open json playlist file.");
+            let mut contents = String::new();
+            f.read_to_string(&mut contents)
+                .await
+                .expect("Read playlist content.");
+
+            match serde_json::from_str(&contents) {
+                Ok(p) => p,
+                Err(e) => {
+                    error!(target: Target::file_mail(), channel = id; "Playlist file not readable! {e}");
+                    JsonPlaylist::new(date.clone(), start_sec)
+                }
             }
         };
 
@@ -214,7 +257,11 @@ pub async fn read_json(
             ));
         }
 
-        set_defaults(&mut playlist);
+        set_defaults(
+            &mut playlist,
+            config.processing.crossfade,
+            config.processing.fps,
+        );
 
         return playlist;
     }
@@ -1,4 +1,5 @@
 use std::{
+    path::Path,
     process::Stdio,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -7,6 +8,7 @@ use std::{
     time::Instant,
 };
 
+use chrono::NaiveDate;
 use log::*;
 use regex::Regex;
 use tokio::{
@@ -15,13 +17,16 @@ use tokio::{
     sync::Mutex,
 };
 
+use crate::file::{select_storage_type, StorageType};
 use crate::player::filter::FilterType::Audio;
 use crate::player::utils::{
-    is_close, is_remote, loop_image, sec_to_time, seek_and_length, JsonPlaylist, Media,
+    is_close, is_live, is_remote, json_serializer::set_defaults, loop_image, rights, sec_to_time,
+    seek_and_length, JsonPlaylist, Media,
 };
 use crate::utils::{
     config::{OutputMode::Null, PlayoutConfig, FFMPEG_IGNORE_ERRORS, IMAGE_FORMAT},
     errors::ProcessError,
+    ffmpeg_bundle,
     logging::Target,
 };
 use crate::vec_strings;
@@ -93,7 +98,7 @@ async fn check_media(
     dec_cmd.append(&mut filter.map());
     dec_cmd.append(&mut vec_strings!["-t", process_length, "-f", "null", "-"]);
 
-    let mut enc_proc = Command::new("ffmpeg")
+    let mut enc_proc = Command::new(ffmpeg_bundle::ffmpeg())
         .args(dec_cmd)
         .kill_on_drop(true)
         .stderr(Stdio::piped())
@@ -166,6 +171,7 @@ pub async fn validate_playlist(
 ) {
     let id = config.general.channel_id;
     let date = playlist.date;
+    let day = NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok();
 
     if config.text.add_text && !config.text.text_from_filename {
         // Turn of drawtext filter with zmq, because its port is needed by the decoder instance.
@@ -188,7 +194,7 @@ pub async fn validate_playlist(
         let pos = index + 1;
 
         if !is_remote(&item.source) {
-            if item.audio.is_empty() {
+            if item.audio.source.is_empty() {
                 if let Err(e) = item.add_probe(false).await {
                     error!(target: Target::file_mail(), channel = id;
                         "[Validation] Error on position <yellow>{pos:0>3}</> - <yellow>{}</>: {e}",
@@ -241,6 +247,17 @@ pub async fn validate_playlist(
             }
         }
 
+        if let Some(day) = day {
+            let air_time = rights::air_time(&config, day, begin);
+
+            if let Err(e) = rights::check(&item.source, &air_time, item.out - item.seek).await {
+                error!(target: Target::file_mail(), channel = id;
+                    "[Validation] Clip on position <yellow>{pos:0>3}</> - <yellow>{}</> is outside its rights window, {e}: <b><magenta>{}</></b>",
+                    sec_to_time(begin), item.source
+                );
+            }
+        }
+
         begin += item.out - item.seek;
     }
 
@@ -264,3 +281,122 @@ pub async fn validate_playlist(
         sec_to_time(begin - config.playlist.start_sec.unwrap())
     );
 }
+
+/// Check a playlist without starting ffmpeg, for the `--validate-only` dry run.
+///
+/// Every source gets probed, the expected start time per clip is printed and
+/// missing files, probe errors, gaps and overlaps are reported.
+/// Returns an error when problems are found.
+pub async fn dry_run(
+    config: &PlayoutConfig,
+    mut playlist: JsonPlaylist,
+) -> Result<(), ProcessError> {
+    let id = config.general.channel_id;
+    let start = config.playlist.start_sec.unwrap();
+    let target_length = config.playlist.length_sec.unwrap();
+    let check_exists = matches!(
+        select_storage_type(&config.channel.storage),
+        StorageType::Local
+    );
+    let mut problems = 0;
+    let mut begin = start;
+    let day = NaiveDate::parse_from_str(&playlist.date, "%Y-%m-%d").ok();
+
+    playlist.start_sec = Some(start);
+    set_defaults(
+        &mut playlist,
+        config.processing.crossfade,
+        config.processing.fps,
+    );
+
+    info!(target: Target::file_mail(), channel = id;
+        "[Dry-run] Playlist from <yellow>{}</>, <yellow>{}</> clips", playlist.date, playlist.program.len()
+    );
+
+    for (index, item) in playlist.program.iter_mut().enumerate() {
+        let pos = index + 1;
+        let playlist_length = item.out - item.seek;
+
+        if check_exists && !is_remote(&item.source) && !Path::new(&item.source).is_file() {
+            error!(target: Target::file_mail(), channel = id;
+                "[Dry-run] <yellow>{pos:0>3}</> - <yellow>{}</>: file not found: <b><magenta>{}</></b>",
+                sec_to_time(begin), item.source
+            );
+            problems += 1;
+        } else if is_live(&item.source) {
+            // live inputs are only reachable while on air
+        } else if let Err(e) = item.add_probe(!item.audio.source.is_empty()).await {
+            error!(target: Target::file_mail(), channel = id;
+                "[Dry-run] <yellow>{pos:0>3}</> - <yellow>{}</>: probe failed: {e}", sec_to_time(begin)
+            );
+            problems += 1;
+        }
+
+        if item.seek >= item.out {
+            error!(target: Target::file_mail(), channel = id;
+                "[Dry-run] <yellow>{pos:0>3}</> - <yellow>{}</>: in point <yellow>{}</> is not before out point <yellow>{}</>",
+                sec_to_time(begin), item.seek, item.out
+            );
+            problems += 1;
+        } else if let Some(duration) = item.probe.as_ref().and_then(|p| p.format.duration) {
+            if duration + 1.2 < item.out {
+                error!(target: Target::file_mail(), channel = id;
+                    "[Dry-run] <yellow>{pos:0>3}</> - <yellow>{}</>: gap of <yellow>{}</>, out point is behind file duration <yellow>{}</>",
+                    sec_to_time(begin), sec_to_time(item.out - duration), sec_to_time(duration)
+                );
+                problems += 1;
+            }
+        }
+
+        if let Some(day) = day {
+            let air_time = rights::air_time(config, day, begin);
+
+            if let Err(e) = rights::check(&item.source, &air_time, playlist_length).await {
+                error!(target: Target::file_mail(), channel = id;
+                    "[Dry-run] <yellow>{pos:0>3}</> - <yellow>{}</>: outside rights window, {e}", sec_to_time(begin)
+                );
+                problems += 1;
+            }
+        }
+
+        info!(target: Target::file_mail(), channel = id;
+            "[Dry-run] <yellow>{pos:0>3}</> - <yellow>{}</> (<yellow>{}</>): <b><magenta>{}</></b>",
+            sec_to_time(begin), sec_to_time(playlist_length), item.source
+        );
+
+        if begin >= start + target_length {
+            warn!(target: Target::file_mail(), channel = id;
+                "[Dry-run] <yellow>{pos:0>3}</> overlaps into the next playlist and will not be played"
+            );
+        }
+
+        begin += playlist_length;
+    }
+
+    let length = begin - start;
+
+    if !config.playlist.infinit && target_length > length + 1.2 {
+        error!(target: Target::file_mail(), channel = id;
+            "[Dry-run] Gap at the end, playlist is <yellow>{}</> too short", sec_to_time(target_length - length)
+        );
+        problems += 1;
+    } else if length > target_length + 1.2 {
+        warn!(target: Target::file_mail(), channel = id;
+            "[Dry-run] Playlist overlaps by <yellow>{}</>, last clip will be cut", sec_to_time(length - target_length)
+        );
+    }
+
+    info!(target: Target::file_mail(), channel = id;
+        "[Dry-run] Playlist length: <yellow>{}</>, target length: <yellow>{}</>",
+        sec_to_time(length), sec_to_time(target_length)
+    );
+
+    if problems > 0 {
+        return Err(ProcessError::Custom(format!(
+            "Playlist from {} has {problems} problem(s)",
+            playlist.date
+        )));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+/*
+Loudness cache for the EBU R128 normalization.
+
+The first loudnorm pass measures a file and the values go to the loudness table.
+Later playbacks read them and normalize in one linear pass, without analyzing
+the file again. Until a file is measured, it gets the single pass dynamic mode.
+*/
+
+use std::{collections::HashSet, sync::LazyLock, time::UNIX_EPOCH};
+
+use log::*;
+use serde_json::Value;
+use tokio::{fs, process::Command, sync::Mutex};
+
+use crate::db::{handles, models::Loudness};
+use crate::player::{controller::ChannelManager, utils::Media};
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, ffmpeg_bundle, logging::Target};
+
+/// Files which are currently analyzed.
+static IN_PROGRESS: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Read the measured values from the JSON block, which loudnorm prints at the end.
+///
+/// Silent files have no finite loudness and give no result.
+pub fn parse(output: &str) -> Option<Loudness> {
+    let start = output.rfind('{')?;
+    let end = output.rfind('}')?;
+    let data: Value = serde_json::from_str(output.get(start..=end)?).ok()?;
+    let value = |key: &str| {
+        data.get(key)?
+            .as_str()?
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+    };
+
+    Some(Loudness {
+        input_i: value("input_i")?,
+        input_lra: value("input_lra")?,
+        input_tp: value("input_tp")?,
+        input_thresh: value("input_thresh")?,
+        ..Default::default()
+    })
+}
+
+/// File size and modification time, remote sources have none.
+pub async fn file_version(source: &str) -> (i64, i64) {
+    match fs::metadata(source).await {
+        Ok(meta) => (
+            meta.len() as i64,
+            meta.modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or_default(),
+        ),
+        Err(_) => (0, 0),
+    }
+}
+
+/// Measure the first audio stream of the source, with the targets from config.
+pub async fn analyze(config: &PlayoutConfig, source: &str) -> Result<Loudness, ServiceError> {
+    let filter = format!(
+        "loudnorm=I={}:LRA={}:TP={}:print_format=json",
+        config.processing.loudnorm_i, config.processing.loudnorm_lra, config.processing.loudnorm_tp
+    );
+    let output = Command::new(ffmpeg_bundle::ffmpeg())
+        .args([
+            "-hide_banner",
+            "-nostats",
+            "-nostdin",
+            "-i",
+            source,
+            "-map",
+            "0:a:0",
+            "-af",
+            &filter,
+            "-f",
+            "null",
+            "-",
+        ])
+        .output()
+        .await?;
+
+    parse(&String::from_utf8_lossy(&output.stderr))
+        .ok_or_else(|| ServiceError::Conflict("No loudness measured".to_string()))
+}
+
+/// Add the cached loudness to the clip, unknown files get measured in background.
+pub async fn add_loudness(manager: &ChannelManager, config: &PlayoutConfig, node: &mut Media) {
+    if !config.processing.loudnorm
+        || config.processing.copy_audio
+        || !node.audio.is_empty()
+        || !node.audio_tracks.is_empty()
+        || node.probe.as_ref().is_none_or(|p| p.audio.is_empty())
+    {
+        return;
+    }
+
+    let name = if node.key.is_empty() {
+        node.source.clone()
+    } else {
+        node.key.clone()
+    };
+    let (size, modified) = file_version(&node.source).await;
+
+    if let Ok(loudness) = handles::select_loudness(&manager.db_pool, &name, size, modified).await {
+        node.loudness = Some(loudness);
+        return;
+    }
+
+    if !IN_PROGRESS.lock().await.insert(name.clone()) {
+        return;
+    }
+
+    let id = config.general.channel_id;
+    let pool = manager.db_pool.clone();
+    let config = config.clone();
+    let source = node.source.clone();
+
+    tokio::spawn(async move {
+        match analyze(&config, &source).await {
+            Ok(mut loudness) => {
+                loudness.source.clone_from(&name);
+                loudness.size = size;
+                loudness.modified = modified;
+
+                debug!(target: Target::file_mail(), channel = id;
+                    "Measured <yellow>{} LUFS</> for <b><magenta>{source}</></b>", loudness.input_i
+                );
+
+                if let Err(e) = handles::insert_loudness(&pool, &loudness).await {
+                    error!(target: Target::file_mail(), channel = id; "Save loudness failed: {e}");
+                }
+            }
+            Err(e) => {
+                warn!(target: Target::file_mail(), channel = id;
+                    "Loudness analysis of <b><magenta>{source}</></b> failed: {e}"
+                );
+            }
+        }
+
+        IN_PROGRESS.lock().await.remove(&name);
+    });
+}
@@ -4,7 +4,7 @@ use std::{
     io::Error,
     net::TcpListener,
     path::{Path, PathBuf},
-    process::{exit, Stdio},
+    process::exit,
     str::FromStr,
     sync::{atomic::Ordering, Arc},
 };
@@ -20,16 +20,29 @@ use serde::{de::Deserializer, Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 use tokio::{
     fs::{metadata, File},
-    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
-    process::{ChildStderr, Command},
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt},
+    process::ChildStderr,
     sync::Mutex,
 };
 
+pub mod blacklist;
+pub mod decoder_params;
+pub mod detect;
+pub mod gap;
 pub mod import;
 pub mod json_serializer;
 pub mod json_validate;
+pub mod loudness;
 pub mod probe;
-
+pub mod progress;
+pub mod rights;
+pub mod script;
+pub mod scte35;
+pub mod subtitle;
+pub mod visual_radio;
+
+use crate::db::models::Loudness;
+use crate::file::{clean_raw_abs_path, utils::ABS_PATH_INDICATOR};
 use crate::player::{
     controller::{
         ChannelManager,
@@ -38,12 +51,19 @@ use crate::player::{
     filter::{filter_chains, Filters},
 };
 use crate::utils::{
-    config::{OutputMode::*, PlayoutConfig, FFMPEG_IGNORE_ERRORS, FFMPEG_UNRECOVERABLE_ERRORS},
+    config::{
+        AspectMode, FpsMode, PlayoutConfig, FFMPEG_IGNORE_ERRORS, FFMPEG_UNRECOVERABLE_ERRORS,
+        IMAGE_FORMAT,
+    },
     errors::ServiceError,
     logging::Target,
     time_machine::time_now,
 };
+use decoder_params::DecoderParams;
+use detect::Detector;
 pub use json_serializer::{read_json, JsonPlaylist};
+use progress::Progress;
+use scte35::Scte35;
 
 use crate::vec_strings;
 
@@ -130,11 +150,51 @@ pub fn prepare_output_cmd(
         cmd.append(&mut vec_strings!("-map", format!("{i}:s?")));
     }
 
+    if !config.processing.audio_languages.is_empty()
+        && !output_params.iter().any(|p| p.starts_with("-metadata:s:a"))
+    {
+        // language metadata is an output option, so it must be set for every output
+        let mut params = audio_language_params(config);
+
+        for (i, param) in output_params.iter().enumerate() {
+            params.push(param.clone());
+
+            if i > 0
+                && !param.starts_with('-')
+                && !output_params[i - 1].starts_with('-')
+                && i < output_params.len() - 1
+            {
+                params.append(&mut audio_language_params(config));
+            }
+        }
+
+        output_params = params;
+    }
+
     cmd.append(&mut output_params);
 
     cmd
 }
 
+fn audio_language_params(config: &PlayoutConfig) -> Vec<String> {
+    let mut params = vec![];
+
+    for (i, language) in config
+        .processing
+        .audio_languages
+        .iter()
+        .take(config.processing.audio_tracks.max(1) as usize)
+        .enumerate()
+    {
+        params.append(&mut vec_strings![
+            format!("-metadata:s:a:{i}"),
+            format!("language={language}")
+        ]);
+    }
+
+    params
+}
+
 /// map media struct to json object
 pub fn get_media_map(media: Media) -> Value {
     let mut obj = json!({
@@ -151,6 +211,12 @@ pub fn get_media_map(media: Media) -> Value {
             .insert("title".to_string(), Value::String(title));
     }
 
+    if let Some(artist) = media.artist {
+        obj.as_object_mut()
+            .unwrap()
+            .insert("artist".to_string(), Value::String(artist));
+    }
+
     obj
 }
 
@@ -176,6 +242,10 @@ pub async fn get_data_map(manager: &ChannelManager) -> Map<String, Value> {
 
     data_map.insert("index".to_string(), json!(media.index));
     data_map.insert("ingest".to_string(), json!(ingest_is_alive));
+    data_map.insert(
+        "manual_wait".to_string(),
+        json!(manager.manual_wait.load(Ordering::SeqCst)),
+    );
     data_map.insert("mode".to_string(), json!(config.mode));
     data_map.insert(
         "shift".to_string(),
@@ -186,6 +256,10 @@ pub async fn get_data_map(manager: &ChannelManager) -> Map<String, Value> {
         json!((played_time * 1000.0).round() / 1000.0),
     );
     data_map.insert("media".to_string(), get_media_map(media));
+    data_map.insert(
+        "encoder".to_string(),
+        json!(*manager.encoder_progress.lock().await),
+    );
 
     data_map
 }
@@ -203,6 +277,10 @@ pub struct Media {
     pub index: Option<usize>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     #[serde(rename = "in")]
     pub seek: f64,
     pub out: f64,
@@ -220,12 +298,8 @@ pub struct Media {
     #[serde(deserialize_with = "null_string")]
     pub source: String,
 
-    #[serde(
-        default,
-        deserialize_with = "null_string",
-        skip_serializing_if = "is_empty_string"
-    )]
-    pub audio: String,
+    #[serde(default, skip_serializing_if = "ClipAudio::is_empty")]
+    pub audio: ClipAudio,
 
     #[serde(skip_serializing, skip_deserializing)]
     pub cmd: Option<Vec<String>>,
@@ -236,6 +310,30 @@ pub struct Media {
     #[serde(default, skip_serializing_if = "is_empty_string")]
     pub custom_filter: String,
 
+    /// Filter plugins for this clip, like `old_film_look:grain=20`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filters: Vec<String>,
+
+    /// Decoder parameters for this clip only, like `-err_detect ignore_err`.
+    #[serde(default, skip_serializing_if = "is_empty_string")]
+    pub custom_decoder_params: String,
+
+    /// Aspect mode for this clip, instead of the one from the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aspect_mode: Option<AspectMode>,
+
+    /// Frame rate conversion for this clip, instead of the one from the config.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fps_mode: Option<FpsMode>,
+
+    /// Logo for this clip, instead of the scheduled one. An empty string hides the logo.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logo: Option<String>,
+
+    /// Cover image for audio clips in visual radio.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artwork: Option<String>,
+
     #[serde(skip_serializing, skip_deserializing)]
     pub probe: Option<MediaProbe>,
 
@@ -248,11 +346,205 @@ pub struct Media {
     #[serde(skip_serializing, skip_deserializing)]
     pub next_ad: bool,
 
+    /// The logo differs from the previous/next clip, so it fades in/out.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub logo_in: bool,
+
+    #[serde(skip_serializing, skip_deserializing)]
+    pub logo_out: bool,
+
     #[serde(default, skip_serializing, skip_deserializing)]
     pub skip: bool,
 
     #[serde(default, skip_serializing)]
     pub unit: ProcessUnit,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scte35: Option<Scte35>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audio_tracks: Vec<AudioTrack>,
+
+    #[serde(skip_serializing, skip_deserializing)]
+    pub transition: Option<Transition>,
+
+    /// Seconds from the clip start, which the crossfade from the previous clip has shown already.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub crossfade_in: f64,
+
+    /// Stinger parts, the tail after the cut at the clip start and the head before the cut at its end.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub stinger_in: bool,
+
+    #[serde(skip_serializing, skip_deserializing)]
+    pub stinger_out: bool,
+
+    /// Measured loudness from the cache, for the linear loudnorm pass.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub loudness: Option<Loudness>,
+
+    /// Keyframe before the seek point, the decoder seeks to it and trims the rest frame accurately.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub keyframe: Option<f64>,
+
+    /// Manual clips wait for the take from the operator.
+    #[serde(default, skip_serializing_if = "Trigger::is_auto")]
+    pub trigger: Trigger,
+
+    /// Start of message as timecode `HH:MM:SS:FF`, it sets the in point in frames of the clip.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub som: Option<String>,
+
+    /// End of message as timecode `HH:MM:SS:FF`, it sets the out point in frames of the clip.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub eom: Option<String>,
+}
+
+/// Start of a clip, automatic on its time or manual by the operator.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    #[default]
+    Auto,
+    Manual,
+}
+
+impl Trigger {
+    pub fn is_auto(&self) -> bool {
+        *self == Self::Auto
+    }
+}
+
+/// Crossfade into the next clip, at the end of the current one.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Transition {
+    pub source: String,
+    pub seek: f64,
+    pub duration: f64,
+    /// Streams from the next clip, they get known when the transition is prepared.
+    pub video: bool,
+    pub audio_streams: usize,
+}
+
+impl Transition {
+    pub fn new(next: &Media, duration: f64) -> Self {
+        Self {
+            source: next.source.clone(),
+            seek: next.seek,
+            duration,
+            ..Default::default()
+        }
+    }
+}
+
+/// Audio source for one output track of a clip.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AudioTrack {
+    /// External audio file, when empty the track comes from the clip source.
+    #[serde(default, skip_serializing_if = "is_empty_string")]
+    pub source: String,
+    /// Audio stream index in the source, default is the track number for embedded tracks
+    /// and the first stream for external files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stream: Option<i32>,
+}
+
+/// Audio options of a clip.
+///
+/// In the playlist it is a plain string, when only the external audio file is set,
+/// otherwise an object like: `{"gain_db": -3, "channel_map": [3, 4]}`
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(from = "ClipAudioValue", into = "ClipAudioValue")]
+pub struct ClipAudio {
+    /// External audio file, instead of the audio from the clip source.
+    pub source: String,
+    /// Gain in dB, on top of the global volume.
+    pub gain_db: f64,
+    /// Source channels for the output, counted from 1. `[3, 4]` takes channels 3 and 4 as stereo.
+    pub channel_map: Vec<u32>,
+    /// Audio stream of the source for the first output track.
+    pub stream_index: Option<i32>,
+}
+
+impl ClipAudio {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Pan filter for the channel map, the layout follows the number of channels.
+    pub fn pan(&self) -> Option<String> {
+        if self.channel_map.is_empty() {
+            return None;
+        }
+
+        let layout = match self.channel_map.len() {
+            1 => "mono".to_string(),
+            2 => "stereo".to_string(),
+            n => format!("{n}c"),
+        };
+        let channels = self
+            .channel_map
+            .iter()
+            .enumerate()
+            .map(|(i, c)| format!("c{i}=c{}", c.saturating_sub(1)))
+            .collect::<Vec<_>>()
+            .join("|");
+
+        Some(format!("pan={layout}|{channels}"))
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum ClipAudioValue {
+    Source(Option<String>),
+    Options {
+        #[serde(default, skip_serializing_if = "is_empty_string")]
+        source: String,
+        #[serde(default, skip_serializing_if = "is_zero")]
+        gain_db: f64,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        channel_map: Vec<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        stream_index: Option<i32>,
+    },
+}
+
+impl From<ClipAudioValue> for ClipAudio {
+    fn from(value: ClipAudioValue) -> Self {
+        match value {
+            ClipAudioValue::Source(source) => Self {
+                source: source.unwrap_or_default(),
+                ..Default::default()
+            },
+            ClipAudioValue::Options {
+                source,
+                gain_db,
+                channel_map,
+                stream_index,
+            } => Self {
+                source,
+                gain_db,
+                channel_map,
+                stream_index,
+            },
+        }
+    }
+}
+
+impl From<ClipAudio> for ClipAudioValue {
+    fn from(audio: ClipAudio) -> Self {
+        if audio.gain_db == 0.0 && audio.channel_map.is_empty() && audio.stream_index.is_none() {
+            return Self::Source(Some(audio.source));
+        }
+
+        Self::Options {
+            source: audio.source,
+            gain_db: audio.gain_db,
+            channel_map: audio.channel_map,
+            stream_index: audio.stream_index,
+        }
+    }
 }
 
 impl Media {
@@ -273,22 +565,43 @@ impl Media {
             begin: None,
             index: Some(index),
             title: None,
+            artist: None,
+            description: None,
             seek: 0.0,
             out: duration,
             duration,
             duration_audio: 0.0,
             category: String::new(),
             source: src.to_string(),
-            audio: String::new(),
+            audio: ClipAudio::default(),
             cmd: Some(vec_strings!["-i", src]),
             filter: None,
             custom_filter: String::new(),
+            filters: vec![],
+            custom_decoder_params: String::new(),
+            aspect_mode: None,
+            fps_mode: None,
+            logo: None,
+            artwork: None,
             probe,
             probe_audio: None,
             last_ad: false,
             next_ad: false,
+            logo_in: false,
+            logo_out: false,
             skip: false,
             unit: Decoder,
+            scte35: None,
+            audio_tracks: vec![],
+            transition: None,
+            crossfade_in: 0.0,
+            stinger_in: false,
+            stinger_out: false,
+            loudness: None,
+            keyframe: None,
+            trigger: Trigger::Auto,
+            som: None,
+            eom: None,
         }
     }
 
@@ -315,8 +628,8 @@ impl Media {
                 Err(e) => errors.push(e.to_string()),
             };
 
-            if check_audio && Path::new(&self.audio).is_file() {
-                match MediaProbe::new(&self.audio).await {
+            if check_audio && Path::new(&self.audio.source).is_file() {
+                match MediaProbe::new(&self.audio.source).await {
                     Ok(probe) => {
                         self.probe_audio = Some(probe.clone());
 
@@ -336,11 +649,68 @@ impl Media {
         Ok(())
     }
 
+    /// Frame rate of the video stream, when the clip is probed.
+    pub fn fps(&self) -> Option<f64> {
+        self.probe
+            .as_ref()
+            .and_then(|p| p.video.first())
+            .map(|v| fps_calc(&v.frame_rate, 0.0))
+            .filter(|f| *f > 0.0)
+    }
+
+    /// Set in and out point from the SOM/EOM timecodes.
+    ///
+    /// The frames count with the rate of the clip, without probe with the given one.
+    pub fn apply_timecode(&mut self, fps: f64) {
+        let fps = self.fps().unwrap_or(fps);
+
+        if let Some(seek) = self.som.as_deref().and_then(|t| timecode_to_sec(t, fps)) {
+            self.seek = seek;
+        }
+
+        if let Some(out) = self.eom.as_deref().and_then(|t| timecode_to_sec(t, fps)) {
+            self.out = out;
+        }
+    }
+
+    /// Input and stream index for every declared audio track.
+    ///
+    /// External files are added as inputs after the clip source and the optional `audio` file.
+    /// Without declared tracks, the `audio.stream_index` selects the stream for the first one.
+    pub fn audio_track_inputs(&self) -> Vec<(i32, i32)> {
+        let mut next_input = if self.audio.source.is_empty() { 1 } else { 2 };
+
+        if self.audio_tracks.is_empty() {
+            if let Some(stream) = self.audio.stream_index {
+                return vec![(next_input - 1, stream)];
+            }
+        }
+
+        self.audio_tracks
+            .iter()
+            .enumerate()
+            .map(|(i, track)| {
+                if track.source.is_empty() {
+                    (0, track.stream.unwrap_or(i as i32))
+                } else {
+                    next_input += 1;
+
+                    (next_input - 1, track.stream.unwrap_or_default())
+                }
+            })
+            .collect()
+    }
+
     pub async fn add_filter(
         &mut self,
         config: &PlayoutConfig,
         filter_chain: &Option<Arc<Mutex<Vec<String>>>>,
     ) {
+        if visual_radio::is_active(config, self) {
+            let mut input = visual_radio::input_cmd(config, self);
+            self.cmd.get_or_insert_with(Vec::new).append(&mut input);
+        }
+
         let mut node = self.clone();
         self.filter = Some(filter_chains(config, &mut node, filter_chain).await);
     }
@@ -353,22 +723,43 @@ impl Default for Media {
             begin: None,
             index: Some(0),
             title: None,
+            artist: None,
+            description: None,
             seek: 0.0,
             out: 0.0,
             duration: 0.0,
             duration_audio: 0.0,
             category: String::new(),
             source: String::new(),
-            audio: String::new(),
+            audio: ClipAudio::default(),
             cmd: Some(vec_strings!["-i", String::new()]),
             filter: None,
             custom_filter: String::new(),
+            filters: vec![],
+            custom_decoder_params: String::new(),
+            aspect_mode: None,
+            fps_mode: None,
+            logo: None,
+            artwork: None,
             probe: None,
             probe_audio: None,
             last_ad: false,
             next_ad: false,
+            logo_in: false,
+            logo_out: false,
             skip: false,
             unit: Decoder,
+            scte35: None,
+            audio_tracks: vec![],
+            transition: None,
+            crossfade_in: 0.0,
+            stinger_in: false,
+            stinger_out: false,
+            loudness: None,
+            keyframe: None,
+            trigger: Trigger::Auto,
+            som: None,
+            eom: None,
         }
     }
 }
@@ -376,6 +767,7 @@ impl Default for Media {
 impl PartialEq for Media {
     fn eq(&self, other: &Self) -> bool {
         self.title == other.title
+            && self.artist == other.artist
             && self.seek == other.seek
             && self.out == other.out
             && self.duration == other.duration
@@ -383,6 +775,14 @@ impl PartialEq for Media {
             && self.category == other.category
             && self.audio == other.audio
             && self.custom_filter == other.custom_filter
+            && self.filters == other.filters
+            && self.custom_decoder_params == other.custom_decoder_params
+            && self.aspect_mode == other.aspect_mode
+            && self.fps_mode == other.fps_mode
+            && self.artwork == other.artwork
+            && self.trigger == other.trigger
+            && self.som == other.som
+            && self.eom == other.eom
     }
 }
 
@@ -400,6 +800,11 @@ fn is_empty_string(st: &String) -> bool {
     *st == String::new()
 }
 
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
+}
+
 /// Calculate fps from rate/factor string
 pub fn fps_calc(r_frame_rate: &str, default: f64) -> f64 {
     if let Some((r, f)) = r_frame_rate.split_once('/') {
@@ -514,6 +919,31 @@ pub fn time_to_sec(time_str: &str, timezone: &Option<Tz>) -> f64 {
     t.next().unwrap_or(0.0) * 3600.0 + t.next().unwrap_or(0.0) * 60.0 + t.next().unwrap_or(0.0)
 }
 
+/// Convert a timecode `HH:MM:SS:FF` to seconds.
+///
+/// Frames count with the nominal rate, 30 for 29.97 fps, so the timecode runs like a
+/// non drop frame timecode from the tape. None when the timecode is not valid for the rate.
+pub fn timecode_to_sec(timecode: &str, fps: f64) -> Option<f64> {
+    let nominal = fps.round() as u64;
+    let parts = timecode
+        .trim()
+        .split(':')
+        .map(|n| n.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+
+    let [h, m, s, f] = parts[..] else {
+        return None;
+    };
+
+    if nominal == 0 || m > 59 || s > 59 || f >= nominal {
+        return None;
+    }
+
+    let frames = ((h * 60 + m) * 60 + s) * nominal + f;
+
+    Some(frames as f64 / fps)
+}
+
 /// Convert floating number (seconds) to a formatted time string.
 pub fn sec_to_time(sec: f64) -> String {
     let s = (sec * 1000.0).round() / 1000.0;
@@ -581,6 +1011,51 @@ pub fn get_delta(config: &PlayoutConfig, begin: &f64) -> (f64, f64) {
     (current_delta, total_delta)
 }
 
+/// Correct the drift between playhead and wall clock, by moving the out point of the clip.
+///
+/// A negative delta means the playout is late, the clip gets shorter; when it is early the clip
+/// gets longer, as far as its duration allows. The correction is limited to `max` seconds
+/// per clip, so a larger drift gets corrected over the following clips.
+/// Returns the applied correction.
+pub fn drift_correction(node: &mut Media, delta: f64, max: f64) -> f64 {
+    if max <= 0.0 || delta.abs() < 0.04 || node.duration <= 0.0 {
+        return 0.0;
+    }
+
+    let correction = if delta < 0.0 {
+        -(-delta).min(max).min(node.out - node.seek - 1.0).max(0.0)
+    } else {
+        delta.min(max).min(node.duration - node.out).max(0.0)
+    };
+
+    node.out += correction;
+
+    correction
+}
+
+/// Index in a reloaded program, where the playout continues after the current clip.
+///
+/// `index` points to the next clip in the old program, so the clip before is playing.
+/// When this clip is also in the new program, with the same begin, the changes only affect
+/// the following clips and can be spliced in. Otherwise None, then the program has to be
+/// initialized on the current time.
+pub fn splice_index(old: &[Media], index: usize, new: &[Media]) -> Option<usize> {
+    if index == 0 || index > old.len() {
+        return None;
+    }
+
+    let current = &old[index - 1];
+    let begin = current.begin?;
+
+    new.iter()
+        .position(|node| {
+            node.source == current.source
+                && is_close(node.seek, current.seek, 0.04)
+                && node.begin.is_some_and(|b| is_close(b, begin, 0.04))
+        })
+        .map(|i| i + 1)
+}
+
 /// Loop image until target duration is reached.
 pub fn loop_image(config: &PlayoutConfig, node: &Media) -> Vec<String> {
     let duration = node.out - node.seek;
@@ -591,12 +1066,12 @@ pub fn loop_image(config: &PlayoutConfig, node: &Media) -> Vec<String> {
         node.source
     );
 
-    if Path::new(&node.audio).is_file() {
+    if Path::new(&node.audio.source).is_file() {
         if node.seek > 0.0 {
             source_cmd.append(&mut vec_strings!["-ss", node.seek]);
         }
 
-        source_cmd.append(&mut vec_strings!["-i", node.audio.clone()]);
+        source_cmd.append(&mut vec_strings!["-i", node.audio.source.clone()]);
     }
 
     source_cmd.append(&mut vec_strings!["-t", duration]);
@@ -681,8 +1156,10 @@ pub fn seek_and_length(config: &PlayoutConfig, node: &mut Media) -> Vec<String>
     if remote_source && node.probe.clone().and_then(|f| f.format.duration).is_none() {
         node.out -= node.seek;
         node.seek = 0.0;
+        node.keyframe = None;
     } else if node.seek > 0.5 {
-        source_cmd.append(&mut vec_strings!["-ss", node.seek]);
+        // the input seek goes to the keyframe, the trim filter cuts the frames until the seek point
+        source_cmd.append(&mut vec_strings!["-ss", node.keyframe.unwrap_or(node.seek)]);
     }
 
     if loop_count > 1 {
@@ -691,13 +1168,22 @@ pub fn seek_and_length(config: &PlayoutConfig, node: &mut Media) -> Vec<String>
         source_cmd.append(&mut vec_strings!["-stream_loop", loop_count]);
     }
 
+    if is_live(&node.source) {
+        // end the clip when the input stops sending
+        source_cmd.append(&mut vec_strings!["-rw_timeout", LIVE_TIMEOUT]);
+    }
+
+    source_cmd.append(&mut DecoderParams::parse(&node.custom_decoder_params).input);
     source_cmd.append(&mut vec_strings!["-i", node.source.clone()]);
 
     if node.duration > node.out || remote_source || loop_count > 1 {
-        source_cmd.append(&mut vec_strings!["-t", node.out - node.seek]);
+        source_cmd.append(&mut vec_strings![
+            "-t",
+            node.out - node.keyframe.unwrap_or(node.seek)
+        ]);
     }
 
-    if !node.audio.is_empty() {
+    if !node.audio.source.is_empty() {
         if node.seek > 0.5 {
             source_cmd.append(&mut vec_strings!["-ss", node.seek]);
         }
@@ -709,13 +1195,28 @@ pub fn seek_and_length(config: &PlayoutConfig, node: &mut Media) -> Vec<String>
             loop_audio = true;
         }
 
-        source_cmd.append(&mut vec_strings!["-i", node.audio.clone()]);
+        source_cmd.append(&mut vec_strings!["-i", node.audio.source.clone()]);
 
         if cut_audio || loop_audio || remote_source {
             source_cmd.append(&mut vec_strings!["-t", node.out - node.seek]);
         }
     }
 
+    for track in node.audio_tracks.iter().filter(|t| !t.source.is_empty()) {
+        if node.seek > 0.5 {
+            source_cmd.append(&mut vec_strings!["-ss", node.seek]);
+        }
+
+        source_cmd.append(&mut vec_strings![
+            "-i",
+            track.source.clone(),
+            "-t",
+            node.out - node.seek
+        ]);
+    }
+
+    source_cmd.append(&mut subtitle::input_cmd(config, node));
+
     if config.processing.vtt_enable {
         let vtt_file = Path::new(&node.source).with_extension("vtt");
         let vtt_dummy = config
@@ -807,7 +1308,7 @@ pub fn gen_dummy(config: &PlayoutConfig, duration: f64) -> (String, Vec<String>)
 
 pub fn is_remote(path: &str) -> bool {
     // to-do : maybe needed to be more responsive to the storage type for example get the remote pattern of a specific storage type like "s3"
-    Regex::new(r"^(https?|rtmps?|rts?p|udp|tcp|srt)://.*")
+    Regex::new(r"^(https?|rtmps?|rts?p|udp|tcp|srt|s?ftp)://.*")
         .unwrap()
         .is_match(&path.to_lowercase())
         || Regex::new(r"^(s3):/.*") // s3 implementation
@@ -815,6 +1316,74 @@ pub fn is_remote(path: &str) -> bool {
             .is_match(&path.to_lowercase())
 }
 
+/// Read timeout for live inputs, in microseconds.
+pub const LIVE_TIMEOUT: i64 = 5_000_000;
+
+/// Live network input, which is played as it comes, without a known duration.
+/// Crossfade is possible between two file clips, which are long enough and not looped.
+pub fn can_crossfade(duration: f64, current: &Media, next: &Media) -> bool {
+    let is_image = |m: &Media| {
+        Path::new(&m.source)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .is_some_and(|e| IMAGE_FORMAT.contains(&e.as_str()))
+    };
+
+    duration > 0.0
+        && [current, next].iter().all(|m| {
+            !is_remote(&m.source)
+                && !is_image(m)
+                && m.out <= m.duration
+                && m.out - m.seek > duration * 2.0
+        })
+}
+
+/// Logo for the clip: the override from the playlist, the scheduled one
+/// at the clip begin, or the default logo. Advertisements have no logo.
+pub fn logo_path(config: &PlayoutConfig, node: &Media) -> Option<String> {
+    if !config.processing.add_logo || node.category == "advertisement" {
+        return None;
+    }
+
+    let path = match &node.logo {
+        Some(logo) if logo.is_empty() => return None,
+        Some(logo) => clean_raw_abs_path(&config.channel.storage, logo, ABS_PATH_INDICATOR)
+            .ok()?
+            .1
+            .to_string_lossy()
+            .to_string(),
+        None => {
+            let time = node.begin.unwrap_or_default() % 86400.0;
+
+            config
+                .processing
+                .logo_slots
+                .iter()
+                .find(|slot| slot.contains(time))
+                .map_or_else(|| config.processing.logo_path.clone(), |s| s.path.clone())
+        }
+    };
+
+    is_logo(&path).then_some(path)
+}
+
+/// Check if the logo exists, image sequences like `logo_%03d.png` need their first frame.
+pub fn is_logo(path: &str) -> bool {
+    let Some(pattern) = Regex::new(r"%(\d*)d").unwrap().captures(path) else {
+        return Path::new(path).is_file();
+    };
+    let width = pattern[1].parse::<usize>().unwrap_or(0);
+
+    // image2 looks for the first frame from 0 to 4
+    (0..5).any(|n| Path::new(&path.replacen(&pattern[0], &format!("{n:0width$}"), 1)).is_file())
+}
+
+pub fn is_live(path: &str) -> bool {
+    Regex::new(r"^(srt|udp|rtp)://.*")
+        .unwrap()
+        .is_match(&path.to_lowercase())
+}
+
 /// Check if file can include or has to exclude.
 /// For example when a file is on given HLS output path, it should exclude.
 /// Or when the file extension is set under storage config it can be include.
@@ -827,7 +1396,7 @@ pub fn include_file_extension(config: &PlayoutConfig, file_path: &Path) -> bool
         }
     }
 
-    if config.output.mode == HLS {
+    if config.output.mode.is_segmented() {
         if let Some(ts_path) = config
             .output
             .output_cmd
@@ -849,7 +1418,7 @@ pub fn include_file_extension(config: &PlayoutConfig, file_path: &Path) -> bool
             .clone()
             .unwrap_or_else(|| vec![String::new()])
             .iter()
-            .find(|s| s.contains(".m3u8") && !s.contains("master.m3u8"))
+            .find(|s| (s.contains(".m3u8") && !s.contains("master.m3u8")) || s.ends_with(".mpd"))
         {
             if let Some(p) = Path::new(m3u8_path).parent() {
                 if file_path.starts_with(p) {
@@ -869,10 +1438,22 @@ pub async fn stderr_reader(
     ignore: Vec<String>,
     suffix: ProcessUnit,
     channel_id: i32,
+    mut detector: Option<Detector>,
+    mut progress: Option<Progress>,
 ) -> Result<(), ServiceError> {
     let mut lines = buffer.lines();
 
     while let Some(line) = lines.next_line().await? {
+        if detector.as_mut().is_some_and(|d| d.process(&line)) {
+            continue;
+        }
+
+        if let Some(progress) = progress.as_mut() {
+            if progress.process(&line).await {
+                continue;
+            }
+        }
+
         if FFMPEG_IGNORE_ERRORS.iter().any(|i| line.contains(*i))
             || ignore.iter().any(|i| line.contains(i))
         {
@@ -884,12 +1465,13 @@ pub async fn stderr_reader(
                 "<bright black>[{suffix}]</> {}",
                 line.replace("[info] ", "")
             );
-        } else if line.contains("[warning]") {
+        } else if line.contains("[warning]") || line.starts_with("WARNING:") {
             warn!(target: Target::file_mail(), channel = channel_id;
                 "<bright black>[{suffix}]</> {}",
                 line.replace("[warning] ", "")
             );
-        } else if line.contains("[error]") || line.contains("[fatal]") {
+        } else if line.contains("[error]") || line.contains("[fatal]") || line.starts_with("ERROR:")
+        {
             error!(target: Target::file_mail(), channel = channel_id;
                 "<bright black>[{suffix}]</> {}",
                 line.replace("[error] ", "").replace("[fatal] ", "")
@@ -911,145 +1493,6 @@ pub async fn stderr_reader(
     Ok(())
 }
 
-/// Run program to test if it is in system.
-async fn is_in_system(name: &str) -> Result<(), String> {
-    match Command::new(name)
-        .stderr(Stdio::null())
-        .stdout(Stdio::null())
-        .spawn()
-    {
-        Ok(mut proc) => {
-            if let Err(e) = proc.wait().await {
-                return Err(format!("{e}"));
-            };
-        }
-        Err(e) => return Err(format!("{name} not found on system! {e}")),
-    }
-
-    Ok(())
-}
-
-async fn ffmpeg_filter_and_libs(config: &mut PlayoutConfig) -> Result<(), String> {
-    let id = config.general.channel_id;
-    let ignore_flags = [
-        "--enable-gpl",
-        "--enable-version3",
-        "--enable-runtime-cpudetect",
-        "--enable-avfilter",
-        "--enable-zlib",
-        "--enable-pic",
-        "--enable-nonfree",
-    ];
-
-    let mut ff_proc = match Command::new("ffmpeg")
-        .args(["-filters"])
-        .kill_on_drop(true)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-    {
-        Err(e) => {
-            return Err(format!("couldn't spawn ffmpeg process: {e}"));
-        }
-        Ok(proc) => proc,
-    };
-
-    let out_buffer = BufReader::new(ff_proc.stdout.take().unwrap());
-    let err_buffer = BufReader::new(ff_proc.stderr.take().unwrap());
-
-    // stderr shows only the ffmpeg configuration
-    // get codec library's
-    let mut lines = err_buffer.lines();
-    while let Ok(Some(line)) = lines.next_line().await {
-        if line.contains("configuration:") {
-            let configs = line.split_whitespace();
-
-            for flag in configs {
-                if flag.contains("--enable") && !ignore_flags.contains(&flag) {
-                    config
-                        .general
-                        .ffmpeg_libs
-                        .push(flag.replace("--enable-", ""));
-                }
-            }
-            break;
-        }
-    }
-
-    // stdout shows filter from ffmpeg
-    // get filters
-    let mut lines = out_buffer.lines();
-    while let Ok(Some(line)) = lines.next_line().await {
-        if line.contains('>') {
-            let filter_line = line.split_whitespace().collect::<Vec<_>>();
-
-            if filter_line.len() > 2 {
-                config
-                    .general
-                    .ffmpeg_filters
-                    .push(filter_line[1].to_string());
-            }
-        }
-    }
-
-    if let Err(e) = ff_proc.wait().await {
-        error!(target: Target::file_mail(), channel = id; "{e}");
-    };
-
-    Ok(())
-}
-
-/// Validate ffmpeg/ffprobe/ffplay.
-///
-/// Check if they are in system and has all libs and codecs we need.
-pub async fn validate_ffmpeg(config: &mut PlayoutConfig) -> Result<(), String> {
-    is_in_system("ffmpeg").await?;
-    is_in_system("ffprobe").await?;
-
-    if config.output.mode == Desktop {
-        is_in_system("ffplay").await?;
-    }
-
-    ffmpeg_filter_and_libs(config).await?;
-
-    if config
-        .output
-        .output_cmd
-        .as_ref()
-        .unwrap()
-        .contains(&"libx264".to_string())
-        && !config.general.ffmpeg_libs.contains(&"libx264".to_string())
-    {
-        return Err("ffmpeg contains no libx264!".to_string());
-    }
-
-    if config.text.add_text
-        && !config.text.text_from_filename
-        && !config.general.ffmpeg_libs.contains(&"libzmq".to_string())
-    {
-        return Err(
-            "ffmpeg contains no libzmq! Disable add_text in config or compile ffmpeg with libzmq."
-                .to_string(),
-        );
-    }
-
-    if config
-        .output
-        .output_cmd
-        .as_ref()
-        .unwrap()
-        .contains(&"libfdk_aac".to_string())
-        && !config
-            .general
-            .ffmpeg_libs
-            .contains(&"libfdk-aac".to_string())
-    {
-        return Err("ffmpeg contains no libfdk-aac!".to_string());
-    }
-
-    Ok(())
-}
-
 /// get a free tcp socket
 pub fn gen_tcp_socket(exclude_socket: String) -> Option<String> {
     for _ in 0..100 {
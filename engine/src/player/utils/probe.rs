@@ -1,9 +1,13 @@
+use std::collections::BTreeMap;
+
 use log::*;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DisplayFromStr};
 use tokio::process;
 
-use crate::utils::errors::ProcessError;
+use crate::file::utils::login;
+use crate::player::utils::{is_live, LIVE_TIMEOUT};
+use crate::utils::{errors::ProcessError, ffmpeg_bundle};
 
 pub async fn ffprobe(path: impl AsRef<std::path::Path>) -> Result<FfProbe, FfProbeError> {
     ffprobe_config(path).await
@@ -12,7 +16,7 @@ pub async fn ffprobe(path: impl AsRef<std::path::Path>) -> Result<FfProbe, FfPro
 pub async fn ffprobe_config(path: impl AsRef<std::path::Path>) -> Result<FfProbe, FfProbeError> {
     let path = path.as_ref();
 
-    let mut cmd = process::Command::new("ffprobe");
+    let mut cmd = process::Command::new(ffmpeg_bundle::ffprobe());
 
     cmd.args([
         "-v",
@@ -23,7 +27,14 @@ pub async fn ffprobe_config(path: impl AsRef<std::path::Path>) -> Result<FfProbe
         "json",
     ]);
 
-    cmd.arg(path);
+    if is_live(&path.to_string_lossy()) {
+        cmd.args(["-rw_timeout", &LIVE_TIMEOUT.to_string()]);
+    }
+
+    match login::resolve(&path.to_string_lossy()) {
+        Some(url) => cmd.arg(url),
+        None => cmd.arg(path),
+    };
 
     // Prevent CMD popup on Windows.
     #[cfg(target_os = "windows")]
@@ -38,6 +49,96 @@ pub async fn ffprobe_config(path: impl AsRef<std::path::Path>) -> Result<FfProbe
     serde_json::from_slice::<FfProbe>(&out.stdout).map_err(FfProbeError::Deserialize)
 }
 
+/// Search window before the seek point, longer GOPs get no keyframe seek.
+const KEYFRAME_WINDOW: f64 = 20.0;
+
+/// Last keyframe time from the ffprobe frame list, at or before the given time.
+pub fn last_keyframe(output: &str, time: f64) -> Option<f64> {
+    output
+        .lines()
+        .filter_map(|l| l.trim().trim_end_matches(',').parse::<f64>().ok())
+        .filter(|t| *t <= time)
+        .reduce(f64::max)
+}
+
+/// Keyframe of the first video stream before the given time, for a frame accurate seek.
+pub async fn keyframe_before(path: &str, time: f64) -> Option<f64> {
+    let interval = format!("{:.3}%{:.3}", (time - KEYFRAME_WINDOW).max(0.0), time);
+    let out = process::Command::new(ffmpeg_bundle::ffprobe())
+        .args([
+            "-v",
+            "error",
+            "-select_streams",
+            "v:0",
+            "-skip_frame",
+            "nokey",
+            "-show_entries",
+            "frame=pts_time",
+            "-read_intervals",
+            &interval,
+            "-of",
+            "csv=p=0",
+            path,
+        ])
+        .output()
+        .await
+        .ok()?;
+
+    if !out.status.success() {
+        return None;
+    }
+
+    last_keyframe(&String::from_utf8_lossy(&out.stdout), time)
+}
+
+/// Frames which idet samples for the interlace detection.
+const IDET_FRAMES: usize = 200;
+
+/// Result of the idet multi frame detection, interlaced when most frames have a field order.
+pub fn idet_result(output: &str) -> Option<bool> {
+    let line = output
+        .lines()
+        .rfind(|l| l.contains("Multi frame detection:"))?;
+    let count = |key: &str| {
+        line.split_once(key)
+            .and_then(|(_, v)| v.split_whitespace().next())
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or_default()
+    };
+    let fields = count("TFF:") + count("BFF:");
+    let progressive = count("Progressive:");
+
+    if fields + progressive == 0 {
+        return None;
+    }
+
+    Some(fields > progressive)
+}
+
+/// Sample the frames after the seek point with idet, to see if the clip is interlaced.
+pub async fn interlaced(path: &str, seek: f64) -> Option<bool> {
+    let out = process::Command::new(ffmpeg_bundle::ffmpeg())
+        .args([
+            "-hide_banner",
+            "-nostats",
+            "-ss",
+            &seek.to_string(),
+            "-i",
+            path,
+        ])
+        .args(["-map", "0:v:0", "-frames:v", &IDET_FRAMES.to_string()])
+        .args(["-vf", "idet", "-an", "-f", "null", "-"])
+        .output()
+        .await
+        .ok()?;
+
+    if !out.status.success() {
+        return None;
+    }
+
+    idet_result(&String::from_utf8_lossy(&out.stderr))
+}
+
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum FfProbeError {
@@ -90,7 +191,12 @@ pub struct Stream {
     pub width: Option<i64>,
     pub height: Option<i64>,
     pub r_frame_rate: String,
+    pub avg_frame_rate: Option<String>,
     pub field_order: Option<String>,
+    pub color_transfer: Option<String>,
+    pub color_primaries: Option<String>,
+    pub color_space: Option<String>,
+    pub color_range: Option<String>,
 }
 
 #[serde_as]
@@ -103,6 +209,8 @@ pub struct Format {
     pub size: Option<i64>,
     #[serde_as(as = "Option<DisplayFromStr>")]
     pub bit_rate: Option<i64>,
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
@@ -110,6 +218,9 @@ pub struct MediaFormat {
     pub duration: Option<f64>,
     pub nb_streams: i64,
     pub size: i64,
+    /// Embedded tags like title and artist, keys in lower case.
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
 }
 
 impl MediaFormat {
@@ -118,6 +229,11 @@ impl MediaFormat {
             duration: format.duration,
             nb_streams: format.nb_streams,
             size: format.size.unwrap_or_default(),
+            tags: format
+                .tags
+                .into_iter()
+                .map(|(key, value)| (key.to_lowercase(), value))
+                .collect(),
         }
     }
 }
@@ -151,7 +267,13 @@ pub struct VideoStream {
     pub width: Option<i64>,
     pub height: Option<i64>,
     pub frame_rate: String,
+    /// Average frame rate, it differs from the frame rate with soft telecine.
+    pub avg_frame_rate: Option<String>,
     pub field_order: Option<String>,
+    pub color_transfer: Option<String>,
+    pub color_primaries: Option<String>,
+    pub color_space: Option<String>,
+    pub color_range: Option<String>,
 }
 
 impl VideoStream {
@@ -165,7 +287,48 @@ impl VideoStream {
             width: stream.width,
             height: stream.height,
             frame_rate: stream.r_frame_rate,
+            avg_frame_rate: stream.avg_frame_rate,
             field_order: stream.field_order,
+            color_transfer: stream.color_transfer,
+            color_primaries: stream.color_primaries,
+            color_space: stream.color_space,
+            color_range: stream.color_range,
+        }
+    }
+
+    /// HDR transfer characteristics, PQ or HLG.
+    pub fn is_hdr(&self) -> bool {
+        matches!(
+            self.color_transfer.as_deref(),
+            Some("smpte2084" | "arib-std-b67")
+        )
+    }
+
+    /// Color matrix in the naming of the scale filter.
+    ///
+    /// Clips without tag are guessed from the height, like ffmpeg does it.
+    pub fn color_matrix(&self) -> &'static str {
+        match self.color_space.as_deref() {
+            Some("bt709") => "bt709",
+            Some("smpte170m" | "bt470bg") => "bt601",
+            Some("bt2020nc" | "bt2020c") => "bt2020",
+            Some("smpte240m") => "smpte240m",
+            Some("fcc") => "fcc",
+            _ if self.height.is_some_and(|h| h < 720) => "bt601",
+            _ => "bt709",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SubtitleStream {
+    pub codec_name: Option<String>,
+}
+
+impl SubtitleStream {
+    pub fn new(stream: Stream) -> Self {
+        Self {
+            codec_name: stream.codec_name,
         }
     }
 }
@@ -175,6 +338,8 @@ pub struct MediaProbe {
     pub format: MediaFormat,
     pub audio: Vec<AudioStream>,
     pub video: Vec<VideoStream>,
+    #[serde(default)]
+    pub subtitle: Vec<SubtitleStream>,
 }
 
 impl MediaProbe {
@@ -183,6 +348,7 @@ impl MediaProbe {
     ) -> Result<Self, ProcessError> {
         let mut a_stream = vec![];
         let mut v_stream = vec![];
+        let mut s_stream = vec![];
 
         let probe = ffprobe(input).await?;
         for stream in probe.streams {
@@ -192,6 +358,7 @@ impl MediaProbe {
                 match c_type.as_str() {
                     "audio" => a_stream.push(AudioStream::new(stream)),
                     "video" => v_stream.push(VideoStream::new(stream)),
+                    "subtitle" => s_stream.push(SubtitleStream::new(stream)),
                     _ => {}
                 }
             } else {
@@ -203,6 +370,7 @@ impl MediaProbe {
             format: MediaFormat::new(probe.format),
             audio: a_stream,
             video: v_stream,
+            subtitle: s_stream,
         })
     }
 
@@ -0,0 +1,167 @@
+/*
+Encoder progress.
+
+The encoder writes its `-progress` report to stderr, as `key=value` lines, every half second.
+A block ends with `progress=continue` or `progress=end`, then the values are taken over in
+the channel status, so dashboards can show fps, bitrate and speed of the running encoder,
+not only that its process runs.
+*/
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::player::controller::ChannelManager;
+use crate::utils::time_machine::time_now;
+
+/// Last progress report from the encoder.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct EncoderProgress {
+    pub frame: u64,
+    pub fps: f64,
+    /// Bitrate in kbit/s.
+    pub bitrate: f64,
+    /// Encoding speed, 1.0 is real time.
+    pub speed: f64,
+    pub drop_frames: u64,
+    pub dup_frames: u64,
+    /// Written output in bytes.
+    pub total_size: u64,
+    /// Encoded time in seconds.
+    pub out_time: f64,
+    pub updated: String,
+}
+
+/// Number from a progress value, like `1.01x` or `2500.3kbits/s`, `N/A` is 0.
+fn number<T: std::str::FromStr + Default>(value: &str) -> T {
+    value
+        .trim_end_matches("kbits/s")
+        .trim_end_matches('x')
+        .trim()
+        .parse()
+        .unwrap_or_default()
+}
+
+pub struct Progress {
+    state: Arc<Mutex<Option<EncoderProgress>>>,
+    values: EncoderProgress,
+    timezone: Option<chrono_tz::Tz>,
+}
+
+impl Progress {
+    pub async fn new(manager: &ChannelManager) -> Self {
+        Self {
+            state: manager.encoder_progress.clone(),
+            values: EncoderProgress::default(),
+            timezone: manager.config.lock().await.channel.timezone,
+        }
+    }
+
+    /// Handle the lines of the progress report, returns false for all other lines.
+    pub async fn process(&mut self, line: &str) -> bool {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            return false;
+        };
+
+        match key {
+            "frame" => self.values.frame = number(value),
+            "fps" => self.values.fps = number(value),
+            "bitrate" => self.values.bitrate = number(value),
+            "speed" => self.values.speed = number(value),
+            "drop_frames" => self.values.drop_frames = number(value),
+            "dup_frames" => self.values.dup_frames = number(value),
+            "total_size" => self.values.total_size = number(value),
+            "out_time_us" => self.values.out_time = (number::<f64>(value) / 1_000_000.0).max(0.0),
+            "progress" => {
+                self.values.updated = time_now(&self.timezone).to_rfc3339();
+                *self.state.lock().await = Some(self.values.clone());
+            }
+            "out_time_ms" | "out_time" => {}
+            _ => return key.starts_with("stream_") && !key.contains(' '),
+        }
+
+        true
+    }
+}
+
+/// Channel state for the metrics.
+pub struct ChannelMetrics {
+    pub id: i32,
+    pub running: bool,
+    pub encoder: Option<EncoderProgress>,
+}
+
+/// Metrics in the text format of Prometheus.
+pub fn metrics(channels: &[ChannelMetrics]) -> String {
+    type Value = fn(&EncoderProgress) -> f64;
+
+    let gauges: [(&str, &str, &str, Value); 7] = [
+        (
+            "encoder_fps",
+            "gauge",
+            "Frames per second of the encoder",
+            |p| p.fps,
+        ),
+        (
+            "encoder_bitrate_kbits",
+            "gauge",
+            "Output bitrate in kbit/s",
+            |p| p.bitrate,
+        ),
+        (
+            "encoder_speed",
+            "gauge",
+            "Encoding speed, 1 is real time",
+            |p| p.speed,
+        ),
+        ("encoder_frames_total", "counter", "Encoded frames", |p| {
+            p.frame as f64
+        }),
+        (
+            "encoder_drop_frames_total",
+            "counter",
+            "Dropped frames",
+            |p| p.drop_frames as f64,
+        ),
+        (
+            "encoder_dup_frames_total",
+            "counter",
+            "Duplicated frames",
+            |p| p.dup_frames as f64,
+        ),
+        (
+            "encoder_output_bytes",
+            "counter",
+            "Written output in bytes",
+            |p| p.total_size as f64,
+        ),
+    ];
+
+    let mut text = "# HELP ffplayout_channel_running Channel is running\n# TYPE ffplayout_channel_running gauge\n".to_string();
+
+    for channel in channels {
+        text.push_str(&format!(
+            "ffplayout_channel_running{{channel=\"{}\"}} {}\n",
+            channel.id, channel.running as u8
+        ));
+    }
+
+    for (name, kind, help, value) in gauges {
+        text.push_str(&format!(
+            "# HELP ffplayout_{name} {help}\n# TYPE ffplayout_{name} {kind}\n"
+        ));
+
+        for channel in channels {
+            if let Some(progress) = &channel.encoder {
+                text.push_str(&format!(
+                    "ffplayout_{name}{{channel=\"{}\"}} {}\n",
+                    channel.id,
+                    value(progress)
+                ));
+            }
+        }
+    }
+
+    text
+}
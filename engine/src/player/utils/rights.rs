@@ -0,0 +1,146 @@
+/*
+Rights windows.
+
+Licensed content may only air between its `valid_from` and `valid_until` date. The window
+comes from a sidecar JSON next to the clip, `clip.mp4` has it in `clip.rights.json`:
+
+{"valid_from": "2024-06-01", "valid_until": "2024-12-31T20:00:00"}
+
+A date without time is the begin of the day for `valid_from`, and the end of the day for
+`valid_until`. Times without offset are in the time zone of the channel. Folder mode, the
+playlist generator and the playlist playout skip clips outside their window with a warning.
+*/
+
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone};
+use chrono_tz::Tz;
+use log::*;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::utils::{config::PlayoutConfig, logging::Target, time_machine::time_now};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Rights {
+    #[serde(default)]
+    pub valid_from: Option<String>,
+    #[serde(default)]
+    pub valid_until: Option<String>,
+}
+
+/// Sidecar file with the rights window of a clip.
+pub fn sidecar(source: &str) -> String {
+    Path::new(source)
+        .with_extension("rights.json")
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Date time from RFC 3339, a local date time or a date.
+fn parse_date(value: &str, tz: &Tz, end_of_day: bool) -> Option<DateTime<Tz>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(tz));
+    }
+
+    let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+        .or_else(|| {
+            let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+
+            if end_of_day {
+                date.succ_opt()?
+                    .and_time(NaiveTime::MIN)
+                    .checked_sub_signed(TimeDelta::seconds(1))
+            } else {
+                Some(date.and_time(NaiveTime::MIN))
+            }
+        })?;
+
+    tz.from_local_datetime(&naive).earliest()
+}
+
+impl Rights {
+    /// Check the window at the given time, the error tells why the clip can not air.
+    pub fn check(&self, time: &DateTime<Tz>) -> Result<(), String> {
+        let tz = time.timezone();
+
+        if let Some(from) = &self.valid_from {
+            match parse_date(from, &tz, false) {
+                Some(date) if *time < date => return Err(format!("embargo until {from}")),
+                None => return Err(format!("invalid valid_from: {from}")),
+                _ => {}
+            }
+        }
+
+        if let Some(until) = &self.valid_until {
+            match parse_date(until, &tz, true) {
+                Some(date) if *time > date => return Err(format!("expired since {until}")),
+                None => return Err(format!("invalid valid_until: {until}")),
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rights window of the clip, None when it has no sidecar.
+pub async fn read(source: &str) -> Result<Option<Rights>, String> {
+    let file = sidecar(source);
+
+    if !Path::new(&file).is_file() {
+        return Ok(None);
+    }
+
+    let content = fs::read_to_string(&file)
+        .await
+        .map_err(|e| format!("{file} is not readable: {e}"))?;
+
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("{file} is not valid: {e}"))
+}
+
+/// Check the rights window of the clip for the time it is on air.
+///
+/// A sidecar which can not be read blocks the clip too, better than airing it without rights.
+pub async fn check(source: &str, begin: &DateTime<Tz>, duration: f64) -> Result<(), String> {
+    let end = *begin + TimeDelta::milliseconds((duration.max(0.0) * 1000.0) as i64);
+
+    match read(source).await? {
+        Some(rights) => rights.check(begin).and_then(|_| rights.check(&end)),
+        None => Ok(()),
+    }
+}
+
+/// Time in the channel time zone, from a playlist date and the seconds since midnight.
+pub fn air_time(config: &PlayoutConfig, date: NaiveDate, seconds: f64) -> DateTime<Tz> {
+    let tz = time_now(&config.channel.timezone).timezone();
+    let midnight = tz
+        .from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .earliest()
+        .unwrap_or_else(|| time_now(&config.channel.timezone));
+
+    midnight + TimeDelta::milliseconds((seconds * 1000.0) as i64)
+}
+
+/// Check the clip for the time it is on air, and log a warning when it is outside its window.
+pub async fn is_blocked(
+    config: &PlayoutConfig,
+    source: &str,
+    begin: &DateTime<Tz>,
+    duration: f64,
+) -> bool {
+    match check(source, begin, duration).await {
+        Ok(_) => false,
+        Err(reason) => {
+            warn!(target: Target::file_mail(), channel = config.general.channel_id;
+                "Skip <b><magenta>{source}</></b>, outside rights window: {reason}"
+            );
+
+            true
+        }
+    }
+}
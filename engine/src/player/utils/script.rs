@@ -0,0 +1,277 @@
+/*
+Scheduler hooks.
+
+A Lua script of the channel can change the next clip, for scheduling logic which the playlist can
+not express. The script gets loaded into an embedded Lua runtime and can define these functions:
+
+- `on_before_item`: before a clip of the playlist gets prepared
+- `on_gap`: before a gap gets filled, at the end of the playlist or after a dropped live source
+- `on_midnight`: with the first clip of the next playlist, when the day changes
+
+Each function gets the clip and a context with hook, channel and playlist date as tables, and
+returns a table with the fields to change, like `{ source = "/media/news.mp4" }`, or nothing to
+keep the clip. Every channel has its own Lua state, so globals of the script live between the
+hooks, until the script file changes. The playout waits for the hook, until the timeout.
+
+The runtime needs the `lua` feature, which is on by default.
+*/
+
+use std::{fmt, path::PathBuf, time::Duration};
+
+use log::*;
+use serde::Deserialize;
+
+use crate::player::utils::Media;
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
+
+#[cfg(feature = "lua")]
+use std::{
+    collections::HashMap,
+    fs,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Instant, SystemTime},
+};
+
+#[cfg(feature = "lua")]
+use mlua::{Function, HookTriggers, Lua, LuaSerdeExt, Value, VmState};
+#[cfg(feature = "lua")]
+use serde_json::json;
+
+/// Run time of a hook, when no timeout is configured.
+const DEFAULT_TIMEOUT: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Hook {
+    BeforeItem,
+    Gap,
+    Midnight,
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::BeforeItem => write!(f, "on_before_item"),
+            Self::Gap => write!(f, "on_gap"),
+            Self::Midnight => write!(f, "on_midnight"),
+        }
+    }
+}
+
+/// Fields of the clip, which a hook can change.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct NodeChange {
+    pub source: Option<String>,
+    #[serde(rename = "in")]
+    pub seek: Option<f64>,
+    pub out: Option<f64>,
+    pub duration: Option<f64>,
+    pub title: Option<String>,
+    pub category: Option<String>,
+    pub custom_filter: Option<String>,
+    pub filters: Option<Vec<String>>,
+}
+
+impl NodeChange {
+    pub fn apply(self, node: &mut Media) {
+        if let Some(source) = self.source.filter(|s| *s != node.source) {
+            // the new source needs its own probe
+            node.source = source;
+            node.probe = None;
+            node.probe_audio = None;
+            node.keyframe = None;
+            node.cmd = None;
+        }
+
+        if let Some(seek) = self.seek {
+            node.seek = seek;
+        }
+
+        if let Some(out) = self.out {
+            node.out = out;
+        }
+
+        if let Some(duration) = self.duration {
+            node.duration = duration;
+        }
+
+        if let Some(title) = self.title {
+            node.title = Some(title);
+        }
+
+        if let Some(category) = self.category {
+            node.category = category;
+        }
+
+        if let Some(custom_filter) = self.custom_filter {
+            node.custom_filter = custom_filter;
+        }
+
+        if let Some(filters) = self.filters {
+            node.filters = filters;
+        }
+    }
+}
+
+/// Lua state of a channel script.
+#[cfg(feature = "lua")]
+struct Runtime {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    lua: Lua,
+}
+
+#[cfg(feature = "lua")]
+type SharedRuntime = Arc<Mutex<Option<Runtime>>>;
+
+/// Runtimes by channel id, a channel waits only for its own hooks.
+#[cfg(feature = "lua")]
+static RUNTIMES: LazyLock<Mutex<HashMap<i32, SharedRuntime>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn lua_error(e: impl fmt::Display) -> ServiceError {
+    ServiceError::Conflict(e.to_string())
+}
+
+/// Run the function and stop the script, when it runs longer than the limit.
+#[cfg(feature = "lua")]
+fn with_timeout<T>(
+    lua: &Lua,
+    limit: Duration,
+    func: impl FnOnce() -> mlua::Result<T>,
+) -> Result<T, ServiceError> {
+    let start = Instant::now();
+
+    lua.set_hook(
+        HookTriggers::new().every_nth_instruction(1000),
+        move |_, _| {
+            if start.elapsed() > limit {
+                Err(mlua::Error::runtime(format!(
+                    "no answer in {} seconds",
+                    limit.as_secs_f64()
+                )))
+            } else {
+                Ok(VmState::Continue)
+            }
+        },
+    );
+
+    let result = func();
+    lua.remove_hook();
+
+    result.map_err(lua_error)
+}
+
+#[cfg(feature = "lua")]
+fn load(path: &PathBuf, limit: Duration) -> Result<Runtime, ServiceError> {
+    let code = fs::read_to_string(path)?;
+    let lua = Lua::new();
+
+    with_timeout(&lua, limit, || {
+        lua.load(&code)
+            .set_name(format!("@{}", path.to_string_lossy()))
+            .exec()
+    })?;
+
+    Ok(Runtime {
+        path: path.clone(),
+        modified: fs::metadata(path).and_then(|m| m.modified()).ok(),
+        lua,
+    })
+}
+
+#[cfg(feature = "lua")]
+fn call(
+    id: i32,
+    path: PathBuf,
+    limit: Duration,
+    hook: Hook,
+    date: &str,
+    node: &Media,
+) -> Result<Option<NodeChange>, ServiceError> {
+    let runtime = RUNTIMES
+        .lock()
+        .unwrap()
+        .entry(id)
+        .or_insert_with(|| Arc::new(Mutex::new(None)))
+        .clone();
+    let mut runtime = runtime.lock().unwrap();
+    let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+
+    // load the script again, after it changed
+    if !runtime
+        .as_ref()
+        .is_some_and(|r| r.path == path && r.modified == modified)
+    {
+        *runtime = None;
+        *runtime = Some(load(&path, limit)?);
+    }
+
+    let lua = &runtime.as_ref().unwrap().lua;
+    let Some(func) = lua
+        .globals()
+        .get::<Option<Function>>(hook.to_string())
+        .map_err(lua_error)?
+    else {
+        return Ok(None);
+    };
+
+    let context = lua
+        .to_value(&json!({"hook": hook.to_string(), "channel": id, "date": date}))
+        .map_err(lua_error)?;
+    let node = lua.to_value(node).map_err(lua_error)?;
+    let result: Value = with_timeout(lua, limit, || func.call((node, context)))?;
+
+    if result.is_nil() {
+        return Ok(None);
+    }
+
+    lua.from_value(result).map(Some).map_err(lua_error)
+}
+
+#[cfg(not(feature = "lua"))]
+fn call(
+    _id: i32,
+    _path: PathBuf,
+    _limit: Duration,
+    _hook: Hook,
+    _date: &str,
+    _node: &Media,
+) -> Result<Option<NodeChange>, ServiceError> {
+    Err(lua_error("ffplayout is built without the lua feature"))
+}
+
+/// Run the hook and apply the changes of the script to the clip.
+///
+/// On errors the clip stays as it is.
+pub async fn run(config: &PlayoutConfig, hook: Hook, date: &str, node: &mut Media) {
+    if !config.script.enable || !config.script.path.is_file() {
+        return;
+    }
+
+    let id = config.general.channel_id;
+    let path = config.script.path.clone();
+    let limit = Duration::from_secs_f64(if config.script.timeout > 0.0 {
+        config.script.timeout
+    } else {
+        DEFAULT_TIMEOUT
+    });
+    let date = date.to_string();
+    let current = node.clone();
+
+    let change = tokio::task::spawn_blocking(move || call(id, path, limit, hook, &date, &current))
+        .await
+        .map_err(lua_error)
+        .and_then(|r| r);
+
+    match change {
+        Ok(Some(change)) => {
+            debug!(target: Target::file_mail(), channel = id; "Hook <yellow>{hook}</> changed <b><magenta>{}</></b>", node.source);
+
+            change.apply(node);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            error!(target: Target::file_mail(), channel = id; "Hook {hook}: {e}");
+        }
+    }
+}
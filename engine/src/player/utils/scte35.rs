@@ -0,0 +1,582 @@
+/*
+SCTE-35 ad insertion markers.
+
+Clips in a playlist can carry a cue:
+
+    {
+        "in": 0, "out": 30.0, "duration": 30.0, "source": "/media/show.mp4",
+        "scte35": { "splice_time": 25.0, "duration": 120.0, "event_id": 1001 }
+    }
+
+ffmpeg can not create SCTE-35 sections by itself, so for HLS output the cues
+are written as EXT-X-DATERANGE/EXT-X-CUE-OUT/EXT-X-CUE-IN tags to the m3u8 playlist,
+by a sidecar task which follows the playlist updates from ffmpeg.
+
+ffmpeg writes to a hidden playlist next to the configured one, with the program_date_time
+flag to map cues to segments. The sidecar is the only writer of the configured playlist,
+it publishes every update of the hidden playlist with the cue tags. Date times are only kept
+when they are enabled in the config, or when the playlist has cues.
+Ladders with one playlist per rendition (%v) get no cue tags.
+
+For MPEG-TS the engine injects the sections itself, in the encoded stream of the relay:
+the PMT gets a SCTE-35 stream with a CUEI registration, and a splice_insert goes out at
+the start and at the end of a break. Only outputs where the engine writes the final bytes
+can keep them, which are UDP output legs.
+*/
+
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Local, TimeDelta};
+use log::*;
+use serde::{Deserialize, Serialize};
+use tokio::{fs, sync::Mutex};
+
+use crate::utils::{
+    config::{add_hls_flag, PlayoutConfig},
+    logging::Target,
+};
+
+/// PID of the SCTE-35 stream, which gets added to the program.
+pub const SCTE35_PID: u16 = 0x01F4;
+const TS_PACKET: usize = 188;
+
+const CUE_TAGS: [&str; 3] = [
+    "#EXT-X-DATERANGE:ID=\"scte35-",
+    "#EXT-X-CUE-OUT",
+    "#EXT-X-CUE-IN",
+];
+
+/// Cue definition from playlist clip.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Scte35 {
+    /// Splice point, in seconds from clip start.
+    #[serde(default)]
+    pub splice_time: f64,
+    /// Length of the ad break in seconds.
+    pub duration: f64,
+    pub event_id: u32,
+}
+
+/// Cue with absolute time, ready to mark in the output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CueEvent {
+    pub event_id: u32,
+    pub start: DateTime<Local>,
+    pub duration: f64,
+}
+
+impl CueEvent {
+    pub fn new(cue: &Scte35, clip_start: DateTime<Local>) -> Self {
+        Self {
+            event_id: cue.event_id,
+            start: clip_start + TimeDelta::milliseconds((cue.splice_time * 1000.0) as i64),
+            duration: cue.duration,
+        }
+    }
+
+    pub fn end(&self) -> DateTime<Local> {
+        self.start + TimeDelta::milliseconds((self.duration * 1000.0) as i64)
+    }
+}
+
+fn crc32_mpeg2(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for byte in data {
+        crc ^= u32::from(*byte) << 24;
+
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
+
+/// Build a splice_info_section with an immediate splice_insert command.
+///
+/// With `out_of_network` the section marks the start of the break, with the break duration,
+/// without it marks the return to the network.
+pub fn splice_insert(event_id: u32, duration: f64, out_of_network: bool) -> Vec<u8> {
+    let mut command = event_id.to_be_bytes().to_vec();
+    // splice_event_cancel_indicator = 0, reserved
+    command.push(0x7F);
+    // out_of_network_indicator, program_splice_flag, duration_flag, splice_immediate_flag, reserved
+    command.push(if out_of_network { 0xFF } else { 0x5F });
+
+    if out_of_network {
+        // auto_return = 1, reserved, 33 bit duration in 90kHz
+        let ticks = (duration * 90000.0).round() as u64 & 0x1_FFFF_FFFF;
+        command.push(0xFE | (ticks >> 32) as u8);
+        command.extend_from_slice(&(ticks as u32).to_be_bytes());
+    }
+
+    // unique_program_id, avail_num, avails_expected
+    command.extend_from_slice(&[0x00, 0x01, 0x00, 0x00]);
+
+    let mut section = vec![
+        // protocol_version
+        0x00,
+        // encrypted_packet, encryption_algorithm, pts_adjustment
+        0x00,
+        0x00,
+        0x00,
+        0x00,
+        0x00,
+        // cw_index
+        0x00,
+        // tier = 0xFFF, splice_command_length
+        0xFF,
+        0xF0 | (command.len() >> 8) as u8,
+        command.len() as u8,
+        // splice_command_type: splice_insert
+        0x05,
+    ];
+    section.append(&mut command);
+    // descriptor_loop_length
+    section.extend_from_slice(&[0x00, 0x00]);
+
+    let section_length = section.len() + 4;
+    let mut data = vec![
+        0xFC,
+        // section_syntax_indicator = 0, private_indicator = 0, sap_type = 3
+        0x30 | (section_length >> 8) as u8,
+        section_length as u8,
+    ];
+    data.append(&mut section);
+
+    let crc = crc32_mpeg2(&data);
+    data.extend_from_slice(&crc.to_be_bytes());
+
+    data
+}
+
+fn to_hex(data: &[u8]) -> String {
+    let hex: String = data.iter().map(|b| format!("{b:02X}")).collect();
+
+    format!("0x{hex}")
+}
+
+struct Segment<'a> {
+    start: Option<DateTime<Local>>,
+    duration: f64,
+    lines: Vec<&'a str>,
+}
+
+fn date_time(line: &str) -> Option<DateTime<Local>> {
+    let value = line.strip_prefix("#EXT-X-PROGRAM-DATE-TIME:")?;
+
+    DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z")
+        .or_else(|_| DateTime::parse_from_rfc3339(value))
+        .ok()
+        .map(|t| t.with_timezone(&Local))
+}
+
+/// Add cue tags to a HLS playlist.
+///
+/// Tags from a previous run get removed first, so this can be called on every playlist update.
+pub fn insert_hls_cues(m3u8: &str, cues: &[CueEvent]) -> String {
+    let mut header = vec![];
+    let mut segments: Vec<Segment> = vec![];
+    let mut current = vec![];
+    let mut next_time: Option<DateTime<Local>> = None;
+    let mut segment_time = None;
+    let mut segment_duration = 0.0;
+
+    for line in m3u8.lines() {
+        if CUE_TAGS.iter().any(|t| line.starts_with(t)) {
+            continue;
+        }
+
+        if segments.is_empty() && current.is_empty() && !line.starts_with("#EXTINF") {
+            if let Some(time) = date_time(line) {
+                segment_time = Some(time);
+                current.push(line);
+            } else if line.starts_with("#EXT-X-DISCONTINUITY") {
+                current.push(line);
+            } else {
+                header.push(line);
+            }
+
+            continue;
+        }
+
+        if let Some(time) = date_time(line) {
+            segment_time = Some(time);
+        } else if let Some(extinf) = line.strip_prefix("#EXTINF:") {
+            segment_duration = extinf
+                .split(',')
+                .next()
+                .and_then(|d| d.parse().ok())
+                .unwrap_or_default();
+        }
+
+        current.push(line);
+
+        if !line.starts_with('#') && !line.is_empty() {
+            let start = segment_time.take().or(next_time);
+            next_time =
+                start.map(|t| t + TimeDelta::milliseconds((segment_duration * 1000.0) as i64));
+            segments.push(Segment {
+                start,
+                duration: segment_duration,
+                lines: std::mem::take(&mut current),
+            });
+        }
+    }
+
+    let mut output: Vec<String> = header.iter().map(ToString::to_string).collect();
+
+    for segment in &segments {
+        if let Some(start) = &segment.start {
+            let end = *start + TimeDelta::milliseconds((segment.duration * 1000.0) as i64);
+
+            for cue in cues {
+                if cue.start >= *start && cue.start < end {
+                    output.push(format!(
+                        "#EXT-X-DATERANGE:ID=\"scte35-{}\",START-DATE=\"{}\",PLANNED-DURATION={:.3},SCTE35-OUT={}",
+                        cue.event_id,
+                        cue.start.format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+                        cue.duration,
+                        to_hex(&splice_insert(cue.event_id, cue.duration, true))
+                    ));
+                    output.push(format!("#EXT-X-CUE-OUT:DURATION={:.3}", cue.duration));
+                }
+
+                if cue.end() >= *start && cue.end() < end {
+                    output.push("#EXT-X-CUE-IN".to_string());
+                }
+            }
+        }
+
+        output.extend(segment.lines.iter().map(ToString::to_string));
+    }
+
+    output.extend(current.iter().map(ToString::to_string));
+
+    let mut result = output.join("\n");
+    result.push('\n');
+
+    result
+}
+
+/// Find the m3u8 playlist in the output command.
+pub fn hls_playlist(config: &PlayoutConfig) -> Option<PathBuf> {
+    config
+        .output
+        .output_cmd
+        .as_ref()?
+        .iter()
+        .rev()
+        .find(|a| a.ends_with(".m3u8"))
+        .map(PathBuf::from)
+}
+
+/// The playlist which gets cue tags, ladders with one playlist per rendition have none.
+pub fn cue_playlist(config: &PlayoutConfig) -> Option<PathBuf> {
+    hls_playlist(config).filter(|p| !p.to_string_lossy().contains("%v"))
+}
+
+/// Hidden playlist in the same folder, which ffmpeg writes instead.
+pub fn private_playlist(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+
+    path.with_file_name(format!(".{name}"))
+}
+
+/// Let ffmpeg write to the private playlist, with the date times for the cue mapping.
+pub fn redirect_playlist(config: &PlayoutConfig, cmd: &mut Vec<String>) {
+    let Some(path) = cue_playlist(config) else {
+        return;
+    };
+    let public = path.to_string_lossy();
+
+    if let Some(arg) = cmd.iter_mut().rev().find(|a| **a == public) {
+        *arg = private_playlist(&path).to_string_lossy().to_string();
+        add_hls_flag(cmd, "program_date_time");
+    }
+}
+
+/// Playlist as it gets published: with cue tags, and with date times only when
+/// they are enabled or needed by the cues.
+pub fn publish_playlist(m3u8: &str, cues: &[CueEvent], date_time: bool) -> String {
+    let content = insert_hls_cues(m3u8, cues);
+
+    if date_time || content.lines().any(|l| l.starts_with(CUE_TAGS[0])) {
+        return content;
+    }
+
+    let mut result = content
+        .lines()
+        .filter(|l| !l.starts_with("#EXT-X-PROGRAM-DATE-TIME:"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    result.push('\n');
+
+    result
+}
+
+/// Sidecar task, which publishes the playlist from ffmpeg with the cue tags.
+pub async fn hls_cue_writer(
+    config: PlayoutConfig,
+    cues: Arc<Mutex<Vec<CueEvent>>>,
+    is_alive: Arc<AtomicBool>,
+) {
+    let id = config.general.channel_id;
+    let Some(path) = cue_playlist(&config) else {
+        return;
+    };
+    let private = private_playlist(&path);
+    let tmp = path.with_extension("m3u8.scte35");
+    let date_time = config.output.hls_program_date_time;
+    let mut last_modified = SystemTime::UNIX_EPOCH;
+
+    // continue the playlist from a previous run, for append_list
+    if !private.is_file() {
+        if let Ok(content) = fs::read_to_string(&path).await {
+            if let Err(e) = fs::write(&private, insert_hls_cues(&content, &[])).await {
+                error!(target: Target::file_mail(), channel = id; "Write HLS playlist failed: {e}");
+            }
+        }
+    }
+
+    while is_alive.load(Ordering::SeqCst) {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        let modified = match fs::metadata(&private).await.and_then(|m| m.modified()) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+
+        if modified == last_modified {
+            continue;
+        }
+
+        // ffmpeg replaces the playlist in one step, the content is always complete
+        let Ok(content) = fs::read_to_string(&private).await else {
+            continue;
+        };
+        last_modified = modified;
+
+        let mut cue_list = cues.lock().await;
+        // keep cues for the length of a longer playlist window
+        let limit = Local::now() - TimeDelta::hours(2);
+        cue_list.retain(|c| c.end() > limit);
+
+        let new_content = publish_playlist(&content, &cue_list, date_time);
+        drop(cue_list);
+
+        if let Err(e) = async {
+            fs::write(&tmp, new_content).await?;
+            fs::rename(&tmp, &path).await
+        }
+        .await
+        {
+            error!(target: Target::file_mail(), channel = id; "Write SCTE-35 cues failed: {e}");
+        }
+    }
+}
+
+fn packet_pid(packet: &[u8]) -> u16 {
+    (u16::from(packet[1] & 0x1F) << 8) | u16::from(packet[2])
+}
+
+/// PMT section with the SCTE-35 stream and the CUEI registration descriptor,
+/// `None` when the section has it already or can not be read.
+fn add_cue_stream(section: &[u8]) -> Option<Vec<u8>> {
+    if section.len() < 16 {
+        return None;
+    }
+
+    let info_len = (usize::from(section[10] & 0x0F) << 8) | usize::from(section[11]);
+    let es_start = 12 + info_len;
+    let crc_pos = section.len() - 4;
+    let mut i = es_start;
+
+    while i + 5 <= crc_pos {
+        if packet_pid(&section[i..]) == SCTE35_PID {
+            return None;
+        }
+
+        i += 5 + ((usize::from(section[i + 3] & 0x0F) << 8) | usize::from(section[i + 4]));
+    }
+
+    if i != crc_pos {
+        return None;
+    }
+
+    let mut data = section[..es_start].to_vec();
+    data.extend_from_slice(&[0x05, 0x04, b'C', b'U', b'E', b'I']);
+    data.extend_from_slice(&section[es_start..crc_pos]);
+    data.extend_from_slice(&[
+        0x86,
+        0xE0 | (SCTE35_PID >> 8) as u8,
+        SCTE35_PID as u8,
+        0xF0,
+        0x00,
+    ]);
+
+    let info_len = info_len + 6;
+    data[10] = (section[10] & 0xF0) | (info_len >> 8) as u8;
+    data[11] = info_len as u8;
+
+    let section_length = data.len() + 4 - 3;
+    data[1] = (section[1] & 0xF0) | (section_length >> 8) as u8;
+    data[2] = section_length as u8;
+
+    let crc = crc32_mpeg2(&data);
+    data.extend_from_slice(&crc.to_be_bytes());
+
+    Some(data)
+}
+
+/// Injects cues in a MPEG-TS stream.
+///
+/// The stream can come in chunks of any size, incomplete packets wait for the next chunk.
+/// PMTs which fit in one packet get the SCTE-35 stream, the sections go out after the
+/// first PMT.
+#[derive(Debug, Default)]
+pub struct TsInjector {
+    pmt_pid: Option<u16>,
+    has_pmt: bool,
+    continuity: u8,
+    buffer: Vec<u8>,
+    pending: Vec<u8>,
+    breaks: Vec<CueEvent>,
+}
+
+impl TsInjector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take the cues which are due from the list: the splice out goes out at the start
+    /// of the break and the splice in at its end.
+    pub fn schedule(&mut self, cues: &mut Vec<CueEvent>, now: DateTime<Local>) {
+        let (due, waiting): (Vec<_>, Vec<_>) = std::mem::take(cues)
+            .into_iter()
+            .partition(|c| c.start <= now);
+        *cues = waiting;
+
+        for cue in due {
+            self.push_section(&splice_insert(cue.event_id, cue.duration, true));
+            self.breaks.push(cue);
+        }
+
+        let (ended, running): (Vec<_>, Vec<_>) = std::mem::take(&mut self.breaks)
+            .into_iter()
+            .partition(|c| c.end() <= now);
+        self.breaks = running;
+
+        for cue in ended {
+            self.push_section(&splice_insert(cue.event_id, 0.0, false));
+        }
+    }
+
+    /// Send a section, which fits in one packet, with the next chunk.
+    pub fn push_section(&mut self, section: &[u8]) {
+        let mut packet = vec![
+            0x47,
+            0x40 | (SCTE35_PID >> 8) as u8,
+            SCTE35_PID as u8,
+            0x10 | self.continuity,
+            // pointer_field
+            0x00,
+        ];
+        packet.extend_from_slice(section);
+        packet.resize(TS_PACKET, 0xFF);
+
+        self.continuity = (self.continuity + 1) & 0x0F;
+        self.pending.append(&mut packet);
+    }
+
+    /// Complete packets of the stream, with the changed PMTs and the pending sections.
+    pub fn process(&mut self, data: &[u8]) -> Vec<u8> {
+        self.buffer.extend_from_slice(data);
+
+        let mut output = Vec::with_capacity(self.buffer.len() + self.pending.len());
+        let mut pos = 0;
+
+        while self.buffer.len() - pos >= TS_PACKET {
+            if self.buffer[pos] != 0x47 {
+                // out of sync, pass the bytes until the next sync byte
+                output.push(self.buffer[pos]);
+                pos += 1;
+                continue;
+            }
+
+            let mut packet = self.buffer[pos..pos + TS_PACKET].to_vec();
+            self.rewrite(&mut packet);
+            output.append(&mut packet);
+            pos += TS_PACKET;
+        }
+
+        self.buffer.drain(..pos);
+
+        if self.has_pmt {
+            output.append(&mut self.pending);
+        }
+
+        output
+    }
+
+    fn rewrite(&mut self, packet: &mut [u8]) {
+        let pid = packet_pid(packet);
+
+        if packet[1] & 0x40 == 0 || (pid != 0 && Some(pid) != self.pmt_pid) {
+            return;
+        }
+
+        let start = match (packet[3] >> 4) & 0x03 {
+            0x01 => 4,
+            0x03 => 5 + usize::from(packet[4]),
+            _ => return,
+        };
+
+        if start >= TS_PACKET {
+            return;
+        }
+
+        let table = start + 1 + usize::from(packet[start]);
+
+        if table + 3 > TS_PACKET {
+            return;
+        }
+
+        let end = table
+            + 3
+            + ((usize::from(packet[table + 1] & 0x0F) << 8) | usize::from(packet[table + 2]));
+
+        if end > TS_PACKET || end < table + 12 {
+            return;
+        }
+
+        if pid == 0 && packet[table] == 0x00 {
+            // first program, after the section header and before the CRC
+            self.pmt_pid = packet[table + 8..end - 4]
+                .chunks_exact(4)
+                .find(|p| p[0] != 0 || p[1] != 0)
+                .map(|p| (u16::from(p[2] & 0x1F) << 8) | u16::from(p[3]));
+        } else if pid != 0 && packet[table] == 0x02 {
+            self.has_pmt = true;
+
+            if let Some(section) = add_cue_stream(&packet[table..end]) {
+                if table + section.len() <= TS_PACKET {
+                    packet[table..table + section.len()].copy_from_slice(&section);
+                    packet[table + section.len()..].fill(0xFF);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use crate::player::utils::Media;
+use crate::utils::config::{OutputMode::*, PlayoutConfig, SubtitleMode};
+use crate::vec_strings;
+
+/// Sidecar extensions, in order of preference.
+const SIDECAR_EXTENSIONS: [&str; 4] = ["srt", "ass", "ssa", "vtt"];
+
+/// Bitmap subtitle codecs, which the MPEG-TS muxer can carry.
+const TS_SUBTITLE_CODECS: [&str; 2] = ["dvb_subtitle", "dvb_teletext"];
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubtitleSource {
+    /// Sidecar file next to the clip.
+    File(String),
+    /// Subtitle stream in the clip, with its index and codec name.
+    Embedded(usize, String),
+}
+
+impl SubtitleSource {
+    fn is_text(&self) -> bool {
+        match self {
+            Self::File(_) => true,
+            Self::Embedded(_, codec) => is_text_codec(codec),
+        }
+    }
+}
+
+fn is_text_codec(codec: &str) -> bool {
+    !codec.contains("pgs") && !codec.starts_with("dv") && codec != "xsub"
+}
+
+/// Find subtitle for clip, a sidecar file with the same name wins over embedded streams.
+pub fn subtitle_source(node: &Media) -> Option<SubtitleSource> {
+    let source = Path::new(&node.source);
+
+    for ext in SIDECAR_EXTENSIONS {
+        let file = source.with_extension(ext);
+
+        if file.is_file() {
+            return Some(SubtitleSource::File(file.to_string_lossy().to_string()));
+        }
+    }
+
+    node.probe
+        .as_ref()
+        .and_then(|p| p.subtitle.first())
+        .map(|s| SubtitleSource::Embedded(0, s.codec_name.clone().unwrap_or_default()))
+}
+
+/// Burn in subtitles, in burn mode, or when passthrough is not possible.
+///
+/// Text subtitles can be carried as WebVTT in HLS, but not in MPEG-TS,
+/// so in stream mode they get burned in.
+pub fn burn_in(config: &PlayoutConfig, source: &SubtitleSource) -> bool {
+    source.is_text()
+        && (config.processing.subtitle_mode == SubtitleMode::Burn
+            || (config.processing.subtitle_mode == SubtitleMode::Passthrough
+                && config.output.mode != HLS))
+}
+
+fn escape(path: &str) -> String {
+    path.replace('\\', "/")
+        .replace(':', "\\:")
+        .replace('\'', "\\'")
+}
+
+/// Subtitles filter, which keeps the subtitles in sync when the clip starts with a seek.
+pub fn burn_filter(node: &Media, source: &SubtitleSource) -> String {
+    let subtitles = match source {
+        SubtitleSource::File(file) => format!("subtitles=filename='{}'", escape(file)),
+        SubtitleSource::Embedded(index, _) => {
+            format!("subtitles=filename='{}':si={index}", escape(&node.source))
+        }
+    };
+
+    if node.seek > 0.0 {
+        format!(
+            "setpts=PTS+{}/TB,{subtitles},setpts=PTS-STARTPTS",
+            node.seek
+        )
+    } else {
+        subtitles
+    }
+}
+
+/// Input parameters for a passthrough sidecar file, for HLS output.
+pub fn input_cmd(config: &PlayoutConfig, node: &Media) -> Vec<String> {
+    let mut cmd = vec![];
+
+    if config.processing.subtitle_mode != SubtitleMode::Passthrough
+        || config.output.mode != HLS
+        || config.processing.vtt_enable
+    {
+        return cmd;
+    }
+
+    if let Some(SubtitleSource::File(file)) = subtitle_source(node) {
+        if node.seek > 0.5 {
+            cmd.append(&mut vec_strings!["-ss", node.seek]);
+        }
+
+        cmd.append(&mut vec_strings!["-i", file]);
+    }
+
+    cmd
+}
+
+/// Mapping and codec for passthrough subtitles.
+///
+/// `cmd` is the decoder command with all inputs, a sidecar file is always the last input.
+pub fn output_cmd(config: &PlayoutConfig, node: &Media, cmd: &[String]) -> Vec<String> {
+    if config.processing.subtitle_mode != SubtitleMode::Passthrough || config.processing.vtt_enable
+    {
+        return vec![];
+    }
+
+    match subtitle_source(node) {
+        Some(SubtitleSource::File(file)) if config.output.mode == HLS => {
+            let input = cmd.iter().filter(|a| *a == "-i").count().saturating_sub(1);
+
+            if cmd.contains(&file) {
+                vec_strings!["-map", format!("{input}:s:0"), "-c:s", "webvtt"]
+            } else {
+                vec![]
+            }
+        }
+        Some(SubtitleSource::Embedded(index, codec)) => {
+            if config.output.mode == HLS && is_text_codec(&codec) {
+                vec_strings!["-map", format!("0:s:{index}?"), "-c:s", "webvtt"]
+            } else if config.output.mode != HLS && TS_SUBTITLE_CODECS.contains(&codec.as_str()) {
+                vec_strings!["-map", format!("0:s:{index}?"), "-c:s", "copy"]
+            } else {
+                vec![]
+            }
+        }
+        _ => vec![],
+    }
+}
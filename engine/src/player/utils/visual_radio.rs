@@ -0,0 +1,147 @@
+/*
+Visual radio.
+
+Audio clips without video stream get a picture: the artwork of the clip, an audio visualizer
+at the bottom and the now playing text. So a music folder runs as video channel, without
+rendering the files before. The picture and a second reading of the audio for the visualizer
+come as extra inputs, after the inputs of the clip.
+*/
+
+use std::path::Path;
+
+use crate::player::{controller::ProcessUnit::*, utils::Media};
+use crate::utils::{config::PlayoutConfig, events::clip_data, metadata::song};
+use crate::vec_strings;
+
+/// Image names in the folder of the clip, which are taken as artwork.
+const COVER_NAMES: [&str; 4] = ["cover.jpg", "cover.png", "folder.jpg", "folder.png"];
+
+/// The clip is audio only and gets a generated picture.
+pub fn is_active(config: &PlayoutConfig, node: &Media) -> bool {
+    config.visual_radio.enable
+        && !config.processing.audio_only
+        && node.unit == Decoder
+        && node
+            .probe
+            .as_ref()
+            .is_some_and(|p| p.video.is_empty() && !p.audio.is_empty())
+}
+
+/// Artwork of the clip: from the playlist, an image with the same name or a cover
+/// in the folder of the clip, or the one from config.
+pub fn artwork(config: &PlayoutConfig, node: &Media) -> Option<String> {
+    if let Some(artwork) = node.artwork.as_ref().filter(|a| Path::new(a).is_file()) {
+        return Some(artwork.clone());
+    }
+
+    let source = Path::new(&node.source);
+    let mut candidates = ["jpg", "jpeg", "png"]
+        .iter()
+        .map(|ext| source.with_extension(ext))
+        .collect::<Vec<_>>();
+
+    if let Some(folder) = source.parent() {
+        candidates.extend(COVER_NAMES.iter().map(|name| folder.join(name)));
+    }
+
+    candidates
+        .into_iter()
+        .find(|path| path.is_file())
+        .map(|path| path.to_string_lossy().to_string())
+        .or_else(|| {
+            Path::new(&config.visual_radio.artwork_path)
+                .is_file()
+                .then(|| config.visual_radio.artwork_path.clone())
+        })
+}
+
+fn visualizer(config: &PlayoutConfig) -> Option<String> {
+    let (width, height) = (config.processing.width, config.processing.height / 4);
+
+    match config.visual_radio.visualizer.as_str() {
+        "" | "none" => None,
+        "waveform" => Some(format!(
+            "showwaves=s={width}x{height}:mode=cline:colors=white"
+        )),
+        _ => Some(format!(
+            "showfreqs=s={width}x{height}:mode=bar:ascale=log:fscale=log:colors=white"
+        )),
+    }
+}
+
+/// Inputs for the picture, which get appended to the clip command.
+pub fn input_cmd(config: &PlayoutConfig, node: &Media) -> Vec<String> {
+    let duration = node.out - node.seek;
+    let fps = config.processing.fps;
+
+    let mut cmd = match artwork(config, node) {
+        Some(image) => vec_strings!["-loop", "1", "-framerate", fps, "-t", duration, "-i", image],
+        None => vec_strings![
+            "-f",
+            "lavfi",
+            "-t",
+            duration,
+            "-i",
+            format!(
+                "color=c=black:s={}x{}:r={fps}",
+                config.processing.width, config.processing.height
+            )
+        ],
+    };
+
+    if visualizer(config).is_some() {
+        if node.seek > 0.5 {
+            cmd.append(&mut vec_strings!["-ss", node.seek]);
+        }
+
+        cmd.append(&mut vec_strings!["-t", duration, "-i", node.source.clone()]);
+    }
+
+    cmd
+}
+
+/// Input index of the picture.
+pub fn video_input(config: &PlayoutConfig, node: &Media) -> i32 {
+    let inputs = node
+        .cmd
+        .as_ref()
+        .map_or(0, |cmd| cmd.iter().filter(|arg| *arg == "-i").count());
+    let extra = if visualizer(config).is_some() { 2 } else { 1 };
+
+    inputs.saturating_sub(extra) as i32
+}
+
+/// Video filter from the picture input, with visualizer and now playing text.
+pub fn filter(config: &PlayoutConfig, node: &Media) -> String {
+    let (width, height) = (config.processing.width, config.processing.height);
+    let mut filter = format!(
+        "scale={width}:{height}:force_original_aspect_ratio=decrease,pad={width}:{height}:(ow-iw)/2:(oh-ih)/2,setsar=1,format=yuv420p"
+    );
+
+    if let Some(visualizer) = visualizer(config) {
+        let audio = video_input(config, node) + 1;
+
+        filter.push_str(&format!(
+            "[bg];[{audio}:a:0]{visualizer},format=rgba[vis];[bg][vis]overlay=0:H-h:eof_action=pass"
+        ));
+    }
+
+    if config.visual_radio.now_playing {
+        let text = song(&clip_data(node))
+            .replace('\'', "'\\\\\\''")
+            .replace('%', "\\\\\\%")
+            .replace(':', "\\:");
+        let font = if Path::new(&config.text.font_path).is_file() {
+            format!(":fontfile='{}'", config.text.font_path)
+        } else {
+            String::new()
+        };
+
+        filter.push_str(&format!(
+            ",drawtext=text='{text}':{}{font}",
+            config.visual_radio.style
+        ));
+    }
+
+    filter
+}
@@ -1,6 +1,7 @@
 use std::{
     collections::HashSet,
     fmt,
+    hash::{Hash, Hasher},
     str::FromStr,
     time::{Duration, SystemTime},
 };
@@ -9,10 +10,12 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use uuid::Uuid;
 
+use crate::db::models::{Role, UserMeta};
 use crate::utils::errors::ServiceError;
 
 pub mod broadcast;
 pub mod routes;
+pub mod ws;
 
 #[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
@@ -43,24 +46,35 @@ impl fmt::Display for Endpoint {
     }
 }
 
-#[derive(Debug, Eq, Hash, PartialEq, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct UuidData {
     pub uuid: Uuid,
     pub expiration: SystemTime,
+    /// User who requested the UUID, the streams are limited to the channels of the user.
+    pub user: UserMeta,
 }
 
 impl UuidData {
-    pub fn new() -> Self {
+    pub fn new(user: UserMeta) -> Self {
         Self {
             uuid: Uuid::new_v4(),
             expiration: SystemTime::now() + Duration::from_secs(2 * 3600), // 2 hours
+            user,
         }
     }
 }
 
-impl Default for UuidData {
-    fn default() -> Self {
-        Self::new()
+impl PartialEq for UuidData {
+    fn eq(&self, other: &Self) -> bool {
+        self.uuid == other.uuid
+    }
+}
+
+impl Eq for UuidData {}
+
+impl Hash for UuidData {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.uuid.hash(state);
     }
 }
 
@@ -85,3 +99,26 @@ pub fn check_uuid(uuids: &mut HashSet<UuidData>, uuid: &str) -> Result<&'static
         )),
     }
 }
+
+/// Check the UUID and if its user has access to the channel.
+pub fn check_channel_uuid(
+    uuids: &mut HashSet<UuidData>,
+    uuid: &str,
+    channel: &i32,
+) -> Result<(), ServiceError> {
+    check_uuid(uuids, uuid)?;
+
+    let client_uuid = Uuid::parse_str(uuid)?;
+    let roles = [Role::ChannelAdmin, Role::User, Role::Guest];
+
+    if uuids
+        .iter()
+        .any(|entry| entry.uuid == client_uuid && entry.user.has_role(channel, &roles))
+    {
+        Ok(())
+    } else {
+        Err(ServiceError::Forbidden(
+            "No access to this channel".to_string(),
+        ))
+    }
+}
@@ -1,13 +1,18 @@
-use actix_web::{get, post, web, Responder};
+use actix_http::ws::handshake;
+use actix_web::{body::BodyStream, get, post, web, HttpRequest, HttpResponse, Responder};
 use actix_web_grants::proc_macro::protect;
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
 
-use super::{check_uuid, prune_uuids, SseAuthState, UuidData};
-use crate::db::models::Role;
+use super::{check_channel_uuid, check_uuid, prune_uuids, SseAuthState, UuidData};
+use crate::db::models::{Role, UserMeta};
 use crate::player::controller::ChannelController;
-use crate::sse::{broadcast::Broadcaster, Endpoint};
-use crate::utils::errors::ServiceError;
+use crate::sse::{broadcast::Broadcaster, ws, Endpoint};
+use crate::utils::{
+    errors::ServiceError,
+    sync::{self, SYNC_HEADER},
+};
 
 #[derive(Deserialize, Serialize)]
 struct User {
@@ -29,12 +34,15 @@ impl User {
 /// ```
 #[post("/generate-uuid")]
 #[protect(
-    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User"),
+    any("Role::GlobalAdmin", "Role::ChannelAdmin", "Role::User", "Role::Guest"),
     ty = "Role"
 )]
-async fn generate_uuid(data: web::Data<SseAuthState>) -> Result<impl Responder, ServiceError> {
+async fn generate_uuid(
+    data: web::Data<SseAuthState>,
+    user: web::ReqData<UserMeta>,
+) -> Result<impl Responder, ServiceError> {
     let mut uuids = data.uuids.lock().await;
-    let new_uuid = UuidData::new();
+    let new_uuid = UuidData::new(user.into_inner());
     let user_auth = User::new(Endpoint::default(), new_uuid.uuid.to_string());
 
     prune_uuids(&mut uuids);
@@ -77,7 +85,7 @@ async fn event_stream(
 ) -> Result<impl Responder, ServiceError> {
     let mut uuids = data.uuids.lock().await;
 
-    check_uuid(&mut uuids, user.uuid.as_str())?;
+    check_channel_uuid(&mut uuids, user.uuid.as_str(), &id)?;
 
     let manager = controllers
         .lock()
@@ -85,8 +93,81 @@ async fn event_stream(
         .get(*id)
         .await
         .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
-    
+
     Ok(broadcaster
         .new_client(manager.clone(), user.endpoint.clone())
         .await)
 }
+
+/// **Playhead for a backup engine**
+///
+/// Needs the shared sync secret from the channel config.
+///
+/// ```BASH
+/// curl -X GET 'http://127.0.0.1:8787/data/sync/1' -H 'X-Ffplayout-Sync: <SECRET>'
+/// ```
+#[get("/sync/{id}")]
+async fn sync_playhead(
+    req: HttpRequest,
+    id: web::Path<i32>,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<impl Responder, ServiceError> {
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+    let secret = manager.config.lock().await.sync.secret.clone();
+    let given = req
+        .headers()
+        .get(SYNC_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or_default();
+
+    if secret.is_empty() || given != secret {
+        return Err(ServiceError::Unauthorized("Wrong sync secret".to_string()));
+    }
+
+    Ok(web::Json(sync::playhead(&manager).await))
+}
+
+/// **WebSocket for the playout status**
+///
+/// Pushes the current clip with elapsed and remaining time, the next clip and the playout events.
+///
+/// ```BASH
+/// websocat 'ws://127.0.0.1:8787/ws/channel/1?uuid=f2f8c29b-712a-48c5-8919-b535d3a05a3a'
+/// ```
+#[get("/ws/channel/{id}")]
+pub async fn ws_channel(
+    req: HttpRequest,
+    payload: web::Payload,
+    data: web::Data<SseAuthState>,
+    id: web::Path<i32>,
+    user: web::Query<User>,
+    controllers: web::Data<Mutex<ChannelController>>,
+) -> Result<HttpResponse, ServiceError> {
+    let mut uuids = data.uuids.lock().await;
+
+    check_channel_uuid(&mut uuids, user.uuid.as_str(), &id)?;
+
+    let manager = controllers
+        .lock()
+        .await
+        .get(*id)
+        .await
+        .ok_or(ServiceError::BadRequest("Channel not found".to_string()))?;
+
+    let mut response =
+        handshake(req.head()).map_err(|e| ServiceError::BadRequest(e.to_string()))?;
+    let (tx, rx) = mpsc::channel(32);
+
+    actix_web::rt::spawn(ws::session(manager, payload, tx));
+
+    let stream = ReceiverStream::new(rx).map(Ok::<_, actix_web::Error>);
+
+    Ok(HttpResponse::from(
+        response.body(BodyStream::new(stream)).map_into_boxed_body(),
+    ))
+}
@@ -0,0 +1,113 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use actix_codec::{Decoder, Encoder};
+use actix_http::ws::{Codec, Frame, Message};
+use actix_web::web::{self, Bytes, BytesMut};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use tokio::{
+    sync::{broadcast, mpsc},
+    time::interval,
+};
+
+use crate::player::{controller::ChannelManager, utils::get_data_map};
+use crate::utils::events::next_clip_data;
+
+/// Current clip with elapsed and remaining time, the next clip and the engine state.
+pub async fn status(manager: &ChannelManager) -> Value {
+    let mut data = get_data_map(manager).await;
+    let media = manager.current_media.lock().await.clone();
+    let elapsed = data
+        .get("elapsed")
+        .and_then(Value::as_f64)
+        .unwrap_or_default();
+    let (remaining, next) = match media {
+        Some(media) => (
+            media.out - media.seek - elapsed,
+            next_clip_data(manager, &media).await,
+        ),
+        None => (0.0, None),
+    };
+
+    data.insert("type".to_string(), json!("status"));
+    data.insert(
+        "running".to_string(),
+        json!(manager.is_alive.load(Ordering::SeqCst)),
+    );
+    data.insert(
+        "remaining".to_string(),
+        json!((remaining.max(0.0) * 1000.0).round() / 1000.0),
+    );
+    data.insert("next".to_string(), json!(next));
+
+    Value::Object(data)
+}
+
+async fn send(codec: &mut Codec, sender: &mpsc::Sender<Bytes>, message: Message) -> bool {
+    let mut buffer = BytesMut::new();
+
+    codec.encode(message, &mut buffer).is_ok() && sender.send(buffer.freeze()).await.is_ok()
+}
+
+/// Push the status every second and the playout events, as soon as they happen.
+///
+/// Incoming frames are only read for ping and close.
+pub async fn session(
+    manager: ChannelManager,
+    mut payload: web::Payload,
+    sender: mpsc::Sender<Bytes>,
+) {
+    let mut codec = Codec::new();
+    let mut incoming = BytesMut::new();
+    let mut events = manager.events.subscribe();
+    let mut ticker = interval(Duration::from_secs(1));
+
+    loop {
+        let messages = tokio::select! {
+            _ = ticker.tick() => vec![Message::Text(status(&manager).await.to_string().into())],
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let mut data = serde_json::to_value(&event).unwrap_or_default();
+                    data["type"] = json!("event");
+
+                    // state changes go out without waiting for the next tick
+                    vec![
+                        Message::Text(data.to_string().into()),
+                        Message::Text(status(&manager).await.to_string().into()),
+                    ]
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            chunk = payload.next() => {
+                let Some(Ok(chunk)) = chunk else {
+                    break;
+                };
+
+                incoming.extend_from_slice(&chunk);
+                let mut replies = vec![];
+
+                loop {
+                    match codec.decode(&mut incoming) {
+                        Ok(Some(Frame::Ping(data))) => replies.push(Message::Pong(data)),
+                        Ok(Some(Frame::Close(reason))) => {
+                            send(&mut codec, &sender, Message::Close(reason)).await;
+                            return;
+                        }
+                        Ok(Some(_)) => {}
+                        Ok(None) => break,
+                        Err(_) => return,
+                    }
+                }
+
+                replies
+            }
+        };
+
+        for message in messages {
+            if !send(&mut codec, &sender, message).await {
+                return;
+            }
+        }
+    }
+}
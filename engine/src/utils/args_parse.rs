@@ -69,13 +69,25 @@ pub struct Args {
     #[clap(long, env, help_heading = Some("Initial Setup"), help = "SMTP port for system mail")]
     pub smtp_port: Option<u16>,
 
+    #[clap(long, env, help_heading = Some("Initial Setup"), help = "OIDC issuer URL, like: https://auth.example.org/realms/ffplayout")]
+    pub oidc_issuer: Option<String>,
+
+    #[clap(long, env, help_heading = Some("Initial Setup"), help = "OIDC client id")]
+    pub oidc_client_id: Option<String>,
+
+    #[clap(long, env, help_heading = Some("Initial Setup"), help = "OIDC client secret")]
+    pub oidc_client_secret: Option<String>,
+
+    #[clap(long, env, help_heading = Some("Initial Setup"), help = "OIDC redirect URL, like: https://playout.example.org/auth/oidc/callback")]
+    pub oidc_redirect_url: Option<String>,
+
     #[clap(long, env, help_heading = Some("Initial Setup / General"), help = "Logging path")]
     pub logs: Option<String>,
 
     #[clap(long, env, help_heading = Some("Initial Setup / General"), help = "Path to public files, also HLS playlists")]
     pub public: Option<String>,
 
-    #[clap(long, help_heading = Some("Initial Setup / Playlist"), help = "Path to playlist, or playlist root folder")]
+    #[clap(long, env, help_heading = Some("Initial Setup / Playlist"), help = "Path to playlist, or playlist root folder")]
     pub playlists: Option<String>,
 
     #[clap(long, help_heading = Some("General"), help = "Add or update a global admin user")]
@@ -103,14 +115,31 @@ pub struct Args {
 
     #[clap(
         long,
+        env,
         help_heading = Some("General"),
         help = "import advanced channel configuration from file."
     )]
     pub import_advanced: Option<PathBuf>,
 
-    #[clap(long, help_heading = Some("General"), help = "Import channel configuration from file")]
+    #[clap(long, env, help_heading = Some("General"), help = "Import channel configuration from file")]
     pub import_config: Option<PathBuf>,
 
+    #[clap(
+        long,
+        env,
+        help_heading = Some("General"),
+        help = "URL of a static ffmpeg build, which is used instead of the ffmpeg from the system"
+    )]
+    pub ffmpeg_url: Option<String>,
+
+    #[clap(
+        long,
+        env,
+        help_heading = Some("General"),
+        help = "SHA-256 checksum of the ffmpeg build archive"
+    )]
+    pub ffmpeg_sha256: Option<String>,
+
     #[clap(long, help_heading = Some("General"), help = "List available channel ids")]
     pub list_channels: bool,
 
@@ -134,6 +163,14 @@ pub struct Args {
     #[clap(long, env, help_heading = Some("General"), help = "Add timestamp to log line")]
     pub log_timestamp: bool,
 
+    #[clap(
+        long,
+        env,
+        help_heading = Some("General"),
+        help = "Log format: text, json or both"
+    )]
+    pub log_format: Option<String>,
+
     #[clap(
         short,
         long,
@@ -165,22 +202,58 @@ pub struct Args {
     )]
     pub start: Option<String>,
 
+    #[clap(
+        long,
+        help_heading = Some("Playlist"),
+        help = "Import text/m3u file or CSV rundown as playlist for the date",
+        value_names = ["FILE", "YYYY-MM-DD"],
+        num_args = 2,
+    )]
+    pub import_playlist: Option<Vec<String>>,
+
     #[clap(short = 'T', long, help_heading = Some("Playlist"), help = "JSON template file for generating playlist")]
     pub template: Option<PathBuf>,
 
     #[clap(long, help_heading = Some("Playlist"), help = "Only validate given playlist")]
     pub validate: bool,
 
+    #[clap(
+        long,
+        alias = "dry-run",
+        help_heading = Some("Playlist"),
+        help = "Check playlist of today, or given date, without starting ffmpeg",
+        value_name = "YYYY-MM-DD",
+        num_args = 0..=1,
+        default_missing_value = "",
+    )]
+    pub validate_only: Option<String>,
+
     #[clap(long, env, help_heading = Some("Playout"), help = "Run playout without webserver and frontend")]
     pub foreground: bool,
 
+    #[clap(
+        long,
+        env,
+        help_heading = Some("Playout"),
+        help = "Run only channel 1, configured from flags and environment, with an in-memory database"
+    )]
+    pub single_channel: bool,
+
+    #[clap(
+        long,
+        env,
+        help_heading = Some("Playout"),
+        help = "Seconds to wait on shutdown, until ffmpeg has closed the output [default: 10]"
+    )]
+    pub drain_timeout: Option<u64>,
+
     #[clap(short, long, help_heading = Some("Playout"), help = "Play folder content")]
     pub folder: Option<PathBuf>,
 
-    #[clap(short, long, help_heading = Some("Playout"), help = "Set output mode: desktop, hls, null, stream")]
+    #[clap(short, long, env, help_heading = Some("Playout"), help = "Set output mode: desktop, hls, null, stream")]
     pub output: Option<OutputMode>,
 
-    #[clap(short, long, help_heading = Some("Playout"), help = "Set audio volume")]
+    #[clap(short, long, env, help_heading = Some("Playout"), help = "Set audio volume")]
     pub volume: Option<f64>,
 
     #[clap(long, help_heading = Some("Playout"), help = "Skip validation process")]
@@ -217,6 +290,39 @@ fn clean_input(input: &str) -> String {
         .to_string()
 }
 
+/// Paths for the single channel mode, they come from flags or environment and not from a prompt.
+async fn single_channel(pool: &Pool<Sqlite>, args: &Args) -> Result<(), ProcessError> {
+    let mut global = handles::select_global(pool).await?;
+
+    if let Some(storage) = &args.storage {
+        global.storage.clone_from(storage);
+    }
+
+    if let Some(playlists) = &args.playlists {
+        global.playlists.clone_from(playlists);
+    }
+
+    if let Some(logs) = &args.logs {
+        global.logs.clone_from(logs);
+    }
+
+    if let Some(public) = &args.public {
+        global.public.clone_from(public);
+    }
+
+    handles::update_global(pool, global.clone()).await?;
+
+    let mut channel = handles::select_channel(pool, &1).await?;
+    channel.public = global.public;
+    channel.playlists = global.playlists;
+    channel.storage = global.storage;
+
+    handles::update_channel(pool, 1, channel).await?;
+    handles::update_player(pool, 1, true).await?;
+
+    Ok(())
+}
+
 pub async fn init_args(pool: &Pool<Sqlite>) -> Result<bool, ProcessError> {
     let mut args = ARGS.clone();
     let mut init = false;
@@ -225,9 +331,18 @@ pub async fn init_args(pool: &Pool<Sqlite>) -> Result<bool, ProcessError> {
         init = handles::db_migrate(pool).await?;
     }
 
+    if args.single_channel {
+        single_channel(pool, &args).await?;
+    }
+
     let channels = handles::select_related_channels(pool, None)
         .await
         .unwrap_or(vec![Channel::default()]);
+    let channel_ids = if args.single_channel {
+        Some(vec![1])
+    } else {
+        ARGS.channel.clone()
+    };
 
     if args.init {
         let check_user = handles::select_users(pool).await;
@@ -333,6 +448,23 @@ pub async fn init_args(pool: &Pool<Sqlite>) -> Result<bool, ProcessError> {
                 .prompt()?;
         }
 
+        // OIDC is optional, so there is no prompt for it
+        if let Some(issuer) = args.oidc_issuer {
+            global.oidc_issuer = issuer;
+        }
+
+        if let Some(client_id) = args.oidc_client_id {
+            global.oidc_client_id = client_id;
+        }
+
+        if let Some(client_secret) = args.oidc_client_secret {
+            global.oidc_client_secret = client_secret;
+        }
+
+        if let Some(redirect_url) = args.oidc_redirect_url {
+            global.oidc_redirect_url = redirect_url;
+        }
+
         handles::update_global(pool, global.clone()).await?;
 
         let mut channel = handles::select_channel(pool, &1).await?;
@@ -379,6 +511,7 @@ pub async fn init_args(pool: &Pool<Sqlite>) -> Result<bool, ProcessError> {
             role_id: Some(1),
             channel_ids: Some(chl.clone()),
             token: None,
+            channel_roles: Default::default(),
         };
 
         handles::insert_or_update_user(pool, ff_user).await?;
@@ -431,7 +564,7 @@ pub async fn init_args(pool: &Pool<Sqlite>) -> Result<bool, ProcessError> {
     }
 
     if let Some(path) = &ARGS.import_advanced {
-        if let Some(channel) = &ARGS.channel {
+        if let Some(channel) = &channel_ids {
             for id in channel {
                 match AdvancedConfig::import(pool, *id, path).await {
                     Ok(_) => println!("Import config done..."),
@@ -446,7 +579,7 @@ pub async fn init_args(pool: &Pool<Sqlite>) -> Result<bool, ProcessError> {
     }
 
     if let Some(path) = &ARGS.import_config {
-        if let Some(channel) = &ARGS.channel {
+        if let Some(channel) = &channel_ids {
             for id in channel {
                 match PlayoutConfig::import(pool, *id, path).await {
                     Ok(_) => println!("Import config done..."),
@@ -0,0 +1,375 @@
+/*
+As-run log.
+
+Once a playlist day is over, the scheduled clips of its playlist are compared with the airing
+history and written to a daily log, as BXF like XML or as CSV with configurable columns.
+Each entry has the scheduled and the actual start time with their deviation. Scheduled
+clips which never aired are `skipped`, clips on air which are not in the schedule, like
+filler, are `substituted`.
+*/
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeDelta, TimeZone, Timelike};
+use chrono_tz::Tz;
+use log::*;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use tokio::{
+    fs,
+    sync::Mutex,
+    time::{sleep, Duration},
+};
+
+use crate::db::{handles, models::Airing};
+use crate::player::utils::{rights::air_time, Media};
+use crate::utils::{
+    config::PlayoutConfig, epg::escape, errors::ServiceError, history::TIME_FORMAT,
+    logging::Target, playlist::read_playlist, time_machine::time_now,
+};
+
+/// Seconds an airing may start after the end of its scheduled slot and still count as aired.
+const MATCH_WINDOW: f64 = 600.0;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AsRunStatus {
+    #[default]
+    Aired,
+    Skipped,
+    Substituted,
+}
+
+impl AsRunStatus {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Aired => "aired",
+            Self::Skipped => "skipped",
+            Self::Substituted => "substituted",
+        }
+    }
+}
+
+/// One line of the as-run log, times are in the channel time zone.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct AsRunEntry {
+    pub scheduled: Option<String>,
+    pub actual: Option<String>,
+    /// Seconds the clip started after its scheduled time.
+    pub deviation: Option<f64>,
+    pub planned: f64,
+    pub duration: Option<f64>,
+    pub source: String,
+    pub title: String,
+    pub category: String,
+    pub status: AsRunStatus,
+}
+
+fn title(title: &str, source: &str) -> String {
+    if title.is_empty() {
+        Path::new(source)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string()
+    } else {
+        title.to_string()
+    }
+}
+
+fn substituted(airing: &Airing) -> AsRunEntry {
+    AsRunEntry {
+        actual: Some(airing.started_at.clone()),
+        planned: airing.planned,
+        duration: airing.duration,
+        source: airing.source.clone(),
+        title: title(&airing.title, &airing.source),
+        category: airing.category.clone(),
+        status: AsRunStatus::Substituted,
+        ..Default::default()
+    }
+}
+
+/// Compare the scheduled clips with the airings, both in the order of their start time.
+///
+/// A scheduled clip matches the next airing of the same source, which starts within its slot
+/// plus a tolerance. Airings in between are substituted.
+pub fn reconcile(
+    scheduled: &[(DateTime<Tz>, Media)],
+    airings: &[Airing],
+    tz: &Tz,
+) -> Vec<AsRunEntry> {
+    let started = |airing: &Airing| {
+        NaiveDateTime::parse_from_str(&airing.started_at, TIME_FORMAT)
+            .ok()
+            .and_then(|t| tz.from_local_datetime(&t).earliest())
+    };
+    let mut entries = vec![];
+    let mut next = 0;
+
+    for (begin, node) in scheduled {
+        let planned = node.out - node.seek;
+        let mut entry = AsRunEntry {
+            scheduled: Some(begin.format(TIME_FORMAT).to_string()),
+            planned,
+            source: node.source.clone(),
+            title: title(node.title.as_deref().unwrap_or_default(), &node.source),
+            category: node.category.clone(),
+            status: AsRunStatus::Skipped,
+            ..Default::default()
+        };
+
+        let found = airings[next..].iter().position(|airing| {
+            airing.source == node.source
+                && started(airing).is_some_and(|time| {
+                    let deviation = (time - *begin).num_milliseconds() as f64 / 1000.0;
+
+                    deviation.abs() <= planned.max(0.0) + MATCH_WINDOW
+                })
+        });
+
+        if let Some(pos) = found {
+            entries.extend(airings[next..next + pos].iter().map(substituted));
+
+            let airing = &airings[next + pos];
+
+            entry.actual = Some(airing.started_at.clone());
+            entry.deviation =
+                started(airing).map(|time| (time - *begin).num_milliseconds() as f64 / 1000.0);
+            entry.duration = airing.duration;
+            entry.status = AsRunStatus::Aired;
+            next += pos + 1;
+        }
+
+        entries.push(entry);
+    }
+
+    entries.extend(airings[next..].iter().map(substituted));
+
+    entries
+}
+
+/// Playlist day which is on air at the given time.
+fn playlist_day(config: &PlayoutConfig, now: &DateTime<Tz>) -> NaiveDate {
+    let start_sec = config.playlist.start_sec.unwrap_or_default();
+    let day = now.date_naive();
+
+    // before the day start, the playlist of yesterday is still playing
+    if (now.num_seconds_from_midnight() as f64) < start_sec {
+        day - TimeDelta::days(1)
+    } else {
+        day
+    }
+}
+
+/// As-run entries of a playlist day.
+pub async fn report(
+    pool: &Pool<Sqlite>,
+    config: &PlayoutConfig,
+    date: NaiveDate,
+) -> Result<Vec<AsRunEntry>, ServiceError> {
+    let start_sec = config.playlist.start_sec.unwrap_or_default();
+    let length = config.playlist.length_sec.unwrap_or(86400.0);
+    let begin = air_time(config, date, start_sec);
+    let end = air_time(config, date, start_sec + length);
+    let tz = begin.timezone();
+    let mut scheduled = vec![];
+
+    if let Ok(playlist) = read_playlist(config, date.format("%Y-%m-%d").to_string()).await {
+        let mut offset = 0.0;
+
+        for node in playlist.program {
+            let time = air_time(config, date, start_sec + offset);
+
+            offset += node.out - node.seek;
+            scheduled.push((time, node));
+        }
+    }
+
+    // a few seconds tolerance for clips which start early
+    let tolerance = TimeDelta::seconds(5);
+    let airings = handles::select_airings(
+        pool,
+        config.general.channel_id,
+        &(begin - tolerance).format(TIME_FORMAT).to_string(),
+        &(end - tolerance).format(TIME_FORMAT).to_string(),
+    )
+    .await?;
+
+    Ok(reconcile(&scheduled, &airings, &tz))
+}
+
+fn column(entry: &AsRunEntry, name: &str) -> String {
+    let number = |value: Option<f64>| value.map(|v| format!("{v:.3}")).unwrap_or_default();
+
+    match name {
+        "scheduled" => entry.scheduled.clone().unwrap_or_default(),
+        "actual" => entry.actual.clone().unwrap_or_default(),
+        "deviation" => number(entry.deviation),
+        "planned" => number(Some(entry.planned)),
+        "duration" => number(entry.duration),
+        "source" => entry.source.clone(),
+        "title" => entry.title.clone(),
+        "category" => entry.category.clone(),
+        "status" => entry.status.as_str().to_string(),
+        _ => String::new(),
+    }
+}
+
+/// As-run log as CSV with the given columns.
+pub fn csv(entries: &[AsRunEntry], columns: &[String]) -> Result<String, ServiceError> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+
+    writer
+        .write_record(columns)
+        .map_err(|e| ServiceError::Conflict(e.to_string()))?;
+
+    for entry in entries {
+        writer
+            .write_record(columns.iter().map(|c| column(entry, c)))
+            .map_err(|e| ServiceError::Conflict(e.to_string()))?;
+    }
+
+    let data = writer
+        .into_inner()
+        .map_err(|e| ServiceError::Conflict(e.to_string()))?;
+
+    Ok(String::from_utf8_lossy(&data).to_string())
+}
+
+/// As-run log as BXF like XML document.
+pub fn xml(channel_id: i32, date: &NaiveDate, entries: &[AsRunEntry]) -> String {
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<BxfMessage origin=\"ffplayout\" messageType=\"Information\">\n  <BxfData action=\"add\">\n    <AsRun channel=\"channel-{channel_id}\" date=\"{date}\">\n"
+    );
+
+    for entry in entries {
+        xml.push_str(&format!(
+            "      <AsRunEvent status=\"{}\">\n",
+            entry.status.as_str()
+        ));
+
+        let fields = [
+            ("ScheduledStart", entry.scheduled.clone()),
+            ("ActualStart", entry.actual.clone()),
+            ("Deviation", entry.deviation.map(|d| format!("{d:.3}"))),
+            ("ScheduledDuration", Some(format!("{:.3}", entry.planned))),
+            ("ActualDuration", entry.duration.map(|d| format!("{d:.3}"))),
+            ("Title", Some(entry.title.clone())),
+            ("Source", Some(entry.source.clone())),
+            (
+                "Category",
+                Some(entry.category.clone()).filter(|c| !c.is_empty()),
+            ),
+        ];
+
+        for (tag, value) in fields {
+            if let Some(value) = value {
+                xml.push_str(&format!("        <{tag}>{}</{tag}>\n", escape(&value)));
+            }
+        }
+
+        xml.push_str("      </AsRunEvent>\n");
+    }
+
+    xml.push_str("    </AsRun>\n  </BxfData>\n</BxfMessage>\n");
+
+    xml
+}
+
+/// Folder of the as-run logs.
+pub fn path(config: &PlayoutConfig) -> PathBuf {
+    if config.asrun.path.as_os_str().is_empty() {
+        config
+            .channel
+            .logs
+            .join("asrun")
+            .join(config.general.channel_id.to_string())
+    } else {
+        config.asrun.path.clone()
+    }
+}
+
+/// Extension of the log file, from the configured format.
+pub fn extension(format: &str) -> &str {
+    if format.eq_ignore_ascii_case("csv") {
+        "csv"
+    } else {
+        "xml"
+    }
+}
+
+fn render(
+    config: &PlayoutConfig,
+    date: NaiveDate,
+    entries: &[AsRunEntry],
+    format: &str,
+) -> Result<String, ServiceError> {
+    match extension(format) {
+        "csv" => csv(entries, &config.asrun.columns),
+        _ => Ok(xml(config.general.channel_id, &date, entries)),
+    }
+}
+
+/// As-run log of the day in the given format.
+pub async fn document(
+    pool: &Pool<Sqlite>,
+    config: &PlayoutConfig,
+    date: NaiveDate,
+    format: &str,
+) -> Result<String, ServiceError> {
+    let entries = report(pool, config, date).await?;
+
+    render(config, date, &entries, format)
+}
+
+/// File of the as-run log of the day.
+pub fn file(config: &PlayoutConfig, date: &NaiveDate, format: &str) -> PathBuf {
+    path(config)
+        .join(format!("asrun_{date}"))
+        .with_extension(extension(format))
+}
+
+async fn write_log(pool: &Pool<Sqlite>, config: &PlayoutConfig) -> Result<(), ServiceError> {
+    let now = time_now(&config.channel.timezone);
+    let date = playlist_day(config, &now) - TimeDelta::days(1);
+    let file = file(config, &date, &config.asrun.format);
+
+    if file.is_file() {
+        return Ok(());
+    }
+
+    let entries = report(pool, config, date).await?;
+
+    if !entries.iter().any(|e| e.actual.is_some()) {
+        return Ok(());
+    }
+
+    let content = render(config, date, &entries, &config.asrun.format)?;
+
+    fs::create_dir_all(path(config)).await?;
+    fs::write(&file, content).await?;
+
+    info!(target: Target::file_mail(), channel = config.general.channel_id; "Write as-run log <b><magenta>{}</></b>", file.display());
+
+    Ok(())
+}
+
+/// Write the as-run log of the last playlist day, once it is over.
+pub async fn run(pool: Pool<Sqlite>, config: Arc<Mutex<PlayoutConfig>>) {
+    // the channel manager holds the other reference
+    while Arc::strong_count(&config) > 1 {
+        let config = config.lock().await.clone();
+
+        if config.asrun.enable {
+            if let Err(e) = write_log(&pool, &config).await {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "As-run log: {e}");
+            }
+        }
+
+        sleep(Duration::from_secs(60)).await;
+    }
+}
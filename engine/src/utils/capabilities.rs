@@ -0,0 +1,125 @@
+/*
+Capabilities of the ffmpeg build.
+
+ffmpeg gets asked once for its encoders, muxers, filters and protocols. The configs of the
+channels are checked against them, so a missing part gives a clear error at start, instead
+of a failing ffmpeg process later.
+*/
+
+use std::{collections::HashSet, env};
+
+use log::*;
+use tokio::sync::OnceCell;
+
+use crate::utils::{ffmpeg_bundle, hw_accel::ffmpeg_list};
+
+static CAPABILITIES: OnceCell<Option<Capabilities>> = OnceCell::const_new();
+
+#[derive(Clone, Debug, Default)]
+pub struct Capabilities {
+    /// Path of the ffmpeg executable, for the messages.
+    pub path: String,
+    pub encoders: HashSet<String>,
+    pub muxers: HashSet<String>,
+    pub filters: HashSet<String>,
+    pub protocols: HashSet<String>,
+}
+
+/// Names from the list of `ffmpeg -encoders` or `ffmpeg -muxers`, after the `---` line.
+pub fn parse_list(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .skip_while(|l| l.trim().is_empty() || l.trim().chars().any(|c| c != '-'))
+        .skip(1)
+        .filter_map(|l| l.split_whitespace().nth(1))
+        .flat_map(|n| n.split(','))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Names from the list of `ffmpeg -filters`, the lines have the form `TSC name A->A ...`.
+pub fn parse_filters(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let mut parts = l.split_whitespace();
+            let name = parts.nth(1)?;
+
+            parts
+                .next()
+                .is_some_and(|io| io.contains("->"))
+                .then(|| name.to_string())
+        })
+        .collect()
+}
+
+/// Output protocols from the list of `ffmpeg -protocols`.
+pub fn parse_protocols(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .skip_while(|l| l.trim() != "Output:")
+        .skip(1)
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Full path of ffmpeg, the bundled one or the one from `PATH`.
+fn ffmpeg_path() -> String {
+    let ffmpeg = ffmpeg_bundle::ffmpeg();
+
+    if ffmpeg.is_absolute() {
+        return ffmpeg.to_string_lossy().to_string();
+    }
+
+    env::var_os("PATH")
+        .and_then(|paths| {
+            env::split_paths(&paths)
+                .map(|p| p.join(&ffmpeg))
+                .find(|p| p.is_file())
+        })
+        .unwrap_or(ffmpeg)
+        .to_string_lossy()
+        .to_string()
+}
+
+async fn probe() -> Result<Capabilities, String> {
+    let capabilities = Capabilities {
+        path: ffmpeg_path(),
+        encoders: parse_list(&ffmpeg_list("-encoders").await?),
+        muxers: parse_list(&ffmpeg_list("-muxers").await?),
+        filters: parse_filters(&ffmpeg_list("-filters").await?),
+        protocols: parse_protocols(&ffmpeg_list("-protocols").await?),
+    };
+
+    if capabilities.encoders.is_empty() {
+        return Err(format!("{} lists no encoders", capabilities.path));
+    }
+
+    debug!(
+        "ffmpeg <b><magenta>{}</></b> has <yellow>{}</> encoders, <yellow>{}</> muxers, <yellow>{}</> filters and <yellow>{}</> output protocols",
+        capabilities.path,
+        capabilities.encoders.len(),
+        capabilities.muxers.len(),
+        capabilities.filters.len(),
+        capabilities.protocols.len()
+    );
+
+    Ok(capabilities)
+}
+
+/// Capabilities of ffmpeg, they are probed on the first call.
+///
+/// `None` when ffmpeg can not be run, then there is nothing to check against.
+pub async fn get() -> Option<&'static Capabilities> {
+    CAPABILITIES
+        .get_or_init(|| async {
+            probe()
+                .await
+                .map_err(|e| error!("Probe ffmpeg capabilities: {e}"))
+                .ok()
+        })
+        .await
+        .as_ref()
+}
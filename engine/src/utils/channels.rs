@@ -50,13 +50,16 @@ const QSV_FILTER_OVERLAY: &str = "overlay_qsv={}:shortest=1";
 
 const OUTPUT_PARM: &str = "-c:v libx264 -crf 23 -x264-params keyint=50:min-keyint=25:scenecut=-1 -maxrate 1300k -bufsize 2600k -preset faster -tune zerolatency -profile:v Main -level 3.1 -c:a aac -ar 44100 -b:a 128k -flags +cgop -f hls -hls_time 6 -hls_list_size 600 -hls_flags append_list+delete_segments+omit_endlist -hls_segment_filename live/stream-%d.ts live/stream.m3u8";
 
+/// Create a channel with default configs. The id is only set on worker nodes,
+/// to get the same id as on the controller.
 pub async fn create_channel(
     conn: &Pool<Sqlite>,
     controllers: Arc<Mutex<ChannelController>>,
     queue: Arc<Mutex<Vec<Arc<Mutex<MailQueue>>>>>,
     target_channel: Channel,
+    id: Option<i32>,
 ) -> Result<Channel, ServiceError> {
-    let channel = handles::insert_channel(conn, target_channel).await?;
+    let channel = handles::insert_channel(conn, target_channel, id).await?;
 
     handles::new_channel_presets(conn, channel.id).await?;
     handles::update_channel(conn, channel.id, channel.clone()).await?;
@@ -128,7 +131,7 @@ pub async fn create_channel(
     let config = get_config(conn, channel.id).await?;
 
     let m_queue = Arc::new(Mutex::new(MailQueue::new(channel.id, config.mail.clone())));
-    let manager = ChannelManager::new(conn.clone(), channel.clone(), config).await;
+    let manager = ChannelManager::new(conn.clone(), channel.clone(), config).await?;
 
     if let Err(e) = manager.storage.lock().await.copy_assets().await {
         error!("{e}");
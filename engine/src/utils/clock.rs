@@ -0,0 +1,115 @@
+/*
+System clock checks.
+
+At startup the system clock gets validated: a date before the release of this version points
+to a missing real time clock, and an unsynchronized clock lets the playout drift away.
+While running, the wall clock is compared with the monotonic clock. When it jumps, for example
+through a NTP step, the playlist gets initialized again on the new time, instead of filling or
+skipping the difference. Leap seconds stay below the jump threshold and are ignored.
+Summer/winter time changes are handled by the playlist iterator with a time shift.
+*/
+
+use std::{sync::atomic::Ordering, time::Duration};
+
+use log::*;
+use serde_json::json;
+use tokio::{
+    process::Command,
+    time::{interval, Instant},
+};
+
+use crate::player::{
+    controller::{ChannelManager, ProcessUnit::*},
+    utils::{is_close, time_in_seconds},
+};
+use crate::utils::{
+    config::ProcessMode::Playlist, events::EventKind, logging::Target, time_machine::time_now,
+};
+
+/// Difference between wall clock and monotonic clock in seconds, which counts as jump.
+pub const CLOCK_JUMP: f64 = 2.0;
+
+/// Earliest plausible year of the system clock.
+const MIN_YEAR: i32 = 2024;
+
+/// Jump of the wall clock, None when it runs with the monotonic clock.
+///
+/// Both values are the elapsed seconds since the last check, the wall clock
+/// as seconds of the day, so the day change is no jump.
+pub fn jump(wall: f64, monotonic: f64) -> Option<f64> {
+    let mut diff = (wall - monotonic) % 86400.0;
+
+    if diff > 43200.0 {
+        diff -= 86400.0;
+    } else if diff < -43200.0 {
+        diff += 86400.0;
+    }
+
+    (diff.abs() > CLOCK_JUMP).then_some(diff)
+}
+
+/// Check if the system clock is plausible and synchronized.
+pub async fn check(id: i32) {
+    let year = time_now(&None).format("%Y").to_string();
+
+    if year.parse::<i32>().unwrap_or_default() < MIN_YEAR {
+        error!(target: Target::file_mail(), channel = id;
+            "System clock is not plausible, year: <yellow>{year}</>"
+        );
+    }
+
+    // timedatectl is only available on systemd based systems
+    if let Ok(output) = Command::new("timedatectl")
+        .args(["show", "--property=NTPSynchronized", "--value"])
+        .output()
+        .await
+    {
+        if output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "no" {
+            warn!(target: Target::file_mail(), channel = id;
+                "System clock is not synchronized, playout time can drift"
+            );
+        }
+    }
+}
+
+/// Watch the wall clock, runs as long as the player.
+pub async fn watch(manager: ChannelManager) {
+    let id = manager.id;
+    let mut ticker = interval(Duration::from_secs(1));
+    let mut last_mono = Instant::now();
+    let mut last_wall = None;
+
+    loop {
+        ticker.tick().await;
+
+        let config = manager.config.lock().await.clone();
+        let wall = time_in_seconds(&config.channel.timezone);
+        let mono = last_mono.elapsed().as_secs_f64();
+
+        last_mono = Instant::now();
+
+        let Some(last) = last_wall.replace(wall) else {
+            continue;
+        };
+
+        let Some(diff) = jump(wall - last, mono) else {
+            continue;
+        };
+
+        if config.processing.mode != Playlist
+            || !config.playlist.length.contains(':')
+            || !manager.is_alive.load(Ordering::SeqCst)
+            || is_close(diff.abs(), 3600.0, CLOCK_JUMP)
+        {
+            continue;
+        }
+
+        warn!(target: Target::file_mail(), channel = id;
+            "System clock jumped <yellow>{diff:.3}</> seconds, resynchronize playlist"
+        );
+
+        manager.emit(EventKind::ClockJump, json!({"jump": diff}));
+        manager.list_init.store(true, Ordering::SeqCst);
+        manager.stop(Decoder).await;
+    }
+}
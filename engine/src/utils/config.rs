@@ -1,27 +1,31 @@
 use std::{
+    collections::BTreeMap,
     fmt,
     path::{Path, PathBuf},
     str::FromStr,
 };
 
-use chrono::NaiveTime;
+use chrono::{NaiveTime, Weekday};
 use chrono_tz::Tz;
 use flexi_logger::Level;
+use glob::Pattern;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use shlex::split;
 use sqlx::{Pool, Sqlite};
 use tokio::{fs, io::AsyncReadExt};
 use ts_rs::TS;
 
 use crate::file::{clean_raw_abs_path, norm_abs_path};
-use crate::utils::{gen_tcp_socket, time_to_sec};
+use crate::utils::{gen_tcp_socket, hw_accel, time_to_sec};
 use crate::vec_strings;
 use crate::AdvancedConfig;
 use crate::ARGS;
 use crate::{
     db::{handles, models},
     file::utils::ABS_PATH_INDICATOR,
+    player::{output::hls_key, utils::is_logo},
 };
 
 use super::errors::ServiceError;
@@ -61,6 +65,61 @@ pub const FFMPEG_UNRECOVERABLE_ERRORS: [&str; 9] = [
     "Option not found",
 ];
 
+/// Config fields, which a running channel takes without restart, they apply from the next clip on.
+/// `section.*` stands for all fields of the section.
+pub const LIVE_FIELDS: [&str; 18] = [
+    "processing.add_logo",
+    "processing.logo",
+    "processing.logo_scale",
+    "processing.logo_opacity",
+    "processing.logo_position",
+    "processing.logo_schedule",
+    "processing.logo_loop",
+    "processing.logo_fps",
+    "processing.volume",
+    "playlist.gap_policy",
+    "storage.filler",
+    "text.font",
+    "text.style",
+    "text.regex",
+    "text.now_next_format",
+    "text.now_next_style",
+    "mail.*",
+    "hooks.*",
+];
+
+/// Field, which a running channel takes without restart.
+pub fn is_live_field(field: &str) -> bool {
+    let section = field.split('.').next().unwrap_or_default();
+
+    LIVE_FIELDS.contains(&field) || LIVE_FIELDS.contains(&format!("{section}.*").as_str())
+}
+
+/// Config fields, which run commands or write files on the host,
+/// only global admins can change them. `section.*` stands for all fields of the section.
+pub const GLOBAL_ADMIN_FIELDS: [&str; 12] = [
+    "hooks.*",
+    "graphics.command",
+    "task.path",
+    "script.path",
+    "storage.cache_path",
+    "recorder.path",
+    "recorder.param",
+    "asrun.path",
+    "ingest.input_param",
+    "output.output_param",
+    "output.legs",
+    "output.icecast_video",
+];
+
+/// Field, which only a global admin can change.
+pub fn is_global_admin_field(field: &str) -> bool {
+    let section = field.split('.').next().unwrap_or_default();
+
+    GLOBAL_ADMIN_FIELDS.contains(&field)
+        || GLOBAL_ADMIN_FIELDS.contains(&format!("{section}.*").as_str())
+}
+
 #[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
 #[ts(export, export_to = "playout_config.d.ts")]
 #[serde(rename_all = "lowercase")]
@@ -70,17 +129,34 @@ pub enum OutputMode {
     HLS,
     Null,
     Stream,
+    Srt,
+    Ndi,
+    Decklink,
+    Icecast,
+    Dash,
+    Whip,
 }
 
 impl OutputMode {
     fn new(s: &str) -> Self {
         match s {
             "desktop" => Self::Desktop,
+            "dash" => Self::Dash,
             "null" => Self::Null,
             "stream" => Self::Stream,
+            "srt" => Self::Srt,
+            "ndi" => Self::Ndi,
+            "decklink" => Self::Decklink,
+            "icecast" => Self::Icecast,
+            "whip" => Self::Whip,
             _ => Self::HLS,
         }
     }
+
+    /// HLS and DASH are written to segments by one ffmpeg instance, which decodes and encodes.
+    pub fn is_segmented(&self) -> bool {
+        matches!(self, Self::HLS | Self::Dash)
+    }
 }
 
 impl FromStr for OutputMode {
@@ -92,7 +168,16 @@ impl FromStr for OutputMode {
             "hls" => Ok(Self::HLS),
             "null" => Ok(Self::Null),
             "stream" => Ok(Self::Stream),
-            _ => Err("Use 'desktop', 'hls', 'null' or 'stream'".to_string()),
+            "srt" => Ok(Self::Srt),
+            "ndi" => Ok(Self::Ndi),
+            "decklink" => Ok(Self::Decklink),
+            "icecast" => Ok(Self::Icecast),
+            "dash" => Ok(Self::Dash),
+            "whip" => Ok(Self::Whip),
+            _ => Err(
+                "Use 'desktop', 'hls', 'dash', 'null', 'stream', 'srt', 'whip', 'ndi', 'decklink' or 'icecast'"
+                    .to_string(),
+            ),
         }
     }
 }
@@ -104,246 +189,798 @@ impl fmt::Display for OutputMode {
             OutputMode::HLS => write!(f, "hls"),
             OutputMode::Null => write!(f, "null"),
             OutputMode::Stream => write!(f, "stream"),
+            OutputMode::Srt => write!(f, "srt"),
+            OutputMode::Ndi => write!(f, "ndi"),
+            OutputMode::Decklink => write!(f, "decklink"),
+            OutputMode::Icecast => write!(f, "icecast"),
+            OutputMode::Dash => write!(f, "dash"),
+            OutputMode::Whip => write!(f, "whip"),
         }
     }
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq, TS)]
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
 #[ts(export, export_to = "playout_config.d.ts")]
 #[serde(rename_all = "lowercase")]
-pub enum ProcessMode {
-    Folder,
+pub enum SrtMode {
     #[default]
-    Playlist,
+    Caller,
+    Listener,
 }
 
-impl ProcessMode {
+impl SrtMode {
     fn new(s: &str) -> Self {
         match s {
-            "folder" => Self::Folder,
-            _ => Self::Playlist,
+            "listener" => Self::Listener,
+            _ => Self::Caller,
         }
     }
 }
 
-impl fmt::Display for ProcessMode {
+impl FromStr for SrtMode {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "caller" => Ok(Self::Caller),
+            "listener" => Ok(Self::Listener),
+            _ => Err("Use 'caller' or 'listener'".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for SrtMode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            ProcessMode::Folder => write!(f, "folder"),
-            ProcessMode::Playlist => write!(f, "playlist"),
+            SrtMode::Caller => write!(f, "caller"),
+            SrtMode::Listener => write!(f, "listener"),
         }
     }
 }
 
-impl FromStr for ProcessMode {
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+#[serde(rename_all = "lowercase")]
+pub enum RecordFormat {
+    #[default]
+    Ts,
+    Mkv,
+}
+
+impl RecordFormat {
+    fn new(s: &str) -> Self {
+        match s {
+            "mkv" => Self::Mkv,
+            _ => Self::Ts,
+        }
+    }
+
+    /// Muxer name for the segment muxer.
+    pub fn muxer(&self) -> &str {
+        match *self {
+            RecordFormat::Ts => "mpegts",
+            RecordFormat::Mkv => "matroska",
+        }
+    }
+}
+
+impl FromStr for RecordFormat {
     type Err = String;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         match input {
-            "folder" => Ok(Self::Folder),
-            "playlist" => Ok(Self::Playlist),
-            _ => Err("Use 'folder' or 'playlist'".to_string()),
+            "ts" => Ok(Self::Ts),
+            "mkv" => Ok(Self::Mkv),
+            _ => Err("Use 'ts' or 'mkv'".to_string()),
         }
     }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize, TS)]
-pub struct Template {
-    pub sources: Vec<Source>,
+impl fmt::Display for RecordFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecordFormat::Ts => write!(f, "ts"),
+            RecordFormat::Mkv => write!(f, "mkv"),
+        }
+    }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Serialize, TS)]
-pub struct Source {
-    #[ts(type = "string")]
-    pub start: NaiveTime,
-    #[ts(type = "string")]
-    pub duration: NaiveTime,
-    pub shuffle: bool,
-    pub paths: Vec<PathBuf>,
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleMode {
+    #[default]
+    None,
+    Burn,
+    Passthrough,
 }
 
-/// Channel Config
-///
-/// This we init ones, when ffplayout is starting and use them globally in the hole program.
-#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
-#[ts(export, export_to = "playout_config.d.ts")]
-pub struct PlayoutConfig {
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub channel: Channel,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub advanced: AdvancedConfig,
-    pub general: General,
-    pub mail: Mail,
-    pub logging: Logging,
-    pub processing: Processing,
-    pub ingest: Ingest,
-    pub playlist: Playlist,
-    pub storage: Storage,
-    pub text: Text,
-    pub task: Task,
-    #[serde(alias = "out")]
-    pub output: Output,
+impl SubtitleMode {
+    fn new(s: &str) -> Self {
+        match s {
+            "burn" => Self::Burn,
+            "passthrough" => Self::Passthrough,
+            _ => Self::None,
+        }
+    }
 }
 
-#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
-pub struct Channel {
-    pub logs: PathBuf,
-    pub public: PathBuf,
-    pub playlists: PathBuf,
-    pub storage: PathBuf,
-    pub shared: bool,
-    #[ts(type = "string")]
-    pub timezone: Option<Tz>,
+impl FromStr for SubtitleMode {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "none" => Ok(Self::None),
+            "burn" => Ok(Self::Burn),
+            "passthrough" => Ok(Self::Passthrough),
+            _ => Err("Use 'none', 'burn' or 'passthrough'".to_string()),
+        }
+    }
 }
 
-impl Channel {
-    pub fn new(config: &models::GlobalSettings, channel: models::Channel) -> Self {
-        Self {
-            logs: PathBuf::from(config.logs.clone()),
-            public: PathBuf::from(channel.public.clone()),
-            playlists: PathBuf::from(channel.playlists.clone()),
-            storage: PathBuf::from(channel.storage.clone()),
-            shared: config.shared,
-            timezone: channel.timezone,
+impl fmt::Display for SubtitleMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SubtitleMode::None => write!(f, "none"),
+            SubtitleMode::Burn => write!(f, "burn"),
+            SubtitleMode::Passthrough => write!(f, "passthrough"),
         }
     }
 }
 
-#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+/// Deinterlacing of the clips, `auto` checks the field order and samples the frames with idet.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
 #[ts(export, export_to = "playout_config.d.ts")]
-pub struct General {
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub id: i32,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub channel_id: i32,
-    pub stop_threshold: f64,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub generate: Option<Vec<String>>,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub ffmpeg_filters: Vec<String>,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub ffmpeg_libs: Vec<String>,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub template: Option<Template>,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub skip_validation: bool,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub validate: bool,
+#[serde(rename_all = "lowercase")]
+pub enum Deinterlace {
+    #[default]
+    Auto,
+    On,
+    Off,
 }
 
-impl General {
-    fn new(config: &models::Configuration) -> Self {
-        Self {
-            id: config.id,
-            channel_id: config.channel_id,
-            stop_threshold: config.general_stop_threshold,
-            generate: None,
-            ffmpeg_filters: vec![],
-            ffmpeg_libs: vec![],
-            template: None,
-            skip_validation: false,
-            validate: false,
+impl Deinterlace {
+    fn new(s: &str) -> Self {
+        match s {
+            "on" => Self::On,
+            "off" => Self::Off,
+            _ => Self::Auto,
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, TS)]
-#[ts(export, export_to = "playout_config.d.ts")]
-pub struct Mail {
-    #[serde(skip_deserializing)]
-    pub show: bool,
-    pub subject: String,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub smtp_server: String,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub smtp_starttls: bool,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub smtp_user: String,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub smtp_password: String,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub smtp_port: u16,
-    pub recipient: String,
-    #[ts(type = "string")]
-    pub mail_level: Level,
-    pub interval: i64,
-}
+impl FromStr for Deinterlace {
+    type Err = String;
 
-impl Mail {
-    fn new(global: &models::GlobalSettings, config: &models::Configuration) -> Self {
-        Self {
-            show: !global.smtp_password.is_empty() && global.smtp_server != "mail.example.org",
-            subject: config.mail_subject.clone(),
-            smtp_server: global.smtp_server.clone(),
-            smtp_starttls: global.smtp_starttls,
-            smtp_user: global.smtp_user.clone(),
-            smtp_password: global.smtp_password.clone(),
-            smtp_port: global.smtp_port,
-            recipient: config.mail_recipient.clone(),
-            mail_level: string_to_log_level(config.mail_level.clone()),
-            interval: config.mail_interval,
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "auto" => Ok(Self::Auto),
+            "on" => Ok(Self::On),
+            "off" => Ok(Self::Off),
+            _ => Err("Use 'auto', 'on' or 'off'".to_string()),
         }
     }
 }
 
-impl Default for Mail {
-    fn default() -> Self {
-        Mail {
-            show: false,
-            subject: String::default(),
-            smtp_server: String::default(),
-            smtp_starttls: bool::default(),
-            smtp_user: String::default(),
-            smtp_password: String::default(),
-            smtp_port: 465,
-            recipient: String::default(),
-            mail_level: Level::Debug,
-            interval: i64::default(),
+impl fmt::Display for Deinterlace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Deinterlace::Auto => write!(f, "auto"),
+            Deinterlace::On => write!(f, "on"),
+            Deinterlace::Off => write!(f, "off"),
         }
     }
 }
 
-#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+/// Fitting of clips with another aspect ratio into the output.
+///
+/// Letterbox keeps the full width, pillarbox the full height, auto keeps the full picture.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
 #[ts(export, export_to = "playout_config.d.ts")]
-pub struct Logging {
-    pub ffmpeg_level: String,
-    pub ingest_level: String,
-    pub detect_silence: bool,
-    pub ignore_lines: Vec<String>,
+#[serde(rename_all = "kebab-case")]
+pub enum AspectMode {
+    #[default]
+    Auto,
+    Letterbox,
+    Pillarbox,
+    CenterCrop,
+    Stretch,
 }
 
-impl Logging {
-    fn new(config: &models::Configuration) -> Self {
-        Self {
-            ffmpeg_level: config.logging_ffmpeg_level.clone(),
-            ingest_level: config.logging_ingest_level.clone(),
-            detect_silence: config.logging_detect_silence,
-            ignore_lines: config.logging_ignore.split(';').map(String::from).collect(),
+impl AspectMode {
+    fn new(s: &str) -> Self {
+        s.parse().unwrap_or_default()
+    }
+}
+
+impl FromStr for AspectMode {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "auto" => Ok(Self::Auto),
+            "letterbox" => Ok(Self::Letterbox),
+            "pillarbox" => Ok(Self::Pillarbox),
+            "center-crop" => Ok(Self::CenterCrop),
+            "stretch" => Ok(Self::Stretch),
+            _ => {
+                Err("Use 'auto', 'letterbox', 'pillarbox', 'center-crop' or 'stretch'".to_string())
+            }
         }
     }
 }
 
-#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+impl fmt::Display for AspectMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AspectMode::Auto => write!(f, "auto"),
+            AspectMode::Letterbox => write!(f, "letterbox"),
+            AspectMode::Pillarbox => write!(f, "pillarbox"),
+            AspectMode::CenterCrop => write!(f, "center-crop"),
+            AspectMode::Stretch => write!(f, "stretch"),
+        }
+    }
+}
+
+/// Frame rate conversion of clips with another frame rate.
+///
+/// Drop duplicates or drops frames, blend mixes neighbor frames, interpolate calculates new
+/// frames from the motion and ivtc removes the telecine from 29.97 fps clips with film content.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
 #[ts(export, export_to = "playout_config.d.ts")]
-pub struct Processing {
-    pub mode: ProcessMode,
-    pub audio_only: bool,
-    pub copy_audio: bool,
+#[serde(rename_all = "lowercase")]
+pub enum FpsMode {
+    #[default]
+    Auto,
+    Drop,
+    Blend,
+    Interpolate,
+    Ivtc,
+}
+
+impl FpsMode {
+    fn new(s: &str) -> Self {
+        s.parse().unwrap_or_default()
+    }
+}
+
+impl FromStr for FpsMode {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "auto" => Ok(Self::Auto),
+            "drop" => Ok(Self::Drop),
+            "blend" => Ok(Self::Blend),
+            "interpolate" => Ok(Self::Interpolate),
+            "ivtc" => Ok(Self::Ivtc),
+            _ => Err("Use 'auto', 'drop', 'blend', 'interpolate' or 'ivtc'".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for FpsMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FpsMode::Auto => write!(f, "auto"),
+            FpsMode::Drop => write!(f, "drop"),
+            FpsMode::Blend => write!(f, "blend"),
+            FpsMode::Interpolate => write!(f, "interpolate"),
+            FpsMode::Ivtc => write!(f, "ivtc"),
+        }
+    }
+}
+
+/// Filter for tone mapping HDR clips to SDR.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+#[serde(rename_all = "lowercase")]
+pub enum Tonemap {
+    Off,
+    #[default]
+    Zscale,
+    Libplacebo,
+}
+
+impl Tonemap {
+    fn new(s: &str) -> Self {
+        match s {
+            "off" => Self::Off,
+            "libplacebo" => Self::Libplacebo,
+            _ => Self::Zscale,
+        }
+    }
+}
+
+impl FromStr for Tonemap {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "off" => Ok(Self::Off),
+            "zscale" => Ok(Self::Zscale),
+            "libplacebo" => Ok(Self::Libplacebo),
+            _ => Err("Use 'off', 'zscale' or 'libplacebo'".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Tonemap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Tonemap::Off => write!(f, "off"),
+            Tonemap::Zscale => write!(f, "zscale"),
+            Tonemap::Libplacebo => write!(f, "libplacebo"),
+        }
+    }
+}
+
+/// Target color space of the channel.
+///
+/// Auto takes BT.709 for HD and BT.601 for SD outputs. Clips in another matrix or in full
+/// range get converted, the encoder gets the matching tags. Off passes the clips through.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSpace {
+    Off,
+    #[default]
+    Auto,
+    Bt709,
+    Bt601,
+}
+
+impl ColorSpace {
+    fn new(s: &str) -> Self {
+        s.parse().unwrap_or_default()
+    }
+
+    /// Color space of the output with the given height, `None` when it is off.
+    pub fn target(&self, height: i64) -> Option<Self> {
+        match self {
+            Self::Off => None,
+            Self::Auto if height >= 720 => Some(Self::Bt709),
+            Self::Auto => Some(Self::Bt601),
+            space => Some(*space),
+        }
+    }
+
+    /// Color matrix, primaries and range tags for the encoder.
+    ///
+    /// SD with 480 lines has the NTSC primaries, all other SD the PAL primaries.
+    pub fn encoder_tags(&self, height: i64) -> Vec<String> {
+        let (matrix, primaries, transfer) = match self.target(height) {
+            Some(Self::Bt709) => ("bt709", "bt709", "bt709"),
+            Some(_) if height <= 480 => ("smpte170m", "smpte170m", "smpte170m"),
+            Some(_) => ("bt470bg", "bt470bg", "smpte170m"),
+            None => return vec![],
+        };
+
+        vec_strings![
+            "-colorspace",
+            matrix,
+            "-color_primaries",
+            primaries,
+            "-color_trc",
+            transfer,
+            "-color_range",
+            "tv"
+        ]
+    }
+}
+
+impl FromStr for ColorSpace {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "off" => Ok(Self::Off),
+            "auto" => Ok(Self::Auto),
+            "bt709" => Ok(Self::Bt709),
+            "bt601" => Ok(Self::Bt601),
+            _ => Err("Use 'off', 'auto', 'bt709' or 'bt601'".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for ColorSpace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ColorSpace::Off => write!(f, "off"),
+            ColorSpace::Auto => write!(f, "auto"),
+            ColorSpace::Bt709 => write!(f, "bt709"),
+            ColorSpace::Bt601 => write!(f, "bt601"),
+        }
+    }
+}
+
+/// Program for decoding and encoding.
+///
+/// GStreamer is experimental: it encodes the null, stream, SRT, NDI, desktop and DeckLink
+/// output and decodes clips which need no ffmpeg filters, all other clips use ffmpeg.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Ffmpeg,
+    Gstreamer,
+}
+
+impl Backend {
+    fn new(s: &str) -> Self {
+        s.parse().unwrap_or_default()
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "ffmpeg" => Ok(Self::Ffmpeg),
+            "gstreamer" => Ok(Self::Gstreamer),
+            _ => Err("Use 'ffmpeg' or 'gstreamer'".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Backend::Ffmpeg => write!(f, "ffmpeg"),
+            Backend::Gstreamer => write!(f, "gstreamer"),
+        }
+    }
+}
+
+/// Hardware acceleration profile, for decoding, filters and encoding.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+#[serde(rename_all = "lowercase")]
+pub enum HwAccel {
+    #[default]
+    None,
+    Nvenc,
+    Qsv,
+    Vaapi,
+}
+
+impl HwAccel {
+    fn new(s: &str) -> Self {
+        match s {
+            "nvenc" => Self::Nvenc,
+            "qsv" => Self::Qsv,
+            "vaapi" => Self::Vaapi,
+            _ => Self::None,
+        }
+    }
+}
+
+impl FromStr for HwAccel {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "none" => Ok(Self::None),
+            "nvenc" => Ok(Self::Nvenc),
+            "qsv" => Ok(Self::Qsv),
+            "vaapi" => Ok(Self::Vaapi),
+            _ => Err("Use 'none', 'nvenc', 'qsv' or 'vaapi'".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for HwAccel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HwAccel::None => write!(f, "none"),
+            HwAccel::Nvenc => write!(f, "nvenc"),
+            HwAccel::Qsv => write!(f, "qsv"),
+            HwAccel::Vaapi => write!(f, "vaapi"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, Eq, PartialEq, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+#[serde(rename_all = "lowercase")]
+pub enum ProcessMode {
+    Folder,
+    #[default]
+    Playlist,
+}
+
+impl ProcessMode {
+    fn new(s: &str) -> Self {
+        match s {
+            "folder" => Self::Folder,
+            _ => Self::Playlist,
+        }
+    }
+}
+
+impl fmt::Display for ProcessMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProcessMode::Folder => write!(f, "folder"),
+            ProcessMode::Playlist => write!(f, "playlist"),
+        }
+    }
+}
+
+impl FromStr for ProcessMode {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "folder" => Ok(Self::Folder),
+            "playlist" => Ok(Self::Playlist),
+            _ => Err("Use 'folder' or 'playlist'".to_string()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, TS)]
+pub struct Template {
+    pub sources: Vec<Source>,
+    #[serde(default)]
+    pub bumpers: Option<Bumpers>,
+}
+
+impl Template {
+    /// Sources which play on the given weekday.
+    pub fn sources_on(&self, weekday: Weekday) -> Vec<Source> {
+        self.sources
+            .iter()
+            .filter(|s| s.weekdays.is_empty() || s.weekdays.contains(&weekday))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Station IDs or bumpers, which the generator inserts between the clips.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, TS)]
+pub struct Bumpers {
+    pub path: PathBuf,
+    /// Insert a bumper after this number of clips, 0 disables it.
+    #[serde(default)]
+    pub every_items: usize,
+    /// Insert a bumper after this number of minutes, 0 disables it.
+    #[serde(default)]
+    pub every_minutes: f64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, TS)]
+pub struct Source {
+    #[ts(type = "string")]
+    pub start: NaiveTime,
+    #[ts(type = "string")]
+    pub duration: NaiveTime,
+    pub shuffle: bool,
+    /// Pick the clips which fill the block best, to keep the filler short.
+    #[serde(default)]
+    pub best_fit: bool,
+    /// Play the next episodes from the folders, continue with the following ones next time.
+    #[serde(default)]
+    pub series: bool,
+    pub paths: Vec<PathBuf>,
+    /// Library query, like `tag:music AND duration<300`, takes the clips instead of the paths.
+    #[serde(default)]
+    pub query: String,
+    /// Days on which the block plays, every day when empty.
+    #[ts(type = "string[]")]
+    #[serde(default)]
+    pub weekdays: Vec<Weekday>,
+}
+
+/// Channel Config
+///
+/// This we init ones, when ffplayout is starting and use them globally in the hole program.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct PlayoutConfig {
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub channel: Channel,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub advanced: AdvancedConfig,
+    pub general: General,
+    pub mail: Mail,
+    pub logging: Logging,
+    pub processing: Processing,
+    pub ingest: Ingest,
+    pub playlist: Playlist,
+    pub storage: Storage,
+    pub text: Text,
+    pub task: Task,
+    #[serde(default)]
+    pub script: Script,
+    #[serde(default)]
+    pub recorder: Recorder,
+    #[serde(default)]
+    pub webhook: Webhook,
+    #[serde(default)]
+    pub hooks: EventHooks,
+    #[serde(default)]
+    pub mqtt: Mqtt,
+    #[serde(default)]
+    pub transition: Transition,
+    #[serde(default)]
+    pub monitor: Monitor,
+    #[serde(default)]
+    pub failover: Failover,
+    #[serde(default)]
+    pub sync: PlayoutSync,
+    #[serde(default)]
+    pub graphics: Graphics,
+    #[serde(default)]
+    pub ticker: Ticker,
+    #[serde(default)]
+    pub clock: Clock,
+    #[serde(default)]
+    pub visual_radio: VisualRadio,
+    #[serde(default)]
+    pub asrun: AsRun,
+    #[serde(default)]
+    pub preview: PreviewStream,
+    #[serde(alias = "out")]
+    pub output: Output,
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+pub struct Channel {
+    pub logs: PathBuf,
+    pub public: PathBuf,
+    pub playlists: PathBuf,
+    pub storage: PathBuf,
+    pub shared: bool,
+    #[ts(type = "string")]
+    pub timezone: Option<Tz>,
+}
+
+impl Channel {
+    pub fn new(config: &models::GlobalSettings, channel: models::Channel) -> Self {
+        Self {
+            logs: PathBuf::from(config.logs.clone()),
+            public: PathBuf::from(channel.public.clone()),
+            playlists: PathBuf::from(channel.playlists.clone()),
+            storage: PathBuf::from(channel.storage.clone()),
+            shared: config.shared,
+            timezone: channel.timezone,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct General {
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub id: i32,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub channel_id: i32,
+    pub stop_threshold: f64,
+    #[serde(default)]
+    pub drift_correction: f64,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub generate: Option<Vec<String>>,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub template: Option<Template>,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub skip_validation: bool,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub validate: bool,
+}
+
+impl General {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            id: config.id,
+            channel_id: config.channel_id,
+            stop_threshold: config.general_stop_threshold,
+            drift_correction: config.general_drift_correction,
+            generate: None,
+            template: None,
+            skip_validation: false,
+            validate: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Mail {
+    #[serde(skip_deserializing)]
+    pub show: bool,
+    pub subject: String,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub smtp_server: String,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub smtp_starttls: bool,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub smtp_user: String,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub smtp_password: String,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub smtp_port: u16,
+    pub recipient: String,
+    #[ts(type = "string")]
+    pub mail_level: Level,
+    pub interval: i64,
+}
+
+impl Mail {
+    fn new(global: &models::GlobalSettings, config: &models::Configuration) -> Self {
+        Self {
+            show: !global.smtp_password.is_empty() && global.smtp_server != "mail.example.org",
+            subject: config.mail_subject.clone(),
+            smtp_server: global.smtp_server.clone(),
+            smtp_starttls: global.smtp_starttls,
+            smtp_user: global.smtp_user.clone(),
+            smtp_password: global.smtp_password.clone(),
+            smtp_port: global.smtp_port,
+            recipient: config.mail_recipient.clone(),
+            mail_level: string_to_log_level(config.mail_level.clone()),
+            interval: config.mail_interval,
+        }
+    }
+}
+
+impl Default for Mail {
+    fn default() -> Self {
+        Mail {
+            show: false,
+            subject: String::default(),
+            smtp_server: String::default(),
+            smtp_starttls: bool::default(),
+            smtp_user: String::default(),
+            smtp_password: String::default(),
+            smtp_port: 465,
+            recipient: String::default(),
+            mail_level: Level::Debug,
+            interval: i64::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Logging {
+    pub ffmpeg_level: String,
+    pub ingest_level: String,
+    pub detect_silence: bool,
+    pub ignore_lines: Vec<String>,
+}
+
+impl Logging {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            ffmpeg_level: config.logging_ffmpeg_level.clone(),
+            ingest_level: config.logging_ingest_level.clone(),
+            detect_silence: config.logging_detect_silence,
+            ignore_lines: config.logging_ignore.split(';').map(String::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Processing {
+    pub mode: ProcessMode,
+    pub audio_only: bool,
+    pub copy_audio: bool,
     pub copy_video: bool,
     pub width: i64,
     pub height: i64,
@@ -357,6 +994,19 @@ pub struct Processing {
     pub logo_scale: String,
     pub logo_opacity: f64,
     pub logo_position: String,
+    /// Logos for day-parts, one per line like `18:00:00-20:00:00 logos/prime.png`.
+    /// Other times use the default logo.
+    #[serde(default)]
+    pub logo_schedule: Vec<String>,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub logo_slots: Vec<LogoSlot>,
+    /// How often an animated logo plays, it holds the last frame afterwards. 0 loops endless.
+    #[serde(default)]
+    pub logo_loop: i64,
+    /// Frame rate for image sequences like `logo_%03d.png`, 0 takes the rate from the file.
+    #[serde(default)]
+    pub logo_fps: f64,
     pub audio_tracks: i32,
     #[serde(default = "default_track_index")]
     pub audio_track_index: i32,
@@ -368,11 +1018,99 @@ pub struct Processing {
     pub vtt_enable: bool,
     #[serde(default)]
     pub vtt_dummy: Option<String>,
+    /// Language codes for the output audio tracks, like: ["deu", "eng"]
+    #[serde(default)]
+    pub audio_languages: Vec<String>,
+    #[serde(default)]
+    pub subtitle_mode: SubtitleMode,
+    /// Crossfade length in seconds between consecutive clips, 0 for hard cuts.
+    #[serde(default)]
+    pub crossfade: f64,
+    /// Video transition from the xfade filter, like fade, wipeleft, dissolve.
+    #[serde(default)]
+    pub crossfade_transition: String,
+    /// Audio fade curve from the acrossfade filter, like tri, qsin, exp.
+    #[serde(default)]
+    pub crossfade_curve: String,
+    /// EBU R128 normalization, two-pass with the measured loudness from the cache.
+    #[serde(default)]
+    pub loudnorm: bool,
+    /// Target integrated loudness in LUFS.
+    #[serde(default = "default_loudnorm_i")]
+    pub loudnorm_i: f64,
+    /// Target loudness range in LU.
+    #[serde(default = "default_loudnorm_lra")]
+    pub loudnorm_lra: f64,
+    /// Maximum true peak in dBTP.
+    #[serde(default = "default_loudnorm_tp")]
+    pub loudnorm_tp: f64,
+    #[serde(default)]
+    pub hw_accel: HwAccel,
+    /// GPU index for NVENC, render node like `/dev/dri/renderD128` for QSV and VAAPI.
+    #[serde(default)]
+    pub hw_device: String,
+    #[serde(default)]
+    pub deinterlace: Deinterlace,
+    #[serde(default)]
+    pub aspect_mode: AspectMode,
+    #[serde(default)]
+    pub fps_mode: FpsMode,
+    /// Tone mapping of HDR clips (PQ and HLG) to the SDR output.
+    #[serde(default)]
+    pub tonemap: Tonemap,
+    /// Curve of the tone mapping, like hable, mobius or reinhard.
+    #[serde(default)]
+    pub tonemap_algorithm: String,
+    /// Peak luminance of the SDR target in nits.
+    #[serde(default)]
+    pub tonemap_peak: f64,
+    /// Color space of the output, clips in another matrix or range get converted.
+    #[serde(default)]
+    pub color_space: ColorSpace,
+    /// Program for decoding and encoding, GStreamer is experimental.
+    #[serde(default)]
+    pub backend: Backend,
     #[ts(skip)]
     #[serde(skip_serializing, skip_deserializing)]
     pub cmd: Option<Vec<String>>,
 }
 
+/// Resolved entry from the logo schedule.
+#[derive(Debug, Default, Clone)]
+pub struct LogoSlot {
+    pub start: f64,
+    pub end: f64,
+    pub path: String,
+}
+
+impl LogoSlot {
+    fn new(storage: &Path, line: &str) -> Option<Self> {
+        let (range, logo) = line.split_once(char::is_whitespace)?;
+        let (start, end) = range.split_once('-')?;
+
+        if !start.contains(':') || !end.contains(':') {
+            return None;
+        }
+
+        let (_, path) = clean_raw_abs_path(storage, logo.trim(), ABS_PATH_INDICATOR).ok()?;
+
+        Some(Self {
+            start: time_to_sec(start, &None),
+            end: time_to_sec(end, &None),
+            path: path.to_string_lossy().to_string(),
+        })
+    }
+
+    /// Time of day in seconds, ranges can go over midnight like 22:00-06:00.
+    pub fn contains(&self, time: f64) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
 impl Processing {
     fn new(config: &models::Configuration) -> Self {
         Self {
@@ -391,6 +1129,16 @@ impl Processing {
             logo_scale: config.processing_logo_scale.clone(),
             logo_opacity: config.processing_logo_opacity,
             logo_position: config.processing_logo_position.clone(),
+            logo_schedule: config
+                .processing_logo_schedule
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+            logo_slots: vec![],
+            logo_loop: config.processing_logo_loop,
+            logo_fps: config.processing_logo_fps,
             audio_tracks: config.processing_audio_tracks,
             audio_channels: config.processing_audio_channels,
             volume: config.processing_volume,
@@ -398,6 +1146,31 @@ impl Processing {
             override_filter: config.processing_override_filter,
             vtt_enable: config.processing_vtt_enable,
             vtt_dummy: config.processing_vtt_dummy.clone(),
+            audio_languages: config
+                .processing_audio_languages
+                .split(';')
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(String::from)
+                .collect(),
+            subtitle_mode: SubtitleMode::new(&config.processing_subtitle_mode),
+            crossfade: config.processing_crossfade,
+            crossfade_transition: config.processing_crossfade_transition.clone(),
+            crossfade_curve: config.processing_crossfade_curve.clone(),
+            loudnorm: config.processing_loudnorm,
+            loudnorm_i: config.processing_loudnorm_i,
+            loudnorm_lra: config.processing_loudnorm_lra,
+            loudnorm_tp: config.processing_loudnorm_tp,
+            hw_accel: HwAccel::new(&config.processing_hw_accel),
+            hw_device: config.processing_hw_device.clone(),
+            deinterlace: Deinterlace::new(&config.processing_deinterlace),
+            aspect_mode: AspectMode::new(&config.processing_aspect_mode),
+            fps_mode: FpsMode::new(&config.processing_fps_mode),
+            tonemap: Tonemap::new(&config.processing_tonemap),
+            tonemap_algorithm: config.processing_tonemap_algorithm.clone(),
+            tonemap_peak: config.processing_tonemap_peak,
+            color_space: ColorSpace::new(&config.processing_color_space),
+            backend: Backend::new(&config.processing_backend),
             cmd: None,
         }
     }
@@ -437,6 +1210,12 @@ pub struct Playlist {
     #[serde(skip_serializing, skip_deserializing)]
     pub length_sec: Option<f64>,
     pub infinit: bool,
+    /// Fill of gaps by their length, one policy per line, like `0-60 loop`.
+    #[serde(default)]
+    pub gap_policy: Vec<String>,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub gap_rules: Vec<GapRule>,
 }
 
 impl Playlist {
@@ -447,10 +1226,96 @@ impl Playlist {
             length: config.playlist_length.clone(),
             length_sec: None,
             infinit: config.playlist_infinit,
+            gap_policy: config
+                .playlist_gap_policy
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+            gap_rules: vec![],
+        }
+    }
+}
+
+/// Fill of a gap in the playlist.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GapPolicy {
+    /// Repeat the last clip of the playlist.
+    Loop,
+    /// Clip from the filler folder, which fits the gap best.
+    Fit,
+    /// Slate file, it loops over the gap.
+    Slate(String),
+    /// Clips from the library, which match the query, like `tag:evergreen`.
+    Query(String),
+}
+
+impl fmt::Display for GapPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Loop => write!(f, "loop"),
+            Self::Fit => write!(f, "fit"),
+            Self::Slate(path) => write!(f, "slate {path}"),
+            Self::Query(query) => write!(f, "query {query}"),
         }
     }
 }
 
+/// Resolved entry from the gap policy, for gaps from `min` up to `max` seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GapRule {
+    pub min: f64,
+    pub max: Option<f64>,
+    pub policy: GapPolicy,
+}
+
+impl GapRule {
+    /// Line like `0-60 loop`, `60-600 fit`, `600-3600 tag evergreen` or `3600- slate slates/offair.mp4`.
+    ///
+    /// Lengths are seconds or `HH:MM:SS`, an open end takes all longer gaps.
+    pub fn new(storage: &Path, line: &str) -> Option<Self> {
+        let mut parts = line.split_whitespace();
+        let (min, max) = parts.next()?.split_once('-')?;
+        let policy = parts.next()?.to_lowercase();
+        let value = parts.collect::<Vec<_>>().join(" ");
+        let seconds = |s: &str| {
+            if s.contains(':') {
+                Some(time_to_sec(s, &None))
+            } else {
+                s.parse::<f64>().ok()
+            }
+        };
+
+        let policy = match (policy.as_str(), value.is_empty()) {
+            ("loop", true) => GapPolicy::Loop,
+            ("fit", true) => GapPolicy::Fit,
+            ("slate", false) => {
+                let (_, path) = clean_raw_abs_path(storage, &value, ABS_PATH_INDICATOR).ok()?;
+
+                GapPolicy::Slate(path.to_string_lossy().to_string())
+            }
+            ("tag", false) => GapPolicy::Query(format!("tag:{value}")),
+            ("query", false) => GapPolicy::Query(value),
+            _ => return None,
+        };
+
+        Some(Self {
+            min: seconds(min)?,
+            max: if max.is_empty() {
+                None
+            } else {
+                Some(seconds(max)?)
+            },
+            policy,
+        })
+    }
+
+    pub fn contains(&self, gap: f64) -> bool {
+        gap >= self.min && self.max.is_none_or(|max| gap < max)
+    }
+}
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
 #[ts(export, export_to = "playout_config.d.ts")]
 pub struct Storage {
@@ -465,80 +1330,571 @@ pub struct Storage {
     #[serde(skip_serializing, skip_deserializing)]
     pub filler_path: PathBuf,
     pub extensions: Vec<String>,
+    pub max_depth: usize,
+    pub exclude: Vec<String>,
     pub shuffle: bool,
+    pub shuffle_history: usize,
+    /// Sources, which played in the last minutes, come after the others in the next shuffle.
+    pub shuffle_history_minutes: usize,
+    pub cache_path: PathBuf,
+    pub cache_size: usize,
+    pub weights: BTreeMap<String, f64>,
+    /// Minutes between two scans of the media library, 0 disables the scanner.
+    #[serde(default)]
+    pub library_interval: f64,
+    /// Library query for folder mode, like `tag:music AND duration<300`, instead of all files.
+    #[serde(default)]
+    pub query: String,
+    /// Generate thumbnails and preview sprites for the files of the media library.
+    #[serde(default)]
+    pub previews: bool,
     #[serde(skip_deserializing)]
     pub shared_storage: bool,
 }
 
-impl Storage {
-    fn new(config: &models::Configuration, path: PathBuf, shared_storage: bool) -> Self {
+impl Storage {
+    fn new(config: &models::Configuration, path: PathBuf, shared_storage: bool) -> Self {
+        Self {
+            path,
+            paths: vec![],
+            filler: config.storage_filler.clone(),
+            filler_path: PathBuf::from(config.storage_filler.clone()),
+            extensions: config
+                .storage_extensions
+                .split(';')
+                .map(String::from)
+                .collect(),
+            max_depth: config.storage_max_depth.max(0) as usize,
+            exclude: config
+                .storage_exclude
+                .split(';')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(String::from)
+                .collect(),
+            shuffle: config.storage_shuffle,
+            shuffle_history: config.storage_shuffle_history.max(0) as usize,
+            shuffle_history_minutes: config.storage_shuffle_history_minutes.max(0) as usize,
+            cache_path: PathBuf::from(config.storage_cache_path.clone()),
+            cache_size: config.storage_cache_size.max(0) as usize,
+            weights: config
+                .storage_weights
+                .split(';')
+                .filter_map(|w| {
+                    let (prefix, weight) = w.rsplit_once('=')?;
+
+                    Some((prefix.trim().to_string(), weight.trim().parse().ok()?))
+                })
+                .collect(),
+            library_interval: config.storage_library_interval,
+            query: config.storage_query.clone(),
+            previews: config.storage_previews,
+            shared_storage,
+        }
+    }
+
+    /// Check if a path, found under the given root, is excluded from scanning.
+    ///
+    /// The path is excluded when it is deeper than `max_depth` (0 means no limit),
+    /// or when the path, or one of its parent folders, matches an `exclude` glob.
+    pub fn is_excluded(&self, root: &Path, path: &Path) -> bool {
+        let relative = path
+            .strip_prefix(root)
+            .or_else(|_| path.strip_prefix(&self.path))
+            .unwrap_or(path);
+
+        if self.max_depth > 0 && relative.components().count() > self.max_depth {
+            return true;
+        }
+
+        self.exclude
+            .iter()
+            .filter_map(|p| Pattern::new(p.trim_start_matches('/')).ok())
+            .any(|pattern| {
+                relative
+                    .ancestors()
+                    .filter(|a| !a.as_os_str().is_empty())
+                    .any(|a| pattern.matches_path(a))
+            })
+    }
+
+    /// Get the weight for a media source, from the longest matching path prefix.
+    /// Paths without a matching prefix have a weight of 1.0.
+    pub fn weight(&self, source: &str) -> f64 {
+        let source = Path::new(source);
+        let source = source.strip_prefix(&self.path).unwrap_or(source);
+
+        // compare whole path components, so `news` is no prefix of `newsletter/`
+        self.weights
+            .iter()
+            .map(|(prefix, weight)| (Path::new(prefix.trim_matches('/')), weight))
+            .filter(|(prefix, _)| source.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.components().count())
+            .map_or(1.0, |(_, weight)| *weight)
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Text {
+    pub add_text: bool,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub node_pos: Option<usize>,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub zmq_stream_socket: Option<String>,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub zmq_server_socket: Option<String>,
+    #[serde(alias = "fontfile")]
+    pub font: String,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub font_path: String,
+    pub text_from_filename: bool,
+    pub style: String,
+    pub regex: String,
+    /// Show the current and the next title, updated with each clip over the zmq text channel.
+    #[serde(default)]
+    pub now_next: bool,
+    /// Template with `{{title}}`, `{{next_title}}` and `{{time}}`.
+    #[serde(default)]
+    pub now_next_format: String,
+    /// drawtext options, like `x=10:y=h-40:fontsize=24`.
+    #[serde(default)]
+    pub now_next_style: String,
+}
+
+impl Text {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            add_text: config.text_add,
+            node_pos: None,
+            zmq_stream_socket: None,
+            zmq_server_socket: None,
+            font: config.text_font.clone(),
+            font_path: config.text_font.clone(),
+            text_from_filename: config.text_from_filename,
+            style: config.text_style.clone(),
+            regex: config.text_regex.clone(),
+            now_next: config.text_now_next,
+            now_next_format: config.text_now_next_format.clone(),
+            now_next_style: config.text_now_next_style.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Task {
+    pub enable: bool,
+    pub path: PathBuf,
+}
+
+impl Task {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            enable: config.task_enable,
+            path: PathBuf::from(config.task_path.clone()),
+        }
+    }
+}
+
+/// Script for the scheduler hooks, it can change the next clip.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Script {
+    pub enable: bool,
+    pub path: PathBuf,
+    /// Longest run time of one hook in seconds, the clip stays unchanged after it.
+    pub timeout: f64,
+}
+
+impl Script {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            enable: config.script_enable,
+            path: PathBuf::from(config.script_path.clone()),
+            timeout: config.script_timeout,
+        }
+    }
+}
+
+/// Shell commands for playout events, for legacy station automation.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct EventHooks {
+    pub clip_start: String,
+    pub clip_end: String,
+    /// Command for errors, like a missing clip or a decoder restart.
+    pub error: String,
+    /// Longest run time of a command in seconds, then it gets killed.
+    pub timeout: f64,
+}
+
+impl EventHooks {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            clip_start: config.hooks_clip_start.clone(),
+            clip_end: config.hooks_clip_end.clone(),
+            error: config.hooks_error.clone(),
+            timeout: config.hooks_timeout,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Webhook {
+    /// Endpoints which get the events as JSON POST.
+    pub urls: Vec<String>,
+    /// Key for the HMAC-SHA256 signature, no signature when empty.
+    pub secret: String,
+    /// Events to send, all when empty.
+    pub events: Vec<String>,
+}
+
+impl Webhook {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            urls: config
+                .webhook_urls
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+            secret: config.webhook_secret.clone(),
+            events: config
+                .webhook_events
+                .split(';')
+                .map(str::trim)
+                .filter(|e| !e.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Mqtt {
+    pub enable: bool,
+    /// Broker address as host:port.
+    pub broker: String,
+    /// Base topic, the event name gets appended. Defaults to ffplayout/{channel id}.
+    pub topic: String,
+    pub username: String,
+    pub password: String,
+}
+
+impl Mqtt {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            enable: config.mqtt_enable,
+            broker: config.mqtt_broker.clone(),
+            topic: config.mqtt_topic.clone(),
+            username: config.mqtt_username.clone(),
+            password: config.mqtt_password.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Transition {
+    /// Stinger clip with alpha channel, which plays over the cut between two clips.
+    /// An empty path disables it.
+    pub stinger_path: String,
+    /// Seconds from the stinger start to the cut, usually the point where it covers the picture.
+    pub stinger_offset: f64,
+}
+
+impl Transition {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            stinger_path: config.transition_stinger_path.clone(),
+            stinger_offset: config.transition_stinger_offset,
+        }
+    }
+}
+
+/// Alarms for silence, black and frozen frames in the played clips.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Monitor {
+    /// Seconds of silence until the alarm, 0 disables the detection.
+    pub silence_duration: f64,
+    /// Noise tolerance in dB, quieter audio counts as silence.
+    pub silence_noise: f64,
+    /// Seconds of black frames until the alarm, 0 disables the detection.
+    pub black_duration: f64,
+    /// Luminance threshold from 0 to 1, darker pixels count as black.
+    pub black_level: f64,
+    /// Seconds of a frozen picture until the alarm and the skip to the next clip, 0 disables it.
+    #[serde(default)]
+    pub freeze_duration: f64,
+}
+
+impl Monitor {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            silence_duration: config.monitor_silence_duration,
+            silence_noise: config.monitor_silence_noise,
+            black_duration: config.monitor_black_duration,
+            black_level: config.monitor_black_level,
+            freeze_duration: config.monitor_freeze_duration,
+        }
+    }
+}
+
+/// Secondary source, for when the playlist is missing or the storage is not reachable.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Failover {
+    pub enable: bool,
+    /// Folder with clips, a single clip or image, or a live input URL.
+    pub source: String,
+}
+
+impl Failover {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            enable: config.failover_enable,
+            source: config.failover_source.clone(),
+        }
+    }
+}
+
+/// Main/backup synchronization of two engines.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct PlayoutSync {
+    /// Sync URL of the main engine, when set this engine runs as backup.
+    pub main_url: String,
+    /// Shared secret, the main engine serves its playhead only when it is set.
+    pub secret: String,
+}
+
+impl PlayoutSync {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            main_url: config.sync_main_url.clone(),
+            secret: config.sync_secret.clone(),
+        }
+    }
+}
+
+/// HTML graphics, rendered by a sidecar and laid over the program.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Graphics {
+    pub enable: bool,
+    /// URL or local path of the web page.
+    pub source: String,
+    /// Render command with the placeholders {source}, {output}, {width} and {height}.
+    /// Empty uses wkhtmltoimage.
+    pub command: String,
+    /// Seconds between two renderings.
+    pub interval: f64,
+}
+
+impl Graphics {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            enable: config.graphics_enable,
+            source: config.graphics_source.clone(),
+            command: config.graphics_command.clone(),
+            interval: config.graphics_interval,
+        }
+    }
+}
+
+/// News ticker from RSS or JSON feeds, scrolling at the bottom of the program.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Ticker {
+    pub enable: bool,
+    /// Feed URLs, one per line.
+    pub feeds: Vec<String>,
+    /// Seconds between two pulls of the feeds.
+    pub interval: f64,
+    /// Text between the headlines.
+    pub separator: String,
+    /// Day-parts with ticker, one per line like `06:00:00-09:00:00`. Empty shows it all day.
+    pub schedule: Vec<String>,
+    /// Scroll speed in pixels per second.
+    pub speed: f64,
+    /// drawtext options, like `y=h-50:fontsize=28:fontcolor=white`.
+    pub style: String,
+}
+
+impl Ticker {
+    fn new(config: &models::Configuration) -> Self {
+        let lines = |text: &str| {
+            text.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(ToString::to_string)
+                .collect()
+        };
+
+        Self {
+            enable: config.ticker_enable,
+            feeds: lines(&config.ticker_feeds),
+            interval: config.ticker_interval,
+            separator: config.ticker_separator.clone(),
+            schedule: lines(&config.ticker_schedule),
+            speed: config.ticker_speed,
+            style: config.ticker_style.clone(),
+        }
+    }
+}
+
+/// Digital clock over the program, in the time zone of the channel.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct Clock {
+    pub enable: bool,
+    /// strftime format, like `%H:%M:%S`.
+    pub format: String,
+    /// drawtext position, like `x=w-tw-40:y=40`.
+    pub position: String,
+    /// Font file, empty takes the font from the text section.
+    pub font: String,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub font_path: String,
+    /// drawtext options, like `fontsize=32:fontcolor=white`.
+    pub style: String,
+}
+
+impl Clock {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            enable: config.clock_enable,
+            format: config.clock_format.clone(),
+            position: config.clock_position.clone(),
+            font: config.clock_font.clone(),
+            font_path: config.clock_font.clone(),
+            style: config.clock_style.clone(),
+        }
+    }
+}
+
+/// Video for audio only clips, from artwork, audio visualizer and now playing text.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct VisualRadio {
+    pub enable: bool,
+    /// Image for clips without own artwork, empty gives a black background.
+    pub artwork: String,
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub artwork_path: String,
+    /// `spectrum`, `waveform` or `none`.
+    pub visualizer: String,
+    /// Show artist and title of the clip.
+    pub now_playing: bool,
+    /// drawtext options for the now playing text, like `x=40:y=40:fontsize=36`.
+    pub style: String,
+}
+
+impl VisualRadio {
+    fn new(config: &models::Configuration) -> Self {
+        Self {
+            enable: config.visual_radio_enable,
+            artwork: config.visual_radio_artwork.clone(),
+            artwork_path: config.visual_radio_artwork.clone(),
+            visualizer: config.visual_radio_visualizer.clone(),
+            now_playing: config.visual_radio_now_playing,
+            style: config.visual_radio_style.clone(),
+        }
+    }
+}
+
+/// Daily as-run log, with the scheduled and the actual start time of each clip.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
+#[ts(export, export_to = "playout_config.d.ts")]
+pub struct AsRun {
+    pub enable: bool,
+    /// Target directory for the logs, empty writes them to `asrun/<channel id>` in the log path.
+    pub path: PathBuf,
+    /// `xml` for a BXF like document or `csv`.
+    pub format: String,
+    /// Columns of the CSV log.
+    pub columns: Vec<String>,
+    /// Save a frame of the program output at each clip start, as proof-of-play.
+    pub thumbnails: bool,
+    /// Minutes between additional frames, 0 saves only at clip start.
+    pub thumbnail_interval: f64,
+}
+
+impl AsRun {
+    fn new(config: &models::Configuration) -> Self {
         Self {
-            path,
-            paths: vec![],
-            filler: config.storage_filler.clone(),
-            filler_path: PathBuf::from(config.storage_filler.clone()),
-            extensions: config
-                .storage_extensions
+            enable: config.asrun_enable,
+            path: PathBuf::from(config.asrun_path.clone()),
+            format: config.asrun_format.clone(),
+            columns: config
+                .asrun_columns
                 .split(';')
-                .map(String::from)
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
                 .collect(),
-            shuffle: config.storage_shuffle,
-            shared_storage,
+            thumbnails: config.asrun_thumbnails,
+            thumbnail_interval: config.asrun_thumbnail_interval,
         }
     }
 }
 
+/// Low bitrate HLS stream of the program output, for monitoring in the browser.
 #[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
 #[ts(export, export_to = "playout_config.d.ts")]
-pub struct Text {
-    pub add_text: bool,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub node_pos: Option<usize>,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub zmq_stream_socket: Option<String>,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub zmq_server_socket: Option<String>,
-    #[serde(alias = "fontfile")]
-    pub font: String,
-    #[ts(skip)]
-    #[serde(skip_serializing, skip_deserializing)]
-    pub font_path: String,
-    pub text_from_filename: bool,
-    pub style: String,
-    pub regex: String,
+pub struct PreviewStream {
+    pub enable: bool,
+    /// Video height, the width follows the aspect.
+    pub height: i64,
+    /// Video bitrate in kbit/s.
+    pub bitrate: i64,
 }
 
-impl Text {
+impl PreviewStream {
     fn new(config: &models::Configuration) -> Self {
         Self {
-            add_text: config.text_add,
-            node_pos: None,
-            zmq_stream_socket: None,
-            zmq_server_socket: None,
-            font: config.text_font.clone(),
-            font_path: config.text_font.clone(),
-            text_from_filename: config.text_from_filename,
-            style: config.text_style.clone(),
-            regex: config.text_regex.clone(),
+            enable: config.preview_enable,
+            height: config.preview_height,
+            bitrate: config.preview_bitrate,
         }
     }
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
 #[ts(export, export_to = "playout_config.d.ts")]
-pub struct Task {
+pub struct Recorder {
+    /// Start recording together with the playout.
     pub enable: bool,
+    /// Target directory for the recordings.
     pub path: PathBuf,
+    pub format: RecordFormat,
+    /// Segment length in seconds, aligned to the wall clock.
+    pub segment: i64,
+    /// Days to keep recordings, 0 keeps them forever.
+    pub retention: i64,
+    /// Encoding parameters for the recordings.
+    pub param: String,
 }
 
-impl Task {
+impl Recorder {
     fn new(config: &models::Configuration) -> Self {
         Self {
-            enable: config.task_enable,
-            path: PathBuf::from(config.task_path.clone()),
+            enable: config.recorder_enable,
+            path: PathBuf::from(config.recorder_path.clone()),
+            format: RecordFormat::new(&config.recorder_format),
+            segment: config.recorder_segment,
+            retention: config.recorder_retention,
+            param: config.recorder_param.clone(),
         }
     }
 }
@@ -548,6 +1904,120 @@ impl Task {
 pub struct Output {
     pub mode: OutputMode,
     pub output_param: String,
+    #[serde(default)]
+    pub srt_mode: SrtMode,
+    /// host:port, the host is the bind address in listener mode.
+    #[serde(default)]
+    pub srt_address: String,
+    #[serde(default)]
+    pub srt_passphrase: String,
+    /// Latency in milliseconds.
+    #[serde(default = "default_srt_latency")]
+    pub srt_latency: i64,
+    /// NDI sender name, default is ffplayout-<channel id>.
+    #[serde(default)]
+    pub ndi_name: String,
+    /// DeckLink device name, or its index.
+    #[serde(default)]
+    pub decklink_device: String,
+    /// SDI video mode, like 1080i50, 720p59.94, pal or ntsc.
+    #[serde(default)]
+    pub decklink_mode: String,
+    /// Additional outputs in stream and SRT mode, one ffmpeg output parameter string each,
+    /// like `-f flv rtmp://127.0.0.1/live/stream`. They get a copy of the encoded stream.
+    #[serde(default)]
+    pub legs: Vec<String>,
+    /// Icecast server as host:port.
+    #[serde(default)]
+    pub icecast_address: String,
+    /// Mount point, like `/radio.mp3`.
+    #[serde(default)]
+    pub icecast_mount: String,
+    #[serde(default)]
+    pub icecast_username: String,
+    #[serde(default)]
+    pub icecast_password: String,
+    /// Audio format for the mount: mp3, aac or opus.
+    #[serde(default)]
+    pub icecast_format: String,
+    /// Audio bitrate in kbit/s.
+    #[serde(default)]
+    pub icecast_bitrate: i64,
+    /// Output parameters for a generated video with cover and spectrum,
+    /// like `-c:v libx264 -c:a aac -f flv rtmp://127.0.0.1/live/radio`. Empty disables it.
+    #[serde(default)]
+    pub icecast_video: String,
+    /// Cover image for the generated video, a black background when empty.
+    #[serde(default)]
+    pub icecast_cover: String,
+    /// Push the title of each clip to the metadata endpoint of the mount.
+    #[serde(default)]
+    pub icecast_metadata: bool,
+    /// Metadata endpoint, like `http://127.0.0.1:8000/admin.cgi` for Shoutcast.
+    /// Empty uses `/admin/metadata` of the Icecast address.
+    #[serde(default)]
+    pub icecast_metadata_url: String,
+    /// Renditions of the HLS output, one per line as `<width>x<height> <video kbit/s> [<audio kbit/s>]`,
+    /// largest first. When set, the HLS output parameters are generated from it.
+    #[serde(default)]
+    pub hls_ladder: Vec<String>,
+    /// fMP4 segments instead of MPEG-TS, for the ladder.
+    #[serde(default)]
+    pub hls_fmp4: bool,
+    /// Segment length in seconds, for the ladder.
+    #[serde(default = "default_hls_time")]
+    pub hls_time: f64,
+    /// Low latency HLS for the ladder, with CMAF chunks and playlists which announce the
+    /// next segment.
+    #[serde(default)]
+    pub hls_low_latency: bool,
+    /// Length of a CMAF chunk in seconds, in low latency mode.
+    #[serde(default = "default_hls_part_time")]
+    pub hls_part_time: f64,
+    /// Add `EXT-X-PROGRAM-DATE-TIME` tags to the HLS playlists, the low latency playlists
+    /// have them always.
+    #[serde(default)]
+    pub hls_program_date_time: bool,
+    /// Encrypt the HLS segments with AES-128.
+    #[serde(default)]
+    pub hls_encryption: bool,
+    /// Seconds until a new key, 0 keeps one key until restart.
+    #[serde(default)]
+    pub hls_key_rotation: i64,
+    /// Key URL for the players, with `{channel}` and `{key}` placeholders. Empty uses the API.
+    #[serde(default)]
+    pub hls_key_url: String,
+    /// IV of the keys: `sequence` uses the segment number, `random` a random IV per key.
+    #[serde(default)]
+    pub hls_key_iv: String,
+    /// Push the stream output through the relay, so the playout keeps running
+    /// while the destination reconnects.
+    #[serde(default)]
+    pub reconnect: bool,
+    /// Failed connections in a row, until an output gives up. 0 tries forever.
+    #[serde(default)]
+    pub reconnect_attempts: i64,
+    /// Longest wait between two connections in seconds, the wait doubles from one second.
+    #[serde(default = "default_reconnect_delay")]
+    pub reconnect_delay: i64,
+    /// Seconds an output can be unreachable, until an event and error message go out. 0 disables it.
+    #[serde(default = "default_reconnect_alert")]
+    pub reconnect_alert: i64,
+    /// Image which the outputs get, while the encoder sends no data. Empty disables it.
+    #[serde(default)]
+    pub reconnect_slate: String,
+    /// WHIP endpoint of the WebRTC server.
+    #[serde(default)]
+    pub whip_url: String,
+    /// Bearer token for the WHIP endpoint.
+    #[serde(default)]
+    pub whip_token: String,
+    /// Video bitrate in kbit/s, also the max rate.
+    #[serde(default = "default_whip_bitrate")]
+    pub whip_bitrate: i64,
+    /// H.264 profile: baseline, main or high. WebRTC has no B-frames in any of them.
+    #[serde(default = "default_whip_profile")]
+    pub whip_profile: String,
     #[ts(skip)]
     #[serde(skip_serializing, skip_deserializing)]
     pub output_count: usize,
@@ -557,6 +2027,10 @@ pub struct Output {
     #[ts(skip)]
     #[serde(skip_serializing, skip_deserializing)]
     pub output_cmd: Option<Vec<String>>,
+    /// Muxer and destination of the stream output, when it goes through the relay.
+    #[ts(skip)]
+    #[serde(skip_serializing, skip_deserializing)]
+    pub push: Option<Vec<String>>,
 }
 
 impl Output {
@@ -564,11 +2038,488 @@ impl Output {
         Self {
             mode: OutputMode::new(&config.output_mode),
             output_param: config.output_param.clone(),
+            srt_mode: SrtMode::new(&config.output_srt_mode),
+            srt_address: config.output_srt_address.clone(),
+            srt_passphrase: config.output_srt_passphrase.clone(),
+            srt_latency: config.output_srt_latency,
+            ndi_name: config.output_ndi_name.clone(),
+            decklink_device: config.output_decklink_device.clone(),
+            decklink_mode: config.output_decklink_mode.clone(),
+            legs: config
+                .output_legs
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+            icecast_address: config.output_icecast_address.clone(),
+            icecast_mount: config.output_icecast_mount.clone(),
+            icecast_username: config.output_icecast_username.clone(),
+            icecast_password: config.output_icecast_password.clone(),
+            icecast_format: config.output_icecast_format.clone(),
+            icecast_bitrate: config.output_icecast_bitrate,
+            icecast_video: config.output_icecast_video.clone(),
+            icecast_cover: config.output_icecast_cover.clone(),
+            icecast_metadata: config.output_icecast_metadata,
+            icecast_metadata_url: config.output_icecast_metadata_url.clone(),
+            hls_ladder: config
+                .output_hls_ladder
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+            hls_fmp4: config.output_hls_fmp4,
+            hls_time: config.output_hls_time,
+            hls_low_latency: config.output_hls_low_latency,
+            hls_part_time: config.output_hls_part_time,
+            hls_program_date_time: config.output_hls_program_date_time,
+            hls_encryption: config.output_hls_encryption,
+            hls_key_rotation: config.output_hls_key_rotation,
+            hls_key_url: config.output_hls_key_url.clone(),
+            hls_key_iv: config.output_hls_key_iv.clone(),
+            reconnect: config.output_reconnect,
+            reconnect_attempts: config.output_reconnect_attempts,
+            reconnect_delay: config.output_reconnect_delay,
+            reconnect_alert: config.output_reconnect_alert,
+            reconnect_slate: config.output_reconnect_slate.clone(),
+            whip_url: config.output_whip_url.clone(),
+            whip_token: config.output_whip_token.clone(),
+            whip_bitrate: config.output_whip_bitrate,
+            whip_profile: config.output_whip_profile.clone(),
+            push: None,
             output_count: 0,
             output_filter: None,
             output_cmd: None,
         }
     }
+
+    /// Output parameters for the NDI sender, ffmpeg needs to be built with libndi_newtek.
+    fn ndi_cmd(&self, channel_id: i32) -> Vec<String> {
+        let name = if self.ndi_name.is_empty() {
+            format!("ffplayout-{channel_id}")
+        } else {
+            self.ndi_name.clone()
+        };
+
+        vec_strings![
+            "-pix_fmt",
+            "uyvy422",
+            "-c:a",
+            "pcm_s16le",
+            "-f",
+            "libndi_newtek",
+            name
+        ]
+    }
+
+    /// Encoder parameters for Icecast, the relay sends the audio to the mount.
+    fn icecast_cmd(&self) -> Vec<String> {
+        let codec = match self.icecast_format.as_str() {
+            "aac" => "aac",
+            "opus" => "libopus",
+            _ => "libmp3lame",
+        };
+
+        vec_strings![
+            "-c:a",
+            codec,
+            "-b:a",
+            format!("{}k", self.icecast_bitrate.max(8)),
+            "-ar",
+            "48000",
+            "-f",
+            "mpegts",
+            "pipe:1"
+        ]
+    }
+
+    /// Encoder parameters for WHIP: H.264 without B-frames and Opus, as WebRTC needs them.
+    /// Keyframes every second, so that viewers join fast.
+    pub fn whip_cmd(&self, fps: f64) -> Vec<String> {
+        let gop = fps.round().max(1.0);
+        let bitrate = self.whip_bitrate.max(100);
+        let profile = match self.whip_profile.as_str() {
+            "main" | "high" => self.whip_profile.as_str(),
+            _ => "baseline",
+        };
+
+        vec_strings![
+            "-c:v",
+            "libx264",
+            "-profile:v",
+            profile,
+            "-preset",
+            "veryfast",
+            "-tune",
+            "zerolatency",
+            "-bf",
+            "0",
+            "-g",
+            gop,
+            "-keyint_min",
+            gop,
+            "-sc_threshold",
+            "0",
+            "-b:v",
+            format!("{bitrate}k"),
+            "-maxrate",
+            format!("{bitrate}k"),
+            "-bufsize",
+            format!("{bitrate}k"),
+            "-pix_fmt",
+            "yuv420p",
+            "-c:a",
+            "libopus",
+            "-b:a",
+            "128k",
+            "-ar",
+            "48000",
+            "-ac",
+            "2",
+            "-f",
+            "mpegts",
+            "pipe:1"
+        ]
+    }
+
+    /// Output parameters for the DeckLink card, the device index gets resolved at start.
+    fn decklink_cmd(&self) -> Vec<String> {
+        let mut cmd = vec_strings!["-c:a", "pcm_s16le", "-ar", "48000"];
+
+        if let Some(order) = VideoMode::new(&self.decklink_mode).and_then(|m| m.field_order) {
+            cmd.append(&mut vec_strings!["-field_order", order]);
+        }
+
+        cmd.append(&mut vec_strings!["-f", "decklink", self.decklink_device]);
+
+        cmd
+    }
+
+    /// Output parameters for the ladder, with a master playlist and a variant stream
+    /// per rendition. The keyframes of all renditions are aligned to the segment length.
+    pub fn ladder_cmd(&self, fps: f64) -> Option<Vec<String>> {
+        let renditions: Vec<HlsRendition> = self
+            .hls_ladder
+            .iter()
+            .filter_map(|l| HlsRendition::new(l))
+            .collect();
+
+        if renditions.is_empty() {
+            return None;
+        }
+
+        let count = renditions.len();
+        let hls_time = if self.hls_time > 0.0 {
+            self.hls_time
+        } else {
+            6.0
+        };
+        let gop = (fps * hls_time).round().max(1.0);
+        let v_links: String = (0..count).map(|i| format!("[v{i}]")).collect();
+        let a_links: String = (0..count).map(|i| format!("[a{i}]")).collect();
+        let mut filter = format!("[0:v]split={count}{v_links}");
+
+        for (i, r) in renditions.iter().enumerate() {
+            filter.push_str(&format!(";[v{i}]scale={}:{}[v{i}_out]", r.width, r.height));
+        }
+
+        filter.push_str(&format!(";[0:a:0]asplit={count}{a_links}"));
+
+        let mut cmd = vec_strings!["-filter_complex", filter];
+
+        for (i, r) in renditions.iter().enumerate() {
+            cmd.append(&mut vec_strings![
+                "-map",
+                format!("[v{i}_out]"),
+                "-map",
+                format!("[a{i}]"),
+                format!("-c:v:{i}"),
+                "libx264",
+                format!("-b:v:{i}"),
+                format!("{}k", r.video_bitrate),
+                format!("-maxrate:v:{i}"),
+                format!("{}k", r.video_bitrate),
+                format!("-bufsize:v:{i}"),
+                format!("{}k", r.video_bitrate * 2),
+                format!("-c:a:{i}"),
+                "aac",
+                format!("-b:a:{i}"),
+                format!("{}k", r.audio_bitrate)
+            ]);
+        }
+
+        cmd.append(&mut vec_strings![
+            "-preset",
+            "faster",
+            "-profile:v",
+            "main",
+            "-pix_fmt",
+            "yuv420p",
+            "-g",
+            gop,
+            "-keyint_min",
+            gop,
+            "-sc_threshold",
+            "0",
+            "-flags",
+            "+cgop",
+            "-ar",
+            "48000"
+        ]);
+
+        if self.mode == OutputMode::Dash || self.hls_low_latency {
+            cmd.append(&mut self.dash_cmd(hls_time));
+
+            return Some(cmd);
+        }
+
+        let (segment_type, extension) = if self.hls_fmp4 {
+            ("fmp4", "m4s")
+        } else {
+            ("mpegts", "ts")
+        };
+        let var_stream_map = (0..count)
+            .map(|i| format!("v:{i},a:{i}"))
+            .collect::<Vec<String>>()
+            .join(" ");
+        cmd.append(&mut vec_strings![
+            "-f",
+            "hls",
+            "-hls_time",
+            hls_time,
+            "-hls_list_size",
+            "600",
+            "-hls_flags",
+            "append_list+delete_segments+omit_endlist+independent_segments",
+            "-hls_segment_type",
+            segment_type
+        ]);
+
+        if self.hls_fmp4 {
+            cmd.append(&mut vec_strings![
+                "-hls_fmp4_init_filename",
+                "stream_%v_init.mp4"
+            ]);
+        }
+
+        cmd.append(&mut vec_strings![
+            "-hls_segment_filename",
+            format!("live/stream_%v-%d.{extension}"),
+            "-master_pl_name",
+            "master.m3u8",
+            "-var_stream_map",
+            var_stream_map,
+            "live/stream_%v.m3u8"
+        ]);
+
+        Some(cmd)
+    }
+
+    /// Muxer parameters for DASH, with HLS playlists for the same segments.
+    ///
+    /// In low latency mode the segments are written in CMAF chunks while they are encoding,
+    /// and the playlists announce the next segment in advance.
+    fn dash_cmd(&self, seg_duration: f64) -> Vec<String> {
+        let mut cmd = vec_strings![
+            "-f",
+            "dash",
+            "-dash_segment_type",
+            "mp4",
+            "-seg_duration",
+            seg_duration,
+            "-window_size",
+            "10",
+            "-extra_window_size",
+            "5",
+            "-use_template",
+            "1",
+            "-hls_playlist",
+            "1",
+            "-hls_master_name",
+            "master.m3u8"
+        ];
+
+        if self.hls_low_latency {
+            let part_time = if self.hls_part_time > 0.0 {
+                self.hls_part_time.min(seg_duration)
+            } else {
+                0.5
+            };
+
+            cmd.append(&mut vec_strings![
+                "-use_timeline",
+                "0",
+                "-ldash",
+                "1",
+                "-streaming",
+                "1",
+                "-lhls",
+                "1",
+                "-frag_type",
+                "duration",
+                "-frag_duration",
+                part_time,
+                "-write_prft",
+                "1"
+            ]);
+        } else {
+            cmd.append(&mut vec_strings!["-use_timeline", "1"]);
+        }
+
+        cmd.append(&mut vec_strings![
+            "-adaptation_sets",
+            "id=0,streams=v id=1,streams=a",
+            "-init_seg_name",
+            "stream_$RepresentationID$_init.m4s",
+            "-media_seg_name",
+            "stream_$RepresentationID$-$Number$.m4s",
+            "live/stream.mpd"
+        ]);
+
+        cmd
+    }
+}
+
+/// Take muxer and destination from the end of the stream parameters, to push them
+/// through the relay. Only single outputs can be pushed.
+pub fn split_push(cmd: &mut Vec<String>) -> Option<Vec<String>> {
+    let outputs = cmd
+        .iter()
+        .enumerate()
+        .filter(|(i, p)| *i > 0 && !p.starts_with('-') && !cmd[i - 1].starts_with('-'))
+        .count();
+
+    if outputs != 1 || cmd.last()?.starts_with('-') {
+        return None;
+    }
+
+    let url = cmd.len() - 1;
+    let start = if url >= 2 && cmd[url - 2] == "-f" {
+        url - 2
+    } else {
+        url
+    };
+
+    Some(cmd.split_off(start))
+}
+
+/// Add a flag to `-hls_flags`, for the parameters of the HLS muxer.
+pub fn add_hls_flag(cmd: &mut Vec<String>, flag: &str) {
+    if !cmd.windows(2).any(|w| w[0] == "-f" && w[1] == "hls") {
+        return;
+    }
+
+    if let Some(i) = cmd.iter().position(|p| p == "-hls_flags") {
+        if let Some(flags) = cmd.get_mut(i + 1) {
+            if !flags.contains(flag) {
+                flags.push_str(&format!("+{flag}"));
+            }
+        }
+    } else if !cmd.is_empty() {
+        let pos = cmd.len() - 1;
+        cmd.splice(pos..pos, vec_strings!["-hls_flags", flag]);
+    }
+}
+
+/// Add an option before the playlist, for the parameters of the HLS muxer.
+fn add_hls_option(cmd: &mut Vec<String>, key: &str, value: &str) {
+    if !cmd.windows(2).any(|w| w[0] == "-f" && w[1] == "hls") || cmd.contains(&key.to_string()) {
+        return;
+    }
+
+    let pos = cmd.len() - 1;
+    cmd.splice(pos..pos, vec_strings![key, value]);
+}
+
+/// One rendition of the HLS ladder, bitrates in kbit/s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlsRendition {
+    pub width: i64,
+    pub height: i64,
+    pub video_bitrate: i64,
+    pub audio_bitrate: i64,
+}
+
+impl HlsRendition {
+    /// Parse a line like `1280x720 3000k 128k`, the audio bitrate is optional.
+    pub fn new(line: &str) -> Option<Self> {
+        let bitrate = |s: &str| s.trim_end_matches(['k', 'K']).parse::<i64>().ok();
+        let mut parts = line.split_whitespace();
+        let (width, height) = parts.next()?.split_once('x')?;
+        let width = width.parse::<i64>().ok()?;
+        let height = height.parse::<i64>().ok()?;
+        let video_bitrate = bitrate(parts.next()?)?;
+        let audio_bitrate = match parts.next() {
+            Some(b) => bitrate(b)?,
+            None => 128,
+        };
+
+        if width <= 0 || height <= 0 || video_bitrate <= 0 || audio_bitrate <= 0 {
+            return None;
+        }
+
+        // scaled sizes must be even for yuv420p
+        Some(Self {
+            width: width / 2 * 2,
+            height: height / 2 * 2,
+            video_bitrate,
+            audio_bitrate,
+        })
+    }
+}
+
+/// Video format of a SDI video mode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoMode {
+    pub width: i64,
+    pub height: i64,
+    /// Frames per second, for interlaced modes the half field rate.
+    pub fps: f64,
+    /// Field order for interlaced modes, `tt` or `bb`.
+    pub field_order: Option<String>,
+}
+
+impl VideoMode {
+    pub fn new(mode: &str) -> Option<Self> {
+        let mode = match mode.to_lowercase().as_str() {
+            "pal" => "576i50".to_string(),
+            "ntsc" => "486i59.94".to_string(),
+            m => m.to_string(),
+        };
+        let re = Regex::new(r"^(\d{3,4})([ip])(\d+(?:\.\d+)?)$").unwrap();
+        let caps = re.captures(&mode)?;
+        let height = caps[1].parse::<i64>().ok()?;
+        let width = match height {
+            486 | 576 => 720,
+            720 => 1280,
+            1080 => 1920,
+            2160 => 3840,
+            _ => return None,
+        };
+        let mut rate = caps[3].parse::<f64>().ok()?;
+
+        // NTSC rates need the exact value, to match the card modes
+        if rate.fract() != 0.0 {
+            rate = rate.ceil() * 1000.0 / 1001.0;
+        }
+
+        if &caps[2] == "i" {
+            Some(Self {
+                width,
+                height,
+                fps: rate / 2.0,
+                field_order: Some(if height < 720 { "bb" } else { "tt" }.to_string()),
+            })
+        } else {
+            Some(Self {
+                width,
+                height,
+                fps: rate,
+                field_order: None,
+            })
+        }
+    }
 }
 
 pub fn string_to_log_level(l: String) -> Level {
@@ -595,6 +2546,12 @@ pub fn string_to_output_mode(l: String) -> OutputMode {
         "hls" => OutputMode::HLS,
         "null" => OutputMode::Null,
         "stream" => OutputMode::Stream,
+        "srt" => OutputMode::Srt,
+        "ndi" => OutputMode::Ndi,
+        "decklink" => OutputMode::Decklink,
+        "icecast" => OutputMode::Icecast,
+        "dash" => OutputMode::Dash,
+        "whip" => OutputMode::Whip,
         _ => OutputMode::HLS,
     }
 }
@@ -603,6 +2560,46 @@ fn default_track_index() -> i32 {
     -1
 }
 
+fn default_loudnorm_i() -> f64 {
+    -23.0
+}
+
+fn default_loudnorm_lra() -> f64 {
+    7.0
+}
+
+fn default_loudnorm_tp() -> f64 {
+    -1.0
+}
+
+fn default_srt_latency() -> i64 {
+    120
+}
+
+fn default_hls_time() -> f64 {
+    6.0
+}
+
+fn default_hls_part_time() -> f64 {
+    0.5
+}
+
+fn default_reconnect_delay() -> i64 {
+    30
+}
+
+fn default_reconnect_alert() -> i64 {
+    60
+}
+
+fn default_whip_bitrate() -> i64 {
+    2500
+}
+
+fn default_whip_profile() -> String {
+    "baseline".to_string()
+}
+
 impl PlayoutConfig {
     pub async fn new(pool: &Pool<Sqlite>, channel_id: i32) -> Result<Self, ServiceError> {
         let global = handles::select_global(pool).await?;
@@ -611,7 +2608,7 @@ impl PlayoutConfig {
         let adv_config = handles::select_advanced_configuration(pool, channel_id).await?;
 
         let channel = Channel::new(&global, channel);
-        let advanced = AdvancedConfig::new(adv_config);
+        let mut advanced = AdvancedConfig::new(adv_config);
         let general = General::new(&config);
         let mail = Mail::new(&global, &config);
         let logging = Logging::new(&config);
@@ -620,6 +2617,21 @@ impl PlayoutConfig {
         let mut playlist = Playlist::new(&config);
         let mut text = Text::new(&config);
         let task = Task::new(&config);
+        let script = Script::new(&config);
+        let recorder = Recorder::new(&config);
+        let webhook = Webhook::new(&config);
+        let hooks = EventHooks::new(&config);
+        let mqtt = Mqtt::new(&config);
+        let transition = Transition::new(&config);
+        let monitor = Monitor::new(&config);
+        let failover = Failover::new(&config);
+        let sync = PlayoutSync::new(&config);
+        let graphics = Graphics::new(&config);
+        let ticker = Ticker::new(&config);
+        let mut clock = Clock::new(&config);
+        let mut visual_radio = VisualRadio::new(&config);
+        let asrun = AsRun::new(&config);
+        let preview = PreviewStream::new(&config);
         let mut output = Output::new(&config);
         let mut storage = Storage::new(&config, channel.storage.clone(), channel.shared);
 
@@ -669,7 +2681,22 @@ impl PlayoutConfig {
         let (logo, logo_path) =
             clean_raw_abs_path(&channel.storage, &processing.logo, ABS_PATH_INDICATOR)?;
 
-        if processing.add_logo && !logo_path.is_file() {
+        processing.logo_slots = processing
+            .logo_schedule
+            .iter()
+            .filter_map(|line| LogoSlot::new(&channel.storage, line))
+            .collect();
+
+        playlist.gap_rules = playlist
+            .gap_policy
+            .iter()
+            .filter_map(|line| GapRule::new(&channel.storage, line))
+            .collect();
+
+        if processing.add_logo
+            && !is_logo(&logo_path.to_string_lossy())
+            && processing.logo_slots.is_empty()
+        {
             processing.add_logo = false;
         }
 
@@ -680,6 +2707,24 @@ impl PlayoutConfig {
             processing.audio_tracks = 1;
         }
 
+        if output.mode == OutputMode::Icecast {
+            // radio channel, without video pipeline
+            processing.audio_only = true;
+        }
+
+        if output.mode == OutputMode::Decklink {
+            if let Some(mode) = VideoMode::new(&output.decklink_mode) {
+                // SDI needs exact the format from the video mode
+                processing.width = mode.width;
+                processing.height = mode.height;
+                processing.fps = mode.fps;
+            }
+        }
+
+        if !processing.audio_only && !processing.copy_video {
+            hw_accel::apply(&processing, &mut advanced);
+        }
+
         let mut process_cmd = vec_strings![];
 
         if processing.audio_only {
@@ -688,6 +2733,10 @@ impl PlayoutConfig {
             process_cmd.append(&mut vec_strings!["-c:v", "copy"]);
         } else if let Some(decoder_cmd) = &advanced.decoder.output_cmd {
             process_cmd.append(&mut decoder_cmd.clone());
+        } else if let Some(mut hw_cmd) =
+            hw_accel::intermediate_cmd(&processing.hw_accel, &processing)
+        {
+            process_cmd.append(&mut hw_cmd);
         } else {
             let bitrate = format!("{}k", processing.width * processing.height / 16);
             let buff_size = format!("{}k", (processing.width * processing.height / 16) / 2);
@@ -714,12 +2763,18 @@ impl PlayoutConfig {
             ]);
         }
 
+        if !processing.audio_only && !processing.copy_video && advanced.decoder.output_cmd.is_none()
+        {
+            process_cmd.append(&mut processing.color_space.encoder_tags(processing.height));
+        }
+
         if processing.copy_audio {
             process_cmd.append(&mut vec_strings!["-c:a", "copy"]);
         } else if advanced.decoder.output_cmd.is_none() {
             process_cmd.append(&mut pre_audio_codec(
                 &processing.custom_filter,
                 &ingest.custom_filter,
+                processing.loudnorm,
                 processing.audio_channels,
             ));
         }
@@ -733,9 +2788,56 @@ impl PlayoutConfig {
         output.output_count = 1;
         output.output_filter = None;
 
+        let mut output_param = match output.ladder_cmd(processing.fps) {
+            Some(cmd) if output.mode.is_segmented() && !processing.audio_only => Some(cmd),
+            _ => split(output.output_param.as_str()),
+        };
+
+        if output.mode == OutputMode::HLS {
+            if let Some(cmd) = output_param.as_mut() {
+                // the time comes from the system clock at start of ffmpeg,
+                // which is also the clock of the scheduler
+                if output.hls_program_date_time {
+                    add_hls_flag(cmd, "program_date_time");
+                }
+
+                if output.hls_encryption {
+                    add_hls_option(
+                        cmd,
+                        "-hls_key_info_file",
+                        &hls_key::info_file(&channel.public).to_string_lossy(),
+                    );
+
+                    if output.hls_key_rotation > 0 {
+                        add_hls_flag(cmd, "periodic_rekey");
+                    }
+                }
+            }
+        }
+
         if output.mode == OutputMode::Null {
             output.output_cmd = Some(vec_strings!["-f", "null", "-"]);
-        } else if let Some(mut cmd) = split(output.output_param.as_str()) {
+        } else if output.mode == OutputMode::Ndi {
+            output.output_cmd = Some(output.ndi_cmd(channel_id));
+        } else if output.mode == OutputMode::Decklink {
+            output.output_cmd = Some(output.decklink_cmd());
+        } else if output.mode == OutputMode::Icecast {
+            output.output_cmd = Some(output.icecast_cmd());
+        } else if output.mode == OutputMode::Whip {
+            output.output_cmd = Some(output.whip_cmd(processing.fps));
+        } else if let Some(mut cmd) = output_param {
+            if output.mode == OutputMode::Stream && output.reconnect {
+                output.push = split_push(&mut cmd);
+            }
+
+            if output.mode == OutputMode::Srt
+                || (output.mode == OutputMode::Stream
+                    && (!output.legs.is_empty() || output.push.is_some()))
+            {
+                // the relay reads the encoded stream from stdout
+                cmd.append(&mut vec_strings!["-f", "mpegts", "pipe:1"]);
+            }
+
             // get output count according to the var_stream_map value, or by counting output parameters
             if let Some(i) = cmd.clone().iter().position(|m| m == "-var_stream_map") {
                 output.output_count = cmd[i + 1].split_whitespace().count();
@@ -758,7 +2860,11 @@ impl PlayoutConfig {
             let re_m3 = Regex::new(r"\](\S+?\.m3u8)").unwrap();
 
             for item in &mut cmd {
-                if item.ends_with(".ts") || (item.ends_with(".m3u8") && item != "master.m3u8") {
+                if item.ends_with(".ts")
+                    || item.ends_with(".m4s")
+                    || item.ends_with(".mpd")
+                    || (item.ends_with(".m3u8") && item != "master.m3u8")
+                {
                     if is_tee_muxer {
                         // Processes the `item` string to replace `.ts` and `.m3u8` filenames with their absolute paths.
                         // Ensures that the corresponding directories exist.
@@ -811,6 +2917,12 @@ impl PlayoutConfig {
             output.output_cmd = Some(cmd);
         }
 
+        if !processing.audio_only && !processing.copy_video {
+            output.output_cmd = output
+                .output_cmd
+                .map(|cmd| hw_accel::encoder_cmd(&processing.hw_accel, &cmd));
+        }
+
         // when text overlay without text_from_filename is on, turn also the RPC server on,
         // to get text messages from it
         if text.add_text && !text.text_from_filename {
@@ -829,6 +2941,22 @@ impl PlayoutConfig {
         text.font = font;
         text.font_path = font_path.to_string_lossy().to_string();
 
+        if clock.font.is_empty() {
+            clock.font_path.clone_from(&text.font_path);
+        } else {
+            let (font, font_path) =
+                clean_raw_abs_path(&channel.storage, &clock.font, ABS_PATH_INDICATOR)?;
+            clock.font = font;
+            clock.font_path = font_path.to_string_lossy().to_string();
+        }
+
+        if !visual_radio.artwork.is_empty() {
+            let (artwork, artwork_path) =
+                clean_raw_abs_path(&channel.storage, &visual_radio.artwork, ABS_PATH_INDICATOR)?;
+            visual_radio.artwork = artwork;
+            visual_radio.artwork_path = artwork_path.to_string_lossy().to_string();
+        }
+
         Ok(Self {
             channel,
             advanced,
@@ -841,6 +2969,21 @@ impl PlayoutConfig {
             storage,
             text,
             task,
+            script,
+            recorder,
+            webhook,
+            hooks,
+            mqtt,
+            transition,
+            monitor,
+            failover,
+            sync,
+            graphics,
+            ticker,
+            clock,
+            visual_radio,
+            asrun,
+            preview,
             output,
         })
     }
@@ -869,12 +3012,118 @@ impl PlayoutConfig {
 
         Ok(())
     }
+
+    /// Fields, which differ in the other config, like `processing.volume`.
+    pub fn changed_fields(&self, other: &Self) -> Vec<String> {
+        let (Ok(Value::Object(old)), Ok(Value::Object(new))) =
+            (serde_json::to_value(self), serde_json::to_value(other))
+        else {
+            return vec![];
+        };
+        let mut fields = vec![];
+
+        for (section, old_values) in &old {
+            let new_values = &new[section];
+
+            match (old_values, new_values) {
+                (Value::Object(old_values), Value::Object(new_values)) => {
+                    for (key, value) in old_values {
+                        if new_values.get(key) != Some(value) {
+                            fields.push(format!("{section}.{key}"));
+                        }
+                    }
+                }
+                (a, b) if a != b => fields.push(section.clone()),
+                _ => {}
+            }
+        }
+
+        fields
+    }
+
+    /// Config with the values of a partial config, like `{"processing": {"volume": 0.8}}`.
+    ///
+    /// Like a config from the API, the result has no runtime values and needs to be stored
+    /// and loaded again.
+    pub fn patch(&self, patch: &Value) -> Result<Self, serde_json::Error> {
+        fn merge(target: &mut Value, patch: &Value) {
+            match (target, patch) {
+                (Value::Object(target), Value::Object(patch)) => {
+                    for (key, value) in patch {
+                        merge(target.entry(key.clone()).or_insert(Value::Null), value);
+                    }
+                }
+                (target, patch) => *target = patch.clone(),
+            }
+        }
+
+        let mut value = serde_json::to_value(self)?;
+        merge(&mut value, patch);
+
+        let mut config: Self = serde_json::from_value(value)?;
+        config.channel = self.channel.clone();
+        config.advanced = self.advanced.clone();
+
+        Ok(config)
+    }
+
+    /// Take the live fields of the new config, together with the paths which belong to them.
+    ///
+    /// Runtime values, like the zmq sockets of the running channel, stay.
+    pub fn apply_live(&mut self, new: &Self) {
+        let processing = &mut self.processing;
+
+        processing.add_logo = new.processing.add_logo;
+        processing.logo.clone_from(&new.processing.logo);
+        processing.logo_path.clone_from(&new.processing.logo_path);
+        processing.logo_scale.clone_from(&new.processing.logo_scale);
+        processing.logo_opacity = new.processing.logo_opacity;
+        processing
+            .logo_position
+            .clone_from(&new.processing.logo_position);
+        processing
+            .logo_schedule
+            .clone_from(&new.processing.logo_schedule);
+        processing.logo_slots.clone_from(&new.processing.logo_slots);
+        processing.logo_loop = new.processing.logo_loop;
+        processing.logo_fps = new.processing.logo_fps;
+        processing.volume = new.processing.volume;
+
+        self.playlist
+            .gap_policy
+            .clone_from(&new.playlist.gap_policy);
+        self.playlist.gap_rules.clone_from(&new.playlist.gap_rules);
+
+        self.storage.filler.clone_from(&new.storage.filler);
+        self.storage
+            .filler_path
+            .clone_from(&new.storage.filler_path);
+
+        self.text.font.clone_from(&new.text.font);
+        self.text.font_path.clone_from(&new.text.font_path);
+        self.text.style.clone_from(&new.text.style);
+        self.text.regex.clone_from(&new.text.regex);
+        self.text
+            .now_next_format
+            .clone_from(&new.text.now_next_format);
+        self.text
+            .now_next_style
+            .clone_from(&new.text.now_next_style);
+
+        self.mail = new.mail.clone();
+        self.hooks = new.hooks.clone();
+    }
 }
 
-/// When custom_filter contains loudnorm filter use a different audio encoder,
+/// When custom_filter contains loudnorm filter, or normalization is on, use a different audio encoder,
 /// s302m has higher quality, but is experimental
 /// and works not well together with the loudnorm filter.
-fn pre_audio_codec(proc_filter: &str, ingest_filter: &str, channel_count: u8) -> Vec<String> {
+fn pre_audio_codec(
+    proc_filter: &str,
+    ingest_filter: &str,
+    loudnorm: bool,
+    channel_count: u8,
+) -> Vec<String> {
     let mut codec = vec_strings![
         "-c:a",
         "s302m",
@@ -888,7 +3137,7 @@ fn pre_audio_codec(proc_filter: &str, ingest_filter: &str, channel_count: u8) ->
         channel_count
     ];
 
-    if proc_filter.contains("loudnorm") || ingest_filter.contains("loudnorm") {
+    if loudnorm || proc_filter.contains("loudnorm") || ingest_filter.contains("loudnorm") {
         codec = vec_strings![
             "-c:a",
             "mp2",
@@ -958,6 +3207,36 @@ pub async fn get_config(
             config.output.output_count = 1;
             config.output.output_filter = None;
             config.output.output_cmd = Some(vec_strings!["-f", "null", "-"]);
+        } else if config.output.mode == OutputMode::Ndi {
+            config.output.output_count = 1;
+            config.output.output_filter = None;
+            config.output.output_cmd = Some(config.output.ndi_cmd(config.general.channel_id));
+        } else if config.output.mode == OutputMode::Decklink {
+            config.output.output_count = 1;
+            config.output.output_filter = None;
+            config.output.output_cmd = Some(config.output.decklink_cmd());
+        } else if config.output.mode == OutputMode::Whip {
+            config.output.output_count = 1;
+            config.output.output_filter = None;
+            config.output.output_cmd = Some(config.output.whip_cmd(config.processing.fps));
+        } else if config.output.mode == OutputMode::Srt
+            || (config.output.mode == OutputMode::Stream
+                && (!config.output.legs.is_empty() || config.output.reconnect))
+        {
+            if let Some(cmd) = config.output.output_cmd.as_mut() {
+                if !cmd.contains(&"pipe:1".to_string()) {
+                    if config.output.mode == OutputMode::Stream && config.output.reconnect {
+                        config.output.push = split_push(cmd);
+                    }
+
+                    if config.output.mode == OutputMode::Srt
+                        || !config.output.legs.is_empty()
+                        || config.output.push.is_some()
+                    {
+                        cmd.append(&mut vec_strings!["-f", "mpegts", "pipe:1"]);
+                    }
+                }
+            }
         }
     }
 
@@ -0,0 +1,482 @@
+/*
+Validation of the playout config.
+
+The API checks a new config before it gets stored, so a channel never runs with a config
+which can not work: numbers out of range, files which not exist in the channel storage and
+encoders, muxers, filters or protocols which the ffmpeg build has not. Each problem comes
+with the field of the config. The channels check their config again on start.
+*/
+
+use std::{fmt, path::Path};
+
+use serde::Serialize;
+use shlex::split;
+
+use crate::file::{
+    clean_raw_abs_path, select_storage_type, utils::ABS_PATH_INDICATOR, StorageType,
+};
+use crate::player::output::{pipeline::gstreamer, relay};
+use crate::utils::{
+    capabilities::{self, Capabilities},
+    config::{Backend, GapPolicy, GapRule, OutputMode, PlayoutConfig, Tonemap},
+};
+
+/// Problem of a config field.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigIssue {
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    fn new(field: &str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Time in `HH:MM:SS` format, with hours up to `max_hours`.
+fn is_time(value: &str, max_hours: f64) -> bool {
+    let parts = value
+        .split(':')
+        .map(|p| p.parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>();
+
+    match parts.as_deref() {
+        Some([h, m, s]) => {
+            (0.0..60.0).contains(m)
+                && (0.0..60.0).contains(s)
+                && *h >= 0.0
+                && h * 3600.0 + m * 60.0 + s <= max_hours * 3600.0
+        }
+        _ => false,
+    }
+}
+
+/// Check the numbers and time values of the config.
+pub fn check_values(config: &PlayoutConfig) -> Vec<ConfigIssue> {
+    let mut issues = vec![];
+    let processing = &config.processing;
+    let mut check = |valid: bool, field: &str, message: &str| {
+        if !valid {
+            issues.push(ConfigIssue::new(field, message));
+        }
+    };
+
+    check(
+        config.general.stop_threshold >= 0.0,
+        "general.stop_threshold",
+        "must not be negative",
+    );
+
+    if !processing.audio_only {
+        check(
+            processing.width > 0 && processing.width % 2 == 0,
+            "processing.width",
+            "must be a positive, even number",
+        );
+        check(
+            processing.height > 0 && processing.height % 2 == 0,
+            "processing.height",
+            "must be a positive, even number",
+        );
+        check(
+            processing.aspect > 0.0,
+            "processing.aspect",
+            "must be greater than 0",
+        );
+        check(
+            processing.fps > 0.0 && processing.fps <= 120.0,
+            "processing.fps",
+            "must be between 0 and 120",
+        );
+    }
+
+    check(
+        (0.0..=1.0).contains(&processing.logo_opacity),
+        "processing.logo_opacity",
+        "must be between 0 and 1",
+    );
+    check(
+        processing.audio_tracks >= 1,
+        "processing.audio_tracks",
+        "must be at least 1",
+    );
+    check(
+        processing.audio_channels >= 1,
+        "processing.audio_channels",
+        "must be at least 1",
+    );
+    check(
+        processing.volume >= 0.0,
+        "processing.volume",
+        "must not be negative",
+    );
+    check(
+        processing.crossfade >= 0.0,
+        "processing.crossfade",
+        "must not be negative",
+    );
+    check(
+        processing.backend != Backend::Gstreamer || gstreamer::has_output(&config.output.mode),
+        "processing.backend",
+        &format!("GStreamer has no {} output", config.output.mode),
+    );
+    check(
+        is_time(&config.playlist.day_start, 24.0) && config.playlist.day_start != "24:00:00",
+        "playlist.day_start",
+        "must be a time in HH:MM:SS format",
+    );
+    check(
+        !config.playlist.length.contains(':') || is_time(&config.playlist.length, 24.0),
+        "playlist.length",
+        "must be a duration in HH:MM:SS format, not longer than 24 hours",
+    );
+    check(
+        config.script.timeout >= 0.0,
+        "script.timeout",
+        "must not be negative",
+    );
+    check(
+        config.hooks.timeout >= 0.0,
+        "hooks.timeout",
+        "must not be negative",
+    );
+
+    for line in &config.playlist.gap_policy {
+        check(
+            GapRule::new(&config.channel.storage, line).is_some(),
+            "playlist.gap_policy",
+            &format!("'{line}' is no policy, use a line like: 60-600 fit"),
+        );
+    }
+
+    if config.output.mode == OutputMode::HLS {
+        check(
+            config.output.hls_time > 0.0,
+            "output.hls_time",
+            "must be greater than 0",
+        );
+    }
+
+    issues
+}
+
+/// Check, that the files of the enabled features exist in the channel storage.
+///
+/// Remote storages are not checked.
+pub fn check_paths(config: &PlayoutConfig, storage: &Path) -> Vec<ConfigIssue> {
+    let mut issues = vec![];
+
+    if !matches!(select_storage_type(storage), StorageType::Local) {
+        return issues;
+    }
+
+    // a missing filler is replaced by a dummy clip, so it is no error
+    let files = [
+        (
+            config.processing.add_logo,
+            "processing.logo",
+            &config.processing.logo,
+        ),
+        (config.text.add_text, "text.font", &config.text.font),
+    ];
+
+    for (enabled, field, value) in files {
+        if !enabled || value.trim().is_empty() {
+            continue;
+        }
+
+        let exists = clean_raw_abs_path(storage, value.trim(), ABS_PATH_INDICATOR)
+            .map(|(_, path)| path.exists())
+            .unwrap_or(false);
+
+        if !exists {
+            issues.push(ConfigIssue::new(field, &format!("{value} not exists")));
+        }
+    }
+
+    for rule in &config.playlist.gap_rules {
+        if let GapPolicy::Slate(path) = &rule.policy {
+            if !Path::new(path).is_file() {
+                issues.push(ConfigIssue::new(
+                    "playlist.gap_policy",
+                    &format!("{path} not exists"),
+                ));
+            }
+        }
+    }
+
+    if config.script.enable && !config.script.path.is_file() {
+        issues.push(ConfigIssue::new(
+            "script.path",
+            &format!("{} not exists", config.script.path.display()),
+        ));
+    }
+
+    issues
+}
+
+/// Encoders of ffmpeg parameters, without `copy`.
+fn codecs(params: &[String]) -> Vec<String> {
+    params
+        .windows(2)
+        .filter(|w| {
+            let key = w[0].split(':').next().unwrap_or_default();
+
+            matches!(key, "-c" | "-codec" | "-vcodec" | "-acodec")
+        })
+        .map(|w| w[1].clone())
+        .filter(|c| c != "copy")
+        .collect()
+}
+
+/// Encoders from the output parameters, without `copy`.
+pub fn output_codecs(output_param: &str) -> Vec<String> {
+    codecs(&split(output_param).unwrap_or_default())
+}
+
+/// Part of ffmpeg, which a config needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    Encoder,
+    Muxer,
+    Filter,
+    Protocol,
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Encoder => write!(f, "encoder"),
+            Self::Muxer => write!(f, "muxer"),
+            Self::Filter => write!(f, "filter"),
+            Self::Protocol => write!(f, "protocol"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+    pub field: String,
+    pub feature: Feature,
+    pub name: String,
+}
+
+#[derive(Default)]
+struct Requirements(Vec<Requirement>);
+
+impl Requirements {
+    fn add(&mut self, field: &str, feature: Feature, name: &str) {
+        if !self
+            .0
+            .iter()
+            .any(|r| r.feature == feature && r.name == name)
+        {
+            self.0.push(Requirement {
+                field: field.to_string(),
+                feature,
+                name: name.to_string(),
+            });
+        }
+    }
+
+    /// Encoders, muxers and protocols of ffmpeg parameters.
+    fn params(&mut self, field: &str, params: &[String]) {
+        for codec in codecs(params) {
+            self.add(field, Feature::Encoder, &codec);
+        }
+
+        for muxer in params.windows(2).filter(|w| w[0] == "-f").map(|w| &w[1]) {
+            self.add(field, Feature::Muxer, muxer);
+        }
+
+        for scheme in params
+            .iter()
+            .filter_map(|p| p.split_once("://").map(|(s, _)| s))
+        {
+            if !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric()) {
+                self.add(field, Feature::Protocol, &scheme.to_lowercase());
+            }
+        }
+    }
+}
+
+/// Encoders, muxers, filters and protocols, which the config needs from ffmpeg.
+///
+/// The generated commands are used, when the config is already prepared for a channel,
+/// otherwise the parameters from the config.
+pub fn requirements(config: &PlayoutConfig) -> Vec<Requirement> {
+    let mut needs = Requirements::default();
+    let output = &config.output;
+    let processing = &config.processing;
+    // GStreamer encodes the output, a relay behind it still runs with ffmpeg
+    let gst_output = processing.backend == Backend::Gstreamer
+        && gstreamer::has_output(&output.mode)
+        && !relay::is_relay(config);
+
+    if let Some(cmd) = &processing.cmd {
+        needs.params("processing", cmd);
+    }
+
+    match output.mode {
+        OutputMode::Ndi | OutputMode::Decklink if gst_output => {}
+        OutputMode::Ndi => needs.add("output.mode", Feature::Muxer, "libndi_newtek"),
+        OutputMode::Decklink => needs.add("output.mode", Feature::Muxer, "decklink"),
+        OutputMode::Icecast => {
+            let codec = match output.icecast_format.as_str() {
+                "aac" => "aac",
+                "opus" => "libopus",
+                _ => "libmp3lame",
+            };
+
+            needs.add("output.icecast_format", Feature::Encoder, codec);
+            needs.add("output.mode", Feature::Protocol, "icecast");
+        }
+        OutputMode::Whip => {
+            needs.add("output.mode", Feature::Encoder, "libx264");
+            needs.add("output.mode", Feature::Encoder, "libopus");
+            needs.add("output.mode", Feature::Muxer, "whip");
+        }
+        OutputMode::Srt => needs.add("output.mode", Feature::Protocol, "srt"),
+        OutputMode::HLS => needs.add("output.mode", Feature::Muxer, "hls"),
+        OutputMode::Dash => needs.add("output.mode", Feature::Muxer, "dash"),
+        OutputMode::Desktop | OutputMode::Null | OutputMode::Stream => {}
+    }
+
+    if output.mode != OutputMode::Desktop && !gst_output {
+        let params = match &output.output_cmd {
+            Some(cmd) => cmd.clone(),
+            None if matches!(
+                output.mode,
+                OutputMode::HLS | OutputMode::Stream | OutputMode::Srt | OutputMode::Dash
+            ) =>
+            {
+                split(&output.output_param).unwrap_or_default()
+            }
+            None => vec![],
+        };
+
+        needs.params("output.output_param", &params);
+
+        if let Some(push) = &output.push {
+            needs.params("output.output_param", push);
+        }
+    }
+
+    if matches!(output.mode, OutputMode::Stream | OutputMode::Srt) {
+        for leg in &output.legs {
+            needs.params("output.legs", &split(leg).unwrap_or_default());
+        }
+    }
+
+    if output.mode == OutputMode::Icecast && !output.icecast_video.trim().is_empty() {
+        needs.params(
+            "output.icecast_video",
+            &split(&output.icecast_video).unwrap_or_default(),
+        );
+    }
+
+    let filters = [
+        (config.text.add_text, "text.add_text", "drawtext"),
+        (
+            config.text.add_text && !config.text.text_from_filename,
+            "text.text_from_filename",
+            "zmq",
+        ),
+        (processing.add_logo, "processing.add_logo", "overlay"),
+        (processing.loudnorm, "processing.loudnorm", "loudnorm"),
+        (
+            processing.tonemap == Tonemap::Zscale,
+            "processing.tonemap",
+            "zscale",
+        ),
+        (
+            processing.tonemap == Tonemap::Libplacebo,
+            "processing.tonemap",
+            "libplacebo",
+        ),
+        (processing.crossfade > 0.0, "processing.crossfade", "xfade"),
+        (
+            processing.crossfade > 0.0,
+            "processing.crossfade",
+            "acrossfade",
+        ),
+        (
+            config.monitor.silence_duration > 0.0,
+            "monitor.silence_duration",
+            "silencedetect",
+        ),
+        (
+            config.monitor.black_duration > 0.0,
+            "monitor.black_duration",
+            "blackdetect",
+        ),
+        (
+            config.monitor.freeze_duration > 0.0,
+            "monitor.freeze_duration",
+            "freezedetect",
+        ),
+    ];
+
+    for (enabled, field, filter) in filters {
+        if enabled {
+            needs.add(field, Feature::Filter, filter);
+        }
+    }
+
+    needs.0
+}
+
+/// Requirements of the config, which the ffmpeg build not fulfills.
+pub fn lint(config: &PlayoutConfig, capabilities: &Capabilities) -> Vec<ConfigIssue> {
+    requirements(config)
+        .into_iter()
+        .filter(|r| {
+            let available = match r.feature {
+                Feature::Encoder => &capabilities.encoders,
+                Feature::Muxer => &capabilities.muxers,
+                Feature::Filter => &capabilities.filters,
+                Feature::Protocol => &capabilities.protocols,
+            };
+
+            !available.contains(&r.name)
+        })
+        .map(|r| {
+            ConfigIssue::new(
+                &r.field,
+                &format!(
+                    "{} {} requested but not available in {}",
+                    r.feature, r.name, capabilities.path
+                ),
+            )
+        })
+        .collect()
+}
+
+/// Check the config against the capabilities of ffmpeg.
+///
+/// Without ffmpeg on the system the check is skipped.
+pub async fn check_ffmpeg(config: &PlayoutConfig) -> Vec<ConfigIssue> {
+    match capabilities::get().await {
+        Some(capabilities) => lint(config, capabilities),
+        None => vec![],
+    }
+}
+
+/// All problems of the config, empty when the config is valid.
+pub async fn check(config: &PlayoutConfig, storage: &Path) -> Vec<ConfigIssue> {
+    let mut issues = check_values(config);
+    issues.append(&mut check_paths(config, storage));
+    issues.append(&mut check_ffmpeg(config).await);
+
+    issues
+}
@@ -1,17 +1,22 @@
-use std::{error::Error, fmt, str::FromStr, sync::atomic::Ordering};
+use std::{error::Error, fmt, path::Path, str::FromStr, sync::atomic::Ordering, time::Duration};
 
 use log::*;
+use regex::{Captures, Regex};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
 use sqlx::{Pool, Sqlite};
+use tokio::time::sleep;
 use zeromq::{Socket, SocketRecv, SocketSend, ZmqMessage};
 
 use crate::db::handles;
 use crate::player::{
-    controller::{ChannelManager, ProcessUnit::*},
-    utils::{get_delta, get_media_map},
+    controller::{ChannelManager, HoldMode, ProcessUnit::*},
+    utils::{get_delta, get_media_map, is_remote, Media},
+};
+use crate::utils::{
+    config::ProcessMode::Playlist, errors::ServiceError, logging::Target, time_machine::time_now,
+    workers, TextFilter, TextMessage,
 };
-use crate::utils::{config::OutputMode::*, errors::ServiceError, logging::Target, TextFilter};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 struct TextParams {
@@ -19,9 +24,24 @@ struct TextParams {
     message: TextFilter,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Default, Deserialize, Serialize, Clone)]
 pub struct ControlParams {
     pub control: PlayerCtl,
+    /// Playlist index for goto.
+    #[serde(default)]
+    pub index: Option<usize>,
+    /// Clip source for goto, instead of the index.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// In point for goto, or position in the clip for seek, in seconds.
+    #[serde(default)]
+    pub position: Option<f64>,
+    /// Relative seek in seconds, negative seeks backwards.
+    #[serde(default)]
+    pub offset: Option<f64>,
+    /// Loop the current clip or freeze on its last frame, for hold.
+    #[serde(default)]
+    pub mode: Option<HoldMode>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -36,6 +56,7 @@ pub enum ProcessCtl {
     Status,
     Start,
     Stop,
+    StopAfterCurrent,
     Restart,
 }
 
@@ -47,6 +68,7 @@ impl FromStr for ProcessCtl {
             "status" => Ok(Self::Status),
             "start" => Ok(Self::Start),
             "stop" => Ok(Self::Stop),
+            "stop_after_current" => Ok(Self::StopAfterCurrent),
             "restart" => Ok(Self::Restart),
             _ => Err(format!("Command '{input}' not found!")),
         }
@@ -59,6 +81,7 @@ impl fmt::Display for ProcessCtl {
             Self::Status => write!(f, "status"),
             Self::Start => write!(f, "start"),
             Self::Stop => write!(f, "stop"),
+            Self::StopAfterCurrent => write!(f, "stop_after_current"),
             Self::Restart => write!(f, "restart"),
         }
     }
@@ -69,6 +92,11 @@ impl fmt::Display for ProcessCtl {
 pub enum PlayerCtl {
     Back,
     Next,
+    Goto,
+    Seek,
+    Hold,
+    Release,
+    Take,
     #[default]
     Reset,
 }
@@ -80,6 +108,11 @@ impl FromStr for PlayerCtl {
         match input.to_lowercase().as_str() {
             "back" => Ok(Self::Back),
             "next" => Ok(Self::Next),
+            "goto" => Ok(Self::Goto),
+            "seek" => Ok(Self::Seek),
+            "hold" => Ok(Self::Hold),
+            "release" => Ok(Self::Release),
+            "take" => Ok(Self::Take),
             "reset" => Ok(Self::Reset),
             _ => Err(format!("Command '{input}' not found!")),
         }
@@ -91,6 +124,11 @@ impl fmt::Display for PlayerCtl {
         match *self {
             Self::Back => write!(f, "back"),
             Self::Next => write!(f, "next"),
+            Self::Goto => write!(f, "goto"),
+            Self::Seek => write!(f, "seek"),
+            Self::Hold => write!(f, "hold"),
+            Self::Release => write!(f, "release"),
+            Self::Take => write!(f, "take"),
             Self::Reset => write!(f, "reset"),
         }
     }
@@ -101,6 +139,25 @@ pub struct Process {
     pub command: ProcessCtl,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InsertMode {
+    #[default]
+    Push,
+    Trim,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InsertParams {
+    pub source: String,
+    #[serde(default)]
+    pub duration: Option<f64>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub mode: InsertMode,
+}
+
 async fn zmq_send(msg: &str, socket_addr: &str) -> Result<String, Box<dyn Error>> {
     let mut socket = zeromq::ReqSocket::new();
     socket.connect(&format!("tcp://{socket_addr}")).await?;
@@ -124,7 +181,7 @@ pub async fn send_message(
             *clips_filter.lock().await = vec![filter.clone()];
         }
 
-        if config.output.mode == HLS {
+        if config.output.mode.is_segmented() {
             if manager.ingest_is_alive.load(Ordering::SeqCst) {
                 let filter_server = format!("drawtext@dyntext reinit {filter}");
 
@@ -142,7 +199,7 @@ pub async fn send_message(
             }
         }
 
-        if config.output.mode != HLS || !manager.ingest_is_alive.load(Ordering::SeqCst) {
+        if !config.output.mode.is_segmented() || !manager.ingest_is_alive.load(Ordering::SeqCst) {
             let filter_stream = format!("drawtext@dyntext reinit {filter}");
 
             if let Ok(reply) = zmq_send(
@@ -162,10 +219,268 @@ pub async fn send_message(
     ))
 }
 
+/// Set the volume of the running clip, over the zmq socket of the decoder and the ingest.
+///
+/// Without a volume filter in the clip, the new volume applies from the next clip on.
+pub async fn send_volume(manager: &ChannelManager) {
+    let config = manager.config.lock().await.clone();
+    let mut sockets = vec![config.text.zmq_stream_socket.clone()];
+
+    if manager.ingest_is_alive.load(Ordering::SeqCst) {
+        sockets.push(config.text.zmq_server_socket.clone());
+    }
+
+    for socket in sockets.into_iter().flatten() {
+        for nr in 0..config.processing.audio_tracks {
+            let command = format!("volume@volume{nr} volume {}", config.processing.volume);
+
+            if let Err(e) = zmq_send(&command, &socket).await {
+                debug!(target: Target::file_mail(), channel = config.general.channel_id; "Volume over zmq: {e}");
+            }
+        }
+    }
+}
+
+fn clip_title(media: &Media) -> String {
+    match &media.title {
+        Some(title) if !title.is_empty() => title.clone(),
+        _ => Path::new(&media.source)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Run a process command on the channel, the response is the state or `Success`.
+pub async fn process_command(
+    manager: &ChannelManager,
+    command: ProcessCtl,
+) -> Result<&'static str, ServiceError> {
+    let worker_id = manager.channel.lock().await.worker_id;
+
+    if let Some(worker_id) = worker_id {
+        return workers::process_command(manager, worker_id, command).await;
+    }
+
+    manager.list_init.store(true, Ordering::SeqCst);
+
+    if manager.is_processing.load(Ordering::SeqCst) {
+        return Err(ServiceError::Conflict(
+            "A command is already being processed, please wait".to_string(),
+        ));
+    }
+
+    manager.is_processing.store(true, Ordering::SeqCst);
+
+    let result = match command {
+        ProcessCtl::Status => {
+            if manager.is_alive.load(Ordering::SeqCst) {
+                if manager.stop_after_current.load(Ordering::SeqCst) {
+                    Ok("stopping")
+                } else {
+                    Ok("active")
+                }
+            } else {
+                Ok("not running")
+            }
+        }
+        ProcessCtl::Start => {
+            if manager.is_alive.load(Ordering::SeqCst) {
+                Ok("Success")
+            } else {
+                manager.channel.lock().await.active = true;
+                manager.start().await.map(|_| "Success")
+            }
+        }
+        ProcessCtl::Stop => {
+            manager.channel.lock().await.active = false;
+            manager.stop_all(true).await;
+
+            Ok("Success")
+        }
+        ProcessCtl::StopAfterCurrent => {
+            if manager.is_alive.load(Ordering::SeqCst) {
+                manager.stop_after_current();
+            }
+
+            Ok("Success")
+        }
+        ProcessCtl::Restart => {
+            manager.channel.lock().await.active = false;
+            manager.stop_all(false).await;
+
+            sleep(Duration::from_millis(500)).await;
+
+            manager.channel.lock().await.active = true;
+            manager.start().await.map(|_| "Success")
+        }
+    };
+
+    manager.is_processing.store(false, Ordering::SeqCst);
+
+    result
+}
+
+/// Fill the template variables of a text message, unknown variables stay as they are.
+pub fn render_template(
+    text: &str,
+    current: Option<&Media>,
+    next: Option<&Media>,
+    time: &str,
+) -> String {
+    Regex::new(r"\{\{\s*(\w+)\s*\}\}")
+        .unwrap()
+        .replace_all(text, |caps: &Captures| match &caps[1] {
+            "title" => current.map(clip_title).unwrap_or_default(),
+            "next_title" => next.map(clip_title).unwrap_or_default(),
+            "time" => time.to_string(),
+            _ => caps[0].to_string(),
+        })
+        .to_string()
+}
+
+async fn render_message(manager: &ChannelManager, message: &TextMessage) -> TextFilter {
+    let timezone = manager.config.lock().await.channel.timezone;
+    let current = manager.current_media.lock().await.clone();
+    let next = match current.as_ref().and_then(|m| m.index) {
+        Some(index) => manager.current_list.lock().await.get(index + 1).cloned(),
+        None => None,
+    };
+    let mut filter = message.filter.clone();
+
+    filter.text = filter.text.map(|text| {
+        render_template(
+            &text,
+            current.as_ref(),
+            next.as_ref(),
+            &time_now(&timezone).format("%H:%M").to_string(),
+        )
+    });
+
+    if let Some(position) = message.position {
+        let (x, y) = position.coordinates();
+        filter.x = Some(x.to_string());
+        filter.y = Some(y.to_string());
+    }
+
+    filter
+}
+
+/// Update the now/next text for a starting clip.
+///
+/// In HLS mode the drawtext filter belongs to the decoder, which can need a moment to listen.
+pub async fn send_now_next(manager: ChannelManager, node: Media) {
+    let config = manager.config.lock().await.clone();
+
+    if !config.text.now_next || config.text.zmq_stream_socket.is_none() {
+        return;
+    }
+
+    let next = match node.index {
+        Some(index) => manager.current_list.lock().await.get(index + 1).cloned(),
+        None => None,
+    };
+    let text = render_template(
+        &config.text.now_next_format,
+        Some(&node),
+        next.as_ref(),
+        &time_now(&config.channel.timezone)
+            .format("%H:%M")
+            .to_string(),
+    );
+    let filter = TextFilter::with_style(&text, &config.text.now_next_style);
+
+    for _ in 0..10 {
+        if send_message(manager.clone(), filter.clone()).await.is_ok() {
+            return;
+        }
+
+        sleep(Duration::from_millis(500)).await;
+    }
+
+    warn!(target: Target::file_mail(), channel = manager.id; "Now/next text could not be sent");
+}
+
+/// Show the queued messages one after the other, each for its duration.
+async fn run_message_queue(manager: ChannelManager) {
+    loop {
+        let message = {
+            let mut queue = manager.text_queue.lock().await;
+
+            match queue.pop_front() {
+                Some(message) => message,
+                None => {
+                    manager.text_queue_active.store(false, Ordering::SeqCst);
+                    break;
+                }
+            }
+        };
+
+        let filter = render_message(&manager, &message).await;
+
+        if let Err(e) = send_message(manager.clone(), filter).await {
+            error!(target: Target::file_mail(), channel = manager.id; "Text message not sent: {e}");
+        }
+
+        if message.duration > 0.0 {
+            sleep(Duration::from_secs_f64(message.duration)).await;
+
+            if manager.text_queue.lock().await.is_empty() {
+                let clear = TextFilter {
+                    text: Some(String::new()),
+                    ..message.filter
+                };
+
+                if let Err(e) = send_message(manager.clone(), clear).await {
+                    error!(target: Target::file_mail(), channel = manager.id; "Text message not cleared: {e}");
+                }
+            }
+        }
+    }
+}
+
+/// Add a text message to the queue, the response has its position in the queue.
+pub async fn queue_message(
+    manager: ChannelManager,
+    message: TextMessage,
+) -> Result<Map<String, Value>, ServiceError> {
+    if manager.config.lock().await.text.zmq_stream_socket.is_none() {
+        return Err(ServiceError::ServiceUnavailable(
+            "Text messages are not enabled!".to_string(),
+        ));
+    }
+
+    let mut data_map = Map::new();
+    let mut queue = manager.text_queue.lock().await;
+    queue.push_back(message);
+
+    data_map.insert("position".to_string(), json!(queue.len()));
+
+    if !manager.text_queue_active.swap(true, Ordering::SeqCst) {
+        tokio::spawn(run_message_queue(manager.clone()));
+    }
+
+    Ok(data_map)
+}
+
+/// Drop the queued messages and clear the current text.
+pub async fn clear_messages(manager: ChannelManager) -> Result<Map<String, Value>, ServiceError> {
+    manager.text_queue.lock().await.clear();
+
+    send_message(
+        manager,
+        TextFilter {
+            text: Some(String::new()),
+            ..Default::default()
+        },
+    )
+    .await
+}
+
 pub async fn control_state(
     conn: &Pool<Sqlite>,
     manager: &ChannelManager,
-    command: &PlayerCtl,
+    params: &ControlParams,
 ) -> Result<Map<String, Value>, ServiceError> {
     let config = manager.config.lock().await.clone();
     let id = config.general.channel_id;
@@ -175,7 +490,7 @@ pub async fn control_state(
     let mut data_map = Map::new();
     let mut shift = 0.0;
 
-    match command {
+    match params.control {
         PlayerCtl::Back => {
             if index > 1 && current_list.len() > 1 {
                 let mut media = current_list[index - 2].clone();
@@ -212,6 +527,132 @@ pub async fn control_state(
             }
         }
 
+        PlayerCtl::Goto => {
+            let position = match (&params.source, params.index) {
+                (Some(source), _) => {
+                    let found = |m: &Media| &m.key == source || &m.source == source;
+
+                    current_list
+                        .iter()
+                        .skip(index)
+                        .position(found)
+                        .map(|i| i + index)
+                        .or_else(|| current_list.iter().position(found))
+                }
+                (None, Some(i)) => (i < current_list.len()).then_some(i),
+                (None, None) => None,
+            };
+
+            let Some(goto) = position else {
+                return Err(ServiceError::BadRequest("Clip not found".to_string()));
+            };
+
+            let mut media = current_list[goto].clone();
+            let seek = params
+                .position
+                .unwrap_or(media.seek)
+                .clamp(media.seek, (media.out - 1.0).max(media.seek));
+            (shift, _) = get_delta(&config, &(media.begin.unwrap_or(0.0) + seek - media.seek));
+
+            info!(target: Target::file_mail(), channel = id; "Go to clip <yellow>{goto}</>, position: <yellow>{seek:.3}</>");
+
+            manager.list_init.store(true, Ordering::SeqCst);
+
+            if let Err(e) = media.add_probe(false).await {
+                error!(target: Target::file_mail(), channel = id; "{e:?}");
+            };
+
+            media.seek = seek;
+
+            data_map.insert("operation".to_string(), json!("goto"));
+            data_map.insert("shifted_seconds".to_string(), json!(shift));
+            data_map.insert("media".to_string(), get_media_map(media));
+        }
+
+        PlayerCtl::Seek => {
+            let current = manager.current_media.lock().await.clone();
+            let Some(mut media) = current
+                .and_then(|m| m.index)
+                .and_then(|i| current_list.get(i))
+                .cloned()
+            else {
+                return Err(ServiceError::BadRequest("No clip is playing".to_string()));
+            };
+
+            let time_shift = manager.channel.lock().await.time_shift;
+            let (delta, _) = get_delta(&config, &media.begin.unwrap_or(0.0));
+            // position in the clip file, which is playing now
+            let playing = media.seek + time_shift - delta;
+            let seek = params
+                .position
+                .unwrap_or(playing + params.offset.unwrap_or_default())
+                .clamp(media.seek, (media.out - 1.0).max(media.seek));
+            (shift, _) = get_delta(&config, &(media.begin.unwrap_or(0.0) + seek - media.seek));
+
+            info!(target: Target::file_mail(), channel = id; "Seek in current clip to: <yellow>{seek:.3}</>");
+
+            manager.list_init.store(true, Ordering::SeqCst);
+            media.seek = seek;
+
+            data_map.insert("operation".to_string(), json!("seek"));
+            data_map.insert("shifted_seconds".to_string(), json!(shift));
+            data_map.insert("media".to_string(), get_media_map(media));
+        }
+
+        PlayerCtl::Hold => {
+            let mode = params.mode.unwrap_or(HoldMode::Loop);
+
+            info!(target: Target::file_mail(), channel = id; "Hold current clip, mode: <yellow>{mode:?}</>");
+
+            // the current clip plays on, the hold starts when it ends
+            *manager.hold.lock().await = Some(mode);
+
+            data_map.insert("operation".to_string(), json!("hold"));
+            data_map.insert("mode".to_string(), json!(mode));
+
+            return Ok(data_map);
+        }
+
+        PlayerCtl::Release => {
+            if manager.hold.lock().await.take().is_none() {
+                return Err(ServiceError::Conflict("Playout is not on hold".to_string()));
+            }
+
+            // like next, the program continues now with the following clip
+            if index < current_list.len() {
+                let media = current_list[index].clone();
+                (shift, _) = get_delta(&config, &media.begin.unwrap_or(0.0));
+
+                info!(target: Target::file_mail(), channel = id; "Release hold, continue with next clip");
+
+                data_map.insert("media".to_string(), get_media_map(media));
+            }
+
+            data_map.insert("operation".to_string(), json!("release"));
+            data_map.insert("shifted_seconds".to_string(), json!(shift));
+        }
+
+        PlayerCtl::Take => {
+            if !manager.manual_wait.swap(false, Ordering::SeqCst) {
+                return Err(ServiceError::Conflict(
+                    "No clip waits for the take".to_string(),
+                ));
+            }
+
+            // the manual clip starts now, the following clips start later
+            if index < current_list.len() {
+                let media = current_list[index].clone();
+                (shift, _) = get_delta(&config, &media.begin.unwrap_or(0.0));
+
+                info!(target: Target::file_mail(), channel = id; "Take manual clip <b><magenta>{}</></b>", media.source);
+
+                data_map.insert("media".to_string(), get_media_map(media));
+            }
+
+            data_map.insert("operation".to_string(), json!("take"));
+            data_map.insert("shifted_seconds".to_string(), json!(shift));
+        }
+
         PlayerCtl::Reset => {
             info!(target: Target::file_mail(), channel = id; "Reset playout to original state");
 
@@ -227,3 +668,131 @@ pub async fn control_state(
 
     Ok(data_map)
 }
+
+/// Insert a clip in the program, before the clip at `index`.
+///
+/// With `Push` the following clips start later, this is applied as time shift and the returned
+/// value is the change of it. With `Trim` the following filler gets shorter by the clip length,
+/// so the program stays on time; when there is no filler long enough, the clips get pushed too.
+pub fn insert_clip(
+    list: &mut Vec<Media>,
+    index: usize,
+    mut node: Media,
+    mode: &InsertMode,
+    filler: &Path,
+) -> f64 {
+    let index = index.min(list.len());
+    let length = node.out - node.seek;
+    let begin = match list.get(index) {
+        Some(next) => next.begin.unwrap_or_default(),
+        None => list
+            .last()
+            .map(|last| last.begin.unwrap_or_default() + last.out - last.seek)
+            .unwrap_or_default(),
+    };
+    let mut shift = -length;
+
+    if let Some(next) = list.get_mut(index) {
+        if *mode == InsertMode::Trim
+            && Path::new(&next.source).starts_with(filler)
+            && next.out - next.seek - length >= 1.0
+        {
+            next.out -= length;
+            next.begin = Some(begin + length);
+            shift = 0.0;
+        }
+    }
+
+    // pushed clips keep their begin, the inserted clip starts before and the time shift follows
+    node.begin = Some(begin + shift);
+    list.insert(index, node);
+
+    for (i, item) in list.iter_mut().enumerate().skip(index) {
+        item.index = Some(i);
+    }
+
+    shift
+}
+
+/// Insert a clip or live source as next item of the running playlist.
+pub async fn insert_next(
+    conn: &Pool<Sqlite>,
+    manager: &ChannelManager,
+    params: &InsertParams,
+) -> Result<Map<String, Value>, ServiceError> {
+    let config = manager.config.lock().await.clone();
+    let id = config.general.channel_id;
+
+    if config.processing.mode != Playlist {
+        return Err(ServiceError::BadRequest(
+            "Insert is only possible in playlist mode".to_string(),
+        ));
+    }
+
+    let source = if is_remote(&params.source) {
+        params.source.clone()
+    } else {
+        let storage = manager.storage.lock().await;
+        let path = storage
+            .fetch_file_path(&storage.interpreted_file_path(&params.source))
+            .await?;
+
+        if !Path::new(&path).is_file() {
+            return Err(ServiceError::BadRequest(format!(
+                "Source {} not found",
+                params.source
+            )));
+        }
+
+        path
+    };
+
+    let index = manager.current_index.load(Ordering::SeqCst);
+    let mut node = Media::new(index, &source, true).await;
+    let duration = match params.duration {
+        Some(d) if node.duration > 0.0 => d.min(node.duration),
+        Some(d) => d,
+        None => node.duration,
+    };
+
+    if duration <= 0.0 {
+        return Err(ServiceError::BadRequest(
+            "Duration of the source is unknown".to_string(),
+        ));
+    }
+
+    node.key.clone_from(&params.source);
+    node.title.clone_from(&params.title);
+    node.duration = node.duration.max(duration);
+    node.out = duration;
+
+    let mut current_list = manager.current_list.lock().await;
+    let shift = insert_clip(
+        &mut current_list,
+        index,
+        node.clone(),
+        &params.mode,
+        &config.storage.filler_path,
+    );
+
+    drop(current_list);
+
+    info!(target: Target::file_mail(), channel = id;
+        "Insert next: <b><magenta>{}</></b>, duration: <yellow>{duration:.3}</>", params.source
+    );
+
+    if shift != 0.0 {
+        let current_date = manager.current_date.lock().await.clone();
+        let mut channel = manager.channel.lock().await;
+        channel.time_shift += shift;
+
+        handles::update_stat(conn, id, &Some(current_date), channel.time_shift).await?;
+    }
+
+    let mut data_map = Map::new();
+    data_map.insert("operation".to_string(), json!("insert_next"));
+    data_map.insert("shifted_seconds".to_string(), json!(shift));
+    data_map.insert("media".to_string(), get_media_map(node));
+
+    Ok(data_map)
+}
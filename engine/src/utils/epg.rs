@@ -0,0 +1,136 @@
+/*
+Electronic program guide in XMLTV format.
+
+The guide is built from the playlists of the next days, with the title, description
+and category of each clip. Clips without title use the file name.
+*/
+
+use std::path::Path;
+
+use chrono::{prelude::*, TimeDelta};
+use log::*;
+
+use crate::player::utils::Media;
+use crate::utils::{config::PlayoutConfig, playlist::read_playlist, time_machine::time_now};
+
+/// One entry of the guide.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Programme {
+    pub start: DateTime<FixedOffset>,
+    pub stop: DateTime<FixedOffset>,
+    pub title: String,
+    pub description: Option<String>,
+    pub category: String,
+}
+
+impl Programme {
+    pub fn new(start: DateTime<FixedOffset>, node: &Media) -> Self {
+        let length = TimeDelta::try_milliseconds(((node.out - node.seek) * 1000.0) as i64)
+            .unwrap_or_default();
+        let title = node.title.clone().unwrap_or_else(|| {
+            Path::new(&node.source)
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
+
+        Self {
+            start,
+            stop: start + length,
+            title,
+            description: node.description.clone(),
+            category: node.category.clone(),
+        }
+    }
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Programme of the channel, from today on for the given number of days.
+pub async fn programme(config: &PlayoutConfig, days: i64) -> Vec<Programme> {
+    let now = time_now(&config.channel.timezone);
+    let start_sec = config.playlist.start_sec.unwrap_or_default();
+    let mut day = now.date_naive();
+    let mut programme = vec![];
+
+    // before the day start, the playlist of yesterday is still playing
+    if (now.num_seconds_from_midnight() as f64) < start_sec {
+        day -= TimeDelta::days(1);
+    }
+
+    for _ in 0..days.max(1) {
+        let date = day.format("%Y-%m-%d").to_string();
+        let playlist = match read_playlist(config, date.clone()).await {
+            Ok(playlist) => playlist,
+            Err(e) => {
+                debug!("No playlist for EPG from {date}: {e}");
+                day += TimeDelta::days(1);
+                continue;
+            }
+        };
+        let naive =
+            day.and_time(NaiveTime::MIN) + TimeDelta::milliseconds((start_sec * 1000.0) as i64);
+        let Some(mut start) = now
+            .timezone()
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|t| t.fixed_offset())
+        else {
+            day += TimeDelta::days(1);
+            continue;
+        };
+
+        for node in &playlist.program {
+            let item = Programme::new(start, node);
+
+            start = item.stop;
+            programme.push(item);
+        }
+
+        day += TimeDelta::days(1);
+    }
+
+    programme
+}
+
+/// XMLTV document of the programme.
+pub fn xmltv(channel_id: i32, channel_name: &str, programme: &[Programme]) -> String {
+    let channel = format!("channel-{channel_id}");
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE tv SYSTEM \"xmltv.dtd\">\n<tv generator-info-name=\"ffplayout\">\n  <channel id=\"{channel}\">\n    <display-name>{}</display-name>\n  </channel>\n",
+        escape(channel_name)
+    );
+
+    for item in programme {
+        xml.push_str(&format!(
+            "  <programme start=\"{}\" stop=\"{}\" channel=\"{channel}\">\n    <title>{}</title>\n",
+            item.start.format("%Y%m%d%H%M%S %z"),
+            item.stop.format("%Y%m%d%H%M%S %z"),
+            escape(&item.title)
+        ));
+
+        if let Some(description) = &item.description {
+            xml.push_str(&format!("    <desc>{}</desc>\n", escape(description)));
+        }
+
+        if !item.category.is_empty() {
+            xml.push_str(&format!(
+                "    <category>{}</category>\n",
+                escape(&item.category)
+            ));
+        }
+
+        xml.push_str("  </programme>\n");
+    }
+
+    xml.push_str("</tv>\n");
+
+    xml
+}
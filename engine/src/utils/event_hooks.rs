@@ -0,0 +1,142 @@
+/*
+Shell commands for playout events.
+
+For station automation which can not take webhooks or MQTT. A command runs with `sh -c` at
+clip start, clip end or on errors, the event comes in environment variables:
+
+- `FFP_EVENT`, `FFP_CHANNEL` and `FFP_TIME`
+- `FFP_DATA` with the event data as JSON
+- one variable for each plain value of the data, like `FFP_SOURCE`, `FFP_TITLE` or `FFP_IN`
+
+The playout does not wait for the commands, they get killed after the timeout.
+*/
+
+use std::{process::Stdio, sync::Arc, time::Duration};
+
+use log::*;
+use serde_json::Value;
+use tokio::{
+    process::Command,
+    sync::{broadcast, Mutex},
+    time::timeout,
+};
+
+use crate::utils::{
+    config::{EventHooks, PlayoutConfig},
+    events::{EventKind, PlayoutEvent},
+    logging::Target,
+};
+
+/// Run time of a command, when no timeout is configured.
+const DEFAULT_TIMEOUT: f64 = 30.0;
+
+/// Events, which run the error command.
+const ERROR_EVENTS: [EventKind; 5] = [
+    EventKind::PlaylistMissing,
+    EventKind::Filler,
+    EventKind::Restart,
+    EventKind::Corrupt,
+    EventKind::OutputUnreachable,
+];
+
+/// Command for the event, `None` when the event has no hook.
+pub fn command<'a>(hooks: &'a EventHooks, event: &EventKind) -> Option<&'a str> {
+    let command = match event {
+        EventKind::ClipStart => &hooks.clip_start,
+        EventKind::ClipEnd => &hooks.clip_end,
+        e if ERROR_EVENTS.contains(e) => &hooks.error,
+        _ => return None,
+    };
+
+    Some(command.trim()).filter(|c| !c.is_empty())
+}
+
+/// Environment variables of the event.
+pub fn env(event: &PlayoutEvent) -> Vec<(String, String)> {
+    let mut vars = vec![
+        ("FFP_EVENT".to_string(), event.event.to_string()),
+        ("FFP_CHANNEL".to_string(), event.channel.to_string()),
+        ("FFP_TIME".to_string(), event.time.clone()),
+        ("FFP_DATA".to_string(), event.data.to_string()),
+    ];
+
+    if let Some(data) = event.data.as_object() {
+        for (key, value) in data {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                Value::Bool(b) => b.to_string(),
+                _ => continue,
+            };
+
+            vars.push((format!("FFP_{}", key.to_uppercase()), value));
+        }
+    }
+
+    vars
+}
+
+async fn execute(command: String, event: PlayoutEvent, limit: f64) {
+    let id = event.channel;
+    let child = Command::new("sh")
+        .args(["-c", &command])
+        .envs(env(&event))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            error!(target: Target::file_mail(), channel = id; "Hook for <yellow>{}</> could not start: {e}", event.event);
+            return;
+        }
+    };
+
+    match timeout(Duration::from_secs_f64(limit), child.wait()).await {
+        Ok(Ok(status)) if status.success() => {}
+        Ok(Ok(status)) => {
+            warn!(target: Target::file_mail(), channel = id; "Hook for <yellow>{}</> exited with {status}", event.event);
+        }
+        Ok(Err(e)) => {
+            error!(target: Target::file_mail(), channel = id; "Hook for <yellow>{}</>: {e}", event.event);
+        }
+        Err(_) => {
+            let _ = child.kill().await;
+
+            warn!(target: Target::file_mail(), channel = id;
+                "Hook for <yellow>{}</> killed after <yellow>{limit}</> seconds", event.event
+            );
+        }
+    }
+}
+
+/// Run the commands of the playout events.
+///
+/// The config is read for every event, so changes apply without restart.
+pub async fn run(config: Arc<Mutex<PlayoutConfig>>, mut events: broadcast::Receiver<PlayoutEvent>) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                warn!("Event hooks missed <yellow>{count}</> events");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let hooks = config.lock().await.hooks.clone();
+
+        if let Some(command) = command(&hooks, &event.event) {
+            let limit = if hooks.timeout > 0.0 {
+                hooks.timeout
+            } else {
+                DEFAULT_TIMEOUT
+            };
+
+            tokio::spawn(execute(command.to_string(), event, limit));
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::broadcast;
+
+use crate::player::{controller::ChannelManager, utils::Media};
+
+/// Buffered events per subscriber, slow subscribers lose the oldest ones.
+const EVENT_BUFFER: usize = 128;
+
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    ChannelStart,
+    ChannelStop,
+    ClipStart,
+    ClipEnd,
+    PlaylistMissing,
+    Filler,
+    Restart,
+    Silence,
+    Black,
+    Freeze,
+    Corrupt,
+    Failover,
+    ClockJump,
+    OutputUnreachable,
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::ChannelStart => write!(f, "channel_start"),
+            Self::ChannelStop => write!(f, "channel_stop"),
+            Self::ClipStart => write!(f, "clip_start"),
+            Self::ClipEnd => write!(f, "clip_end"),
+            Self::PlaylistMissing => write!(f, "playlist_missing"),
+            Self::Filler => write!(f, "filler"),
+            Self::Restart => write!(f, "restart"),
+            Self::Silence => write!(f, "silence"),
+            Self::Black => write!(f, "black"),
+            Self::Freeze => write!(f, "freeze"),
+            Self::Corrupt => write!(f, "corrupt"),
+            Self::Failover => write!(f, "failover"),
+            Self::ClockJump => write!(f, "clock_jump"),
+            Self::OutputUnreachable => write!(f, "output_unreachable"),
+        }
+    }
+}
+
+/// Playout event, for notifying external systems.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PlayoutEvent {
+    pub event: EventKind,
+    pub channel: i32,
+    pub time: String,
+    pub data: Value,
+}
+
+impl PlayoutEvent {
+    pub fn new(event: EventKind, channel: i32, data: Value) -> Self {
+        Self {
+            event,
+            channel,
+            time: Local::now().to_rfc3339(),
+            data,
+        }
+    }
+}
+
+pub fn channel() -> broadcast::Sender<PlayoutEvent> {
+    broadcast::channel(EVENT_BUFFER).0
+}
+
+/// Event data for a clip.
+pub fn clip_data(node: &Media) -> Value {
+    json!({
+        "index": node.index,
+        "title": node.title,
+        "artist": node.artist,
+        "source": node.source,
+        "category": node.category,
+        "in": node.seek,
+        "out": node.out,
+        "duration": node.duration,
+    })
+}
+
+/// Event data for the clip after the given one, from the current list.
+pub async fn next_clip_data(manager: &ChannelManager, node: &Media) -> Option<Value> {
+    let index = node.index?;
+
+    manager
+        .current_list
+        .lock()
+        .await
+        .get(index + 1)
+        .map(clip_data)
+}
+
+/// Event data for a starting clip, together with the next clip from the current list.
+pub async fn clip_start_data(manager: &ChannelManager, node: &Media) -> Value {
+    let mut data = clip_data(node);
+    data["next"] = json!(next_clip_data(manager, node).await);
+
+    data
+}
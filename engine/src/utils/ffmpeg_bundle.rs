@@ -0,0 +1,289 @@
+/*
+Bundled ffmpeg.
+
+Optional a static ffmpeg build gets downloaded and used instead of the ffmpeg from the system,
+so the engine runs with the same ffmpeg on each distribution. The build is pinned by the SHA-256
+checksum of its archive. It is stored next to the database, in a folder per checksum, and only
+downloaded again when the checksum changes.
+*/
+
+use std::{
+    env::consts::EXE_SUFFIX,
+    ffi::OsStr,
+    io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use log::*;
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use tokio::{fs, io::AsyncWriteExt, process::Command};
+
+use crate::db::DB_PATH;
+use crate::utils::errors::ProcessError;
+use crate::ARGS;
+
+const BINARIES: [&str; 3] = ["ffmpeg", "ffprobe", "ffplay"];
+
+static BUNDLE: OnceLock<PathBuf> = OnceLock::new();
+
+fn exe(name: &str) -> String {
+    format!("{name}{EXE_SUFFIX}")
+}
+
+/// Path of a binary from the bundle, or only its name, to run the one from the system.
+///
+/// Not every build has ffplay, then the one from the system is used.
+fn binary(name: &str) -> PathBuf {
+    BUNDLE
+        .get()
+        .map(|dir| dir.join(exe(name)))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(name))
+}
+
+pub fn ffmpeg() -> PathBuf {
+    binary("ffmpeg")
+}
+
+pub fn ffprobe() -> PathBuf {
+    binary("ffprobe")
+}
+
+pub fn ffplay() -> PathBuf {
+    binary("ffplay")
+}
+
+/// Folder of the bundled builds, next to the database.
+fn base_dir() -> PathBuf {
+    DB_PATH
+        .as_ref()
+        .ok()
+        .and_then(|path| path.parent())
+        .unwrap_or(Path::new(""))
+        .join("ffmpeg")
+}
+
+pub fn is_checksum(value: &str) -> bool {
+    value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Download the archive and return the SHA-256 checksum of it.
+async fn download(url: &str, archive: &Path) -> Result<String, ProcessError> {
+    let error = |e: reqwest::Error| ProcessError::IO(format!("Download {url}: {e}"));
+    let mut response = Client::new()
+        .get(url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(error)?;
+    let mut file = fs::File::create(archive).await?;
+    let mut hasher = Sha256::new();
+
+    while let Some(chunk) = response.chunk().await.map_err(error)? {
+        hasher.update(&chunk);
+        file.write_all(&chunk).await?;
+    }
+
+    file.flush().await?;
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// Unpack the archive with tar, or unzip for zip files.
+async fn unpack(archive: &Path, dir: &Path) -> Result<(), ProcessError> {
+    let status = if archive.extension() == Some(OsStr::new("zip")) {
+        Command::new("unzip")
+            .arg("-q")
+            .arg(archive)
+            .arg("-d")
+            .arg(dir)
+            .status()
+            .await
+    } else {
+        Command::new("tar")
+            .arg("-xf")
+            .arg(archive)
+            .arg("-C")
+            .arg(dir)
+            .status()
+            .await
+    }
+    .map_err(ProcessError::CommandSpawn)?;
+
+    if !status.success() {
+        return Err(ProcessError::IO(format!(
+            "Unpack {} failed: {status}",
+            archive.display()
+        )));
+    }
+
+    Ok(())
+}
+
+/// Move the binaries from the unpacked archive into `bin`, returns the found ones.
+fn collect(unpacked: &Path, bin: &Path) -> io::Result<Vec<&'static str>> {
+    let mut found = vec![];
+    let mut dirs = vec![unpacked.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                dirs.push(path);
+                continue;
+            }
+
+            let Some(name) = BINARIES
+                .into_iter()
+                .find(|b| path.file_name() == Some(OsStr::new(&exe(b))) && !found.contains(b))
+            else {
+                continue;
+            };
+
+            let target = bin.join(exe(name));
+            std::fs::rename(&path, &target)?;
+
+            #[cfg(target_family = "unix")]
+            {
+                use std::os::unix::fs::PermissionsExt;
+
+                std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755))?;
+            }
+
+            found.push(name);
+        }
+    }
+
+    Ok(found)
+}
+
+/// Download, verify and unpack the build into `dir`.
+///
+/// Everything happens in a temporary folder, `dir` exists only with a complete build.
+async fn install(url: &str, sha256: &str, dir: &Path) -> Result<(), ProcessError> {
+    let tmp = dir.with_extension("tmp");
+    let unpacked = tmp.join("unpacked");
+    let bin = tmp.join("bin");
+
+    if tmp.exists() {
+        fs::remove_dir_all(&tmp).await?;
+    }
+
+    fs::create_dir_all(&unpacked).await?;
+    fs::create_dir_all(&bin).await?;
+
+    let name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|u| u.rsplit('/').next())
+        .filter(|n| !n.is_empty())
+        .unwrap_or("ffmpeg.tar.xz");
+    let archive = tmp.join(name);
+
+    info!("Download ffmpeg from <b><magenta>{url}</></b>");
+
+    let result = async {
+        let checksum = download(url, &archive).await?;
+
+        if checksum != sha256 {
+            return Err(ProcessError::Custom(format!(
+                "Checksum of {url} is {checksum}, expected {sha256}"
+            )));
+        }
+
+        unpack(&archive, &unpacked).await?;
+
+        let found = collect(&unpacked, &bin)?;
+
+        if !found.contains(&"ffmpeg") || !found.contains(&"ffprobe") {
+            return Err(ProcessError::Custom(format!(
+                "{url} contains no ffmpeg and ffprobe"
+            )));
+        }
+
+        fs::rename(&bin, dir).await?;
+
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = fs::remove_dir_all(&tmp).await {
+        error!("Remove {}: {e}", tmp.display());
+    }
+
+    result
+}
+
+/// Remove the builds of older checksums.
+async fn remove_old(base: &Path, current: &Path) -> io::Result<()> {
+    let mut entries = fs::read_dir(base).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+
+        if path.is_dir() && path != current {
+            debug!(
+                "Remove old ffmpeg build <b><magenta>{}</></b>",
+                path.display()
+            );
+            fs::remove_dir_all(path).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Use the bundled ffmpeg, when `--ffmpeg-url` is set, it gets downloaded when it is missing.
+///
+/// When the download fails, the ffmpeg from the system is used.
+pub async fn init() -> Result<(), ProcessError> {
+    let Some(url) = ARGS.ffmpeg_url.as_deref() else {
+        return Ok(());
+    };
+
+    let sha256 = ARGS
+        .ffmpeg_sha256
+        .as_deref()
+        .map(str::to_lowercase)
+        .filter(|s| is_checksum(s))
+        .ok_or_else(|| {
+            ProcessError::Input(
+                "--ffmpeg-url needs --ffmpeg-sha256, with the SHA-256 checksum of the archive"
+                    .to_string(),
+            )
+        })?;
+
+    let base = base_dir();
+    let dir = base.join(&sha256[..16]);
+
+    if !dir.is_dir() {
+        fs::create_dir_all(&base).await?;
+
+        if let Err(e) = install(url, &sha256, &dir).await {
+            error!("Bundled ffmpeg: {e}, use ffmpeg from the system");
+            return Ok(());
+        }
+
+        if let Err(e) = remove_old(&base, &dir).await {
+            error!("Remove old ffmpeg builds: {e}");
+        }
+    }
+
+    let dir = fs::canonicalize(&dir).await?;
+
+    info!(
+        "Use bundled ffmpeg from <b><magenta>{}</></b>",
+        dir.display()
+    );
+
+    BUNDLE.get_or_init(|| dir);
+
+    Ok(())
+}
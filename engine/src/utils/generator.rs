@@ -4,28 +4,63 @@
 ///
 /// The generator takes the files from storage, which are set in config.
 /// It also respect the shuffle/sort mode.
-use std::io::Error;
+use std::{io::Error, path::PathBuf};
 
-use chrono::Timelike;
+use chrono::{Datelike, NaiveDate, Timelike};
 use lexical_sort::{natural_lexical_cmp, StringSort};
 use log::*;
-use rand::{rng, seq::SliceRandom, Rng};
+use rand::{
+    rng,
+    seq::{IndexedRandom, SliceRandom},
+    Rng,
+};
+use sqlx::{Pool, Sqlite};
 use tokio::fs;
 
+use crate::db::handles;
+use crate::file::{utils::filler::select, StorageBackend};
 // use crate::file::utils::
 use crate::player::{
     controller::ChannelManager,
     input::folder::FolderSource,
     utils::{
-        get_date_range, include_file_extension, json_serializer::JsonPlaylist, sum_durations, Media,
+        get_date_range, include_file_extension, json_serializer::JsonPlaylist, rights,
+        sum_durations, Media,
     },
 };
 use crate::utils::{
-    config::{PlayoutConfig, Template},
+    config::{Bumpers, PlayoutConfig, Source, Template},
+    library,
     logging::Target,
     time_to_sec,
 };
 
+/// Next episodes of a series, which fit in the total length.
+///
+/// Continues after the last played episode, and starts again from the beginning,
+/// when the last episode was the end of the series.
+pub fn series_episodes(clip_list: Vec<Media>, last: Option<&str>, total_length: f64) -> Vec<Media> {
+    let start = last
+        .and_then(|l| clip_list.iter().position(|c| c.source == l))
+        .map_or(0, |p| (p + 1) % clip_list.len());
+    let mut episodes = vec![];
+    let mut length = 0.0;
+
+    for clip in clip_list.into_iter().skip(start) {
+        if length + clip.out - clip.seek > total_length {
+            break;
+        }
+
+        length += clip.out - clip.seek;
+        episodes.push(clip);
+    }
+
+    episodes
+}
+
+/// Allowed gap in seconds, between the picked clips and the block length, on best fit.
+pub const BEST_FIT_TOLERANCE: f64 = 1.0;
+
 pub fn random_list(clip_list: Vec<Media>, total_length: f64) -> Vec<Media> {
     let mut max_attempts = 10000;
     let mut randomized_clip_list: Vec<Media> = vec![];
@@ -90,6 +125,55 @@ pub fn ordered_list(clip_list: Vec<Media>, total_length: f64) -> Vec<Media> {
     ordered_clip_list
 }
 
+/// Pick clips, which fill the total length as good as possible.
+///
+/// This is a subset sum in seconds resolution, each clip is used once. It stops early when
+/// the remaining gap is within the tolerance. The order of the clip list is kept.
+pub fn best_fit_list(clip_list: Vec<Media>, total_length: f64, tolerance: f64) -> Vec<Media> {
+    if sum_durations(&clip_list) <= total_length {
+        return clip_list;
+    }
+
+    let target = total_length.floor() as usize;
+    let mut from: Vec<Option<(usize, usize)>> = vec![None; target + 1];
+    let mut best = 0;
+
+    for (i, clip) in clip_list.iter().enumerate() {
+        let length = (clip.out - clip.seek).ceil().max(1.0) as usize;
+
+        for t in (length..=target).rev() {
+            if from[t].is_none() && (t == length || from[t - length].is_some()) {
+                from[t] = Some((i, t - length));
+                best = best.max(t);
+            }
+        }
+
+        if (target - best) as f64 <= tolerance {
+            break;
+        }
+    }
+
+    let mut picked = vec![];
+    let mut t = best;
+
+    while let Some((i, prev)) = from[t] {
+        picked.push(i);
+
+        if prev == 0 {
+            break;
+        }
+
+        t = prev;
+    }
+
+    clip_list
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| picked.contains(i))
+        .map(|(_, c)| c)
+        .collect()
+}
+
 pub async fn filler_list(
     config: &PlayoutConfig,
     manager: &ChannelManager,
@@ -101,91 +185,257 @@ pub async fn filler_list(
         .await
         .fill_filler_list(config, None)
         .await;
-    let mut index = 0;
-    let mut filler_clip_list: Vec<Media> = vec![];
-    let mut target_duration = 0.0;
-    let clip_list_length = filler_list.len();
+    let durations = filler_list.iter().map(|f| f.duration).collect::<Vec<_>>();
+
+    // whole clips first, only the last one gets cut to the block end
+    select(&durations, total_length, true)
+        .into_iter()
+        .map(|(i, out)| {
+            let mut clip = filler_list[i].clone();
+            clip.out = out;
+
+            clip
+        })
+        .collect()
+}
 
-    if clip_list_length > 0 {
-        while target_duration < total_length {
-            if index == clip_list_length {
-                index = 0;
+/// Clips from the given folders, sorted when they are not shuffled later.
+async fn clips_from_paths(
+    config: &PlayoutConfig,
+    storage: &StorageBackend,
+    paths: &[PathBuf],
+    shuffle: bool,
+) -> Vec<Media> {
+    let mut clips = vec![];
+
+    for path in paths {
+        debug!("Search files in <b><magenta>{path:?}</></b>");
+        let mut file_list = vec![];
+
+        match storage.walk_media_dir(path, config).await {
+            Ok(storage_paths) => {
+                for single_path in storage_paths {
+                    if storage.is_file(&single_path).await
+                        && include_file_extension(config, &single_path)
+                        && !config.storage.is_excluded(path, &single_path)
+                    {
+                        let file = single_path.to_string_lossy().to_string();
+                        file_list.push(file);
+                    }
+                }
+            }
+            Err(e) => {
+                error!("{e:?}");
             }
+        }
 
-            let selected_clip = filler_list[index].clone();
+        if !shuffle {
+            file_list.string_sort_unstable(natural_lexical_cmp);
+        }
 
-            target_duration += selected_clip.duration;
-            filler_clip_list.push(selected_clip);
-            index += 1;
+        for entry in file_list {
+            let media = Media::new(0, &entry, true).await;
+            clips.push(media);
+        }
+    }
+
+    clips
+}
+
+/// Clips from the media library, which match the query, in sorted order.
+async fn clips_from_query(config: &PlayoutConfig, pool: &Pool<Sqlite>, query: &str) -> Vec<Media> {
+    let id = config.general.channel_id;
+    let mut clips = vec![];
+
+    debug!(target: Target::all(), channel = id; "Search library with <b><magenta>{query}</></b>");
+
+    let mut file_list = match library::query(pool, id, query).await {
+        Ok(items) => items.into_iter().map(|i| i.path).collect::<Vec<_>>(),
+        Err(e) => {
+            error!(target: Target::all(), channel = id; "Library query <b><magenta>{query}</></b> failed: {e}");
+            vec![]
+        }
+    };
+
+    file_list.string_sort_unstable(natural_lexical_cmp);
+
+    for entry in file_list {
+        let media = Media::new(0, &entry, true).await;
+        clips.push(media);
+    }
+
+    clips
+}
+
+/// Insert bumpers into a block: at its begin, when it is not the first block of the day,
+/// and after every N clips or every M minutes.
+///
+/// Clips at the end, which not fit anymore in the block duration, are removed.
+pub fn insert_bumpers(
+    clip_list: Vec<Media>,
+    bumpers: &[Media],
+    rule: &Bumpers,
+    first_block: bool,
+    duration: f64,
+) -> Vec<Media> {
+    let mut rng = rng();
+    let mut bumper = || {
+        let mut clip = bumpers.choose(&mut rng).cloned().unwrap_or_default();
+        clip.category = "bumper".to_string();
+
+        clip
+    };
+    let mut list = vec![];
+    let mut items = 0;
+    let mut length = 0.0;
+
+    if !first_block {
+        list.push(bumper());
+    }
+
+    for clip in clip_list {
+        if (rule.every_items > 0 && items >= rule.every_items)
+            || (rule.every_minutes > 0.0 && length >= rule.every_minutes * 60.0)
+        {
+            list.push(bumper());
+            items = 0;
+            length = 0.0;
         }
 
-        let over_length = target_duration - total_length;
-        let last_index = filler_clip_list.len() - 1;
+        items += 1;
+        length += clip.out - clip.seek;
+        list.push(clip);
+    }
 
-        filler_clip_list[last_index].out = filler_clip_list[last_index].duration - over_length;
+    while list.len() > 1
+        && (sum_durations(&list) > duration || list.last().is_some_and(|c| c.category == "bumper"))
+    {
+        list.pop();
     }
 
-    filler_clip_list
+    list
 }
 
+/// Generate the program of one day from the template blocks of its weekday.
+///
+/// Each block starts at its start time, gaps between blocks and the rest of the day
+/// are filled with filler, and a block ends at the latest when the next one starts.
 pub async fn generate_from_template(
     config: &PlayoutConfig,
     manager: &ChannelManager,
-    template: Template,
-) -> FolderSource {
+    template: &Template,
+    date: NaiveDate,
+    total_length: f64,
+) -> Vec<Media> {
     let mut media_list = vec![];
     let mut rng = rng();
     let mut index: usize = 0;
     let id = config.general.channel_id;
     let storage = manager.storage.lock().await.clone();
+    let day_start = config.playlist.start_sec.unwrap_or_default();
+    let mut sources: Vec<(f64, Source)> = template
+        .sources_on(date.weekday())
+        .into_iter()
+        .map(|s| {
+            let offset =
+                (s.start.num_seconds_from_midnight() as f64 - day_start).rem_euclid(86400.0);
+
+            (offset, s)
+        })
+        .collect();
+
+    sources.sort_by(|(o1, _), (o2, _)| o1.total_cmp(o2));
+
+    let bumpers = match &template.bumpers {
+        Some(rule) => {
+            clips_from_paths(config, &storage, std::slice::from_ref(&rule.path), false).await
+        }
+        None => vec![],
+    };
+
+    for (i, (offset, source)) in sources.iter().enumerate() {
+        let (offset, source) = (*offset, source.clone());
+        let block_end = sources.get(i + 1).map_or(total_length, |(next, _)| *next);
+        let length = sum_durations(&media_list);
+
+        if offset > length + 1.0 {
+            let mut filler = filler_list(config, manager, offset - length).await;
 
-    for source in template.sources {
-        let mut source_list = vec![];
-        let duration = (source.duration.hour() as f64 * 3600.0)
+            media_list.append(&mut filler);
+        }
+
+        let duration = ((source.duration.hour() as f64 * 3600.0)
             + (source.duration.minute() as f64 * 60.0)
-            + source.duration.second() as f64;
+            + source.duration.second() as f64)
+            .min(block_end - offset);
+
+        if duration <= 0.0 {
+            continue;
+        }
 
         debug!(target: Target::all(), channel = id; "Generating playlist block with <yellow>{duration:.2}</> seconds length");
 
-        for path in source.paths {
-            debug!("Search files in <b><magenta>{path:?}</></b>");
-            let mut file_list = vec![];
-
-            match storage.walk_dir(path).await {
-                Ok(storage_paths) => {
-                    for single_path in storage_paths {
-                        if storage.is_file(&single_path).await
-                            && include_file_extension(config, &single_path)
-                        {
-                            let file = single_path.to_string_lossy().to_string();
-                            file_list.push(file);
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("{e:?}");
-                }
-            }
+        let mut source_list = if source.query.trim().is_empty() {
+            clips_from_paths(
+                config,
+                &storage,
+                &source.paths,
+                source.shuffle && !source.series,
+            )
+            .await
+        } else {
+            clips_from_query(config, &manager.db_pool, &source.query).await
+        };
 
-            // while let Some(Ok(entry)) = entries.next().await {
-            //     if entry.path().is_file() && include_file_extension(config, &entry.path()) {
-            //         let file = entry.path().to_string_lossy().to_string();
+        let block_begin = rights::air_time(config, date, day_start + offset);
+        let mut allowed = vec![];
 
-            //         file_list.push(file);
-            //     }
-            // }
+        for clip in source_list {
+            if !rights::is_blocked(config, &clip.source, &block_begin, duration).await {
+                allowed.push(clip);
+            }
+        }
 
-            if !source.shuffle {
-                file_list.string_sort_unstable(natural_lexical_cmp);
+        source_list = allowed;
+
+        let mut timed_list = if source.series {
+            let key = if source.query.trim().is_empty() {
+                source
+                    .paths
+                    .iter()
+                    .map(|p| p.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(";")
+            } else {
+                source.query.clone()
+            };
+            let last = handles::select_series_episode(&manager.db_pool, id, &key)
+                .await
+                .unwrap_or_default();
+            let episodes = series_episodes(source_list, last.as_deref(), duration);
+
+            match episodes.last() {
+                Some(episode) => {
+                    if let Err(e) =
+                        handles::update_series_episode(&manager.db_pool, id, &key, &episode.source)
+                            .await
+                    {
+                        error!(target: Target::all(), channel = id; "Save series progress failed: {e}");
+                    }
+                }
+                None => {
+                    warn!(target: Target::all(), channel = id; "No episode of series <b><magenta>{key}</></b> fits in block");
+                }
             }
 
-            for entry in file_list {
-                let media = Media::new(0, &entry, true).await;
-                source_list.push(media);
+            episodes
+        } else if source.best_fit {
+            if source.shuffle {
+                source_list.shuffle(&mut rng);
             }
-        }
 
-        let mut timed_list = if source.shuffle {
+            best_fit_list(source_list, duration, BEST_FIT_TOLERANCE)
+        } else if source.shuffle {
             source_list.shuffle(&mut rng);
 
             random_list(source_list, duration)
@@ -193,6 +443,12 @@ pub async fn generate_from_template(
             ordered_list(source_list, duration)
         };
 
+        if let Some(rule) = &template.bumpers {
+            if !bumpers.is_empty() {
+                timed_list = insert_bumpers(timed_list, &bumpers, rule, i == 0, duration);
+            }
+        }
+
         let total_length = sum_durations(&timed_list);
 
         if duration > total_length {
@@ -204,13 +460,21 @@ pub async fn generate_from_template(
         media_list.append(&mut timed_list);
     }
 
+    let length = sum_durations(&media_list);
+
+    if total_length > length + 1.0 {
+        let mut filler = filler_list(config, manager, total_length - length).await;
+
+        media_list.append(&mut filler);
+    }
+
     for item in &mut media_list {
         item.index = Some(index);
 
         index += 1;
     }
 
-    FolderSource::from_list(manager, media_list).await
+    media_list
 }
 
 /// Generate playlists
@@ -232,7 +496,6 @@ pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlay
     let playlist_root = &config.channel.playlists;
     let mut playlists = vec![];
     let mut date_range = vec![];
-    let mut from_template = false;
 
     if !playlist_root.is_dir() {
         error!(
@@ -251,12 +514,10 @@ pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlay
     }
 
     // gives an iterator with infinit length
-    let mut folder_iter = if let Some(template) = &config.general.template {
-        from_template = true;
-
-        generate_from_template(&config, manager, template.clone()).await
+    let mut folder_iter = if config.general.template.is_none() {
+        Some(FolderSource::new(&config, manager.clone()).await)
     } else {
-        FolderSource::new(&config, manager.clone()).await
+        None
     };
 
     let list_length = manager.current_list.lock().await.len();
@@ -269,6 +530,14 @@ pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlay
         let playlist_file = &playlist_path.join(format!("{date}.json"));
         let mut length = 0.0;
         let mut round = 0;
+        let day = match NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+            Ok(d) => d,
+            Err(e) => {
+                error!(target: Target::all(), channel = id; "Invalid date <yellow>{date}</>: {e}");
+
+                continue;
+            }
+        };
 
         fs::create_dir_all(playlist_path).await?;
 
@@ -298,14 +567,19 @@ pub async fn playlist_generator(manager: &ChannelManager) -> Result<Vec<JsonPlay
             program: vec![],
         };
 
-        if from_template {
-            let media_list = manager.current_list.lock().await;
-            playlist.program = media_list.to_vec();
-        } else {
+        if let Some(template) = &config.general.template {
+            playlist.program =
+                generate_from_template(&config, manager, template, day, total_length).await;
+        } else if let Some(folder_iter) = folder_iter.as_mut() {
+            let day_start = config.playlist.start_sec.unwrap_or_default();
+
             while let Some(item) = folder_iter.next().await {
                 let duration = item.duration;
+                let begin = rights::air_time(&config, day, day_start + length);
 
-                if total_length >= length + duration {
+                if total_length >= length + duration
+                    && !rights::is_blocked(&config, &item.source, &begin, duration).await
+                {
                     playlist.program.push(item);
 
                     length += duration;
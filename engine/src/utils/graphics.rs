@@ -0,0 +1,102 @@
+/*
+HTML graphics overlay.
+
+Tickers, clocks or weather widgets are maintained by designers as web page. A sidecar renders
+the page in intervals to a transparent image, which the decoder lays over the program.
+The image gets replaced by a rename, so the overlay never reads a half written file.
+*/
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use log::*;
+use shlex::split;
+use tokio::{fs, process::Command, time::interval};
+
+use crate::player::controller::ChannelManager;
+use crate::utils::{config::PlayoutConfig, logging::Target};
+
+/// Render command, when no custom one is set.
+pub const RENDER_CMD: &str = "wkhtmltoimage --quiet --transparent --format png --width {width} --height {height} {source} {output}";
+
+/// Rendered graphics of the channel.
+pub fn overlay_path(config: &PlayoutConfig) -> PathBuf {
+    config.channel.public.join("graphics.png")
+}
+
+/// Render command with the placeholders replaced, None when it can not be split.
+pub fn render_cmd(config: &PlayoutConfig, output: &Path) -> Option<Vec<String>> {
+    let template = if config.graphics.command.trim().is_empty() {
+        RENDER_CMD
+    } else {
+        &config.graphics.command
+    };
+
+    let args = split(template)?
+        .into_iter()
+        .map(|arg| {
+            arg.replace("{source}", &config.graphics.source)
+                .replace("{output}", &output.to_string_lossy())
+                .replace("{width}", &config.processing.width.to_string())
+                .replace("{height}", &config.processing.height.to_string())
+        })
+        .collect::<Vec<_>>();
+
+    (!args.is_empty()).then_some(args)
+}
+
+/// Render the page in the interval, runs as long as the player.
+pub async fn run(manager: ChannelManager) {
+    let id = manager.id;
+    let config = manager.config.lock().await.clone();
+    let output = overlay_path(&config);
+    let temp = output.with_extension("tmp.png");
+    let mut ticker = interval(Duration::from_secs_f64(config.graphics.interval.max(1.0)));
+    let mut failed = false;
+
+    let Some(cmd) = render_cmd(&config, &temp) else {
+        error!(target: Target::file_mail(), channel = id; "Graphics render command is not valid: <yellow>{}</>", config.graphics.command);
+        return;
+    };
+
+    // graphics from a former run are outdated
+    if let Err(e) = fs::remove_file(&output).await {
+        trace!("{e}");
+    }
+
+    loop {
+        ticker.tick().await;
+
+        match Command::new(&cmd[0])
+            .args(&cmd[1..])
+            .kill_on_drop(true)
+            .output()
+            .await
+        {
+            Ok(out) if out.status.success() => {
+                if let Err(e) = fs::rename(&temp, &output).await {
+                    error!(target: Target::file_mail(), channel = id; "Graphics could not be saved: {e}");
+                } else if failed {
+                    info!(target: Target::file_mail(), channel = id; "Graphics render again");
+                    failed = false;
+                }
+            }
+            Ok(out) => {
+                if !failed {
+                    warn!(target: Target::file_mail(), channel = id;
+                        "Graphics render failed: {}",
+                        String::from_utf8_lossy(&out.stderr).trim()
+                    );
+                }
+
+                failed = true;
+            }
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = id; "Graphics renderer <b><magenta>{}</></b> can not run: {e}", cmd[0]);
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+/*
+Airing history.
+
+Every clip on air is saved with source, title, start time and the duration it really ran.
+Reports over a date range give the aired clips and their play counts, for music royalty
+reporting and advertiser proof-of-play. Start times are in the time zone of the channel.
+*/
+
+use std::sync::Arc;
+
+use chrono::{DateTime, NaiveDate, TimeDelta};
+use log::*;
+use serde_json::Value;
+use sqlx::{Pool, Sqlite};
+use tokio::sync::{broadcast, Mutex};
+
+use crate::db::{handles, models::Airing};
+use crate::utils::{
+    config::PlayoutConfig,
+    errors::ServiceError,
+    events::{EventKind, PlayoutEvent},
+    logging::Target,
+    time_machine::time_now,
+};
+
+pub const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// Range of start times for a report, from `start` until the end of `end`, as dates `YYYY-MM-DD`.
+pub fn date_range(start: &str, end: &str) -> Result<(String, String), ServiceError> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| ServiceError::BadRequest(format!("Invalid date: {date}")))
+    };
+    let start = parse(start)?;
+    let end = parse(end)? + TimeDelta::days(1);
+
+    if end <= start {
+        return Err(ServiceError::BadRequest(
+            "End date is before start date".to_string(),
+        ));
+    }
+
+    Ok((start.to_string(), end.to_string()))
+}
+
+/// Airings as CSV, one line per clip.
+pub fn csv(airings: &[Airing]) -> Result<String, ServiceError> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+
+    writer
+        .write_record([
+            "started_at",
+            "source",
+            "title",
+            "artist",
+            "category",
+            "planned",
+            "duration",
+        ])
+        .map_err(|e| ServiceError::Conflict(e.to_string()))?;
+
+    for airing in airings {
+        writer
+            .write_record([
+                airing.started_at.clone(),
+                airing.source.clone(),
+                airing.title.clone(),
+                airing.artist.clone(),
+                airing.category.clone(),
+                format!("{:.3}", airing.planned),
+                airing
+                    .duration
+                    .map(|d| format!("{d:.3}"))
+                    .unwrap_or_default(),
+            ])
+            .map_err(|e| ServiceError::Conflict(e.to_string()))?;
+    }
+
+    let data = writer
+        .into_inner()
+        .map_err(|e| ServiceError::Conflict(e.to_string()))?;
+
+    Ok(String::from_utf8_lossy(&data).to_string())
+}
+
+fn event_time(event: &PlayoutEvent) -> Option<DateTime<chrono::FixedOffset>> {
+    DateTime::parse_from_rfc3339(&event.time).ok()
+}
+
+fn text(data: &Value, key: &str) -> String {
+    data[key].as_str().unwrap_or_default().to_string()
+}
+
+/// Save the clips of the channel events into the airing history.
+///
+/// A clip gets its duration at its end, or at the start of the next clip, when the end is missing.
+pub async fn run(
+    pool: Pool<Sqlite>,
+    config: Arc<Mutex<PlayoutConfig>>,
+    mut events: broadcast::Receiver<PlayoutEvent>,
+) {
+    let mut on_air: Option<(i64, DateTime<chrono::FixedOffset>)> = None;
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                warn!("Airing history missed <yellow>{count}</> events");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let Some(time) = event_time(&event) else {
+            continue;
+        };
+
+        if matches!(
+            event.event,
+            EventKind::ClipStart | EventKind::ClipEnd | EventKind::ChannelStop
+        ) {
+            if let Some((id, start)) = on_air.take() {
+                let duration = (time - start).num_milliseconds() as f64 / 1000.0;
+
+                if let Err(e) = handles::update_airing_duration(&pool, id, duration).await {
+                    error!(target: Target::file_mail(), channel = event.channel; "Save airing duration failed: {e}");
+                }
+            }
+        }
+
+        if event.event != EventKind::ClipStart {
+            continue;
+        }
+
+        let tz = time_now(&config.lock().await.channel.timezone).timezone();
+        let data = &event.data;
+        let airing = Airing {
+            channel_id: event.channel,
+            source: text(data, "source"),
+            title: text(data, "title"),
+            artist: text(data, "artist"),
+            category: text(data, "category"),
+            started_at: time.with_timezone(&tz).format(TIME_FORMAT).to_string(),
+            planned: data["out"].as_f64().unwrap_or_default()
+                - data["in"].as_f64().unwrap_or_default(),
+            ..Default::default()
+        };
+
+        match handles::insert_airing(&pool, &airing).await {
+            Ok(id) => on_air = Some((id, time)),
+            Err(e) => {
+                error!(target: Target::file_mail(), channel = event.channel; "Save airing failed: {e}");
+            }
+        }
+    }
+}
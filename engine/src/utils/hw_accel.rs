@@ -0,0 +1,323 @@
+/*
+Hardware acceleration profiles.
+
+A profile sets everything, which is needed for NVENC, QuickSync or VAAPI: hardware decoding
+of the clips, the hardware filters for deinterlace, scale and logo overlay, the intermediate
+codec between decoder and encoder and the hardware encoder for the output. Settings in the
+advanced config have priority, so single parts can still be tuned by hand.
+
+The filter builder takes the frames out of the GPU memory only for filters, which have no
+hardware version. Logo, graphics and text are drawn in software on their own layer, which
+the hardware overlay of the profile puts over the program.
+*/
+
+use std::{path::Path, process::Stdio};
+
+use shlex::split;
+use tokio::process::Command;
+
+use crate::utils::{
+    advanced_config::AdvancedConfig,
+    config::{HwAccel, PlayoutConfig, Processing},
+    ffmpeg_bundle,
+};
+use crate::vec_strings;
+
+/// Render node for VAAPI, when no device is configured.
+pub const DEFAULT_RENDER_NODE: &str = "/dev/dri/renderD128";
+
+/// Name of the hwaccel in `ffmpeg -hwaccels`.
+pub fn hwaccel_name(accel: &HwAccel) -> Option<&'static str> {
+    match accel {
+        HwAccel::None => None,
+        HwAccel::Nvenc => Some("cuda"),
+        HwAccel::Qsv => Some("qsv"),
+        HwAccel::Vaapi => Some("vaapi"),
+    }
+}
+
+/// Input parameters for hardware decoding, the frames stay in the GPU memory.
+pub fn input_param(accel: &HwAccel, device: &str) -> Option<String> {
+    let param = match accel {
+        HwAccel::None => return None,
+        HwAccel::Nvenc if device.is_empty() => {
+            "-hwaccel cuda -hwaccel_output_format cuda".to_string()
+        }
+        HwAccel::Nvenc => {
+            format!("-hwaccel cuda -hwaccel_device {device} -hwaccel_output_format cuda")
+        }
+        HwAccel::Qsv if device.is_empty() => {
+            "-hwaccel qsv -init_hw_device qsv=hw -filter_hw_device hw -hwaccel_output_format qsv"
+                .to_string()
+        }
+        HwAccel::Qsv => format!(
+            "-hwaccel qsv -init_hw_device qsv=hw:hw_any,child_device={device} -filter_hw_device hw -hwaccel_output_format qsv"
+        ),
+        HwAccel::Vaapi => format!(
+            "-hwaccel vaapi -vaapi_device {} -hwaccel_output_format vaapi",
+            if device.is_empty() {
+                DEFAULT_RENDER_NODE
+            } else {
+                device
+            }
+        ),
+    };
+
+    Some(param)
+}
+
+/// Filter, which moves software frames into the GPU memory.
+pub fn upload(accel: &HwAccel) -> Option<&'static str> {
+    match accel {
+        HwAccel::None => None,
+        HwAccel::Nvenc => Some("hwupload_cuda"),
+        HwAccel::Qsv => Some("hwupload=extra_hw_frames=64"),
+        HwAccel::Vaapi => Some("hwupload"),
+    }
+}
+
+/// Overlay filter of the profile.
+pub fn overlay(accel: &HwAccel) -> Option<&'static str> {
+    match accel {
+        HwAccel::None => None,
+        HwAccel::Nvenc => Some("overlay_cuda"),
+        HwAccel::Qsv => Some("overlay_qsv"),
+        HwAccel::Vaapi => Some("overlay_vaapi"),
+    }
+}
+
+/// Upload of a layer with alpha channel, in the pixel format which the hardware overlay takes.
+pub fn overlay_upload(accel: &HwAccel) -> Option<String> {
+    let format = match accel {
+        HwAccel::None => return None,
+        HwAccel::Nvenc => "yuva420p",
+        HwAccel::Qsv => "bgra",
+        HwAccel::Vaapi => "rgba",
+    };
+
+    upload(accel).map(|u| format!("format={format},{u}"))
+}
+
+/// Video parameters of the intermediate stream from decoder to encoder.
+pub fn intermediate_cmd(accel: &HwAccel, processing: &Processing) -> Option<Vec<String>> {
+    let bitrate = format!("{}k", processing.width * processing.height / 16);
+    let buff_size = format!("{}k", (processing.width * processing.height / 16) / 2);
+    let mut cmd = match accel {
+        HwAccel::None => return None,
+        HwAccel::Nvenc => vec_strings!["-c:v", "h264_nvenc", "-preset", "p2", "-tune", "ll"],
+        HwAccel::Qsv => vec_strings!["-c:v", "mpeg2_qsv"],
+        HwAccel::Vaapi => vec_strings!["-c:v", "mpeg2_vaapi"],
+    };
+
+    cmd.append(&mut vec_strings![
+        "-r",
+        &processing.fps,
+        "-g",
+        "1",
+        "-b:v",
+        &bitrate,
+        "-minrate",
+        &bitrate,
+        "-maxrate",
+        &bitrate,
+        "-bufsize",
+        &buff_size,
+        "-mpegts_flags",
+        "initial_discontinuity"
+    ]);
+
+    Some(cmd)
+}
+
+/// Hardware encoder for a software video codec of the output.
+pub fn encoder(accel: &HwAccel, codec: &str) -> Option<String> {
+    let prefix = match codec {
+        "libx264" | "h264" => "h264",
+        "libx265" | "hevc" => "hevc",
+        "mpeg2video" => "mpeg2",
+        _ => return None,
+    };
+    let suffix = match accel {
+        HwAccel::None => return None,
+        HwAccel::Nvenc if prefix == "mpeg2" => return None,
+        HwAccel::Nvenc => "nvenc",
+        HwAccel::Qsv => "qsv",
+        HwAccel::Vaapi => "vaapi",
+    };
+
+    Some(format!("{prefix}_{suffix}"))
+}
+
+/// Take the profile for all parts, which are not set in the advanced config.
+pub fn apply(processing: &Processing, advanced: &mut AdvancedConfig) {
+    let accel = &processing.hw_accel;
+    let Some(input) = input_param(accel, &processing.hw_device) else {
+        return;
+    };
+    let filter = &mut advanced.filter;
+
+    for input_param in [
+        &mut advanced.decoder.input_param,
+        &mut advanced.encoder.input_param,
+        &mut advanced.ingest.input_param,
+    ] {
+        if input_param.is_none() {
+            *input_param = Some(input.clone());
+        }
+    }
+
+    advanced.decoder.input_cmd = advanced.decoder.input_param.as_deref().and_then(split);
+    advanced.encoder.input_cmd = advanced.encoder.input_param.as_deref().and_then(split);
+    advanced.ingest.input_cmd = advanced.ingest.input_param.as_deref().and_then(split);
+
+    // the logo gets scaled in software, before it goes into the GPU memory
+    let (deinterlace, fps, scale) = match accel {
+        HwAccel::Nvenc => ("yadif_cuda=0:-1:0", None, "scale_cuda={}:{}:format=yuv420p"),
+        HwAccel::Qsv => (
+            "deinterlace_qsv",
+            Some("vpp_qsv=framerate={}"),
+            "scale_qsv={}:{}",
+        ),
+        _ => ("deinterlace_vaapi", None, "scale_vaapi={}:{}"),
+    };
+
+    filter.deinterlace.get_or_insert(deinterlace.to_string());
+    filter.scale.get_or_insert(scale.to_string());
+
+    if let Some(overlay) = overlay(accel) {
+        filter
+            .overlay_logo
+            .get_or_insert(format!("{overlay}={{}}:shortest=1"));
+    }
+
+    if let Some(fps) = fps {
+        filter.fps.get_or_insert(fps.to_string());
+    }
+}
+
+/// Switch the video codecs of the output to the hardware encoder.
+///
+/// Options which only the software encoders know are removed or mapped.
+pub fn encoder_cmd(accel: &HwAccel, cmd: &[String]) -> Vec<String> {
+    if *accel == HwAccel::None {
+        return cmd.to_vec();
+    }
+
+    let mut new_cmd = vec![];
+    let mut iter = cmd.iter();
+
+    while let Some(param) = iter.next() {
+        let key = param.split(':').next().unwrap_or_default();
+
+        match key {
+            "-c" | "-codec" | "-vcodec" if param.contains(":v") || key == "-vcodec" => {
+                let codec = iter.next().cloned().unwrap_or_default();
+
+                new_cmd.push(param.clone());
+                new_cmd.push(encoder(accel, &codec).unwrap_or(codec));
+            }
+            "-x264-params" | "-x265-params" | "-crf" | "-pix_fmt" => {
+                iter.next();
+            }
+            "-tune" => {
+                iter.next();
+
+                if *accel == HwAccel::Nvenc {
+                    new_cmd.append(&mut vec_strings![param, "ll"]);
+                }
+            }
+            "-preset" => {
+                let preset = iter.next().cloned().unwrap_or_default();
+
+                match accel {
+                    HwAccel::Nvenc if preset.starts_with('p') && preset.len() == 2 => {
+                        new_cmd.append(&mut vec_strings![param, preset]);
+                    }
+                    HwAccel::Nvenc => new_cmd.append(&mut vec_strings![param, "p4"]),
+                    HwAccel::Qsv => new_cmd.append(&mut vec_strings![param, preset]),
+                    _ => {}
+                }
+            }
+            "-profile" => {
+                let profile = iter.next().cloned().unwrap_or_default();
+
+                new_cmd.append(&mut vec_strings![param, profile.to_lowercase()]);
+            }
+            "-level" if *accel != HwAccel::Nvenc => {
+                iter.next();
+            }
+            _ => new_cmd.push(param.clone()),
+        }
+    }
+
+    new_cmd
+}
+
+/// Output of a list option of ffmpeg, like `-encoders`.
+pub async fn ffmpeg_list(arg: &str) -> Result<String, String> {
+    let output = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(["-hide_banner", arg])
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("couldn't spawn ffmpeg process: {e}"))?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Check at start, that ffmpeg and the system support the profile.
+pub async fn detect(config: &PlayoutConfig) -> Result<(), String> {
+    let accel = &config.processing.hw_accel;
+    let Some(name) = hwaccel_name(accel) else {
+        return Ok(());
+    };
+
+    let hwaccels = ffmpeg_list("-hwaccels").await?;
+
+    if !hwaccels.lines().any(|l| l.trim() == name) {
+        return Err(format!("ffmpeg has no {name} hardware acceleration!"));
+    }
+
+    let encoders = ffmpeg_list("-encoders").await?;
+    let mut needed = vec![];
+
+    if let Some(cmd) = &config.processing.cmd {
+        needed.extend(
+            cmd.windows(2)
+                .filter(|w| w[0] == "-c:v")
+                .map(|w| w[1].clone()),
+        );
+    }
+
+    if let Some(cmd) = &config.output.output_cmd {
+        needed.extend(
+            cmd.windows(2)
+                .filter(|w| w[0].starts_with("-c:v") || w[0] == "-vcodec")
+                .map(|w| w[1].clone())
+                .filter(|c| c.ends_with(name) || c.ends_with("_nvenc")),
+        );
+    }
+
+    for codec in needed {
+        if !encoders.split_whitespace().any(|e| e == codec) {
+            return Err(format!("ffmpeg contains no {codec} encoder!"));
+        }
+    }
+
+    let device = &config.processing.hw_device;
+
+    if matches!(accel, HwAccel::Qsv | HwAccel::Vaapi) {
+        let node = if device.is_empty() {
+            DEFAULT_RENDER_NODE
+        } else {
+            device
+        };
+
+        if (*accel == HwAccel::Vaapi || !device.is_empty()) && !Path::new(node).exists() {
+            return Err(format!("Render node {node} not found!"));
+        }
+    }
+
+    Ok(())
+}
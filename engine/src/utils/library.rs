@@ -0,0 +1,548 @@
+/*
+Media library.
+
+The storage of a channel gets indexed into the media_library table: probe data, embedded tags,
+loudness and a checksum per file. A background scanner keeps the index up to date. Only new
+and changed files, by size and modification time, are probed again, removed files leave the index.
+
+Queries like `tag:music AND duration<300` select files from the index, so folder mode and
+the playlist generator can take themed clips from one physical library.
+*/
+
+use std::{
+    collections::{HashMap, HashSet},
+    io::SeekFrom,
+    path::Path,
+    str::FromStr,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use async_walkdir::WalkDir;
+use futures_util::StreamExt;
+use log::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{types::Json, Pool, Sqlite};
+use tokio::{
+    fs::File,
+    io::{self, AsyncReadExt, AsyncSeekExt},
+    sync::Mutex,
+    time::sleep,
+};
+
+use crate::db::{
+    handles,
+    models::{LibraryItem, LibraryQuery},
+};
+use crate::player::utils::{
+    fps_calc, include_file_extension,
+    loudness::{analyze, file_version},
+    probe::MediaProbe,
+};
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, logging::Target};
+
+/// Bytes from start and end of a file, which go into the checksum.
+const CHECKSUM_BLOCK: u64 = 1024 * 1024;
+
+/// Channels with a running scan.
+static IN_PROGRESS: LazyLock<Mutex<HashSet<i32>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct ScanResult {
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+/// SHA-256 over size, start and end of the file.
+///
+/// Media files are big, hashing the whole content would take too long for a scan.
+pub async fn checksum(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path).await?;
+    let size = file.metadata().await?.len();
+    let mut hasher = Sha256::new();
+    let mut block = vec![];
+
+    hasher.update(size.to_le_bytes());
+    (&mut file)
+        .take(CHECKSUM_BLOCK)
+        .read_to_end(&mut block)
+        .await?;
+    hasher.update(&block);
+
+    if size > CHECKSUM_BLOCK * 2 {
+        block.clear();
+        file.seek(SeekFrom::End(-(CHECKSUM_BLOCK as i64))).await?;
+        (&mut file)
+            .take(CHECKSUM_BLOCK)
+            .read_to_end(&mut block)
+            .await?;
+        hasher.update(&block);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// Library entry from the probe data, files which can not be probed get only size and checksum.
+pub fn library_item(channel_id: i32, path: &str, probe: Option<&MediaProbe>) -> LibraryItem {
+    let mut item = LibraryItem {
+        channel_id,
+        path: path.to_string(),
+        ..Default::default()
+    };
+
+    let Some(probe) = probe else {
+        return item;
+    };
+
+    let tag = |key: &str| probe.format.tags.get(key).cloned().unwrap_or_default();
+
+    item.duration = probe.format.duration.unwrap_or_default();
+    item.title = tag("title");
+    item.artist = tag("artist");
+    item.album = tag("album");
+    item.genre = tag("genre");
+    item.tags = Json(probe.format.tags.clone());
+
+    if let Some(video) = probe.video.first() {
+        item.video_codec = video.codec_name.clone().unwrap_or_default();
+        item.width = video.width.unwrap_or_default();
+        item.height = video.height.unwrap_or_default();
+        item.fps = fps_calc(&video.frame_rate, 0.0);
+    }
+
+    if let Some(audio) = probe.audio.first() {
+        item.audio_codec = audio.codec_name.clone().unwrap_or_default();
+    }
+
+    item
+}
+
+async fn index_file(
+    pool: &Pool<Sqlite>,
+    config: &PlayoutConfig,
+    path: &str,
+    size: i64,
+    modified: i64,
+) -> LibraryItem {
+    let probe = MediaProbe::new(path).await.ok();
+    let mut item = library_item(config.general.channel_id, path, probe.as_ref());
+
+    item.size = size;
+    item.modified = modified;
+    item.checksum = checksum(Path::new(path)).await.unwrap_or_default();
+    item.loudness = handles::select_loudness(pool, path, size, modified)
+        .await
+        .ok()
+        .map(|l| l.input_i);
+
+    // with loudness normalization the values are needed for playout anyway
+    if item.loudness.is_none()
+        && config.processing.loudnorm
+        && probe.is_some_and(|p| !p.audio.is_empty())
+    {
+        if let Ok(mut loudness) = analyze(config, path).await {
+            loudness.source = path.to_string();
+            loudness.size = size;
+            loudness.modified = modified;
+            item.loudness = Some(loudness.input_i);
+
+            if let Err(e) = handles::insert_loudness(pool, &loudness).await {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "Save loudness failed: {e}");
+            }
+        }
+    }
+
+    item
+}
+
+async fn scan_storage(
+    pool: &Pool<Sqlite>,
+    config: &PlayoutConfig,
+) -> Result<ScanResult, ServiceError> {
+    let id = config.general.channel_id;
+    let root = &config.channel.storage;
+    let mut result = ScanResult::default();
+    let mut known = handles::select_library_versions(pool, id)
+        .await?
+        .into_iter()
+        .map(|(path, size, modified)| (path, (size, modified)))
+        .collect::<HashMap<_, _>>();
+    let mut entries = WalkDir::new(root);
+
+    while let Some(Ok(entry)) = entries.next().await {
+        let path = entry.path();
+
+        if !path.is_file()
+            || !include_file_extension(config, &path)
+            || config.storage.is_excluded(root, &path)
+        {
+            continue;
+        }
+
+        let path = path.to_string_lossy().to_string();
+        let version = file_version(&path).await;
+
+        match known.remove(&path) {
+            Some(v) if v == version => continue,
+            Some(_) => result.updated += 1,
+            None => result.added += 1,
+        }
+
+        let item = index_file(pool, config, &path, version.0, version.1).await;
+        handles::upsert_library_item(pool, &item).await?;
+    }
+
+    for path in known.keys() {
+        handles::delete_library_item(pool, id, path).await?;
+        result.removed += 1;
+    }
+
+    Ok(result)
+}
+
+/// Index new and changed files of the channel storage, and remove the missing ones.
+pub async fn scan(pool: &Pool<Sqlite>, config: &PlayoutConfig) -> Result<ScanResult, ServiceError> {
+    let id = config.general.channel_id;
+
+    if !config.channel.storage.is_dir() {
+        return Err(ServiceError::Conflict(
+            "Media library needs a local storage".to_string(),
+        ));
+    }
+
+    if !IN_PROGRESS.lock().await.insert(id) {
+        return Err(ServiceError::Conflict(
+            "Media library scan is running".to_string(),
+        ));
+    }
+
+    let result = scan_storage(pool, config).await;
+
+    IN_PROGRESS.lock().await.remove(&id);
+
+    if let Ok(r) = &result {
+        if r.added + r.updated + r.removed > 0 {
+            info!(target: Target::file_mail(), channel = id;
+                "Media library: <yellow>{}</> added, <yellow>{}</> updated, <yellow>{}</> removed",
+                r.added, r.updated, r.removed
+            );
+        }
+    }
+
+    result
+}
+
+/// Fields with numbers, which can be compared.
+const NUMBER_FIELDS: [&str; 7] = [
+    "duration", "width", "height", "fps", "loudness", "size", "modified",
+];
+
+/// Embedded tags, which hold categories of a file.
+const CATEGORY_TAGS: [&str; 4] = ["genre", "category", "keywords", "tags"];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+}
+
+/// Query on the media library, like `tag:music AND duration<300`.
+///
+/// Terms are `tag:<category>`, `<field>:<text>`, `<number field><op><value>` and plain text,
+/// combined with `AND`, `OR`, `NOT` and parentheses. Terms without operator are joined with `AND`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LibraryFilter {
+    And(Box<LibraryFilter>, Box<LibraryFilter>),
+    Or(Box<LibraryFilter>, Box<LibraryFilter>),
+    Not(Box<LibraryFilter>),
+    /// Category from genre or the embedded category tags.
+    Tag(String),
+    /// Text in a field, empty field means path, title, artist or album.
+    Text(String, String),
+    /// Number field, operator and value.
+    Compare(String, String, f64),
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut word = String::new();
+    let mut quoted = false;
+
+    let push = |word: &mut String, tokens: &mut Vec<Token>| {
+        if !word.is_empty() {
+            tokens.push(Token::Word(std::mem::take(word)));
+        }
+    };
+
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            _ if quoted => word.push(c),
+            '(' | ')' => {
+                push(&mut word, &mut tokens);
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            _ if c.is_whitespace() => push(&mut word, &mut tokens),
+            _ => word.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("Quote is not closed".to_string());
+    }
+
+    push(&mut word, &mut tokens);
+
+    Ok(tokens)
+}
+
+fn term(word: &str) -> Result<LibraryFilter, String> {
+    if let Some((field, value)) = word.split_once(':') {
+        let field = field.to_lowercase();
+
+        return Ok(match field.as_str() {
+            "tag" => LibraryFilter::Tag(value.to_lowercase()),
+            f if NUMBER_FIELDS.contains(&f) => LibraryFilter::Compare(
+                field,
+                "=".to_string(),
+                value
+                    .parse()
+                    .map_err(|_| format!("No number in '{word}'"))?,
+            ),
+            _ => LibraryFilter::Text(field, value.to_lowercase()),
+        });
+    }
+
+    if let Some(pos) = word.find(['<', '>', '=']) {
+        let field = word[..pos].to_lowercase();
+        let rest = &word[pos..];
+        let op_len = if rest.starts_with("<=") || rest.starts_with(">=") {
+            2
+        } else {
+            1
+        };
+
+        if !NUMBER_FIELDS.contains(&field.as_str()) {
+            return Err(format!("Field '{field}' can not be compared"));
+        }
+
+        let value = rest[op_len..]
+            .parse()
+            .map_err(|_| format!("No number in '{word}'"))?;
+
+        return Ok(LibraryFilter::Compare(
+            field,
+            rest[..op_len].to_string(),
+            value,
+        ));
+    }
+
+    Ok(LibraryFilter::Text(String::new(), word.to_lowercase()))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn keyword(&self, key: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Word(w)) if w.eq_ignore_ascii_case(key))
+    }
+
+    fn or(&mut self) -> Result<LibraryFilter, String> {
+        let mut filter = self.and()?;
+
+        while self.keyword("or") {
+            self.pos += 1;
+            filter = LibraryFilter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<LibraryFilter, String> {
+        let mut filter = self.not()?;
+
+        while self.pos < self.tokens.len()
+            && self.tokens[self.pos] != Token::Close
+            && !self.keyword("or")
+        {
+            if self.keyword("and") {
+                self.pos += 1;
+            }
+
+            filter = LibraryFilter::And(Box::new(filter), Box::new(self.not()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn not(&mut self) -> Result<LibraryFilter, String> {
+        if self.keyword("not") {
+            self.pos += 1;
+
+            return Ok(LibraryFilter::Not(Box::new(self.not()?)));
+        }
+
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<LibraryFilter, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+
+        match token {
+            Some(Token::Open) => {
+                let filter = self.or()?;
+
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    return Err("Parenthesis is not closed".to_string());
+                }
+
+                self.pos += 1;
+
+                Ok(filter)
+            }
+            Some(Token::Word(word)) => term(&word),
+            Some(Token::Close) => Err("Unexpected ')'".to_string()),
+            None => Err("Query ends too early".to_string()),
+        }
+    }
+}
+
+impl FromStr for LibraryFilter {
+    type Err = String;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(query)?,
+            pos: 0,
+        };
+        let filter = parser.or()?;
+
+        if parser.pos < parser.tokens.len() {
+            return Err("Unexpected ')'".to_string());
+        }
+
+        Ok(filter)
+    }
+}
+
+impl LibraryFilter {
+    pub fn matches(&self, item: &LibraryItem) -> bool {
+        match self {
+            Self::And(a, b) => a.matches(item) && b.matches(item),
+            Self::Or(a, b) => a.matches(item) || b.matches(item),
+            Self::Not(f) => !f.matches(item),
+            Self::Tag(tag) => CATEGORY_TAGS
+                .iter()
+                .filter_map(|key| item.tags.get(*key))
+                .chain([&item.genre])
+                .flat_map(|value| value.split([',', ';', '/']))
+                .any(|category| category.trim().to_lowercase() == *tag),
+            Self::Text(field, text) => {
+                let values = match field.as_str() {
+                    "" => vec![&item.path, &item.title, &item.artist, &item.album],
+                    "path" => vec![&item.path],
+                    "title" => vec![&item.title],
+                    "artist" => vec![&item.artist],
+                    "album" => vec![&item.album],
+                    "genre" => vec![&item.genre],
+                    "codec" => vec![&item.video_codec, &item.audio_codec],
+                    key => item.tags.get(key).into_iter().collect(),
+                };
+
+                values.iter().any(|v| v.to_lowercase().contains(text))
+            }
+            Self::Compare(field, op, value) => {
+                let number = match field.as_str() {
+                    "duration" => Some(item.duration),
+                    "width" => Some(item.width as f64),
+                    "height" => Some(item.height as f64),
+                    "fps" => Some(item.fps),
+                    "loudness" => item.loudness,
+                    "size" => Some(item.size as f64),
+                    "modified" => Some(item.modified as f64),
+                    _ => None,
+                };
+
+                number.is_some_and(|n| match op.as_str() {
+                    "<" => n < *value,
+                    "<=" => n <= *value,
+                    ">" => n > *value,
+                    ">=" => n >= *value,
+                    _ => (n - value).abs() < 0.001,
+                })
+            }
+        }
+    }
+}
+
+/// Files of the channel library, which match the query.
+pub async fn query(
+    pool: &Pool<Sqlite>,
+    channel_id: i32,
+    query: &str,
+) -> Result<Vec<LibraryItem>, ServiceError> {
+    let filter = LibraryFilter::from_str(query).map_err(ServiceError::BadRequest)?;
+    let items = handles::select_library_items(pool, channel_id).await?;
+
+    Ok(items.into_iter().filter(|i| filter.matches(i)).collect())
+}
+
+/// Search with the filters of the library API, the tag query is applied after the database filters.
+pub async fn search(
+    pool: &Pool<Sqlite>,
+    channel_id: i32,
+    search: &LibraryQuery,
+) -> Result<Vec<LibraryItem>, ServiceError> {
+    if search.query.trim().is_empty() {
+        return Ok(handles::select_library(pool, channel_id, search).await?);
+    }
+
+    let filter = LibraryFilter::from_str(&search.query).map_err(ServiceError::BadRequest)?;
+    let all = LibraryQuery {
+        limit: i64::MAX,
+        offset: 0,
+        ..search.clone()
+    };
+    let limit = if search.limit > 0 { search.limit } else { 100 };
+
+    Ok(handles::select_library(pool, channel_id, &all)
+        .await?
+        .into_iter()
+        .filter(|i| filter.matches(i))
+        .skip(search.offset.max(0) as usize)
+        .take(limit as usize)
+        .collect())
+}
+
+/// Scan the storage in the interval from config, runs as long as the channel exists.
+///
+/// The config is read before every scan, so changes apply without restart.
+pub async fn run(pool: Pool<Sqlite>, config: Arc<Mutex<PlayoutConfig>>) {
+    // the channel manager holds the other reference
+    while Arc::strong_count(&config) > 1 {
+        let config = config.lock().await.clone();
+        let interval = config.storage.library_interval;
+
+        if interval <= 0.0 {
+            sleep(Duration::from_secs(60)).await;
+            continue;
+        }
+
+        if let Err(e) = scan(&pool, &config).await {
+            debug!(target: Target::file_mail(), channel = config.general.channel_id; "Media library scan: {e}");
+        }
+
+        sleep(Duration::from_secs_f64(interval * 60.0)).await;
+    }
+}
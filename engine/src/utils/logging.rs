@@ -3,7 +3,7 @@ use std::{
     env,
     io::{self, ErrorKind, Write},
     path::PathBuf,
-    sync::{Arc, RwLock},
+    sync::{Arc, LazyLock, RwLock},
 };
 
 use flexi_logger::{
@@ -12,9 +12,13 @@ use flexi_logger::{
     WriteMode,
 };
 
-use log::{kv::Value, *};
+use log::{
+    kv::{self, Key, Value, VisitSource},
+    *,
+};
 use paris::formatter::colorize_string;
 use regex::Regex;
+use serde_json::{json, Map};
 use tokio::sync::Mutex;
 
 use super::ARGS;
@@ -26,6 +30,39 @@ use crate::utils::{
 };
 
 const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.6f%:z";
+const JSON_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.6f%:z";
+
+static LOG_FORMAT: LazyLock<LogFormat> =
+    LazyLock::new(|| LogFormat::new(ARGS.log_format.as_deref()));
+
+/// Format of the log output, JSON lines are for log pipelines like Loki or ELK.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+    /// Text as usual, JSON in addition: to stdout on console, or to `*.json` files.
+    Both,
+}
+
+impl LogFormat {
+    pub fn new(s: Option<&str>) -> Self {
+        match s.map(str::to_lowercase).as_deref() {
+            Some("json") => Self::Json,
+            Some("both") => Self::Both,
+            _ => Self::Text,
+        }
+    }
+
+    /// File formats to write, true is JSON.
+    fn files(&self) -> &[bool] {
+        match self {
+            Self::Text => &[false],
+            Self::Json => &[true],
+            Self::Both => &[false, true],
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Target;
@@ -60,9 +97,26 @@ pub struct LogConsole;
 
 impl LogWriter for LogConsole {
     fn write(&self, now: &mut DeferredNow, record: &Record<'_>) -> std::io::Result<()> {
-        console_formatter(&mut std::io::stderr(), now, record)?;
+        match *LOG_FORMAT {
+            LogFormat::Text => {
+                console_formatter(&mut std::io::stderr(), now, record)?;
+
+                println!();
+            }
+            LogFormat::Json => {
+                json_formatter(&mut std::io::stdout(), now, record)?;
+
+                println!();
+            }
+            LogFormat::Both => {
+                console_formatter(&mut std::io::stderr(), now, record)?;
+                eprintln!();
+
+                json_formatter(&mut std::io::stdout(), now, record)?;
+                println!();
+            }
+        }
 
-        println!();
         Ok(())
     }
     fn flush(&self) -> std::io::Result<()> {
@@ -72,48 +126,54 @@ impl LogWriter for LogConsole {
 
 pub struct MultiFileLogger {
     log_path: PathBuf,
-    writers: RwLock<HashMap<i32, Arc<FileLogWriter>>>,
+    format: LogFormat,
+    writers: RwLock<HashMap<(i32, bool), Arc<FileLogWriter>>>,
 }
 
 impl MultiFileLogger {
-    pub fn new(log_path: PathBuf) -> Self {
+    pub fn new(log_path: PathBuf, format: LogFormat) -> Self {
         Self {
             log_path,
+            format,
             writers: RwLock::new(HashMap::new()),
         }
     }
 
-    fn get_writer(&self, channel: i32) -> io::Result<Arc<FileLogWriter>> {
+    fn get_writer(&self, channel: i32, json: bool) -> io::Result<Arc<FileLogWriter>> {
         // Lock the writers HashMap
         let mut writers = self.writers.write().unwrap();
 
         // Check if the writer already exists
-        if let hash_map::Entry::Vacant(entry) = writers.entry(channel) {
-            let writer = FileLogWriter::builder(
-                FileSpec::default()
-                    .suppress_timestamp()
-                    .directory(&self.log_path)
-                    .basename("ffplayout")
-                    .discriminant(channel.to_string()),
-            )
-            .format(file_formatter)
-            .append()
-            .rotate(
-                Criterion::Age(Age::Day),
-                Naming::TimestampsCustomFormat {
-                    current_infix: Some(""),
-                    format: "%Y-%m-%d",
-                },
-                Cleanup::KeepLogFiles(ARGS.log_backup_count.unwrap_or(14)),
-            )
-            .try_build()
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        if let hash_map::Entry::Vacant(entry) = writers.entry((channel, json)) {
+            let mut file_spec = FileSpec::default()
+                .suppress_timestamp()
+                .directory(&self.log_path)
+                .basename("ffplayout")
+                .discriminant(channel.to_string());
+
+            if json && self.format == LogFormat::Both {
+                file_spec = file_spec.suffix("json");
+            }
+
+            let writer = FileLogWriter::builder(file_spec)
+                .format(if json { json_formatter } else { file_formatter })
+                .append()
+                .rotate(
+                    Criterion::Age(Age::Day),
+                    Naming::TimestampsCustomFormat {
+                        current_infix: Some(""),
+                        format: "%Y-%m-%d",
+                    },
+                    Cleanup::KeepLogFiles(ARGS.log_backup_count.unwrap_or(14)),
+                )
+                .try_build()
+                .map_err(|e| io::Error::other(e.to_string()))?;
 
             let arc_writer = Arc::new(writer);
             entry.insert(arc_writer.clone());
             Ok(arc_writer)
         } else {
-            Ok(writers.get(&channel).unwrap().clone())
+            Ok(writers.get(&(channel, json)).unwrap().clone())
         }
     }
 }
@@ -129,8 +189,11 @@ impl LogWriter for MultiFileLogger {
         )
         .unwrap_or(0);
 
-        let writer = self.get_writer(channel)?;
-        writer.write(now, record)
+        for json in self.format.files() {
+            self.get_writer(channel, *json)?.write(now, record)?;
+        }
+
+        Ok(())
     }
 
     fn flush(&self) -> io::Result<()> {
@@ -249,6 +312,41 @@ fn file_formatter(
     )
 }
 
+/// Collect the key-values from a log record.
+#[derive(Default)]
+struct JsonFields(Map<String, serde_json::Value>);
+
+impl<'kvs> VisitSource<'kvs> for JsonFields {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = serde_json::to_value(&value).unwrap_or(json!(value.to_string()));
+        self.0.insert(key.to_string(), value);
+
+        Ok(())
+    }
+}
+
+/// Write the record as one JSON object, without color tags.
+pub fn json_formatter(
+    w: &mut dyn Write,
+    now: &mut DeferredNow,
+    record: &Record,
+) -> std::io::Result<()> {
+    let mut fields = JsonFields::default();
+    let _ = record.key_values().visit(&mut fields);
+    let channel = fields.0.remove("channel").unwrap_or(json!(0));
+
+    let line = json!({
+        "timestamp": now.now().format(JSON_TIME_FORMAT).to_string(),
+        "channel": channel,
+        "target": record.module_path().unwrap_or(record.target()),
+        "level": record.level().as_str(),
+        "message": strip_tags(&record.args().to_string()),
+        "fields": fields.0,
+    });
+
+    write!(w, "{line}")
+}
+
 pub fn log_file_path() -> PathBuf {
     let config = GLOBAL_SETTINGS.get().unwrap();
     let mut log_path = PathBuf::from(&ARGS.logs.as_ref().unwrap_or(&config.logs));
@@ -268,7 +366,7 @@ fn file_logger() -> Box<dyn LogWriter> {
     if ARGS.log_to_console {
         Box::new(LogConsole)
     } else {
-        Box::new(MultiFileLogger::new(log_file_path()))
+        Box::new(MultiFileLogger::new(log_file_path(), *LOG_FORMAT))
     }
 }
 
@@ -0,0 +1,118 @@
+/*
+Icecast metadata.
+
+Listeners see the current song in their player. At each clip start, the title and artist
+get pushed to the metadata endpoint of the mount. Shoutcast servers understand the same
+request on their `admin.cgi`.
+*/
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use log::*;
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::utils::{
+    config::PlayoutConfig,
+    events::{EventKind, PlayoutEvent},
+    logging::Target,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Song text from clip data, `artist - title`.
+///
+/// Without title, the file name of the source is used.
+pub fn song(data: &Value) -> String {
+    let title = data["title"]
+        .as_str()
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| t.trim().to_string())
+        .unwrap_or_else(|| {
+            let source = data["source"].as_str().unwrap_or_default();
+
+            Path::new(source)
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
+
+    match data["artist"].as_str().map(str::trim) {
+        Some(artist) if !artist.is_empty() => format!("{artist} - {title}"),
+        _ => title,
+    }
+}
+
+/// Metadata endpoint of the channel, None when there is no server.
+pub fn metadata_url(config: &PlayoutConfig) -> Option<String> {
+    let output = &config.output;
+
+    if !output.icecast_metadata_url.is_empty() {
+        Some(output.icecast_metadata_url.clone())
+    } else if !output.icecast_address.is_empty() {
+        Some(format!("http://{}/admin/metadata", output.icecast_address))
+    } else {
+        None
+    }
+}
+
+async fn update(client: &Client, config: &PlayoutConfig, url: &str, song: &str) {
+    let output = &config.output;
+    let mount = format!("/{}", output.icecast_mount.trim_start_matches('/'));
+
+    let result = client
+        .get(url)
+        .basic_auth(&output.icecast_username, Some(&output.icecast_password))
+        .query(&[("mode", "updinfo"), ("mount", &mount), ("song", song)])
+        .send()
+        .await;
+
+    match result {
+        Ok(response) if response.status().is_success() => {
+            debug!(target: Target::file_mail(), channel = config.general.channel_id; "Icecast metadata: <yellow>{song}</>");
+        }
+        Ok(response) => {
+            warn!(target: Target::file_mail(), channel = config.general.channel_id; "Icecast metadata update failed: {}", response.status());
+        }
+        Err(e) => {
+            warn!(target: Target::file_mail(), channel = config.general.channel_id; "Icecast metadata update failed: {e}");
+        }
+    }
+}
+
+/// Push the song of each starting clip to the Icecast mount.
+///
+/// The config is read for every event, so changes apply without restart.
+pub async fn run(config: Arc<Mutex<PlayoutConfig>>, mut events: broadcast::Receiver<PlayoutEvent>) {
+    let client = Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap_or_default();
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                warn!("Icecast metadata missed <yellow>{count}</> events");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        if event.event != EventKind::ClipStart {
+            continue;
+        }
+
+        let config = config.lock().await.clone();
+
+        if !config.output.icecast_metadata {
+            continue;
+        }
+
+        if let Some(url) = metadata_url(&config) {
+            update(&client, &config, &url, &song(&event.data)).await;
+        }
+    }
+}
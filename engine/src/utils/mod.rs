@@ -17,17 +17,38 @@ use serde::{
 
 pub mod advanced_config;
 pub mod args_parse;
+pub mod asrun;
+pub mod capabilities;
 pub mod channels;
+pub mod clock;
 pub mod config;
+pub mod config_check;
 pub mod control;
+pub mod epg;
 pub mod errors;
+pub mod event_hooks;
+pub mod events;
+pub mod ffmpeg_bundle;
 pub mod generator;
+pub mod graphics;
+pub mod history;
+pub mod hw_accel;
+pub mod library;
 pub mod logging;
 pub mod mail;
+pub mod metadata;
+pub mod mqtt;
+pub mod playhead;
 pub mod playlist;
+pub mod preview;
+pub mod preview_session;
+pub mod sync;
 pub mod system;
 pub mod task_runner;
+pub mod ticker;
 pub mod time_machine;
+pub mod webhook;
+pub mod workers;
 
 use crate::db::GLOBAL_SETTINGS;
 use crate::player::utils::time_to_sec;
@@ -91,6 +112,38 @@ where
     deserializer.deserialize_any(StringOrNumberVisitor)
 }
 
+impl TextFilter {
+    /// Text with the style from drawtext options, like `x=10:y=h-40:fontsize=24`.
+    pub fn with_style(text: &str, style: &str) -> Self {
+        let mut filter = Self {
+            text: Some(text.to_string()),
+            ..Default::default()
+        };
+
+        for option in style.split(':') {
+            let Some((key, value)) = option.split_once('=') else {
+                continue;
+            };
+            let value = Some(value.trim().trim_matches('\'').to_string());
+
+            match key.trim() {
+                "x" => filter.x = value,
+                "y" => filter.y = value,
+                "fontsize" => filter.fontsize = value,
+                "line_spacing" => filter.line_spacing = value,
+                "fontcolor" => filter.fontcolor = value,
+                "alpha" => filter.alpha = value,
+                "box" => filter.r#box = value,
+                "boxcolor" => filter.boxcolor = value,
+                "boxborderw" => filter.boxborderw = value,
+                _ => {}
+            }
+        }
+
+        filter
+    }
+}
+
 impl fmt::Display for TextFilter {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let escaped_text = self
@@ -154,6 +207,49 @@ impl fmt::Display for TextFilter {
     }
 }
 
+/// Position presets for text messages, they set x and y of the drawtext filter.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TextPosition {
+    LowerThird,
+    Top,
+    TopLeft,
+    TopRight,
+    Center,
+    Bottom,
+    BottomLeft,
+    BottomRight,
+}
+
+impl TextPosition {
+    pub fn coordinates(&self) -> (&str, &str) {
+        match self {
+            Self::LowerThird => ("w*0.05", "h*0.75"),
+            Self::Top => ("(w-text_w)/2", "h*0.05"),
+            Self::TopLeft => ("w*0.05", "h*0.05"),
+            Self::TopRight => ("w*0.95-text_w", "h*0.05"),
+            Self::Center => ("(w-text_w)/2", "(h-text_h)/2"),
+            Self::Bottom => ("(w-text_w)/2", "h*0.95-text_h"),
+            Self::BottomLeft => ("w*0.05", "h*0.95-text_h"),
+            Self::BottomRight => ("w*0.95-text_w", "h*0.95-text_h"),
+        }
+    }
+}
+
+/// Text message for the message queue.
+///
+/// The text can have the template variables `{{title}}`, `{{next_title}}` and `{{time}}`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TextMessage {
+    #[serde(flatten)]
+    pub filter: TextFilter,
+    /// Seconds to show the message, 0 keeps it until the next message.
+    #[serde(default)]
+    pub duration: f64,
+    #[serde(default)]
+    pub position: Option<TextPosition>,
+}
+
 pub fn public_path() -> PathBuf {
     let config = GLOBAL_SETTINGS.get().unwrap();
     let dev_path = env::current_dir()
@@ -0,0 +1,278 @@
+/*
+MQTT publisher, for studio automation like lighting or CG systems.
+
+A minimal MQTT 3.1.1 client, which only publishes with QoS 0.
+Each playout event goes as JSON to {topic}/{event}. The clip_start message
+is retained, so systems which connect later still know what is on air.
+*/
+
+use std::{
+    io, process,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::*;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{tcp::OwnedWriteHalf, TcpStream},
+    sync::{broadcast, Mutex},
+    task::JoinHandle,
+    time::{interval, timeout},
+};
+
+use crate::utils::{
+    config::{Mqtt, PlayoutConfig},
+    events::{EventKind, PlayoutEvent},
+    logging::Target,
+};
+
+const KEEP_ALIVE: u16 = 60;
+const TIMEOUT: Duration = Duration::from_secs(10);
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+const PINGREQ: [u8; 2] = [0xC0, 0x00];
+const DISCONNECT: [u8; 2] = [0xE0, 0x00];
+
+fn encode_length(buf: &mut Vec<u8>, mut length: usize) {
+    loop {
+        let mut byte = (length % 128) as u8;
+        length /= 128;
+
+        if length > 0 {
+            byte |= 0x80;
+        }
+
+        buf.push(byte);
+
+        if length == 0 {
+            break;
+        }
+    }
+}
+
+fn encode_str(buf: &mut Vec<u8>, value: &[u8]) {
+    buf.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    buf.extend_from_slice(value);
+}
+
+fn packet(header: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut buf = vec![header];
+    encode_length(&mut buf, body.len());
+    buf.extend(body);
+
+    buf
+}
+
+/// CONNECT packet with clean session, the password is only used together with a username.
+pub fn connect_packet(client_id: &str, username: &str, password: &str) -> Vec<u8> {
+    let mut body = vec![];
+    let mut flags = 0x02;
+
+    if !username.is_empty() {
+        flags |= 0x80;
+
+        if !password.is_empty() {
+            flags |= 0x40;
+        }
+    }
+
+    encode_str(&mut body, b"MQTT");
+    body.push(4); // protocol level 3.1.1
+    body.push(flags);
+    body.extend_from_slice(&KEEP_ALIVE.to_be_bytes());
+    encode_str(&mut body, client_id.as_bytes());
+
+    if !username.is_empty() {
+        encode_str(&mut body, username.as_bytes());
+
+        if !password.is_empty() {
+            encode_str(&mut body, password.as_bytes());
+        }
+    }
+
+    packet(0x10, body)
+}
+
+/// PUBLISH packet with QoS 0.
+pub fn publish_packet(topic: &str, payload: &[u8], retain: bool) -> Vec<u8> {
+    let mut body = vec![];
+    encode_str(&mut body, topic.as_bytes());
+    body.extend_from_slice(payload);
+
+    packet(0x30 | retain as u8, body)
+}
+
+/// Topic for the event, the base topic defaults to ffplayout/{channel id}.
+pub fn topic(settings: &Mqtt, event: &PlayoutEvent) -> String {
+    let base = settings.topic.trim().trim_end_matches('/');
+
+    if base.is_empty() {
+        format!("ffplayout/{}/{}", event.channel, event.event)
+    } else {
+        format!("{base}/{}", event.event)
+    }
+}
+
+struct Connection {
+    settings: Mqtt,
+    writer: OwnedWriteHalf,
+    reader: JoinHandle<()>,
+}
+
+impl Connection {
+    async fn new(settings: &Mqtt, channel: i32) -> io::Result<Self> {
+        let mut stream = timeout(TIMEOUT, TcpStream::connect(&settings.broker)).await??;
+        let client_id = format!("ffplayout-{channel}-{}", process::id());
+        let mut connack = [0u8; 4];
+
+        stream
+            .write_all(&connect_packet(
+                &client_id,
+                &settings.username,
+                &settings.password,
+            ))
+            .await?;
+        timeout(TIMEOUT, stream.read_exact(&mut connack)).await??;
+
+        if connack[0] != 0x20 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Broker sent no CONNACK",
+            ));
+        }
+
+        if connack[3] != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                format!("Broker refused connection, return code {}", connack[3]),
+            ));
+        }
+
+        let (mut read_half, writer) = stream.into_split();
+
+        // ping responses are not of interest, the reader only notices a closed connection
+        let reader = tokio::spawn(async move {
+            let mut buffer = [0u8; 256];
+            while let Ok(1..) = read_half.read(&mut buffer).await {}
+        });
+
+        Ok(Self {
+            settings: settings.clone(),
+            writer,
+            reader,
+        })
+    }
+
+    fn is_closed(&self) -> bool {
+        self.reader.is_finished()
+    }
+
+    async fn publish(&mut self, event: &PlayoutEvent) -> io::Result<()> {
+        let payload = serde_json::to_vec(event)?;
+        let retain = event.event == EventKind::ClipStart;
+
+        self.writer
+            .write_all(&publish_packet(
+                &topic(&self.settings, event),
+                &payload,
+                retain,
+            ))
+            .await
+    }
+
+    async fn close(mut self) {
+        let _ = self.writer.write_all(&DISCONNECT).await;
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+/// Publish playout events to the configured MQTT broker.
+///
+/// The connection is opened with the first event, after a failure
+/// the next try happens with an event after the reconnect delay.
+pub async fn run(config: Arc<Mutex<PlayoutConfig>>, mut events: broadcast::Receiver<PlayoutEvent>) {
+    let mut connection: Option<Connection> = None;
+    let mut last_try: Option<Instant> = None;
+    let mut failed = false;
+    let mut ping = interval(Duration::from_secs(KEEP_ALIVE as u64 / 2));
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(count)) => {
+                        warn!("MQTT missed <yellow>{count}</> events");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                let settings = config.lock().await.mqtt.clone();
+
+                if connection
+                    .as_ref()
+                    .is_some_and(|c| !settings.enable || c.settings != settings || c.is_closed())
+                {
+                    if let Some(conn) = connection.take() {
+                        conn.close().await;
+                    }
+                }
+
+                if !settings.enable {
+                    continue;
+                }
+
+                if connection.is_none() {
+                    if last_try.is_some_and(|t| t.elapsed() < RECONNECT_DELAY) {
+                        continue;
+                    }
+
+                    last_try = Some(Instant::now());
+
+                    match Connection::new(&settings, event.channel).await {
+                        Ok(conn) => {
+                            info!(target: Target::file_mail(), channel = event.channel;
+                                "Connected to MQTT broker <b><magenta>{}</></b>", settings.broker
+                            );
+                            connection = Some(conn);
+                            failed = false;
+                        }
+                        Err(e) => {
+                            if !failed {
+                                error!(target: Target::file_mail(), channel = event.channel;
+                                    "MQTT broker <b><magenta>{}</></b> not reachable: {e}", settings.broker
+                                );
+                            }
+
+                            failed = true;
+                            continue;
+                        }
+                    }
+                }
+
+                if let Some(conn) = connection.as_mut() {
+                    if let Err(e) = conn.publish(&event).await {
+                        error!(target: Target::file_mail(), channel = event.channel;
+                            "MQTT publish <yellow>{}</> failed: {e}", event.event
+                        );
+                        connection = None;
+                        failed = true;
+                    }
+                }
+            }
+            _ = ping.tick() => {
+                if let Some(conn) = connection.as_mut() {
+                    if conn.writer.write_all(&PINGREQ).await.is_err() {
+                        connection = None;
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+/*
+Playhead of a channel.
+
+Index, source and position of the clip on air are saved every few seconds and when the engine
+shuts down. After a restart the channel continues at this place, also when the playout was
+moved away from the clock, by a hold or a manual take, or when it runs in folder mode.
+*/
+
+use std::{sync::atomic::Ordering, time::Duration};
+
+use chrono::DateTime;
+use chrono_tz::Tz;
+use log::*;
+use tokio::time::interval;
+
+use crate::db::{handles, models::Playhead};
+use crate::player::controller::ChannelManager;
+use crate::utils::{logging::Target, time_machine::time_now};
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Playhead of the clip on air, `None` before the first clip started.
+pub async fn current(manager: &ChannelManager) -> Option<Playhead> {
+    let started = (*manager.clip_started.lock().await)?;
+    let node = manager.current_media.lock().await.clone()?;
+    let timezone = manager.config.lock().await.channel.timezone;
+
+    Some(Playhead {
+        channel_id: manager.id,
+        date: manager.current_date.lock().await.clone(),
+        node_index: node.index? as i64,
+        position: node.seek + started.elapsed().as_secs_f64(),
+        source: node.source,
+        saved_at: time_now(&timezone).to_rfc3339(),
+    })
+}
+
+pub async fn save(manager: &ChannelManager) {
+    let Some(playhead) = current(manager).await else {
+        return;
+    };
+
+    if let Err(e) = handles::update_playhead(&manager.db_pool, &playhead).await {
+        error!(target: Target::file_mail(), channel = manager.id; "Save playhead failed: {e}");
+    }
+}
+
+/// Saved playhead of the channel.
+pub async fn load(manager: &ChannelManager) -> Option<Playhead> {
+    match handles::select_playhead(&manager.db_pool, manager.id).await {
+        Ok(playhead) => playhead,
+        Err(e) => {
+            error!(target: Target::file_mail(), channel = manager.id; "Read playhead failed: {e}");
+            None
+        }
+    }
+}
+
+/// Seconds since the playhead was saved.
+pub fn elapsed(playhead: &Playhead, timezone: &Option<Tz>) -> f64 {
+    DateTime::parse_from_rfc3339(&playhead.saved_at)
+        .map(|saved| (time_now(timezone).fixed_offset() - saved).num_milliseconds() as f64 / 1000.0)
+        .unwrap_or_default()
+        .max(0.0)
+}
+
+/// Save the playhead periodically, runs as long as the channel.
+pub async fn run(manager: ChannelManager) {
+    let mut ticker = interval(SAVE_INTERVAL);
+
+    loop {
+        ticker.tick().await;
+
+        // on shutdown the drain saves the last position
+        if manager.is_alive.load(Ordering::SeqCst) {
+            save(&manager).await;
+        }
+    }
+}
@@ -1,12 +1,21 @@
 use std::path::PathBuf;
 
 use log::*;
+use sqlx::{Pool, Sqlite};
 use tokio::fs;
 
+use crate::db::{handles, models::PlaylistRevision};
 use crate::file::norm_abs_path;
 use crate::player::controller::ChannelManager;
-use crate::player::utils::{json_reader, json_writer, JsonPlaylist};
-use crate::utils::{config::PlayoutConfig, errors::ServiceError, generator::playlist_generator};
+use crate::player::utils::{
+    import::{foreign_playlist_path, read_foreign_playlist},
+    json_reader, json_writer, JsonPlaylist, Media,
+};
+use crate::utils::{
+    config::PlayoutConfig,
+    errors::{ProcessError, ServiceError},
+    generator::playlist_generator,
+};
 
 pub async fn read_playlist(
     config: &PlayoutConfig,
@@ -21,8 +30,22 @@ pub async fn read_playlist(
         .join(date.clone())
         .with_extension("json");
 
+    if !playlist_path.is_file() {
+        if let Some(foreign) = foreign_playlist_path(&playlist_path) {
+            return read_foreign_playlist(&foreign, &date)
+                .await
+                .map_err(|e| ServiceError::NoContent(e.to_string()));
+        }
+    }
+
     match json_reader(&playlist_path).await {
-        Ok(p) => Ok(p),
+        Ok(mut p) => {
+            for item in &mut p.program {
+                item.apply_timecode(config.processing.fps);
+            }
+
+            Ok(p)
+        }
         Err(e) => Err(ServiceError::NoContent(e.to_string())),
     }
 }
@@ -141,3 +164,93 @@ pub async fn delete_playlist(config: &PlayoutConfig, date: &str) -> Result<Strin
         Ok(format!("No playlist to delete on: {date}"))
     }
 }
+
+fn diff_line(node: &Media) -> String {
+    format!("{} [{:.3}-{:.3}]", node.source, node.seek, node.out)
+}
+
+/// Difference between two programs, one line per removed (-) and added (+) clip.
+pub fn playlist_diff(old: &[Media], new: &[Media]) -> String {
+    let old: Vec<String> = old.iter().map(diff_line).collect();
+    let new: Vec<String> = new.iter().map(diff_line).collect();
+    let mut lcs = vec![vec![0_usize; new.len() + 1]; old.len() + 1];
+
+    for (i, o) in old.iter().enumerate().rev() {
+        for (j, n) in new.iter().enumerate().rev() {
+            lcs[i][j] = if o == n {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = vec![];
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            lines.push(format!("+ {}", new[j]));
+            j += 1;
+        } else {
+            lines.push(format!("- {}", old[i]));
+            i += 1;
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// Store a new revision of the playlist.
+///
+/// A playlist from before the first revision, generated or copied over, gets stored first,
+/// without user, so it can be restored too.
+pub async fn save_revision(
+    conn: &Pool<Sqlite>,
+    channel_id: i32,
+    user_id: Option<i32>,
+    old: Option<&JsonPlaylist>,
+    new: &JsonPlaylist,
+) -> Result<i32, ProcessError> {
+    let username = match user_id {
+        Some(id) => handles::select_user(conn, id).await?.username,
+        None => String::new(),
+    };
+    let old_program = old.map(|o| o.program.as_slice()).unwrap_or_default();
+
+    if let Some(old) = old {
+        if handles::select_playlist_revisions(conn, channel_id, &new.date)
+            .await?
+            .is_empty()
+        {
+            let revision = PlaylistRevision {
+                id: 0,
+                channel_id,
+                date: new.date.clone(),
+                user_id: None,
+                username: String::new(),
+                diff: playlist_diff(&[], &old.program),
+                playlist: Some(serde_json::to_string(old)?),
+                created_at: String::new(),
+            };
+
+            handles::insert_playlist_revision(conn, revision).await?;
+        }
+    }
+
+    let revision = PlaylistRevision {
+        id: 0,
+        channel_id,
+        date: new.date.clone(),
+        user_id,
+        username,
+        diff: playlist_diff(old_program, &new.program),
+        playlist: Some(serde_json::to_string(new)?),
+        created_at: String::new(),
+    };
+
+    handles::insert_playlist_revision(conn, revision).await
+}
@@ -0,0 +1,251 @@
+/*
+Library previews.
+
+Every video file of the media library gets a thumbnail and a sprite sheet for hover previews:
+up to 100 frames tiled in one JPEG, with a WebVTT file which points each time range to its
+tile. The files are stored by checksum in `preview/library` of the channel public path,
+so renamed files keep their previews and changed files get new ones.
+*/
+
+use std::{collections::HashSet, path::PathBuf, process::Stdio, sync::Arc};
+
+use log::*;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use tokio::{
+    fs,
+    process::Command,
+    sync::Mutex,
+    time::{sleep, Duration},
+};
+
+use crate::db::{handles, models::LibraryItem};
+use crate::player::utils::sec_to_time;
+use crate::utils::{config::PlayoutConfig, errors::ServiceError, ffmpeg_bundle, logging::Target};
+use crate::vec_strings;
+
+pub const SPRITE_COLUMNS: i64 = 10;
+pub const SPRITE_ROWS: i64 = 10;
+/// Width of one tile in the sprite sheet.
+pub const FRAME_WIDTH: i64 = 160;
+const THUMBNAIL_WIDTH: i64 = 320;
+
+/// Links to the preview files of a library item.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct Preview {
+    pub thumbnail: String,
+    pub sprite: String,
+    pub vtt: String,
+}
+
+impl Preview {
+    pub fn new(channel_id: i32, checksum: &str) -> Self {
+        let base = format!("/{channel_id}/preview/library/{checksum}");
+
+        Self {
+            thumbnail: format!("{base}/thumbnail.jpg"),
+            sprite: format!("{base}/sprite.jpg"),
+            vtt: format!("{base}/sprite.vtt"),
+        }
+    }
+}
+
+/// Folder with the preview files of all items.
+pub fn root(config: &PlayoutConfig) -> PathBuf {
+    config.channel.public.join("preview").join("library")
+}
+
+/// Folder with the preview files of an item.
+pub fn folder(config: &PlayoutConfig, item: &LibraryItem) -> PathBuf {
+    root(config).join(&item.checksum)
+}
+
+/// Size of one tile, with the aspect of the video and an even height.
+pub fn frame_size(item: &LibraryItem) -> Option<(i64, i64)> {
+    if item.width <= 0 || item.height <= 0 || item.checksum.is_empty() {
+        return None;
+    }
+
+    let height = (FRAME_WIDTH * item.height / item.width / 2 * 2).max(2);
+
+    Some((FRAME_WIDTH, height))
+}
+
+/// Seconds between two tiles, so that the whole clip fits into one sprite sheet.
+pub fn frame_interval(duration: f64) -> f64 {
+    (duration / (SPRITE_COLUMNS * SPRITE_ROWS) as f64).max(1.0)
+}
+
+pub fn thumbnail_cmd(item: &LibraryItem, file: &str) -> Vec<String> {
+    vec_strings![
+        "-hide_banner",
+        "-nostats",
+        "-v",
+        "error",
+        "-ss",
+        format!("{:.3}", item.duration * 0.1),
+        "-i",
+        item.path,
+        "-frames:v",
+        "1",
+        "-vf",
+        format!("scale={THUMBNAIL_WIDTH}:-2"),
+        "-q:v",
+        "3",
+        "-y",
+        file
+    ]
+}
+
+pub fn sprite_cmd(item: &LibraryItem, file: &str) -> Option<Vec<String>> {
+    let (width, height) = frame_size(item)?;
+
+    Some(vec_strings![
+        "-hide_banner",
+        "-nostats",
+        "-v",
+        "error",
+        "-i",
+        item.path,
+        "-an",
+        "-vf",
+        format!(
+            "fps=1/{:.3},scale={width}:{height},tile={SPRITE_COLUMNS}x{SPRITE_ROWS}",
+            frame_interval(item.duration)
+        ),
+        "-frames:v",
+        "1",
+        "-q:v",
+        "4",
+        "-y",
+        file
+    ])
+}
+
+/// WebVTT with the tile of each time range, like `sprite.jpg#xywh=160,0,160,90`.
+pub fn vtt(item: &LibraryItem) -> Option<String> {
+    let (width, height) = frame_size(item)?;
+    let interval = frame_interval(item.duration);
+    let count = ((item.duration / interval).ceil() as i64).clamp(1, SPRITE_COLUMNS * SPRITE_ROWS);
+    let mut vtt = "WEBVTT\n".to_string();
+
+    for i in 0..count {
+        let start = i as f64 * interval;
+        let end = (start + interval).min(item.duration.max(start + 0.001));
+
+        vtt.push_str(&format!(
+            "\n{} --> {}\nsprite.jpg#xywh={},{},{width},{height}\n",
+            sec_to_time(start),
+            sec_to_time(end),
+            i % SPRITE_COLUMNS * width,
+            i / SPRITE_COLUMNS * height
+        ));
+    }
+
+    Some(vtt)
+}
+
+async fn ffmpeg(cmd: Vec<String>) -> Result<(), ServiceError> {
+    let output = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(cmd)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(ServiceError::Conflict(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Generate the previews of an item, when they are missing.
+pub async fn generate(config: &PlayoutConfig, item: &LibraryItem) -> Result<Preview, ServiceError> {
+    let folder = folder(config, item);
+    let file = |name: &str| folder.join(name).to_string_lossy().to_string();
+    let (Some(sprite_cmd), Some(vtt)) = (sprite_cmd(item, &file("sprite.jpg")), vtt(item)) else {
+        return Err(ServiceError::NoContent(format!(
+            "No video in {}",
+            item.path
+        )));
+    };
+    let preview = Preview::new(config.general.channel_id, &item.checksum);
+
+    // the vtt is written last, so it marks complete previews
+    if folder.join("sprite.vtt").is_file() {
+        return Ok(preview);
+    }
+
+    fs::create_dir_all(&folder).await?;
+
+    ffmpeg(thumbnail_cmd(item, &file("thumbnail.jpg"))).await?;
+    ffmpeg(sprite_cmd).await?;
+    fs::write(folder.join("sprite.vtt"), vtt).await?;
+
+    debug!(target: Target::file_mail(), channel = config.general.channel_id;
+        "Save previews for <b><magenta>{}</></b>", item.path
+    );
+
+    Ok(preview)
+}
+
+/// Generate missing previews and remove the ones from files which left the library.
+///
+/// Files which failed are not tried again, until they change.
+async fn update(
+    pool: &Pool<Sqlite>,
+    config: &PlayoutConfig,
+    failed: &mut HashSet<String>,
+) -> Result<(), ServiceError> {
+    let items = handles::select_library_items(pool, config.general.channel_id).await?;
+    let checksums: HashSet<&str> = items.iter().map(|i| i.checksum.as_str()).collect();
+
+    for item in &items {
+        if frame_size(item).is_none() || failed.contains(&item.checksum) {
+            continue;
+        }
+
+        if let Err(e) = generate(config, item).await {
+            failed.insert(item.checksum.clone());
+
+            warn!(target: Target::file_mail(), channel = config.general.channel_id;
+                "Previews for <b><magenta>{}</></b> failed: {e}", item.path
+            );
+        }
+    }
+
+    let Ok(mut entries) = fs::read_dir(root(config)).await else {
+        return Ok(());
+    };
+
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if entry.path().is_dir() && !checksums.contains(name.as_str()) {
+            fs::remove_dir_all(entry.path()).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Keep the previews in line with the media library.
+pub async fn run(pool: Pool<Sqlite>, config: Arc<Mutex<PlayoutConfig>>) {
+    let mut failed = HashSet::new();
+
+    // the channel manager holds the other reference
+    while Arc::strong_count(&config) > 1 {
+        let config = config.lock().await.clone();
+
+        if config.storage.previews {
+            if let Err(e) = update(&pool, &config, &mut failed).await {
+                error!(target: Target::file_mail(), channel = config.general.channel_id; "Library previews: {e}");
+            }
+        }
+
+        sleep(Duration::from_secs(60)).await;
+    }
+}
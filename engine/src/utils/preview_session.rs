@@ -0,0 +1,245 @@
+/*
+Preview sessions.
+
+Editors can check a file before it goes into a playlist: a short lived ffmpeg process
+transcodes a part of the file to HLS, with the filter chain of the channel, so logo, text,
+loudness and scaling are the same as on air. The HLS files are served under
+`/<channel id>/preview/session/<session id>/index.m3u8`, the session ends after a few
+minutes and its files get removed.
+*/
+
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::LazyLock,
+    time::Duration,
+};
+
+use log::*;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs,
+    process::{Child, Command},
+    sync::Mutex,
+    time::sleep,
+};
+use uuid::Uuid;
+
+use crate::player::utils::{seek_and_length, Media};
+use crate::utils::{
+    config::PlayoutConfig,
+    errors::ServiceError,
+    ffmpeg_bundle,
+    logging::{fmt_cmd, Target},
+};
+use crate::vec_strings;
+
+/// Time until a session ends.
+pub const SESSION_TIME: Duration = Duration::from_secs(300);
+/// Longest part of a file, which gets transcoded.
+pub const MAX_LENGTH: f64 = 300.0;
+/// Running sessions per channel, the oldest one ends for a new one.
+const MAX_SESSIONS: usize = 2;
+
+struct Session {
+    channel_id: i32,
+    dir: PathBuf,
+    child: Child,
+}
+
+/// Running sessions, in the order they started.
+static SESSIONS: LazyLock<Mutex<Vec<(String, Session)>>> = LazyLock::new(|| Mutex::new(vec![]));
+
+fn default_length() -> f64 {
+    60.0
+}
+
+/// File and part of it for a preview, seek and length in seconds.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PreviewRequest {
+    pub source: String,
+    #[serde(default)]
+    pub seek: f64,
+    #[serde(default = "default_length")]
+    pub length: f64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct PreviewSession {
+    pub id: String,
+    pub url: String,
+    /// Seconds until the session ends.
+    pub expires: u64,
+}
+
+/// Folder with the HLS files of a session.
+pub fn path(config: &PlayoutConfig, id: &str) -> PathBuf {
+    config
+        .channel
+        .public
+        .join("preview")
+        .join("session")
+        .join(id)
+}
+
+/// Set seek, length and filter chain of the clip, like for the playout.
+pub async fn prepare(config: &PlayoutConfig, node: &mut Media, seek: f64, length: f64) {
+    node.seek = seek.clamp(0.0, node.duration.max(0.0));
+    node.out = (node.seek + length.clamp(1.0, MAX_LENGTH)).min(node.duration);
+    node.cmd = Some(seek_and_length(config, node));
+    node.add_filter(config, &None).await;
+}
+
+/// Build the ffmpeg command, which transcodes the clip to HLS.
+pub fn cmd(config: &PlayoutConfig, node: &Media, dir: &Path) -> Vec<String> {
+    let mut cmd = vec_strings!["-hide_banner", "-nostats", "-v", "level+error"];
+
+    cmd.append(&mut node.cmd.clone().unwrap_or_default());
+
+    if let Some(mut filter) = node.filter.clone() {
+        cmd.append(&mut filter.cmd());
+        cmd.append(&mut filter.map());
+    }
+
+    cmd.append(&mut vec_strings![
+        "-c:v",
+        "libx264",
+        "-preset",
+        "veryfast",
+        "-b:v",
+        format!("{}k", config.preview.bitrate.max(100) * 2),
+        "-c:a",
+        "aac",
+        "-b:a",
+        "128k",
+        "-f",
+        "hls",
+        "-hls_time",
+        "2",
+        "-hls_list_size",
+        "0",
+        "-hls_playlist_type",
+        "event",
+        "-hls_segment_filename",
+        dir.join("segment_%d.ts").to_string_lossy(),
+        dir.join("index.m3u8").to_string_lossy()
+    ]);
+
+    cmd
+}
+
+async fn remove(session: Session) {
+    let Session { dir, mut child, .. } = session;
+
+    let _ = child.kill().await;
+
+    if let Err(e) = fs::remove_dir_all(&dir).await {
+        debug!("Remove preview session {}: {e}", dir.display());
+    }
+}
+
+/// End a session and remove its files.
+pub async fn stop(channel_id: i32, id: &str) -> Result<(), ServiceError> {
+    let mut sessions = SESSIONS.lock().await;
+    let pos = sessions
+        .iter()
+        .position(|(i, s)| i == id && s.channel_id == channel_id)
+        .ok_or(ServiceError::BadRequest(
+            "Preview session not found".to_string(),
+        ))?;
+    let (_, session) = sessions.remove(pos);
+
+    drop(sessions);
+    remove(session).await;
+
+    Ok(())
+}
+
+/// Start a preview session for a file of the channel storage.
+pub async fn start(
+    config: &PlayoutConfig,
+    source: &Path,
+    request: &PreviewRequest,
+) -> Result<PreviewSession, ServiceError> {
+    let channel_id = config.general.channel_id;
+
+    if !source.is_file() {
+        return Err(ServiceError::BadRequest(format!(
+            "File not found: {}",
+            request.source
+        )));
+    }
+
+    let mut node = Media::new(0, &source.to_string_lossy(), true).await;
+
+    if node.duration <= 0.0 {
+        return Err(ServiceError::Conflict(format!(
+            "No media in {}",
+            request.source
+        )));
+    }
+
+    prepare(config, &mut node, request.seek, request.length).await;
+
+    let id = Uuid::new_v4().to_string();
+    let dir = path(config, &id);
+    let cmd = cmd(config, &node, &dir);
+
+    fs::create_dir_all(&dir).await?;
+
+    debug!(target: Target::file_mail(), channel = channel_id;
+        "Preview session CMD: <bright-blue>ffmpeg {}</>",
+        fmt_cmd(&cmd)
+    );
+
+    let child = Command::new(ffmpeg_bundle::ffmpeg())
+        .args(cmd)
+        .kill_on_drop(true)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let mut sessions = SESSIONS.lock().await;
+    let mut old = vec![];
+
+    while sessions
+        .iter()
+        .filter(|(_, s)| s.channel_id == channel_id)
+        .count()
+        >= MAX_SESSIONS
+    {
+        if let Some(pos) = sessions
+            .iter()
+            .position(|(_, s)| s.channel_id == channel_id)
+        {
+            old.push(sessions.remove(pos).1);
+        }
+    }
+
+    sessions.push((
+        id.clone(),
+        Session {
+            channel_id,
+            dir,
+            child,
+        },
+    ));
+    drop(sessions);
+
+    for session in old {
+        remove(session).await;
+    }
+
+    let session_id = id.clone();
+
+    tokio::spawn(async move {
+        sleep(SESSION_TIME).await;
+        let _ = stop(channel_id, &session_id).await;
+    });
+
+    Ok(PreviewSession {
+        url: format!("/{channel_id}/preview/session/{id}/index.m3u8"),
+        id,
+        expires: SESSION_TIME.as_secs(),
+    })
+}
@@ -0,0 +1,143 @@
+/*
+Main/backup synchronization, for redundant playout with two engines.
+
+The main engine serves its playhead on /data/sync/{id}, secured with a shared secret.
+A backup engine polls it every second and takes over the time shift of the main,
+so both play the same clip at the same position and a downstream switcher can cut
+between them. The playhead follows the wall clock, so the clocks of both servers
+must be synchronized, for example over NTP.
+*/
+
+use std::{sync::atomic::Ordering, time::Duration};
+
+use log::*;
+use reqwest::Client;
+use serde_json::{json, Value};
+use tokio::time::interval;
+
+use crate::db::handles;
+use crate::player::{
+    controller::{ChannelManager, ProcessUnit::*},
+    utils::get_data_map,
+};
+use crate::utils::{config::ProcessMode::Playlist, logging::Target};
+
+/// Header with the shared secret.
+pub const SYNC_HEADER: &str = "X-Ffplayout-Sync";
+
+/// Allowed time shift difference in seconds, about one frame.
+pub const SYNC_TOLERANCE: f64 = 0.04;
+
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Current playhead of the channel: date, time shift, clip index and elapsed time.
+pub async fn playhead(manager: &ChannelManager) -> Value {
+    let mut data = get_data_map(manager).await;
+
+    data.insert(
+        "date".to_string(),
+        json!(manager.current_date.lock().await.clone()),
+    );
+    data.insert(
+        "running".to_string(),
+        json!(manager.is_alive.load(Ordering::SeqCst)),
+    );
+
+    Value::Object(data)
+}
+
+/// Time shift to apply on the backup, None when it follows the main already.
+///
+/// Playheads from different days are not compared, this happens only around the day change.
+pub fn correction(own: &Value, main: &Value) -> Option<f64> {
+    let shift = main["shift"].as_f64()?;
+
+    if !main["running"].as_bool().unwrap_or_default() || own["date"] != main["date"] {
+        return None;
+    }
+
+    ((own["shift"].as_f64().unwrap_or_default() - shift).abs() > SYNC_TOLERANCE).then_some(shift)
+}
+
+async fn fetch(client: &Client, url: &str, secret: &str) -> Result<Value, reqwest::Error> {
+    client
+        .get(url)
+        .header(SYNC_HEADER, secret)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+}
+
+/// Follow the playhead of the main engine, runs as long as the player of the backup.
+pub async fn follow(manager: ChannelManager) {
+    let id = manager.id;
+    let client = Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap_or_default();
+    let mut ticker = interval(Duration::from_secs(1));
+    let mut failed = false;
+    let mut mismatch = 0;
+
+    loop {
+        ticker.tick().await;
+
+        let config = manager.config.lock().await.clone();
+
+        if config.sync.main_url.is_empty() || config.processing.mode != Playlist {
+            continue;
+        }
+
+        let main = match fetch(&client, &config.sync.main_url, &config.sync.secret).await {
+            Ok(main) => main,
+            Err(e) => {
+                if !failed {
+                    warn!(target: Target::file_mail(), channel = id;
+                        "Main engine <b><magenta>{}</></b> not reachable: {e}", config.sync.main_url
+                    );
+                }
+
+                failed = true;
+                continue;
+            }
+        };
+
+        if failed {
+            info!(target: Target::file_mail(), channel = id; "Main engine is reachable again");
+            failed = false;
+        }
+
+        let own = playhead(&manager).await;
+
+        if let Some(shift) = correction(&own, &main) {
+            info!(target: Target::file_mail(), channel = id;
+                "Follow main engine, time shift: <yellow>{shift:.3}</> seconds"
+            );
+
+            let date = manager.current_date.lock().await.clone();
+            manager.channel.lock().await.time_shift = shift;
+
+            if let Err(e) = handles::update_stat(&manager.db_pool, id, &Some(date), shift).await {
+                error!(target: Target::file_mail(), channel = id; "Save time shift failed: {e}");
+            }
+
+            manager.list_init.store(true, Ordering::SeqCst);
+            manager.stop(Decoder).await;
+            mismatch = 0;
+        } else if own["index"] != main["index"] && own["date"] == main["date"] {
+            // same time shift, but another clip over some polls: the playlists are not the same
+            mismatch += 1;
+
+            if mismatch == 3 {
+                warn!(target: Target::file_mail(), channel = id;
+                    "Backup plays clip <yellow>{}</>, main plays clip <yellow>{}</>, check the playlists",
+                    own["index"], main["index"]
+                );
+            }
+        } else {
+            mismatch = 0;
+        }
+    }
+}
@@ -0,0 +1,181 @@
+/*
+News ticker.
+
+The headlines of RSS, Atom or JSON feeds are pulled in an interval, joined with the separator
+and written to a text file. A drawtext filter reloads the file on every frame and scrolls
+the text through the program. Outside of the day-parts the file gets empty.
+*/
+
+use std::{io, path::PathBuf, time::Duration};
+
+use log::*;
+use regex::Regex;
+use reqwest::Client;
+use serde_json::Value;
+use tokio::{
+    fs,
+    time::{interval, Instant},
+};
+
+use crate::player::{
+    controller::ChannelManager,
+    utils::{time_in_seconds, time_to_sec},
+};
+use crate::utils::{config::PlayoutConfig, logging::Target};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Text file of the ticker.
+pub fn ticker_path(config: &PlayoutConfig) -> PathBuf {
+    config.channel.public.join("ticker.txt")
+}
+
+/// Headlines from a RSS, Atom or JSON feed.
+///
+/// JSON can be a list of strings, a list of objects with `title`,
+/// or an object with such a list in `items` like JSON Feed.
+pub fn headlines(body: &str) -> Vec<String> {
+    let body = body.trim();
+
+    if body.starts_with('{') || body.starts_with('[') {
+        let Ok(value) = serde_json::from_str::<Value>(body) else {
+            return vec![];
+        };
+        let items = match &value {
+            Value::Object(obj) => obj.get("items").and_then(Value::as_array),
+            Value::Array(list) => Some(list),
+            _ => None,
+        };
+
+        return items
+            .into_iter()
+            .flatten()
+            .filter_map(|item| match item {
+                Value::String(title) => Some(title.as_str()),
+                _ => item.get("title").and_then(Value::as_str),
+            })
+            .map(clean)
+            .filter(|t| !t.is_empty())
+            .collect();
+    }
+
+    let item = Regex::new(r"(?s)<(item|entry)[\s>].*?</(item|entry)>").unwrap();
+    let title = Regex::new(r"(?s)<title[^>]*>(.*?)</title>").unwrap();
+
+    item.find_iter(body)
+        .filter_map(|i| title.captures(i.as_str()))
+        .map(|c| clean(&c[1]))
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// Title without CDATA, tags, entities and line breaks.
+fn clean(text: &str) -> String {
+    let text = text
+        .trim()
+        .trim_start_matches("<![CDATA[")
+        .trim_end_matches("]]>");
+    let text = Regex::new(r"<[^>]*>").unwrap().replace_all(text, "");
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Check if the time of day is in one of the day-parts, no day-parts are always on.
+pub fn on_air(schedule: &[String], time: f64) -> bool {
+    schedule.is_empty()
+        || schedule.iter().any(|line| {
+            let Some((start, end)) = line.trim().split_once('-') else {
+                return false;
+            };
+            let (start, end) = (time_to_sec(start, &None), time_to_sec(end, &None));
+
+            if start <= end {
+                time >= start && time < end
+            } else {
+                time >= start || time < end
+            }
+        })
+}
+
+/// Create the empty text file, the drawtext filter needs it at start.
+pub async fn init(config: &PlayoutConfig) -> io::Result<()> {
+    fs::write(ticker_path(config), "").await
+}
+
+async fn pull(client: &Client, config: &PlayoutConfig) -> Vec<String> {
+    let mut lines = vec![];
+
+    for url in &config.ticker.feeds {
+        match client.get(url).timeout(TIMEOUT).send().await {
+            Ok(resp) if resp.status().is_success() => {
+                lines.append(&mut headlines(&resp.text().await.unwrap_or_default()));
+            }
+            Ok(resp) => {
+                warn!(target: Target::file_mail(), channel = config.general.channel_id; "Ticker feed <b><magenta>{url}</></b> failed: {}", resp.status());
+            }
+            Err(e) => {
+                warn!(target: Target::file_mail(), channel = config.general.channel_id; "Ticker feed <b><magenta>{url}</></b> failed: {e}");
+            }
+        }
+    }
+
+    lines
+}
+
+/// Pull the feeds and update the text file, runs as long as the player.
+pub async fn run(manager: ChannelManager) {
+    let config = manager.config.lock().await.clone();
+    let path = ticker_path(&config);
+    let temp = path.with_extension("tmp.txt");
+    let client = Client::new();
+    let mut ticker = interval(Duration::from_secs(1));
+    let mut pulled: Option<Instant> = None;
+    let mut lines = vec![];
+    let mut current = String::new();
+
+    loop {
+        ticker.tick().await;
+
+        if pulled.is_none_or(|t| t.elapsed().as_secs_f64() >= config.ticker.interval) {
+            let new_lines = pull(&client, &config).await;
+
+            // keep the old headlines, when no feed answers
+            if !new_lines.is_empty() {
+                lines = new_lines;
+            }
+
+            pulled = Some(Instant::now());
+        }
+
+        let time = time_in_seconds(&config.channel.timezone);
+        let text = if on_air(&config.ticker.schedule, time) {
+            lines.join(&config.ticker.separator)
+        } else {
+            String::new()
+        };
+
+        if text == current {
+            continue;
+        }
+
+        // drawtext reads the file on every frame, the rename keeps it complete
+        if let Err(e) = async {
+            fs::write(&temp, &text).await?;
+            fs::rename(&temp, &path).await
+        }
+        .await
+        {
+            error!(target: Target::file_mail(), channel = manager.id; "Ticker text could not be saved: {e}");
+        } else {
+            current = text;
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::{sync::Arc, time::Duration};
+
+use hmac::{Hmac, Mac};
+use log::*;
+use reqwest::Client;
+use sha2::Sha256;
+use tokio::sync::{broadcast, Mutex};
+
+use crate::utils::{
+    config::{PlayoutConfig, Webhook},
+    events::PlayoutEvent,
+    logging::Target,
+};
+
+const ATTEMPTS: u32 = 5;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// HMAC-SHA256 of the payload as hex string, for the `X-Ffplayout-Signature` header.
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(payload);
+
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Post the event to one endpoint, retry with backoff when it fails.
+async fn deliver(client: Client, webhook: Webhook, url: String, event: PlayoutEvent) {
+    let payload = serde_json::to_vec(&event).unwrap_or_default();
+    let mut delay = Duration::from_secs(1);
+    let mut last_error = String::new();
+
+    for attempt in 1..=ATTEMPTS {
+        let mut request = client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .header("X-Ffplayout-Event", event.event.to_string())
+            .body(payload.clone());
+
+        if !webhook.secret.is_empty() {
+            request = request.header(
+                "X-Ffplayout-Signature",
+                format!("sha256={}", sign(&webhook.secret, &payload)),
+            );
+        }
+
+        match request.send().await {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => last_error = response.status().to_string(),
+            Err(e) => last_error = e.to_string(),
+        }
+
+        if attempt < ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    error!(target: Target::file_mail(), channel = event.channel;
+        "Webhook <b><magenta>{url}</></b> failed for <yellow>{}</>: {last_error}", event.event
+    );
+}
+
+/// Send playout events to the configured webhooks.
+///
+/// The config is read for every event, so changes apply without restart.
+pub async fn run(config: Arc<Mutex<PlayoutConfig>>, mut events: broadcast::Receiver<PlayoutEvent>) {
+    let client = Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap_or_default();
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(count)) => {
+                warn!("Webhook missed <yellow>{count}</> events");
+                continue;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+
+        let webhook = config.lock().await.webhook.clone();
+
+        if !webhook.events.is_empty() && !webhook.events.contains(&event.event.to_string()) {
+            continue;
+        }
+
+        for url in &webhook.urls {
+            tokio::spawn(deliver(
+                client.clone(),
+                webhook.clone(),
+                url.clone(),
+                event.clone(),
+            ));
+        }
+    }
+}
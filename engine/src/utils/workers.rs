@@ -0,0 +1,426 @@
+/*
+Remote worker nodes, for running channels on more than one server.
+
+The controller keeps configs and playlists in its database and places channels on registered
+workers. A worker is a normal ffplayout engine, the controller talks to it over the API, with
+an API token of a global admin on the worker. Media and playlists must be on a shared storage,
+with the same paths on all nodes.
+
+A health check runs every few seconds. When a worker misses too many checks, its channels
+get placed on the online worker with the fewest channels, or back on the controller.
+When the worker comes back, it stops the channels which run now somewhere else.
+
+The controller remembers which channels it has sent to a worker. Only those get removed there,
+channels which are created on the worker itself are never touched. The worker remembers too,
+which channels a controller has created, it refuses to update or delete its own channels
+with the same ID and reports only the channels of the controller.
+*/
+
+use std::{sync::Arc, time::Duration};
+
+use log::*;
+use reqwest::{Client, Method, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use tokio::{sync::Mutex, time::interval};
+
+use crate::db::{
+    handles,
+    models::{Channel, Worker},
+};
+use crate::player::controller::{ChannelController, ChannelManager};
+use crate::utils::{
+    advanced_config::AdvancedConfig, config::PlayoutConfig, control::ProcessCtl,
+    errors::ServiceError,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Missed health checks, before the channels of a worker get placed somewhere else.
+pub const MAX_FAILURES: i32 = 3;
+
+/// Channel with its configs, as the controller sends it to a worker.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WorkerChannel {
+    pub channel: Channel,
+    pub config: PlayoutConfig,
+    pub advanced: AdvancedConfig,
+}
+
+/// Channel state, as a worker reports it on the health check.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WorkerChannelState {
+    pub id: i32,
+    pub running: bool,
+}
+
+fn worker_error(worker: &Worker, e: impl ToString) -> ServiceError {
+    ServiceError::ServiceUnavailable(format!("Worker {}: {}", worker.name, e.to_string()))
+}
+
+fn request(worker: &Worker, method: Method, path: &str) -> RequestBuilder {
+    Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .unwrap_or_default()
+        .request(method, format!("{}/api{path}", worker.url))
+        .bearer_auth(&worker.token)
+}
+
+async fn send(worker: &Worker, request: RequestBuilder) -> Result<reqwest::Response, ServiceError> {
+    request
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| worker_error(worker, e))
+}
+
+/// Channels on the worker, and if they run.
+pub async fn status(worker: &Worker) -> Result<Vec<WorkerChannelState>, ServiceError> {
+    send(worker, request(worker, Method::GET, "/worker/status"))
+        .await?
+        .json()
+        .await
+        .map_err(|e| worker_error(worker, e))
+}
+
+/// Send channel and configs to the worker, it creates the channel when it not exists.
+pub async fn push_channel(worker: &Worker, manager: &ChannelManager) -> Result<(), ServiceError> {
+    // remember it before sending, a failed request can still have created the channel
+    handles::insert_worker_channel(&manager.db_pool, worker.id, manager.id).await?;
+
+    let config = manager.config.lock().await.clone();
+    let mut channel = manager.channel.lock().await.clone();
+    channel.worker_id = None;
+
+    let data = WorkerChannel {
+        channel,
+        advanced: config.advanced.clone(),
+        config,
+    };
+
+    let response = request(
+        worker,
+        Method::PUT,
+        &format!("/worker/channel/{}", manager.id),
+    )
+    .json(&data)
+    .send()
+    .await
+    .map_err(|e| worker_error(worker, e))?;
+
+    // the worker has its own channel with this ID, it is not ours to remove
+    if response.status() == StatusCode::CONFLICT {
+        handles::delete_worker_channel(&manager.db_pool, worker.id, manager.id).await?;
+
+        return Err(ServiceError::Conflict(format!(
+            "Worker {}: channel {} exists there",
+            worker.name, manager.id
+        )));
+    }
+
+    response
+        .error_for_status()
+        .map_err(|e| worker_error(worker, e))?;
+
+    Ok(())
+}
+
+/// Run a process command for the channel on the worker, returns the answer of the worker.
+pub async fn send_command(
+    worker: &Worker,
+    id: i32,
+    command: &ProcessCtl,
+) -> Result<String, ServiceError> {
+    send(
+        worker,
+        request(worker, Method::POST, &format!("/control/{id}/process/"))
+            .json(&serde_json::json!({ "command": command })),
+    )
+    .await?
+    .json()
+    .await
+    .map_err(|e| worker_error(worker, e))
+}
+
+/// Process command for a channel on a worker, the active state is kept on the controller too.
+pub async fn process_command(
+    manager: &ChannelManager,
+    worker_id: i32,
+    command: ProcessCtl,
+) -> Result<&'static str, ServiceError> {
+    let worker = handles::select_worker(&manager.db_pool, worker_id).await?;
+    let answer = send_command(&worker, manager.id, &command).await?;
+    let active = match command {
+        ProcessCtl::Start | ProcessCtl::Restart => Some(true),
+        ProcessCtl::Stop => Some(false),
+        _ => None,
+    };
+
+    if let Some(active) = active {
+        manager.channel.lock().await.active = active;
+        handles::update_player(&manager.db_pool, manager.id, active).await?;
+    }
+
+    Ok(match answer.as_str() {
+        "active" => "active",
+        "stopping" => "stopping",
+        "not running" => "not running",
+        _ => "Success",
+    })
+}
+
+/// Stop the channel on the worker and remove it there.
+pub async fn remove_channel(
+    pool: &Pool<Sqlite>,
+    worker: &Worker,
+    id: i32,
+) -> Result<(), ServiceError> {
+    send(
+        worker,
+        request(worker, Method::DELETE, &format!("/worker/channel/{id}")),
+    )
+    .await?;
+
+    handles::delete_worker_channel(pool, worker.id, id).await?;
+
+    Ok(())
+}
+
+/// Channels on the worker, which the controller has placed there but which belong now
+/// somewhere else. Channels, which the controller has never sent to the worker, are left alone.
+pub fn stale_channels(
+    worker_id: i32,
+    states: &[WorkerChannelState],
+    channels: &[Channel],
+    placed: &[i32],
+) -> Vec<i32> {
+    states
+        .iter()
+        .filter(|s| placed.contains(&s.id))
+        .filter(|s| {
+            !channels
+                .iter()
+                .any(|c| c.id == s.id && c.worker_id == Some(worker_id))
+        })
+        .map(|s| s.id)
+        .collect()
+}
+
+/// Send the changed configs of a channel to its worker, when it runs on one.
+///
+/// Errors are only logged, the worker gets the configs again when the channel moves.
+pub async fn sync_channel(pool: &Pool<Sqlite>, manager: &ChannelManager) {
+    let Some(worker_id) = manager.channel.lock().await.worker_id else {
+        return;
+    };
+
+    match handles::select_worker(pool, worker_id).await {
+        Ok(worker) => {
+            if let Err(e) = push_channel(&worker, manager).await {
+                error!("Sync channel {} to worker: {e}", manager.id);
+            }
+        }
+        Err(e) => error!("Worker {worker_id}: {e}"),
+    }
+}
+
+/// Move a channel to a worker, or back to the controller with `None`.
+///
+/// An active channel gets stopped on its old place and started on the new one.
+pub async fn place_channel(
+    pool: &Pool<Sqlite>,
+    manager: &ChannelManager,
+    worker: Option<&Worker>,
+) -> Result<(), ServiceError> {
+    let id = manager.id;
+    let (active, old_worker) = {
+        let channel = manager.channel.lock().await;
+
+        (channel.active, channel.worker_id)
+    };
+
+    if old_worker == worker.map(|w| w.id) {
+        return Ok(());
+    }
+
+    if let Some(worker) = worker {
+        push_channel(worker, manager).await?;
+    }
+
+    handles::update_channel_worker(pool, id, worker.map(|w| w.id)).await?;
+    manager.channel.lock().await.worker_id = worker.map(|w| w.id);
+
+    match old_worker {
+        Some(old_id) => match handles::select_worker(pool, old_id).await {
+            Ok(old) if old.online => {
+                if let Err(e) = remove_channel(pool, &old, id).await {
+                    warn!("Remove channel {id} from worker: {e}");
+                }
+            }
+            _ => {}
+        },
+        // the run loop ends, because the channel runs not here anymore
+        None => manager.stop_all(false).await,
+    }
+
+    if active {
+        match worker {
+            Some(worker) => {
+                send_command(worker, id, &ProcessCtl::Start).await?;
+            }
+            None => manager.start().await?,
+        }
+    }
+
+    info!(
+        "Place channel {id} on {}",
+        worker.map_or("controller".to_string(), |w| format!("worker {}", w.name))
+    );
+
+    Ok(())
+}
+
+/// Online worker with the fewest channels.
+pub fn least_loaded<'a>(
+    workers: &'a [Worker],
+    channels: &[Channel],
+    exclude: i32,
+) -> Option<&'a Worker> {
+    workers
+        .iter()
+        .filter(|w| w.online && w.id != exclude)
+        .min_by_key(|w| {
+            channels
+                .iter()
+                .filter(|c| c.worker_id == Some(w.id))
+                .count()
+        })
+}
+
+/// Place the channels of an offline worker on another worker, or on the controller.
+async fn replace_channels(
+    pool: &Pool<Sqlite>,
+    controllers: &Mutex<ChannelController>,
+    worker: &Worker,
+) -> Result<(), ServiceError> {
+    let workers = handles::select_workers(pool).await?;
+    let mut channels = handles::select_related_channels(pool, None).await?;
+
+    for i in 0..channels.len() {
+        if channels[i].worker_id != Some(worker.id) {
+            continue;
+        }
+
+        let id = channels[i].id;
+        let target = least_loaded(&workers, &channels, worker.id).cloned();
+
+        let Some(manager) = controllers.lock().await.get(id).await else {
+            continue;
+        };
+
+        match place_channel(pool, &manager, target.as_ref()).await {
+            Ok(_) => channels[i].worker_id = target.map(|w| w.id),
+            Err(e) => error!("Place channel {id} from worker {}: {e}", worker.name),
+        }
+    }
+
+    Ok(())
+}
+
+/// Health check of one worker.
+///
+/// Active channels, which the worker lost, for example after a restart, get sent again.
+/// Channels, which the controller had placed on it but which are placed now somewhere else,
+/// get removed.
+async fn check_worker(
+    pool: &Pool<Sqlite>,
+    controllers: &Mutex<ChannelController>,
+    worker: &Worker,
+) -> Result<(), ServiceError> {
+    let states = match status(worker).await {
+        Ok(states) => states,
+        Err(e) => {
+            let failures = worker.failures + 1;
+            let online = failures < MAX_FAILURES;
+
+            handles::update_worker_state(pool, worker.id, online, failures).await?;
+
+            if worker.online && !online {
+                error!("{e}, place its channels on other nodes");
+
+                replace_channels(pool, controllers, worker).await?;
+            }
+
+            return Ok(());
+        }
+    };
+
+    if !worker.online {
+        info!("Worker {} is online", worker.name);
+    }
+
+    handles::update_worker_state(pool, worker.id, true, 0).await?;
+
+    let channels = handles::select_related_channels(pool, None).await?;
+    let placed = handles::select_worker_channels(pool, worker.id).await?;
+
+    for id in placed
+        .iter()
+        .filter(|id| !states.iter().any(|s| s.id == **id))
+    {
+        // the worker has removed it already
+        handles::delete_worker_channel(pool, worker.id, *id).await?;
+    }
+
+    for id in stale_channels(worker.id, &states, &channels, &placed) {
+        info!(
+            "Channel {id} is placed on another node, remove it from worker {}",
+            worker.name
+        );
+
+        remove_channel(pool, worker, id).await?;
+    }
+
+    for channel in channels
+        .iter()
+        .filter(|c| c.active && c.worker_id == Some(worker.id))
+    {
+        if states.iter().any(|s| s.id == channel.id && s.running) {
+            continue;
+        }
+
+        let Some(manager) = controllers.lock().await.get(channel.id).await else {
+            continue;
+        };
+
+        push_channel(worker, &manager).await?;
+        send_command(worker, channel.id, &ProcessCtl::Start).await?;
+    }
+
+    Ok(())
+}
+
+/// Health check of all workers, runs as long as the controller.
+pub async fn run(pool: Pool<Sqlite>, controllers: Arc<Mutex<ChannelController>>) {
+    let mut ticker = interval(CHECK_INTERVAL);
+
+    loop {
+        ticker.tick().await;
+
+        let workers = match handles::select_workers(&pool).await {
+            Ok(workers) => workers,
+            Err(e) => {
+                error!("Select workers: {e}");
+                continue;
+            }
+        };
+
+        for worker in workers {
+            if let Err(e) = check_worker(&pool, &controllers, &worker).await {
+                error!("Check worker {}: {e}", worker.name);
+            }
+        }
+    }
+}
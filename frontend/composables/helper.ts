@@ -235,10 +235,38 @@ export const playlistOperations = () => {
                     delete item.custom_filter
                 }
 
+                if (!item.filters?.length) {
+                    delete item.filters
+                }
+
+                if (!item.custom_decoder_params) {
+                    delete item.custom_decoder_params
+                }
+
+                if (!item.aspect_mode) {
+                    delete item.aspect_mode
+                }
+
+                if (!item.fps_mode) {
+                    delete item.fps_mode
+                }
+
                 if (!item.title) {
                     delete item.title
                 }
 
+                if (!item.artist) {
+                    delete item.artist
+                }
+
+                if (!item.description) {
+                    delete item.description
+                }
+
+                if (item.trigger !== 'manual') {
+                    delete item.trigger
+                }
+
                 if (
                     begin + (item.out - item.in) >
                     configStore.playout.playlist.startInSec + configStore.playout.playlist.lengthInSec
@@ -8,6 +8,7 @@ export default {
     },
     button: {
         login: 'Anmelden',
+        ssoLogin: 'Mit SSO anmelden',
         home: 'Start',
         player: 'Wiedergabe',
         media: 'Medien',
@@ -50,19 +51,25 @@ export default {
         reset: 'Playout-Zustand zurücksetzen',
         restart: 'Playout neu starten',
         next: 'Zum nächsten Clip springen',
+        take: 'Nächsten Clip starten',
     },
     player: {
         start: 'Start',
         file: 'Datei',
         play: 'Abspielen',
         title: 'Titel',
+        artist: 'Künstler',
+        description: 'Beschreibung',
         duration: 'Dauer',
         total: 'Gesamt',
         in: 'Eingang',
         out: 'Ausgang',
         ad: 'Werbung',
+        manual: 'Manueller Start',
+        manualHelp: 'Der Clip wartet mit Füller auf den Start durch den Operator',
         edit: 'Bearbeiten',
         delete: 'Löschen',
+        goto: 'Doppelklick, um ab hier zu spielen',
         copy: 'Wiedergabeliste kopieren',
         loop: 'Clips in {date} Wiedergabeliste wiederholen',
         remote: 'Externe Quelle zur Wiedergabeliste hinzufügen',
@@ -76,6 +83,11 @@ export default {
         addEdit: 'Quelle hinzufügen/bearbeiten',
         audio: 'Audio',
         customFilter: 'Benutzerdefinierter Filter',
+        filterPlugins: 'Filter-Plugins',
+        decoderParams: 'Decoder-Parameter',
+        aspectMode: 'Seitenverhältnis-Modus',
+        fpsMode: 'Bildraten-Modus',
+        channelDefault: 'Kanal-Standard',
         deleteFrom: 'Programm löschen von',
         deleteSuccess: 'Wiedergabeliste gelöscht...',
         generateProgram: 'Programm generieren',
@@ -83,7 +95,16 @@ export default {
         advanced: 'Erweitert',
         sorted: 'Sortiert',
         shuffle: 'Zufall',
+        bestFit: 'Best Fit',
+        bestFitHelp: 'Wählt die Clips, die den Block am besten füllen, damit wenig Füller nötig ist',
+        series: 'Serie',
+        seriesHelp: 'Spielt die nächsten Folgen aus dem Ordner, beim nächsten Mal geht es nach der letzten Folge weiter',
+        libraryQuery: 'Bibliotheksabfrage, wie tag:music AND duration<300',
+        libraryQueryHelp: 'Nimmt die Clips aus der Medienbibliothek statt aus den Ordnern, mit Tags, Feldern und Vergleichen, verknüpft mit AND, OR und NOT',
+        weekdays: 'Wochentage des Blocks, jeden Tag wenn keiner ausgewählt ist',
         shift: 'Zeitverschiebung',
+        encoder: 'Encoder',
+        dropped: 'verworfen',
         all: 'Alle',
         addBlock: 'Zeitblock hinzufügen',
         infinitInfo: 'Die Wiedergabe läuft im unendlichen Modus. Es sind keine zeitbasierten Informationen möglich.',
@@ -180,24 +201,50 @@ export default {
         playlist: 'Wiedergabeliste',
         storage: 'Speicher',
         text: 'Text',
+        script: 'Scheduler-Skript',
         task: 'Aufgabe',
+        recorder: 'Aufzeichnung',
+        webhook: 'Webhook',
+        transition: 'Übergang',
+        monitor: 'Überwachung',
+        failover: 'Ausfallquelle',
+        sync: 'Sync',
+        graphics: 'Grafiken',
+        ticker: 'Ticker',
+        clock: 'Uhr',
+        visualRadio: 'Visual Radio',
         output: 'Ausgabe',
         placeholderPass: 'Passwort',
         help: 'Hilfe',
         generalHelp: 'Manchmal kann es passieren, dass eine Datei beschädigt ist, aber dennoch abgespielt werden kann. Dies kann zu einem Streaming-Fehler für alle folgenden Dateien führen. Die einzige Lösung in diesem Fall ist, ffplayout zu stoppen und erneut zu starten.',
         stopThreshold: 'Der Schwellenwert stoppt ffplayout, wenn es zeitlich asynchron über diesem Wert ist. Eine Zahl unter 3 kann unerwartete Fehler verursachen.',
+        driftCorrection: 'Korrigiert die Abweichung zwischen Playout und Uhrzeit, indem der Out-Punkt jedes Clips um höchstens so viele Sekunden verschoben wird. 0 deaktiviert die Korrektur.',
         mailHelp: `Sende Fehlermeldungen an eine E-Mail-Adresse, wie z.B. fehlende Clips, fehlendes oder ungültiges Playlist-Format usw. Lass den Empfänger leer, wenn du dies nicht benötigst.`,
         mailInterval: 'Das Intervall bezieht sich auf die Anzahl der Sekunden, bis eine neue E-Mail gesendet wird; der Wert muss in 10er-Schritten und nicht unter 30 Sekunden liegen.',
         logHelp: 'Passen Sie das Verhalten des Loggings an.',
         logDetect: 'Protokolliert eine Fehlermeldung, wenn die Audioleitung während des Validierungsprozesses 15 Sekunden lang stumm ist.',
         logIgnore: 'Ignoriere Zeichenfolgen, die übereinstimmende Zeilen enthalten; das Format ist eine durch Semikolon getrennte Liste.',
         processingHelp: 'Die Standardverarbeitung für alle Clips stellt die Einzigartigkeit sicher.',
-        processingLogoPath: 'Das Logo wird nur verwendet, wenn der Pfad existiert; der Pfad ist relativ zum Speicherordner.',
-        processingLogoScale: `Lass die Skalierung des Logos leer, wenn keine Skalierung erforderlich ist. Das Format lautet 'Breite:Höhe', zum Beispiel: '100:-1' für proportionale Skalierung.`,
+        processingLogoPath: 'Das Logo wird nur verwendet, wenn der Pfad existiert; der Pfad ist relativ zum Speicherordner. Animierte GIF, APNG, MOV mit Alphakanal und Bildsequenzen wie logos/bug_%03d.png werden unterstützt.',
+        processingLogoScale: `Lass die Skalierung des Logos leer, wenn keine Skalierung erforderlich ist. Das Format lautet 'Breite:Höhe', zum Beispiel: '100:-1' für proportionale Skalierung. Ein Prozentwert wie '10%' skaliert auf die Ausgabebreite.`,
+        processingLogoLoop: 'Wie oft ein animiertes Logo abgespielt wird, danach bleibt das letzte Bild stehen. 0 wiederholt endlos.',
+        processingLogoFps: 'Bildrate für Bildsequenzen, 0 nimmt die Rate aus der Datei.',
         processingLogoPosition: `Die Position wird im Format 'x:y' angegeben.`,
+        processingLogoSchedule: 'Ein Logo pro Zeile für einen Tagesabschnitt, wie: 18:00:00-20:00:00 logos/prime.png. Das Logo wechselt mit dem Clip, der im Zeitbereich beginnt, zu anderen Zeiten gilt das Standardlogo. Ein Clip in der Playlist kann ein eigenes Logo setzen.',
         processingAudioTracks: 'Gib an, wie viele Audiospuren verarbeitet werden sollen.',
         processingAudioIndex: 'Welche Audiospur verwendet werden soll, -1 für alle.',
         processingAudioChannels: 'Stelle die Anzahl der Audiokanäle ein, wenn das Audio mehr Kanäle als Stereo hat.',
+        processingCrossfade: 'Länge der Überblendung zwischen zwei Clips in Sekunden, 0 deaktiviert sie. Live-Quellen, entfernte Quellen und Bilder werden ohne Überblendung geschnitten.',
+        processingCrossfadeTransition: 'Videoübergang des xfade-Filters, wie fade, dissolve, wipeleft oder slideup.',
+        processingCrossfadeCurve: 'Audiokurve des acrossfade-Filters, wie tri, qsin, exp oder log.',
+        processingLoudnorm: 'EBU-R128-Normalisierung. Jede Datei wird einmal gemessen und das Ergebnis zwischengespeichert, damit sie bei der nächsten Wiedergabe linear normalisiert werden kann. Bis dahin wird der dynamische Modus verwendet.',
+        processingAspectMode: 'Wie Clips mit anderem Seitenverhältnis eingepasst werden: auto behält das ganze Bild mit Balken, letterbox die volle Breite, pillarbox die volle Höhe, center-crop füllt das Bild und stretch verzerrt es.',
+        processingDeinterlace: 'Auto deinterlaced Clips mit Halbbildreihenfolge, die idet an einer Stichprobe von Frames bestätigt. An und aus gelten für alle Clips.',
+        processingTonemap: 'HDR-Clips (PQ und HLG) werden per Tone Mapping auf SDR in BT.709 umgesetzt. zscale braucht ffmpeg mit libzimg, libplacebo mit libplacebo.',
+        processingFpsMode: 'Umwandlung von Clips mit anderer Bildrate: auto verwirft oder verdoppelt Bilder bei ganzzahligen Verhältnissen wie 50 zu 25, überblendet Bilder bei den anderen wie 30 zu 25 und entfernt das Telecine von interlaced 29,97 fps Filmclips. Interpolate berechnet neue Bilder aus der Bewegung und braucht viel CPU.',
+        processingColorSpace: 'Ziel-Farbraum der Ausgabe: auto nimmt BT.709 für HD und BT.601 für SD. Clips mit einer anderen Farbmatrix oder im vollen Bereich werden umgewandelt und der Encoder bekommt die passenden Farb-Tags. Clips ohne Tags gelten unter 720 Zeilen als BT.601.',
+        processingBackend: 'Experimentell: GStreamer kodiert die Ausgabe null, stream, SRT, NDI, desktop und DeckLink und dekodiert Clips, die keine ffmpeg-Filter brauchen, wie Logo, Text, Loudnorm, Seek oder Fades. Alle anderen Clips werden mit ffmpeg dekodiert. Benötigt gst-launch-1.0 mit den Plugins für die Ausgabe.',
+        processingHwAccel: 'Dekodierung, Skalierung, Deinterlacing und Logo-Overlay auf der GPU und die Ausgabe mit dem Hardware-Encoder. Einstellungen der erweiterten Konfiguration haben Vorrang.',
         processingCustomFilter: 'Füge benutzerdefinierte Filter zur Verarbeitung hinzu. Die Filterausgaben müssen mit [c_v_out] für Video-Filter und [c_a_out] für Audio-Filter enden.',
         processingOverrideFilter: 'Achtung: Diese Option überschreibt alle Standardfilter, d.h. es findet keine automatische Formatkorrektur mehr statt, der Befehl muss wie folgt aufgebaut sein: -filter_complex [0:v]fps=25,scale=1280:-1[vout];[0:a:0]volume=0.5[aout] -map [vout] -map [aout]',
         processingVTTEnable: 'VTT kann nur im HLS-Modus verwendet werden und nur, wenn *.vtt-Dateien mit demselben Namen wie die Videodatei vorhanden sind.',
@@ -208,17 +255,49 @@ export default {
         playlistDayStart: 'Zu welcher Zeit die Playlist starten soll; lasse es leer, wenn die Playlist immer von Anfang an starten soll.',
         playlistLength: 'Ziel-Länge der Playlist; wenn es leer ist, wird die reale Länge nicht berücksichtigt.',
         playlistInfinit: 'Eine einzelne Playlist-Datei endlos wiederholen.',
+        playlistGapPolicy: 'Eine Regel pro Zeile, für Lücken am Ende der Playlist, z. B.: 60-900 fit. Der Bereich ist die Länge der Lücke in Sekunden, danach folgt die Regel: loop (letzter Clip), fit (am besten passender Füller), slate <Pfad> oder tag <Name> (Clips aus der Medienbibliothek). Andere Lücken werden mit dem Füller gefüllt.',
         storageHelp: 'Speichereinstellungen, die Standorte sind relativ zum Kanal-Speicher.',
         storageFiller: 'Verwenden Sie einen Platzhalter, um eine fehlende Datei abzuspielen oder um die verbleibende Zeit auf insgesamt 24 Stunden zu füllen. Es kann sich um eine Datei oder einen Ordner mit relativem Pfad handeln, der bei Bedarf wiederholt wird.',
         storageExtension: 'Gib an, welche Dateien gesucht und verwendet werden sollen.',
         storageShuffle: 'Wähle Dateien zufällig aus (im Ordner-Modus und bei der Playlist-Erstellung).',
+        storageLibrary: 'Minuten zwischen zwei Scans der Medienbibliothek, 0 deaktiviert den Scanner.',
+        storageQuery: 'Nimmt die Dateien im Ordner-Modus aus der Medienbibliothek, mit einer Abfrage wie tag:music AND duration<300. Leer verwendet den ganzen Speicher.',
+        storagePreviews: 'Für jedes Video der Mediathek ein Vorschaubild und ein Sprite mit WebVTT für Scrubbing-Vorschauen erzeugen.',
         textHelp: 'Texteinblendung in Kombination mit libzmq für die Fernmanipulation von Text.',
         textFont: 'Relativer Pfad zum Kanal-Speicher.',
         textFromFile: 'Extrahiere Text aus einem Dateinamen.',
         textStyle: 'Definiere die Parameter für drawtext, wie Position, Farbe usw. Das Posten von Text über die API überschreibt dies.',
         textRegex: 'Formatiere Dateinamen, um einen Titel daraus zu extrahieren.',
+        textNowNext: 'Zeigt bei jedem Clipstart den Titel des aktuellen und des nächsten Clips. Benötigt Text hinzufügen und funktioniert nicht zusammen mit Text aus Datei.',
+        textNowNextFormat: 'Vorlage für den Jetzt/Danach-Text, mit den Variablen title, next_title und time in doppelten geschweiften Klammern.',
+        textNowNextStyle: 'Drawtext-Parameter für den Jetzt/Danach-Text, wie Position, Größe und Box.',
         taskHelp: 'Führe ein externes Programm mit einem gegebenen Medienobjekt aus. Das Medienobjekt ist im JSON-Format und enthält alle Informationen über den aktuellen Clip. Das externe Programm kann ein Skript oder eine Binärdatei sein, sollte aber nur für kurze Zeit laufen.',
         taskPath: 'Pfad zur ausführbaren Datei.',
+        scriptHelp: 'Ein Lua-Skript, das den nächsten Clip ändern kann, für eigene Planungslogik. Es kann die Funktionen on_before_item, aufgerufen vor jedem Clip der Playlist, on_gap, bevor eine Lücke gefüllt wird, und on_midnight, mit dem ersten Clip der nächsten Playlist, definieren. Sie bekommen den Clip und eine Kontext-Tabelle und geben eine Tabelle mit den zu ändernden Feldern zurück, oder nichts. Das Skript läuft in der eingebetteten Lua-Laufzeit und behält seine globalen Variablen zwischen den Aufrufen. Das Playout wartet bis zum Timeout auf einen Hook.',
+        recorderHelp: 'Zeichnet die Programmausgabe in segmentierten Dateien auf, für Sendenachweise und Mitschnitte. Die Aufzeichnung kann auch über die API gestartet und gestoppt werden.',
+        recorderPath: 'Zielordner für die Aufzeichnungen.',
+        recorderRetention: 'Tage, die Aufzeichnungen aufbewahrt werden, 0 behält sie für immer.',
+        webhookHelp: 'Sendet Playout-Ereignisse als JSON-POST-Anfragen an externe Systeme. Mit einem Schlüssel bekommt jede Anfrage eine HMAC-SHA256-Signatur im Header X-Ffplayout-Signature.',
+        webhookUrls: 'Ein Endpunkt pro Zeile.',
+        webhookEvents: 'Zu sendende Ereignisse, durch Semikolon getrennt, alle wenn leer: channel_start, channel_stop, clip_start, clip_end, playlist_missing, filler, restart, silence, black, freeze, corrupt, failover, clock_jump, output_unreachable.',
+        eventHooks: 'Ereignis-Hooks',
+        eventHooksHelp: 'Shell-Befehle, die bei Clipstart, Clipende und Fehlern ausgeführt werden. Das Ereignis steht in Umgebungsvariablen: FFP_EVENT, FFP_CHANNEL, FFP_TIME, FFP_DATA als JSON und eine Variable für jeden Wert, wie FFP_SOURCE oder FFP_TITLE. Das Playout wartet nicht auf die Befehle, sie werden nach dem Timeout in Sekunden beendet.',
+        mqttHelp: 'Veröffentlicht die Playout-Ereignisse an einen MQTT-Broker, für Studioautomation wie Licht- oder Grafiksysteme. Der aktuelle Clip wird als Retained-Nachricht gesendet.',
+        mqttTopic: 'Basis-Topic, der Ereignisname wird angehängt. Standard ist ffplayout/<Kanal-ID>.',
+        transitionHelp: 'Ein Stinger ist ein kurzer Clip mit Alphakanal, wie ProRes 4444, der über dem Schnitt zwischen zwei Clips abgespielt wird. Ein leerer Pfad deaktiviert ihn.',
+        transitionStingerOffset: 'Sekunden vom Start des Stingers bis zum Schnitt, meist der Punkt, an dem der Stinger das ganze Bild verdeckt.',
+        monitorHelp: 'Alarme bei Stille, Schwarzbild und Standbild in den gespielten Clips, was meist auf eine fehlerhafte Quelldatei hinweist. Wenn die Dauer in Sekunden erreicht ist, wird ein Fehler geloggt und das Ereignis silence, black oder freeze gesendet. Ein Clip mit Standbild wird außerdem übersprungen und der nächste Clip startet. Eine Dauer von 0 deaktiviert die Erkennung. Audio unter dem Rauschpegel gilt als Stille, Pixel unter dem Schwarzwert (0 - 1) als schwarz.',
+        failoverHelp: 'Zweite Quelle, für den Fall dass die Playlist fehlt oder der Speicher nicht erreichbar ist. Die Quelle kann ein Ordner mit Clips, ein einzelner Clip oder ein Bild, oder die URL eines Live-Eingangs sein. Nach jedem Clip der Ausfallquelle wird die Hauptquelle erneut geprüft, und die Ausspielung wechselt zurück, sobald sie wieder verfügbar ist. Beide Wechsel senden das Ereignis failover.',
+        syncHelp: 'Synchronisation von Haupt- und Backup-Engine. Die Haupt-Engine braucht ein Secret, dann stellt sie ihre Abspielposition unter /data/sync/ und der Kanal-ID bereit. Eine Backup-Engine bekommt das gleiche Secret und die URL der Haupt-Engine, sie folgt deren Zeitverschiebung. Die Uhren beider Server müssen synchron laufen.',
+        graphicsHelp: "HTML-Grafiken wie Ticker, Uhren oder Wetter-Widgets. Ein Render-Befehl wandelt die Webseite im Intervall in ein transparentes Bild, das über das Programm gelegt wird. Der Befehl bekommt die Platzhalter {'{source}'}, {'{output}'}, {'{width}'} und {'{height}'}, wenn leer wird wkhtmltoimage verwendet.",
+        tickerHelp: 'Nachrichtenticker am unteren Rand des Programms. Die Schlagzeilen der RSS-, Atom- oder JSON-Feeds werden im Intervall (Sekunden) abgerufen und laufen mit der Geschwindigkeit in Pixel pro Sekunde durch. Style nimmt drawtext-Optionen wie Position, Schriftgröße und Box. Im HLS-Modus beginnt der Ticker mit jedem Clip neu.',
+        tickerSchedule: 'Tageszeiten mit Ticker, eine pro Zeile wie 06:00:00-09:00:00. Leer zeigt ihn den ganzen Tag.',
+        clockHelp: 'Digitale Uhr über dem Programm, in der Zeitzone des Kanals. Das Format nutzt strftime, wie %H:%M:%S. Position und Style nehmen drawtext-Optionen, die Schrift ist ein Pfad im Speicher, leer nimmt die Schrift aus dem Textbereich.',
+        visualRadioHelp: 'Video für Audioclips ohne Bild, so läuft eine Musiksammlung als Videokanal. Das Artwork kommt aus der Playlist, von einem Bild mit dem Namen der Audiodatei, einer cover.jpg oder folder.jpg in ihrem Ordner, oder vom Artwork hier. Unten wird ein Spektrum oder eine Wellenform des Tons gezeigt, der Now-Playing-Text nimmt drawtext-Optionen als Style.',
+        preview: 'Vorschau-Stream',
+        previewHelp: 'HLS-Stream der Programmausgabe mit niedriger Bitrate und Segmenten von einer Sekunde, zur Überwachung des Kanals im Browser, unabhängig von den Ausgabe-Einstellungen. Die Playlist ist /<Kanal-ID>/preview/stream.m3u8, sie kann als Vorschau-URL des Kanals benutzt werden.',
+        asrun: 'As-Run-Log',
+        asrunHelp: 'Tägliches As-Run-Log des letzten Playlist-Tages, mit geplanter und tatsächlicher Startzeit jedes Clips. Clips, die nie gesendet wurden, sind als skipped markiert, Clips, die an ihrer Stelle liefen, als substituted. Das Format ist ein BXF-ähnliches XML oder CSV, die Spalten werden mit Semikolon getrennt: scheduled, actual, deviation, planned, duration, title, source, category, status. Ohne Pfad landen die Logs unter asrun/<Kanal-ID> im Log-Pfad. Thumbnails speichern bei jedem Clipstart ein Bild der Programmausgabe, mit Intervall zusätzlich alle paar Minuten, als Sendenachweis im Ordner thumbnails neben den Logs.',
         outputHelp: `Die endgültige Playout-Codierung, passe die Einstellungen nach deinen Bedürfnissen an. Verwende den 'stream'-Modus und passe den 'Ausgabe-Parameter' an, wenn du zu einem RTMP/RTSP/SRT/...-Server streamen möchtest. Im Produktionsbetrieb verwende kein HLS mit ffplayout; nutze Nginx oder einen anderen Webserver!`,
         outputParam: 'HLS-Segment- und Playlist-Pfade sind relativ.',
         restartTile: 'Playout neustarten',
@@ -244,6 +323,23 @@ export default {
         confirmPass: 'Passwort bestätigen',
         save: 'Speichern',
         admin: 'Administrator',
+        role: 'Rolle',
+        channel_admin: 'Kanal-Admin',
+        user: 'Operator',
+        guest: 'Zuschauer',
+        channelRoles: 'Rolle pro Kanal',
+        defaultRole: 'Standardrolle',
+        global_admin: 'Globaler Admin',
+        apiTokens: 'API-Tokens',
+        tokenName: 'Token-Name',
+        tokenExpires: 'Läuft ab',
+        tokenLastUsed: 'Zuletzt benutzt',
+        tokenRevoke: 'Token widerrufen',
+        tokenRevoked: 'Widerrufen',
+        tokenDays: 'Tage',
+        tokenAdd: 'Token hinzufügen',
+        tokenOnce: 'Kopiere den Token jetzt, er wird nicht noch einmal angezeigt',
+        tokenAddFailed: 'Token hinzufügen fehlgeschlagen',
         deleteNotPossible: 'Löschen des aktuellen Benutzers nicht möglich!',
         deleteSuccess: 'Benutzer erfolgreich gelöscht!',
         deleteError: 'Fehler beim Löschen des Benutzers',
@@ -8,6 +8,7 @@ export default {
     },
     button: {
         login: 'Logar',
+        ssoLogin: 'Entrar com SSO',
         home: 'Início',
         player: 'Player',
         media: 'Armazenamento',
@@ -50,19 +51,25 @@ export default {
         reset: 'Redefinir Estado do Playout',
         restart: 'Reiniciar Serviço do Playout',
         next: 'Ir para o próximo Clipe',
+        take: 'Iniciar próximo clipe',
     },
     player: {
         start: 'Horário',
         file: 'Arquivo',
         play: 'Play',
         title: 'Título',
+        artist: 'Artista',
+        description: 'Descrição',
         duration: 'Duração',
         total: 'Total',
         in: 'Início',
         out: 'Fim',
         ad: 'Ad',
+        manual: 'Início manual',
+        manualHelp: 'O clipe espera com preenchimento pelo início do operador',
         edit: 'Editar',
         delete: 'Deletar',
+        goto: 'Clique duas vezes para reproduzir a partir daqui',
         copy: 'Copiar playlist',
         loop: 'Repetir Clipes na Playlist',
         remote: 'Adicionar fonte (remota) à Playlist',
@@ -76,6 +83,11 @@ export default {
         addEdit: 'Adicionar/Editar Fonte',
         audio: 'Áudio',
         customFilter: 'Filtro Personalizado',
+        filterPlugins: 'Plugins de Filtro',
+        decoderParams: 'Parâmetros do Decodificador',
+        aspectMode: 'Modo de Proporção',
+        fpsMode: 'Modo de FPS',
+        channelDefault: 'Padrão do Canal',
         deleteFrom: 'Excluir programação de',
         deleteSuccess: 'Lista de reprodução excluída...',
         generateProgram: 'Gerar Programação',
@@ -83,7 +95,16 @@ export default {
         advanced: 'Avançado',
         sorted: 'Ordenado',
         shuffle: 'Aleatório',
+        bestFit: 'Melhor Ajuste',
+        bestFitHelp: 'Escolhe os clipes que melhor preenchem o bloco, para reduzir o preenchimento',
+        series: 'Série',
+        seriesHelp: 'Reproduz os próximos episódios da pasta, na próxima vez continua após o último episódio',
+        libraryQuery: 'Consulta da biblioteca, como tag:music AND duration<300',
+        libraryQueryHelp: 'Usa os clipes da biblioteca de mídia em vez das pastas, com tags, campos e comparações combinados com AND, OR e NOT',
+        weekdays: 'Dias da semana do bloco, todos os dias quando nenhum está selecionado',
         shift: 'Diferença horária',
+        encoder: 'Codificador',
+        dropped: 'descartados',
         all: 'Todos',
         addBlock: 'Adicionar bloco de tempo',
         infinitInfo: 'O playout é executado no modo infinito. Nenhuma informação baseada em tempo é possível',
@@ -180,24 +201,50 @@ export default {
         playlist: 'Playlist',
         storage: 'Armazenamento',
         text: 'Texto',
+        script: 'Script do Agendador',
         task: 'Tarefa',
+        recorder: 'Gravador',
+        webhook: 'Webhook',
+        transition: 'Transição',
+        monitor: 'Monitor',
+        failover: 'Failover',
+        sync: 'Sincronização',
+        graphics: 'Gráficos',
+        ticker: 'Ticker',
+        clock: 'Relógio',
+        visualRadio: 'Rádio Visual',
         output: 'Saída',
         placeholderPass: 'Senha',
         help: 'Ajuda',
         generalHelp: 'Às vezes pode acontecer de um arquivo estar corrompido, mas ainda ser reproduzível. Isso pode causar um erro de streaming para todos os arquivos seguintes. A única solução nesse caso é parar o ffplayout e reiniciá-lo.',
         stopThreshold: 'O limite para o ffplayout se ele estiver fora de sincronia acima deste valor. Um número abaixo de 3 pode causar erros inesperados.',
+        driftCorrection: 'Corrige o desvio entre o playout e o relógio, movendo o ponto de saída de cada clipe no máximo esta quantidade de segundos. 0 desativa.',
         mailHelp: `Envie mensagens de erro para um endereço de e-mail, como clipes ausentes, formato de playlist ausente ou inválido, etc. Deixe o destinatário em branco se não precisar disso.`,
         mailInterval: 'O intervalo se refere ao número de segundos até o envio de um novo e-mail; o valor deve ser em incrementos de 10 e não inferior a 30 segundos.',
         logHelp: 'Ajuste o comportamento de log.',
         logDetect: 'Registra uma mensagem de erro se a linha de áudio estiver em silêncio por 15 segundos durante o processo de validação.',
         logIgnore: 'Ignorar strings que contenham linhas correspondentes; o formato é uma lista separada por ponto e vírgula.',
         processingHelp: 'O processamento padrão para todos os clipes garante a exclusividade.',
-        processingLogoPath: 'O logotipo só é usado se o caminho existir; o caminho é relativo à pasta de armazenamento.',
-        processingLogoScale: `Deixe a escala do logotipo em branco se não for necessário escalonamento. O formato é 'largura:altura', por exemplo: '100:-1' para escalonamento proporcional.`,
+        processingLogoPath: 'O logotipo só é usado se o caminho existir; o caminho é relativo à pasta de armazenamento. GIF animado, APNG, MOV com alfa e sequências de imagens como logos/bug_%03d.png são suportados.',
+        processingLogoScale: `Deixe a escala do logotipo em branco se não for necessário escalonamento. O formato é 'largura:altura', por exemplo: '100:-1' para escalonamento proporcional. Uma porcentagem como '10%' escala para a largura da saída.`,
+        processingLogoLoop: 'Quantas vezes um logotipo animado é reproduzido, depois o último quadro permanece. 0 repete sem fim.',
+        processingLogoFps: 'Taxa de quadros para sequências de imagens, 0 usa a taxa do arquivo.',
         processingLogoPosition: `A posição é especificada no formato 'x:y'.`,
+        processingLogoSchedule: 'Um logo por linha para uma faixa horária, como: 18:00:00-20:00:00 logos/prime.png. O logo muda com o clipe que começa no intervalo, nos outros horários vale o logo padrão. Um clipe na playlist pode definir seu próprio logo.',
         processingAudioTracks: 'Especifique quantas faixas de áudio devem ser processadas.',
         processingAudioIndex: 'Qual linha de áudio usar, -1 para todas.',
         processingAudioChannels: 'Defina a contagem de canais de áudio, se o áudio tiver mais canais do que estéreo.',
+        processingCrossfade: 'Duração em segundos da transição entre dois clipes, 0 a desativa. Fontes ao vivo, fontes remotas e imagens são cortadas sem transição.',
+        processingCrossfadeTransition: 'Transição de vídeo do filtro xfade, como fade, dissolve, wipeleft ou slideup.',
+        processingCrossfadeCurve: 'Curva de áudio do filtro acrossfade, como tri, qsin, exp ou log.',
+        processingLoudnorm: 'Normalização EBU R128. Cada arquivo é medido uma vez e o resultado é armazenado em cache, para que possa ser normalizado de forma linear na próxima reprodução. Até lá, o modo dinâmico é usado.',
+        processingAspectMode: 'Como clipes com outra proporção se encaixam: auto mantém a imagem inteira com barras, letterbox mantém a largura total, pillarbox a altura total, center-crop preenche o quadro e stretch distorce a imagem.',
+        processingDeinterlace: 'Auto desentrelaça clipes com ordem de campos, que o idet confirma em uma amostra de quadros. Ligado e desligado valem para todos os clipes.',
+        processingTonemap: 'Clipes HDR (PQ e HLG) recebem mapeamento de tons para SDR em BT.709. zscale precisa do ffmpeg com libzimg, libplacebo com libplacebo.',
+        processingFpsMode: 'Conversão de clipes com outra taxa de quadros: auto descarta ou duplica quadros em proporções inteiras como 50 para 25, mistura quadros nas outras como 30 para 25 e remove o telecine de clipes de filme entrelaçados a 29,97 fps. Interpolate calcula novos quadros a partir do movimento e exige muita CPU.',
+        processingColorSpace: 'Espaço de cor de destino da saída: auto usa BT.709 para HD e BT.601 para SD. Clipes com outra matriz de cor ou em faixa completa são convertidos e o codificador recebe as tags de cor correspondentes. Clipes sem tags abaixo de 720 linhas contam como BT.601.',
+        processingBackend: 'Experimental: o GStreamer codifica as saídas null, stream, SRT, NDI, desktop e DeckLink e decodifica clipes que não precisam de filtros do ffmpeg, como logo, texto, loudnorm, seek ou fades. Todos os outros clipes são decodificados com o ffmpeg. Requer gst-launch-1.0 com os plugins da saída.',
+        processingHwAccel: 'Decodificação, escala, desentrelaçamento e sobreposição do logo na GPU e a saída com o codificador de hardware. As configurações avançadas têm prioridade.',
         processingCustomFilter: 'Adicione filtros personalizados ao processamento. As saídas de filtro devem terminar com [c_v_out] para filtros de vídeo e [c_a_out] para filtros de áudio.',
         processingOverrideFilter: 'Attention: This option overwrites all standard filters, i.e. automatic format correction no longer takes place, the command must be structured as follows: -filter_complex [0:v]fps=25,scale=1280:-1[vout];[0:a:0]volume=0.5[aout] -map [vout] -map [aout]',
         processingVTTEnable: 'VTT só pode ser usado no modo HLS e apenas se houver arquivos *.vtt com o mesmo nome do arquivo de vídeo.',
@@ -208,17 +255,49 @@ export default {
         playlistDayStart: 'A que horas a playlist deve começar; deixe em branco se a playlist sempre começar do início.',
         playlistLength: 'Duração alvo da playlist; quando estiver em branco, o comprimento real não será considerado.',
         playlistInfinit: 'Reproduza infinitamente um único arquivo de playlist.',
+        playlistGapPolicy: 'Uma regra por linha, para lacunas no fim da playlist, por exemplo: 60-900 fit. O intervalo é a duração da lacuna em segundos, seguido da política: loop (último clipe), fit (preenchimento que melhor se encaixa), slate <caminho> ou tag <nome> (clipes da biblioteca de mídia). Outras lacunas usam o preenchimento.',
         storageHelp: 'Configurações de armazenamento, os locais são relativos ao armazenamento do canal.',
         storageFiller: 'Use um preenchimento para reproduzir no lugar de um arquivo ausente ou preencher o tempo restante para alcançar um total de 24 horas. Pode ser um arquivo ou uma pasta com caminho relativo, e será repetido quando necessário.',
         storageExtension: 'Especifique quais arquivos procurar e usar.',
         storageShuffle: 'Escolha arquivos aleatoriamente (no modo de pasta e geração de playlist).',
+        storageLibrary: 'Minutos entre duas varreduras da biblioteca de mídia, 0 desativa o scanner.',
+        storageQuery: 'Usa os arquivos no modo de pasta da biblioteca de mídia, com uma consulta como tag:music AND duration<300. Vazio usa todo o armazenamento.',
+        storagePreviews: 'Gera uma miniatura e um sprite de prévia com WebVTT para cada vídeo da biblioteca de mídia, para prévias ao navegar.',
         textHelp: 'Sobrepor texto em combinação com libzmq para manipulação remota de texto.',
         textFont: 'Caminho relativo ao armazenamento do canal.',
         textFromFile: 'Extração de texto a partir de um nome de arquivo.',
         textStyle: 'Defina os parâmetros drawtext, como posição, cor, etc. Postar texto pela API substituirá isso.',
         textRegex: 'Formate nomes de arquivos para extrair um título deles.',
+        textNowNext: 'Mostra o título do clipe atual e do próximo a cada início de clipe. Requer Adicionar Texto e não funciona junto com Texto do Arquivo.',
+        textNowNextFormat: 'Modelo para o texto agora/próximo, com as variáveis title, next_title e time entre chaves duplas.',
+        textNowNextStyle: 'Parâmetros drawtext para o texto agora/próximo, como posição, tamanho e caixa.',
         taskHelp: 'Execute um programa externo com um objeto de mídia fornecido. O objeto de mídia está em formato JSON e contém todas as informações sobre o clipe atual. O programa externo pode ser um script ou binário, mas deve ser executado apenas por um curto período de tempo.',
         taskPath: 'Caminho para o executável.',
+        scriptHelp: 'Um script Lua que pode alterar o próximo clipe, para lógica de agendamento própria. Ele pode definir as funções on_before_item, chamada antes de cada clipe da playlist, on_gap, antes de uma lacuna ser preenchida, e on_midnight, com o primeiro clipe da próxima playlist. Elas recebem o clipe e uma tabela de contexto e retornam uma tabela com os campos a alterar, ou nada. O script roda no runtime Lua embutido e mantém suas variáveis globais entre as chamadas. O playout espera por um hook até o tempo limite.',
+        recorderHelp: 'Grava a saída do programa em arquivos segmentados, para conformidade e verificação de exibição. O gravador também pode ser iniciado e parado pela API.',
+        recorderPath: 'Diretório de destino das gravações.',
+        recorderRetention: 'Dias para manter as gravações, 0 mantém para sempre.',
+        webhookHelp: 'Envia eventos do playout como requisições JSON POST para sistemas externos. Com um segredo, cada requisição recebe uma assinatura HMAC-SHA256 no cabeçalho X-Ffplayout-Signature.',
+        webhookUrls: 'Um endpoint por linha.',
+        webhookEvents: 'Eventos a enviar, separados por ponto e vírgula, todos quando vazio: channel_start, channel_stop, clip_start, clip_end, playlist_missing, filler, restart, silence, black, freeze, corrupt, failover, clock_jump, output_unreachable.',
+        eventHooks: 'Hooks de Eventos',
+        eventHooksHelp: 'Comandos shell, executados no início e no fim do clipe e em erros. O evento vem em variáveis de ambiente: FFP_EVENT, FFP_CHANNEL, FFP_TIME, FFP_DATA como JSON e uma variável para cada valor, como FFP_SOURCE ou FFP_TITLE. O playout não espera pelos comandos, eles são encerrados após o timeout em segundos.',
+        mqttHelp: 'Publica os eventos do playout em um broker MQTT, para automação de estúdio como sistemas de iluminação ou de grafismo. O clipe atual é enviado como mensagem retida.',
+        mqttTopic: 'Tópico base, o nome do evento é adicionado ao final. O padrão é ffplayout/<id do canal>.',
+        transitionHelp: 'Um stinger é um clipe curto com canal alfa, como ProRes 4444, que é reproduzido sobre o corte entre dois clipes. Deixe o caminho vazio para desativá-lo.',
+        transitionStingerOffset: 'Segundos do início do stinger até o corte, geralmente o ponto em que o stinger cobre a imagem inteira.',
+        monitorHelp: 'Alarmes para silêncio, quadros pretos e imagem congelada nos clipes reproduzidos, o que geralmente indica um arquivo de origem ruim. Quando a duração em segundos é atingida, um erro é registrado e o evento silence, black ou freeze é enviado. Um clipe congelado também é pulado e o próximo clipe começa. Uma duração de 0 desativa a detecção. Áudio abaixo do nível de ruído conta como silêncio, pixels abaixo do nível de preto (0 - 1) contam como pretos.',
+        failoverHelp: 'Fonte secundária, para quando a playlist está ausente ou o armazenamento não está acessível. A fonte pode ser uma pasta com clipes, um único clipe ou imagem, ou a URL de uma entrada ao vivo. Após cada clipe de failover, a fonte principal é verificada novamente e a reprodução volta quando ela se recupera. As duas trocas enviam o evento failover.',
+        syncHelp: 'Sincronização entre motor principal e de backup. O motor principal precisa de um segredo, então ele fornece sua posição de reprodução em /data/sync/ e o id do canal. Um motor de backup recebe o mesmo segredo e a URL do principal, ele segue o deslocamento de tempo do principal. Os relógios dos dois servidores devem estar sincronizados.',
+        graphicsHelp: "Gráficos HTML como tickers, relógios ou widgets de clima. Um comando de renderização transforma a página web no intervalo em uma imagem transparente, que é sobreposta ao programa. O comando recebe os marcadores {'{source}'}, {'{output}'}, {'{width}'} e {'{height}'}, quando vazio é usado o wkhtmltoimage.",
+        tickerHelp: 'Ticker de notícias na parte inferior do programa. As manchetes dos feeds RSS, Atom ou JSON são buscadas no intervalo (segundos) e rolam com a velocidade em pixels por segundo. Style recebe opções do drawtext como posição, tamanho da fonte e caixa. No modo HLS o ticker recomeça a cada clipe.',
+        tickerSchedule: 'Períodos do dia com ticker, um por linha como 06:00:00-09:00:00. Vazio mostra o dia todo.',
+        clockHelp: 'Relógio digital sobre o programa, no fuso horário do canal. O formato usa strftime, como %H:%M:%S. Posição e estilo recebem opções do drawtext, a fonte é um caminho no armazenamento, vazio usa a fonte da seção de texto.',
+        visualRadioHelp: 'Vídeo para clipes de áudio sem imagem, assim uma biblioteca de música roda como canal de vídeo. A arte vem da playlist, de uma imagem com o mesmo nome do arquivo de áudio, de um cover.jpg ou folder.jpg na sua pasta, ou da arte aqui. Um espectro ou forma de onda do áudio aparece embaixo, o texto tocando agora recebe opções do drawtext como estilo.',
+        preview: 'Stream de Prévia',
+        previewHelp: 'Stream HLS de baixa taxa de bits da saída do programa com segmentos de um segundo, para monitorar o canal no navegador, independente das configurações de saída. A playlist é /<id do canal>/preview/stream.m3u8, ela pode ser usada como URL de prévia do canal.',
+        asrun: 'Log As-Run',
+        asrunHelp: 'Log as-run diário do último dia da playlist, com horário de início planejado e real de cada clipe. Clipes que nunca foram ao ar são marcados como skipped, clipes que foram ao ar no lugar deles como substituted. O formato é um XML parecido com BXF ou CSV, as colunas são separadas por ponto e vírgula: scheduled, actual, deviation, planned, duration, title, source, category, status. Com o caminho vazio, os logs ficam em asrun/<id do canal> no caminho de logs. Thumbnails salvam um quadro da saída do programa no início de cada clipe, e com intervalo a cada poucos minutos, como prova de exibição na pasta thumbnails ao lado dos logs.',
         outputHelp: `A codificação final do playout, ajuste as configurações de acordo com suas necessidades. Use o modo 'stream' e ajuste o 'Parâmetro de Saída' quando quiser fazer streaming para um servidor RTMP/RTSP/SRT/... No ambiente de produção, não sirva playlists HLS com ffplayout; use Nginx ou outro servidor web!`,
         outputParam: 'Os caminhos dos segmentos e playlists HLS são relativos.',
         restartTile: 'Reiniciar Playout',
@@ -244,6 +323,23 @@ export default {
         confirmPass: 'Confirmar Senha',
         save: 'Salvar',
         admin: 'Administrador',
+        role: 'Função',
+        channel_admin: 'Administrador do canal',
+        user: 'Operador',
+        guest: 'Visualizador',
+        channelRoles: 'Função por canal',
+        defaultRole: 'Função padrão',
+        global_admin: 'Administrador global',
+        apiTokens: 'Tokens de API',
+        tokenName: 'Nome do token',
+        tokenExpires: 'Expira',
+        tokenLastUsed: 'Último uso',
+        tokenRevoke: 'Revogar token',
+        tokenRevoked: 'Revogado',
+        tokenDays: 'Dias',
+        tokenAdd: 'Adicionar token',
+        tokenOnce: 'Copie o token agora, ele não será mostrado novamente',
+        tokenAddFailed: 'Falha ao adicionar token',
         deleteNotPossible: 'Excluir o usuário atual não é possível!',
         deleteSuccess: 'Usuário deletado com sucesso!',
         deleteError: 'Erro ao deletar usuário',
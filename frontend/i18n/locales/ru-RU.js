@@ -8,6 +8,7 @@ export default {
     },
     button: {
         login: 'Логин',
+        ssoLogin: 'Login with SSO',
         home: 'Домашняя страница',
         player: 'Плеер',
         media: 'Файлы',
@@ -50,19 +51,25 @@ export default {
         reset: 'Сбросить Playout State',
         restart: 'Перезапустить Playout',
         next: 'Переключить на следующий файл',
+        take: 'Take next clip',
     },
     player: {
         start: 'Начало',
         file: 'Файл',
         play: 'Проиграть',
         title: 'Название файла',
+        artist: 'Artist',
+        description: 'Description',
         duration: 'Длительность',
         total: 'Всего',
         in: 'Начало',
         out: 'Конец',
         ad: 'Реклама',
+        manual: 'Manual Start',
+        manualHelp: 'The clip waits with filler for the take from the operator',
         edit: 'Редактировать',
         delete: 'Удалить',
+        goto: 'Double click to play from here',
         copy: 'Скопировать плейлист',
         loop: 'Повторить все видео в плейлисте',
         remote: 'Добавить (удаленно) файл в плейлист',
@@ -76,6 +83,11 @@ export default {
         addEdit: 'Добавить/Редактировать файл',
         audio: 'Звук',
         customFilter: 'Пользовательский фильтр',
+        filterPlugins: 'Плагины фильтров',
+        decoderParams: 'Параметры декодера',
+        aspectMode: 'Режим соотношения сторон',
+        fpsMode: 'Режим частоты кадров',
+        channelDefault: 'По умолчанию канала',
         deleteFrom: 'Удалить плейлист за',
         deleteSuccess: 'Плейлист удален...',
         generateProgram: 'Генерация плейлиста',
@@ -83,7 +95,16 @@ export default {
         advanced: 'Расширенный',
         sorted: 'Отсортировать',
         shuffle: 'Перемещать/Перетасовать',
+        bestFit: 'Best Fit',
+        bestFitHelp: 'Pick the clips which fill the block best, to keep the filler short',
+        series: 'Series',
+        seriesHelp: 'Play the next episodes from the folder, the following time the playout continues after the last episode',
+        libraryQuery: 'Library query, like tag:music AND duration<300',
+        libraryQueryHelp: 'Take the clips from the media library instead of the folders, with tags, fields and comparisons combined by AND, OR and NOT',
+        weekdays: 'Weekdays of the block, every day when none is selected',
         shift: 'Перемотка',
+        encoder: 'Кодировщик',
+        dropped: 'пропущено',
         all: 'Все',
         addBlock: 'Добавить время начало передачи',
         infinitInfo: 'Воспроизведение работает в бесконечном режиме. Никакая информация, основанная на времени, невозможна.',
@@ -180,24 +201,50 @@ export default {
         playlist: 'Плейлист',
         storage: 'Storage',
         text: 'Текст',
+        script: 'Скрипт планировщика',
         task: 'Task',
+        recorder: 'Recorder',
+        webhook: 'Webhook',
+        transition: 'Transition',
+        monitor: 'Monitor',
+        failover: 'Failover',
+        sync: 'Sync',
+        graphics: 'Graphics',
+        ticker: 'Ticker',
+        clock: 'Clock',
+        visualRadio: 'Visual Radio',
         output: 'Out',
         placeholderPass: 'Password',
         help: 'Help',
         generalHelp: 'Sometimes it can happen that a file is corrupt but still playable. This can produce a streaming error for all following files. The only solution in this case is to stop ffplayout and start it again.',
         stopThreshold: 'The threshold stops ffplayout if it is asynchronous in time above this value. A number below 3 can cause unexpected errors.',
+        driftCorrection: 'Correct the drift between playout and wall clock, by moving the out point of each clip at most this many seconds. 0 disables it.',
         mailHelp: `Send error messages to an email address, such as missing clips, missing or invalid playlist format, etc.. Leave the recipient blank if you don't need this.`,
         mailInterval: 'The interval refers to the number of seconds until a new email is sent; the value must be in increments of 10 and not lower then 30 seconds.',
         logHelp: 'Adjust logging behavior.',
         logDetect: 'Logs an error message if the audio line is silent for 15 seconds during the validation process.',
         logIgnore: 'Ignore strings that contain matched lines; the format is a semicolon-separated list.',
         processingHelp: 'Default processing for all clips ensures uniqueness.',
-        processingLogoPath: 'The logo is used only if the path exists; the path is relative to the storage folder.',
-        processingLogoScale: `Leave logo scale blank if no scaling is needed. The format is 'width:height', for example: '100:-1' for proportional scaling.`,
+        processingLogoPath: 'The logo is used only if the path exists; the path is relative to the storage folder. Animated GIF, APNG, MOV with alpha and image sequences like logos/bug_%03d.png are supported.',
+        processingLogoScale: `Leave logo scale blank if no scaling is needed. The format is 'width:height', for example: '100:-1' for proportional scaling. A percentage like '10%' scales to the output width.`,
+        processingLogoLoop: 'How often an animated logo plays, afterwards the last frame stays. 0 loops endless.',
+        processingLogoFps: 'Frame rate for image sequences, 0 takes the rate from the file.',
         processingLogoPosition: `Position is specified in the format 'x:y'`,
+        processingLogoSchedule: 'One logo per line for a day-part, like: 18:00:00-20:00:00 logos/prime.png. The logo changes with the clip that starts in the time range, other times use the default logo. A clip in the playlist can set its own logo.',
         processingAudioTracks: 'Specify how many audio tracks should be processed.',
         processingAudioIndex: 'Which audio line to use, -1 for all.',
         processingAudioChannels: 'Set the audio channel count, if audio has more channels than stereo.',
+        processingCrossfade: 'Length in seconds of the crossfade between two clips, 0 disables it. Live sources, remote sources and images are cut without crossfade.',
+        processingCrossfadeTransition: 'Video transition of the xfade filter, like fade, dissolve, wipeleft or slideup.',
+        processingCrossfadeCurve: 'Audio curve of the acrossfade filter, like tri, qsin, exp or log.',
+        processingLoudnorm: 'EBU R128 normalization. Every file gets measured once and the result is cached, so it can be normalized linear on the next playback. Until then the dynamic mode is used.',
+        processingAspectMode: 'Как вписываются клипы с другим соотношением сторон: auto сохраняет всё изображение с полосами, letterbox сохраняет полную ширину, pillarbox полную высоту, center-crop заполняет кадр, stretch растягивает изображение.',
+        processingDeinterlace: 'Auto выполняет деинтерлейсинг клипов с порядком полей, который idet подтверждает на выборке кадров. On и off действуют для всех клипов.',
+        processingTonemap: 'HDR-клипы (PQ и HLG) преобразуются в SDR BT.709 с помощью тонального отображения. Для zscale нужен ffmpeg с libzimg, для libplacebo с libplacebo.',
+        processingFpsMode: 'Преобразование клипов с другой частотой кадров: auto отбрасывает или дублирует кадры при целых соотношениях, например 50 к 25, смешивает кадры при остальных, например 30 к 25, и убирает телесин у чересстрочных фильмов 29,97 fps. Interpolate вычисляет новые кадры по движению и требует много CPU.',
+        processingColorSpace: 'Целевое цветовое пространство вывода: auto использует BT.709 для HD и BT.601 для SD. Клипы с другой цветовой матрицей или в полном диапазоне преобразуются, а кодировщик получает соответствующие цветовые теги. Клипы без тегов ниже 720 строк считаются BT.601.',
+        processingBackend: 'Экспериментально: GStreamer кодирует выходы null, stream, SRT, NDI, desktop и DeckLink и декодирует клипы, которым не нужны фильтры ffmpeg, такие как логотип, текст, loudnorm, перемотка или затухания. Все остальные клипы декодируются с помощью ffmpeg. Требуется gst-launch-1.0 с плагинами для выхода.',
+        processingHwAccel: 'Декодирование, масштабирование, деинтерлейсинг и наложение логотипа на GPU, вывод через аппаратный кодировщик. Расширенные настройки имеют приоритет.',
         processingCustomFilter: 'Add custom filters to the processing. The filter outputs must end with [c_v_out] for video filters and [c_a_out] for audio filters.',
         processingOverrideFilter: 'Attention: This option overwrites all standard filters, i.e. automatic format correction no longer takes place, the command must be structured as follows: -filter_complex [0:v]fps=25,scale=1280:-1[vout];[0:a:0]volume=0.5[aout] -map [vout] -map [aout]',
         processingVTTEnable: 'VTT can only be used in HLS mode and only if there are *.vtt files with the same name as the video file.',
@@ -208,17 +255,49 @@ export default {
         playlistDayStart: 'At what time the playlist should start; leave it blank if the playlist should always start at the beginning.',
         playlistLength: 'Target length of the playlist; when it is blank, the real length will not be considered.',
         playlistInfinit: 'Loop a single playlist file infinitely.',
+        playlistGapPolicy: 'One rule per line, for gaps at the playlist end, like: 60-900 fit. The range is the gap length in seconds, then follows the policy: loop (last clip), fit (best fitting filler), slate <path> or tag <name> (clips from the media library). Other gaps use the filler.',
         storageHelp: 'Storage settings, locations are relative to channel storage.',
         storageFiller: 'Use filler to play in place of a missing file or to fill the remaining time to reach a total of 24 hours. It can be a file or folder, with relative path, and will loop when necessary.',
         storageExtension: 'Specify which files to search and use.',
         storageShuffle: 'Pick files randomly (in folder mode and playlist generation).',
+        storageLibrary: 'Minutes between two scans of the media library, 0 disables the scanner.',
+        storageQuery: 'Take the files in folder mode from the media library, with a query like tag:music AND duration<300. Empty uses the whole storage.',
+        storagePreviews: 'Generate a thumbnail and a preview sprite with WebVTT for every video in the media library, for scrubbing previews.',
         textHelp: 'Overlay text in combination with libzmq for remote text manipulation.',
         textFont: 'Relative path to channel storage.',
         textFromFile: 'Extraction of text from a filename.',
         textStyle: 'Define the drawtext parameters, such as position, color, etc. Posting text over the API will override this.',
         textRegex: 'Format file names to extract a title from them.',
+        textNowNext: 'Show the title of the current and the next clip at every clip start. Needs Add Text and does not work together with Text from File.',
+        textNowNextFormat: 'Template for the now/next text, with the variables title, next_title and time in double curly braces.',
+        textNowNextStyle: 'Drawtext parameters for the now/next text, like position, size and box.',
         taskHelp: 'Run an external program with a given media object. The media object is in JSON format and contains all the information about the current clip. The external program can be a script or a binary, but it should only run for a short time.',
         taskPath: 'Path to executable.',
+        scriptHelp: 'Lua-скрипт, который может изменить следующий клип, для собственной логики планирования. Он может определить функции on_before_item, вызываемую перед каждым клипом плейлиста, on_gap, перед заполнением пропуска, и on_midnight, с первым клипом следующего плейлиста. Они получают клип и таблицу контекста и возвращают таблицу с изменяемыми полями или ничего. Скрипт выполняется во встроенной среде Lua и сохраняет глобальные переменные между вызовами. Плейаут ждёт хук до истечения тайм-аута.',
+        recorderHelp: 'Record the program output to segmented files, for compliance and air checks. The recorder can also be started and stopped over the API.',
+        recorderPath: 'Target directory for the recordings.',
+        recorderRetention: 'Days to keep recordings, 0 keeps them forever.',
+        webhookHelp: 'Send playout events as JSON POST requests to external systems. With a secret, each request gets a HMAC-SHA256 signature in the X-Ffplayout-Signature header.',
+        webhookUrls: 'One endpoint per line.',
+        webhookEvents: 'Events to send, separated by semicolon, all when empty: channel_start, channel_stop, clip_start, clip_end, playlist_missing, filler, restart, silence, black, freeze, corrupt, failover, clock_jump, output_unreachable.',
+        eventHooks: 'Event Hooks',
+        eventHooksHelp: 'Shell commands, which run on clip start, clip end and errors. The event comes in environment variables: FFP_EVENT, FFP_CHANNEL, FFP_TIME, FFP_DATA as JSON and one variable for each value, like FFP_SOURCE or FFP_TITLE. The playout does not wait for the commands, they get stopped after the timeout in seconds.',
+        mqttHelp: 'Publish the playout events to a MQTT broker, for studio automation like lighting or CG systems. The current clip is sent as retained message.',
+        mqttTopic: 'Base topic, the event name gets appended. Defaults to ffplayout/<channel id>.',
+        transitionHelp: 'A stinger is a short clip with alpha channel, like ProRes 4444, which plays over the cut between two clips. Leave the path empty to disable it.',
+        transitionStingerOffset: 'Seconds from the stinger start to the cut, usually the point where the stinger covers the whole picture.',
+        monitorHelp: 'Alarms for silence, black and frozen frames in the played clips, which usually means a bad source file. When the duration in seconds is reached, an error is logged and the silence, black or freeze event is sent. A frozen clip is also skipped and the next clip starts. A duration of 0 disables the detection. Audio below the noise level counts as silence, pixels below the black level (0 - 1) count as black.',
+        failoverHelp: 'Secondary source, for when the playlist is missing or the storage is not reachable. The source can be a folder with clips, a single clip or image, or a live input URL. After each failover clip the primary source is checked again, and the playout switches back when it recovers. Both switches send the failover event.',
+        syncHelp: 'Main/backup synchronization of two engines. The main engine needs a secret, then it serves its playhead on /data/sync/ and the channel id. A backup engine gets the same secret and the URL of the main, it follows the time shift of the main. The clocks of both servers must be synchronized.',
+        graphicsHelp: "HTML graphics like tickers, clocks or weather widgets. A render command turns the web page in the interval into a transparent image, which is laid over the program. The command gets the placeholders {'{source}'}, {'{output}'}, {'{width}'} and {'{height}'}, when empty wkhtmltoimage is used.",
+        tickerHelp: 'News ticker at the bottom of the program. The headlines of the RSS, Atom or JSON feeds are pulled in the interval (seconds) and scroll with the speed in pixels per second. Style takes drawtext options like position, font size and box. In HLS mode the ticker starts again with each clip.',
+        tickerSchedule: 'Day-parts with ticker, one per line like 06:00:00-09:00:00. Empty shows it all day.',
+        clockHelp: 'Digital clock over the program, in the time zone of the channel. The format uses strftime, like %H:%M:%S. Position and style take drawtext options, the font is a path in the storage, empty takes the font from the text section.',
+        visualRadioHelp: 'Video for audio clips without picture, so a music library runs as video channel. The artwork comes from the playlist, an image with the same name as the audio file, a cover.jpg or folder.jpg in its folder, or the artwork here. A spectrum or waveform of the audio is shown at the bottom, the now playing text takes drawtext options as style.',
+        preview: 'Preview Stream',
+        previewHelp: 'Low bitrate HLS stream of the program output with one second segments, for monitoring the channel in the browser, independent of the output settings. The playlist is /<channel id>/preview/stream.m3u8, it can be used as preview URL of the channel.',
+        asrun: 'As-run Log',
+        asrunHelp: 'Daily as-run log of the last playlist day, with scheduled and actual start time of each clip. Clips which never aired are marked as skipped, clips which aired in their place as substituted. Format is a BXF like XML or CSV, columns are separated by semicolon: scheduled, actual, deviation, planned, duration, title, source, category, status. An empty path writes the logs to asrun/<channel id> in the log path. Thumbnails save a frame of the program output at each clip start, and with an interval every few minutes, as proof-of-play in the thumbnails folder next to the logs.',
         outputHelp: `The final playout encoding, set the settings according to your needs. Use 'stream' mode and adjust the 'Output Parameter' when you want to stream to an RTMP/RTSP/SRT/... server.
         In production, don't serve HLS playlists with ffplayout; use Nginx or another web server!`,
         outputParam: 'HLS segment and playlist paths are relative.',
@@ -245,6 +324,23 @@ export default {
         confirmPass: 'Подтвердите пароль',
         save: 'Сохранить',
         admin: 'Админ',
+        role: 'Role',
+        channel_admin: 'Channel Admin',
+        user: 'Operator',
+        guest: 'Viewer',
+        channelRoles: 'Role per Channel',
+        defaultRole: 'Default role',
+        global_admin: 'Global Admin',
+        apiTokens: 'API Tokens',
+        tokenName: 'Token Name',
+        tokenExpires: 'Expires',
+        tokenLastUsed: 'Last used',
+        tokenRevoke: 'Revoke token',
+        tokenRevoked: 'Revoked',
+        tokenDays: 'Days',
+        tokenAdd: 'Add token',
+        tokenOnce: 'Copy the token now, it is not shown again',
+        tokenAddFailed: 'Add token failed',
         deleteNotPossible: 'Delete current user not possible!',
         deleteSuccess: 'Удаление пользователя успешно!',
         deleteError: 'Удаление пользователя с ошибкой',
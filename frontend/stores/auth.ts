@@ -45,6 +45,13 @@ export const useAuth = defineStore('auth', {
             this.authHeader = {}
         },
 
+        tokenLogin(token: string, refresh: string) {
+            this.updateToken(token, refresh)
+            const decodedToken = jwtDecode<JwtPayloadExt>(token)
+            this.isLogin = true
+            this.role = decodedToken.role
+        },
+
         async obtainToken(username: string, password: string) {
             let code = 0
             const payload = {
@@ -60,10 +67,7 @@ export const useAuth = defineStore('auth', {
                 },
             })
                 .then((response: Token) => {
-                    this.updateToken(response.access, response.refresh)
-                    const decodedToken = jwtDecode<JwtPayloadExt>(response.access)
-                    this.isLogin = true
-                    this.role = decodedToken.role
+                    this.tokenLogin(response.access, response.refresh)
                 })
                 .catch((e) => {
                     code = e.status
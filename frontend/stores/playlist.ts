@@ -19,8 +19,10 @@ export const usePlaylist = defineStore('playlist', {
         current: {} as PlaylistItem,
         currentIndex: 0,
         ingestRuns: false,
+        manualWait: false,
         elapsedSec: 0,
         shift: 0,
+        encoder: null as EncoderProgress | null,
         playoutIsRunning: false,
         last_channel: 0,
         firstLoad: true,
@@ -82,7 +84,9 @@ export const usePlaylist = defineStore('playlist', {
             this.currentIndex = item.index
             this.elapsedSec = item.elapsed
             this.ingestRuns = item.ingest
+            this.manualWait = item.manual_wait ?? false
             this.shift = item.shift
+            this.encoder = item.encoder ?? null
 
             this.progressValue = (this.elapsedSec * 100) / this.current.out
         },
@@ -49,9 +49,23 @@ declare global {
         confirm?: string
         admin?: boolean
         channel_ids?: number[]
+        channel_roles?: Record<number, string>
         role_id?: number
     }
 
+    interface ApiToken {
+        id?: number
+        user_id?: number
+        name: string
+        role_id: number
+        channel_ids?: number[]
+        created_at?: string
+        expires_in?: number
+        expires_at?: string
+        last_used?: string
+        revoked?: boolean
+    }
+
     interface Crumb {
         text: string
         path: string
@@ -69,18 +83,36 @@ declare global {
         program: PlaylistItem[]
     }
 
+    interface ClipAudio {
+        source?: string
+        gain_db?: number
+        channel_map?: number[]
+        stream_index?: number
+    }
+
     interface PlaylistItem {
         date?: string
         uid: string
         begin: number
         title?: string | null
+        artist?: string
+        description?: string
         source: string
         duration: number
         in: number
         out: number
-        audio?: string
+        audio?: string | ClipAudio
         category?: string
         custom_filter?: string
+        filters?: string[]
+        custom_decoder_params?: string
+        aspect_mode?: string
+        fps_mode?: string
+        logo?: string
+        artwork?: string
+        trigger?: string
+        som?: string
+        eom?: string
         overtime?: boolean
     }
 
@@ -117,7 +149,11 @@ declare global {
         start: string
         duration: string
         shuffle: boolean
+        best_fit: boolean
+        series: boolean
         paths: string[]
+        query: string
+        weekdays: string[]
     }
 
     interface Template {
@@ -143,10 +179,24 @@ declare global {
         media: PlaylistItem
         index: number
         ingest: boolean
+        manual_wait?: boolean
         mode: string
         elapsed: number
         shift: number
         title?: string
+        encoder?: EncoderProgress | null
+    }
+
+    interface EncoderProgress {
+        frame: number
+        fps: number
+        bitrate: number
+        speed: number
+        drop_frames: number
+        dup_frames: number
+        total_size: number
+        out_time: number
+        updated: string
     }
 
     interface SplitTime {
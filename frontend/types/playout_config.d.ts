@@ -1,6 +1,133 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type General = { stop_threshold: number, };
+/**
+ * Daily as-run log, with the scheduled and the actual start time of each clip.
+ */
+export type AsRun = { enable: boolean, 
+/**
+ * Target directory for the logs, empty writes them to `asrun/<channel id>` in the log path.
+ */
+path: string, 
+/**
+ * `xml` for a BXF like document or `csv`.
+ */
+format: string, 
+/**
+ * Columns of the CSV log.
+ */
+columns: Array<string>, 
+/**
+ * Save a frame of the program output at each clip start, as proof-of-play.
+ */
+thumbnails: boolean, 
+/**
+ * Minutes between additional frames, 0 saves only at clip start.
+ */
+thumbnail_interval: number, };
+
+/**
+ * Fitting of clips with another aspect ratio into the output.
+ *
+ * Letterbox keeps the full width, pillarbox the full height, auto keeps the full picture.
+ */
+export type AspectMode = "auto" | "letterbox" | "pillarbox" | "center-crop" | "stretch";
+
+/**
+ * Program for decoding and encoding.
+ *
+ * GStreamer is experimental: it encodes the null, stream, SRT, NDI, desktop and DeckLink
+ * output and decodes clips which need no ffmpeg filters, all other clips use ffmpeg.
+ */
+export type Backend = "ffmpeg" | "gstreamer";
+
+/**
+ * Digital clock over the program, in the time zone of the channel.
+ */
+export type Clock = { enable: boolean, 
+/**
+ * strftime format, like `%H:%M:%S`.
+ */
+format: string, 
+/**
+ * drawtext position, like `x=w-tw-40:y=40`.
+ */
+position: string, 
+/**
+ * Font file, empty takes the font from the text section.
+ */
+font: string, 
+/**
+ * drawtext options, like `fontsize=32:fontcolor=white`.
+ */
+style: string, };
+
+/**
+ * Target color space of the channel.
+ *
+ * Auto takes BT.709 for HD and BT.601 for SD outputs. Clips in another matrix or in full
+ * range get converted, the encoder gets the matching tags. Off passes the clips through.
+ */
+export type ColorSpace = "off" | "auto" | "bt709" | "bt601";
+
+/**
+ * Deinterlacing of the clips, `auto` checks the field order and samples the frames with idet.
+ */
+export type Deinterlace = "auto" | "on" | "off";
+
+/**
+ * Shell commands for playout events, for legacy station automation.
+ */
+export type EventHooks = { clip_start: string, clip_end: string, 
+/**
+ * Command for errors, like a missing clip or a decoder restart.
+ */
+error: string, 
+/**
+ * Longest run time of a command in seconds, then it gets killed.
+ */
+timeout: number, };
+
+/**
+ * Secondary source, for when the playlist is missing or the storage is not reachable.
+ */
+export type Failover = { enable: boolean, 
+/**
+ * Folder with clips, a single clip or image, or a live input URL.
+ */
+source: string, };
+
+/**
+ * Frame rate conversion of clips with another frame rate.
+ *
+ * Drop duplicates or drops frames, blend mixes neighbor frames, interpolate calculates new
+ * frames from the motion and ivtc removes the telecine from 29.97 fps clips with film content.
+ */
+export type FpsMode = "auto" | "drop" | "blend" | "interpolate" | "ivtc";
+
+export type General = { stop_threshold: number, drift_correction: number, };
+
+/**
+ * HTML graphics, rendered by a sidecar and laid over the program.
+ */
+export type Graphics = { enable: boolean, 
+/**
+ * URL or local path of the web page.
+ */
+source: string, 
+/**
+ * Render command with the placeholders {source}, {output}, {width} and {height}.
+ * Empty uses wkhtmltoimage.
+ */
+command: string, 
+/**
+ * Seconds between two renderings.
+ */
+interval: number, };
+
+/**
+ * Hardware acceleration profile, for decoding, filters and encoding.
+ */
+export type HwAccel = "none" | "nvenc" | "qsv" | "vaapi";
 
 export type Ingest = { enable: boolean, input_param: string, custom_filter: string, };
 
@@ -8,25 +135,443 @@ export type Logging = { ffmpeg_level: string, ingest_level: string, detect_silen
 
 export type Mail = { show: boolean, subject: string, recipient: string, mail_level: string, interval: bigint, };
 
-export type Output = { mode: OutputMode, output_param: string, };
+/**
+ * Alarms for silence, black and frozen frames in the played clips.
+ */
+export type Monitor = { 
+/**
+ * Seconds of silence until the alarm, 0 disables the detection.
+ */
+silence_duration: number, 
+/**
+ * Noise tolerance in dB, quieter audio counts as silence.
+ */
+silence_noise: number, 
+/**
+ * Seconds of black frames until the alarm, 0 disables the detection.
+ */
+black_duration: number, 
+/**
+ * Luminance threshold from 0 to 1, darker pixels count as black.
+ */
+black_level: number, 
+/**
+ * Seconds of a frozen picture until the alarm and the skip to the next clip, 0 disables it.
+ */
+freeze_duration: number, };
 
-export type OutputMode = "desktop" | "hls" | "null" | "stream";
+export type Mqtt = { enable: boolean, 
+/**
+ * Broker address as host:port.
+ */
+broker: string, 
+/**
+ * Base topic, the event name gets appended. Defaults to ffplayout/{channel id}.
+ */
+topic: string, username: string, password: string, };
 
-export type Playlist = { day_start: string, length: string, infinit: boolean, };
+export type Output = { mode: OutputMode, output_param: string, srt_mode: SrtMode, 
+/**
+ * host:port, the host is the bind address in listener mode.
+ */
+srt_address: string, srt_passphrase: string, 
+/**
+ * Latency in milliseconds.
+ */
+srt_latency: bigint, 
+/**
+ * NDI sender name, default is ffplayout-<channel id>.
+ */
+ndi_name: string, 
+/**
+ * DeckLink device name, or its index.
+ */
+decklink_device: string, 
+/**
+ * SDI video mode, like 1080i50, 720p59.94, pal or ntsc.
+ */
+decklink_mode: string, 
+/**
+ * Additional outputs in stream and SRT mode, one ffmpeg output parameter string each,
+ * like `-f flv rtmp://127.0.0.1/live/stream`. They get a copy of the encoded stream.
+ */
+legs: Array<string>, 
+/**
+ * Icecast server as host:port.
+ */
+icecast_address: string, 
+/**
+ * Mount point, like `/radio.mp3`.
+ */
+icecast_mount: string, icecast_username: string, icecast_password: string, 
+/**
+ * Audio format for the mount: mp3, aac or opus.
+ */
+icecast_format: string, 
+/**
+ * Audio bitrate in kbit/s.
+ */
+icecast_bitrate: bigint, 
+/**
+ * Output parameters for a generated video with cover and spectrum,
+ * like `-c:v libx264 -c:a aac -f flv rtmp://127.0.0.1/live/radio`. Empty disables it.
+ */
+icecast_video: string, 
+/**
+ * Cover image for the generated video, a black background when empty.
+ */
+icecast_cover: string, 
+/**
+ * Push the title of each clip to the metadata endpoint of the mount.
+ */
+icecast_metadata: boolean, 
+/**
+ * Metadata endpoint, like `http://127.0.0.1:8000/admin.cgi` for Shoutcast.
+ * Empty uses `/admin/metadata` of the Icecast address.
+ */
+icecast_metadata_url: string, 
+/**
+ * Renditions of the HLS output, one per line as `<width>x<height> <video kbit/s> [<audio kbit/s>]`,
+ * largest first. When set, the HLS output parameters are generated from it.
+ */
+hls_ladder: Array<string>, 
+/**
+ * fMP4 segments instead of MPEG-TS, for the ladder.
+ */
+hls_fmp4: boolean, 
+/**
+ * Segment length in seconds, for the ladder.
+ */
+hls_time: number, 
+/**
+ * Low latency HLS for the ladder, with CMAF chunks and playlists which announce the
+ * next segment.
+ */
+hls_low_latency: boolean, 
+/**
+ * Length of a CMAF chunk in seconds, in low latency mode.
+ */
+hls_part_time: number, 
+/**
+ * Add `EXT-X-PROGRAM-DATE-TIME` tags to the HLS playlists, the low latency playlists
+ * have them always.
+ */
+hls_program_date_time: boolean, 
+/**
+ * Encrypt the HLS segments with AES-128.
+ */
+hls_encryption: boolean, 
+/**
+ * Seconds until a new key, 0 keeps one key until restart.
+ */
+hls_key_rotation: bigint, 
+/**
+ * Key URL for the players, with `{channel}` and `{key}` placeholders. Empty uses the API.
+ */
+hls_key_url: string, 
+/**
+ * IV of the keys: `sequence` uses the segment number, `random` a random IV per key.
+ */
+hls_key_iv: string, 
+/**
+ * Push the stream output through the relay, so the playout keeps running
+ * while the destination reconnects.
+ */
+reconnect: boolean, 
+/**
+ * Failed connections in a row, until an output gives up. 0 tries forever.
+ */
+reconnect_attempts: bigint, 
+/**
+ * Longest wait between two connections in seconds, the wait doubles from one second.
+ */
+reconnect_delay: bigint, 
+/**
+ * Seconds an output can be unreachable, until an event and error message go out. 0 disables it.
+ */
+reconnect_alert: bigint, 
+/**
+ * Image which the outputs get, while the encoder sends no data. Empty disables it.
+ */
+reconnect_slate: string, 
+/**
+ * WHIP endpoint of the WebRTC server.
+ */
+whip_url: string, 
+/**
+ * Bearer token for the WHIP endpoint.
+ */
+whip_token: string, 
+/**
+ * Video bitrate in kbit/s, also the max rate.
+ */
+whip_bitrate: bigint, 
+/**
+ * H.264 profile: baseline, main or high. WebRTC has no B-frames in any of them.
+ */
+whip_profile: string, };
+
+export type OutputMode = "desktop" | "hls" | "null" | "stream" | "srt" | "ndi" | "decklink" | "icecast" | "dash" | "whip";
+
+export type Playlist = { day_start: string, length: string, infinit: boolean, 
+/**
+ * Fill of gaps by their length, one policy per line, like `0-60 loop`.
+ */
+gap_policy: Array<string>, };
 
 /**
  * Channel Config
  *
  * This we init ones, when ffplayout is starting and use them globally in the hole program.
  */
-export type PlayoutConfig = { general: General, mail: Mail, logging: Logging, processing: Processing, ingest: Ingest, playlist: Playlist, storage: Storage, text: Text, task: Task, output: Output, };
+export type PlayoutConfig = { general: General, mail: Mail, logging: Logging, processing: Processing, ingest: Ingest, playlist: Playlist, storage: Storage, text: Text, task: Task, script: Script, recorder: Recorder, webhook: Webhook, hooks: EventHooks, mqtt: Mqtt, transition: Transition, monitor: Monitor, failover: Failover, sync: PlayoutSync, graphics: Graphics, ticker: Ticker, clock: Clock, visual_radio: VisualRadio, asrun: AsRun, preview: PreviewStream, output: Output, };
+
+/**
+ * Main/backup synchronization of two engines.
+ */
+export type PlayoutSync = { 
+/**
+ * Sync URL of the main engine, when set this engine runs as backup.
+ */
+main_url: string, 
+/**
+ * Shared secret, the main engine serves its playhead only when it is set.
+ */
+secret: string, };
+
+/**
+ * Low bitrate HLS stream of the program output, for monitoring in the browser.
+ */
+export type PreviewStream = { enable: boolean, 
+/**
+ * Video height, the width follows the aspect.
+ */
+height: bigint, 
+/**
+ * Video bitrate in kbit/s.
+ */
+bitrate: bigint, };
 
 export type ProcessMode = "folder" | "playlist";
 
-export type Processing = { mode: ProcessMode, audio_only: boolean, copy_audio: boolean, copy_video: boolean, width: bigint, height: bigint, aspect: number, fps: number, add_logo: boolean, logo: string, logo_scale: string, logo_opacity: number, logo_position: string, audio_tracks: number, audio_track_index: number, audio_channels: number, volume: number, custom_filter: string, override_filter: boolean, vtt_enable: boolean, vtt_dummy: string | null, };
+export type Processing = { mode: ProcessMode, audio_only: boolean, copy_audio: boolean, copy_video: boolean, width: bigint, height: bigint, aspect: number, fps: number, add_logo: boolean, logo: string, logo_scale: string, logo_opacity: number, logo_position: string, 
+/**
+ * Logos for day-parts, one per line like `18:00:00-20:00:00 logos/prime.png`.
+ * Other times use the default logo.
+ */
+logo_schedule: Array<string>, 
+/**
+ * How often an animated logo plays, it holds the last frame afterwards. 0 loops endless.
+ */
+logo_loop: bigint, 
+/**
+ * Frame rate for image sequences like `logo_%03d.png`, 0 takes the rate from the file.
+ */
+logo_fps: number, audio_tracks: number, audio_track_index: number, audio_channels: number, volume: number, custom_filter: string, override_filter: boolean, vtt_enable: boolean, vtt_dummy: string | null, 
+/**
+ * Language codes for the output audio tracks, like: ["deu", "eng"]
+ */
+audio_languages: Array<string>, subtitle_mode: SubtitleMode, 
+/**
+ * Crossfade length in seconds between consecutive clips, 0 for hard cuts.
+ */
+crossfade: number, 
+/**
+ * Video transition from the xfade filter, like fade, wipeleft, dissolve.
+ */
+crossfade_transition: string, 
+/**
+ * Audio fade curve from the acrossfade filter, like tri, qsin, exp.
+ */
+crossfade_curve: string, 
+/**
+ * EBU R128 normalization, two-pass with the measured loudness from the cache.
+ */
+loudnorm: boolean, 
+/**
+ * Target integrated loudness in LUFS.
+ */
+loudnorm_i: number, 
+/**
+ * Target loudness range in LU.
+ */
+loudnorm_lra: number, 
+/**
+ * Maximum true peak in dBTP.
+ */
+loudnorm_tp: number, hw_accel: HwAccel, 
+/**
+ * GPU index for NVENC, render node like `/dev/dri/renderD128` for QSV and VAAPI.
+ */
+hw_device: string, deinterlace: Deinterlace, aspect_mode: AspectMode, fps_mode: FpsMode, 
+/**
+ * Tone mapping of HDR clips (PQ and HLG) to the SDR output.
+ */
+tonemap: Tonemap, 
+/**
+ * Curve of the tone mapping, like hable, mobius or reinhard.
+ */
+tonemap_algorithm: string, 
+/**
+ * Peak luminance of the SDR target in nits.
+ */
+tonemap_peak: number, 
+/**
+ * Color space of the output, clips in another matrix or range get converted.
+ */
+color_space: ColorSpace, 
+/**
+ * Program for decoding and encoding, GStreamer is experimental.
+ */
+backend: Backend, };
 
-export type Storage = { filler: string, extensions: Array<string>, shuffle: boolean, shared_storage: boolean, };
+export type RecordFormat = "ts" | "mkv";
+
+export type Recorder = { 
+/**
+ * Start recording together with the playout.
+ */
+enable: boolean, 
+/**
+ * Target directory for the recordings.
+ */
+path: string, format: RecordFormat, 
+/**
+ * Segment length in seconds, aligned to the wall clock.
+ */
+segment: bigint, 
+/**
+ * Days to keep recordings, 0 keeps them forever.
+ */
+retention: bigint, 
+/**
+ * Encoding parameters for the recordings.
+ */
+param: string, };
+
+/**
+ * Script for the scheduler hooks, it can change the next clip.
+ */
+export type Script = { enable: boolean, path: string, 
+/**
+ * Longest run time of one hook in seconds, the clip stays unchanged after it.
+ */
+timeout: number, };
+
+export type SrtMode = "caller" | "listener";
+
+export type Storage = { filler: string, extensions: Array<string>, max_depth: number, exclude: Array<string>, shuffle: boolean, shuffle_history: number, 
+/**
+ * Sources, which played in the last minutes, come after the others in the next shuffle.
+ */
+shuffle_history_minutes: number, cache_path: string, cache_size: number, weights: { [key in string]?: number }, 
+/**
+ * Minutes between two scans of the media library, 0 disables the scanner.
+ */
+library_interval: number, 
+/**
+ * Library query for folder mode, like `tag:music AND duration<300`, instead of all files.
+ */
+query: string, 
+/**
+ * Generate thumbnails and preview sprites for the files of the media library.
+ */
+previews: boolean, shared_storage: boolean, };
+
+export type SubtitleMode = "none" | "burn" | "passthrough";
 
 export type Task = { enable: boolean, path: string, };
 
-export type Text = { add_text: boolean, font: string, text_from_filename: boolean, style: string, regex: string, };
+export type Text = { add_text: boolean, font: string, text_from_filename: boolean, style: string, regex: string, 
+/**
+ * Show the current and the next title, updated with each clip over the zmq text channel.
+ */
+now_next: boolean, 
+/**
+ * Template with `{{title}}`, `{{next_title}}` and `{{time}}`.
+ */
+now_next_format: string, 
+/**
+ * drawtext options, like `x=10:y=h-40:fontsize=24`.
+ */
+now_next_style: string, };
+
+/**
+ * News ticker from RSS or JSON feeds, scrolling at the bottom of the program.
+ */
+export type Ticker = { enable: boolean, 
+/**
+ * Feed URLs, one per line.
+ */
+feeds: Array<string>, 
+/**
+ * Seconds between two pulls of the feeds.
+ */
+interval: number, 
+/**
+ * Text between the headlines.
+ */
+separator: string, 
+/**
+ * Day-parts with ticker, one per line like `06:00:00-09:00:00`. Empty shows it all day.
+ */
+schedule: Array<string>, 
+/**
+ * Scroll speed in pixels per second.
+ */
+speed: number, 
+/**
+ * drawtext options, like `y=h-50:fontsize=28:fontcolor=white`.
+ */
+style: string, };
+
+/**
+ * Filter for tone mapping HDR clips to SDR.
+ */
+export type Tonemap = "off" | "zscale" | "libplacebo";
+
+export type Transition = { 
+/**
+ * Stinger clip with alpha channel, which plays over the cut between two clips.
+ * An empty path disables it.
+ */
+stinger_path: string, 
+/**
+ * Seconds from the stinger start to the cut, usually the point where it covers the picture.
+ */
+stinger_offset: number, };
+
+/**
+ * Video for audio only clips, from artwork, audio visualizer and now playing text.
+ */
+export type VisualRadio = { enable: boolean, 
+/**
+ * Image for clips without own artwork, empty gives a black background.
+ */
+artwork: string, 
+/**
+ * `spectrum`, `waveform` or `none`.
+ */
+visualizer: string, 
+/**
+ * Show artist and title of the clip.
+ */
+now_playing: boolean, 
+/**
+ * drawtext options for the now playing text, like `x=40:y=40:fontsize=36`.
+ */
+style: string, };
+
+export type Webhook = { 
+/**
+ * Endpoints which get the events as JSON POST.
+ */
+urls: Array<string>, 
+/**
+ * Key for the HMAC-SHA256 signature, no signature when empty.
+ */
+secret: string, 
+/**
+ * Events to send, all when empty.
+ */
+events: Array<string>, };
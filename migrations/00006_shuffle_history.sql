@@ -0,0 +1 @@
+ALTER TABLE configurations ADD storage_shuffle_history INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD storage_max_depth INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD storage_exclude TEXT NOT NULL DEFAULT "";
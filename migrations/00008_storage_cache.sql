@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD storage_cache_path TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD storage_cache_size INTEGER NOT NULL DEFAULT 3;
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_audio_languages TEXT NOT NULL DEFAULT "";
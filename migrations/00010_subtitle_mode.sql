@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_subtitle_mode TEXT NOT NULL DEFAULT "none";
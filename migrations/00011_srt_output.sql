@@ -0,0 +1,4 @@
+ALTER TABLE configurations ADD output_srt_mode TEXT NOT NULL DEFAULT "caller";
+ALTER TABLE configurations ADD output_srt_address TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD output_srt_passphrase TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD output_srt_latency INTEGER NOT NULL DEFAULT 120;
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD output_ndi_name TEXT NOT NULL DEFAULT "";
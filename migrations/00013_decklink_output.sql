@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD output_decklink_device TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD output_decklink_mode TEXT NOT NULL DEFAULT "1080i50";
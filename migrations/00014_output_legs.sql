@@ -0,0 +1 @@
+ALTER TABLE configurations ADD output_legs TEXT NOT NULL DEFAULT "";
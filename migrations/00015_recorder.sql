@@ -0,0 +1,6 @@
+ALTER TABLE configurations ADD recorder_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD recorder_path TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD recorder_format TEXT NOT NULL DEFAULT "ts";
+ALTER TABLE configurations ADD recorder_segment INTEGER NOT NULL DEFAULT 3600;
+ALTER TABLE configurations ADD recorder_retention INTEGER NOT NULL DEFAULT 7;
+ALTER TABLE configurations ADD recorder_param TEXT NOT NULL DEFAULT "-c:v libx264 -preset veryfast -crf 26 -c:a aac -b:a 128k";
@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD webhook_urls TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD webhook_secret TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD webhook_events TEXT NOT NULL DEFAULT "";
@@ -0,0 +1,5 @@
+ALTER TABLE configurations ADD mqtt_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD mqtt_broker TEXT NOT NULL DEFAULT "127.0.0.1:1883";
+ALTER TABLE configurations ADD mqtt_topic TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD mqtt_username TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD mqtt_password TEXT NOT NULL DEFAULT "";
@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD processing_crossfade REAL NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD processing_crossfade_transition TEXT NOT NULL DEFAULT "fade";
+ALTER TABLE configurations ADD processing_crossfade_curve TEXT NOT NULL DEFAULT "tri";
@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD transition_stinger_path TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD transition_stinger_offset REAL NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_logo_schedule TEXT NOT NULL DEFAULT "";
@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD processing_logo_loop INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD processing_logo_fps REAL NOT NULL DEFAULT 0;
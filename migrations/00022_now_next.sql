@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD text_now_next INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD text_now_next_format TEXT NOT NULL DEFAULT "Now: {{title}} / Next: {{next_title}}";
+ALTER TABLE configurations ADD text_now_next_style TEXT NOT NULL DEFAULT "x=w*0.05:y=h*0.9:fontsize=24:fontcolor=#ffffff:box=1:boxcolor=#000000@0.6:boxborderw=6";
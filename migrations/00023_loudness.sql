@@ -0,0 +1,15 @@
+ALTER TABLE configurations ADD processing_loudnorm INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD processing_loudnorm_i REAL NOT NULL DEFAULT -23;
+ALTER TABLE configurations ADD processing_loudnorm_lra REAL NOT NULL DEFAULT 7;
+ALTER TABLE configurations ADD processing_loudnorm_tp REAL NOT NULL DEFAULT -1;
+
+CREATE TABLE
+    loudness (
+        source TEXT PRIMARY KEY,
+        size INTEGER NOT NULL DEFAULT 0,
+        modified INTEGER NOT NULL DEFAULT 0,
+        input_i REAL NOT NULL,
+        input_lra REAL NOT NULL,
+        input_tp REAL NOT NULL,
+        input_thresh REAL NOT NULL
+    );
@@ -0,0 +1,4 @@
+ALTER TABLE configurations ADD monitor_silence_duration REAL NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD monitor_silence_noise REAL NOT NULL DEFAULT -60;
+ALTER TABLE configurations ADD monitor_black_duration REAL NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD monitor_black_level REAL NOT NULL DEFAULT 0.1;
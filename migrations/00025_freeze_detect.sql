@@ -0,0 +1 @@
+ALTER TABLE configurations ADD monitor_freeze_duration REAL NOT NULL DEFAULT 0;
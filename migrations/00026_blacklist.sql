@@ -0,0 +1,8 @@
+CREATE TABLE
+    blacklist (
+        source TEXT PRIMARY KEY,
+        size INTEGER NOT NULL DEFAULT 0,
+        modified INTEGER NOT NULL DEFAULT 0,
+        error TEXT NOT NULL DEFAULT '',
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
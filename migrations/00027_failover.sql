@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD failover_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD failover_source TEXT NOT NULL DEFAULT '';
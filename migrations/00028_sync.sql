@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD sync_main_url TEXT NOT NULL DEFAULT '';
+ALTER TABLE configurations ADD sync_secret TEXT NOT NULL DEFAULT '';
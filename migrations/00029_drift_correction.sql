@@ -0,0 +1 @@
+ALTER TABLE configurations ADD general_drift_correction REAL NOT NULL DEFAULT 0.0;
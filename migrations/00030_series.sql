@@ -0,0 +1,9 @@
+CREATE TABLE
+    series (
+        channel_id INTEGER NOT NULL,
+        path TEXT NOT NULL,
+        episode TEXT NOT NULL DEFAULT '',
+        updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        PRIMARY KEY (channel_id, path),
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
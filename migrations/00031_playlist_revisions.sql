@@ -0,0 +1,14 @@
+CREATE TABLE
+    playlist_revisions (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        channel_id INTEGER NOT NULL,
+        date TEXT NOT NULL,
+        user_id INTEGER,
+        username TEXT NOT NULL DEFAULT '',
+        diff TEXT NOT NULL DEFAULT '',
+        playlist TEXT NOT NULL,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE INDEX playlist_revisions_date ON playlist_revisions (channel_id, date);
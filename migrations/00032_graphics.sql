@@ -0,0 +1,4 @@
+ALTER TABLE configurations ADD graphics_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD graphics_source TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD graphics_command TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD graphics_interval REAL NOT NULL DEFAULT 5.0;
@@ -0,0 +1,7 @@
+ALTER TABLE configurations ADD ticker_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD ticker_feeds TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD ticker_interval REAL NOT NULL DEFAULT 300.0;
+ALTER TABLE configurations ADD ticker_separator TEXT NOT NULL DEFAULT " +++ ";
+ALTER TABLE configurations ADD ticker_schedule TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD ticker_speed REAL NOT NULL DEFAULT 100.0;
+ALTER TABLE configurations ADD ticker_style TEXT NOT NULL DEFAULT "y=h-50:fontsize=28:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=8";
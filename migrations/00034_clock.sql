@@ -0,0 +1,5 @@
+ALTER TABLE configurations ADD clock_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD clock_format TEXT NOT NULL DEFAULT "%H:%M:%S";
+ALTER TABLE configurations ADD clock_position TEXT NOT NULL DEFAULT "x=w-tw-40:y=40";
+ALTER TABLE configurations ADD clock_font TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD clock_style TEXT NOT NULL DEFAULT "fontsize=32:fontcolor=white:shadowx=2:shadowy=2";
@@ -0,0 +1,8 @@
+ALTER TABLE configurations ADD output_icecast_address TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD output_icecast_mount TEXT NOT NULL DEFAULT "/radio";
+ALTER TABLE configurations ADD output_icecast_username TEXT NOT NULL DEFAULT "source";
+ALTER TABLE configurations ADD output_icecast_password TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD output_icecast_format TEXT NOT NULL DEFAULT "mp3";
+ALTER TABLE configurations ADD output_icecast_bitrate INTEGER NOT NULL DEFAULT 128;
+ALTER TABLE configurations ADD output_icecast_video TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD output_icecast_cover TEXT NOT NULL DEFAULT "";
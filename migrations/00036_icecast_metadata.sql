@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD output_icecast_metadata INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD output_icecast_metadata_url TEXT NOT NULL DEFAULT "";
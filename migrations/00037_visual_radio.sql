@@ -0,0 +1,5 @@
+ALTER TABLE configurations ADD visual_radio_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD visual_radio_artwork TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD visual_radio_visualizer TEXT NOT NULL DEFAULT "spectrum";
+ALTER TABLE configurations ADD visual_radio_now_playing INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE configurations ADD visual_radio_style TEXT NOT NULL DEFAULT "x=40:y=40:fontsize=36:fontcolor=white:box=1:boxcolor=black@0.5:boxborderw=10";
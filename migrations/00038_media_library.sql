@@ -0,0 +1,26 @@
+ALTER TABLE configurations ADD storage_library_interval REAL NOT NULL DEFAULT 60;
+
+CREATE TABLE
+    media_library (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        channel_id INTEGER NOT NULL,
+        path TEXT NOT NULL,
+        size INTEGER NOT NULL DEFAULT 0,
+        modified INTEGER NOT NULL DEFAULT 0,
+        checksum TEXT NOT NULL DEFAULT '',
+        duration REAL NOT NULL DEFAULT 0,
+        video_codec TEXT NOT NULL DEFAULT '',
+        audio_codec TEXT NOT NULL DEFAULT '',
+        width INTEGER NOT NULL DEFAULT 0,
+        height INTEGER NOT NULL DEFAULT 0,
+        fps REAL NOT NULL DEFAULT 0,
+        loudness REAL,
+        title TEXT NOT NULL DEFAULT '',
+        artist TEXT NOT NULL DEFAULT '',
+        album TEXT NOT NULL DEFAULT '',
+        genre TEXT NOT NULL DEFAULT '',
+        tags TEXT NOT NULL DEFAULT '{}',
+        scanned_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        UNIQUE (channel_id, path),
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD storage_query TEXT NOT NULL DEFAULT "";
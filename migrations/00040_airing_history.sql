@@ -0,0 +1,15 @@
+CREATE TABLE
+    airings (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        channel_id INTEGER NOT NULL,
+        source TEXT NOT NULL,
+        title TEXT NOT NULL DEFAULT '',
+        artist TEXT NOT NULL DEFAULT '',
+        category TEXT NOT NULL DEFAULT '',
+        started_at TEXT NOT NULL,
+        planned REAL NOT NULL DEFAULT 0,
+        duration REAL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
+
+CREATE INDEX airings_started ON airings (channel_id, started_at);
@@ -0,0 +1,4 @@
+ALTER TABLE configurations ADD asrun_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD asrun_path TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD asrun_format TEXT NOT NULL DEFAULT "xml";
+ALTER TABLE configurations ADD asrun_columns TEXT NOT NULL DEFAULT "scheduled;actual;deviation;planned;duration;title;source;status";
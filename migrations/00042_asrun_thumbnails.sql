@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD asrun_thumbnails INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD asrun_thumbnail_interval REAL NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD storage_previews INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD preview_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD preview_height INTEGER NOT NULL DEFAULT 360;
+ALTER TABLE configurations ADD preview_bitrate INTEGER NOT NULL DEFAULT 600;
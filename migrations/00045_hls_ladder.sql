@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD output_hls_ladder TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD output_hls_fmp4 INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD output_hls_time REAL NOT NULL DEFAULT 6.0;
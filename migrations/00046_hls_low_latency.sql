@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD output_hls_low_latency INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD output_hls_part_time REAL NOT NULL DEFAULT 0.5;
+ALTER TABLE configurations ADD output_hls_program_date_time INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+ALTER TABLE configurations ADD output_hls_encryption INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD output_hls_key_rotation INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD output_hls_key_url TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD output_hls_key_iv TEXT NOT NULL DEFAULT "sequence";
@@ -0,0 +1,5 @@
+ALTER TABLE configurations ADD output_reconnect INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD output_reconnect_attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD output_reconnect_delay INTEGER NOT NULL DEFAULT 30;
+ALTER TABLE configurations ADD output_reconnect_alert INTEGER NOT NULL DEFAULT 60;
+ALTER TABLE configurations ADD output_reconnect_slate TEXT NOT NULL DEFAULT "";
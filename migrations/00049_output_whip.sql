@@ -0,0 +1,4 @@
+ALTER TABLE configurations ADD output_whip_url TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD output_whip_token TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD output_whip_bitrate INTEGER NOT NULL DEFAULT 2500;
+ALTER TABLE configurations ADD output_whip_profile TEXT NOT NULL DEFAULT "baseline";
@@ -0,0 +1,2 @@
+ALTER TABLE configurations ADD processing_hw_accel TEXT NOT NULL DEFAULT "none";
+ALTER TABLE configurations ADD processing_hw_device TEXT NOT NULL DEFAULT "";
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_deinterlace TEXT NOT NULL DEFAULT "auto";
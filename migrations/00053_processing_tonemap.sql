@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD processing_tonemap TEXT NOT NULL DEFAULT "zscale";
+ALTER TABLE configurations ADD processing_tonemap_algorithm TEXT NOT NULL DEFAULT "hable";
+ALTER TABLE configurations ADD processing_tonemap_peak REAL NOT NULL DEFAULT 100.0;
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_fps_mode TEXT NOT NULL DEFAULT "auto";
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_color_space TEXT NOT NULL DEFAULT "auto";
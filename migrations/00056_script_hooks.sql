@@ -0,0 +1,3 @@
+ALTER TABLE configurations ADD script_enable INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE configurations ADD script_path TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD script_timeout REAL NOT NULL DEFAULT 2.0;
@@ -0,0 +1,4 @@
+ALTER TABLE configurations ADD hooks_clip_start TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD hooks_clip_end TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD hooks_error TEXT NOT NULL DEFAULT "";
+ALTER TABLE configurations ADD hooks_timeout REAL NOT NULL DEFAULT 30.0;
@@ -0,0 +1 @@
+ALTER TABLE user_channels ADD role_id INTEGER NULL DEFAULT NULL REFERENCES roles (id) ON DELETE SET NULL;
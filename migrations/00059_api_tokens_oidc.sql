@@ -0,0 +1,20 @@
+CREATE TABLE
+    api_tokens (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        token_hash TEXT NOT NULL UNIQUE,
+        role_id INTEGER NOT NULL DEFAULT 4,
+        channel_ids TEXT NOT NULL DEFAULT '',
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        expires_at TEXT,
+        last_used TEXT,
+        revoked INTEGER NOT NULL DEFAULT 0,
+        FOREIGN KEY (user_id) REFERENCES user (id) ON UPDATE CASCADE ON DELETE CASCADE,
+        FOREIGN KEY (role_id) REFERENCES roles (id) ON UPDATE CASCADE ON DELETE SET DEFAULT
+    );
+
+ALTER TABLE global ADD oidc_issuer TEXT NOT NULL DEFAULT "";
+ALTER TABLE global ADD oidc_client_id TEXT NOT NULL DEFAULT "";
+ALTER TABLE global ADD oidc_client_secret TEXT NOT NULL DEFAULT "";
+ALTER TABLE global ADD oidc_redirect_url TEXT NOT NULL DEFAULT "";
@@ -0,0 +1,15 @@
+CREATE TABLE
+    audit_log (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        channel_id INTEGER,
+        user_id INTEGER,
+        username TEXT NOT NULL DEFAULT '',
+        method TEXT NOT NULL,
+        path TEXT NOT NULL,
+        status INTEGER NOT NULL DEFAULT 0,
+        old_value TEXT,
+        new_value TEXT,
+        created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+
+CREATE INDEX audit_log_channel ON audit_log (channel_id, created_at);
@@ -0,0 +1,8 @@
+CREATE TABLE
+    channel_groups (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        UNIQUE (name)
+    );
+
+ALTER TABLE channels ADD group_id INTEGER NULL DEFAULT NULL REFERENCES channel_groups (id) ON DELETE SET NULL;
@@ -0,0 +1,13 @@
+CREATE TABLE
+    workers (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        name TEXT NOT NULL,
+        url TEXT NOT NULL,
+        token TEXT NOT NULL,
+        online INTEGER NOT NULL DEFAULT 0,
+        failures INTEGER NOT NULL DEFAULT 0,
+        last_seen TEXT NULL DEFAULT NULL,
+        UNIQUE (name)
+    );
+
+ALTER TABLE channels ADD worker_id INTEGER NULL DEFAULT NULL REFERENCES workers (id) ON DELETE SET NULL;
@@ -0,0 +1,10 @@
+CREATE TABLE
+    playheads (
+        channel_id INTEGER PRIMARY KEY,
+        date TEXT NOT NULL DEFAULT '',
+        node_index INTEGER NOT NULL DEFAULT 0,
+        source TEXT NOT NULL,
+        position REAL NOT NULL DEFAULT 0,
+        saved_at TEXT NOT NULL,
+        FOREIGN KEY (channel_id) REFERENCES channels (id) ON UPDATE CASCADE ON DELETE CASCADE
+    );
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD processing_backend TEXT NOT NULL DEFAULT "ffmpeg";
@@ -0,0 +1 @@
+ALTER TABLE configurations ADD playlist_gap_policy TEXT NOT NULL DEFAULT "";
@@ -0,0 +1,4 @@
+ALTER TABLE user ADD oidc_issuer TEXT;
+ALTER TABLE user ADD oidc_subject TEXT;
+
+CREATE UNIQUE INDEX user_oidc_identity ON user (oidc_issuer, oidc_subject);
@@ -0,0 +1,6 @@
+CREATE TABLE
+    worker_channels (
+        worker_id INTEGER NOT NULL REFERENCES workers (id) ON DELETE CASCADE,
+        channel_id INTEGER NOT NULL,
+        PRIMARY KEY (worker_id, channel_id)
+    );
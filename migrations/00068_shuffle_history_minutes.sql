@@ -0,0 +1 @@
+ALTER TABLE configurations ADD storage_shuffle_history_minutes INTEGER NOT NULL DEFAULT 0;
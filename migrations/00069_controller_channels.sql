@@ -0,0 +1,5 @@
+CREATE TABLE
+    controller_channels (
+        channel_id INTEGER PRIMARY KEY REFERENCES channels (id) ON DELETE CASCADE,
+        user_id INTEGER NOT NULL REFERENCES user (id) ON DELETE CASCADE
+    );
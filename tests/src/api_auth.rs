@@ -0,0 +1,256 @@
+use serde_json::json;
+use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+
+use ffplayout::api::{
+    audit::{changes, path_channel},
+    auth,
+    oidc::{self, IdClaims},
+};
+use ffplayout::db::{
+    handles,
+    models::{ApiToken, AuditEntry, AuditQuery, Role, User, UserMeta},
+};
+use ffplayout::player::controller::ChannelManager;
+use ffplayout::utils::config::PlayoutConfig;
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager, Pool<Sqlite>) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let user = User {
+        id: 0,
+        mail: Some("admin@mail.com".to_string()),
+        username: "admin".to_string(),
+        password: "admin".to_string(),
+        role_id: Some(1),
+        channel_ids: Some(vec![1]),
+        token: None,
+        channel_roles: Default::default(),
+    };
+
+    handles::insert_user(&pool, user.clone()).await.unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool.clone(), channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager, pool)
+}
+
+#[actix_web::test]
+async fn test_user_channel_roles() {
+    let (_, _, pool) = prepare_config().await;
+
+    let user = User {
+        id: 0,
+        mail: Some("operator@mail.com".to_string()),
+        username: "operator".to_string(),
+        password: "operator".to_string(),
+        role_id: Some(3),
+        channel_ids: Some(vec![1]),
+        token: None,
+        channel_roles: [(1, Role::Guest), (2, Role::GlobalAdmin)].into(),
+    };
+
+    handles::insert_user(&pool, user).await.unwrap();
+
+    let user = handles::select_login(&pool, "operator").await.unwrap();
+
+    assert_eq!(user.channel_roles, [(1, Role::Guest)].into());
+
+    let meta = UserMeta::new(user.id, vec![1, 3], Role::User, user.channel_roles);
+
+    assert_eq!(meta.channel_role(&1), &Role::Guest);
+    assert_eq!(meta.channel_role(&3), &Role::User);
+    assert!(!meta.has_role(&1, &[Role::ChannelAdmin, Role::User]));
+    assert!(meta.has_role(&1, &[Role::Guest]));
+    assert!(meta.has_role(&3, &[Role::ChannelAdmin, Role::User]));
+    assert!(!meta.has_role(&2, &[Role::Guest]));
+
+    let admin = UserMeta::new(1, vec![], Role::GlobalAdmin, Default::default());
+
+    assert!(admin.has_role(&2, &[]));
+}
+
+#[actix_web::test]
+async fn test_api_tokens() {
+    let (_, _, pool) = prepare_config().await;
+    let user = handles::select_login(&pool, "admin").await.unwrap();
+
+    let token = auth::generate_api_token();
+    let mut api_token = ApiToken {
+        user_id: user.id,
+        name: "scheduler".to_string(),
+        token_hash: auth::hash_api_token(&token),
+        role_id: 1,
+        channel_ids: vec![1],
+        ..Default::default()
+    };
+
+    assert!(token.starts_with(auth::API_TOKEN_PREFIX));
+
+    let id = handles::insert_api_token(&pool, &api_token).await.unwrap();
+    let claims = auth::api_token_claims(&pool, &token).await.unwrap();
+
+    // a token with channels has no global admin rights
+    assert_eq!(claims.role, Role::ChannelAdmin);
+    assert_eq!(claims.channels, vec![1]);
+
+    let tokens = handles::select_api_tokens(&pool, Some(user.id))
+        .await
+        .unwrap();
+
+    assert_eq!(tokens.len(), 1);
+    assert!(tokens[0].last_used.is_some());
+
+    handles::revoke_api_token(&pool, id, Some(user.id + 1))
+        .await
+        .unwrap();
+
+    assert!(auth::api_token_claims(&pool, &token).await.is_ok());
+
+    handles::revoke_api_token(&pool, id, Some(user.id))
+        .await
+        .unwrap();
+
+    assert!(auth::api_token_claims(&pool, &token).await.is_err());
+
+    let token = auth::generate_api_token();
+    api_token.token_hash = auth::hash_api_token(&token);
+    api_token.expires_in = Some(0);
+
+    handles::insert_api_token(&pool, &api_token).await.unwrap();
+
+    assert!(auth::api_token_claims(&pool, &token).await.is_err());
+
+    assert_eq!(Role::GlobalAdmin.limit(&Role::User), Role::User);
+    assert_eq!(Role::Guest.limit(&Role::User), Role::Guest);
+}
+
+#[actix_web::test]
+async fn test_oidc_user() {
+    let (_, _, pool) = prepare_config().await;
+    let admin = handles::select_login(&pool, "admin").await.unwrap();
+    let issuer = "https://auth.example.org/realms/ffplayout";
+    let claims = |sub: &str| IdClaims {
+        sub: sub.to_string(),
+        preferred_username: Some("admin".to_string()),
+        ..Default::default()
+    };
+
+    // the provider name never logs in as the local user
+    let user = oidc::provider_user(&pool, issuer, claims("a1"))
+        .await
+        .unwrap();
+
+    assert_ne!(user.id, admin.id);
+    assert_eq!(user.username, "admin-2");
+    assert_eq!(user.role_id, Some(4));
+
+    let again = oidc::provider_user(&pool, issuer, claims("a1"))
+        .await
+        .unwrap();
+
+    assert_eq!(again.id, user.id);
+
+    let other = oidc::provider_user(&pool, "https://other.example.org", claims("a1"))
+        .await
+        .unwrap();
+
+    assert_ne!(other.id, user.id);
+    assert_eq!(other.username, "admin-3");
+    assert!(handles::select_oidc_user(&pool, issuer, "b2")
+        .await
+        .is_err());
+}
+
+#[actix_web::test]
+async fn test_audit_log() {
+    let (_, _, pool) = prepare_config().await;
+
+    let old = json!({"processing": {"volume": 1.0, "width": 1024}, "mail": {"smtp_password": "a"}});
+    let new = json!({"processing": {"volume": 0.8, "width": 1024}, "mail": {"smtp_password": "b"}});
+    let (old_value, new_value) = changes(&old, &new);
+
+    assert_eq!(
+        old_value,
+        json!({"mail.smtp_password": "***", "processing.volume": 1.0})
+    );
+    assert_eq!(
+        new_value,
+        json!({"mail.smtp_password": "***", "processing.volume": 0.8})
+    );
+
+    assert_eq!(path_channel("/api/playout/config/2"), Some(2));
+    assert_eq!(path_channel("/api/control/1/playout/"), Some(1));
+    assert_eq!(path_channel("/api/user/3"), None);
+
+    for (channel_id, username) in [(Some(1), "admin"), (Some(2), "operator"), (None, "admin")] {
+        let entry = AuditEntry {
+            channel_id,
+            user_id: Some(1),
+            username: username.to_string(),
+            method: "PUT".to_string(),
+            path: "/api/playout/config/1".to_string(),
+            status: 200,
+            old_value: Some(old_value.to_string()),
+            new_value: Some(new_value.to_string()),
+            ..Default::default()
+        };
+
+        handles::insert_audit_entry(&pool, &entry).await.unwrap();
+    }
+
+    let all = handles::select_audit_entries(&pool, &AuditQuery::default(), None)
+        .await
+        .unwrap();
+
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[0].channel_id, None);
+
+    let query = AuditQuery {
+        username: "admin".to_string(),
+        method: "put".to_string(),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        handles::select_audit_entries(&pool, &query, None)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+
+    let channel_entries = handles::select_audit_entries(&pool, &AuditQuery::default(), Some(&[2]))
+        .await
+        .unwrap();
+
+    assert_eq!(channel_entries.len(), 1);
+    assert_eq!(channel_entries[0].username, "operator");
+    assert!(
+        handles::select_audit_entries(&pool, &AuditQuery::default(), Some(&[]))
+            .await
+            .unwrap()
+            .is_empty()
+    );
+
+    let serialized = serde_json::to_value(&channel_entries[0]).unwrap();
+
+    assert_eq!(serialized["new_value"]["processing.volume"], json!(0.8));
+}
@@ -0,0 +1,100 @@
+use serde_json::json;
+use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+
+use ffplayout::db::{
+    handles,
+    models::{ChannelGroup, User},
+};
+use ffplayout::player::controller::ChannelManager;
+use ffplayout::utils::config::PlayoutConfig;
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager, Pool<Sqlite>) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let user = User {
+        id: 0,
+        mail: Some("admin@mail.com".to_string()),
+        username: "admin".to_string(),
+        password: "admin".to_string(),
+        role_id: Some(1),
+        channel_ids: Some(vec![1]),
+        token: None,
+        channel_roles: Default::default(),
+    };
+
+    handles::insert_user(&pool, user.clone()).await.unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool.clone(), channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager, pool)
+}
+
+#[actix_web::test]
+async fn test_channel_groups() {
+    let (config, _, pool) = prepare_config().await;
+
+    let group = ChannelGroup {
+        name: "News".to_string(),
+        channel_ids: vec![1],
+        ..Default::default()
+    };
+
+    let id = handles::insert_channel_group(&pool, &group).await.unwrap();
+    let stored = handles::select_channel_group(&pool, id).await.unwrap();
+
+    assert_eq!(stored.name, "News");
+    assert_eq!(stored.channel_ids, vec![1]);
+    assert_eq!(
+        handles::select_channel(&pool, &1).await.unwrap().group_id,
+        Some(id)
+    );
+
+    let group = ChannelGroup {
+        name: "Sports".to_string(),
+        channel_ids: vec![],
+        ..Default::default()
+    };
+
+    handles::update_channel_group(&pool, id, &group)
+        .await
+        .unwrap();
+
+    let groups = handles::select_channel_groups(&pool).await.unwrap();
+
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].name, "Sports");
+    assert!(groups[0].channel_ids.is_empty());
+
+    handles::delete_channel_group(&pool, id).await.unwrap();
+
+    assert!(handles::select_channel_groups(&pool)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let patched = config
+        .patch(&json!({"processing": {"volume": 0.5}}))
+        .unwrap();
+
+    assert_eq!(patched.processing.volume, 0.5);
+    assert_eq!(patched.processing.width, config.processing.width);
+    assert_eq!(patched.playlist.day_start, config.playlist.day_start);
+}
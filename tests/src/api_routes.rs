@@ -1,12 +1,25 @@
-use actix_web::{get, web, App, Error, HttpResponse, Responder};
+use std::{collections::HashSet, sync::Arc};
 
-use serde_json::json;
+use actix_http::ws::Frame;
+use actix_web::{dev::Service, get, web, App, Error, HttpMessage, HttpResponse, Responder};
+use actix_web_grants::authorities::AttachAuthorities;
+use futures_util::StreamExt;
+
+use serde_json::{json, Value};
 use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use tokio::sync::Mutex;
 
-use ffplayout::api::routes::login;
-use ffplayout::db::{handles, init_globales, models::User};
-use ffplayout::player::controller::ChannelManager;
-use ffplayout::utils::config::PlayoutConfig;
+use ffplayout::api::routes::{get_public, healthz, login, readyz, update_playout_config};
+use ffplayout::db::{
+    handles, init_globales,
+    models::{Role, User, UserMeta},
+};
+use ffplayout::player::{
+    controller::{ChannelController, ChannelManager},
+    utils::{JsonPlaylist, Media},
+};
+use ffplayout::sse::{routes::ws_channel, SseAuthState, UuidData};
+use ffplayout::utils::{config::PlayoutConfig, mail::MailQueue, playlist::save_revision};
 // use ffplayout::validator;
 
 async fn prepare_config() -> (PlayoutConfig, ChannelManager, Pool<Sqlite>) {
@@ -34,13 +47,16 @@ async fn prepare_config() -> (PlayoutConfig, ChannelManager, Pool<Sqlite>) {
         role_id: Some(1),
         channel_ids: Some(vec![1]),
         token: None,
+        channel_roles: Default::default(),
     };
 
     handles::insert_user(&pool, user.clone()).await.unwrap();
 
     let config = PlayoutConfig::new(&pool, 1).await.unwrap();
     let channel = handles::select_channel(&pool, &1).await.unwrap();
-    let manager = ChannelManager::new(pool.clone(), channel, config.clone()).await;
+    let manager = ChannelManager::new(pool.clone(), channel, config.clone())
+        .await
+        .unwrap();
 
     (config, manager, pool)
 }
@@ -91,3 +107,259 @@ async fn test_login() {
 
     assert_eq!(res.status().as_u16(), 400);
 }
+
+#[actix_web::test]
+async fn test_ws_channel() {
+    let (_, manager, _) = prepare_config().await;
+    let uuid = UuidData::new(UserMeta::new(1, vec![1], Role::Guest, Default::default()));
+    // viewer of another channel
+    let foreign = UuidData::new(UserMeta::new(2, vec![2], Role::Guest, Default::default()));
+    let mut controller = ChannelController::new();
+    controller.add(manager);
+
+    let auth = web::Data::new(SseAuthState {
+        uuids: Mutex::new(HashSet::from([uuid.clone(), foreign.clone()])),
+    });
+    let controllers = web::Data::new(Mutex::new(controller));
+
+    let mut srv = actix_test::start(move || {
+        App::new()
+            .app_data(auth.clone())
+            .app_data(controllers.clone())
+            .service(ws_channel)
+    });
+
+    let res = srv
+        .get("/ws/channel/1?uuid=f2f8c29b-712a-48c5-8919-b535d3a05a3a")
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status().as_u16(), 401);
+
+    let res = srv
+        .get(format!("/ws/channel/1?uuid={}", foreign.uuid))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(res.status().as_u16(), 403);
+
+    let mut socket = srv
+        .ws_at(&format!("/ws/channel/1?uuid={}", uuid.uuid))
+        .await
+        .unwrap();
+
+    let Some(Ok(Frame::Text(text))) = socket.next().await else {
+        panic!("no status message");
+    };
+
+    let status: Value = serde_json::from_slice(&text).unwrap();
+
+    assert_eq!(status["type"], "status");
+    assert_eq!(status["running"], false);
+    assert_eq!(status["remaining"], 0.0);
+}
+
+#[actix_web::test]
+async fn test_playlist_revisions() {
+    let (_, _, pool) = prepare_config().await;
+    let date = "2024-02-01".to_string();
+
+    let mut old = JsonPlaylist::new(date.clone(), 0.0);
+    old.program = vec![
+        Media::new(0, "assets/media_mix/with_audio.mp4", false).await,
+        Media::new(1, "assets/media_mix/dual_audio.mp4", false).await,
+    ];
+
+    let mut new = old.clone();
+    new.program.remove(0);
+    new.program
+        .push(Media::new(2, "assets/media_mix/av_sync.mp4", false).await);
+
+    let id = save_revision(&pool, 1, Some(1), Some(&old), &new)
+        .await
+        .unwrap();
+    let revisions = handles::select_playlist_revisions(&pool, 1, &date)
+        .await
+        .unwrap();
+
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[0].id, id);
+    assert_eq!(revisions[0].username, "admin");
+    assert!(revisions[0].playlist.is_none());
+    assert_eq!(revisions[1].user_id, None);
+    assert_eq!(revisions[1].diff.lines().count(), 2);
+
+    let diff: Vec<&str> = revisions[0].diff.lines().collect();
+
+    assert_eq!(diff.len(), 2);
+    assert!(diff[0].starts_with("- assets/media_mix/with_audio.mp4"));
+    assert!(diff[1].starts_with("+ assets/media_mix/av_sync.mp4"));
+
+    let revision = handles::select_playlist_revision(&pool, 1, revisions[1].id)
+        .await
+        .unwrap();
+    let playlist: JsonPlaylist = serde_json::from_str(&revision.playlist.unwrap()).unwrap();
+
+    assert_eq!(playlist.program.len(), 2);
+    assert_eq!(playlist.program[0].source, old.program[0].source);
+}
+
+#[actix_web::test]
+async fn test_health() {
+    let (_, manager, _) = prepare_config().await;
+    let mut controller = ChannelController::new();
+    controller.add(manager.clone());
+
+    let controllers = web::Data::new(Mutex::new(controller));
+
+    let srv = actix_test::start(move || {
+        App::new()
+            .app_data(controllers.clone())
+            .service(healthz)
+            .service(readyz)
+    });
+
+    // inactive channels are not checked
+    assert!(srv
+        .get("/healthz")
+        .send()
+        .await
+        .unwrap()
+        .status()
+        .is_success());
+    assert_eq!(
+        srv.get("/readyz").send().await.unwrap().status().as_u16(),
+        503
+    );
+
+    manager.channel.lock().await.active = true;
+
+    let mut res = srv.get("/healthz").send().await.unwrap();
+    let body: Value = res.json().await.unwrap();
+
+    assert_eq!(res.status().as_u16(), 503);
+    assert_eq!(body, json!([{"id": 1, "alive": false, "ready": false}]));
+    assert!(!manager.pipeline_running().await);
+}
+
+#[actix_web::test]
+async fn test_get_public_preview() {
+    let (_, manager, _) = prepare_config().await;
+    let mut controller = ChannelController::new();
+    controller.add(manager);
+
+    let controllers = web::Data::new(Mutex::new(controller));
+    let folder = "assets/hls/preview/library/test_checksum";
+
+    tokio::fs::create_dir_all(folder).await.unwrap();
+    tokio::fs::write(format!("{folder}/thumbnail.jpg"), b"jpg")
+        .await
+        .unwrap();
+
+    let srv =
+        actix_test::start(move || App::new().app_data(controllers.clone()).service(get_public));
+
+    let res = srv
+        .get("/1/preview/library/test_checksum/thumbnail.jpg")
+        .send()
+        .await
+        .unwrap();
+
+    assert!(res.status().is_success());
+
+    for path in [
+        "/1/preview/library%2F..%2F..%2F..%2FCargo.toml",
+        "/1/preview/library%2Ftest_checksum%2F..%2F..%2F..%2F..%2Fassets%2Fhls%2Fpreview%2Flibrary%2Ftest_checksum%2Fthumbnail.jpg",
+    ] {
+        let res = srv.get(path).send().await.unwrap();
+
+        assert_eq!(res.status().as_u16(), 400, "{path}");
+    }
+
+    tokio::fs::remove_dir_all(folder).await.unwrap();
+}
+
+#[actix_web::test]
+async fn test_global_admin_fields() {
+    let (config, manager, pool) = prepare_config().await;
+    let mut controller = ChannelController::new();
+    controller.add(manager);
+
+    let controllers = web::Data::new(Mutex::new(controller));
+    let mail_queues = web::Data::new(Mutex::new(Vec::<Arc<Mutex<MailQueue>>>::new()));
+
+    let start = |role: Role| {
+        let pool = web::Data::new(pool.clone());
+        let controllers = controllers.clone();
+        let mail_queues = mail_queues.clone();
+
+        actix_test::start(move || {
+            let role = role.clone();
+
+            App::new()
+                .app_data(pool.clone())
+                .app_data(controllers.clone())
+                .app_data(mail_queues.clone())
+                .wrap_fn(move |req, srv| {
+                    req.attach(vec![role.clone()]);
+                    req.extensions_mut().insert(UserMeta::new(
+                        1,
+                        vec![1],
+                        role.clone(),
+                        Default::default(),
+                    ));
+                    srv.call(req)
+                })
+                .service(update_playout_config)
+        })
+    };
+
+    let mut hook = config.clone();
+    hook.hooks.clip_start = "touch /tmp/pwned".to_string();
+
+    let mut graphics = config.clone();
+    graphics.graphics.command = "touch /tmp/pwned".to_string();
+
+    let mut task = config.clone();
+    task.task.path = "/tmp/pwned.sh".into();
+
+    let mut output = config.clone();
+    output.output.output_param = "-f null -y /etc/passwd".to_string();
+
+    let mut volume = config.clone();
+    volume.processing.volume = 0.5;
+
+    let srv = start(Role::ChannelAdmin);
+
+    for (changed, field) in [
+        (&hook, "hooks.clip_start"),
+        (&graphics, "graphics.command"),
+        (&task, "task.path"),
+        (&output, "output.output_param"),
+    ] {
+        let mut res = srv
+            .put("/playout/config/1")
+            .send_json(changed)
+            .await
+            .unwrap();
+        let body: Value = res.json().await.unwrap();
+
+        assert_eq!(res.status().as_u16(), 403, "{field}");
+        assert!(body.as_str().unwrap().contains(field));
+    }
+
+    let res = srv
+        .put("/playout/config/1")
+        .send_json(&volume)
+        .await
+        .unwrap();
+
+    assert_ne!(res.status().as_u16(), 403);
+
+    let srv = start(Role::GlobalAdmin);
+    let res = srv.put("/playout/config/1").send_json(&hook).await.unwrap();
+
+    assert_ne!(res.status().as_u16(), 403);
+}
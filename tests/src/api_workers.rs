@@ -0,0 +1,266 @@
+use std::sync::Arc;
+
+use actix_web::{dev::Service, web, App, HttpMessage};
+use actix_web_grants::authorities::AttachAuthorities;
+
+use serde_json::{json, Value};
+use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use tokio::sync::Mutex;
+
+use ffplayout::api::{
+    audit::path_channel,
+    routes::{remove_worker_channel, update_worker_channel, worker_status},
+};
+use ffplayout::db::{
+    handles,
+    models::{Channel, Role, User, UserMeta, Worker},
+};
+use ffplayout::player::controller::{ChannelController, ChannelManager};
+use ffplayout::utils::{
+    config::PlayoutConfig,
+    mail::MailQueue,
+    workers::{least_loaded, stale_channels, WorkerChannel, WorkerChannelState, MAX_FAILURES},
+};
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager, Pool<Sqlite>) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let user = User {
+        id: 0,
+        mail: Some("admin@mail.com".to_string()),
+        username: "admin".to_string(),
+        password: "admin".to_string(),
+        role_id: Some(1),
+        channel_ids: Some(vec![1]),
+        token: None,
+        channel_roles: Default::default(),
+    };
+
+    handles::insert_user(&pool, user.clone()).await.unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool.clone(), channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager, pool)
+}
+
+#[actix_web::test]
+async fn test_workers() {
+    let (_, _, pool) = prepare_config().await;
+
+    let channel = handles::insert_channel(
+        &pool,
+        Channel {
+            name: "Channel 7".to_string(),
+            ..Default::default()
+        },
+        Some(7),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(channel.id, 7);
+
+    let worker = Worker {
+        name: "node-2".to_string(),
+        url: "http://10.0.0.2:8787/".to_string(),
+        token: "ffp_secret".to_string(),
+        ..Default::default()
+    };
+
+    let id = handles::insert_worker(&pool, &worker).await.unwrap();
+    handles::update_worker_state(&pool, id, true, 0)
+        .await
+        .unwrap();
+
+    let worker = handles::select_worker(&pool, id).await.unwrap();
+
+    assert_eq!(worker.url, "http://10.0.0.2:8787");
+    assert!(worker.online);
+    assert!(worker.last_seen.is_some());
+    assert!(serde_json::to_value(&worker)
+        .unwrap()
+        .get("token")
+        .is_none());
+
+    handles::update_channel_worker(&pool, 7, Some(id))
+        .await
+        .unwrap();
+
+    let channels = handles::select_related_channels(&pool, None).await.unwrap();
+    let placed = channels.iter().find(|c| c.id == 7).unwrap();
+
+    assert_eq!(placed.worker_id, Some(id));
+    assert!(!Channel {
+        active: true,
+        ..placed.clone()
+    }
+    .runs_here());
+
+    let second = handles::insert_worker(
+        &pool,
+        &Worker {
+            name: "node-3".to_string(),
+            url: "http://10.0.0.3:8787".to_string(),
+            token: "ffp_other".to_string(),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    handles::update_worker_state(&pool, second, true, 0)
+        .await
+        .unwrap();
+
+    let workers = handles::select_workers(&pool).await.unwrap();
+
+    assert_eq!(least_loaded(&workers, &channels, 0).unwrap().id, second);
+    assert_eq!(least_loaded(&workers, &channels, second).unwrap().id, id);
+
+    handles::update_worker_state(&pool, second, false, MAX_FAILURES)
+        .await
+        .unwrap();
+
+    let workers = handles::select_workers(&pool).await.unwrap();
+
+    assert!(least_loaded(&workers, &channels, id).is_none());
+
+    handles::insert_worker_channel(&pool, id, 7).await.unwrap();
+    handles::insert_worker_channel(&pool, id, 8).await.unwrap();
+    handles::insert_worker_channel(&pool, id, 8).await.unwrap();
+
+    let placed = handles::select_worker_channels(&pool, id).await.unwrap();
+
+    assert_eq!(placed, vec![7, 8]);
+
+    // 1 is a channel of the worker itself, 7 runs still there, 8 was moved away
+    let states = [1, 7, 8].map(|id| WorkerChannelState { id, running: true });
+
+    assert_eq!(stale_channels(id, &states, &channels, &placed), vec![8]);
+    assert_eq!(
+        stale_channels(second, &states, &channels, &placed),
+        vec![7, 8]
+    );
+    assert!(stale_channels(id, &states, &channels, &[]).is_empty());
+
+    handles::delete_worker_channel(&pool, id, 8).await.unwrap();
+
+    assert_eq!(
+        handles::select_worker_channels(&pool, id).await.unwrap(),
+        vec![7]
+    );
+
+    handles::delete_worker(&pool, id).await.unwrap();
+
+    assert!(handles::select_worker_channels(&pool, id)
+        .await
+        .unwrap()
+        .is_empty());
+
+    assert_eq!(
+        handles::select_channel(&pool, &7).await.unwrap().worker_id,
+        None
+    );
+
+    assert_eq!(path_channel("/api/worker/1"), None);
+    assert_eq!(path_channel("/api/worker/channel/7"), Some(7));
+    assert_eq!(path_channel("/api/group/2/process/"), None);
+}
+
+#[actix_web::test]
+async fn test_worker_channel_conflict() {
+    let (config, manager, pool) = prepare_config().await;
+    let channel = manager.channel.lock().await.clone();
+    let mut controller = ChannelController::new();
+    controller.add(manager);
+
+    let controllers = web::Data::new(Mutex::new(controller));
+    let mail_queues = web::Data::new(Mutex::new(Vec::<Arc<Mutex<MailQueue>>>::new()));
+    let db_pool = web::Data::new(pool.clone());
+
+    let srv = actix_test::start(move || {
+        App::new()
+            .app_data(db_pool.clone())
+            .app_data(controllers.clone())
+            .app_data(mail_queues.clone())
+            .wrap_fn(|req, srv| {
+                req.attach(vec![Role::GlobalAdmin]);
+                req.extensions_mut().insert(UserMeta::new(
+                    1,
+                    vec![],
+                    Role::GlobalAdmin,
+                    Default::default(),
+                ));
+                srv.call(req)
+            })
+            .service(update_worker_channel)
+            .service(remove_worker_channel)
+            .service(worker_status)
+    });
+
+    // channel 1 was created on the worker itself, a controller sends its own channel 1
+    let mut remote = config.clone();
+    remote.output.output_param = "-f null -".to_string();
+
+    let data = WorkerChannel {
+        channel: Channel {
+            name: "Remote".to_string(),
+            ..channel
+        },
+        advanced: remote.advanced.clone(),
+        config: remote,
+    };
+
+    let res = srv.put("/worker/channel/1").send_json(&data).await.unwrap();
+
+    assert_eq!(res.status().as_u16(), 409);
+
+    let res = srv.delete("/worker/channel/1").send().await.unwrap();
+
+    assert_eq!(res.status().as_u16(), 409);
+
+    let mut res = srv.get("/worker/status").send().await.unwrap();
+    let body: Value = res.json().await.unwrap();
+
+    assert_eq!(body, json!([]));
+
+    let stored = handles::select_channel(&pool, &1).await.unwrap();
+    let stored_config = PlayoutConfig::new(&pool, 1).await.unwrap();
+
+    assert_ne!(stored.name, "Remote");
+    assert_eq!(
+        stored_config.output.output_param,
+        config.output.output_param
+    );
+
+    // the same channel, when this controller has created it
+    handles::insert_controller_channel(&pool, 1, 1)
+        .await
+        .unwrap();
+
+    let mut res = srv.get("/worker/status").send().await.unwrap();
+    let body: Value = res.json().await.unwrap();
+
+    assert_eq!(body, json!([{"id": 1, "running": false}]));
+
+    let res = srv.put("/worker/channel/1").send_json(&data).await.unwrap();
+
+    assert_ne!(res.status().as_u16(), 409);
+}
@@ -0,0 +1,342 @@
+use std::{path::Path, sync::atomic::Ordering};
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use ffplayout::db::{handles, models::Airing};
+use ffplayout::player::{
+    controller::{ChannelManager, HoldMode},
+    utils::*,
+};
+use ffplayout::utils::{config::PlayoutConfig, control, history, time_machine::time_now};
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET processing_width = 1024, processing_height = 576;
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager)
+}
+
+#[tokio::test]
+async fn test_control_stop_after_current() {
+    let (_, manager) = prepare_config().await;
+    let command: control::ProcessCtl = serde_json::from_str(r#""stop_after_current""#).unwrap();
+
+    assert_eq!(command, control::ProcessCtl::StopAfterCurrent);
+    assert_eq!(command.to_string(), "stop_after_current");
+
+    // a stopped channel has nothing to finish
+    let result = control::process_command(&manager, command.clone())
+        .await
+        .unwrap();
+
+    assert_eq!(result, "Success");
+    assert!(!manager.stop_after_current.load(Ordering::SeqCst));
+
+    manager.is_alive.store(true, Ordering::SeqCst);
+
+    let result = control::process_command(&manager, command).await.unwrap();
+
+    assert_eq!(result, "Success");
+    assert!(manager.stop_after_current.load(Ordering::SeqCst));
+    assert!(!manager.is_processing.load(Ordering::SeqCst));
+    assert_eq!(
+        control::process_command(&manager, control::ProcessCtl::Status)
+            .await
+            .unwrap(),
+        "stopping"
+    );
+
+    manager.is_alive.store(false, Ordering::SeqCst);
+}
+
+#[test]
+fn test_splice_reload() {
+    let clip = |source: &str, begin: f64| Media {
+        source: source.to_string(),
+        begin: Some(begin),
+        out: 60.0,
+        duration: 60.0,
+        ..Default::default()
+    };
+    let old = vec![
+        clip("a.mp4", 0.0),
+        clip("b.mp4", 60.0),
+        clip("c.mp4", 120.0),
+    ];
+
+    // b is playing, c gets replaced
+    let new = vec![
+        clip("a.mp4", 0.0),
+        clip("b.mp4", 60.0),
+        clip("d.mp4", 120.0),
+    ];
+    assert_eq!(splice_index(&old, 2, &new), Some(2));
+
+    // a clip is inserted before b, b moves
+    let new = vec![
+        clip("a.mp4", 0.0),
+        clip("e.mp4", 60.0),
+        clip("b.mp4", 120.0),
+    ];
+    assert_eq!(splice_index(&old, 2, &new), None);
+
+    // b is the last clip now
+    let new = vec![clip("a.mp4", 0.0), clip("b.mp4", 60.0)];
+    assert_eq!(splice_index(&old, 2, &new), Some(2));
+
+    // nothing played yet
+    assert_eq!(splice_index(&old, 0, &new), None);
+}
+
+#[test]
+fn test_insert_next_clip() {
+    let clip = |source: &str, begin: f64, out: f64| Media {
+        source: source.to_string(),
+        begin: Some(begin),
+        index: Some(0),
+        out,
+        duration: out,
+        ..Default::default()
+    };
+    let program = vec![
+        clip("a.mp4", 0.0, 60.0),
+        clip("filler/f.mp4", 60.0, 120.0),
+        clip("b.mp4", 180.0, 60.0),
+    ];
+    let filler = Path::new("filler");
+    let news = clip("srt://127.0.0.1:9000", 0.0, 30.0);
+
+    // push: the inserted clip starts before the next one, the time shift moves the rest
+    let mut list = program.clone();
+    let shift = control::insert_clip(
+        &mut list,
+        1,
+        news.clone(),
+        &control::InsertMode::Push,
+        filler,
+    );
+    assert_eq!(shift, -30.0);
+    assert_eq!(list.len(), 4);
+    assert_eq!(list[1].begin, Some(30.0));
+    assert_eq!(list[2].begin, Some(60.0));
+    assert_eq!(list[3].index, Some(3));
+
+    // trim: the filler gets shorter, b stays on time
+    let mut list = program.clone();
+    let shift = control::insert_clip(
+        &mut list,
+        1,
+        news.clone(),
+        &control::InsertMode::Trim,
+        filler,
+    );
+    assert_eq!(shift, 0.0);
+    assert_eq!(list[1].begin, Some(60.0));
+    assert_eq!(list[2].begin, Some(90.0));
+    assert_eq!(list[2].out, 90.0);
+    assert_eq!(list[3].begin, Some(180.0));
+
+    // trim without filler pushes
+    let mut list = program.clone();
+    let shift = control::insert_clip(&mut list, 2, news, &control::InsertMode::Trim, filler);
+    assert_eq!(shift, -30.0);
+}
+
+#[tokio::test]
+async fn test_control_goto_seek() {
+    let (_, manager) = prepare_config().await;
+    let clip = |source: &str, begin: f64| Media {
+        source: source.to_string(),
+        begin: Some(begin),
+        out: 60.0,
+        duration: 60.0,
+        ..Default::default()
+    };
+
+    *manager.current_list.lock().await = vec![
+        clip("a.mp4", 0.0),
+        clip("b.mp4", 60.0),
+        clip("c.mp4", 120.0),
+    ];
+
+    let goto = |index: Option<usize>, source: Option<&str>, position: Option<f64>| {
+        control::ControlParams {
+            control: control::PlayerCtl::Goto,
+            index,
+            source: source.map(String::from),
+            position,
+            ..Default::default()
+        }
+    };
+
+    let start = control::control_state(&manager.db_pool, &manager, &goto(Some(1), None, None))
+        .await
+        .unwrap();
+    let inside =
+        control::control_state(&manager.db_pool, &manager, &goto(Some(1), None, Some(30.0)))
+            .await
+            .unwrap();
+
+    // the in point moves the time shift
+    let shift =
+        inside["shifted_seconds"].as_f64().unwrap() - start["shifted_seconds"].as_f64().unwrap();
+    assert!(is_close(shift, 30.0, 0.5));
+    assert_eq!(inside["media"]["in"], 30.0);
+
+    let by_source =
+        control::control_state(&manager.db_pool, &manager, &goto(None, Some("c.mp4"), None))
+            .await
+            .unwrap();
+    assert_eq!(by_source["media"]["source"], "c.mp4");
+
+    assert!(
+        control::control_state(&manager.db_pool, &manager, &goto(Some(5), None, None))
+            .await
+            .is_err()
+    );
+
+    // no clip is playing
+    let seek = control::ControlParams {
+        control: control::PlayerCtl::Seek,
+        offset: Some(10.0),
+        ..Default::default()
+    };
+    assert!(control::control_state(&manager.db_pool, &manager, &seek)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_control_hold_release() {
+    let (_, manager) = prepare_config().await;
+    let params = |control, mode| control::ControlParams {
+        control,
+        mode,
+        ..Default::default()
+    };
+
+    let hold = control::control_state(
+        &manager.db_pool,
+        &manager,
+        &params(control::PlayerCtl::Hold, Some(HoldMode::Freeze)),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(hold["mode"], "freeze");
+    assert_eq!(*manager.hold.lock().await, Some(HoldMode::Freeze));
+
+    let release = params(control::PlayerCtl::Release, None);
+
+    assert!(control::control_state(&manager.db_pool, &manager, &release)
+        .await
+        .is_ok());
+    assert_eq!(*manager.hold.lock().await, None);
+    assert!(control::control_state(&manager.db_pool, &manager, &release)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_control_take() {
+    let (_, manager) = prepare_config().await;
+    let take = control::ControlParams {
+        control: control::PlayerCtl::Take,
+        ..Default::default()
+    };
+
+    assert!(control::control_state(&manager.db_pool, &manager, &take)
+        .await
+        .is_err());
+
+    manager.manual_wait.store(true, Ordering::SeqCst);
+
+    let result = control::control_state(&manager.db_pool, &manager, &take)
+        .await
+        .unwrap();
+
+    assert_eq!(result["operation"], "take");
+    assert!(!manager.manual_wait.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_manual_trigger() {
+    let node: Media = serde_json::from_str(
+        r#"{"in":0,"out":10,"duration":10,"source":"a.mp4","trigger":"manual"}"#,
+    )
+    .unwrap();
+
+    assert_eq!(node.trigger, Trigger::Manual);
+
+    let auto: Media =
+        serde_json::from_str(r#"{"in":0,"out":10,"duration":10,"source":"a.mp4"}"#).unwrap();
+
+    assert_eq!(auto.trigger, Trigger::Auto);
+    assert!(!serde_json::to_string(&auto).unwrap().contains("trigger"));
+}
+
+#[tokio::test]
+async fn test_drain_channel() {
+    let (config, manager) = prepare_config().await;
+    let now = time_now(&config.channel.timezone);
+    let airing = Airing {
+        channel_id: 1,
+        source: "/media/a.mp4".to_string(),
+        started_at: (now - chrono::TimeDelta::seconds(30))
+            .format(history::TIME_FORMAT)
+            .to_string(),
+        planned: 60.0,
+        ..Default::default()
+    };
+
+    handles::insert_airing(&manager.db_pool, &airing)
+        .await
+        .unwrap();
+    handles::update_player(&manager.db_pool, 1, true)
+        .await
+        .unwrap();
+    *manager.current_date.lock().await = "2024-06-01".to_string();
+    manager.channel.lock().await.active = true;
+    manager.is_alive.store(true, Ordering::SeqCst);
+
+    manager.drain(std::time::Duration::from_secs(1)).await;
+
+    assert!(!manager.is_alive.load(Ordering::SeqCst));
+    assert!(!manager.channel.lock().await.active);
+
+    // the channel starts again with the engine
+    let channel = handles::select_channel(&manager.db_pool, &1).await.unwrap();
+    assert!(channel.active);
+    assert_eq!(channel.last_date, Some("2024-06-01".to_string()));
+
+    let (start, end) = history::date_range(
+        &airing.started_at[..10],
+        &now.format("%Y-%m-%d").to_string(),
+    )
+    .unwrap();
+    let airings = handles::select_airings(&manager.db_pool, 1, &start, &end)
+        .await
+        .unwrap();
+    let duration = airings[0].duration.unwrap();
+    assert!((29.0..40.0).contains(&duration));
+}
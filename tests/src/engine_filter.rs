@@ -2,9 +2,27 @@ use std::fs;
 
 use sqlx::sqlite::SqlitePoolOptions;
 
-use ffplayout::db::handles;
-use ffplayout::player::{controller::ChannelManager, utils::Media};
-use ffplayout::utils::config::{OutputMode::*, PlayoutConfig};
+use ffplayout::db::{handles, models::Loudness};
+use ffplayout::player::{
+    controller::ChannelManager,
+    utils::{
+        detect::Detector,
+        is_live, is_logo, loudness, prepare_output_cmd,
+        probe::{idet_result, last_keyframe, AudioStream, MediaProbe, VideoStream},
+        seek_and_length,
+        subtitle::{burn_filter, burn_in, SubtitleSource},
+        AudioTrack, Media,
+    },
+};
+use ffplayout::utils::{
+    config::{
+        AspectMode, ColorSpace, Deinterlace, FpsMode, HwAccel, OutputMode::*, PlayoutConfig,
+        SubtitleMode, Tonemap,
+    },
+    events::EventKind,
+    graphics, hw_accel, ticker,
+};
+use ffplayout::vec_strings;
 
 async fn get_config() -> (PlayoutConfig, ChannelManager) {
     let pool = SqlitePoolOptions::new()
@@ -26,7 +44,9 @@ async fn get_config() -> (PlayoutConfig, ChannelManager) {
 
     let config = PlayoutConfig::new(&pool, 1).await.unwrap();
     let channel = handles::select_channel(&pool, &1).await.unwrap();
-    let manager = ChannelManager::new(pool, channel, config.clone()).await;
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
 
     (config, manager)
 }
@@ -47,3 +67,870 @@ async fn simple_filtering() {
 
     // println!("{f:?}");
 }
+
+#[tokio::test]
+async fn external_audio_track() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.audio_tracks = 2;
+
+    let mut media = Media {
+        out: 10.0,
+        duration: 10.0,
+        source: "./assets/media_mix/with_audio.mp4".into(),
+        audio_tracks: vec![
+            AudioTrack::default(),
+            AudioTrack {
+                source: "./assets/media_mix/audio_eng.mp3".into(),
+                stream: None,
+            },
+        ],
+        ..Default::default()
+    };
+
+    let cmd = seek_and_length(&config, &mut media);
+
+    assert_eq!(
+        cmd,
+        vec_strings![
+            "-i",
+            "./assets/media_mix/with_audio.mp4",
+            "-i",
+            "./assets/media_mix/audio_eng.mp3",
+            "-t",
+            "10"
+        ]
+    );
+
+    media.add_filter(&config, &None).await;
+
+    let filter = media.filter.unwrap().cmd();
+
+    assert!(filter[1].contains("[1:a:0]apad=whole_dur=10,anull,volume@volume1=0.05[aout1]"));
+}
+
+#[tokio::test]
+async fn live_source_cmd() {
+    let (config, _) = get_config().await;
+
+    let mut media = Media {
+        seek: 20.0,
+        out: 3600.0,
+        duration: 3600.0,
+        source: "srt://127.0.0.1:9000?mode=caller".into(),
+        ..Default::default()
+    };
+
+    assert!(is_live(&media.source));
+    assert!(is_live("udp://239.0.0.1:1234"));
+    assert!(!is_live("https://example.org/clip.mp4"));
+
+    let cmd = seek_and_length(&config, &mut media);
+
+    assert_eq!(
+        cmd,
+        vec_strings![
+            "-rw_timeout",
+            "5000000",
+            "-i",
+            "srt://127.0.0.1:9000?mode=caller",
+            "-t",
+            "3580"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn subtitle_burn_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.subtitle_mode = SubtitleMode::Passthrough;
+
+    let media = Media {
+        seek: 5.0,
+        out: 10.0,
+        duration: 10.0,
+        source: "./assets/media_mix/with_audio.mp4".into(),
+        ..Default::default()
+    };
+    let text = SubtitleSource::File("C:\\media\\it's.srt".into());
+    let bitmap = SubtitleSource::Embedded(0, "dvb_subtitle".into());
+
+    assert!(burn_in(&config, &text));
+    assert!(!burn_in(&config, &bitmap));
+
+    config.output.mode = HLS;
+
+    assert!(!burn_in(&config, &text));
+
+    assert_eq!(
+        burn_filter(&media, &text),
+        "setpts=PTS+5/TB,subtitles=filename='C\\:/media/it\\'s.srt',setpts=PTS-STARTPTS"
+    );
+    assert_eq!("burn".parse::<SubtitleMode>().unwrap(), SubtitleMode::Burn);
+}
+
+#[tokio::test]
+async fn audio_language_metadata() {
+    let (mut config, _) = get_config().await;
+
+    config.processing.audio_tracks = 2;
+    config.processing.audio_languages = vec_strings!["deu", "eng"];
+    config.output.output_cmd = Some(vec_strings!["-c:a", "aac", "-f", "mpegts", "out.ts"]);
+
+    let cmd = prepare_output_cmd(&config, vec_strings!["-i", "pipe:0"], &None);
+
+    assert_eq!(
+        cmd,
+        vec_strings![
+            "-i",
+            "pipe:0",
+            "-metadata:s:a:0",
+            "language=deu",
+            "-metadata:s:a:1",
+            "language=eng",
+            "-c:a",
+            "aac",
+            "-f",
+            "mpegts",
+            "out.ts"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stinger_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.transition.stinger_path = "/opt/stinger.mov".into();
+    config.transition.stinger_offset = 0.5;
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.out = 10.0;
+    media.stinger_in = true;
+    media.stinger_out = true;
+    media.add_filter(&config, &None).await;
+
+    let filter = media.filter.unwrap().cmd();
+
+    assert!(filter[1].contains(
+        "null[sia];movie=/opt/stinger.mov:seek_point=0.5,setpts=PTS-STARTPTS,format=rgba,scale=1024:576[sib];[sia][sib]overlay=eof_action=pass"
+    ));
+    assert!(filter[1].contains(
+        "null[soa];movie=/opt/stinger.mov,setpts=PTS-STARTPTS+9.5/TB,format=rgba,scale=1024:576[sob];[soa][sob]overlay=eof_action=pass"
+    ));
+}
+
+#[tokio::test]
+async fn animated_logo_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.add_logo = true;
+    config.processing.logo_path = "./assets/logo.png".into();
+    config.processing.logo_loop = 2;
+    config.processing.logo_fps = 12.5;
+    config.processing.logo_scale = "10%".into();
+
+    assert!(is_logo("./assets/logo.png"));
+    assert!(!is_logo("./assets/logo_%03d.png"));
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.add_filter(&config, &None).await;
+
+    let filter = media.filter.unwrap().cmd();
+
+    assert!(filter[1].contains(
+        "movie=./assets/logo.png:loop=2,setpts=N/(12.5*TB),tpad=stop=-1:stop_mode=clone,format=rgba,colorchannelmixer=aa=0.7,scale=102:-1"
+    ));
+}
+
+#[tokio::test]
+async fn loudnorm_filter() {
+    let (mut config, manager) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.loudnorm = true;
+
+    let output = r#"[Parsed_loudnorm_0 @ 0x5581]
+{
+    "input_i" : "-27.61",
+    "input_tp" : "-4.47",
+    "input_lra" : "18.06",
+    "input_thresh" : "-39.20",
+    "output_i" : "-23.04",
+    "output_tp" : "-1.00",
+    "output_lra" : "9.00",
+    "output_thresh" : "-34.38",
+    "normalization_type" : "dynamic",
+    "target_offset" : "0.04"
+}"#;
+    let mut measured = loudness::parse(output).unwrap();
+
+    assert_eq!(measured.input_i, -27.61);
+    assert_eq!(measured.input_thresh, -39.2);
+    assert!(loudness::parse(&output.replace("-27.61", "-inf")).is_none());
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.add_filter(&config, &None).await;
+
+    assert!(
+        media.filter.unwrap().cmd()[1].contains("anull,loudnorm=I=-23:LRA=7:TP=-1,aresample=48000")
+    );
+
+    measured.source = "./assets/media_mix/with_audio.mp4".to_string();
+    handles::insert_loudness(&manager.db_pool, &measured)
+        .await
+        .unwrap();
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    let cached: Loudness = handles::select_loudness(&manager.db_pool, &media.source, 0, 0)
+        .await
+        .unwrap();
+    media.loudness = Some(cached);
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.unwrap().cmd()[1].contains(
+        "loudnorm=I=-23:LRA=7:TP=-1:measured_I=-27.61:measured_LRA=18.06:measured_TP=-4.47:measured_thresh=-39.2:linear=true"
+    ));
+}
+
+#[tokio::test]
+async fn clip_audio_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+
+    let item: Media = serde_json::from_str(
+        r#"{"in": 0, "out": 10, "duration": 10, "source": "./assets/media_mix/with_audio.mp4",
+            "audio": {"gain_db": -3.5, "channel_map": [3, 4], "stream_index": 1}}"#,
+    )
+    .unwrap();
+
+    assert_eq!(item.audio.channel_map, vec![3, 4]);
+    assert_eq!(item.audio_track_inputs(), vec![(0, 1)]);
+    assert_eq!(
+        serde_json::to_value(&item).unwrap()["audio"],
+        serde_json::json!({"gain_db": -3.5, "channel_map": [3, 4], "stream_index": 1})
+    );
+
+    let mut media = Media::new(0, "./assets/media_mix/dual_audio.mp4", true).await;
+    media.audio = item.audio;
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.unwrap().cmd()[1]
+        .contains("anull,pan=stereo|c0=c2|c1=c3,volume@volume0=0.05,volume=-3.5dB[aout0]"));
+
+    let item: Media = serde_json::from_str(
+        r#"{"in": 0, "out": 10, "duration": 10, "source": "a.mp4", "audio": "b.mp3"}"#,
+    )
+    .unwrap();
+
+    assert_eq!(item.audio.source, "b.mp3");
+    assert_eq!(serde_json::to_value(&item).unwrap()["audio"], "b.mp3");
+}
+
+#[tokio::test]
+async fn silence_black_detection() {
+    let (mut config, manager) = get_config().await;
+
+    config.output.mode = Stream;
+    config.monitor.silence_duration = 0.2;
+    config.monitor.black_duration = 0.1;
+    config.monitor.freeze_duration = 5.0;
+
+    let mut media = Media::new(0, "./assets/media_mix/dual_audio.mp4", true).await;
+    media.seek = 10.0;
+    media.add_filter(&config, &None).await;
+
+    let filter = media.filter.clone().unwrap().cmd();
+
+    assert!(filter[1].contains(
+        "blackdetect=d=0:pix_th=0.1,freezedetect=d=0.5,metadata=mode=print:file=pipe\\\\:2:direct=1"
+    ));
+    assert!(filter[1].contains("silencedetect=n=-60dB:d=0.5,ametadata=mode=print"));
+
+    let mut events = manager.events.subscribe();
+    let mut detector = Detector::new(&manager, &config, &media).unwrap();
+
+    assert!(detector.process("frame:25   pts:1000    pts_time:1.5"));
+    assert!(detector.process("lavfi.black_start=1.5"));
+    assert!(detector.process("lavfi.silence_start=1.5"));
+    assert!(detector.process("lavfi.silence_end=1.6"));
+    assert!(detector.process("lavfi.freezedetect.freeze_start=1.5"));
+    assert!(detector.process("lavfi.freezedetect.freeze_duration=0.5"));
+    assert!(!detector.process("[error] something failed"));
+
+    let event = tokio::time::timeout(std::time::Duration::from_secs(1), events.recv())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(event.event, EventKind::Black);
+    assert_eq!(event.data["start"], 11.5);
+
+    // silence ended before the alarm
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn keyframe_seek_cmd() {
+    let (config, _) = get_config().await;
+
+    assert_eq!(
+        last_keyframe("0.000000\n10.010000\n20.020000\n", 15.5),
+        Some(10.01)
+    );
+    assert_eq!(last_keyframe("20.020000\n", 15.5), None);
+
+    let mut media = Media {
+        seek: 12.5,
+        out: 30.0,
+        duration: 60.0,
+        keyframe: Some(10.0),
+        source: "./assets/media_mix/dual_audio.mp4".into(),
+        ..Default::default()
+    };
+
+    let cmd = seek_and_length(&config, &mut media);
+
+    assert_eq!(
+        cmd,
+        vec_strings![
+            "-ss",
+            "10",
+            "-i",
+            "./assets/media_mix/dual_audio.mp4",
+            "-t",
+            "20"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn clip_decoder_params() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+
+    let mut media = Media {
+        out: 10.0,
+        duration: 10.0,
+        source: "./assets/media_mix/with_audio.mp4".into(),
+        custom_decoder_params: "-err_detect ignore_err -pix_fmt yuv420p -deinterlace on".into(),
+        probe: Some(MediaProbe {
+            video: vec![VideoStream {
+                width: Some(1024),
+                height: Some(576),
+                aspect_ratio: Some("16:9".into()),
+                frame_rate: "25/1".into(),
+                field_order: Some("progressive".into()),
+                ..Default::default()
+            }],
+            ..MediaProbe::default()
+        }),
+        ..Default::default()
+    };
+    media.cmd = Some(seek_and_length(&config, &mut media));
+
+    assert_eq!(
+        media.cmd.clone().unwrap(),
+        vec_strings![
+            "-err_detect",
+            "ignore_err",
+            "-i",
+            "./assets/media_mix/with_audio.mp4"
+        ]
+    );
+
+    media.add_filter(&config, &None).await;
+
+    let filter = media.filter.unwrap().cmd();
+
+    assert!(filter[1].starts_with("[0:v:0]format=yuv420p,yadif=0:-1:0[vout0]"));
+}
+
+#[tokio::test]
+async fn deinterlace_detection() {
+    let (mut config, _) = get_config().await;
+
+    assert_eq!(
+        idet_result(
+            "[Parsed_idet_0 @ 0x1] Single frame detection: TFF:   150 BFF:     0 Progressive:    20 Undetermined:    30\n\
+            [Parsed_idet_0 @ 0x1] Multi frame detection: TFF:   180 BFF:     0 Progressive:    12 Undetermined:     8"
+        ),
+        Some(true)
+    );
+    assert_eq!(
+        idet_result(
+            "Multi frame detection: TFF:     0 BFF:     0 Progressive:   200 Undetermined:     0"
+        ),
+        Some(false)
+    );
+    assert_eq!(idet_result("no frames"), None);
+
+    config.output.mode = Stream;
+
+    let mut media = Media {
+        out: 10.0,
+        duration: 10.0,
+        source: "./assets/media_mix/with_audio.mp4".into(),
+        probe: Some(MediaProbe {
+            video: vec![VideoStream {
+                width: Some(1024),
+                height: Some(576),
+                aspect_ratio: Some("16:9".into()),
+                frame_rate: "25/1".into(),
+                field_order: Some("tt".into()),
+                ..Default::default()
+            }],
+            ..MediaProbe::default()
+        }),
+        ..Default::default()
+    };
+
+    config.processing.deinterlace = Deinterlace::Off;
+    media.add_filter(&config, &None).await;
+
+    assert!(!media.filter.take().unwrap().cmd()[1].contains("yadif"));
+
+    config.processing.deinterlace = Deinterlace::On;
+    media.probe.as_mut().unwrap().video[0].field_order = Some("progressive".into());
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.take().unwrap().cmd()[1].starts_with("[0:v:0]yadif=0:-1:0"));
+
+    config.processing.deinterlace = Deinterlace::Auto;
+    media.add_filter(&config, &None).await;
+
+    assert!(!media.filter.take().unwrap().cmd()[1].contains("yadif"));
+}
+
+#[tokio::test]
+async fn aspect_mode_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+
+    let mut media = Media {
+        out: 10.0,
+        duration: 10.0,
+        source: "./assets/media_mix/with_audio.mp4".into(),
+        probe: Some(MediaProbe {
+            video: vec![VideoStream {
+                width: Some(720),
+                height: Some(576),
+                aspect_ratio: Some("4:3".into()),
+                frame_rate: "25/1".into(),
+                field_order: Some("progressive".into()),
+                ..Default::default()
+            }],
+            ..MediaProbe::default()
+        }),
+        ..Default::default()
+    };
+
+    for (mode, filter) in [
+        (
+            AspectMode::Auto,
+            "[0:v:0]pad='ih*16/9/sar:ih:(ow-iw)/2:(oh-ih)/2',scale=1024:576",
+        ),
+        (
+            AspectMode::Letterbox,
+            "[0:v:0]crop='iw:iw*sar*9/16',scale=1024:576",
+        ),
+        (
+            AspectMode::CenterCrop,
+            "[0:v:0]crop='iw:iw*sar*9/16',scale=1024:576",
+        ),
+        (AspectMode::Stretch, "[0:v:0]scale=1024:576,setdar"),
+    ] {
+        config.processing.aspect_mode = mode;
+        media.add_filter(&config, &None).await;
+
+        assert!(media.filter.take().unwrap().cmd()[1].starts_with(filter));
+    }
+
+    // the clip overrides the config
+    media.probe.as_mut().unwrap().video[0].aspect_ratio = Some("21:9".into());
+    media.aspect_mode = Some(AspectMode::Pillarbox);
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.take().unwrap().cmd()[1].starts_with("[0:v:0]crop='ih*16/9/sar:ih'"));
+
+    media.aspect_mode = Some(AspectMode::Auto);
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.take().unwrap().cmd()[1]
+        .starts_with("[0:v:0]pad='iw:iw*sar*9/16:(ow-iw)/2:(oh-ih)/2'"));
+}
+
+#[tokio::test]
+async fn hdr_tonemap_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+
+    let mut media = Media {
+        out: 10.0,
+        duration: 10.0,
+        source: "./assets/media_mix/with_audio.mp4".into(),
+        probe: Some(MediaProbe {
+            video: vec![VideoStream {
+                width: Some(1024),
+                height: Some(576),
+                aspect_ratio: Some("16:9".into()),
+                frame_rate: "25/1".into(),
+                field_order: Some("progressive".into()),
+                color_transfer: Some("smpte2084".into()),
+                color_primaries: Some("bt2020".into()),
+                ..Default::default()
+            }],
+            ..MediaProbe::default()
+        }),
+        ..Default::default()
+    };
+
+    media.add_filter(&config, &None).await;
+
+    // the SD channel is in BT.601
+    assert!(media.filter.take().unwrap().cmd()[1].starts_with(
+        "[0:v:0]zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,tonemap=tonemap=hable:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p,scale=in_color_matrix=bt709:out_color_matrix=bt601:in_range=tv:out_range=tv[vout0]"
+    ));
+
+    config.processing.tonemap = Tonemap::Libplacebo;
+    config.processing.tonemap_algorithm = "bt.2390".into();
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.take().unwrap().cmd()[1]
+        .starts_with("[0:v:0]libplacebo=tonemapping=bt.2390:colorspace=bt709"));
+
+    media.probe.as_mut().unwrap().video[0].color_transfer = Some("bt709".into());
+    media.add_filter(&config, &None).await;
+
+    assert!(!media.filter.take().unwrap().cmd()[1].contains("libplacebo"));
+}
+
+#[tokio::test]
+async fn fps_mode_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+
+    let mut media = Media {
+        out: 10.0,
+        duration: 10.0,
+        source: "./assets/media_mix/with_audio.mp4".into(),
+        probe: Some(MediaProbe {
+            video: vec![VideoStream {
+                width: Some(1024),
+                height: Some(576),
+                aspect_ratio: Some("16:9".into()),
+                frame_rate: "30/1".into(),
+                field_order: Some("progressive".into()),
+                ..Default::default()
+            }],
+            ..MediaProbe::default()
+        }),
+        ..Default::default()
+    };
+
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.take().unwrap().cmd()[1].starts_with("[0:v:0]framerate=fps=25[vout0]"));
+
+    media.probe.as_mut().unwrap().video[0].frame_rate = "50/1".into();
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.take().unwrap().cmd()[1].starts_with("[0:v:0]fps=25[vout0]"));
+
+    media.fps_mode = Some(FpsMode::Interpolate);
+    media.add_filter(&config, &None).await;
+
+    assert!(
+        media.filter.take().unwrap().cmd()[1].starts_with("[0:v:0]minterpolate=fps=25:mi_mode=mci")
+    );
+
+    // soft telecine, the frames have the film rate
+    config.processing.fps = 23.976;
+    media.fps_mode = None;
+    media.probe.as_mut().unwrap().video[0].frame_rate = "30000/1001".into();
+    media.probe.as_mut().unwrap().video[0].avg_frame_rate = Some("24000/1001".into());
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.take().unwrap().cmd()[1].starts_with("[0:v:0]fps=23.976[vout0]"));
+
+    // hard telecine
+    media.probe.as_mut().unwrap().video[0].avg_frame_rate = Some("30000/1001".into());
+    media.probe.as_mut().unwrap().video[0].field_order = Some("tt".into());
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.take().unwrap().cmd()[1]
+        .starts_with("[0:v:0]fieldmatch,yadif=deint=interlaced,decimate,fps=23.976[vout0]"));
+}
+
+#[tokio::test]
+async fn color_space_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+
+    // SD output, BT.601 by auto
+    let cmd = config.processing.cmd.clone().unwrap();
+    let tags = cmd.windows(2).find(|w| w[0] == "-colorspace").unwrap();
+
+    assert_eq!(tags[1], "bt470bg");
+
+    let mut media = Media {
+        out: 10.0,
+        duration: 10.0,
+        source: "./assets/media_mix/with_audio.mp4".into(),
+        probe: Some(MediaProbe {
+            video: vec![VideoStream {
+                width: Some(1024),
+                height: Some(576),
+                aspect_ratio: Some("16:9".into()),
+                frame_rate: "25/1".into(),
+                field_order: Some("progressive".into()),
+                ..Default::default()
+            }],
+            ..MediaProbe::default()
+        }),
+        ..Default::default()
+    };
+
+    media.add_filter(&config, &None).await;
+
+    assert!(!media.filter.take().unwrap().cmd()[1].contains("color_matrix"));
+
+    // untagged SD archive material on a HD channel
+    config.processing.color_space = ColorSpace::Bt709;
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.take().unwrap().cmd()[1].starts_with(
+        "[0:v:0]scale=in_color_matrix=bt601:out_color_matrix=bt709:in_range=tv:out_range=tv"
+    ));
+
+    media.probe.as_mut().unwrap().video[0].color_space = Some("bt709".into());
+    media.probe.as_mut().unwrap().video[0].color_range = Some("pc".into());
+    media.add_filter(&config, &None).await;
+
+    assert!(media.filter.take().unwrap().cmd()[1].starts_with(
+        "[0:v:0]scale=in_color_matrix=bt709:out_color_matrix=bt709:in_range=full:out_range=tv"
+    ));
+
+    config.processing.color_space = ColorSpace::Off;
+    media.add_filter(&config, &None).await;
+
+    assert!(!media.filter.take().unwrap().cmd()[1].contains("color_matrix"));
+}
+
+#[tokio::test]
+async fn filter_plugins() {
+    let (mut config, _) = get_config().await;
+    let playlists = std::env::temp_dir().join("ffp_filter_plugins");
+    let plugins = playlists.join("filters.d");
+
+    fs::create_dir_all(&plugins).unwrap();
+    fs::write(
+        plugins.join("old_film_look.toml"),
+        "order = 10\nvideo = \"noise=alls={grain}:allf=t,vignette\"\n\n[params]\ngrain = \"12\"\n",
+    )
+    .unwrap();
+    fs::write(
+        plugins.join("sharpen.toml"),
+        "order = 5\nvideo = \"unsharp\"\n",
+    )
+    .unwrap();
+    fs::write(
+        plugins.join("mono_downmix.toml"),
+        "audio = \"pan=mono|c0=0.5*c0+0.5*c1\"\n",
+    )
+    .unwrap();
+
+    config.output.mode = Stream;
+    config.channel.playlists = playlists.clone();
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.filters = vec![
+        "old_film_look:grain=20".into(),
+        "mono_downmix".into(),
+        "sharpen".into(),
+        "../secret".into(),
+        "missing".into(),
+    ];
+    media.add_filter(&config, &None).await;
+
+    let filter = media.filter.unwrap().cmd();
+
+    assert!(filter[1].contains("unsharp,noise=alls=20:allf=t,vignette[vout0]"));
+    assert!(filter[1].contains("pan=mono|c0=0.5*c0+0.5*c1[aout0]"));
+
+    fs::remove_dir_all(playlists).unwrap();
+}
+
+#[tokio::test]
+async fn graphics_filter() {
+    let (mut config, _) = get_config().await;
+    let public = std::env::temp_dir().join("ffp_graphics_filter");
+
+    fs::create_dir_all(&public).unwrap();
+    fs::write(public.join("graphics.png"), b"").unwrap();
+
+    config.output.mode = Stream;
+    config.channel.public = public.clone();
+    config.graphics.enable = true;
+    config.graphics.source = "https://example.org/ticker.html".into();
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.add_filter(&config, &None).await;
+
+    let filter = media.filter.unwrap().cmd();
+
+    assert!(filter[1].contains(&format!(
+        "[v];movie={}/graphics.png:f=image2:loop=0,setpts=N/(FRAME_RATE*TB),format=rgba[l];[v][l]overlay=0:0:shortest=1",
+        public.display()
+    )));
+
+    let cmd = graphics::render_cmd(&config, &public.join("graphics.tmp.png")).unwrap();
+
+    assert_eq!(cmd[0], "wkhtmltoimage");
+    assert!(cmd.contains(&"1024".to_string()));
+    assert_eq!(cmd[cmd.len() - 2], "https://example.org/ticker.html");
+
+    fs::remove_dir_all(public).unwrap();
+}
+
+#[tokio::test]
+async fn ticker_filter() {
+    let (mut config, _) = get_config().await;
+    let public = std::env::temp_dir().join("ffp_ticker_filter");
+
+    fs::create_dir_all(&public).unwrap();
+
+    config.output.mode = HLS;
+    config.channel.public = public.clone();
+    config.ticker.enable = true;
+    config.ticker.speed = 120.0;
+    config.ticker.style = "y=h-50:fontsize=28".into();
+
+    ticker::init(&config).await.unwrap();
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.add_filter(&config, &None).await;
+
+    let filter = media.filter.unwrap().cmd();
+
+    assert!(filter[1].contains(&format!(
+        "drawtext=textfile='{}/ticker.txt':reload=1:expansion=none:x=w-mod(t*120\\,w+tw):y=h-50:fontsize=28",
+        public.display()
+    )));
+
+    fs::remove_dir_all(public).unwrap();
+}
+
+#[tokio::test]
+async fn clock_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.clock.enable = true;
+    config.clock.format = "%H:%M".into();
+    config.clock.position = "x=w-tw-40:y=40".into();
+    config.clock.style = "fontsize=32".into();
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.add_filter(&config, &None).await;
+
+    let filter = media.filter.unwrap().cmd();
+
+    assert!(filter[1].contains("drawtext=text='%{pts\\:gmtime\\:"));
+    assert!(filter[1].contains("\\:%H\\\\:%M}':x=w-tw-40:y=40:fontsize=32"));
+}
+
+#[tokio::test]
+async fn hw_profile_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.processing.hw_accel = HwAccel::Nvenc;
+    config.processing.add_logo = true;
+    config.processing.logo_path = fs::canonicalize("./assets/logo.png")
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    config.clock.enable = true;
+    config.clock.format = "%H:%M".into();
+    config.clock.position = "x=w-tw-40:y=40".into();
+    config.clock.style = "fontsize=32".into();
+
+    let processing = config.processing.clone();
+    hw_accel::apply(&processing, &mut config.advanced);
+
+    let mut media = Media::new(0, "./assets/media_mix/with_audio.mp4", true).await;
+    media.add_filter(&config, &None).await;
+
+    let filter = media.filter.unwrap().cmd();
+    let video = filter[1].split(";[0:a").next().unwrap();
+
+    assert!(
+        video.contains("format=yuva420p,hwupload_cuda[l];[v][l]overlay_cuda=W-w-12:12:shortest=1")
+    );
+    assert!(video.contains("color=c=black@0:s=1024x576:r=25,format=rgba,drawtext=text="));
+    assert!(video.contains("[tl0a][tl0b]overlay_cuda=shortest=1"));
+    assert!(!video.contains("hwdownload"));
+}
+
+#[tokio::test]
+async fn visual_radio_filter() {
+    let (mut config, _) = get_config().await;
+
+    config.output.mode = Stream;
+    config.visual_radio.enable = true;
+    config.visual_radio.visualizer = "spectrum".into();
+    config.visual_radio.now_playing = true;
+    config.visual_radio.style = "x=40:y=40".into();
+
+    let mut media = Media::new(0, "./assets/media_mix/audio_eng.mp3", false).await;
+    media.title = Some("Morning Song".into());
+    media.artist = Some("The Band".into());
+    media.out = 30.0;
+    media.duration = 30.0;
+    media.probe = Some(MediaProbe {
+        audio: vec![AudioStream::default()],
+        ..MediaProbe::default()
+    });
+    media.add_filter(&config, &None).await;
+
+    assert_eq!(
+        media.cmd.unwrap(),
+        vec_strings![
+            "-i",
+            "./assets/media_mix/audio_eng.mp3",
+            "-f",
+            "lavfi",
+            "-t",
+            "30",
+            "-i",
+            "color=c=black:s=1024x576:r=25",
+            "-t",
+            "30",
+            "-i",
+            "./assets/media_mix/audio_eng.mp3"
+        ]
+    );
+
+    let filter = media.filter.unwrap().cmd();
+
+    assert!(filter[1].starts_with(
+        "[1:v:0]scale=1024:576:force_original_aspect_ratio=decrease,pad=1024:576:(ow-iw)/2:(oh-ih)/2,setsar=1,format=yuv420p[bg];[2:a:0]showfreqs=s=1024x144"
+    ));
+    assert!(filter[1].contains(
+        "[bg][vis]overlay=0:H-h:eof_action=pass,drawtext=text='The Band - Morning Song':x=40:y=40"
+    ));
+    assert!(filter[1].contains("[0:a:0]"));
+}
@@ -0,0 +1,241 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use chrono::prelude::*;
+use serial_test::serial;
+use tokio::sync::Mutex;
+
+use ffplayout::db::handles;
+use ffplayout::file::{init_storage, select_storage_type};
+use ffplayout::player::{
+    controller::ChannelManager,
+    input::folder::{insert_media, remove_media, weighted_pick, ShuffleHistory},
+    utils::*,
+};
+use ffplayout::utils::config::PlayoutConfig;
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET processing_width = 1024, processing_height = 576;
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager)
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_folder_insert_remove() {
+    let (mut config, _) = prepare_config().await;
+    let media = |source: &str| Media {
+        source: source.to_string(),
+        ..Default::default()
+    };
+    let sources = Arc::new(Mutex::new(vec![
+        media("a.mp4"),
+        media("c.mp4"),
+        media("e.mp4"),
+    ]));
+    // a and c have played, e comes next
+    let current_index = Arc::new(AtomicUsize::new(2));
+    let next = || async {
+        sources.lock().await[current_index.load(Ordering::SeqCst)]
+            .source
+            .clone()
+    };
+
+    config.storage.shuffle = false;
+
+    // before the playhead
+    insert_media(&config, &sources, &current_index, media("b.mp4")).await;
+    assert_eq!(current_index.load(Ordering::SeqCst), 3);
+    assert_eq!(next().await, "e.mp4");
+
+    // after the playhead
+    insert_media(&config, &sources, &current_index, media("f.mp4")).await;
+    assert_eq!(current_index.load(Ordering::SeqCst), 3);
+    assert_eq!(next().await, "e.mp4");
+
+    // known sources are not added twice
+    insert_media(&config, &sources, &current_index, media("a.mp4")).await;
+    assert_eq!(sources.lock().await.len(), 5);
+
+    assert!(remove_media(&sources, &current_index, "a.mp4").await);
+    assert_eq!(current_index.load(Ordering::SeqCst), 2);
+    assert_eq!(next().await, "e.mp4");
+
+    assert!(remove_media(&sources, &current_index, "f.mp4").await);
+    assert_eq!(current_index.load(Ordering::SeqCst), 2);
+    assert_eq!(next().await, "e.mp4");
+
+    assert!(!remove_media(&sources, &current_index, "x.mp4").await);
+
+    // in shuffle mode new media is still ahead in the current round
+    config.storage.shuffle = true;
+
+    insert_media(&config, &sources, &current_index, media("d.mp4")).await;
+    assert_eq!(current_index.load(Ordering::SeqCst), 2);
+
+    let list = sources.lock().await;
+    let position = list.iter().position(|m| m.source == "d.mp4").unwrap();
+
+    assert!(position >= 2);
+    assert_eq!(
+        list.iter().map(|m| m.index).collect::<Vec<_>>(),
+        (0..list.len()).map(Some).collect::<Vec<_>>()
+    );
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_storage_weight() {
+    let (mut config, _) = prepare_config().await;
+
+    config.storage.path = "assets/storage".into();
+    config.storage.weights.insert("shows".into(), 3.0);
+    config.storage.weights.insert("shows/old".into(), 0.5);
+
+    assert_eq!(config.storage.weight("assets/storage/shows/ep1.mp4"), 3.0);
+    assert_eq!(
+        config.storage.weight("assets/storage/shows/old/ep1.mp4"),
+        0.5
+    );
+    assert_eq!(config.storage.weight("assets/storage/ads/ad.mp4"), 1.0);
+
+    // prefixes match whole folder names only
+    config.storage.weights.insert("news".into(), 2.0);
+
+    assert_eq!(config.storage.weight("assets/storage/news/today.mp4"), 2.0);
+    assert_eq!(
+        config.storage.weight("assets/storage/newsletter/issue.mp4"),
+        1.0
+    );
+    assert_eq!(
+        config.storage.weight("assets/storage/shows_old/ep1.mp4"),
+        1.0
+    );
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_weighted_pick() {
+    let (mut config, _) = prepare_config().await;
+
+    config.storage.path = "assets/storage".into();
+    config.storage.weights.insert("shows".into(), 3.0);
+    config.storage.weights.insert("ads".into(), 0.0);
+
+    let nodes = ["shows/ep1.mp4", "news/today.mp4", "ads/ad.mp4"].map(|source| Media {
+        source: format!("assets/storage/{source}"),
+        ..Default::default()
+    });
+    let mut history = ShuffleHistory::default();
+    let mut plays = [0; 3];
+
+    for _ in 0..4000 {
+        plays[weighted_pick(&config, &nodes, &history).unwrap()] += 1;
+    }
+
+    // shows play three times as often as news, ads with weight 0 never
+    let ratio = plays[0] as f64 / plays[1] as f64;
+
+    assert!((2.5..3.5).contains(&ratio), "{plays:?}");
+    assert_eq!(plays[2], 0);
+
+    // recent clips are left out, as long as others can be picked
+    history.add(&nodes[0].source, Local::now(), 1, 0, nodes.len());
+
+    for _ in 0..100 {
+        assert_eq!(weighted_pick(&config, &nodes, &history), Some(1));
+    }
+
+    // without any weight, all clips get the same chance
+    config.storage.weights.insert("news".into(), 0.0);
+    config.storage.weights.insert("shows".into(), 0.0);
+    let mut plays = [0; 3];
+
+    for _ in 0..300 {
+        plays[weighted_pick(&config, &nodes, &ShuffleHistory::default()).unwrap()] += 1;
+    }
+
+    assert!(plays.iter().all(|p| *p > 0), "{plays:?}");
+    assert_eq!(weighted_pick(&config, &[], &history), None);
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_storage_exclude() {
+    let (mut config, _) = prepare_config().await;
+    let root = std::path::Path::new("assets/storage");
+
+    config.storage.max_depth = 2;
+    config.storage.exclude = vec!["trailers".into(), "*/raw".into()];
+
+    assert!(!config.storage.is_excluded(root, &root.join("clip.mp4")));
+    assert!(!config
+        .storage
+        .is_excluded(root, &root.join("shows/ep1.mp4")));
+    assert!(config
+        .storage
+        .is_excluded(root, &root.join("shows/s1/ep1.mp4")));
+    assert!(config
+        .storage
+        .is_excluded(root, &root.join("trailers/t1.mp4")));
+    assert!(config
+        .storage
+        .is_excluded(root, &root.join("shows/raw/ep1.mp4")));
+
+    // local storage does not walk into excluded or too deep folders
+    let root = std::env::temp_dir().join("ffp_storage_exclude");
+    let _ = std::fs::remove_dir_all(&root);
+
+    for file in [
+        "clip.mp4",
+        "shows/ep1.mp4",
+        "shows/s1/ep1.mp4",
+        "shows/raw/ep1.mp4",
+        "trailers/t1.mp4",
+    ] {
+        std::fs::create_dir_all(root.join(file).parent().unwrap()).unwrap();
+        std::fs::write(root.join(file), "").unwrap();
+    }
+
+    let storage = init_storage(select_storage_type(&root), root.clone(), vec![])
+        .await
+        .unwrap();
+    let mut paths = storage.walk_media_dir(&root, &config).await.unwrap();
+    paths.sort();
+
+    assert_eq!(
+        paths,
+        vec![
+            root.join("clip.mp4"),
+            root.join("shows"),
+            root.join("shows/ep1.mp4")
+        ]
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,153 @@
+use std::path::Path;
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use serial_test::serial;
+
+use ffplayout::db::handles;
+use ffplayout::file::utils::filler;
+use ffplayout::player::{controller::ChannelManager, utils::*};
+use ffplayout::utils::{
+    config::{is_live_field, GapPolicy, GapRule, PlayoutConfig},
+    config_check::{self},
+};
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET processing_width = 1024, processing_height = 576;
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager)
+}
+
+#[tokio::test]
+#[serial]
+async fn test_gap_policy() {
+    let (mut config, _) = prepare_config().await;
+    let storage = Path::new("/tv-media");
+
+    let rules = [
+        "0-60 loop",
+        "60-00:15:00 fit",
+        "900-3600 tag evergreen",
+        "3600- slate slates/offair.mp4",
+    ]
+    .iter()
+    .filter_map(|l| GapRule::new(storage, l))
+    .collect::<Vec<_>>();
+
+    assert_eq!(rules.len(), 4);
+    assert_eq!(rules[1].max, Some(900.0));
+    assert_eq!(rules[3].max, None);
+    assert_eq!(
+        rules[3].policy,
+        GapPolicy::Slate("/tv-media/slates/offair.mp4".to_string())
+    );
+
+    assert_eq!(gap::policy(&rules, 30.0), Some(&GapPolicy::Loop));
+    assert_eq!(gap::policy(&rules, 60.0), Some(&GapPolicy::Fit));
+    assert_eq!(
+        gap::policy(&rules, 1200.0),
+        Some(&GapPolicy::Query("tag:evergreen".to_string()))
+    );
+    assert!(matches!(
+        gap::policy(&rules, 86400.0),
+        Some(GapPolicy::Slate(_))
+    ));
+    assert_eq!(gap::policy(&rules[..2], 1200.0), None);
+
+    assert!(GapRule::new(storage, "60-900 shuffle").is_none());
+    assert!(GapRule::new(storage, "60-900 slate").is_none());
+    assert!(GapRule::new(storage, "loop").is_none());
+
+    let clips = vec![
+        ("a.mp4".to_string(), 300.0),
+        ("b.mp4".to_string(), 540.0),
+        ("c.mp4".to_string(), 1200.0),
+        ("d.mp4".to_string(), 0.0),
+    ];
+
+    assert_eq!(gap::best_fit(&clips, 600.0, &[]), Some(1));
+    assert_eq!(
+        gap::best_fit(&clips, 600.0, &["b.mp4".to_string()]),
+        Some(0)
+    );
+    assert_eq!(gap::best_fit(&clips, 100.0, &[]), Some(0));
+    assert_eq!(
+        gap::best_fit(
+            &clips,
+            600.0,
+            &[
+                "a.mp4".to_string(),
+                "b.mp4".to_string(),
+                "c.mp4".to_string()
+            ]
+        ),
+        Some(1)
+    );
+    assert_eq!(gap::best_fit(&clips[3..], 600.0, &[]), None);
+
+    config.playlist.gap_policy = vec!["0-60 loop".to_string(), "60- evergreen".to_string()];
+
+    let issues = config_check::check_values(&config);
+
+    assert!(issues
+        .iter()
+        .any(|i| i.field == "playlist.gap_policy" && i.message.contains("60- evergreen")));
+    assert!(is_live_field("playlist.gap_policy"));
+}
+
+#[test]
+fn test_filler_select() {
+    let durations = [300.0, 540.0, 1200.0, 0.5, 0.0];
+
+    // whole clips first, longest fitting one, only the last one gets cut
+    assert_eq!(
+        filler::select(&durations, 2100.0, true),
+        vec![(2, 1200.0), (1, 540.0), (0, 300.0), (0, 60.0)]
+    );
+    assert_eq!(
+        filler::select(&durations, 2100.0, false),
+        vec![(2, 1200.0), (1, 540.0), (0, 300.0)]
+    );
+
+    // every clip plays once, before one repeats
+    assert_eq!(
+        filler::select(&durations, 1080.0, true),
+        vec![(1, 540.0), (0, 300.0), (0, 240.0)]
+    );
+    assert_eq!(
+        filler::select(&durations, 3000.0, false),
+        vec![(2, 1200.0), (1, 540.0), (0, 300.0), (1, 540.0), (0, 300.0)]
+    );
+
+    assert_eq!(
+        filler::select(&durations, 400.0, true),
+        vec![(0, 300.0), (0, 100.0)]
+    );
+
+    // shorter than all clips, the smallest cut
+    assert_eq!(filler::select(&durations, 200.0, true), vec![(0, 200.0)]);
+    assert_eq!(filler::select(&durations, 0.8, true), vec![(0, 0.8)]);
+    assert!(filler::select(&durations, 0.8, false).is_empty());
+    assert!(filler::select(&[], 600.0, true).is_empty());
+}
@@ -3,15 +3,15 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use chrono::NaiveTime;
+use chrono::{Local, NaiveTime, TimeDelta, Weekday};
 use sqlx::sqlite::SqlitePoolOptions;
 
 use ffplayout::db::handles;
-use ffplayout::player::{controller::ChannelManager, utils::*};
+use ffplayout::player::{controller::ChannelManager, input::folder::ShuffleHistory, utils::*};
 use ffplayout::utils::config::ProcessMode::Playlist;
 use ffplayout::utils::playlist::generate_playlist;
 use ffplayout::utils::{
-    config::{PlayoutConfig, Source, Template},
+    config::{Bumpers, PlayoutConfig, Source, Template},
     generator::*,
 };
 
@@ -35,7 +35,9 @@ async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
 
     let config = PlayoutConfig::new(&pool, 1).await.unwrap();
     let channel = handles::select_channel(&pool, &1).await.unwrap();
-    let manager = ChannelManager::new(pool, channel, config.clone()).await;
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
 
     (config, manager)
 }
@@ -131,15 +133,24 @@ async fn test_generate_playlist_from_template() {
                 start: NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
                 duration: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
                 shuffle: false,
+                best_fit: false,
+                series: false,
                 paths: vec![PathBuf::from("assets/")],
+                query: String::new(),
+                weekdays: vec![],
             },
             Source {
                 start: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
                 duration: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
                 shuffle: true,
+                best_fit: false,
+                series: false,
                 paths: vec![PathBuf::from("assets/")],
+                query: String::new(),
+                weekdays: vec![],
             },
         ],
+        bumpers: None,
     });
     config.processing.mode = Playlist;
     config.storage.filler = "assets/".into();
@@ -163,3 +174,193 @@ async fn test_generate_playlist_from_template() {
         "total_duration is {total_duration}"
     );
 }
+
+#[test]
+fn test_template_weekdays() {
+    let block = |start: u32, weekdays: Vec<Weekday>| Source {
+        start: NaiveTime::from_hms_opt(start, 0, 0).unwrap(),
+        duration: NaiveTime::from_hms_opt(3, 0, 0).unwrap(),
+        shuffle: false,
+        best_fit: false,
+        series: false,
+        paths: vec![],
+        query: String::new(),
+        weekdays,
+    };
+    let template = Template {
+        sources: vec![
+            block(20, vec![Weekday::Sat, Weekday::Sun]),
+            block(6, vec![]),
+            block(9, vec![Weekday::Mon]),
+        ],
+        bumpers: None,
+    };
+
+    let monday = template.sources_on(Weekday::Mon);
+    assert_eq!(monday.len(), 2);
+    assert_eq!(monday[1].weekdays, vec![Weekday::Mon]);
+
+    let sunday = template.sources_on(Weekday::Sun);
+    assert_eq!(sunday.len(), 2);
+    assert_eq!(sunday[0].weekdays, vec![Weekday::Sat, Weekday::Sun]);
+
+    let source: Source = serde_json::from_str(
+        r#"{"start": "06:00:00", "duration": "03:00:00", "shuffle": true, "paths": [], "weekdays": ["Mon", "Fri"]}"#,
+    )
+    .unwrap();
+    assert_eq!(source.weekdays, vec![Weekday::Mon, Weekday::Fri]);
+}
+
+#[test]
+fn test_shuffle_history() {
+    let clips: Vec<Media> = ["a.mp4", "b.mp4", "c.mp4", "d.mp4"]
+        .iter()
+        .map(|s| Media {
+            source: s.to_string(),
+            ..Default::default()
+        })
+        .collect();
+    let sources =
+        |list: &[Media]| -> Vec<String> { list.iter().map(|c| c.source.clone()).collect() };
+    let now = Local::now();
+
+    // the last two items
+    let mut history = ShuffleHistory::default();
+
+    for source in ["a.mp4", "b.mp4", "c.mp4"] {
+        history.add(source, now, 2, 0, clips.len());
+    }
+
+    assert!(!history.contains("a.mp4"));
+
+    let mut list = clips.clone();
+    history.apply(&mut list);
+    assert_eq!(sources(&list), vec!["a.mp4", "d.mp4", "b.mp4", "c.mp4"]);
+
+    // the last ten minutes
+    let mut history = ShuffleHistory::default();
+    history.add("a.mp4", now - TimeDelta::minutes(15), 0, 10, clips.len());
+    history.add("b.mp4", now - TimeDelta::minutes(5), 0, 10, clips.len());
+    history.add("c.mp4", now, 0, 10, clips.len());
+
+    assert!(!history.contains("a.mp4"));
+    assert!(history.contains("b.mp4"));
+    assert!(history.contains("c.mp4"));
+
+    // items or minutes, whatever keeps more
+    let mut history = ShuffleHistory::default();
+    history.add("a.mp4", now - TimeDelta::minutes(15), 1, 10, clips.len());
+    history.add("b.mp4", now - TimeDelta::minutes(5), 1, 10, clips.len());
+    history.add("c.mp4", now, 1, 10, clips.len());
+
+    assert_eq!(
+        ["a.mp4", "b.mp4", "c.mp4"].map(|s| history.contains(s)),
+        [false, true, true]
+    );
+
+    // one clip of the list stays fresh
+    let mut history = ShuffleHistory::default();
+
+    for source in ["a.mp4", "b.mp4", "c.mp4", "d.mp4"] {
+        history.add(source, now, 10, 60, clips.len());
+    }
+
+    assert!(!history.contains("a.mp4"));
+
+    let mut list = clips.clone();
+    history.apply(&mut list);
+    assert_eq!(list[0].source, "a.mp4");
+
+    // without history the order stays
+    let mut list = clips.clone();
+    ShuffleHistory::default().apply(&mut list);
+    assert_eq!(sources(&list), sources(&clips));
+}
+
+#[test]
+fn test_insert_bumpers() {
+    let clip = |source: &str, duration: f64| Media {
+        source: source.to_string(),
+        duration,
+        out: duration,
+        ..Default::default()
+    };
+    let clips = vec![
+        clip("a.mp4", 600.0),
+        clip("b.mp4", 600.0),
+        clip("c.mp4", 600.0),
+        clip("d.mp4", 600.0),
+    ];
+    let bumpers = vec![clip("id.mp4", 10.0)];
+    let rule = Bumpers {
+        path: PathBuf::from("bumpers"),
+        every_items: 2,
+        every_minutes: 0.0,
+    };
+
+    let list = insert_bumpers(clips.clone(), &bumpers, &rule, true, 3600.0);
+    let sources: Vec<&str> = list.iter().map(|c| c.source.as_str()).collect();
+    assert_eq!(sources, vec!["a.mp4", "b.mp4", "id.mp4", "c.mp4", "d.mp4"]);
+    assert_eq!(list[2].category, "bumper");
+
+    // bumper at block begin, and the last clip does not fit anymore
+    let rule = Bumpers {
+        every_items: 0,
+        every_minutes: 10.0,
+        ..rule
+    };
+    let list = insert_bumpers(clips, &bumpers, &rule, false, 2400.0);
+    let sources: Vec<&str> = list.iter().map(|c| c.source.as_str()).collect();
+    assert_eq!(
+        sources,
+        vec!["id.mp4", "a.mp4", "id.mp4", "b.mp4", "id.mp4", "c.mp4"]
+    );
+}
+
+#[test]
+fn test_best_fit_list() {
+    let clips: Vec<Media> = [1500.0, 700.0, 1200.0, 1100.0, 400.0]
+        .iter()
+        .enumerate()
+        .map(|(i, d)| Media {
+            source: format!("{i}.mp4"),
+            duration: *d,
+            out: *d,
+            ..Default::default()
+        })
+        .collect();
+
+    // 700 + 1200 + 1100 fill 50 minutes exactly, sequential filling stops at 2200
+    let list = best_fit_list(clips.clone(), 3000.0, BEST_FIT_TOLERANCE);
+    let sources: Vec<&str> = list.iter().map(|c| c.source.as_str()).collect();
+    assert_eq!(sum_durations(&list), 3000.0);
+    assert_eq!(sources, vec!["1.mp4", "2.mp4", "3.mp4"]);
+
+    // not enough clips, take all
+    let list = best_fit_list(clips, 10000.0, BEST_FIT_TOLERANCE);
+    assert_eq!(list.len(), 5);
+}
+
+#[test]
+fn test_series_episodes() {
+    let episodes: Vec<Media> = (1..=4)
+        .map(|i| Media {
+            source: format!("series/episode_{i}.mp4"),
+            duration: 1500.0,
+            out: 1500.0,
+            ..Default::default()
+        })
+        .collect();
+
+    let list = series_episodes(episodes.clone(), None, 3600.0);
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].source, "series/episode_1.mp4");
+
+    let list = series_episodes(episodes.clone(), Some("series/episode_2.mp4"), 1800.0);
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].source, "series/episode_3.mp4");
+
+    // rerun from the beginning
+    let list = series_episodes(episodes, Some("series/episode_4.mp4"), 1800.0);
+    assert_eq!(list[0].source, "series/episode_1.mp4");
+}
@@ -0,0 +1,135 @@
+use sqlx::sqlite::SqlitePoolOptions;
+
+use serial_test::serial;
+
+use ffplayout::db::handles;
+use ffplayout::player::{
+    controller::ChannelManager,
+    output::pipeline::{gstreamer, PipelineBackend},
+    utils::{
+        probe::{AudioStream, MediaProbe, VideoStream},
+        *,
+    },
+};
+use ffplayout::utils::{
+    config::{Backend, OutputMode, PlayoutConfig},
+    config_check::{self},
+};
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET processing_width = 1024, processing_height = 576;
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager)
+}
+
+#[tokio::test]
+#[serial]
+async fn test_gstreamer_pipeline() {
+    let (mut config, _) = prepare_config().await;
+
+    config.processing.backend = Backend::Gstreamer;
+    config.processing.add_logo = false;
+    config.processing.loudnorm = false;
+    config.processing.crossfade = 0.0;
+    config.processing.fps = 25.0;
+    config.text.add_text = false;
+    config.monitor.silence_duration = 0.0;
+    config.monitor.black_duration = 0.0;
+    config.monitor.freeze_duration = 0.0;
+
+    let mut node = Media::new(0, "assets/media_mix/with_audio.mp4", false).await;
+    node.duration = 30.0;
+    node.out = 30.0;
+
+    assert!(!gstreamer::can_decode(&config, &node));
+
+    node.probe = Some(MediaProbe {
+        audio: vec![AudioStream::default()],
+        video: vec![VideoStream::default()],
+        ..Default::default()
+    });
+
+    assert!(gstreamer::can_decode(&config, &node));
+
+    let pipeline = gstreamer::decoder_pipeline(&config, &node);
+
+    assert_eq!(pipeline[1], "location=assets/media_mix/with_audio.mp4");
+    assert!(pipeline.contains(
+        &"video/x-raw,format=I420,width=1024,height=576,framerate=25/1,pixel-aspect-ratio=1/1"
+            .to_string()
+    ));
+    assert!(pipeline.ends_with(&["fdsink".to_string(), "fd=1".to_string()]));
+
+    node.seek = 5.0;
+    assert!(!gstreamer::can_decode(&config, &node));
+
+    node.seek = 0.0;
+    config.processing.add_logo = true;
+    assert!(!gstreamer::can_decode(&config, &node));
+
+    config.output.mode = OutputMode::Stream;
+    config.output.push = None;
+    config.output.legs = vec![];
+    config.output.output_cmd = Some(
+        "-c:v libx264 -b:v 2500k -c:a aac -b:a 96k -f flv rtmp://127.0.0.1/live/stream"
+            .split_whitespace()
+            .map(String::from)
+            .collect(),
+    );
+
+    let pipeline = gstreamer::encoder_pipeline(&config).unwrap().join(" ");
+
+    assert!(pipeline.starts_with("fdsrc fd=0 ! decodebin name=dec"));
+    assert!(pipeline.contains("rtmpsink location=rtmp://127.0.0.1/live/stream"));
+    assert!(pipeline.contains("x264enc bitrate=2500 key-int-max=50"));
+    assert!(pipeline.contains("avenc_aac bitrate=96000"));
+
+    config.output.output_cmd = Some(vec!["-f".into(), "mpegts".into(), "file.ts".into()]);
+    assert!(gstreamer::encoder_pipeline(&config).is_err());
+
+    config.output.mode = OutputMode::Decklink;
+    config.output.decklink_device = "1".to_string();
+    config.output.decklink_mode = "1080i59.94".to_string();
+
+    let pipeline = gstreamer::encoder_pipeline(&config).unwrap().join(" ");
+
+    assert!(pipeline.contains("decklinkvideosink device-number=1 mode=1080i5994"));
+
+    assert!(gstreamer::has_output(&OutputMode::Ndi));
+    assert!(!gstreamer::has_output(&OutputMode::HLS));
+    assert!(matches!(
+        PipelineBackend::new(&config),
+        PipelineBackend::GStreamer(_)
+    ));
+
+    config.output.mode = OutputMode::HLS;
+
+    assert!(matches!(
+        PipelineBackend::new(&config),
+        PipelineBackend::Ffmpeg(_)
+    ));
+    assert!(config_check::check_values(&config)
+        .iter()
+        .any(|i| i.field == "processing.backend"));
+}
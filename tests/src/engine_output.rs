@@ -0,0 +1,485 @@
+use std::{path::PathBuf, str::FromStr};
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use serial_test::serial;
+
+use ffplayout::db::handles;
+use ffplayout::player::{
+    output::{hls_key, preview_stream, recorder, relay, whip},
+    utils::*,
+};
+use ffplayout::utils::{
+    config::{split_push, HlsRendition, HwAccel, OutputMode, PlayoutConfig, SrtMode, VideoMode},
+    hw_accel, metadata, preview_session,
+};
+
+async fn output_config(settings: &str) -> PlayoutConfig {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(&format!(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET {settings};
+        "#
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    PlayoutConfig::new(&pool, 1).await.unwrap()
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_udp_leg() {
+    assert_eq!(
+        relay::udp_target("udp://239.0.0.1:1234?pkt_size=1316"),
+        Some("239.0.0.1:1234".to_string())
+    );
+    assert_eq!(relay::udp_target("-f mpegts udp://239.0.0.1:1234"), None);
+    assert_eq!(relay::udp_target("srt://127.0.0.1:9000"), None);
+
+    let config = output_config(
+        "output_mode = 'stream', output_param = '-c:v libx264 -c:a aac', \
+        output_legs = '-f flv rtmp://127.0.0.1/live/stream
+
+udp://127.0.0.1:1234'",
+    )
+    .await;
+
+    assert!(relay::has_cue_output(&config));
+
+    let config = output_config(
+        "output_mode = 'stream', output_param = '-c:v libx264 -c:a aac', \
+        output_legs = '-f flv rtmp://127.0.0.1/live/stream'",
+    )
+    .await;
+
+    assert!(!relay::has_cue_output(&config));
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_srt_output_config() {
+    let config = output_config(
+        r#"output_mode = "srt", output_param = "-c:v libx264 -c:a aac",
+        output_srt_mode = "listener", output_srt_address = "0.0.0.0:9000""#,
+    )
+    .await;
+
+    assert_eq!(config.output.mode, OutputMode::Srt);
+    assert_eq!(config.output.srt_mode, SrtMode::Listener);
+    assert_eq!(config.output.srt_latency, 120);
+    assert_eq!(
+        config.output.output_cmd.unwrap(),
+        vec!["-c:v", "libx264", "-c:a", "aac", "-f", "mpegts", "pipe:1"]
+    );
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_ndi_output_config() {
+    let config = output_config(r#"output_mode = "ndi""#).await;
+
+    assert_eq!(config.output.mode, OutputMode::Ndi);
+    assert_eq!(
+        config.output.output_cmd.unwrap(),
+        vec![
+            "-pix_fmt",
+            "uyvy422",
+            "-c:a",
+            "pcm_s16le",
+            "-f",
+            "libndi_newtek",
+            "ffplayout-1"
+        ]
+    );
+}
+
+#[test]
+fn test_decklink_video_mode() {
+    let mode = VideoMode::new("1080i50").unwrap();
+
+    assert_eq!((mode.width, mode.height, mode.fps), (1920, 1080, 25.0));
+    assert_eq!(mode.field_order, Some("tt".to_string()));
+
+    let mode = VideoMode::new("ntsc").unwrap();
+
+    assert_eq!((mode.width, mode.height), (720, 486));
+    assert_eq!(mode.fps, 30000.0 / 1001.0);
+    assert_eq!(mode.field_order, Some("bb".to_string()));
+
+    let mode = VideoMode::new("720p59.94").unwrap();
+
+    assert_eq!(mode.fps, 60000.0 / 1001.0);
+    assert_eq!(mode.field_order, None);
+
+    assert!(VideoMode::new("1080x50").is_none());
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_output_legs_config() {
+    let config = output_config(
+        "output_mode = 'stream', output_param = '-c:v libx264 -c:a aac', \
+        output_legs = '-f flv rtmp://127.0.0.1/live/stream
+
+-f segment -segment_time 3600 /rec/%03d.ts'",
+    )
+    .await;
+
+    assert_eq!(
+        config.output.legs,
+        vec![
+            "-f flv rtmp://127.0.0.1/live/stream",
+            "-f segment -segment_time 3600 /rec/%03d.ts"
+        ]
+    );
+    assert_eq!(
+        config.output.output_cmd.unwrap(),
+        vec!["-c:v", "libx264", "-c:a", "aac", "-f", "mpegts", "pipe:1"]
+    );
+}
+
+#[test]
+fn test_icecast_song() {
+    let data = serde_json::json!({"title": "Morning Song", "artist": "The Band", "source": "/Music/a.mp3"});
+    assert_eq!(metadata::song(&data), "The Band - Morning Song");
+
+    let data = serde_json::json!({"title": null, "artist": null, "source": "/Music/track01.mp3"});
+    assert_eq!(metadata::song(&data), "track01");
+
+    let data = serde_json::json!({"title": "Jingle", "artist": " ", "source": "/Music/jingle.mp3"});
+    assert_eq!(metadata::song(&data), "Jingle");
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_preview_stream_cmd() {
+    let config = output_config(
+        "output_mode = 'stream', output_param = '-c:v libx264 -c:a aac', \
+        preview_enable = 1, preview_height = 270, preview_bitrate = 400",
+    )
+    .await;
+
+    let cmd = preview_stream::cmd(&config, "level+error").unwrap();
+
+    assert_eq!(&cmd[4..8], ["-f", "mpegts", "-i", "pipe:0"]);
+    assert!(cmd.windows(2).any(|w| w == ["-vf", "scale=-2:270"]));
+    assert!(cmd.windows(2).any(|w| w == ["-b:v", "400k"]));
+    assert!(cmd.windows(2).any(|w| w == ["-bufsize", "800k"]));
+    assert!(cmd.windows(2).any(|w| w == ["-hls_time", "1"]));
+    assert_eq!(
+        &cmd[cmd.len() - 3..],
+        [
+            "-hls_segment_filename",
+            "assets/hls/preview/stream_%d.ts",
+            "assets/hls/preview/stream.m3u8"
+        ]
+    );
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_preview_session_cmd() {
+    let config = output_config("preview_bitrate = 500").await;
+    let mut node = Media::new(0, "assets/media_mix/with_audio.mp4", false).await;
+    node.duration = 600.0;
+
+    preview_session::prepare(&config, &mut node, 700.0, 60.0).await;
+
+    assert_eq!(node.seek, 600.0);
+    assert_eq!(node.out, 600.0);
+
+    preview_session::prepare(&config, &mut node, 120.0, 900.0).await;
+
+    assert_eq!(node.seek, 120.0);
+    assert_eq!(node.out, 120.0 + preview_session::MAX_LENGTH);
+
+    let dir = preview_session::path(&config, "abc");
+    let cmd = preview_session::cmd(&config, &node, &dir);
+
+    assert_eq!(dir, PathBuf::from("assets/hls/preview/session/abc"));
+    assert!(cmd.windows(2).any(|w| w == ["-ss", "120"]));
+    assert!(cmd.windows(2).any(|w| w == ["-b:v", "1000k"]));
+    assert_eq!(
+        &cmd[cmd.len() - 3..],
+        [
+            "-hls_segment_filename",
+            "assets/hls/preview/session/abc/segment_%d.ts",
+            "assets/hls/preview/session/abc/index.m3u8"
+        ]
+    );
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_hls_ladder_cmd() {
+    let config = output_config(
+        "output_mode = 'hls', processing_fps = 25, output_hls_fmp4 = 1, output_hls_time = 4, \
+        output_hls_ladder = '1920x1080 5000k' || char(10) || '1280x721 3000k 96k' || char(10) || 'invalid'",
+    )
+    .await;
+    let cmd = config.output.output_cmd.clone().unwrap();
+
+    assert_eq!(
+        HlsRendition::new("1280x721 3000k 96k"),
+        Some(HlsRendition {
+            width: 1280,
+            height: 720,
+            video_bitrate: 3000,
+            audio_bitrate: 96
+        })
+    );
+    assert_eq!(HlsRendition::new("1280x720"), None);
+    assert_eq!(config.output.output_count, 2);
+    assert_eq!(
+        config.output.output_filter,
+        Some(
+            "[0:v]split=2[v0][v1];[v0]scale=1920:1080[v0_out];[v1]scale=1280:720[v1_out];[0:a:0]asplit=2[a0][a1]"
+                .to_string()
+        )
+    );
+    assert!(!cmd.contains(&"-filter_complex".to_string()));
+    assert!(cmd.windows(2).any(|w| w == ["-b:v:0", "5000k"]));
+    assert!(cmd.windows(2).any(|w| w == ["-b:a:1", "96k"]));
+    assert!(cmd.windows(2).any(|w| w == ["-g", "100"]));
+    assert!(cmd.windows(2).any(|w| w == ["-hls_segment_type", "fmp4"]));
+    assert_eq!(
+        &cmd[cmd.len() - 7..],
+        [
+            "-hls_segment_filename",
+            "assets/hls/live/stream_%v-%d.m4s",
+            "-master_pl_name",
+            "master.m3u8",
+            "-var_stream_map",
+            "v:0,a:0 v:1,a:1",
+            "assets/hls/live/stream_%v.m3u8"
+        ]
+    );
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_hls_low_latency_cmd() {
+    let config = output_config(
+        "output_mode = 'hls', processing_fps = 25, output_hls_time = 2, output_hls_low_latency = 1, \
+        output_hls_part_time = 0.5, output_hls_ladder = '1280x720 3000k'",
+    )
+    .await;
+    let cmd = config.output.output_cmd.clone().unwrap();
+    let rec_cmd = recorder::cmd(&config, "level+error").unwrap();
+
+    assert!(cmd.windows(2).any(|w| w == ["-f", "dash"]));
+    assert!(cmd.windows(2).any(|w| w == ["-seg_duration", "2"]));
+    assert!(cmd.windows(2).any(|w| w == ["-frag_duration", "0.5"]));
+    assert!(cmd.windows(2).any(|w| w == ["-g", "50"]));
+    assert_eq!(cmd.last().unwrap(), "assets/hls/live/stream.mpd");
+    assert!(rec_cmd
+        .windows(2)
+        .any(|w| w == ["-i", "assets/hls/live/media_0.m3u8"]));
+
+    let config = output_config("output_mode = 'hls', output_hls_program_date_time = 1").await;
+    let cmd = config.output.output_cmd.clone().unwrap();
+
+    assert!(cmd.windows(2).any(|w| w
+        == [
+            "-hls_flags",
+            "append_list+delete_segments+omit_endlist+program_date_time"
+        ]));
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_hls_encryption() {
+    let config = output_config(
+        "output_mode = 'hls', output_hls_encryption = 1, output_hls_key_rotation = 600, \
+        output_hls_key_iv = 'random', output_hls_key_url = 'https://keys.example.org/{channel}/{key}'",
+    )
+    .await;
+    let cmd = config.output.output_cmd.clone().unwrap();
+
+    assert!(cmd.windows(2).any(|w| w
+        == [
+            "-hls_flags",
+            "append_list+delete_segments+omit_endlist+periodic_rekey"
+        ]));
+    assert_eq!(
+        &cmd[cmd.len() - 3..],
+        [
+            "-hls_key_info_file",
+            "assets/hls/keys/key_info",
+            "assets/hls/live/stream.m3u8"
+        ]
+    );
+
+    assert!(hls_key::is_key_name("key_1718000000.key"));
+    assert!(!hls_key::is_key_name("key_.key"));
+    assert!(!hls_key::is_key_name("../key_1.key"));
+
+    let name = hls_key::rotate(&config).await.unwrap();
+    let info = std::fs::read_to_string(hls_key::info_file(&config.channel.public)).unwrap();
+    let lines: Vec<&str> = info.lines().collect();
+
+    assert_eq!(lines[0], format!("https://keys.example.org/1/{name}"));
+    assert_eq!(lines[1], format!("assets/hls/keys/{name}"));
+    assert_eq!(lines[2].len(), 32);
+    assert_eq!(std::fs::read(lines[1]).unwrap().len(), 16);
+
+    std::fs::remove_dir_all(hls_key::dir(&config.channel.public)).unwrap();
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_dash_cmd() {
+    let config = output_config(
+        "output_mode = 'dash', processing_fps = 25, output_hls_time = 4, \
+        output_hls_ladder = '1280x720 3000k' || char(10) || '640x360 800k 96k'",
+    )
+    .await;
+    let cmd = config.output.output_cmd.clone().unwrap();
+    let rec_cmd = recorder::cmd(&config, "level+error").unwrap();
+
+    assert_eq!(OutputMode::from_str("dash"), Ok(OutputMode::Dash));
+    assert!(config.output.mode.is_segmented());
+    assert!(config.output.output_filter.is_some());
+    assert!(cmd.windows(2).any(|w| w == ["-f", "dash"]));
+    assert!(cmd.windows(2).any(|w| w == ["-seg_duration", "4"]));
+    assert!(cmd.windows(2).any(|w| w == ["-use_timeline", "1"]));
+    assert!(cmd.windows(2).any(|w| w == ["-hls_playlist", "1"]));
+    assert!(!cmd.contains(&"-ldash".to_string()));
+    assert_eq!(cmd.last().unwrap(), "assets/hls/live/stream.mpd");
+    assert!(rec_cmd
+        .windows(2)
+        .any(|w| w == ["-i", "assets/hls/live/media_0.m3u8"]));
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_stream_reconnect() {
+    let config = output_config(
+        "output_mode = 'stream', output_param = '-c:v libx264 -c:a aac -f flv rtmp://127.0.0.1/live/stream', \
+        output_reconnect = 1, output_reconnect_delay = 8, output_reconnect_slate = 'assets/logo.png'",
+    )
+    .await;
+    let cmd = config.output.output_cmd.clone().unwrap();
+    let slate = relay::slate_cmd(&config, "level+error").unwrap();
+
+    assert!(relay::is_relay(&config));
+    assert_eq!(
+        config.output.push,
+        Some(vec![
+            "-f".to_string(),
+            "flv".to_string(),
+            "rtmp://127.0.0.1/live/stream".to_string()
+        ])
+    );
+    assert!(cmd.ends_with(&["-f".to_string(), "mpegts".to_string(), "pipe:1".to_string()]));
+    assert!(!cmd.contains(&"flv".to_string()));
+    assert!(slate.windows(2).any(|w| w == ["-i", "assets/logo.png"]));
+    assert!(slate.ends_with(&["-f".to_string(), "mpegts".to_string(), "pipe:1".to_string()]));
+
+    let delays: Vec<u64> = (1..=6)
+        .map(|f| relay::reconnect_delay(&config, f).as_secs())
+        .collect();
+
+    assert_eq!(delays, vec![1, 2, 4, 8, 8, 8]);
+
+    let mut multi = vec![
+        "-c:v",
+        "libx264",
+        "-f",
+        "flv",
+        "rtmp://a/live",
+        "-c:v",
+        "libx264",
+        "-f",
+        "flv",
+        "rtmp://b/live",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+
+    assert_eq!(split_push(&mut multi), None);
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_whip_cmd() {
+    let config = output_config(
+        "output_mode = 'whip', processing_fps = 25, output_whip_url = 'https://whip.example.org/live', \
+        output_whip_token = 'secret', output_whip_bitrate = 1800",
+    )
+    .await;
+    let cmd = config.output.output_cmd.clone().unwrap();
+    let leg = whip::leg(&config);
+
+    assert_eq!(OutputMode::from_str("whip"), Ok(OutputMode::Whip));
+    assert!(relay::is_relay(&config));
+    assert!(cmd.windows(2).any(|w| w == ["-bf", "0"]));
+    assert!(cmd.windows(2).any(|w| w == ["-g", "25"]));
+    assert!(cmd.windows(2).any(|w| w == ["-profile:v", "baseline"]));
+    assert!(cmd.windows(2).any(|w| w == ["-maxrate", "1800k"]));
+    assert!(cmd.windows(2).any(|w| w == ["-c:a", "libopus"]));
+    assert!(cmd.ends_with(&["-f".to_string(), "mpegts".to_string(), "pipe:1".to_string()]));
+    assert!(leg.cmd.windows(2).any(|w| w == ["-f", "whip"]));
+    assert!(leg
+        .cmd
+        .windows(2)
+        .any(|w| w == ["-authorization", "secret"]));
+    assert_eq!(leg.cmd.last().unwrap(), "https://whip.example.org/live");
+    assert_eq!(leg.secret, Some("secret".to_string()));
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_hw_accel_profile() {
+    let config = output_config(
+        "output_mode = 'stream', processing_hw_accel = 'nvenc', processing_hw_device = '1', \
+        output_param = '-c:v libx264 -crf 23 -x264-params keyint=50 -preset faster -tune zerolatency \
+        -profile:v Main -c:a aac -f flv rtmp://127.0.0.1/live/stream'",
+    )
+    .await;
+    let cmd = config.output.output_cmd.clone().unwrap();
+    let process_cmd = config.processing.cmd.clone().unwrap();
+
+    assert_eq!(config.processing.hw_accel, HwAccel::Nvenc);
+    assert!(config
+        .advanced
+        .decoder
+        .input_param
+        .as_ref()
+        .is_some_and(|p| p.contains("-hwaccel_device 1")));
+    assert!(config
+        .advanced
+        .filter
+        .scale
+        .as_ref()
+        .is_some_and(|s| s.starts_with("scale_cuda")));
+    assert!(process_cmd.windows(2).any(|w| w == ["-c:v", "h264_nvenc"]));
+    assert!(cmd.windows(2).any(|w| w == ["-c:v", "h264_nvenc"]));
+    assert!(cmd.windows(2).any(|w| w == ["-preset", "p4"]));
+    assert!(cmd.windows(2).any(|w| w == ["-tune", "ll"]));
+    assert!(cmd.windows(2).any(|w| w == ["-profile:v", "main"]));
+    assert!(!cmd.contains(&"-crf".to_string()));
+    assert!(!cmd.contains(&"-x264-params".to_string()));
+    assert!(cmd.windows(2).any(|w| w == ["-c:a", "aac"]));
+
+    let vaapi = hw_accel::encoder_cmd(
+        &HwAccel::Vaapi,
+        &"-c:v:0 libx265 -preset fast -level 4.1 -b:v 3000k"
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>(),
+    );
+
+    assert_eq!(vaapi.join(" "), "-c:v:0 hevc_vaapi -b:v 3000k");
+    assert_eq!(hw_accel::encoder(&HwAccel::Nvenc, "mpeg2video"), None);
+}
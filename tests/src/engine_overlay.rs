@@ -0,0 +1,154 @@
+use sqlx::sqlite::SqlitePoolOptions;
+
+use serial_test::serial;
+
+use ffplayout::db::handles;
+use ffplayout::player::{controller::ChannelManager, utils::*};
+use ffplayout::utils::{
+    config::{LogoSlot, PlayoutConfig},
+    control, ticker, TextFilter, TextMessage, TextPosition,
+};
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET processing_width = 1024, processing_height = 576;
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager)
+}
+
+#[tokio::test]
+#[serial]
+async fn test_logo_schedule() {
+    let (mut config, _) = prepare_config().await;
+    let logo = std::fs::canonicalize("./assets/logo.png")
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+
+    config.processing.add_logo = true;
+    config.processing.logo_path = String::new();
+    config.processing.logo_slots = vec![LogoSlot {
+        start: 79200.0,
+        end: 21600.0,
+        path: logo.clone(),
+    }];
+
+    let mut node = Media {
+        begin: Some(82800.0),
+        ..Media::default()
+    };
+
+    assert_eq!(logo_path(&config, &node), Some(logo.clone()));
+
+    node.begin = Some(36000.0);
+    assert_eq!(logo_path(&config, &node), None);
+
+    node.logo = Some(format!("[abs]:{logo}"));
+    assert_eq!(logo_path(&config, &node), Some(logo.clone()));
+
+    node.begin = Some(3600.0);
+    node.logo = Some(String::new());
+    assert_eq!(logo_path(&config, &node), None);
+
+    node.logo = None;
+    node.category = "advertisement".to_string();
+    assert_eq!(logo_path(&config, &node), None);
+}
+
+#[test]
+fn test_text_template() {
+    let current = Media {
+        title: Some("Evening News".to_string()),
+        source: "/media/news.mp4".to_string(),
+        ..Media::default()
+    };
+    let next = Media {
+        source: "/media/weather_report.mp4".to_string(),
+        ..Media::default()
+    };
+
+    assert_eq!(
+        control::render_template(
+            "{{title}} - next: {{ next_title }} at {{time}} {{unknown}}",
+            Some(&current),
+            Some(&next),
+            "20:15"
+        ),
+        "Evening News - next: weather_report at 20:15 {{unknown}}"
+    );
+
+    let message: TextMessage = serde_json::from_str(
+        r#"{"text": "{{title}}", "duration": 10, "position": "lower_third", "fontsize": 32}"#,
+    )
+    .unwrap();
+
+    assert_eq!(message.duration, 10.0);
+    assert_eq!(message.position, Some(TextPosition::LowerThird));
+    assert_eq!(message.filter.fontsize, Some("32".to_string()));
+}
+
+#[test]
+fn test_now_next_style() {
+    let filter = TextFilter::with_style(
+        "Now: News / Next: Weather",
+        "x=w*0.05:y=h*0.9:fontsize=24:fontcolor=#ffffff:box=1:shadowx=2",
+    );
+
+    assert_eq!(filter.x, Some("w*0.05".to_string()));
+    assert_eq!(filter.y, Some("h*0.9".to_string()));
+    assert_eq!(filter.r#box, Some("1".to_string()));
+    assert_eq!(
+        filter.to_string(),
+        "text='Now\\: News / Next\\: Weather':x='w*0.05':y='h*0.9':fontsize=24:fontcolor=#ffffff:box=1"
+    );
+}
+
+#[test]
+fn test_ticker_headlines() {
+    let rss = r#"<?xml version="1.0"?><rss><channel><title>News</title>
+        <item><title><![CDATA[First &amp; <b>best</b>]]></title></item>
+        <item><title>Second
+            line</title><link>https://example.org</link></item>
+        </channel></rss>"#;
+    let atom =
+        r#"<feed><title>Feed</title><entry><title type="text">Atom news</title></entry></feed>"#;
+    let json = r#"{"version":"https://jsonfeed.org/version/1.1","items":[{"title":"Json news"},{"id":"1"}]}"#;
+
+    assert_eq!(
+        ticker::headlines(rss),
+        vec!["First & best".to_string(), "Second line".to_string()]
+    );
+    assert_eq!(ticker::headlines(atom), vec!["Atom news".to_string()]);
+    assert_eq!(ticker::headlines(json), vec!["Json news".to_string()]);
+    assert_eq!(ticker::headlines(r#"["One", "Two"]"#).len(), 2);
+
+    let schedule = vec![
+        "06:00:00-09:00:00".to_string(),
+        "22:00:00-01:00:00".to_string(),
+    ];
+
+    assert!(ticker::on_air(&[], 0.0));
+    assert!(ticker::on_air(&schedule, 7.0 * 3600.0));
+    assert!(ticker::on_air(&schedule, 0.5 * 3600.0));
+    assert!(!ticker::on_air(&schedule, 12.0 * 3600.0));
+}
@@ -3,7 +3,16 @@ use sqlx::sqlite::SqlitePoolOptions;
 
 use ffplayout::db::handles;
 use ffplayout::player::controller::ChannelManager;
+use ffplayout::player::input::failover::{Failover, FAILOVER_CHECK};
+use ffplayout::player::utils::{
+    blacklist,
+    json_validate::dry_run,
+    rights,
+    script::{self, Hook},
+    JsonPlaylist, Media,
+};
 use ffplayout::utils::config::{PlayoutConfig, ProcessMode::Playlist};
+use ffplayout::utils::events::EventKind;
 use ffplayout::utils::time_machine::set_mock_time;
 use ffplayout::vec_strings;
 use ffplayout::{player::output::player, utils::config::OutputMode::Null};
@@ -28,7 +37,9 @@ async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
 
     let config = PlayoutConfig::new(&pool, 1).await.unwrap();
     let channel = handles::select_channel(&pool, &1).await.unwrap();
-    let manager = ChannelManager::new(pool, channel, config.clone()).await;
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
 
     (config, manager)
 }
@@ -293,3 +304,214 @@ async fn playlist_change_at_six() {
 
     assert_eq!(playlist_date, "2023-02-09");
 }
+
+#[tokio::test]
+#[serial]
+async fn dry_run_missing_file() {
+    let (mut config, _) = prepare_config().await;
+
+    config.playlist.start_sec = Some(0.0);
+    config.playlist.length_sec = Some(86400.0);
+
+    let mut playlist = JsonPlaylist::new("2024-02-01".into(), 0.0);
+    playlist.program[0].source = "assets/media_mix/not_exists.mp4".into();
+
+    assert!(dry_run(&config, playlist).await.is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn blacklist_after_failures() {
+    let (_, manager) = prepare_config().await;
+    let source = "assets/media_mix/dual_audio.mp4";
+    let mut events = manager.events.subscribe();
+
+    assert!(!blacklist::is_listed(&manager, source).await);
+
+    for _ in 1..blacklist::MAX_FAILURES {
+        assert!(!blacklist::failed(&manager, source, "Hit unrecoverable error!").await);
+    }
+
+    // a successful run resets the counter
+    blacklist::played(&manager).await;
+    assert!(!blacklist::failed(&manager, source, "Hit unrecoverable error!").await);
+
+    for _ in 1..blacklist::MAX_FAILURES - 1 {
+        assert!(!blacklist::failed(&manager, source, "Hit unrecoverable error!").await);
+    }
+
+    assert!(blacklist::failed(&manager, source, "Hit unrecoverable error!").await);
+    assert!(blacklist::is_listed(&manager, source).await);
+    assert_eq!(events.try_recv().unwrap().event, EventKind::Corrupt);
+
+    // remote sources are never blacklisted
+    for _ in 0..blacklist::MAX_FAILURES {
+        assert!(!blacklist::failed(&manager, "https://example.org/live.m3u8", "").await);
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn failover_live_source() {
+    let (mut config, manager) = prepare_config().await;
+    let mut failover = Failover::default();
+    let mut events = manager.events.subscribe();
+
+    config.failover.source = "srt://127.0.0.1:40052".into();
+
+    assert!(failover
+        .next(&manager, &config, 0, 0.0, 300.0)
+        .await
+        .is_none());
+
+    config.failover.enable = true;
+
+    let node = failover
+        .next(&manager, &config, 0, 0.0, 300.0)
+        .await
+        .unwrap();
+
+    assert_eq!(node.out, FAILOVER_CHECK);
+    assert!(node.cmd.unwrap().contains(&"-rw_timeout".to_string()));
+    assert!(failover.is_active());
+
+    let node = failover
+        .next(&manager, &config, 1, 60.0, 20.0)
+        .await
+        .unwrap();
+
+    assert_eq!(node.out, 20.0);
+
+    failover.recover(&manager);
+
+    assert!(!failover.is_active());
+    assert_eq!(events.try_recv().unwrap().data["active"], true);
+    assert_eq!(events.try_recv().unwrap().data["active"], false);
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn rights_window() {
+    let (mut config, _) = prepare_config().await;
+    config.channel.timezone = Some("Europe/Berlin".parse().unwrap());
+
+    let folder = std::env::temp_dir().join("ffp_rights_window");
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+
+    let clip = folder.join("clip.mp4");
+    std::fs::copy("assets/media_mix/with_audio.mp4", &clip).unwrap();
+    std::fs::write(
+        rights::sidecar(&clip.to_string_lossy()),
+        r#"{"valid_from": "2024-06-01", "valid_until": "2024-06-30T20:00:00"}"#,
+    )
+    .unwrap();
+
+    let source = clip.to_string_lossy().to_string();
+    let day = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+
+    assert!(rights::check(
+        &source,
+        &rights::air_time(&config, day("2024-05-31"), 86399.0),
+        0.0
+    )
+    .await
+    .is_err());
+    assert!(rights::check(
+        &source,
+        &rights::air_time(&config, day("2024-06-01"), 0.0),
+        60.0
+    )
+    .await
+    .is_ok());
+    // the clip would still run, when the window ends
+    assert!(rights::check(
+        &source,
+        &rights::air_time(&config, day("2024-06-30"), 71990.0),
+        30.0
+    )
+    .await
+    .is_err());
+
+    let mut playlist = JsonPlaylist::new("2024-07-01".into(), 0.0);
+    config.playlist.start_sec = Some(0.0);
+    config.playlist.length_sec = Some(playlist.program[0].out);
+    playlist.program[0].source = source.clone();
+
+    assert!(dry_run(&config, playlist).await.is_err());
+
+    std::fs::remove_dir_all(&folder).unwrap();
+}
+
+#[tokio::test]
+async fn script_hooks() {
+    let (mut config, _) = prepare_config().await;
+
+    let folder = std::env::temp_dir().join("ffp_script_hooks");
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+
+    // swap the clip in a gap, keep all others
+    let hook = folder.join("hooks.lua");
+    std::fs::write(
+        &hook,
+        r#"
+items = 0
+
+function on_before_item(node, ctx)
+    items = items + 1
+end
+
+function on_gap(node, ctx)
+    if ctx.hook == "on_gap" and ctx.date == "2024-06-01" then
+        return { source = "/media/promo.mp4", out = 30, filters = { "mono_downmix" }, title = "after " .. items }
+    end
+end
+"#,
+    )
+    .unwrap();
+
+    config.script.enable = true;
+    config.script.path = hook.clone();
+
+    let mut node = Media::new(0, "", false).await;
+    node.out = 60.0;
+
+    script::run(&config, Hook::BeforeItem, "2024-06-01", &mut node).await;
+
+    assert_eq!(node.source, "");
+
+    script::run(&config, Hook::Gap, "2024-06-01", &mut node).await;
+
+    assert_eq!(node.source, "/media/promo.mp4");
+    assert_eq!(node.out, 30.0);
+    assert_eq!(node.filters, vec!["mono_downmix".to_string()]);
+    // globals live between the hooks
+    assert_eq!(node.title.as_deref(), Some("after 1"));
+
+    // a changed script gets loaded again, a hanging hook keeps the clip
+    std::fs::write(
+        &hook,
+        "function on_midnight(node, ctx)\n    while true do end\nend\n",
+    )
+    .unwrap();
+    config.script.timeout = 0.2;
+
+    script::run(&config, Hook::Midnight, "2024-06-02", &mut node).await;
+
+    assert_eq!(node.source, "/media/promo.mp4");
+
+    // wrong fields keep the clip
+    std::fs::write(
+        &hook,
+        "function on_gap(node, ctx)\n    return { out = \"later\" }\nend\n",
+    )
+    .unwrap();
+
+    script::run(&config, Hook::Gap, "2024-06-02", &mut node).await;
+
+    assert_eq!(node.out, 30.0);
+
+    std::fs::remove_dir_all(&folder).unwrap();
+}
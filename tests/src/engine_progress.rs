@@ -0,0 +1,138 @@
+use sqlx::sqlite::SqlitePoolOptions;
+
+use ffplayout::db::handles;
+use ffplayout::player::{
+    controller::ChannelManager,
+    utils::{
+        progress::{self, ChannelMetrics, Progress},
+        *,
+    },
+};
+use ffplayout::utils::{config::PlayoutConfig, playhead, time_machine::time_now};
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET processing_width = 1024, processing_height = 576;
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager)
+}
+
+#[tokio::test]
+async fn test_save_playhead() {
+    let (config, manager) = prepare_config().await;
+
+    // nothing on air yet
+    playhead::save(&manager).await;
+    assert!(playhead::load(&manager).await.is_none());
+
+    let mut node = Media::new(2, "assets/media_mix/with_audio.mp4", false).await;
+    node.seek = 10.0;
+
+    *manager.current_date.lock().await = "2024-06-01".to_string();
+    *manager.current_media.lock().await = Some(node);
+    *manager.clip_started.lock().await =
+        tokio::time::Instant::now().checked_sub(std::time::Duration::from_secs(2));
+
+    playhead::save(&manager).await;
+
+    let mut saved = playhead::load(&manager).await.unwrap();
+    assert_eq!(saved.date, "2024-06-01");
+    assert_eq!(saved.node_index, 2);
+    assert_eq!(saved.source, "assets/media_mix/with_audio.mp4");
+    assert!((12.0..13.0).contains(&saved.position));
+    assert!(playhead::elapsed(&saved, &config.channel.timezone) < 1.0);
+
+    saved.saved_at =
+        (time_now(&config.channel.timezone) - chrono::TimeDelta::seconds(30)).to_rfc3339();
+    assert!((30.0..31.0).contains(&playhead::elapsed(&saved, &config.channel.timezone)));
+
+    // one playhead per channel
+    playhead::save(&manager).await;
+    let count: i64 = sqlx::query_scalar("SELECT count(*) FROM playheads")
+        .fetch_one(&manager.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+}
+
+#[tokio::test]
+async fn test_encoder_progress() {
+    let (_, manager) = prepare_config().await;
+    let mut parser = Progress::new(&manager).await;
+
+    for line in [
+        "frame=1695",
+        "fps=25.00",
+        "stream_0_0_q=28.0",
+        "bitrate=2512.4kbits/s",
+        "total_size=21289472",
+        "out_time_us=67800000",
+        "out_time_ms=67800000",
+        "out_time=00:01:07.800000",
+        "dup_frames=2",
+        "drop_frames=0",
+        "speed=1.01x",
+    ] {
+        assert!(parser.process(line).await);
+    }
+
+    // values are taken over at the end of a block
+    assert!(manager.encoder_progress.lock().await.is_none());
+    assert!(parser.process("progress=continue").await);
+
+    let progress = manager.encoder_progress.lock().await.clone().unwrap();
+    assert_eq!(progress.frame, 1695);
+    assert_eq!(progress.fps, 25.0);
+    assert_eq!(progress.bitrate, 2512.4);
+    assert_eq!(progress.speed, 1.01);
+    assert_eq!(progress.dup_frames, 2);
+    assert_eq!(progress.out_time, 67.8);
+
+    assert!(parser.process("bitrate=N/A").await);
+    assert!(
+        !parser
+            .process("[info] Output #0, flv, to 'rtmp://localhost/live/stream':")
+            .await
+    );
+    assert!(!parser.process("title=a=b").await);
+
+    let text = progress::metrics(&[
+        ChannelMetrics {
+            id: 1,
+            running: true,
+            encoder: Some(progress),
+        },
+        ChannelMetrics {
+            id: 2,
+            running: false,
+            encoder: None,
+        },
+    ]);
+
+    assert!(text.contains("ffplayout_channel_running{channel=\"1\"} 1\n"));
+    assert!(text.contains("ffplayout_channel_running{channel=\"2\"} 0\n"));
+    assert!(text
+        .contains("# TYPE ffplayout_encoder_fps gauge\nffplayout_encoder_fps{channel=\"1\"} 25\n"));
+    assert!(text.contains("ffplayout_encoder_dup_frames_total{channel=\"1\"} 2\n"));
+    assert!(!text.contains("ffplayout_encoder_fps{channel=\"2\"}"));
+}
@@ -0,0 +1,160 @@
+use std::path::PathBuf;
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use chrono::prelude::*;
+use serial_test::serial;
+
+use ffplayout::db::handles;
+use ffplayout::player::output::recorder;
+use ffplayout::utils::config::PlayoutConfig;
+
+async fn output_config(settings: &str) -> PlayoutConfig {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(&format!(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET {settings};
+        "#
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    PlayoutConfig::new(&pool, 1).await.unwrap()
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_recorder_cmd() {
+    let config = output_config(
+        "output_mode = 'stream', output_param = '-c:v libx264 -c:a aac', \
+        recorder_path = '/tmp/archive', recorder_format = 'mkv', recorder_segment = 1800, \
+        recorder_param = '-c:v libx264 -crf 28'",
+    )
+    .await;
+
+    let cmd = recorder::cmd(&config, "level+error").unwrap();
+
+    assert_eq!(
+        cmd,
+        vec![
+            "-hide_banner",
+            "-nostats",
+            "-v",
+            "level+error",
+            "-f",
+            "mpegts",
+            "-i",
+            "pipe:0",
+            "-c:v",
+            "libx264",
+            "-crf",
+            "28",
+            "-f",
+            "segment",
+            "-segment_format",
+            "matroska",
+            "-segment_time",
+            "1800",
+            "-segment_atclocktime",
+            "1",
+            "-strftime",
+            "1",
+            "-reset_timestamps",
+            "1",
+            "/tmp/archive/%Y-%m-%d_%H-%M-%S.mkv"
+        ]
+    );
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_recorder_retention() {
+    let folder = std::env::temp_dir().join("ffp_recorder_retention");
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+
+    let config = output_config(&format!(
+        "recorder_path = '{}', recorder_format = 'mkv', recorder_retention = 1",
+        folder.display()
+    ))
+    .await;
+    let old = std::time::SystemTime::now() - std::time::Duration::from_secs(3 * 86400);
+    let files = [
+        "2024-06-01_06-00-00.mkv",
+        "2024-06-01_06-30-00.ts",
+        "interview.mkv",
+        "clip_2024-06-01_06-00-00.mkv",
+    ];
+
+    for name in files {
+        let file = std::fs::File::create(folder.join(name)).unwrap();
+        file.set_modified(old).unwrap();
+    }
+
+    let recent = folder.join("2024-06-02_06-00-00.mkv");
+    std::fs::write(&recent, "").unwrap();
+
+    recorder::delete_old_recordings(&config).await.unwrap();
+
+    assert!(!folder.join(files[0]).exists());
+    assert!(files[1..].iter().all(|f| folder.join(f).exists()));
+    assert!(recent.exists());
+
+    let _ = std::fs::remove_dir_all(&folder);
+}
+
+#[test]
+fn test_clip_concat_list() {
+    let time = |t: &str| NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M:%S").unwrap();
+    let files = vec![
+        (
+            time("2024-10-01 10:00:00"),
+            PathBuf::from("/rec/2024-10-01_10-00-00.ts"),
+        ),
+        (
+            time("2024-10-01 11:00:00"),
+            PathBuf::from("/rec/2024-10-01_11-00-00.ts"),
+        ),
+        (
+            time("2024-10-01 12:00:00"),
+            PathBuf::from("/rec/2024-10-01_12-00-00.ts"),
+        ),
+    ];
+
+    let list = recorder::concat_list(
+        &files,
+        time("2024-10-01 10:59:00"),
+        time("2024-10-01 11:00:30"),
+    );
+
+    assert_eq!(
+        list.unwrap(),
+        "file '/rec/2024-10-01_10-00-00.ts'\ninpoint 3540\nfile '/rec/2024-10-01_11-00-00.ts'\noutpoint 30\n"
+    );
+
+    let list = recorder::concat_list(
+        &files,
+        time("2024-10-01 12:10:00"),
+        time("2024-10-01 12:10:05"),
+    );
+
+    assert_eq!(
+        list.unwrap(),
+        "file '/rec/2024-10-01_12-00-00.ts'\ninpoint 600\noutpoint 605\n"
+    );
+
+    assert!(recorder::concat_list(
+        &files,
+        time("2024-10-01 09:00:00"),
+        time("2024-10-01 09:30:00")
+    )
+    .is_none());
+}
@@ -0,0 +1,244 @@
+use sqlx::sqlite::SqlitePoolOptions;
+
+use chrono::prelude::*;
+use serial_test::serial;
+
+use ffplayout::db::handles;
+use ffplayout::player::utils::*;
+use ffplayout::utils::config::PlayoutConfig;
+
+async fn output_config(settings: &str) -> PlayoutConfig {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(&format!(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET {settings};
+        "#
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    PlayoutConfig::new(&pool, 1).await.unwrap()
+}
+
+fn mpeg_crc(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for byte in data {
+        crc ^= u32::from(*byte) << 24;
+
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04C1_1DB7
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
+
+/// MPEG-TS packet with one PSI section.
+fn psi_packet(pid: u16, mut section: Vec<u8>) -> Vec<u8> {
+    let crc = mpeg_crc(&section);
+    section.extend_from_slice(&crc.to_be_bytes());
+
+    let mut packet = vec![0x47, 0x40 | (pid >> 8) as u8, pid as u8, 0x10, 0x00];
+    packet.append(&mut section);
+    packet.resize(188, 0xFF);
+
+    packet
+}
+
+#[test]
+fn test_scte35_splice_insert() {
+    let section = scte35::splice_insert(1001, 30.0, true);
+
+    assert_eq!(section[0], 0xFC);
+    assert_eq!(
+        ((section[1] as usize & 0x0F) << 8) + section[2] as usize,
+        section.len() - 3
+    );
+    assert_eq!(section[13], 0x05);
+    assert_eq!(&section[14..18], &1001u32.to_be_bytes());
+
+    // CRC over the whole section, including the CRC, is zero
+    assert_eq!(mpeg_crc(&section), 0);
+}
+
+#[test]
+fn test_scte35_hls_cues() {
+    let m3u8 = "#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-TARGETDURATION:6
+#EXT-X-MEDIA-SEQUENCE:0
+#EXT-X-PROGRAM-DATE-TIME:2024-02-01T10:00:00.000+0000
+#EXTINF:6.000000,
+stream-0.ts
+#EXTINF:6.000000,
+stream-1.ts
+#EXTINF:6.000000,
+stream-2.ts
+";
+    let start = DateTime::parse_from_rfc3339("2024-02-01T10:00:07+00:00")
+        .unwrap()
+        .with_timezone(&Local);
+    let cue = scte35::CueEvent {
+        event_id: 7,
+        start,
+        duration: 6.0,
+    };
+
+    let result = scte35::insert_hls_cues(m3u8, std::slice::from_ref(&cue));
+    let lines: Vec<&str> = result.lines().collect();
+
+    assert!(lines[7].starts_with("#EXT-X-DATERANGE:ID=\"scte35-7\""));
+    assert_eq!(lines[8], "#EXT-X-CUE-OUT:DURATION=6.000");
+    assert_eq!(lines[9], "#EXTINF:6.000000,");
+    assert_eq!(lines[11], "#EXT-X-CUE-IN");
+    assert_eq!(lines[12], "#EXTINF:6.000000,");
+
+    // running twice don't duplicate tags
+    assert_eq!(scte35::insert_hls_cues(&result, &[cue]), result);
+}
+
+#[test]
+fn test_scte35_ts_injector() {
+    // program 1 with the PMT on PID 0x1000, like ffmpeg writes it
+    let pat = psi_packet(
+        0,
+        vec![
+            0x00, 0xB0, 0x0D, 0x00, 0x01, 0xC1, 0x00, 0x00, 0x00, 0x01, 0xF0, 0x00,
+        ],
+    );
+    // H.264 on PID 0x100 and AAC on PID 0x101
+    let pmt = psi_packet(
+        0x1000,
+        vec![
+            0x02, 0xB0, 0x17, 0x00, 0x01, 0xC1, 0x00, 0x00, 0xE1, 0x00, 0xF0, 0x00, 0x1B, 0xE1,
+            0x00, 0xF0, 0x00, 0x0F, 0xE1, 0x01, 0xF0, 0x00,
+        ],
+    );
+    let mut video = vec![0x47, 0x41, 0x00, 0x10];
+    video.resize(188, 0xAA);
+
+    let mut stream = [pat.clone(), pmt, video.clone()].concat();
+    let mut injector = scte35::TsInjector::new();
+    let start = Local::now();
+    let mut cues = vec![scte35::CueEvent {
+        event_id: 5,
+        start,
+        duration: 30.0,
+    }];
+
+    // not due yet
+    injector.schedule(&mut cues, start - chrono::TimeDelta::seconds(1));
+    assert_eq!(cues.len(), 1);
+
+    injector.schedule(&mut cues, start);
+    assert!(cues.is_empty());
+
+    // incomplete packets wait for the next chunk
+    let tail = stream.split_off(300);
+    let mut output = injector.process(&stream);
+    assert_eq!(output.len(), 188);
+    output.append(&mut injector.process(&tail));
+
+    let packets: Vec<&[u8]> = output.chunks(188).collect();
+
+    assert_eq!(packets.len(), 4);
+    assert_eq!(packets[0], pat.as_slice());
+    assert_eq!(packets[2], video.as_slice());
+
+    // the PMT has the CUEI registration and the SCTE-35 stream, with a valid CRC
+    let pmt = packets[1];
+    let length = ((pmt[6] as usize & 0x0F) << 8) + pmt[7] as usize;
+    let section = &pmt[5..8 + length];
+
+    assert_eq!(mpeg_crc(section), 0);
+    assert_eq!(
+        &section[10..18],
+        &[0xF0, 0x06, 0x05, 0x04, b'C', b'U', b'E', b'I']
+    );
+    assert_eq!(
+        &section[section.len() - 9..section.len() - 4],
+        &[0x86, 0xE1, 0xF4, 0xF0, 0x00]
+    );
+
+    // the cue follows on its own PID
+    let cue = packets[3];
+    let splice = scte35::splice_insert(5, 30.0, true);
+
+    assert_eq!(&cue[..5], &[0x47, 0x41, 0xF4, 0x10, 0x00]);
+    assert_eq!(&cue[5..5 + splice.len()], splice.as_slice());
+
+    // the return to the network comes at the end of the break, already changed PMTs stay
+    injector.schedule(&mut cues, start + chrono::TimeDelta::seconds(30));
+    let output = injector.process(&[packets[1], &video].concat());
+    let packets: Vec<&[u8]> = output.chunks(188).collect();
+    let splice = scte35::splice_insert(5, 0.0, false);
+
+    assert_eq!(packets.len(), 3);
+    assert_eq!(packets[0], pmt);
+    assert_eq!(packets[2][3], 0x11);
+    assert_eq!(&packets[2][5..5 + splice.len()], splice.as_slice());
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_scte35_hls_redirect() {
+    let config = output_config(
+        r#"output_mode = "hls", output_hls_program_date_time = 0, output_param = "-c:v libx264 -f hls -hls_flags append_list -hls_segment_filename live/stream-%d.ts live/stream.m3u8""#,
+    )
+    .await;
+
+    let playlist = scte35::cue_playlist(&config).unwrap();
+    let private = scte35::private_playlist(&playlist);
+    let mut cmd = config.output.output_cmd.clone().unwrap();
+    scte35::redirect_playlist(&config, &mut cmd);
+
+    assert_eq!(private.file_name().unwrap(), ".stream.m3u8");
+    assert_eq!(cmd.last().unwrap(), &private.to_string_lossy());
+    assert!(cmd.contains(&"append_list+program_date_time".to_string()));
+
+    let m3u8 = "#EXTM3U
+#EXT-X-PROGRAM-DATE-TIME:2024-02-01T10:00:00.000+0000
+#EXTINF:6.000000,
+stream-0.ts
+";
+    let start = DateTime::parse_from_rfc3339("2024-02-01T10:00:02+00:00")
+        .unwrap()
+        .with_timezone(&Local);
+    let cue = scte35::CueEvent {
+        event_id: 3,
+        start,
+        duration: 2.0,
+    };
+
+    // date times are only published, when they are enabled or needed by the cues
+    assert_eq!(
+        scte35::publish_playlist(m3u8, &[], false),
+        "#EXTM3U\n#EXTINF:6.000000,\nstream-0.ts\n"
+    );
+    assert_eq!(scte35::publish_playlist(m3u8, &[], true), m3u8);
+    assert!(scte35::publish_playlist(m3u8, &[cue], false)
+        .contains("#EXT-X-PROGRAM-DATE-TIME:2024-02-01T10:00:00.000+0000"));
+
+    let config = output_config(
+        r#"output_mode = "hls", output_param = "-c:v libx264 -f hls -var_stream_map 'v:0 v:1' live/stream_%v.m3u8""#,
+    )
+    .await;
+    let mut cmd = config.output.output_cmd.clone().unwrap();
+    scte35::redirect_playlist(&config, &mut cmd);
+
+    assert!(scte35::cue_playlist(&config).is_none());
+    assert_eq!(cmd, config.output.output_cmd.unwrap());
+}
@@ -0,0 +1,62 @@
+use ffplayout::player::utils::*;
+use ffplayout::utils::{clock, sync};
+
+#[test]
+fn test_sync_correction() {
+    let main =
+        serde_json::json!({"date": "2024-02-01", "shift": 12.5, "index": 4, "running": true});
+    let mut own =
+        serde_json::json!({"date": "2024-02-01", "shift": 0.0, "index": 3, "running": true});
+
+    assert_eq!(sync::correction(&own, &main), Some(12.5));
+
+    own["shift"] = serde_json::json!(12.52);
+    assert_eq!(sync::correction(&own, &main), None);
+
+    // no sync over the day change, or when the main is stopped
+    own["shift"] = serde_json::json!(0.0);
+    own["date"] = serde_json::json!("2024-02-02");
+    assert_eq!(sync::correction(&own, &main), None);
+
+    own["date"] = main["date"].clone();
+    let mut stopped = main.clone();
+    stopped["running"] = serde_json::json!(false);
+    assert_eq!(sync::correction(&own, &stopped), None);
+}
+
+#[test]
+fn test_drift_out_point() {
+    let mut node = Media {
+        seek: 0.0,
+        out: 100.0,
+        duration: 120.0,
+        ..Default::default()
+    };
+
+    // playout is late: shorten the clip, at most by the limit
+    assert_eq!(drift_correction(&mut node, -2.0, 0.5), -0.5);
+    assert_eq!(node.out, 99.5);
+
+    // playout is early: extend the clip
+    assert_eq!(drift_correction(&mut node, 0.3, 0.5), 0.3);
+    assert!(is_close(node.out, 99.8, 0.001));
+
+    // no room to extend, when the clip plays to its end
+    node.out = node.duration;
+    assert_eq!(drift_correction(&mut node, 0.3, 0.5), 0.0);
+
+    // disabled, or below one frame
+    assert_eq!(drift_correction(&mut node, -2.0, 0.0), 0.0);
+    assert_eq!(drift_correction(&mut node, -0.01, 0.5), 0.0);
+}
+
+#[test]
+fn test_clock_jump() {
+    assert_eq!(clock::jump(1.0, 1.001), None);
+    // leap second
+    assert_eq!(clock::jump(2.0, 1.0), None);
+    assert_eq!(clock::jump(31.0, 1.0), Some(30.0));
+    assert_eq!(clock::jump(-59.0, 1.0), Some(-60.0));
+    // day change is no jump
+    assert_eq!(clock::jump(1.0 - 86400.0, 1.0), None);
+}
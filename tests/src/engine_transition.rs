@@ -0,0 +1,53 @@
+use ffplayout::player::utils::*;
+
+#[test]
+fn test_crossfade_defaults() {
+    let clip = |source: &str, seek: f64, out: f64| Media {
+        source: source.to_string(),
+        seek,
+        out,
+        duration: 30.0,
+        ..Media::default()
+    };
+
+    let mut playlist = JsonPlaylist::new("2024-10-01".into(), 0.0);
+    playlist.program = vec![
+        clip("a.mp4", 0.0, 30.0),
+        clip("b.mp4", 5.0, 20.0),
+        clip("c.mp4", 0.0, 3.0),
+        clip("https://example.org/d.mp4", 0.0, 30.0),
+    ];
+
+    for _ in 0..2 {
+        json_serializer::set_defaults(&mut playlist, 2.0, 25.0);
+
+        let program = &playlist.program;
+
+        assert_eq!(
+            program[0].transition,
+            Some(Transition {
+                source: "b.mp4".to_string(),
+                seek: 5.0,
+                duration: 2.0,
+                ..Default::default()
+            })
+        );
+        assert_eq!(program[1].seek, 7.0);
+        assert_eq!(program[1].crossfade_in, 2.0);
+        // too short and remote clips are cut
+        assert!(program[1].transition.is_none());
+        assert!(program[2].transition.is_none());
+        assert_eq!(program[2].seek, 0.0);
+        assert_eq!(program[3].seek, 0.0);
+
+        let begins: Vec<f64> = program.iter().map(|m| m.begin.unwrap()).collect();
+        assert_eq!(begins, vec![0.0, 30.0, 43.0, 46.0]);
+        assert_eq!(playlist.length, Some(76.0));
+    }
+
+    json_serializer::set_defaults(&mut playlist, 0.0, 25.0);
+
+    assert!(playlist.program[0].transition.is_none());
+    assert_eq!(playlist.program[1].seek, 5.0);
+    assert_eq!(playlist.program[2].begin, Some(45.0));
+}
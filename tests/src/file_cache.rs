@@ -0,0 +1,137 @@
+use std::{
+    io::{Read, Write},
+    net::TcpListener,
+};
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use reqwest::Url;
+
+use ffplayout::db::handles;
+use ffplayout::file::utils::{cache::MediaCache, login};
+use ffplayout::player::{controller::ChannelManager, utils::*};
+use ffplayout::utils::config::PlayoutConfig;
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET processing_width = 1024, processing_height = 576;
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager)
+}
+
+#[tokio::test]
+async fn test_media_cache() {
+    let (mut config, _) = prepare_config().await;
+    let root = std::env::temp_dir().join("ffp_media_cache");
+    let _ = std::fs::remove_dir_all(&root);
+    config.storage.cache_path = root.clone();
+
+    let cache = MediaCache::new(&config).unwrap();
+
+    assert_eq!(cache.path(), root.join("1"));
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            std::thread::spawn(move || {
+                let mut request = [0; 1024];
+                let _ = stream.read(&mut request);
+
+                if String::from_utf8_lossy(&request).contains("slow") {
+                    std::thread::sleep(std::time::Duration::from_millis(300));
+                }
+
+                let _ = stream.write_all(
+                    b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nclip",
+                );
+            });
+        }
+    });
+
+    login::register(&Url::parse(&format!("http://{addr}/clips")).unwrap());
+
+    let mut remote = Media::new(0, &format!("http://{addr}/clips/a.mp4?sig=1"), false).await;
+    remote.key = "clips/a.mp4".to_string();
+    let local = Media::new(1, "assets/media_mix/with_audio.mp4", false).await;
+    // urls from outside of a storage are not downloaded
+    let foreign_url = Media::new(2, &format!("http://{addr}/other/b.mp4"), false).await;
+
+    let stale = cache.path().join("00000000000000ab.mp4");
+    let foreign = cache.path().join("notes.txt");
+    let outside = root.join("0000000000000000.mp4");
+
+    std::fs::create_dir_all(cache.path()).unwrap();
+    std::fs::write(&stale, "old").unwrap();
+    std::fs::write(&foreign, "user").unwrap();
+    std::fs::write(&outside, "other channel").unwrap();
+
+    cache
+        .fetch(&[remote.clone(), local.clone(), foreign_url.clone()])
+        .await;
+
+    let cached = cache.cache_file(&remote);
+
+    assert!(!cache.cache_file(&foreign_url).exists());
+
+    assert_eq!(std::fs::read_to_string(&cached).unwrap(), "clip");
+    assert!(!stale.exists());
+    assert!(foreign.exists());
+    assert!(outside.exists());
+
+    // presigned urls change, the cache file not
+    let mut node = Media::new(0, &format!("http://{addr}/clips/a.mp4?sig=2"), false).await;
+    node.key = "clips/a.mp4".to_string();
+    cache.apply(&mut node).await;
+
+    assert_eq!(node.source, cached.to_string_lossy());
+    assert_eq!(node.cmd, Some(vec!["-i".to_string(), node.source.clone()]));
+
+    let mut node = local.clone();
+    cache.apply(&mut node).await;
+
+    assert_eq!(node.source, local.source);
+
+    // a newer prefetch keeps its files, when an older one cleans up later
+    let mut slow = Media::new(3, &format!("http://{addr}/clips/slow.mp4"), false).await;
+    slow.key = "clips/slow.mp4".to_string();
+    let mut next = Media::new(4, &format!("http://{addr}/clips/b.mp4"), false).await;
+    next.key = "clips/b.mp4".to_string();
+
+    tokio::join!(
+        cache.fetch(std::slice::from_ref(&slow)),
+        cache.fetch(std::slice::from_ref(&next))
+    );
+
+    assert!(!cached.exists());
+    assert!(!cache.cache_file(&slow).exists());
+    assert!(cache.cache_file(&next).exists());
+
+    cache.fetch(&[]).await;
+
+    assert!(!cache.cache_file(&next).exists());
+    assert!(foreign.exists());
+
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -0,0 +1,179 @@
+use std::path::PathBuf;
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use chrono::prelude::*;
+use serial_test::serial;
+
+use ffplayout::db::{handles, models::Airing};
+use ffplayout::player::{controller::ChannelManager, output::proof, utils::*};
+use ffplayout::utils::{
+    asrun::{self, AsRunStatus},
+    config::PlayoutConfig,
+};
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET processing_width = 1024, processing_height = 576;
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager)
+}
+
+async fn output_config(settings: &str) -> PlayoutConfig {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(&format!(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET {settings};
+        "#
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    PlayoutConfig::new(&pool, 1).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_asrun_reconcile() {
+    let (mut config, _) = prepare_config().await;
+    config.channel.timezone = Some("UTC".parse().unwrap());
+
+    let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let mut scheduled = vec![];
+
+    for (i, source) in ["/media/a.mp4", "/media/b.mp4", "/media/c.mp4"]
+        .iter()
+        .enumerate()
+    {
+        let mut node = Media::new(i, source, false).await;
+        node.out = 60.0;
+        scheduled.push((
+            rights::air_time(&config, date, 21600.0 + i as f64 * 60.0),
+            node,
+        ));
+    }
+
+    let airing = |source: &str, started_at: &str| Airing {
+        channel_id: 1,
+        source: source.to_string(),
+        started_at: started_at.to_string(),
+        planned: 60.0,
+        duration: Some(60.0),
+        ..Default::default()
+    };
+    let airings = vec![
+        airing("/media/a.mp4", "2024-06-01 06:00:00.120"),
+        airing("/media/filler.mp4", "2024-06-01 06:01:00.120"),
+        airing("/media/c.mp4", "2024-06-01 06:02:00.120"),
+    ];
+    let tz = scheduled[0].0.timezone();
+    let entries = asrun::reconcile(&scheduled, &airings, &tz);
+
+    let status: Vec<AsRunStatus> = entries.iter().map(|e| e.status).collect();
+    assert_eq!(
+        status,
+        vec![
+            AsRunStatus::Aired,
+            AsRunStatus::Skipped,
+            AsRunStatus::Substituted,
+            AsRunStatus::Aired
+        ]
+    );
+    assert_eq!(
+        entries[0].scheduled.as_deref(),
+        Some("2024-06-01 06:00:00.000")
+    );
+    assert_eq!(entries[0].deviation, Some(0.12));
+    assert_eq!(entries[1].actual, None);
+    assert_eq!(entries[2].title, "filler");
+
+    let columns = vec![
+        "scheduled".to_string(),
+        "title".to_string(),
+        "status".to_string(),
+    ];
+    let csv = asrun::csv(&entries, &columns).unwrap();
+    assert!(csv.starts_with("scheduled,title,status\n2024-06-01 06:00:00.000,a,aired\n"));
+    assert!(csv.contains(",b,skipped\n,filler,substituted\n"));
+
+    let xml = asrun::xml(1, &date, &entries);
+    assert!(xml.contains("<AsRun channel=\"channel-1\" date=\"2024-06-01\">"));
+    assert!(xml.contains("<AsRunEvent status=\"skipped\">"));
+    assert!(xml.contains("<Deviation>0.120</Deviation>"));
+    assert_eq!(xml.matches("<ActualStart>").count(), 3);
+}
+
+#[actix_web::test]
+#[serial]
+async fn test_proof_thumbnail_cmd() {
+    let mut config = output_config(
+        "output_mode = 'stream', output_param = '-c:v libx264 -c:a aac', \
+        asrun_path = '/tmp/asrun', asrun_thumbnails = 1",
+    )
+    .await;
+    config.channel.timezone = Some("UTC".parse().unwrap());
+
+    let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let time = rights::air_time(&config, date, 21600.0);
+    let file = proof::file(&config, &time, "/media/Spot (30s).mp4");
+
+    assert_eq!(
+        file,
+        PathBuf::from("/tmp/asrun/thumbnails/2024-06-01/06-00-00_Spot__30s_.jpg")
+    );
+    assert_eq!(
+        proof::file(&config, &time, ""),
+        PathBuf::from("/tmp/asrun/thumbnails/2024-06-01/06-00-00.jpg")
+    );
+
+    let cmd = proof::cmd(&config, &file).unwrap();
+
+    assert_eq!(
+        cmd,
+        vec![
+            "-hide_banner",
+            "-nostats",
+            "-v",
+            "level+error",
+            "-f",
+            "mpegts",
+            "-i",
+            "pipe:0",
+            "-frames:v",
+            "1",
+            "-q:v",
+            "3",
+            "-update",
+            "1",
+            "-y",
+            "/tmp/asrun/thumbnails/2024-06-01/06-00-00_Spot__30s_.jpg"
+        ]
+    );
+}
@@ -0,0 +1,255 @@
+use std::path::{Path, PathBuf};
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use serial_test::serial;
+
+use ffplayout::db::handles;
+use ffplayout::player::controller::ChannelManager;
+use ffplayout::utils::{
+    capabilities::{self, Capabilities},
+    config::{is_live_field, OutputMode, PlayoutConfig},
+    config_check::{self, Feature},
+    ffmpeg_bundle,
+};
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET processing_width = 1024, processing_height = 576;
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager)
+}
+
+#[tokio::test]
+#[serial]
+async fn test_config_check() {
+    let (mut config, _) = prepare_config().await;
+    let storage = Path::new("assets/storage");
+
+    assert!(config_check::check_values(&config).is_empty());
+
+    config.processing.width = 1023;
+    config.processing.fps = 0.0;
+    config.processing.logo_opacity = 1.5;
+    config.playlist.day_start = "25:00:00".into();
+    config.playlist.length = "24:00:00".into();
+
+    let fields = config_check::check_values(&config)
+        .into_iter()
+        .map(|i| i.field)
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        fields,
+        vec![
+            "processing.width",
+            "processing.fps",
+            "processing.logo_opacity",
+            "playlist.day_start"
+        ]
+    );
+
+    config.processing.add_logo = true;
+    config.processing.logo = "missing/logo.png".into();
+    config.text.add_text = false;
+    config.script.enable = false;
+
+    let issues = config_check::check_paths(&config, storage);
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!(
+        issues[0].to_string(),
+        "processing.logo: missing/logo.png not exists"
+    );
+
+    config.processing.add_logo = false;
+
+    assert!(config_check::check_paths(&config, storage).is_empty());
+
+    assert_eq!(
+        config_check::output_codecs(
+            "-c:v libx264 -crf 23 -c:a copy -vcodec mpeg2video -f mpegts out.ts"
+        ),
+        vec!["libx264", "mpeg2video"]
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_config_live_fields() {
+    let (old_config, _) = prepare_config().await;
+    let mut config = old_config.clone();
+
+    assert!(old_config.changed_fields(&config).is_empty());
+
+    config.processing.volume = 0.5;
+    config.processing.logo_opacity = 0.3;
+    config.hooks.timeout = 10.0;
+
+    assert_eq!(
+        old_config.changed_fields(&config),
+        vec![
+            "hooks.timeout",
+            "processing.logo_opacity",
+            "processing.volume"
+        ]
+    );
+    assert!(old_config
+        .changed_fields(&config)
+        .iter()
+        .all(|f| is_live_field(f)));
+
+    config.processing.width = 1280;
+
+    assert!(old_config
+        .changed_fields(&config)
+        .iter()
+        .any(|f| f == "processing.width" && !is_live_field(f)));
+
+    let mut running = old_config.clone();
+    running.text.zmq_stream_socket = Some("127.0.0.1:5555".into());
+    running.apply_live(&config);
+
+    assert_eq!(running.processing.volume, 0.5);
+    assert_eq!(running.hooks.timeout, 10.0);
+    assert_eq!(running.processing.width, old_config.processing.width);
+    assert_eq!(
+        running.text.zmq_stream_socket,
+        Some("127.0.0.1:5555".to_string())
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn test_ffmpeg_capabilities() {
+    let (mut config, _) = prepare_config().await;
+
+    let encoders = "Encoders:
+ V..... = Video
+ A..... = Audio
+ ------
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC (codec h264)
+ V....D mpeg2video           MPEG-2 video
+ A....D aac                  AAC (Advanced Audio Coding)
+ A....D pcm_s16le            PCM signed 16-bit little-endian";
+    let muxers = "File formats:
+ D. = Demuxing supported
+ .E = Muxing supported
+ ---
+  E hls             Apple HTTP Live Streaming
+ DE mpegts          MPEG-TS (MPEG-2 Transport Stream)
+  E null            raw null video";
+    let filters = "Filters:
+  T.. = Timeline support
+  .S. = Slice threading
+ ... overlay           VV->V      Overlay a video source on top of the input.
+ TSC drawtext          V->V       Draw text on top of video frames.";
+    let protocols = "Supported file protocols:
+Input:
+  file
+  http
+Output:
+  file
+  rtmp";
+
+    let capabilities = Capabilities {
+        path: "/usr/bin/ffmpeg".into(),
+        encoders: capabilities::parse_list(encoders),
+        muxers: capabilities::parse_list(muxers),
+        filters: capabilities::parse_filters(filters),
+        protocols: capabilities::parse_protocols(protocols),
+    };
+
+    assert!(capabilities.encoders.contains("pcm_s16le"));
+    assert!(capabilities.muxers.contains("mpegts"));
+    assert!(capabilities.filters.contains("drawtext"));
+    assert!(!capabilities.protocols.contains("http"));
+
+    config.output.mode = OutputMode::Stream;
+    config.output.push = None;
+    config.output.legs = vec![];
+    config.processing.cmd = None;
+    config.processing.add_logo = true;
+    config.processing.loudnorm = true;
+    config.text.add_text = false;
+    config.output.output_cmd = Some(
+        "-c:v libx265 -c:a aac -f flv rtmp://127.0.0.1/live/stream"
+            .split_whitespace()
+            .map(String::from)
+            .collect(),
+    );
+
+    let issues = config_check::lint(&config, &capabilities)
+        .into_iter()
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>();
+
+    assert!(issues.contains(
+        &"output.output_param: encoder libx265 requested but not available in /usr/bin/ffmpeg"
+            .to_string()
+    ));
+    assert!(issues.contains(
+        &"output.output_param: muxer flv requested but not available in /usr/bin/ffmpeg"
+            .to_string()
+    ));
+    assert!(issues.contains(
+        &"processing.loudnorm: filter loudnorm requested but not available in /usr/bin/ffmpeg"
+            .to_string()
+    ));
+    assert!(!issues
+        .iter()
+        .any(|i| i.contains("rtmp") || i.contains("overlay")));
+
+    config.output.mode = OutputMode::Whip;
+    config.output.output_cmd = None;
+
+    let requirements = config_check::requirements(&config)
+        .into_iter()
+        .filter(|r| r.field == "output.mode")
+        .map(|r| (r.feature, r.name))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        requirements,
+        vec![
+            (Feature::Encoder, "libx264".to_string()),
+            (Feature::Encoder, "libopus".to_string()),
+            (Feature::Muxer, "whip".to_string())
+        ]
+    );
+}
+
+#[test]
+fn test_ffmpeg_bundle() {
+    assert!(ffmpeg_bundle::is_checksum(
+        "5d41402abc4b2a76b9719d911017c592e7c5a5b0b2e0f3c7a6d2c2c7a4e1b2f0"
+    ));
+    assert!(!ffmpeg_bundle::is_checksum("5d41402abc4b2a76"));
+    assert!(!ffmpeg_bundle::is_checksum(
+        "zd41402abc4b2a76b9719d911017c592e7c5a5b0b2e0f3c7a6d2c2c7a4e1b2f0"
+    ));
+
+    // without --ffmpeg-url the binaries come from the system
+    assert_eq!(ffmpeg_bundle::ffmpeg(), PathBuf::from("ffmpeg"));
+    assert_eq!(ffmpeg_bundle::ffprobe(), PathBuf::from("ffprobe"));
+}
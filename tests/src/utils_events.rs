@@ -0,0 +1,134 @@
+use sqlx::sqlite::SqlitePoolOptions;
+
+use ffplayout::db::handles;
+use ffplayout::player::controller::ChannelManager;
+use ffplayout::utils::{
+    config::{Mqtt, PlayoutConfig},
+    event_hooks,
+    events::{self, EventKind, PlayoutEvent},
+    mqtt, webhook,
+};
+
+async fn prepare_config() -> (PlayoutConfig, ChannelManager) {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE global SET public = "assets/hls", logs = "assets/log", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "assets/storage";
+        UPDATE configurations SET processing_width = 1024, processing_height = 576;
+        "#,
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    let channel = handles::select_channel(&pool, &1).await.unwrap();
+    let manager = ChannelManager::new(pool, channel, config.clone())
+        .await
+        .unwrap();
+
+    (config, manager)
+}
+
+#[test]
+fn test_webhook_signature() {
+    assert_eq!(
+        webhook::sign("key", b"The quick brown fox jumps over the lazy dog"),
+        "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+    );
+}
+
+#[test]
+fn test_mqtt_packets() {
+    let mut connect = vec![
+        0x10, 35, 0, 4, b'M', b'Q', b'T', b'T', 4, 0xC2, 0, 60, 0, 11,
+    ];
+    connect.extend_from_slice(b"ffplayout-1");
+    connect.extend_from_slice(&[0, 4]);
+    connect.extend_from_slice(b"user");
+    connect.extend_from_slice(&[0, 4]);
+    connect.extend_from_slice(b"pass");
+
+    assert_eq!(mqtt::connect_packet("ffplayout-1", "user", "pass"), connect);
+
+    assert_eq!(
+        mqtt::publish_packet("a/b", b"{}", true),
+        vec![0x31, 7, 0, 3, b'a', b'/', b'b', b'{', b'}']
+    );
+
+    let publish = mqtt::publish_packet("t", &[0; 200], false);
+    assert_eq!(publish[..3], [0x30, 0xCB, 0x01]);
+    assert_eq!(publish.len(), 206);
+
+    let event = PlayoutEvent::new(EventKind::ClipStart, 1, serde_json::Value::Null);
+    let mut settings = Mqtt::default();
+
+    assert_eq!(mqtt::topic(&settings, &event), "ffplayout/1/clip_start");
+
+    settings.topic = "studio/tv1/".to_string();
+
+    assert_eq!(mqtt::topic(&settings, &event), "studio/tv1/clip_start");
+}
+
+#[tokio::test]
+async fn test_event_hooks() {
+    let (mut config, _) = prepare_config().await;
+    let folder = std::env::temp_dir().join("ffp_event_hooks");
+    let _ = std::fs::remove_dir_all(&folder);
+    std::fs::create_dir_all(&folder).unwrap();
+
+    config.hooks.clip_start = format!(
+        "echo \"$FFP_EVENT $FFP_SOURCE $FFP_IN\" > {}",
+        folder.join("start.txt").display()
+    );
+    config.hooks.error = "exit 1".into();
+
+    assert!(event_hooks::command(&config.hooks, &EventKind::ClipEnd).is_none());
+    assert_eq!(
+        event_hooks::command(&config.hooks, &EventKind::Filler),
+        Some("exit 1")
+    );
+    assert!(event_hooks::command(&config.hooks, &EventKind::Silence).is_none());
+
+    let event = PlayoutEvent::new(
+        EventKind::ClipStart,
+        1,
+        serde_json::json!({"source": "/media/clip.mp4", "in": 10.5, "next": {"source": "x.mp4"}}),
+    );
+    let env = event_hooks::env(&event);
+
+    assert!(env.contains(&("FFP_SOURCE".to_string(), "/media/clip.mp4".to_string())));
+    assert!(env.contains(&("FFP_IN".to_string(), "10.5".to_string())));
+    assert!(!env.iter().any(|(k, _)| k == "FFP_NEXT"));
+
+    let config = std::sync::Arc::new(tokio::sync::Mutex::new(config));
+    let sender = events::channel();
+
+    tokio::spawn(event_hooks::run(config, sender.subscribe()));
+    sender.send(event).unwrap();
+
+    let file = folder.join("start.txt");
+
+    for _ in 0..50 {
+        if file.is_file() {
+            break;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap().trim(),
+        "clip_start /media/clip.mp4 10.5"
+    );
+
+    std::fs::remove_dir_all(&folder).unwrap();
+}
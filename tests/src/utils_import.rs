@@ -0,0 +1,160 @@
+use std::path::Path;
+
+use chrono::prelude::*;
+
+use ffplayout::player::utils::*;
+use ffplayout::utils::epg;
+
+#[tokio::test]
+async fn test_import_rundown() {
+    let data = "Start;House_ID;Title;Duration\n\
+        06:00:00;assets/media_mix/dual_audio.mp4;Morning Show;00:10:00\n\
+        06:10:00;dual_audio;Rerun;600\n\
+        06:30:00;assets/missing.mp4;Missing;00:05:00\n\
+        ;not_found_id;;abc\n";
+
+    let rows = import::read_rundown(data).unwrap();
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[1].source, "dual_audio");
+    assert_eq!(rows[1].row, 3);
+
+    let errors = import::rundown_program(rows.clone(), Path::new("assets"), 21600.0)
+        .await
+        .unwrap_err();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].starts_with("Row 4: file assets/missing.mp4 not exists"));
+    assert!(errors[1].contains("house ID not_found_id not found"));
+    assert!(errors[1].contains("invalid duration abc"));
+
+    let program = import::rundown_program(rows[..2].to_vec(), Path::new("assets"), 21600.0)
+        .await
+        .unwrap();
+    assert_eq!(program[0].title, Some("Morning Show".to_string()));
+    assert_eq!(program[1].source, "assets/media_mix/dual_audio.mp4");
+    assert_eq!(program[1].out, 600.0);
+
+    // second clip starts too early
+    let mut rows = rows[..2].to_vec();
+    rows[1].start = "06:05:00".to_string();
+    let errors = import::rundown_program(rows, Path::new("assets"), 21600.0)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        errors,
+        vec!["Row 3: start 06:05:00 does not match calculated start 06:10:00.000"]
+    );
+}
+
+#[test]
+fn test_epg_xmltv() {
+    let start = DateTime::parse_from_rfc3339("2024-02-01T06:00:00+01:00").unwrap();
+    let node = Media {
+        source: "/tv/news_0600.mp4".to_string(),
+        out: 1800.0,
+        duration: 1800.0,
+        category: "news".to_string(),
+        description: Some("Headlines & weather".to_string()),
+        ..Default::default()
+    };
+    let first = epg::Programme::new(start, &node);
+    let second = epg::Programme::new(
+        first.stop,
+        &Media {
+            title: Some("Movie <Premiere>".to_string()),
+            out: 5400.0,
+            ..node.clone()
+        },
+    );
+
+    assert_eq!(first.title, "news_0600");
+    assert_eq!(second.start.to_rfc3339(), "2024-02-01T06:30:00+01:00");
+
+    let xml = epg::xmltv(1, "Channel 1", &[first, second]);
+
+    assert!(xml.contains(r#"<channel id="channel-1">"#));
+    assert!(xml.contains(
+        r#"<programme start="20240201060000 +0100" stop="20240201063000 +0100" channel="channel-1">"#
+    ));
+    assert!(xml.contains("<desc>Headlines &amp; weather</desc>"));
+    assert!(xml.contains("<title>Movie &lt;Premiere&gt;</title>"));
+    assert!(xml.contains("<category>news</category>"));
+}
+
+#[test]
+fn test_parse_m3u_xspf() {
+    let m3u = "#EXTM3U\n\
+        #EXTINF:120.5 tvg-id=\"one\",Opening\n\
+        clips/opening.mp4\n\
+        \n\
+        /media/news.mp4\n\
+        #EXTINF:-1,Live\n\
+        https://example.org/live.m3u8\n";
+
+    let entries = import::parse_m3u(m3u, Path::new("/playlists/2024/02"));
+
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].source, "/playlists/2024/02/clips/opening.mp4");
+    assert_eq!(entries[0].title, Some("Opening".to_string()));
+    assert_eq!(entries[0].duration, Some(120.5));
+    assert_eq!(entries[1].source, "/media/news.mp4");
+    assert_eq!(entries[1].title, None);
+    assert_eq!(entries[2].duration, None);
+
+    let xspf = r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <trackList>
+    <track>
+      <location>file:///media/My%20Movie.mkv</location>
+      <title>Tom &amp; Jerry</title>
+      <duration>5400000</duration>
+    </track>
+    <track>
+      <title>No location</title>
+    </track>
+  </trackList>
+</playlist>"#;
+
+    let entries = import::parse_xspf(xspf, Path::new("/playlists"));
+
+    assert_eq!(
+        entries,
+        vec![import::PlaylistEntry {
+            source: "/media/My Movie.mkv".to_string(),
+            title: Some("Tom & Jerry".to_string()),
+            duration: Some(5400.0),
+        }]
+    );
+}
+
+#[test]
+fn test_timecode_seconds() {
+    assert_eq!(timecode_to_sec("00:01:00:12", 25.0), Some(60.48));
+    assert_eq!(
+        timecode_to_sec("01:00:00:00", 30000.0 / 1001.0),
+        Some(3603.6)
+    );
+    assert_eq!(timecode_to_sec("00:00:01:25", 25.0), None);
+    assert_eq!(timecode_to_sec("00:61:00:00", 25.0), None);
+    assert_eq!(timecode_to_sec("00:01:00", 25.0), None);
+}
+
+#[test]
+fn test_som_eom_points() {
+    let mut playlist: JsonPlaylist = serde_json::from_str(
+        r#"{"channel":"Test 1","date":"2024-02-01","program":[
+            {"in":0,"out":0,"duration":120,"source":"a.mp4","som":"00:00:10:05","eom":"00:01:30:00"},
+            {"in":0,"out":20,"duration":20,"source":"b.mp4"}
+        ]}"#,
+    )
+    .unwrap();
+    playlist.start_sec = Some(0.0);
+
+    json_serializer::set_defaults(&mut playlist, 0.0, 25.0);
+
+    assert_eq!(playlist.program[0].seek, 10.2);
+    assert_eq!(playlist.program[0].out, 90.0);
+    assert_eq!(playlist.program[1].begin, Some(79.8));
+    assert!(serde_json::to_string(&playlist.program[0])
+        .unwrap()
+        .contains(r#""som":"00:00:10:05""#));
+}
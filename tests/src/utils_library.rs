@@ -0,0 +1,230 @@
+use std::str::FromStr;
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use ffplayout::db::{
+    handles,
+    models::{LibraryItem, LibraryQuery},
+};
+use ffplayout::utils::{
+    config::PlayoutConfig,
+    events::{self, EventKind, PlayoutEvent},
+    history, library, preview,
+};
+
+#[tokio::test]
+async fn test_media_library_scan() {
+    let storage = std::env::temp_dir().join("ffp_media_library");
+    let _ = std::fs::remove_dir_all(&storage);
+    std::fs::create_dir_all(storage.join("clips")).unwrap();
+    std::fs::copy(
+        "assets/media_mix/with_audio.mp4",
+        storage.join("clips/with_audio.mp4"),
+    )
+    .unwrap();
+    std::fs::copy(
+        "assets/media_mix/no_audio.mp4",
+        storage.join("no_audio.mp4"),
+    )
+    .unwrap();
+    std::fs::write(storage.join("notes.txt"), "not a clip").unwrap();
+
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    sqlx::query(&format!(
+        r#"UPDATE channels SET public = "assets/hls", playlists = "assets/playlists", storage = "{}";"#,
+        storage.display()
+    ))
+    .execute(&pool)
+    .await
+    .unwrap();
+
+    let config = PlayoutConfig::new(&pool, 1).await.unwrap();
+
+    let result = library::scan(&pool, &config).await.unwrap();
+    assert_eq!((result.added, result.updated, result.removed), (2, 0, 0));
+
+    let result = library::scan(&pool, &config).await.unwrap();
+    assert_eq!(result, library::ScanResult::default());
+
+    let query = LibraryQuery {
+        search: "with_audio".to_string(),
+        ..Default::default()
+    };
+    let items = handles::select_library(&pool, 1, &query).await.unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].checksum.len(), 64);
+    assert_eq!(
+        items[0].checksum,
+        library::checksum(&storage.join("clips/with_audio.mp4"))
+            .await
+            .unwrap()
+    );
+
+    std::fs::remove_file(storage.join("no_audio.mp4")).unwrap();
+
+    let result = library::scan(&pool, &config).await.unwrap();
+    assert_eq!(result.removed, 1);
+
+    let items = handles::select_library(&pool, 1, &LibraryQuery::default())
+        .await
+        .unwrap();
+    assert_eq!(items.len(), 1);
+
+    std::fs::remove_dir_all(&storage).unwrap();
+}
+
+#[test]
+fn test_library_query_filter() {
+    let mut item = LibraryItem {
+        path: "/tv-media/music/clip.mp4".to_string(),
+        duration: 240.0,
+        height: 1080,
+        genre: "Music; Pop".to_string(),
+        artist: "The Band".to_string(),
+        ..Default::default()
+    };
+    item.tags
+        .insert("keywords".to_string(), "summer, live".to_string());
+
+    let filter = |q: &str| library::LibraryFilter::from_str(q).unwrap();
+
+    assert!(filter("tag:music AND duration<300").matches(&item));
+    assert!(filter("tag:live height>=1080").matches(&item));
+    assert!(!filter("tag:music AND duration>300").matches(&item));
+    assert!(filter("tag:jazz OR artist:\"the band\"").matches(&item));
+    assert!(!filter("NOT (tag:pop OR tag:jazz)").matches(&item));
+    assert!(filter("music NOT loudness<-20").matches(&item));
+    assert!(!filter("codec:h264").matches(&item));
+
+    assert!(library::LibraryFilter::from_str("(tag:music").is_err());
+    assert!(library::LibraryFilter::from_str("title<5").is_err());
+    assert!(library::LibraryFilter::from_str("tag:music AND").is_err());
+}
+
+#[tokio::test]
+async fn test_airing_history() {
+    let pool = SqlitePoolOptions::new()
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    handles::db_migrate(&pool).await.unwrap();
+
+    let mut config = PlayoutConfig::new(&pool, 1).await.unwrap();
+    config.channel.timezone = Some("UTC".parse().unwrap());
+
+    let sender = events::channel();
+    let task = tokio::spawn(history::run(
+        pool.clone(),
+        std::sync::Arc::new(tokio::sync::Mutex::new(config)),
+        sender.subscribe(),
+    ));
+    let clip = |source: &str| serde_json::json!({"source": source, "title": "Song", "artist": "Band", "in": 0.0, "out": 180.0});
+    let send = |event, time: &str, data| {
+        let mut event = PlayoutEvent::new(event, 1, data);
+        event.time = time.to_string();
+        sender.send(event).unwrap();
+    };
+
+    send(
+        EventKind::ClipStart,
+        "2024-06-01T10:00:00+00:00",
+        clip("/music/a.mp3"),
+    );
+    send(
+        EventKind::ClipEnd,
+        "2024-06-01T10:03:00+00:00",
+        clip("/music/a.mp3"),
+    );
+    send(
+        EventKind::ClipStart,
+        "2024-06-01T10:03:00+00:00",
+        clip("/music/b.mp3"),
+    );
+    // missing end, the next start closes the clip
+    send(
+        EventKind::ClipStart,
+        "2024-06-01T10:04:30+00:00",
+        clip("/music/a.mp3"),
+    );
+    send(
+        EventKind::ChannelStop,
+        "2024-06-01T10:05:00+00:00",
+        serde_json::json!({}),
+    );
+    drop(sender);
+    task.await.unwrap();
+
+    let (start, end) = history::date_range("2024-06-01", "2024-06-01").unwrap();
+    let airings = handles::select_airings(&pool, 1, &start, &end)
+        .await
+        .unwrap();
+
+    assert_eq!(airings.len(), 3);
+    assert_eq!(airings[0].started_at, "2024-06-01 10:00:00.000");
+    assert_eq!(airings[0].duration, Some(180.0));
+    assert_eq!(airings[1].duration, Some(90.0));
+    assert_eq!(airings[2].duration, Some(30.0));
+
+    let counts = handles::select_play_counts(&pool, 1, &start, &end)
+        .await
+        .unwrap();
+    assert_eq!(counts[0].source, "/music/a.mp3");
+    assert_eq!(counts[0].plays, 2);
+    assert_eq!(counts[0].duration, 210.0);
+
+    let csv = history::csv(&airings).unwrap();
+    assert!(csv.starts_with("started_at,source,title,artist,category,planned,duration\n"));
+    assert!(csv.contains("2024-06-01 10:03:00.000,/music/b.mp3,Song,Band,,180.000,90.000"));
+
+    let (start, end) = history::date_range("2024-06-02", "2024-06-30").unwrap();
+    assert!(handles::select_airings(&pool, 1, &start, &end)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(history::date_range("2024-06-02", "2024-06-01").is_err());
+}
+
+#[test]
+fn test_library_preview_sprite() {
+    let item = LibraryItem {
+        path: "/media/film.mp4".to_string(),
+        checksum: "abc".to_string(),
+        duration: 250.0,
+        width: 1920,
+        height: 1080,
+        ..Default::default()
+    };
+
+    assert_eq!(preview::frame_size(&item), Some((160, 90)));
+    assert_eq!(preview::frame_interval(item.duration), 2.5);
+    assert_eq!(preview::frame_interval(30.0), 1.0);
+
+    let cmd = preview::sprite_cmd(&item, "/tmp/sprite.jpg").unwrap();
+    assert!(cmd.contains(&"fps=1/2.500,scale=160:90,tile=10x10".to_string()));
+    assert_eq!(cmd.last().unwrap(), "/tmp/sprite.jpg");
+
+    let vtt = preview::vtt(&item).unwrap();
+    assert!(
+        vtt.starts_with("WEBVTT\n\n00:00:00.000 --> 00:00:02.500\nsprite.jpg#xywh=0,0,160,90\n")
+    );
+    assert!(vtt.contains("00:00:27.500 --> 00:00:30.000\nsprite.jpg#xywh=160,90,160,90\n"));
+    assert!(vtt.ends_with("00:04:07.500 --> 00:04:10.000\nsprite.jpg#xywh=1440,810,160,90\n"));
+    assert_eq!(vtt.matches(" --> ").count(), 100);
+
+    assert_eq!(
+        preview::Preview::new(1, "abc").vtt,
+        "/1/preview/library/abc/sprite.vtt"
+    );
+
+    let audio = LibraryItem {
+        checksum: "def".to_string(),
+        duration: 180.0,
+        ..Default::default()
+    };
+    assert!(preview::vtt(&audio).is_none());
+}
@@ -0,0 +1,42 @@
+use chrono::prelude::*;
+use flexi_logger::DeferredNow;
+use log::{kv::Value, Level, Record};
+
+use ffplayout::utils::logging::json_formatter;
+
+#[test]
+fn test_json_log_line() {
+    let mut line = vec![];
+    let fields: &[(&str, Value)] = &[
+        ("channel", Value::from(2)),
+        ("file", Value::from("say \"hi\"\\.mp4")),
+    ];
+
+    json_formatter(
+        &mut line,
+        &mut DeferredNow::new(),
+        &Record::builder()
+            .args(format_args!("Play <b><magenta>\"clip\"</></b>\n\tnext"))
+            .level(Level::Warn)
+            .target("ffplayout")
+            .module_path(Some("ffplayout::player::output"))
+            .key_values(&fields)
+            .build(),
+    )
+    .unwrap();
+
+    let line = String::from_utf8(line).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+
+    // one line per record, the control characters are escaped
+    assert!(!line.contains('\n') && !line.contains('\t'));
+    assert!(DateTime::parse_from_rfc3339(value["timestamp"].as_str().unwrap()).is_ok());
+    assert_eq!(value["channel"], 2);
+    assert_eq!(value["target"], "ffplayout::player::output");
+    assert_eq!(value["level"], "WARN");
+    assert_eq!(value["message"], "Play \"clip\"\n\tnext");
+    assert_eq!(
+        value["fields"],
+        serde_json::json!({"file": "say \"hi\"\\.mp4"})
+    );
+}